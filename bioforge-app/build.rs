@@ -0,0 +1,16 @@
+//! Records `git describe` of the source tree the binary is built from, if it is built in one, for
+//! the run manifest.
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    for path in ["../.git/HEAD", "../.git/index", "../.git/refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+    let describe = Command::new("git").args(["describe", "--always", "--dirty", "--tags"]).output().ok();
+    if let Some(output) = describe.filter(|output| output.status.success()) {
+        println!("cargo:rustc-env=BIOFORGE_GIT_DESCRIBE={}", String::from_utf8_lossy(&output.stdout).trim());
+    }
+}
@@ -0,0 +1,314 @@
+//! The command line of the application. Every path has a default relative to the repository
+//! root and an environment variable to set it from, so `cargo run -p bioforge-app` still runs the
+//! demo request while the binary can be pointed at any request and knowledge base.
+use crate::workflow::LogBackend;
+use anyhow::{bail, Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// The environment variable a request path is read from when `--request` is not given.
+pub const REQUEST_ENV: &str = "BIOFORGE_REQUEST";
+
+/// The request looked for in the working directory when neither `--request` nor [`REQUEST_ENV`]
+/// names one.
+pub const WORKING_DIRECTORY_REQUEST: &str = "request.yaml";
+
+/// The demo request, looked for last so that runs from the repository root find it.
+pub const DEMO_REQUEST: &str = "bioforge-app/request.yaml";
+
+#[derive(Debug, Parser)]
+#[command(version, about = "A Framework for Sustainable Bioprocess Design", args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// The options of `run`, which runs when no subcommand is given.
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Runs the end-to-end workflow for a request (the default).
+    Run(RunArgs),
+    /// Runs the Monte Carlo uncertainty study of the upstream cultivation.
+    MonteCarlo(MonteCarloArgs),
+    /// Compares two completed runs.
+    Compare(CompareArgs),
+    /// Runs the end-to-end workflow for every combination of the values of a few parameters.
+    Sweep(SweepArgs),
+}
+
+/// Where a request, the knowledge base it is fulfilled from, and its results live.
+#[derive(Debug, Args)]
+pub struct Inputs {
+    /// The request to fulfil, or `-` to read it from standard input. Defaults to the path in
+    /// `BIOFORGE_REQUEST`, then `request.yaml` in the working directory, then the demo request.
+    #[arg(long)]
+    pub request: Option<PathBuf>,
+    /// The knowledge base directory.
+    #[arg(long, env = "BIOFORGE_KB", default_value = "data/knowledge_base")]
+    pub kb: PathBuf,
+    /// A directory laid out like the knowledge base whose entities replace those of the same id,
+    /// or are added. Repeat it to stack overlays; later ones win.
+    #[arg(long = "kb-overlay", value_name = "DIR")]
+    pub kb_overlays: Vec<PathBuf>,
+    /// The directory each run directory is created in.
+    #[arg(long, env = "BIOFORGE_OUT", default_value = "data/runs")]
+    pub out: PathBuf,
+    /// Aborts when the knowledge base references an asset, rule, material, organism, or labor
+    /// role it does not define, instead of only reporting it.
+    #[arg(long, env = "BIOFORGE_STRICT")]
+    pub strict: bool,
+}
+
+impl Inputs {
+    /// Where the request is read from: `--request`, else [`REQUEST_ENV`], else the first of
+    /// [`WORKING_DIRECTORY_REQUEST`] and [`DEMO_REQUEST`] that exists. A path of `-` is
+    /// standard input.
+    pub fn request_source(&self) -> Result<RequestSource> {
+        let given = match (&self.request, std::env::var_os(REQUEST_ENV)) {
+            (Some(path), _) => Some(RequestSource::Flag(path.clone())),
+            (None, Some(path)) => Some(RequestSource::Env(PathBuf::from(path))),
+            (None, None) => None,
+        };
+        if let Some(source) = given {
+            return Ok(if source.path() == Some(Path::new("-")) { RequestSource::Stdin } else { source });
+        }
+        if Path::new(WORKING_DIRECTORY_REQUEST).is_file() {
+            return Ok(RequestSource::WorkingDirectory(PathBuf::from(WORKING_DIRECTORY_REQUEST)));
+        }
+        if Path::new(DEMO_REQUEST).is_file() {
+            return Ok(RequestSource::Demo(PathBuf::from(DEMO_REQUEST)));
+        }
+        bail!(
+            "No request found: pass --request <PATH> (or `-` for standard input), set {}, or put a {} in the working directory",
+            REQUEST_ENV,
+            WORKING_DIRECTORY_REQUEST
+        )
+    }
+}
+
+/// Where a request is read from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestSource {
+    /// The path `--request` gave.
+    Flag(PathBuf),
+    /// The path [`REQUEST_ENV`] gave.
+    Env(PathBuf),
+    /// [`WORKING_DIRECTORY_REQUEST`].
+    WorkingDirectory(PathBuf),
+    /// [`DEMO_REQUEST`].
+    Demo(PathBuf),
+    /// The request kept in the run directory of a run being resumed.
+    Resumed(PathBuf),
+    /// Standard input.
+    Stdin,
+}
+
+impl RequestSource {
+    /// The file the request is read from, or `None` for standard input.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Flag(path) | Self::Env(path) | Self::WorkingDirectory(path) | Self::Demo(path) | Self::Resumed(path) => {
+                Some(path)
+            }
+            Self::Stdin => None,
+        }
+    }
+
+    /// Reads the request's text, YAML or JSON.
+    pub fn read(&self) -> Result<String> {
+        match self.path() {
+            Some(path) => {
+                std::fs::read_to_string(path).with_context(|| format!("Failed to read the request from {}", self))
+            }
+            None => {
+                let mut yaml = String::new();
+                std::io::stdin().read_to_string(&mut yaml).context("Failed to read the request from standard input")?;
+                Ok(yaml)
+            }
+        }
+    }
+}
+
+impl fmt::Display for RequestSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flag(path) => write!(f, "--request {}", path.display()),
+            Self::Env(path) => write!(f, "{}={}", REQUEST_ENV, path.display()),
+            Self::WorkingDirectory(path) => write!(f, "the working directory's {}", path.display()),
+            Self::Demo(path) => write!(f, "the demo request {}", path.display()),
+            Self::Resumed(path) => write!(f, "the resumed run's {}", path.display()),
+            Self::Stdin => write!(f, "standard input"),
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub inputs: Inputs,
+    /// Plans the workflow and skips the simulations: prints the selected organisms, the initial
+    /// media and its cost, and a blueprint-based estimate of the downstream processes, and writes
+    /// them to `plan.json` in a plan directory.
+    #[arg(long, visible_alias = "plan", env = "BIOFORGE_DRY_RUN")]
+    pub dry_run: bool,
+    /// Runs the one-at-a-time sensitivity study of this file instead of the workflow.
+    #[arg(long, value_name = "STUDY", num_args = 0..=1, default_missing_value = "bioforge-app/sensitivity.yaml")]
+    pub sensitivity: Option<PathBuf>,
+    /// Draws no plots.
+    #[arg(long, env = "BIOFORGE_NO_PLOTS")]
+    pub no_plots: bool,
+    /// Leaves the snapshot of the resolved knowledge base out of the run directory.
+    #[arg(long, env = "BIOFORGE_NO_KB_SNAPSHOT")]
+    pub no_kb_snapshot: bool,
+    /// Resumes the interrupted run in this run directory with its own request: the simulations
+    /// it completed are read back from their logs and only the rest are run. The knowledge base
+    /// must not have changed since the run started.
+    #[arg(long, value_name = "RUN_DIR", conflicts_with_all = ["request", "dry_run", "sensitivity"])]
+    pub resume: Option<PathBuf>,
+    /// Labels the time-series plots with calendar dates instead of elapsed hours.
+    #[arg(long)]
+    pub date_axis: bool,
+    /// How the upstream log lays out its series.
+    #[arg(long, value_enum, env = "BIOFORGE_LOG_FORMAT", default_value_t = LogFormat::Json)]
+    pub log_format: LogFormat,
+    /// Where the time-series logs go: `csv` files or one `sqlite` database per run directory.
+    #[arg(long, env = "BIOFORGE_LOG_BACKEND", default_value = "csv")]
+    pub log_backend: LogBackend,
+}
+
+/// The layout of the upstream CSV log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// The state of each tick as embedded JSON.
+    Json,
+    /// One numeric column per molecule, gas, organism, and asset, for spreadsheets and dataframes.
+    Wide,
+}
+
+#[derive(Debug, Args)]
+pub struct MonteCarloArgs {
+    #[command(flatten)]
+    pub inputs: Inputs,
+    /// The study to run.
+    #[arg(long, env = "BIOFORGE_STUDY", default_value = "bioforge-app/monte_carlo.yaml")]
+    pub study: PathBuf,
+    /// Seeds the draws instead of the study's own seed.
+    #[arg(long, env = "BIOFORGE_SEED")]
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Args)]
+pub struct SweepArgs {
+    /// The request the sweep starts from unless the sweep names its own, and where the knowledge
+    /// base and the run directories are.
+    #[command(flatten)]
+    pub inputs: Inputs,
+    /// The sweep to run.
+    #[arg(long, env = "BIOFORGE_SWEEP", default_value = "bioforge-app/sweep.yaml")]
+    pub sweep: PathBuf,
+    /// Draws no plots of the scenarios, only the overlay of their biomass.
+    #[arg(long, env = "BIOFORGE_NO_PLOTS")]
+    pub no_plots: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct CompareArgs {
+    pub run_a: PathBuf,
+    pub run_b: PathBuf,
+    /// The directory the comparison's run directory is created in.
+    #[arg(long, env = "BIOFORGE_OUT", default_value = "data/runs")]
+    pub out: PathBuf,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Cli {
+        Cli::try_parse_from(std::iter::once("bioforge-app").chain(args.iter().copied())).unwrap()
+    }
+
+    fn run_args(args: &[&str]) -> RunArgs {
+        match parse(args) {
+            Cli { command: Some(Command::Run(run)), .. } => run,
+            Cli { command: None, run } => run,
+            other => panic!("expected a run, got {:?}", other.command),
+        }
+    }
+
+    #[test]
+    fn a_bare_invocation_runs_with_the_defaults() {
+        let run = run_args(&[]);
+        assert_eq!(run.inputs.request, None);
+        assert_eq!(run.inputs.kb, PathBuf::from("data/knowledge_base"));
+        assert!(run.inputs.kb_overlays.is_empty());
+        assert_eq!(run.inputs.out, PathBuf::from("data/runs"));
+        assert!(!run.inputs.strict && !run.dry_run && !run.no_plots && !run.no_kb_snapshot);
+        assert_eq!(run.log_format, LogFormat::Json);
+        assert_eq!(run.sensitivity, None);
+    }
+
+    #[test]
+    fn run_options_parse_with_or_without_the_subcommand() {
+        let options = [
+            "--request", "in/request.json", "--kb", "kb", "--kb-overlay", "site-a", "--kb-overlay", "site-b",
+            "--out", "out", "--strict", "--dry-run", "--no-plots", "--no-kb-snapshot", "--log-format", "wide",
+        ];
+        let with_subcommand: Vec<&str> = std::iter::once("run").chain(options).collect();
+        for args in [&options[..], &with_subcommand[..]] {
+            let run = run_args(args);
+            assert_eq!(run.inputs.request, Some(PathBuf::from("in/request.json")));
+            assert_eq!(run.inputs.kb, PathBuf::from("kb"));
+            assert_eq!(run.inputs.kb_overlays, [PathBuf::from("site-a"), PathBuf::from("site-b")]);
+            assert_eq!(run.inputs.out, PathBuf::from("out"));
+            assert!(run.inputs.strict && run.dry_run && run.no_plots && run.no_kb_snapshot);
+            assert_eq!(run.log_format, LogFormat::Wide);
+        }
+        assert!(run_args(&["--plan"]).dry_run);
+        assert_eq!(run_args(&["--sensitivity"]).sensitivity, Some(PathBuf::from("bioforge-app/sensitivity.yaml")));
+    }
+
+    #[test]
+    fn the_other_subcommands_parse_their_own_options() {
+        match parse(&["monte-carlo", "--study", "study.yaml", "--seed", "7"]).command {
+            Some(Command::MonteCarlo(args)) => {
+                assert_eq!(args.study, PathBuf::from("study.yaml"));
+                assert_eq!(args.seed, Some(7));
+            }
+            other => panic!("expected a Monte Carlo study, got {:?}", other),
+        }
+        match parse(&["compare", "runs/a", "runs/b", "--out", "out"]).command {
+            Some(Command::Compare(args)) => {
+                assert_eq!((args.run_a, args.run_b, args.out), ("runs/a".into(), "runs/b".into(), "out".into()));
+            }
+            other => panic!("expected a comparison, got {:?}", other),
+        }
+        match parse(&["sweep", "--sweep", "sweep.yaml", "--no-plots"]).command {
+            Some(Command::Sweep(args)) => assert!(args.sweep == Path::new("sweep.yaml") && args.no_plots),
+            other => panic!("expected a sweep, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        let fails = |args: &[&str]| Cli::try_parse_from(std::iter::once("bioforge-app").chain(args.iter().copied())).is_err();
+        assert!(fails(&["--log-format", "xml"]));
+        assert!(fails(&["--resume", "runs/a", "--request", "request.yaml"]));
+        assert!(fails(&["--resume", "runs/a", "--dry-run"]));
+        assert!(fails(&["monte-carlo", "--seed", "seven"]));
+        assert!(fails(&["compare", "runs/a"]));
+        assert!(fails(&["--no-such-flag"]));
+    }
+
+    #[test]
+    fn a_request_of_dash_is_read_from_standard_input() {
+        let mut inputs = run_args(&["--request", "-"]).inputs;
+        assert_eq!(inputs.request_source().unwrap(), RequestSource::Stdin);
+        inputs.request = Some(PathBuf::from("in/request.yaml"));
+        assert_eq!(inputs.request_source().unwrap(), RequestSource::Flag(PathBuf::from("in/request.yaml")));
+    }
+}
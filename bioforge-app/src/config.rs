@@ -1,19 +1,27 @@
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use bioforge_schemas::{
     asset::Asset,
     file_formats::{
-        AssetFile, LaborRoleFile, MaterialFile, OrganismFile, ProcessFile, RuleFile,
+        AssetFile, AssumptionsFile, LaborRoleFile, MaterialFile, OrganismFile, ProcessFile, RuleFile,
+        SCHEMA_VERSION,
     },
     labor::LaborRole,
     material::Material,
     organism::Organism,
     process::Process,
     rule::Rule,
+    tea_lca::EconomicAssumptions,
+};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
 };
-use std::{collections::HashMap, fs, path::Path};
 
 /// A container for all the static data loaded from YAML files.
 /// This represents the complete "knowledge base" for a simulation run.
+#[derive(Clone)]
 pub struct KnowledgeBase {
     pub assets: HashMap<String, Asset>,
     pub materials: HashMap<String, Material>,
@@ -21,59 +29,378 @@ pub struct KnowledgeBase {
     pub labor_roles: HashMap<String, LaborRole>,
     pub processes: HashMap<String, Process>,
     pub rules: HashMap<String, Rule>,
+    /// The economic and impact assumptions of `7_assumptions`, or the defaults if it has none.
+    pub assumptions: EconomicAssumptions,
+    /// A hash of the paths and contents of every file under the base directory, so runs can
+    /// tell whether they used the same knowledge base.
+    pub content_hash: String,
+    /// The SHA-256 of each file under the base directory, keyed by its relative path, and of
+    /// each file of the overlays, keyed by the overlay's path joined to it, for the run manifest.
+    pub file_hashes: BTreeMap<String, String>,
+    /// The overlays loaded on top of the base directory, in order.
+    pub overlays: Vec<String>,
+    /// The overlay that supplied each entity overlays replaced or added, keyed by
+    /// `<directory>/<id>`, e.g. `1_materials/CONS-HEXANE-01`; the last overlay wins.
+    pub overrides: BTreeMap<String, String>,
 }
 
 impl KnowledgeBase {
-    /// Loads all data from the specified base directory.
-    pub fn load(base_path: &str) -> Result<Self> {
+    /// Loads the knowledge base in `base_path`, then each of `overlays` in order on top of it.
+    /// An overlay holds any of the numbered directories of a knowledge base; each entity it
+    /// defines replaces the one of the same id, whole, or is added, and its assumption set, if it
+    /// has one, replaces the assumptions. The files of an overlay are hashed under its path.
+    pub fn load_with_overlays(base_path: &str, overlays: &[PathBuf]) -> Result<Self> {
         println!("Loading knowledge base from '{}'...", base_path);
+        let base = Path::new(base_path);
+        let layer = Layer::load(base, true)?;
+        let mut kb = Self {
+            assets: layer.assets,
+            materials: layer.materials,
+            organisms: layer.organisms,
+            labor_roles: layer.labor_roles,
+            processes: layer.processes,
+            rules: layer.rules,
+            assumptions: layer.assumptions.unwrap_or_default(),
+            content_hash: content_hash(base)?,
+            file_hashes: file_hashes(base)?,
+            overlays: Vec::new(),
+            overrides: BTreeMap::new(),
+        };
+
+        for overlay in overlays {
+            println!("Applying knowledge base overlay '{}'...", overlay.display());
+            let layer = Layer::load(overlay, false)?;
+            let name = overlay.to_string_lossy().into_owned();
+            let overrides = &mut kb.overrides;
+            apply_overlay(&mut kb.assets, layer.assets, "3_assets", &name, overrides);
+            apply_overlay(&mut kb.materials, layer.materials, "1_materials", &name, overrides);
+            apply_overlay(&mut kb.organisms, layer.organisms, "2_organisms", &name, overrides);
+            apply_overlay(&mut kb.labor_roles, layer.labor_roles, "4_labor", &name, overrides);
+            apply_overlay(&mut kb.processes, layer.processes, "5_processes", &name, overrides);
+            apply_overlay(&mut kb.rules, layer.rules, "6_rules", &name, overrides);
+            if let Some(assumptions) = layer.assumptions {
+                kb.assumptions = assumptions;
+                overrides.insert("7_assumptions".to_string(), name.clone());
+            }
+            for (relative, hash) in file_hashes(overlay)? {
+                kb.file_hashes.insert(format!("{}/{}", name, relative), hash);
+            }
+            kb.content_hash = format!("{}+{}", kb.content_hash, content_hash(overlay)?);
+            kb.overlays.push(name);
+        }
+
+        println!("Knowledge base loaded successfully.");
+        Ok(kb)
+    }
+}
 
-        let assets = load_yaml_files_into_map(
-            Path::new(base_path).join("3_assets"),
-            |file: AssetFile| file.assets,
-            |item: &Asset| item.asset_id.clone(),
+/// The directory, in a run directory, the knowledge base a run was computed from is exported to.
+pub const KB_SNAPSHOT_DIR: &str = "knowledge_base";
+
+impl KnowledgeBase {
+    /// Writes the knowledge base as it was resolved, overlays applied, to `dir` as a knowledge
+    /// base of its own: one file per numbered directory, entities sorted by id, so that loading it
+    /// gives back the same entities and assumptions.
+    pub fn export(&self, dir: &Path) -> Result<()> {
+        let schema_version = format!("{}.{}", SCHEMA_VERSION.0, SCHEMA_VERSION.1);
+        write_yaml_file(
+            &dir.join("1_materials/materials.yaml"),
+            &MaterialFile { schema_version: schema_version.clone(), materials: sorted_by_id(&self.materials) },
         )?;
-        let materials = load_yaml_files_into_map(
-            Path::new(base_path).join("1_materials"),
-            |file: MaterialFile| file.materials,
-            |item: &Material| item.material_id.clone(),
+        write_yaml_file(
+            &dir.join("2_organisms/organisms.yaml"),
+            &OrganismFile { schema_version: schema_version.clone(), organisms: sorted_by_id(&self.organisms) },
         )?;
-        let organisms = load_yaml_files_into_map(
-            Path::new(base_path).join("2_organisms"),
-            |file: OrganismFile| file.organisms,
-            |item: &Organism| item.organism_id.clone(),
+        write_yaml_file(
+            &dir.join("3_assets/assets.yaml"),
+            &AssetFile { schema_version: schema_version.clone(), assets: sorted_by_id(&self.assets) },
         )?;
-        let labor_roles = load_yaml_files_into_map(
-            Path::new(base_path).join("4_labor"),
-            |file: LaborRoleFile| file.labor_roles,
-            |item: &LaborRole| item.labor_role_id.clone(),
+        write_yaml_file(
+            &dir.join("4_labor/labor_roles.yaml"),
+            &LaborRoleFile { schema_version: schema_version.clone(), labor_roles: sorted_by_id(&self.labor_roles) },
         )?;
-        let processes = load_yaml_files_into_map(
-            Path::new(base_path).join("5_processes"),
-            |file: ProcessFile| file.processes,
-            |item: &Process| item.process_id.clone(),
+        write_yaml_file(
+            &dir.join("5_processes/processes.yaml"),
+            &ProcessFile { schema_version: schema_version.clone(), processes: sorted_by_id(&self.processes) },
         )?;
-        let rules = load_yaml_files_into_map(
-            Path::new(base_path).join("6_rules"),
-            |file: RuleFile| file.rules,
-            |item: &Rule| item.name.clone(),
+        write_yaml_file(
+            &dir.join("6_rules/rules.yaml"),
+            &RuleFile { schema_version: schema_version.clone(), rules: sorted_by_id(&self.rules) },
         )?;
+        write_yaml_file(
+            &dir.join("7_assumptions/assumptions.yaml"),
+            &AssumptionsFile { schema_version, assumptions: self.assumptions.clone() },
+        )
+    }
+}
 
-        println!("Knowledge base loaded successfully.");
+/// The entities of `entities`, cloned, in id order.
+fn sorted_by_id<T: Clone>(entities: &HashMap<String, T>) -> Vec<T> {
+    let mut ids: Vec<&String> = entities.keys().collect();
+    ids.sort();
+    ids.into_iter().map(|id| entities[id].clone()).collect()
+}
+
+/// Writes `file` to `path` as YAML, creating its directory.
+fn write_yaml_file<F: serde::Serialize>(path: &Path, file: &F) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| format!("Failed to create directory: {:?}", parent))?;
+    }
+    fs::write(path, serde_yaml::to_string(file)?).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// The entities and assumption set of one knowledge-base directory.
+struct Layer {
+    assets: HashMap<String, Asset>,
+    materials: HashMap<String, Material>,
+    organisms: HashMap<String, Organism>,
+    labor_roles: HashMap<String, LaborRole>,
+    processes: HashMap<String, Process>,
+    rules: HashMap<String, Rule>,
+    assumptions: Option<EconomicAssumptions>,
+}
+
+impl Layer {
+    /// Loads the knowledge-base directory `base`. Every numbered directory but `7_assumptions`
+    /// must exist when `required`; an overlay may leave any of them out.
+    fn load(base: &Path, required: bool) -> Result<Self> {
         Ok(Self {
-            assets,
-            materials,
-            organisms,
-            labor_roles,
-            processes,
-            rules,
+            assets: load_files_into_map(
+                base.join("3_assets"),
+                required,
+                |file: AssetFile| file.assets,
+                |item: &Asset| item.asset_id.clone(),
+            )?,
+            materials: load_files_into_map(
+                base.join("1_materials"),
+                required,
+                |file: MaterialFile| file.materials,
+                |item: &Material| item.material_id.clone(),
+            )?,
+            organisms: load_files_into_map(
+                base.join("2_organisms"),
+                required,
+                |file: OrganismFile| file.organisms,
+                |item: &Organism| item.organism_id.clone(),
+            )?,
+            labor_roles: load_files_into_map(
+                base.join("4_labor"),
+                required,
+                |file: LaborRoleFile| file.labor_roles,
+                |item: &LaborRole| item.labor_role_id.clone(),
+            )?,
+            processes: load_files_into_map(
+                base.join("5_processes"),
+                required,
+                |file: ProcessFile| file.processes,
+                |item: &Process| item.process_id.clone(),
+            )?,
+            rules: load_files_into_map(
+                base.join("6_rules"),
+                required,
+                |file: RuleFile| file.rules,
+                |item: &Rule| item.name.clone(),
+            )?,
+            assumptions: load_assumptions(base.join("7_assumptions"))?,
+        })
+    }
+}
+
+/// Puts the entities of an overlay named `overlay` into `entities`, replacing those of the same
+/// id, and records in `overrides` that the overlay supplied each, keyed by `<directory>/<id>`.
+fn apply_overlay<T>(
+    entities: &mut HashMap<String, T>,
+    overlay_entities: HashMap<String, T>,
+    directory: &str,
+    overlay: &str,
+    overrides: &mut BTreeMap<String, String>,
+) {
+    for (id, entity) in overlay_entities {
+        let action = if entities.insert(id.clone(), entity).is_some() { "Overriding" } else { "Adding" };
+        println!("  {} {}/{}", action, directory, id);
+        overrides.insert(format!("{}/{}", directory, id), overlay.to_string());
+    }
+}
+
+/// Every file under `base`, with its path relative to `base` in `/`-separated form, in path
+/// order.
+fn files_under(base: &Path) -> Result<Vec<(String, PathBuf)>> {
+    fn collect(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        for entry in fs::read_dir(dir).with_context(|| format!("Failed to read directory: {:?}", dir))? {
+            let path = entry?.path();
+            if path.is_dir() {
+                collect(&path, files)?;
+            } else {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+    let mut files = Vec::new();
+    collect(base, &mut files)?;
+    files.sort();
+    Ok(files
+        .into_iter()
+        .map(|path| (path.strip_prefix(base).unwrap_or(&path).to_string_lossy().replace('\\', "/"), path))
+        .collect())
+}
+
+/// The 64-bit FNV-1a hash, in hex, of the relative path and contents of every file under `base`,
+/// in path order. It is stable across platforms and Rust versions, unlike the standard hasher.
+fn content_hash(base: &Path) -> Result<String> {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for (relative, path) in files_under(base)? {
+        for byte in relative.bytes().chain([0]).chain(fs::read(&path)?) {
+            hash = (hash ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+    Ok(format!("{:016x}", hash))
+}
+
+/// The SHA-256, in hex, of the contents of every file under `base`, keyed by its relative path.
+fn file_hashes(base: &Path) -> Result<BTreeMap<String, String>> {
+    files_under(base)?
+        .into_iter()
+        .map(|(relative, path)| {
+            let digest = Sha256::digest(fs::read(&path)?);
+            Ok((relative, digest.iter().map(|byte| format!("{:02x}", byte)).collect()))
+        })
+        .collect()
+}
+
+/// Loads the single assumption set in `dir_path`, or `None` for a missing or empty directory;
+/// more than one YAML file is an error, since it would be unclear which set applies.
+fn load_assumptions<P: AsRef<Path>>(dir_path: P) -> Result<Option<EconomicAssumptions>> {
+    let dir_path = dir_path.as_ref();
+    if !dir_path.is_dir() {
+        return Ok(None);
+    }
+    let files = data_files_under(dir_path)?;
+    match files.as_slice() {
+        [] => Ok(None),
+        [path] => {
+            let file: AssumptionsFile = read_data_file(path)?;
+            Ok(Some(file.assumptions))
+        }
+        _ => bail!("Expected at most one assumption set in {:?}, found {}", dir_path, files.len()),
+    }
+}
+
+/// The formats knowledge-base and request files are read in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Yaml,
+    Json,
+}
+
+impl DataFormat {
+    /// The format of the file at `path` by its extension: `.yaml` and `.yml` are YAML, `.json`
+    /// is JSON, anything else `None`.
+    pub fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "yaml" | "yml" => Some(Self::Yaml),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    /// The format of `text` read from no file, or from a file without a known extension: JSON if
+    /// it opens with `{`, else YAML.
+    pub fn sniff(text: &str) -> Self {
+        if text.trim_start().starts_with('{') {
+            Self::Json
+        } else {
+            Self::Yaml
+        }
+    }
+
+    /// The extension files of this format are written with.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Yaml => "yaml",
+            Self::Json => "json",
+        }
+    }
+
+    /// Parses `text` in this format.
+    pub fn parse<T: for<'de> serde::Deserialize<'de>>(self, text: &str) -> Result<T> {
+        Ok(match self {
+            Self::Yaml => serde_yaml::from_str(text)?,
+            Self::Json => serde_json::from_str(text)?,
+        })
+    }
+}
+
+impl std::fmt::Display for DataFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Yaml => "YAML",
+            Self::Json => "JSON",
         })
     }
 }
 
-/// Generic helper to load all YAML files in a directory into a HashMap.
-fn load_yaml_files_into_map<P, F, E, T, K>(
+/// The YAML and JSON files under `dir`, at any depth, in path order. Files and directories whose
+/// names start with `_` or `.` are drafts and are skipped.
+fn data_files_under(dir: &Path) -> Result<Vec<PathBuf>> {
+    Ok(files_under(dir)?
+        .into_iter()
+        .filter(|(relative, _)| !relative.split('/').any(|name| name.starts_with('_') || name.starts_with('.')))
+        .map(|(_, path)| path)
+        .filter(|path| DataFormat::of(path).is_some())
+        .collect())
+}
+
+/// The `schema_version` every knowledge-base file wrapper carries.
+#[derive(serde::Deserialize)]
+struct VersionedFile {
+    schema_version: String,
+}
+
+/// Parses the knowledge-base file at `path`, YAML or JSON by its extension, once its
+/// `schema_version` is one this build reads.
+fn read_data_file<F: for<'de> serde::Deserialize<'de>>(path: &Path) -> Result<F> {
+    let format = DataFormat::of(path).unwrap_or(DataFormat::Yaml);
+    let content = fs::read_to_string(path).with_context(|| format!("Failed to read {:?}", path))?;
+    let versioned: VersionedFile =
+        format.parse(&content).with_context(|| format!("Failed to parse {} from {:?}", format, path))?;
+    check_schema_version(path, &versioned.schema_version)?;
+    format.parse(&content).with_context(|| format!("Failed to parse {} from {:?}", format, path))
+}
+
+/// Checks `version`, the `schema_version` of the file at `path`, against [`SCHEMA_VERSION`]:
+/// another major version is an error, another minor version a warning.
+fn check_schema_version(path: &Path, version: &str) -> Result<()> {
+    let (major, minor) = SCHEMA_VERSION;
+    let supported = format!("{}.{}", major, minor);
+    let mut parts = version.trim().splitn(2, '.');
+    let parsed_major = parts.next().and_then(|p| p.parse::<u32>().ok());
+    let parsed_minor = match parts.next() {
+        Some(p) => p.parse::<u32>().ok(),
+        None => Some(0),
+    };
+    let (Some(file_major), Some(file_minor)) = (parsed_major, parsed_minor) else {
+        bail!("{:?} has schema_version '{}', which is not a major.minor version; this build reads {}", path, version, supported);
+    };
+    if file_major != major {
+        bail!("{:?} has schema_version {}, which this build cannot read; it reads {}", path, version, supported);
+    }
+    if file_minor != minor {
+        log::warn!("{:?} has schema_version {}; this build reads {} and may ignore or miss fields", path, version, supported);
+    }
+    Ok(())
+}
+
+/// Loads the items of every YAML and JSON file under `dir_path`, at any depth, into a HashMap by
+/// the key `get_key` gives each. A missing directory loads nothing unless it is `required`.
+///
+/// # Errors
+///
+/// Fails on a file of an unsupported schema version, and on a key defined twice, naming both
+/// files it is defined in.
+fn load_files_into_map<P, F, E, T, K>(
     dir_path: P,
+    required: bool,
     extract_vec: E,
     get_key: K,
 ) -> Result<HashMap<String, T>>
@@ -84,20 +411,158 @@ where
     K: Fn(&T) -> String,                 // A closure to get the key for the map from an item T
 {
     let mut map = HashMap::new();
-    for entry in fs::read_dir(dir_path.as_ref())
-        .with_context(|| format!("Failed to read directory: {:?}", dir_path.as_ref()))?
-    {
-        let entry = entry?;
-        let path = entry.path();
-        if path.is_file() && path.extension().map_or(false, |s| s == "yaml" || s == "yml") {
-            let content = fs::read_to_string(&path)?;
-            let file_wrapper: F = serde_yaml::from_str(&content)
-                .with_context(|| format!("Failed to parse YAML from {:?}", path))?;
-            
-            for item in extract_vec(file_wrapper) {
-                map.insert(get_key(&item), item);
+    if !required && !dir_path.as_ref().is_dir() {
+        return Ok(map);
+    }
+    let mut sources: HashMap<String, PathBuf> = HashMap::new();
+    for path in data_files_under(dir_path.as_ref())? {
+        let file_wrapper: F = read_data_file(&path)?;
+        for item in extract_vec(file_wrapper) {
+            let key = get_key(&item);
+            if let Some(first) = sources.insert(key.clone(), path.clone()) {
+                bail!("'{}' is defined in both {:?} and {:?}", key, first, path);
             }
+            map.insert(key, item);
         }
     }
     Ok(map)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    struct ItemFile {
+        items: Vec<Item>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Item {
+        id: String,
+    }
+
+    /// A fresh, empty directory under the system temp dir for the test `name`.
+    pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bioforge_config_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// The knowledge base of the repository.
+    pub(crate) const KNOWLEDGE_BASE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../data/knowledge_base");
+
+    /// A copy of the knowledge base of the repository in a scratch directory for the test `name`,
+    /// to be changed by the test.
+    pub(crate) fn knowledge_base_copy(name: &str) -> PathBuf {
+        let dir = scratch_dir(name);
+        for (relative, path) in files_under(Path::new(KNOWLEDGE_BASE)).unwrap() {
+            let target = dir.join(relative);
+            fs::create_dir_all(target.parent().unwrap()).unwrap();
+            fs::copy(path, target).unwrap();
+        }
+        dir
+    }
+
+    fn write(dir: &Path, relative: &str, content: &str) {
+        let path = dir.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn load(dir: &Path) -> Result<HashMap<String, Item>> {
+        load_files_into_map(dir, true, |file: ItemFile| file.items, |item: &Item| item.id.clone())
+    }
+
+    #[test]
+    fn files_load_at_any_depth_and_drafts_are_skipped() {
+        let dir = scratch_dir("recursive");
+        write(&dir, "a.yaml", "schema_version: '1.0'\nitems: [{id: A}]\n");
+        write(&dir, "nested/deeper/b.json", r#"{"schema_version": "1.0", "items": [{"id": "B"}]}"#);
+        write(&dir, "_draft.yaml", "schema_version: '1.0'\nitems: [{id: DRAFT}]\n");
+        write(&dir, ".hidden/c.yaml", "schema_version: '1.0'\nitems: [{id: HIDDEN}]\n");
+        write(&dir, "nested/_drafts/d.yaml", "schema_version: '1.0'\nitems: [{id: NESTED_DRAFT}]\n");
+        write(&dir, "notes.txt", "not a data file");
+
+        let mut ids: Vec<String> = load(&dir).unwrap().into_keys().collect();
+        ids.sort();
+        assert_eq!(ids, ["A", "B"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn an_id_defined_twice_names_both_files() {
+        let dir = scratch_dir("duplicate");
+        write(&dir, "first.yaml", "schema_version: '1.0'\nitems: [{id: A}]\n");
+        write(&dir, "nested/second.yaml", "schema_version: '1.0'\nitems: [{id: A}]\n");
+
+        let message = load(&dir).err().unwrap().to_string();
+        assert!(message.contains("'A'"), "{}", message);
+        assert!(message.contains("first.yaml"), "{}", message);
+        assert!(message.contains("second.yaml"), "{}", message);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn another_major_schema_version_is_rejected() {
+        let dir = scratch_dir("major");
+        write(&dir, "a.yaml", "schema_version: '2.0'\nitems: [{id: A}]\n");
+
+        let message = format!("{:#}", load(&dir).err().unwrap());
+        assert!(message.contains("cannot read"), "{}", message);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn schema_versions_are_checked_by_major_then_minor() {
+        let path = Path::new("file.yaml");
+        assert!(check_schema_version(path, "1.0").is_ok());
+        assert!(check_schema_version(path, "1").is_ok());
+        // Another minor version only warns.
+        assert!(check_schema_version(path, "1.7").is_ok());
+        assert!(check_schema_version(path, "0.9").is_err());
+        assert!(check_schema_version(path, "2.0").is_err());
+        assert!(check_schema_version(path, "one").is_err());
+    }
+
+    #[test]
+    fn an_exported_knowledge_base_loads_back_unchanged() {
+        let kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let dir = scratch_dir("export");
+        kb.export(&dir).unwrap();
+        let exported = KnowledgeBase::load_with_overlays(&dir.to_string_lossy(), &[]).unwrap();
+
+        assert_eq!(exported.materials, kb.materials);
+        assert_eq!(exported.organisms, kb.organisms);
+        assert_eq!(exported.assets, kb.assets);
+        assert_eq!(exported.labor_roles, kb.labor_roles);
+        assert_eq!(exported.processes, kb.processes);
+        assert_eq!(exported.rules, kb.rules);
+        assert_eq!(exported.assumptions, kb.assumptions);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn json_files_load_to_the_same_knowledge_base_as_yaml() {
+        let dir = scratch_dir("json");
+        for (relative, path) in files_under(Path::new(KNOWLEDGE_BASE)).unwrap() {
+            let content: serde_json::Value = serde_yaml::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+            let target = dir.join(relative).with_extension("json");
+            fs::create_dir_all(target.parent().unwrap()).unwrap();
+            fs::write(target, serde_json::to_string_pretty(&content).unwrap()).unwrap();
+        }
+        let yaml = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let json = KnowledgeBase::load_with_overlays(&dir.to_string_lossy(), &[]).unwrap();
+
+        assert!(json.file_hashes.keys().all(|file| file.ends_with(".json")));
+        assert_eq!(json.materials, yaml.materials);
+        assert_eq!(json.organisms, yaml.organisms);
+        assert_eq!(json.assets, yaml.assets);
+        assert_eq!(json.labor_roles, yaml.labor_roles);
+        assert_eq!(json.processes, yaml.processes);
+        assert_eq!(json.rules, yaml.rules);
+        assert_eq!(json.assumptions, yaml.assumptions);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
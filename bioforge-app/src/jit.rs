@@ -1,103 +1,944 @@
 use crate::config::KnowledgeBase;
-use anyhow::{Context, Result};
+use crate::plotting::{BiomassScale, PlotConfig};
+use anyhow::{bail, Context, Result};
+use bioforge_core::analysis::{
+    self, campaign::CampaignParameters, carbon_balance::CARBON_DIOXIDE_ID, constraints::{Constraints, OrganismFilter}, media_recipe::{self, MediaRecipe, RecipeIngredient}, BillOfMaterials, BlueprintStep,
+    CostAllocation, DurationSource, ExecutableBlueprint,
+};
+use bioforge_core::simulation::engine::{nutrient_molar_mass, TIME_STEP_HR};
 use bioforge_schemas::{
     environment::{DissolvedComponent, MediaComposition, MediaState, Measurement},
-    organism::Organism,
-    process::Process,
+    organism::{EnvironmentalTolerances, MediaExchangeRate, Organism, ToleranceRange},
+    process::{Method, Process},
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::Path,
 };
-use std::{collections::HashMap, fs, path::Path};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 
 /// Represents a high-level goal for the bioprocess, now supporting multiple targets.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ValorizationRequest {
     pub targets: Vec<TargetRequest>,
+    /// How costs shared by the targets, such as the upstream cultivation, are split between them.
+    /// Defaults to a mass allocation.
+    #[serde(default)]
+    pub cost_allocation: CostAllocation,
+    /// The relative closure error of the upstream carbon or nitrogen balance above which a warning
+    /// is printed. Defaults to 5%.
+    #[serde(default = "default_mass_balance_tolerance")]
+    pub mass_balance_tolerance: f64,
+    /// The year of batches the run is annualized over. Defaults to as many batches as fit.
+    #[serde(default)]
+    pub campaign: CampaignParameters,
+    /// How the plots are drawn and which are drawn. Defaults to every figure as a 1024 × 768 PNG.
+    #[serde(default)]
+    pub plotting: PlotConfig,
+    /// The names of the media components the media composition chart shows. Defaults to the fed
+    /// components and those that peak highest.
+    #[serde(default)]
+    pub plotted_media_components: Option<Vec<String>>,
+    /// Whether the biomass growth chart has a linear or log10 axis, or is drawn with both.
+    /// Defaults to linear.
+    #[serde(default)]
+    pub biomass_scale: BiomassScale,
+    /// The most a batch may cost, take, and emit. Organisms estimated to break a limit on their own
+    /// are not selected, and the run is checked against every limit once it is done.
+    #[serde(default)]
+    pub constraints: Constraints,
+    /// Which organisms the optimizer may select, by the request's `allow_engineered`,
+    /// `excluded_organism_ids`, and `required_organism_ids`. Defaults to any.
+    #[serde(flatten)]
+    pub organism_filter: OrganismFilter,
+    /// The temperature the upstream vessel is held at, in °C. Defaults to the compromise
+    /// temperature of organisms whose shared window is narrow, and otherwise to the vessel's own.
+    #[serde(default)]
+    pub cultivation_temperature_celsius: Option<f64>,
+    /// The share of each substrate's estimated demand charged to the initial media, above 0 and at
+    /// most 1; the feed rules add the rest. Defaults to 30%.
+    #[serde(default = "default_initial_charge_fraction")]
+    pub initial_charge_fraction: f64,
+    /// How many times the nitrogen the organisms' biomass holds is dosed to the initial media, at
+    /// least 1. Defaults to 1.2.
+    #[serde(default = "default_nitrogen_excess_factor")]
+    pub nitrogen_excess_factor: f64,
+    /// How the upstream cultivation is run: as a batch, a fed-batch, or a continuous culture.
+    /// Defaults to a fed-batch with the default feed.
+    #[serde(default)]
+    pub cultivation_strategy: CultivationStrategy,
+    /// A waste stream to valorize. Only organisms that consume one of its components are
+    /// selected, the initial media is charged with it in place of pure substrates, and the
+    /// targets are fitted to what it can make.
+    #[serde(default)]
+    pub available_feedstock: Option<AvailableFeedstock>,
+    /// A train of seed cultures in smaller bioreactors that scales the inoculum up to the
+    /// production bioreactor. Defaults to inoculating the production bioreactor directly.
+    #[serde(default)]
+    pub seed_train: Option<SeedTrain>,
+}
+
+/// How a seed train scales the inoculum up.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeedTrain {
+    /// How many times larger each vessel's working volume is than that of the seed culture
+    /// transferred into it, above 1. Defaults to 10.
+    #[serde(default = "default_scale_up_ratio")]
+    pub scale_up_ratio: f64,
+}
+
+fn default_scale_up_ratio() -> f64 {
+    10.0
+}
+
+/// A feedstock available in a limited amount, such as a waste stream of another process.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AvailableFeedstock {
+    /// The knowledge-base material the feedstock is priced as, sold by the kg.
+    pub material_id: String,
+    pub amount_kg: f64,
+    /// The components the organisms can take up, as mass fractions of the feedstock.
+    pub composition: Vec<FeedstockComponent>,
+    /// Whether targets the feedstock cannot make are scaled down to what it can, or only warned
+    /// about. Defaults to scaling them.
+    #[serde(default = "default_scale_targets")]
+    pub scale_targets: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedstockComponent {
+    pub molecule_id: String,
+    pub molecule_name: String,
+    pub mass_fraction: f64,
+}
+
+impl AvailableFeedstock {
+    /// The component of the feedstock with molecule id `molecule_id`, if it holds one.
+    pub fn component(&self, molecule_id: &str) -> Option<&FeedstockComponent> {
+        self.composition.iter().find(|c| c.molecule_id == molecule_id)
+    }
+
+    /// Whether `organism` takes up any component of the feedstock.
+    pub fn feeds(&self, organism: &Organism) -> bool {
+        organism
+            .dynamic_parameters
+            .metabolic_exchange
+            .media_consumption
+            .iter()
+            .any(|c| self.component(&c.molecule_id).is_some())
+    }
+}
+
+fn default_scale_targets() -> bool {
+    true
+}
+
+/// How the upstream cultivation is fed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum CultivationStrategy {
+    /// The initial media is charged with each substrate's full estimated demand and nothing is
+    /// added; the run ends once growth stops.
+    Batch,
+    /// Each substrate the initial media does not cover is fed once it falls below
+    /// `trigger_g_per_l`.
+    FedBatch {
+        #[serde(default = "default_feed_trigger")]
+        trigger_g_per_l: f64,
+        #[serde(default)]
+        feed: FeedAmount,
+    },
+    /// Every hour, `dilution_rate_per_hr` of the vessel's volume is exchanged for fresh initial
+    /// media. The broth taken out is collected in [`HARVEST_VESSEL_ID`] and counts toward the
+    /// targets.
+    Continuous { dilution_rate_per_hr: f64 },
+}
+
+impl Default for CultivationStrategy {
+    fn default() -> Self {
+        CultivationStrategy::FedBatch { trigger_g_per_l: default_feed_trigger(), feed: FeedAmount::default() }
+    }
+}
+
+/// How much of a substrate a fed-batch adds each time it runs low.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "amount", rename_all = "snake_case")]
+pub enum FeedAmount {
+    /// The estimated shortfall of the substrate in `additions` equal boluses.
+    Bolus {
+        #[serde(default = "default_feed_additions")]
+        additions: u32,
+    },
+    /// As much as brings the substrate back up to `g_per_l`.
+    ToConcentration { g_per_l: f64 },
+}
+
+impl Default for FeedAmount {
+    fn default() -> Self {
+        FeedAmount::Bolus { additions: default_feed_additions() }
+    }
+}
+
+fn default_feed_trigger() -> f64 {
+    1.0
+}
+
+fn default_feed_additions() -> u32 {
+    4
+}
+
+fn default_mass_balance_tolerance() -> f64 {
+    0.05
+}
+
+fn default_initial_charge_fraction() -> f64 {
+    0.3
+}
+
+fn default_nitrogen_excess_factor() -> f64 {
+    1.2
 }
 
 /// Defines a specific target molecule and the objective for its production.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TargetRequest {
     pub molecule_name: String,
     pub objective: Objective,
     pub process_id: String, // Explicitly define the downstream process
     pub target_amount_grams: f64, // The desired final amount of the molecule
+    /// The price a gram of the molecule sells at, for the financial model. Targets without one
+    /// earn no revenue.
+    #[serde(default)]
+    pub selling_price_per_gram: Option<f64>,
+    /// The molecular formula of the molecule, e.g. `C40H56O2`, from which the carbon it holds is
+    /// reported in the carbon balance.
+    #[serde(default)]
+    pub formula: Option<String>,
+    /// The least purity, in percent by mass, the molecule must leave the downstream process at.
+    /// Polishing methods are added to the target's process until it reaches it.
+    #[serde(default)]
+    pub minimum_purity_percent: Option<f64>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub enum Objective {
     MaximizeYield,
     MinimizeCost,
     MinimizeLca,
+    /// The best sum of the yield, cost, and GWP of each candidate, each normalized over the
+    /// candidates so that the best scores 1 and the worst 0, weighted by these weights.
+    Weighted { yield_w: f64, cost_w: f64, lca_w: f64 },
+    /// The knee of the candidates no other beats on yield, cost, and GWP at once: the one closest
+    /// to the ideal of the best of each. Every candidate is written to [`PARETO_CANDIDATES_FILE`].
+    ParetoFront,
+}
+
+/// The dry biomass concentration, in g/L, an organism without a `max_cell_density_g_l` is taken
+/// to reach.
+pub const DEFAULT_MAX_CELL_DENSITY_G_L: f64 = 5.0;
+
+/// The control parameter that gives a bioreactor's working volume, in L.
+const WORKING_VOLUME_KEY: &str = "working_volume";
+
+/// A vessel the upstream cultivation can run on: an asset whose type names a bioreactor and that
+/// has a working volume.
+#[derive(Debug, Clone, Serialize)]
+pub struct Bioreactor {
+    pub asset_id: String,
+    /// The volume of media, in L, the vessel is filled with.
+    pub working_volume_l: f64,
+}
+
+/// The bioreactors of `kb`, smallest first; of the same size, by asset id.
+fn bioreactors(kb: &KnowledgeBase) -> Vec<Bioreactor> {
+    let mut bioreactors: Vec<Bioreactor> = kb
+        .assets
+        .values()
+        .filter(|asset| asset.asset_type.contains("BIOREACTOR"))
+        .filter_map(|asset| {
+            let parameters = asset.operational_parameters.as_ref()?.configuration_and_control.as_ref()?;
+            let volume = parameters.iter().find(|p| p.key == WORKING_VOLUME_KEY)?;
+            Some(Bioreactor { asset_id: asset.asset_id.clone(), working_volume_l: volume.value })
+        })
+        .collect();
+    bioreactors.sort_by(|a, b| a.working_volume_l.total_cmp(&b.working_volume_l).then_with(|| a.asset_id.cmp(&b.asset_id)));
+    bioreactors
+}
+
+/// The culture volume, in L, that holds the grams of dry biomass `required_biomass_g` gives each
+/// of `organisms` without any of them passing its maximum cell density.
+pub fn required_culture_volume_l(organisms: &[Organism], required_biomass_g: &BTreeMap<String, f64>) -> f64 {
+    organisms
+        .iter()
+        .map(|o| {
+            let density = o.dynamic_parameters.max_cell_density_g_l.unwrap_or(DEFAULT_MAX_CELL_DENSITY_G_L);
+            required_biomass_g.get(&o.organism_id).copied().unwrap_or(0.0) / density
+        })
+        .sum()
+}
+
+/// The smallest bioreactor of `kb` whose working volume holds `volume_l` of culture.
+///
+/// # Errors
+///
+/// Fails, naming the largest, if no bioreactor is large enough.
+pub fn select_bioreactor(volume_l: f64, kb: &KnowledgeBase) -> Result<Bioreactor> {
+    let bioreactors = bioreactors(kb);
+    if let Some(bioreactor) = bioreactors.iter().find(|b| b.working_volume_l >= volume_l) {
+        return Ok(bioreactor.clone());
+    }
+    match bioreactors.last() {
+        Some(largest) => bail!(
+            "No bioreactor holds the estimated {:.0} L of culture; the largest, {}, holds {:.0} L",
+            volume_l,
+            largest.asset_id,
+            largest.working_volume_l
+        ),
+        None => bail!("The knowledge base has no bioreactor with a {} to cultivate in", WORKING_VOLUME_KEY),
+    }
+}
+
+/// Sizes the upstream cultivation for the grams of dry biomass `required_biomass_g` gives each
+/// of `organisms`: the smallest bioreactor of `kb` that holds the culture, or, when none does,
+/// as few batches as the largest holds it in, run one after another. Divides the required
+/// biomass into that of one batch, and returns the bioreactor and the number of batches.
+///
+/// # Errors
+///
+/// Fails if `kb` has no bioreactor.
+pub fn split_into_batches(
+    organisms: &[Organism],
+    required_biomass_g: &mut BTreeMap<String, f64>,
+    kb: &KnowledgeBase,
+) -> Result<(Bioreactor, u32)> {
+    let Some(largest) = bioreactors(kb).pop() else {
+        bail!("The knowledge base has no bioreactor with a {} to cultivate in", WORKING_VOLUME_KEY)
+    };
+    let volume_l = required_culture_volume_l(organisms, required_biomass_g);
+    let batches = (volume_l / largest.working_volume_l).ceil().max(1.0) as u32;
+    for required in required_biomass_g.values_mut() {
+        *required /= f64::from(batches);
+    }
+    let bioreactor = select_bioreactor(volume_l / f64::from(batches), kb).unwrap_or(largest);
+    Ok((bioreactor, batches))
+}
+
+/// The port a seed culture leaves its bioreactor through.
+pub const SEED_OUTLET_PORT: &str = "HARVEST_OUTLET";
+
+/// The port a seed culture enters the next bioreactor of its train through.
+pub const SEED_INLET_PORT: &str = "INOCULUM_PORT";
+
+/// The seed bioreactors of `kb` that scale the inoculum up to `production`, smallest first. Each
+/// is the smallest bioreactor, smaller than the one it seeds, that holds the share of that one's
+/// working volume the train's scale-up ratio gives, and has a [`SEED_INLET_PORT`] and a
+/// [`SEED_OUTLET_PORT`]. The train ends once no bioreactor is small enough.
+pub fn seed_train_vessels(production: &Bioreactor, seed_train: &SeedTrain, kb: &KnowledgeBase) -> Vec<Bioreactor> {
+    let has_port = |asset_id: &str, port_id: &str| {
+        kb.assets
+            .get(asset_id)
+            .and_then(|asset| asset.connection_points.as_ref())
+            .is_some_and(|points| points.iter().any(|p| p.port_id == port_id))
+    };
+    let candidates: Vec<Bioreactor> = bioreactors(kb)
+        .into_iter()
+        .filter(|b| has_port(&b.asset_id, SEED_INLET_PORT) && has_port(&b.asset_id, SEED_OUTLET_PORT))
+        .collect();
+    let mut seeds = Vec::new();
+    let mut seeded = production;
+    while let Some(seed) = candidates.iter().find(|b| {
+        b.working_volume_l < seeded.working_volume_l
+            && b.working_volume_l >= seeded.working_volume_l / seed_train.scale_up_ratio
+    }) {
+        seeds.push(seed.clone());
+        seeded = seed;
+    }
+    seeds.reverse();
+    seeds
+}
+
+/// The vessel a continuous cultivation collects the broth it takes out in.
+pub const HARVEST_VESSEL_ID: &str = "CULTIVATION-HARVEST";
+
+/// The molar mass of carbon dioxide in g/mol.
+const CO2_MOLAR_MASS: f64 = 44.009;
+
+/// The cultivation one organism would need to meet a target on its own, for
+/// [`Objective::MinimizeCost`] and [`Objective::MinimizeLca`].
+#[derive(Debug, Clone)]
+pub struct CultivationEstimate {
+    pub organism_id: String,
+    /// Grams of dry biomass holding the target amount at the organism's yield.
+    pub required_biomass_g: f64,
+    /// Hours of exponential growth at the organism's maximum rate from its inoculum to
+    /// `required_biomass_g`.
+    pub duration_hours: f64,
+    /// The substrates the growth consumes, in the totals only, and the energy and labor of the
+    /// smallest bioreactor that holds the culture for `duration_hours`.
+    pub bom: BillOfMaterials,
+    /// Kilograms of carbon dioxide the organism takes up from the gas phase while growing, which
+    /// only photoautotrophs do.
+    pub co2_fixed_kg: f64,
+}
+
+/// Estimates the cultivation `organism` needs to produce the target amount of `target`, or `None`
+/// if it does not produce the molecule or does not grow. Growth is taken as unlimited and
+/// exponential, so the biomass over the cultivation integrates to the biomass formed over the
+/// growth rate, and each substrate and gas is taken up at its maximum rate throughout.
+pub fn estimate_cultivation(organism: &Organism, target: &TargetRequest, kb: &KnowledgeBase) -> Result<Option<CultivationEstimate>> {
+    let growth_rate = organism.dynamic_parameters.growth_rate_per_hr;
+    let Some(yield_mg_g) = organism.yield_for(&target.molecule_name).filter(|y| *y > 0.0) else {
+        return Ok(None);
+    };
+    if growth_rate <= 0.0 {
+        return Ok(None);
+    }
+
+    let required_biomass_g = target.target_amount_grams / (yield_mg_g / 1000.0);
+    let inoculum_g = organism.initial_biomass.value.max(1e-6);
+    let duration_hours = (required_biomass_g / inoculum_g).ln().max(0.0) / growth_rate;
+    let biomass_hours = (required_biomass_g - inoculum_g).max(0.0) / growth_rate;
+    // An organism too much for every bioreactor is estimated in the largest; the selection
+    // reports it.
+    let density = organism.dynamic_parameters.max_cell_density_g_l.unwrap_or(DEFAULT_MAX_CELL_DENSITY_G_L);
+    let bioreactor = match select_bioreactor(required_biomass_g / density, kb) {
+        Ok(bioreactor) => bioreactor,
+        Err(error) => bioreactors(kb).pop().ok_or(error)?,
+    };
+
+    let blueprint = ExecutableBlueprint {
+        process_id: format!("ESTIMATE-{}", organism.organism_id),
+        process_name: format!("Cultivation of {}", organism.organism_name),
+        workflow: vec![BlueprintStep {
+            step: 1,
+            method_id: "MTHD-UP-CULT-ESTIMATE".to_string(),
+            stage: "Cultivation".to_string(),
+            technique: "fed-batch".to_string(),
+            asset_id: bioreactor.asset_id,
+            duration_ticks: (duration_hours / TIME_STEP_HR).ceil() as u64,
+            duration_source: DurationSource::ExpectedDuration,
+            control_parameters: BTreeMap::new(),
+            transition_rules: vec![],
+            required_materials: vec![],
+            qc_checks: vec![],
+            transfers: None,
+        }],
+    };
+    let (mut bom, _) = analysis::estimate_resources(&blueprint, &kb.assets, &kb.labor_roles, &kb.assumptions)?;
+    let exchange = &organism.dynamic_parameters.metabolic_exchange;
+    for consumption in &exchange.media_consumption {
+        let Some(biomass_yield) = biomass_yield(organism, consumption) else { continue };
+        *bom.materials_consumed.entry(consumption.molecule_id.clone()).or_insert(0.0) +=
+            (required_biomass_g - inoculum_g).max(0.0) / biomass_yield;
+    }
+    let co2_fixed_kg = exchange
+        .gas_consumption
+        .iter()
+        .filter(|gas| gas.gas_id == CARBON_DIOXIDE_ID)
+        .map(|gas| gas.max_exchange_rate.value * CO2_MOLAR_MASS / 1e6 * biomass_hours)
+        .fold(0.0, |total, kg| total + kg);
+
+    Ok(Some(CultivationEstimate {
+        organism_id: organism.organism_id.clone(),
+        required_biomass_g,
+        duration_hours,
+        bom,
+        co2_fixed_kg,
+    }))
+}
+
+/// Grams of dry biomass `organism` forms per gram of the substrate of `consumption`: its
+/// `biomass_yield_g_g`, or else the yield its kinetics imply, its maximum growth rate over its
+/// maximum uptake rate. `None` if it takes up none of the substrate or does not grow.
+pub fn biomass_yield(organism: &Organism, consumption: &MediaExchangeRate) -> Option<f64> {
+    let uptake_g_g_hr = consumption.max_exchange_rate.value * nutrient_molar_mass(&consumption.molecule_id) / 1000.0;
+    consumption
+        .biomass_yield_g_g
+        .or_else(|| (uptake_g_g_hr > 0.0).then(|| organism.dynamic_parameters.growth_rate_per_hr / uptake_g_g_hr))
+        .filter(|y| *y > 0.0)
+}
+
+/// The organisms of the knowledge base that can meet `target`, each with its estimate, by
+/// organism id. Organisms the request's organism filter rules out are dropped first: those it
+/// excludes, then engineered ones if it allows none, then, where a required organism makes the
+/// molecule, every organism that is not required, then, given an available feedstock, those
+/// that take up none of its components. Of the rest, organisms whose cultivation alone
+/// is estimated to cost, take, or emit more than the request's constraints allow are pruned. Every
+/// organism dropped or pruned is printed with the reason.
+///
+/// # Errors
+///
+/// Fails if every organism that makes the molecule is dropped or pruned, naming the filter or the
+/// limits that ruled out the last of them.
+fn candidates<'a>(
+    kb: &'a KnowledgeBase,
+    target: &TargetRequest,
+    request: &ValorizationRequest,
+) -> Result<Vec<(&'a Organism, CultivationEstimate)>> {
+    let mut makers = Vec::new();
+    for organism in kb.organisms.values() {
+        if let Some(estimate) = estimate_cultivation(organism, target, kb)? {
+            makers.push((organism, estimate));
+        }
+    }
+    makers.sort_by(|(a, _), (b, _)| a.organism_id.cmp(&b.organism_id));
+
+    let filter = &request.organism_filter;
+    let required = |o: &Organism| filter.required_organism_ids.contains(&o.organism_id);
+    let not_excluded = |o: &Organism| !filter.excluded_organism_ids.contains(&o.organism_id);
+    let requires_any = makers.iter().any(|(o, _)| required(o) && not_excluded(o));
+    let allowed = |o: &Organism| filter.allow_engineered || !o.strain_details.as_ref().is_some_and(|s| s.is_engineered);
+    let chosen = |o: &Organism| !requires_any || required(o);
+    let fed = |o: &Organism| request.available_feedstock.as_ref().is_none_or(|f| f.feeds(o));
+    let filters = [
+        ("excluded_organism_ids", &not_excluded as &dyn Fn(&Organism) -> bool),
+        ("allow_engineered: false", &allowed),
+        ("required_organism_ids", &chosen),
+        ("available_feedstock", &fed),
+    ];
+    for (name, keeps) in filters {
+        let (kept, dropped): (Vec<_>, Vec<_>) = makers.into_iter().partition(|(o, _)| keeps(o));
+        for (organism, _) in &dropped {
+            println!("  Dropped {} ({})", organism.organism_id, name);
+        }
+        if kept.is_empty() && !dropped.is_empty() {
+            bail!(
+                "No organism can produce '{}' under the request's {}, which ruled out the last candidate(s): {}",
+                target.molecule_name,
+                name,
+                dropped.iter().map(|(o, _)| o.organism_id.as_str()).collect::<Vec<_>>().join(", ")
+            );
+        }
+        makers = kept;
+    }
+
+    let constraints = &request.constraints;
+    let mut candidates = Vec::new();
+    let mut pruned = Vec::new();
+    for (organism, estimate) in makers {
+        if !constraints.is_empty() {
+            let cogs = analysis::calculate_cogs(&estimate.bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions)?;
+            let gwp = analysis::calculate_lca(&estimate.bom, &kb.materials, &kb.assets, &kb.assumptions)?.gwp_kg_co2e();
+            let broken: Vec<String> = constraints
+                .check(Some(cogs.total_cogs), Some(estimate.duration_hours), Some(gwp - estimate.co2_fixed_kg))
+                .into_iter()
+                .filter(|check| !check.passed)
+                .map(|check| format!("{} {:.2} > {:.2}", check.quantity, check.actual, check.limit))
+                .collect();
+            if !broken.is_empty() {
+                pruned.push((organism.organism_id.clone(), broken.join(", ")));
+                continue;
+            }
+        }
+        candidates.push((organism, estimate));
+    }
+    for (organism_id, broken) in &pruned {
+        println!("  Pruned {} (estimated {})", organism_id, broken);
+    }
+    if candidates.is_empty() && !pruned.is_empty() {
+        bail!(
+            "No organism can produce {:.2} g of '{}' within the request's constraints",
+            target.target_amount_grams,
+            target.molecule_name
+        );
+    }
+    Ok(candidates)
+}
+
+/// The candidate organism with the highest yield of `target`'s molecule; of organisms with the
+/// same yield, the one with the lowest id.
+fn highest_yield<'a>(kb: &'a KnowledgeBase, target: &TargetRequest, request: &ValorizationRequest) -> Result<&'a Organism> {
+    candidates(kb, target, request)?
+        .into_iter()
+        .filter_map(|(org, _)| org.yield_for(&target.molecule_name).map(|y| (org, y)))
+        .max_by(|(org_a, yield_a), (org_b, yield_b)| {
+            yield_a.total_cmp(yield_b).then_with(|| org_b.organism_id.cmp(&org_a.organism_id))
+        })
+        .map(|(org, _)| org)
+        .context(format!("Optimizer failed to find an organism for '{}'", target.molecule_name))
 }
 
-/// JIT Optimizer: selects the best set of organisms to fulfill the multi-target request.
+/// The organism of the knowledge base that meets `target` at the lowest estimated COGS, after
+/// printing the estimate of every candidate.
+fn lowest_cost<'a>(kb: &'a KnowledgeBase, target: &TargetRequest, request: &ValorizationRequest) -> Result<&'a Organism> {
+    let mut scored = Vec::new();
+    for (organism, estimate) in candidates(kb, target, request)? {
+        let cogs = analysis::calculate_cogs(&estimate.bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions)?;
+        scored.push((organism, estimate, cogs));
+    }
+    scored.sort_by(|(_, a, x), (_, b, y)| x.total_cogs.total_cmp(&y.total_cogs).then_with(|| a.organism_id.cmp(&b.organism_id)));
+
+    println!("Estimated cost to produce {:.2} g ({}):", target.target_amount_grams, kb.assumptions.currency);
+    println!(
+        "  {:<16} {:>12} {:>8} {:>12} {:>12} {:>12}",
+        "Organism", "Biomass (g)", "Hours", "Substrate", "Cultivation", "Total"
+    );
+    for (_, estimate, cogs) in &scored {
+        println!(
+            "  {:<16} {:>12.2} {:>8.1} {:>12.2} {:>12.2} {:>12.2}",
+            estimate.organism_id,
+            estimate.required_biomass_g,
+            estimate.duration_hours,
+            cogs.material_costs,
+            cogs.total_cogs - cogs.material_costs,
+            cogs.total_cogs
+        );
+    }
+    scored
+        .first()
+        .map(|(organism, _, _)| *organism)
+        .context(format!("Optimizer failed to find an organism for '{}'", target.molecule_name))
+}
+
+/// The organism of the knowledge base that meets `target` with the lowest estimated net GWP: that
+/// of its substrates, energy, and asset use, less the carbon dioxide it fixes. Prints the estimate
+/// of every candidate.
+fn lowest_gwp<'a>(kb: &'a KnowledgeBase, target: &TargetRequest, request: &ValorizationRequest) -> Result<&'a Organism> {
+    let mut scored = Vec::new();
+    for (organism, estimate) in candidates(kb, target, request)? {
+        let gwp = analysis::calculate_lca(&estimate.bom, &kb.materials, &kb.assets, &kb.assumptions)?.gwp_kg_co2e();
+        let net = gwp - estimate.co2_fixed_kg;
+        scored.push((organism, estimate, gwp, net));
+    }
+    scored.sort_by(|(_, a, _, x), (_, b, _, y)| x.total_cmp(y).then_with(|| a.organism_id.cmp(&b.organism_id)));
+
+    println!("Estimated GWP to produce {:.2} g (kg CO2e):", target.target_amount_grams);
+    println!(
+        "  {:<16} {:>12} {:>8} {:>12} {:>12} {:>12}",
+        "Organism", "Biomass (g)", "Hours", "GWP", "CO2 fixed", "Net"
+    );
+    for (_, estimate, gwp, net) in &scored {
+        println!(
+            "  {:<16} {:>12.2} {:>8.1} {:>12.3} {:>12.3} {:>12.3}",
+            estimate.organism_id, estimate.required_biomass_g, estimate.duration_hours, gwp, estimate.co2_fixed_kg, net
+        );
+    }
+    scored
+        .first()
+        .map(|(organism, _, _, _)| *organism)
+        .context(format!("Optimizer failed to find an organism for '{}'", target.molecule_name))
+}
+
+/// The file, in the run directory, that [`write_pareto_candidates`] writes.
+pub const PARETO_CANDIDATES_FILE: &str = "candidates_pareto.csv";
+
+/// A candidate organism for a target scored on every objective, for [`Objective::Weighted`] and
+/// [`Objective::ParetoFront`].
+#[derive(Debug, Clone)]
+pub struct CandidateScore {
+    pub organism_id: String,
+    pub yield_mg_g: f64,
+    /// The estimated COGS of the cultivation.
+    pub cost: f64,
+    /// The estimated net GWP of the cultivation, in kg CO2e.
+    pub gwp_kg_co2e: f64,
+    /// `yield_mg_g`, `cost`, and `gwp_kg_co2e` scaled over the candidates so that the best is 1
+    /// and the worst 0.
+    pub normalized: [f64; 3],
+    /// Whether no other candidate is at least as good on every objective and better on one.
+    pub non_dominated: bool,
+}
+
+impl CandidateScore {
+    /// Whether `self` is at least as good as `other` on every objective and better on one.
+    fn dominates(&self, other: &CandidateScore) -> bool {
+        let at_least = self.yield_mg_g >= other.yield_mg_g && self.cost <= other.cost && self.gwp_kg_co2e <= other.gwp_kg_co2e;
+        let better = self.yield_mg_g > other.yield_mg_g || self.cost < other.cost || self.gwp_kg_co2e < other.gwp_kg_co2e;
+        at_least && better
+    }
+
+    /// The weighted sum of the normalized scores over the sum of `weights`.
+    fn weighted(&self, weights: [f64; 3]) -> f64 {
+        let total: f64 = weights.iter().sum();
+        let sum: f64 = self.normalized.iter().zip(weights).map(|(score, weight)| score * weight).sum();
+        if total > 0.0 { sum / total } else { 0.0 }
+    }
+
+    /// The distance of the normalized scores from the ideal of 1 on each.
+    fn distance_from_ideal(&self) -> f64 {
+        self.normalized.iter().map(|score| (1.0 - score).powi(2)).sum::<f64>().sqrt()
+    }
+
+    /// Orders candidates by their distance from the ideal, nearest first, so that the knee of a
+    /// front comes first.
+    fn by_distance(&self, other: &CandidateScore) -> std::cmp::Ordering {
+        self.distance_from_ideal()
+            .total_cmp(&other.distance_from_ideal())
+            .then_with(|| self.organism_id.cmp(&other.organism_id))
+    }
+}
+
+/// Scores every candidate of the knowledge base for `target` that `request` allows on yield, cost,
+/// and net GWP, by organism id.
+pub fn score_candidates<'a>(
+    kb: &'a KnowledgeBase,
+    target: &TargetRequest,
+    request: &ValorizationRequest,
+) -> Result<Vec<(&'a Organism, CandidateScore)>> {
+    let mut scored = Vec::new();
+    for (organism, estimate) in candidates(kb, target, request)? {
+        let cogs = analysis::calculate_cogs(&estimate.bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions)?;
+        let gwp = analysis::calculate_lca(&estimate.bom, &kb.materials, &kb.assets, &kb.assumptions)?.gwp_kg_co2e();
+        scored.push((
+            organism,
+            CandidateScore {
+                organism_id: estimate.organism_id,
+                yield_mg_g: organism.yield_for(&target.molecule_name).unwrap_or(0.0),
+                cost: cogs.total_cogs,
+                gwp_kg_co2e: gwp - estimate.co2_fixed_kg,
+                normalized: [0.0; 3],
+                non_dominated: false,
+            },
+        ));
+    }
+
+    // Normalizes `values` between the worst and best of the candidates; all score 1 when they tie.
+    let normalize = |values: Vec<f64>, higher_is_better: bool| -> Vec<f64> {
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        values
+            .iter()
+            .map(|v| match max - min {
+                range if range > 0.0 && higher_is_better => (v - min) / range,
+                range if range > 0.0 => (max - v) / range,
+                _ => 1.0,
+            })
+            .collect()
+    };
+    let yields = normalize(scored.iter().map(|(_, s)| s.yield_mg_g).collect(), true);
+    let costs = normalize(scored.iter().map(|(_, s)| s.cost).collect(), false);
+    let gwps = normalize(scored.iter().map(|(_, s)| s.gwp_kg_co2e).collect(), false);
+    for (i, (_, score)) in scored.iter_mut().enumerate() {
+        score.normalized = [yields[i], costs[i], gwps[i]];
+    }
+    let dominated: Vec<bool> = scored
+        .iter()
+        .map(|(_, score)| scored.iter().any(|(_, other)| other.dominates(score)))
+        .collect();
+    for ((_, score), dominated) in scored.iter_mut().zip(dominated) {
+        score.non_dominated = !dominated;
+    }
+    Ok(scored)
+}
+
+/// Prints the scores of `scored`, with the value each is ranked by, best first.
+fn print_scores(target: &TargetRequest, kb: &KnowledgeBase, scored: &[(&Organism, CandidateScore, f64)], ranked_by: &str) {
+    println!(
+        "Candidates to produce {:.2} g (cost in {}, GWP in kg CO2e; normalized scores in brackets):",
+        target.target_amount_grams, kb.assumptions.currency
+    );
+    println!(
+        "  {:<16} {:>16} {:>18} {:>18} {:>10}",
+        "Organism", "Yield (mg/g)", "Cost", "GWP", ranked_by
+    );
+    for (_, score, rank) in scored {
+        let [y, c, g] = score.normalized;
+        println!(
+            "  {:<16} {:>9.2} [{:.2}] {:>11.2} [{:.2}] {:>11.2} [{:.2}] {:>10.3}",
+            score.organism_id, score.yield_mg_g, y, score.cost, c, score.gwp_kg_co2e, g, rank
+        );
+    }
+}
+
+/// The organism of the knowledge base with the best weighted score for `target`, after printing
+/// the scores of every candidate.
+fn best_weighted<'a>(
+    kb: &'a KnowledgeBase,
+    target: &TargetRequest,
+    request: &ValorizationRequest,
+    weights: [f64; 3],
+) -> Result<&'a Organism> {
+    let mut scored: Vec<_> = score_candidates(kb, target, request)?
+        .into_iter()
+        .map(|(organism, score)| {
+            let weighted = score.weighted(weights);
+            (organism, score, weighted)
+        })
+        .collect();
+    scored.sort_by(|(_, a, x), (_, b, y)| y.total_cmp(x).then_with(|| a.organism_id.cmp(&b.organism_id)));
+    print_scores(target, kb, &scored, "Score");
+    scored
+        .first()
+        .map(|(organism, _, _)| *organism)
+        .context(format!("Optimizer failed to find an organism for '{}'", target.molecule_name))
+}
+
+/// The knee of the Pareto front of the candidates for `target`, after printing the front with
+/// each candidate's distance from the ideal.
+fn pareto_knee<'a>(kb: &'a KnowledgeBase, target: &TargetRequest, request: &ValorizationRequest) -> Result<&'a Organism> {
+    let mut front: Vec<_> = score_candidates(kb, target, request)?
+        .into_iter()
+        .filter(|(_, score)| score.non_dominated)
+        .map(|(organism, score)| {
+            let distance = score.distance_from_ideal();
+            (organism, score, distance)
+        })
+        .collect();
+    front.sort_by(|(_, a, _), (_, b, _)| a.by_distance(b));
+    print_scores(target, kb, &front, "Distance");
+    if front.len() > 1 {
+        let alternatives: Vec<&str> = front[1..].iter().map(|(_, score, _)| score.organism_id.as_str()).collect();
+        println!("Knee of the Pareto front selected; alternatives on the front: {}", alternatives.join(", "));
+    }
+    front
+        .first()
+        .map(|(organism, _, _)| *organism)
+        .context(format!("Optimizer failed to find an organism for '{}'", target.molecule_name))
+}
+
+/// One row of [`PARETO_CANDIDATES_FILE`].
+#[derive(Serialize)]
+struct ParetoCandidateRow<'a> {
+    molecule_name: &'a str,
+    organism_id: &'a str,
+    yield_mg_g: f64,
+    cost: f64,
+    gwp_kg_co2e: f64,
+    yield_score: f64,
+    cost_score: f64,
+    gwp_score: f64,
+    non_dominated: bool,
+    selected: bool,
+}
+
+/// Writes the scores of every candidate of each [`Objective::ParetoFront`] target of `request` to
+/// [`PARETO_CANDIDATES_FILE`] in `output_dir`, marking the front and the knee selected from it.
+/// Writes nothing when no target asks for the front.
+pub fn write_pareto_candidates(request: &ValorizationRequest, kb: &KnowledgeBase, output_dir: &str) -> Result<()> {
+    let targets: Vec<&TargetRequest> =
+        request.targets.iter().filter(|t| matches!(t.objective, Objective::ParetoFront)).collect();
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    let mut writer = csv::Writer::from_path(Path::new(output_dir).join(PARETO_CANDIDATES_FILE))?;
+    for target in targets {
+        let scored = score_candidates(kb, target, request)?;
+        let knee = scored
+            .iter()
+            .filter(|(_, score)| score.non_dominated)
+            .min_by(|(_, a), (_, b)| a.by_distance(b))
+            .map(|(_, score)| score.organism_id.clone());
+        for (_, score) in &scored {
+            writer.serialize(ParetoCandidateRow {
+                molecule_name: &target.molecule_name,
+                organism_id: &score.organism_id,
+                yield_mg_g: score.yield_mg_g,
+                cost: score.cost,
+                gwp_kg_co2e: score.gwp_kg_co2e,
+                yield_score: score.normalized[0],
+                cost_score: score.normalized[1],
+                gwp_score: score.normalized[2],
+                non_dominated: score.non_dominated,
+                selected: knee.as_ref() == Some(&score.organism_id),
+            })?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// The organisms selected for a request and the one assigned to produce each of its targets.
+/// Where several selected organisms make a molecule, only the assigned one's share counts towards
+/// its target, for the stop rules, the production figures, and the allocation alike.
+#[derive(Debug, Clone)]
+pub struct SelectionPlan {
+    /// The selected organisms, by organism id, with their inocula scaled to the targets.
+    pub organisms: Vec<Organism>,
+    /// The id of the organism producing each target, by molecule name.
+    pub producers: BTreeMap<String, String>,
+    /// The grams of dry biomass each organism must reach in a batch for its most demanding
+    /// target, by organism id.
+    pub required_biomass_g: BTreeMap<String, f64>,
+    /// The smallest bioreactor that holds the culture of a batch.
+    pub bioreactor: Bioreactor,
+    /// The batches, run one after another, that make the targets between them, each alike: more
+    /// than one when no bioreactor holds the whole culture. Only one batch is simulated.
+    pub batches: u32,
+    /// The request's available feedstock against the substrate demand of the targets, set by
+    /// [`fit_targets_to_feedstock`].
+    pub feedstock_balance: Option<FeedstockBalance>,
+    /// The purity each target leaves its downstream process at, set by
+    /// [`select_downstream_processes`].
+    pub purities: Vec<PurityEstimate>,
+}
+
+impl SelectionPlan {
+    /// The organism assigned to produce `molecule_name` and its yield of it, in mg/g.
+    pub fn producer(&self, molecule_name: &str) -> Option<(&Organism, f64)> {
+        let organism_id = self.producers.get(molecule_name)?;
+        let organism = self.organisms.iter().find(|o| &o.organism_id == organism_id)?;
+        Some((organism, organism.yield_for(molecule_name)?))
+    }
+
+    /// The share of `grams` of a target that one of the plan's batches makes.
+    pub fn batch_grams(&self, grams: f64) -> f64 {
+        grams / f64::from(self.batches)
+    }
+}
+
+/// JIT Optimizer: selects the best set of organisms to fulfill the multi-target request, each
+/// target produced by the organism its objective picks. Ties between candidates go to the lowest
+/// organism id.
+///
+/// # Errors
+///
+/// Fails if no organism of the knowledge base produces one of the targets.
 pub fn select_optimal_organism_mix(
     request: &ValorizationRequest,
     kb: &KnowledgeBase,
-) -> Result<Vec<Organism>> {
+) -> Result<SelectionPlan> {
     println!("\n--- [JIT] Running Upstream Optimizer ---");
-    let mut organism_map: HashMap<String, Organism> = HashMap::new();
+    let mut organism_map: BTreeMap<String, Organism> = BTreeMap::new();
+    let mut producers = BTreeMap::new();
 
     // First, select the best organism for each target and store a clone
     for target in &request.targets {
         println!("Optimizing for target: {}", target.molecule_name);
         let best_organism = match target.objective {
-            Objective::MaximizeYield => kb
-                .organisms
-                .values()
-                .filter_map(|org| {
-                    let yield_value = find_yield(org, &target.molecule_name);
-                    yield_value.map(|y| (org, y))
-                })
-                .max_by(|(_, yield_a), (_, yield_b)| {
-                    yield_a
-                        .partial_cmp(yield_b)
-                        .unwrap_or(std::cmp::Ordering::Equal)
-                })
-                .map(|(org, _)| org)
-                .context(format!(
-                    "Optimizer failed to find an organism for '{}'",
-                    target.molecule_name
-                ))?,
-            Objective::MinimizeCost | Objective::MinimizeLca => {
-                println!("Warning: MinimizeCost/MinimizeLca not yet implemented. Defaulting to MaximizeYield.");
-                kb.organisms
-                    .values()
-                    .filter_map(|org| {
-                        let yield_value = find_yield(org, &target.molecule_name);
-                        yield_value.map(|y| (org, y))
-                    })
-                    .max_by(|(_, yield_a), (_, yield_b)| {
-                        yield_a
-                            .partial_cmp(yield_b)
-                            .unwrap_or(std::cmp::Ordering::Equal)
-                    })
-                    .map(|(org, _)| org)
-                    .context(format!(
-                        "Optimizer failed to find an organism for '{}'",
-                        target.molecule_name
-                    ))?
+            Objective::MaximizeYield => highest_yield(kb, target, request)?,
+            Objective::MinimizeCost => lowest_cost(kb, target, request)?,
+            Objective::MinimizeLca => lowest_gwp(kb, target, request)?,
+            Objective::Weighted { yield_w, cost_w, lca_w } => {
+                best_weighted(kb, target, request, [yield_w, cost_w, lca_w])?
             }
+            Objective::ParetoFront => pareto_knee(kb, target, request)?,
         };
 
+        if !best_organism.yield_for(&target.molecule_name).is_some_and(|y| y > 0.0) {
+            bail!(
+                "No organism is assigned to produce '{}': {} holds none of it in its biomass",
+                target.molecule_name,
+                best_organism.organism_id
+            );
+        }
         if !organism_map.contains_key(&best_organism.organism_id) {
             organism_map.insert(best_organism.organism_id.clone(), best_organism.clone());
         }
+        producers.insert(target.molecule_name.clone(), best_organism.organism_id.clone());
     }
 
-    // Now, calculate the required biomass for each selected organism
-    let mut required_biomasses: HashMap<String, f64> = HashMap::new();
-    for org in organism_map.values() {
-        if let Some(target) = request.targets.iter().find(|t| find_yield(org, &t.molecule_name).is_some()) {
-            if let Some(yield_mg_g) = find_yield(org, &target.molecule_name) {
-                if yield_mg_g > 0.0 {
-                    let required = target.target_amount_grams / (yield_mg_g / 1000.0);
-                    required_biomasses.insert(org.organism_id.clone(), required);
-                }
-            }
+    for organism_id in &request.organism_filter.required_organism_ids {
+        if !organism_map.contains_key(organism_id) {
+            bail!(
+                "Required organism '{}' was not selected: it makes none of the targets within the request's constraints",
+                organism_id
+            );
         }
     }
+
+    // Now, calculate the required biomass for each selected organism: enough for the most
+    // demanding of the targets assigned to it.
+    let mut required_biomasses: BTreeMap<String, f64> = BTreeMap::new();
+    for target in &request.targets {
+        let organism = &organism_map[&producers[&target.molecule_name]];
+        let yield_mg_g = organism.yield_for(&target.molecule_name).unwrap_or(0.0);
+        let required = target.target_amount_grams / (yield_mg_g / 1000.0);
+        let entry = required_biomasses.entry(organism.organism_id.clone()).or_insert(0.0);
+        *entry = entry.max(required);
+    }
     
     // Find the maximum required biomass to use as a scaling reference
     let max_required_biomass = required_biomasses.values().cloned().fold(0.0, f64::max);
@@ -120,6 +961,7 @@ pub fn select_optimal_organism_mix(
     }
 
     let selected_organisms: Vec<Organism> = organism_map.into_values().collect();
+    let (bioreactor, batches) = split_into_batches(&selected_organisms, &mut required_biomasses, kb)?;
     
     println!(
         "Final organism set selected: {:?}",
@@ -128,50 +970,390 @@ pub fn select_optimal_organism_mix(
             .map(|o| &o.organism_id)
             .collect::<Vec<_>>()
     );
-    Ok(selected_organisms)
+
+    let window = consortium_window(&selected_organisms)?;
+    println!(
+        "Shared tolerances: {:.1}-{:.1} °C, pH {:.1}-{:.1}",
+        window.temperature.min, window.temperature.max, window.ph.min, window.ph.max
+    );
+    for warning in &window.warnings {
+        println!("Warning: {}", warning);
+    }
+    if let Some(setpoint) = window.temperature_setpoint {
+        println!("The cultivation will be held at {:.1} °C, the midpoint of the shared optimal window.", setpoint);
+    }
+    for target in &request.targets {
+        println!("Producer of {}: {}", target.molecule_name, producers[&target.molecule_name]);
+    }
+    let plan = SelectionPlan {
+        organisms: selected_organisms,
+        producers,
+        required_biomass_g: required_biomasses,
+        bioreactor,
+        batches,
+        feedstock_balance: None,
+        purities: Vec::new(),
+    };
+    print_bioreactor(&plan);
+    Ok(plan)
 }
 
+fn print_bioreactor(plan: &SelectionPlan) {
+    println!(
+        "Bioreactor: {} ({:.0} L working volume) for an estimated {:.1} L of culture{}",
+        plan.bioreactor.asset_id,
+        plan.bioreactor.working_volume_l,
+        required_culture_volume_l(&plan.organisms, &plan.required_biomass_g),
+        if plan.batches > 1 { " per batch" } else { "" }
+    );
+    if plan.batches > 1 {
+        println!("Scale-out: {} batches required; no bioreactor holds the whole culture", plan.batches);
+    }
+}
 
-/// Dynamically generates the initial media formulation based on the metabolic needs of the selected organisms.
+/// How far an available feedstock goes towards the targets of a request.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedstockBalance {
+    pub material_id: String,
+    pub amount_kg: f64,
+    /// The share of the targets the feedstock can make, at most 1.
+    pub achievable_fraction: f64,
+    /// Whether the targets were scaled down to the achievable share.
+    pub targets_scaled: bool,
+    pub components: Vec<FeedstockComponentBalance>,
+    pub targets: Vec<FeedstockTargetBalance>,
+}
+
+/// A component of a feedstock against the estimated demand for it.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedstockComponentBalance {
+    pub molecule_id: String,
+    pub molecule_name: String,
+    pub available_g: f64,
+    pub demand_g: f64,
+    /// The grams left over, or short when negative.
+    pub surplus_g: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedstockTargetBalance {
+    pub molecule_name: String,
+    pub requested_g: f64,
+    pub achievable_g: f64,
+}
+
+/// The file, in the run directory, that [`write_feedstock_balance`] writes.
+pub const FEEDSTOCK_BALANCE_FILE: &str = "feedstock_balance.json";
+
+/// Writes the feedstock balance of `plan` to [`FEEDSTOCK_BALANCE_FILE`] in `output_dir`. Writes
+/// nothing when the request has no feedstock.
+pub fn write_feedstock_balance(plan: &SelectionPlan, output_dir: &str) -> Result<()> {
+    if let Some(balance) = &plan.feedstock_balance {
+        fs::write(Path::new(output_dir).join(FEEDSTOCK_BALANCE_FILE), serde_json::to_string_pretty(balance)?)?;
+    }
+    Ok(())
+}
+
+/// Weighs the request's available feedstock, if it has one, against the substrate demand of
+/// `plan`, and records the balance in the plan. The share of the targets the feedstock can make
+/// is that of the component it falls furthest short of. Unless the feedstock asks only to be
+/// warned, the targets of `request` and the biomass `plan` requires are scaled down to it, and
+/// the plan's bioreactor and batches are chosen again from `kb` for the smaller culture.
+pub fn fit_targets_to_feedstock(request: &mut ValorizationRequest, plan: &mut SelectionPlan, kb: &KnowledgeBase) -> Result<()> {
+    let Some(feedstock) = &request.available_feedstock else { return Ok(()) };
+    println!("\n--- [JIT] Balancing the Available Feedstock ---");
+    let demands = substrate_demands(plan);
+    let components: Vec<FeedstockComponentBalance> = feedstock
+        .composition
+        .iter()
+        .map(|component| {
+            let available_g = feedstock.amount_kg * 1000.0 * component.mass_fraction;
+            let demand_g =
+                demands.get(component.molecule_id.as_str()).map_or(0.0, |(_, g)| *g) * f64::from(plan.batches);
+            FeedstockComponentBalance {
+                molecule_id: component.molecule_id.clone(),
+                molecule_name: component.molecule_name.clone(),
+                available_g,
+                demand_g,
+                surplus_g: available_g - demand_g,
+            }
+        })
+        .collect();
+    let achievable_fraction = components
+        .iter()
+        .filter(|c| c.demand_g > 0.0)
+        .map(|c| c.available_g / c.demand_g)
+        .fold(1.0, f64::min);
+
+    println!("{:.1} kg of {}:", feedstock.amount_kg, feedstock.material_id);
+    println!("  {:<24} {:>14} {:>14} {:>14}", "Component", "Available (g)", "Demand (g)", "Surplus (g)");
+    for c in &components {
+        println!("  {:<24} {:>14.1} {:>14.1} {:>14.1}", c.molecule_name, c.available_g, c.demand_g, c.surplus_g);
+    }
+    let targets_scaled = feedstock.scale_targets && achievable_fraction < 1.0;
+    if achievable_fraction < 1.0 {
+        if targets_scaled {
+            println!("The feedstock covers {:.1}% of the targets; scaling them down to match.", achievable_fraction * 100.0);
+        } else {
+            println!("Warning: the feedstock covers only {:.1}% of the targets.", achievable_fraction * 100.0);
+        }
+    }
+
+    let targets = request
+        .targets
+        .iter_mut()
+        .map(|target| {
+            let balance = FeedstockTargetBalance {
+                molecule_name: target.molecule_name.clone(),
+                requested_g: target.target_amount_grams,
+                achievable_g: target.target_amount_grams * achievable_fraction,
+            };
+            println!("  {}: {:.2} g of {:.2} g achievable", balance.molecule_name, balance.achievable_g, balance.requested_g);
+            if targets_scaled {
+                target.target_amount_grams = balance.achievable_g;
+            }
+            balance
+        })
+        .collect();
+    if targets_scaled {
+        for required in plan.required_biomass_g.values_mut() {
+            *required *= f64::from(plan.batches) * achievable_fraction;
+        }
+        (plan.bioreactor, plan.batches) = split_into_batches(&plan.organisms, &mut plan.required_biomass_g, kb)?;
+        print_bioreactor(plan);
+    }
+    plan.feedstock_balance = Some(FeedstockBalance {
+        material_id: feedstock.material_id.clone(),
+        amount_kg: feedstock.amount_kg,
+        achievable_fraction,
+        targets_scaled,
+        components,
+        targets,
+    });
+    Ok(())
+}
+
+/// A shared temperature window narrower than this, in °C, leaves little room for error and has
+/// the cultivation held at a compromise temperature.
+pub const NARROW_TEMPERATURE_WINDOW_C: f64 = 5.0;
+
+/// A shared pH window narrower than this is warned about.
+pub const NARROW_PH_WINDOW: f64 = 1.0;
+
+/// The conditions every organism of a consortium tolerates.
+#[derive(Debug, Clone)]
+pub struct ConsortiumWindow {
+    /// The temperatures, in °C, inside every organism's viable range.
+    pub temperature: ToleranceRange<f64>,
+    /// The pH inside every organism's viable range.
+    pub ph: ToleranceRange<f64>,
+    /// The temperature, in °C, to hold the cultivation at when the shared window is narrow: the
+    /// midpoint of the organisms' optimal temperatures, kept inside the shared window. `None`
+    /// when the window is wide enough to leave the vessel at its own setting.
+    pub temperature_setpoint: Option<f64>,
+    /// Narrow windows, and byproducts one organism secretes that another does not tolerate.
+    pub warnings: Vec<String>,
+}
+
+/// The conditions every one of `organisms` tolerates. Fails, naming the organisms in conflict,
+/// if their temperature or pH ranges do not overlap.
+pub fn consortium_window(organisms: &[Organism]) -> Result<ConsortiumWindow> {
+    let temperature = shared_range(organisms, "temperature", "°C", |o| &tolerances(o).temperature.range)?;
+    let ph = shared_range(organisms, "pH", "", |o| &tolerances(o).ph.range)?;
+
+    let mut warnings = Vec::new();
+    let temperature_setpoint = (temperature.max - temperature.min < NARROW_TEMPERATURE_WINDOW_C).then(|| {
+        let optima = organisms.iter().map(|o| tolerances(o).temperature.optimal.value);
+        let low = optima.clone().fold(f64::INFINITY, f64::min);
+        let high = optima.fold(f64::NEG_INFINITY, f64::max);
+        warnings.push(format!(
+            "the organisms share only {:.1}-{:.1} °C; their optima span {:.1}-{:.1} °C",
+            temperature.min, temperature.max, low, high
+        ));
+        ((low + high) / 2.0).clamp(temperature.min, temperature.max)
+    });
+    if ph.max - ph.min < NARROW_PH_WINDOW {
+        warnings.push(format!("the organisms share only pH {:.1}-{:.1}", ph.min, ph.max));
+    }
+
+    for secreting in organisms {
+        for byproduct in &secreting.dynamic_parameters.metabolic_exchange.media_secretion {
+            for inhibited in organisms.iter().filter(|o| o.organism_id != secreting.organism_id) {
+                let Some(tolerance) = tolerances(inhibited).chemical.iter().find(|c| c.molecule_id == byproduct.molecule_id) else {
+                    continue;
+                };
+                let limit = tolerance
+                    .minimum_inhibitory_concentration
+                    .as_ref()
+                    .or(tolerance.inhibitory_concentration_50.as_ref())
+                    .map_or(String::new(), |c| format!(" above {} {}", c.value, c.unit));
+                let warning = format!(
+                    "{} secretes {}, which inhibits {}{}",
+                    secreting.organism_id, byproduct.molecule_name, inhibited.organism_id, limit
+                );
+                if !warnings.contains(&warning) {
+                    warnings.push(warning);
+                }
+            }
+        }
+    }
+
+    Ok(ConsortiumWindow { temperature, ph, temperature_setpoint, warnings })
+}
+
+fn tolerances(organism: &Organism) -> &EnvironmentalTolerances {
+    &organism.dynamic_parameters.environmental_tolerances
+}
+
+/// The part of the `range` of every one of `organisms` they share, or an error naming the
+/// organism that needs the most and the one that tolerates the least if they share none.
+fn shared_range<'a>(
+    organisms: &'a [Organism],
+    quantity: &str,
+    unit: &str,
+    range: impl Fn(&'a Organism) -> &'a ToleranceRange<f64>,
+) -> Result<ToleranceRange<f64>> {
+    let highest_min = organisms.iter().max_by(|a, b| range(a).min.total_cmp(&range(b).min));
+    let lowest_max = organisms.iter().min_by(|a, b| range(a).max.total_cmp(&range(b).max));
+    let (Some(highest_min), Some(lowest_max)) = (highest_min, lowest_max) else {
+        bail!("No organisms were selected");
+    };
+    let shared = ToleranceRange { min: range(highest_min).min, max: range(lowest_max).max };
+    if shared.min > shared.max {
+        bail!(
+            "The selected organisms cannot share a vessel: {} needs a {} of at least {}{} but {} tolerates at most {}{}",
+            highest_min.organism_id, quantity, shared.min, unit, lowest_max.organism_id, shared.max, unit
+        );
+    }
+    Ok(shared)
+}
+
+
+/// The molecules an organism can take its nitrogen from: molecule id, name, and the mass
+/// fraction of nitrogen in the molecule.
+const NITROGEN_SOURCES: [(&str, &str, f64); 3] = [
+    ("CHEBI:132204", "ammonia", 14.007 / 17.031),
+    ("CHEBI:49740", "nitrate", 14.007 / 62.004),
+    ("CHEBI:16199", "urea", 2.0 * 14.007 / 60.056),
+];
+
+/// The nitrogen source of organisms that neither list one nor take one up: ammonia.
+const DEFAULT_NITROGEN_SOURCE: &str = "CHEBI:132204";
+
+/// The molecule ids of the nitrogen sources `organism` can grow on: those it lists, or else the
+/// nitrogen sources among its media consumption, or else ammonia.
+pub fn nitrogen_sources(organism: &Organism) -> Vec<&str> {
+    if !organism.static_properties.nitrogen_sources.is_empty() {
+        return organism.static_properties.nitrogen_sources.iter().map(String::as_str).collect();
+    }
+    let mut consumed: Vec<&str> = organism
+        .dynamic_parameters
+        .metabolic_exchange
+        .media_consumption
+        .iter()
+        .map(|c| c.molecule_id.as_str())
+        .filter(|id| NITROGEN_SOURCES.iter().any(|(source, _, _)| source == id))
+        .collect();
+    consumed.sort_unstable();
+    consumed.dedup();
+    if consumed.is_empty() {
+        consumed.push(DEFAULT_NITROGEN_SOURCE);
+    }
+    consumed
+}
+
+/// Dynamically generates the initial media formulation based on the metabolic needs of the
+/// organisms of `plan`. Each substrate is charged with the request's `initial_charge_fraction` of
+/// what the organisms taking it up are estimated to consume growing from their inocula to their
+/// required biomass, or all of it for a batch; the feed rules add the rest. Each organism's
+/// nitrogen is dosed in full, as the cheapest of its nitrogen sources in `kb`, from the nitrogen
+/// content of the biomass it forms and the request's `nitrogen_excess_factor`. Every charge is
+/// capped at the lowest concentration any organism is inhibited at. The media fills the working
+/// volume of the plan's bioreactor.
 pub fn generate_initial_media(
-    organisms: &[Organism],
+    plan: &SelectionPlan,
+    request: &ValorizationRequest,
+    kb: &KnowledgeBase,
     output_dir: &str,
 ) -> Result<MediaState> {
     println!("\n--- [JIT] Generating Initial Media Formulation ---");
-    let mut dissolved_components = HashMap::new();
-
-    // Add common base components
-    dissolved_components.insert(
-        "CHEBI:132204".to_string(), // ammonia
-        DissolvedComponent {
-            molecule_id: "CHEBI:132204".to_string(),
-            molecule_name: "ammonia".to_string(),
-            concentration: Measurement { value: 2.0, unit: "g/L".to_string() },
-        },
-    );
+    let volume_l = plan.bioreactor.working_volume_l;
+    // Grams charged of each component, keyed by molecule id in a BTreeMap so the generated media
+    // (and every log row derived from it) lists components in the same order on every run.
+    let mut charges: BTreeMap<&str, (&str, f64)> = BTreeMap::new();
 
-    // Add carbon sources required by the selected organisms
-    for org in organisms {
-        for consumption in &org.dynamic_parameters.metabolic_exchange.media_consumption {
-            if !dissolved_components.contains_key(&consumption.molecule_id) {
-                println!("Adding required nutrient: {}", consumption.molecule_name);
-                dissolved_components.insert(
-                    consumption.molecule_id.clone(),
-                    DissolvedComponent {
-                        molecule_id: consumption.molecule_id.clone(),
-                        molecule_name: consumption.molecule_name.clone(),
-                        concentration: Measurement { value: 20.0, unit: "g/L".to_string() }, // Default concentration
-                    },
-                );
-            }
+    // A batch is charged with everything it will take up.
+    let charge_fraction = match request.cultivation_strategy {
+        CultivationStrategy::Batch => 1.0,
+        _ => request.initial_charge_fraction,
+    };
+    for (molecule_id, (molecule_name, demand_g)) in substrate_demands(plan) {
+        let charge_g = charge_fraction * demand_g;
+        println!(
+            "Adding required nutrient: {} ({:.0} g of an estimated {:.0} g demand)",
+            molecule_name, charge_g, demand_g
+        );
+        charges.insert(molecule_id, (molecule_name, charge_g));
+    }
+    // A feedstock is charged whole, or a batch's share of it, as much as covers the charge of
+    // every substrate it holds, and brings each of its components along in its own proportion.
+    if let Some(feedstock) = &request.available_feedstock {
+        let feedstock_g = feedstock
+            .composition
+            .iter()
+            .filter_map(|c| Some(charges.get(c.molecule_id.as_str())?.1 / c.mass_fraction))
+            .fold(0.0, f64::max)
+            .min(plan.batch_grams(feedstock.amount_kg * 1000.0));
+        println!("Charging {:.1} kg of feedstock {} in place of its pure components", feedstock_g / 1000.0, feedstock.material_id);
+        for component in &feedstock.composition {
+            charges.insert(&component.molecule_id, (&component.molecule_name, feedstock_g * component.mass_fraction));
         }
     }
 
+    for org in &plan.organisms {
+        let required = plan.required_biomass_g.get(&org.organism_id).copied().unwrap_or(0.0);
+        let formed = (required - org.initial_biomass.value).max(0.0);
+        let nitrogen_g = formed * org.static_properties.elemental_composition.nitrogen * request.nitrogen_excess_factor;
+        let sources = nitrogen_sources(org);
+        let Some(((molecule_id, molecule_name, fraction), _)) = NITROGEN_SOURCES
+            .iter()
+            .filter(|(id, _, _)| sources.contains(id))
+            .filter_map(|source| Some((source, media_recipe::supply_cost(source.0, &kb.materials)? / source.2)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+        else {
+            bail!(
+                "No purchasable material in the knowledge base supplies a nitrogen source of {} ({})",
+                org.organism_name,
+                sources.join(", ")
+            );
+        };
+        let grams = nitrogen_g / fraction;
+        println!(
+            "Dosing nitrogen for {}: {:.1} g N as {:.1} g of {}, the cheapest of its sources per gram of nitrogen",
+            org.organism_name, nitrogen_g, grams, molecule_name
+        );
+        charges.entry(molecule_id).or_insert((molecule_name, 0.0)).1 += grams;
+    }
+
+    let mut dissolved_components = Vec::new();
+    for (molecule_id, (molecule_name, grams)) in charges {
+        let mut concentration = grams / volume_l;
+        if let Some(cap) = inhibitory_concentration(&plan.organisms, molecule_id).filter(|cap| *cap < concentration) {
+            println!("Capping {} at {:.2} g/L, below which no selected organism is inhibited", molecule_name, cap);
+            concentration = cap;
+        }
+        dissolved_components.push(DissolvedComponent {
+            molecule_id: molecule_id.to_string(),
+            molecule_name: molecule_name.to_string(),
+            concentration: Measurement { value: concentration, unit: "g/L".to_string() },
+        });
+    }
+
     let media_state = MediaState {
-        volume: Measurement { value: 500.0, unit: "L".to_string() },
+        volume: Measurement { value: volume_l, unit: "L".to_string() },
         ph: 7.0,
         composition: MediaComposition {
-            dissolved_components: dissolved_components.values().cloned().collect(),
+            dissolved_components,
             dissolved_gases: vec![
                 bioforge_schemas::environment::DissolvedGas {
                     gas_id: "CHEBI:15379".to_string(),
@@ -189,45 +1371,780 @@ pub fn generate_initial_media(
     Ok(media_state)
 }
 
-/// JIT Optimizer: selects the best downstream process for each target.
-pub fn select_downstream_processes<'a>(
+/// The estimated grams of each substrate other than a nitrogen source that the organisms of
+/// `plan` take up in a batch to reach their required biomass, with its name, by molecule id.
+fn substrate_demands(plan: &SelectionPlan) -> BTreeMap<&str, (&str, f64)> {
+    let mut demands: BTreeMap<&str, (&str, f64)> = BTreeMap::new();
+    for org in &plan.organisms {
+        let required = plan.required_biomass_g.get(&org.organism_id).copied().unwrap_or(0.0);
+        let formed = (required - org.initial_biomass.value).max(0.0);
+        for consumption in &org.dynamic_parameters.metabolic_exchange.media_consumption {
+            if NITROGEN_SOURCES.iter().any(|(id, _, _)| *id == consumption.molecule_id) {
+                continue;
+            }
+            let demand = demands.entry(&consumption.molecule_id).or_insert((&consumption.molecule_name, 0.0));
+            if let Some(biomass_yield) = biomass_yield(org, consumption) {
+                demand.1 += formed / biomass_yield;
+            }
+        }
+    }
+    demands
+}
+
+/// Resolves `media` into the purchasable materials of `kb` that make it up, and writes the recipe
+/// to `media_recipe.yaml` in `output_dir`. The components of `feedstock` come from the
+/// feedstock, as much of it as holds the most of any of them.
+pub fn generate_media_recipe(
+    media: &MediaState,
+    feedstock: Option<&AvailableFeedstock>,
+    kb: &KnowledgeBase,
+    output_dir: &str,
+) -> Result<MediaRecipe> {
+    println!("\n--- [JIT] Resolving the Media Recipe ---");
+    let recipe = match feedstock {
+        None => MediaRecipe::resolve(media, &kb.materials)?,
+        Some(feedstock) => {
+            let (from_feedstock, purchased): (Vec<_>, Vec<_>) = media
+                .composition
+                .dissolved_components
+                .iter()
+                .cloned()
+                .partition(|c| feedstock.component(&c.molecule_id).is_some());
+            let mut purchased_media = media.clone();
+            purchased_media.composition.dissolved_components = purchased;
+            let mut recipe = MediaRecipe::resolve(&purchased_media, &kb.materials)?;
+            let amount_kg = from_feedstock
+                .iter()
+                .filter_map(|c| {
+                    let fraction = feedstock.component(&c.molecule_id)?.mass_fraction;
+                    Some(c.concentration.value * media.volume.value / fraction / 1000.0)
+                })
+                .fold(0.0, f64::max);
+            recipe.ingredients.push(RecipeIngredient {
+                material_id: feedstock.material_id.clone(),
+                material_name: kb
+                    .materials
+                    .get(&feedstock.material_id)
+                    .map_or(feedstock.material_id.clone(), |m| m.material_name.clone()),
+                amount: Measurement { value: amount_kg, unit: "kg".to_string() },
+                supplies: from_feedstock.into_iter().map(|c| c.molecule_id).collect(),
+            });
+            recipe.ingredients.sort_by(|a, b| a.material_id.cmp(&b.material_id));
+            recipe
+        }
+    };
+    let materials = analysis::MaterialResolver::new(&kb.materials);
+    println!("Media recipe for {:.0} {} ({}):", recipe.volume.value, recipe.volume.unit, kb.assumptions.currency);
+    println!("  {:<32} {:>12} {:<4} {:>10}  Supplies", "Material", "Amount", "Unit", "Cost");
+    for ingredient in &recipe.ingredients {
+        let supplies: Vec<&str> = ingredient
+            .supplies
+            .iter()
+            .map(|id| {
+                let component = media.composition.dissolved_components.iter().find(|c| &c.molecule_id == id);
+                component.map_or(id.as_str(), |c| c.molecule_name.as_str())
+            })
+            .collect();
+        println!(
+            "  {:<32} {:>12.3} {:<4} {:>10.2}  {}",
+            ingredient.material_name,
+            ingredient.amount.value,
+            ingredient.amount.unit,
+            analysis::material_cost(&materials, &ingredient.material_id, ingredient.amount.value * 1000.0),
+            supplies.join(", ")
+        );
+    }
+
+    let recipe_path = Path::new(output_dir).join("media_recipe.yaml");
+    fs::write(recipe_path, serde_yaml::to_string(&recipe)?)?;
+    Ok(recipe)
+}
+
+/// The lowest concentration, in g/L, at which `molecule_id` inhibits any of `organisms`, or `None`
+/// if none lists a tolerance for it.
+fn inhibitory_concentration(organisms: &[Organism], molecule_id: &str) -> Option<f64> {
+    organisms
+        .iter()
+        .flat_map(|o| &tolerances(o).chemical)
+        .filter(|tolerance| tolerance.molecule_id == molecule_id)
+        .flat_map(|tolerance| [&tolerance.minimum_inhibitory_concentration, &tolerance.inhibitory_concentration_50])
+        .flatten()
+        .map(|c| c.value)
+        .reduce(f64::min)
+}
+
+/// The purity a target leaves its downstream process at.
+#[derive(Debug, Clone, Serialize)]
+pub struct PurityEstimate {
+    pub molecule_name: String,
+    pub process_id: String,
+    /// The estimated purity, in percent by mass, from the producer's yield and the impurities
+    /// each method of the process removes.
+    pub purity_percent: f64,
+    pub minimum_purity_percent: Option<f64>,
+    /// The methods added to the process to reach the minimum purity, in the order they run.
+    pub polishing_method_ids: Vec<String>,
+}
+
+/// The file in the run directory [`PurityEstimate`]s are written to.
+pub const PURITY_REPORT_FILE: &str = "purity_report.json";
+
+/// Writes the purity of each target of `plan` to [`PURITY_REPORT_FILE`] in `output_dir`.
+pub fn write_purity_report(plan: &SelectionPlan, output_dir: &str) -> Result<()> {
+    fs::write(Path::new(output_dir).join(PURITY_REPORT_FILE), serde_json::to_string_pretty(&plan.purities)?)?;
+    Ok(())
+}
+
+/// The purity, in percent, of a product that makes up `fraction` of the mass entering `methods`
+/// once each has removed its share of the impurities.
+fn purity_after<'a>(fraction: f64, methods: impl IntoIterator<Item = &'a Method>) -> f64 {
+    let impurities = methods
+        .into_iter()
+        .filter_map(|m| m.impurity_removal_percent)
+        .fold(1.0 - fraction, |impurities, removal| impurities * (1.0 - removal / 100.0));
+    100.0 * fraction / (fraction + impurities)
+}
+
+/// The methods of `process` in the order of its workflow.
+fn workflow_methods(process: &Process) -> impl Iterator<Item = &Method> {
+    process.default_workflow.iter().filter_map(|id| process.methods.iter().find(|m| &m.method_id == id))
+}
+
+/// The purification methods of the processes in `kb` of the class of `process` that it does not
+/// run, the ones removing the most impurities first. A method shared by several processes is
+/// taken from the first of them by id.
+fn polishing_candidates(process: &Process, kb: &KnowledgeBase) -> Vec<Method> {
+    let mut processes: Vec<&Process> =
+        kb.processes.values().filter(|p| p.component_class == process.component_class).collect();
+    processes.sort_by(|a, b| a.process_id.cmp(&b.process_id));
+    let mut candidates: Vec<Method> = Vec::new();
+    for method in processes.into_iter().flat_map(workflow_methods) {
+        let purifies = method.stage.contains("Purification") && method.impurity_removal_percent.is_some();
+        if purifies
+            && !process.default_workflow.contains(&method.method_id)
+            && !candidates.iter().any(|c| c.method_id == method.method_id)
+        {
+            candidates.push(method.clone());
+        }
+    }
+    // Every candidate has an impurity removal; ties go by method id.
+    let removal = |method: &Method| method.impurity_removal_percent.unwrap_or(0.0);
+    candidates.sort_by(|a, b| removal(b).total_cmp(&removal(a)).then_with(|| a.method_id.cmp(&b.method_id)));
+    candidates
+}
+
+/// Adds [`polishing_candidates`] to `process`, each right after its last method that removes
+/// impurities, until a product making up `fraction` of the mass entering it reaches `minimum`
+/// percent purity. Returns the ids of the methods added, or an error if every candidate together
+/// falls short.
+fn polish(process: &mut Process, fraction: f64, minimum: f64, kb: &KnowledgeBase) -> Result<Vec<String>> {
+    let mut added = Vec::new();
+    let mut candidates = polishing_candidates(process, kb).into_iter();
+    while purity_after(fraction, workflow_methods(process)) < minimum {
+        let Some(method) = candidates.next() else {
+            bail!(
+                "'{}' reaches at most {:.1}% purity with the polishing methods of class '{}' ({}), short of the minimum of {:.1}%",
+                process.process_id,
+                purity_after(fraction, workflow_methods(process)),
+                process.component_class,
+                if added.is_empty() { "none available".to_string() } else { added.join(", ") },
+                minimum
+            );
+        };
+        let position = process
+            .default_workflow
+            .iter()
+            .rposition(|id| process.methods.iter().any(|m| &m.method_id == id && m.impurity_removal_percent.is_some()))
+            .map_or(0, |i| i + 1);
+        process.default_workflow.insert(position, method.method_id.clone());
+        added.push(method.method_id.clone());
+        process.methods.push(method);
+    }
+    Ok(added)
+}
+
+/// JIT Optimizer: selects the best downstream process for each target. The process is the one
+/// the target names, polished with the purification methods of its class until it reaches the
+/// target's minimum purity, if any, and the purity each target reaches is recorded in `plan`. A
+/// process whose blueprint estimate alone breaks a constraint of the request is only warned
+/// about.
+pub fn select_downstream_processes(
     request: &ValorizationRequest,
-    kb: &'a KnowledgeBase,
-) -> Result<Vec<&'a Process>> {
+    plan: &mut SelectionPlan,
+    kb: &KnowledgeBase,
+) -> Result<Vec<Process>> {
     println!("\n--- [JIT] Running Downstream Optimizer ---");
     let mut selected_processes = Vec::new();
+    plan.purities.clear();
 
     for target in &request.targets {
-        let best_process = kb
+        let mut best_process = kb
             .processes
             .get(&target.process_id)
             .context(format!(
                 "Optimizer failed to find a downstream process with id '{}'",
                 target.process_id
-            ))?;
-        
+            ))?
+            .clone();
+
+        // The product enters downstream as the share of the producer's biomass its yield makes.
+        let (_, yield_mg_g) = plan
+            .producer(&target.molecule_name)
+            .context(format!("No organism of the plan produces '{}'", target.molecule_name))?;
+        let fraction = yield_mg_g / 1000.0;
+        let polishing_method_ids = match target.minimum_purity_percent {
+            Some(minimum) => polish(&mut best_process, fraction, minimum, kb)?,
+            None => Vec::new(),
+        };
+        let purity_percent = purity_after(fraction, workflow_methods(&best_process));
+        println!(
+            "Selected process '{}' for target '{}' ({:.1}% purity)",
+            best_process.process_id, target.molecule_name, purity_percent
+        );
+        if !polishing_method_ids.is_empty() {
+            println!("Polishing with {} to reach the minimum purity", polishing_method_ids.join(", "));
+            best_process.notes = format!("{} Polished with {}.", best_process.notes, polishing_method_ids.join(", "));
+        }
+        plan.purities.push(PurityEstimate {
+            molecule_name: target.molecule_name.clone(),
+            process_id: best_process.process_id.clone(),
+            purity_percent,
+            minimum_purity_percent: target.minimum_purity_percent,
+            polishing_method_ids,
+        });
+        if !request.constraints.is_empty() {
+            let blueprint = analysis::generate_blueprint(&best_process, &kb.rules)?;
+            let (bom, cogs) = analysis::estimate_resources(&blueprint, &kb.assets, &kb.labor_roles, &kb.assumptions)?;
+            let gwp = analysis::calculate_lca(&bom, &kb.materials, &kb.assets, &kb.assumptions)?.gwp_kg_co2e();
+            let checks = request.constraints.check(Some(cogs.total_cogs), Some(bom.total_ticks as f64), Some(gwp));
+            for check in checks.iter().filter(|check| !check.passed) {
+                println!(
+                    "Warning: '{}' alone is estimated at {} {:.2}, over the limit of {:.2}",
+                    best_process.process_id, check.quantity, check.actual, check.limit
+                );
+            }
+        }
         selected_processes.push(best_process);
-        println!("Selected process '{}' for target '{}'", best_process.process_id, target.molecule_name);
     }
     Ok(selected_processes)
 }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bioforge_schemas::organism::{AerationCondition, ExchangeConditions, ChemicalTolerance, GasExchangeRate, StrainDetails};
 
-/// Helper function to find the yield of a specific molecule in an organism.
-pub fn find_yield(organism: &Organism, molecule_name: &str) -> Option<f64> {
-    organism
-        .static_properties
-        .targeted_molecular_classes
-        .terpenoids_and_carotenoids
-        .iter()
-        .find(|m| m.molecule == molecule_name)
-        .map(|m| m.concentration_mg_g_dw)
-        .or_else(|| {
+    const GLUCOSE_ID: &str = "CHEBI:17234";
+
+    /// The knowledge base of the repository.
+    fn knowledge_base() -> KnowledgeBase {
+        KnowledgeBase::load_with_overlays(concat!(env!("CARGO_MANIFEST_DIR"), "/../data/knowledge_base"), &[]).unwrap()
+    }
+
+    /// E. coli renamed to `organism_id`, growing on glucose alone at `glucose_yield` g/g and, if
+    /// `co2_uptake` is given, fixing carbon dioxide at that rate in mmol/g_dw/hr.
+    fn organism(kb: &KnowledgeBase, organism_id: &str, glucose_yield: f64, co2_uptake: Option<f64>) -> Organism {
+        let mut organism = kb.organisms["ORG-ECOLI"].clone();
+        organism.organism_id = organism_id.to_string();
+        let exchange = &mut organism.dynamic_parameters.metabolic_exchange;
+        exchange.media_consumption.retain(|c| c.molecule_id == GLUCOSE_ID);
+        exchange.media_consumption.truncate(1);
+        exchange.media_consumption[0].biomass_yield_g_g = Some(glucose_yield);
+        exchange.gas_consumption = co2_uptake
+            .map(|rate| GasExchangeRate {
+                gas_id: CARBON_DIOXIDE_ID.to_string(),
+                gas_name: "carbon dioxide".to_string(),
+                max_exchange_rate: Measurement { value: rate, unit: "mmol/g_dw/hr".to_string() },
+                conditions: ExchangeConditions { aeration: AerationCondition::Aerobic, light: None, notes: None },
+            })
+            .into_iter()
+            .collect();
+        organism
+    }
+
+    fn with_organisms(mut kb: KnowledgeBase, organisms: Vec<Organism>) -> KnowledgeBase {
+        kb.organisms = organisms.into_iter().map(|o| (o.organism_id.clone(), o)).collect();
+        kb
+    }
+
+    fn target(objective: &str) -> TargetRequest {
+        serde_yaml::from_str(&format!("molecule_name: Lutein\nobjective: {objective}\nprocess_id: PROC-TEST\ntarget_amount_grams: 2.5")).unwrap()
+    }
+
+    fn request() -> ValorizationRequest {
+        serde_yaml::from_str("targets: []").unwrap()
+    }
+
+    fn gross_gwp(kb: &KnowledgeBase, estimate: &CultivationEstimate) -> f64 {
+        analysis::calculate_lca(&estimate.bom, &kb.materials, &kb.assets, &kb.assumptions).unwrap().gwp_kg_co2e()
+    }
+
+    #[test]
+    fn fixation_credit_can_decide_the_lowest_gwp() {
+        let kb = knowledge_base();
+        let kb = with_organisms(kb.clone(), vec![organism(&kb, "ORG-HETERO", 0.5, None), organism(&kb, "ORG-PHOTO", 0.45, Some(10.0))]);
+        let target = target("MinimizeLca");
+
+        let hetero = estimate_cultivation(&kb.organisms["ORG-HETERO"], &target, &kb).unwrap().unwrap();
+        let photo = estimate_cultivation(&kb.organisms["ORG-PHOTO"], &target, &kb).unwrap().unwrap();
+        // 1000 g of biomass from the 0.1 g inoculum at 0.8/h: (1000 - 0.1) / 0.8 g·h at
+        // 10 mmol/g/h of CO2, 44.009 g/mol.
+        assert!((photo.co2_fixed_kg - 10.0 * 44.009 / 1e6 * (999.9 / 0.8)).abs() < 1e-9);
+        assert_eq!(hetero.co2_fixed_kg, 0.0);
+        // The photoautotroph emits more for its extra glucose, but less once its credit is taken.
+        let (hetero_gwp, photo_gwp) = (gross_gwp(&kb, &hetero), gross_gwp(&kb, &photo));
+        assert!(photo_gwp > hetero_gwp);
+        assert!(photo_gwp - photo.co2_fixed_kg < hetero_gwp);
+
+        assert_eq!(lowest_gwp(&kb, &target, &request()).unwrap().organism_id, "ORG-PHOTO");
+    }
+
+    /// The knowledge base of the repository with two organisms: `ORG-CHEAP`, growing on glucose,
+    /// and `ORG-RICH`, holding five times the lutein but growing only on a sugar priced at a
+    /// thousand times glucose.
+    fn rich_and_cheap() -> KnowledgeBase {
+        let mut kb = knowledge_base();
+        let mut sugar = kb.materials["MEDIA-GLUCOSE"].clone();
+        sugar.material_id = "MEDIA-RARE-SUGAR".to_string();
+        sugar.metadata.identifiers.as_mut().unwrap().chebi_id = Some("CHEBI:RARE-SUGAR".to_string());
+        sugar.techno_economic_and_lca_profile.lifecycle_stages.manufacturing_and_acquisition.costs[0].value_usd *= 1000.0;
+        kb.materials.insert(sugar.material_id.clone(), sugar);
+        let mut rich = organism(&kb, "ORG-RICH", 0.5, None);
+        rich.static_properties.targeted_molecular_classes.terpenoids_and_carotenoids.iter_mut().for_each(|m| m.concentration_mg_g_dw *= 5.0);
+        rich.dynamic_parameters.metabolic_exchange.media_consumption[0].molecule_id = "CHEBI:RARE-SUGAR".to_string();
+        with_organisms(kb.clone(), vec![rich, organism(&kb, "ORG-CHEAP", 0.5, None)])
+    }
+
+    #[test]
+    fn the_objective_decides_between_the_high_yield_and_the_cheap_organism() {
+        let kb = rich_and_cheap();
+        let selected = |objective: &str| {
+            let request = ValorizationRequest { targets: vec![target(objective)], ..request() };
+            select_optimal_organism_mix(&request, &kb).unwrap().producers["Lutein"].clone()
+        };
+        assert_eq!(selected("MaximizeYield"), "ORG-RICH");
+        assert_eq!(selected("MinimizeCost"), "ORG-CHEAP");
+
+        // The rich organism needs a fifth of the biomass, and so of the sugar, at a thousand times
+        // the price, which outweighs its shorter cultivation.
+        let cogs = |organism_id: &str| {
+            let estimate = estimate_cultivation(&kb.organisms[organism_id], &target("MinimizeCost"), &kb).unwrap().unwrap();
+            analysis::calculate_cogs(&estimate.bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions).unwrap()
+        };
+        let (rich, cheap) = (cogs("ORG-RICH"), cogs("ORG-CHEAP"));
+        assert!((rich.material_costs / cheap.material_costs - 200.0).abs() < 1.0, "{} vs {}", rich.material_costs, cheap.material_costs);
+        assert!(rich.total_cogs > cheap.total_cogs);
+    }
+
+    #[test]
+    fn organism_filters_decide_the_candidates_and_name_the_one_that_empties_them() {
+        let kb = knowledge_base();
+        let strain = |organism: &mut Organism, engineered: bool| {
+            organism.strain_details = Some(StrainDetails { description: None, is_engineered: engineered });
+        };
+        // The engineered organism holds five times the lutein of the wild type.
+        let mut engineered = organism(&kb, "ORG-ENGINEERED", 0.5, None);
+        engineered.static_properties.targeted_molecular_classes.terpenoids_and_carotenoids.iter_mut().for_each(|m| m.concentration_mg_g_dw *= 5.0);
+        strain(&mut engineered, true);
+        let mut wild = organism(&kb, "ORG-WILD", 0.5, None);
+        strain(&mut wild, false);
+        let kb = with_organisms(kb.clone(), vec![engineered, wild]);
+        let target = target("MaximizeYield");
+        let filtered = |yaml: &str| ValorizationRequest { organism_filter: serde_yaml::from_str(yaml).unwrap(), ..request() };
+
+        assert_eq!(highest_yield(&kb, &target, &request()).unwrap().organism_id, "ORG-ENGINEERED");
+        assert_eq!(highest_yield(&kb, &target, &filtered("allow_engineered: false")).unwrap().organism_id, "ORG-WILD");
+        assert_eq!(highest_yield(&kb, &target, &filtered("required_organism_ids: [ORG-WILD]")).unwrap().organism_id, "ORG-WILD");
+
+        let error = highest_yield(&kb, &target, &filtered("allow_engineered: false\nexcluded_organism_ids: [ORG-WILD]")).unwrap_err().to_string();
+        assert!(error.contains("under the request's allow_engineered: false, which ruled out the last candidate(s): ORG-ENGINEERED"), "{}", error);
+        let error = highest_yield(&kb, &target, &filtered("excluded_organism_ids: [ORG-WILD, ORG-ENGINEERED]")).unwrap_err().to_string();
+        assert!(error.contains("under the request's excluded_organism_ids, which ruled out the last candidate(s): ORG-ENGINEERED, ORG-WILD"), "{}", error);
+    }
+
+    #[test]
+    fn the_initial_media_scales_with_the_target_up_to_the_inhibitory_concentration() {
+        let kb = knowledge_base();
+        let output_dir = crate::config::tests::scratch_dir("initial_media_scaling");
+        let glucose_g_l = |producer: &Organism, required_biomass_g: f64| {
+            let plan = SelectionPlan {
+                organisms: vec![producer.clone()],
+                producers: BTreeMap::new(),
+                required_biomass_g: BTreeMap::from([(producer.organism_id.clone(), required_biomass_g)]),
+                bioreactor: select_bioreactor(100.0, &kb).unwrap(),
+                batches: 1,
+                feedstock_balance: None,
+                purities: Vec::new(),
+            };
+            let media = generate_initial_media(&plan, &request(), &kb, output_dir.to_str().unwrap()).unwrap();
+            media.composition.dissolved_components.iter().find(|c| c.molecule_id == GLUCOSE_ID).unwrap().concentration.value
+        };
+        let mut producer = organism(&kb, "ORG-TEST", 0.5, None);
+        producer.dynamic_parameters.environmental_tolerances.chemical.clear();
+        let inoculum_g = producer.initial_biomass.value;
+
+        // 30% of the glucose that forms the biomass at 0.5 g/g, in the 500 L loop.
+        let small = glucose_g_l(&producer, 100.0);
+        let large = glucose_g_l(&producer, 200.0);
+        assert!((small - 0.3 * (100.0 - inoculum_g) / 0.5 / 500.0).abs() < 1e-9, "{}", small);
+        assert!((large / small - (200.0 - inoculum_g) / (100.0 - inoculum_g)).abs() < 1e-9);
+
+        // An organism inhibited at 0.15 g/L of glucose caps the larger charge, but not the smaller.
+        producer.dynamic_parameters.environmental_tolerances.chemical.push(ChemicalTolerance {
+            molecule_id: GLUCOSE_ID.to_string(),
+            molecule_name: "D-glucose".to_string(),
+            minimum_inhibitory_concentration: None,
+            inhibitory_concentration_50: Some(Measurement { value: 0.15, unit: "g/L".to_string() }),
+        });
+        assert_eq!(glucose_g_l(&producer, 100.0), small);
+        assert_eq!(glucose_g_l(&producer, 200.0), 0.15);
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+
+    #[test]
+    fn consortium_windows_of_incompatible_marginal_and_compatible_pairs() {
+        let kb = knowledge_base();
+        // An organism viable from `min` to `max` °C with its optimum at `optimal`, and at pH 5-9.
+        let tolerant = |organism_id: &str, min: f64, max: f64, optimal: f64| {
+            let mut organism = organism(&kb, organism_id, 0.5, None);
+            let tolerances = &mut organism.dynamic_parameters.environmental_tolerances;
+            tolerances.temperature.range = ToleranceRange { min, max };
+            tolerances.temperature.optimal.value = optimal;
+            tolerances.ph.range = ToleranceRange { min: 5.0, max: 9.0 };
+            tolerances.chemical.clear();
             organism
-                .static_properties
-                .targeted_molecular_classes
-                .cell_wall_components
-                .iter()
-                .find(|m| m.molecule == molecule_name)
-                .map(|m| m.concentration_mg_g_dw)
-        })
-}
\ No newline at end of file
+        };
+
+        let error = consortium_window(&[tolerant("ORG-MESO", 20.0, 37.0, 30.0), tolerant("ORG-THERMO", 40.0, 60.0, 50.0)]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The selected organisms cannot share a vessel: ORG-THERMO needs a temperature of at least 40°C but ORG-MESO tolerates at most 37°C"
+        );
+
+        // They share 34-37 °C, under five degrees: held at the midpoint of their optima, 35 °C.
+        let marginal = consortium_window(&[tolerant("ORG-MESO", 20.0, 37.0, 30.0), tolerant("ORG-WARM", 34.0, 50.0, 40.0)]).unwrap();
+        assert_eq!((marginal.temperature.min, marginal.temperature.max), (34.0, 37.0));
+        assert_eq!(marginal.temperature_setpoint, Some(35.0));
+        assert_eq!(marginal.warnings, ["the organisms share only 34.0-37.0 °C; their optima span 30.0-40.0 °C"]);
+
+        let compatible = consortium_window(&[tolerant("ORG-MESO", 20.0, 37.0, 30.0), tolerant("ORG-MILD", 25.0, 40.0, 32.0)]).unwrap();
+        assert_eq!((compatible.temperature.min, compatible.temperature.max), (25.0, 37.0));
+        assert_eq!((compatible.ph.min, compatible.ph.max), (5.0, 9.0));
+        assert_eq!(compatible.temperature_setpoint, None);
+        assert!(compatible.warnings.is_empty(), "{:?}", compatible.warnings);
+    }
+
+    #[test]
+    fn producers_tie_to_the_lowest_id_and_a_target_without_one_fails() {
+        let kb = knowledge_base();
+        // Two identical organisms, listed highest id first.
+        let kb = with_organisms(kb.clone(), vec![organism(&kb, "ORG-B", 0.5, None), organism(&kb, "ORG-A", 0.5, None)]);
+        for objective in ["MaximizeYield", "MinimizeCost", "MinimizeLca"] {
+            let request = ValorizationRequest { targets: vec![target(objective)], ..request() };
+            let plan = select_optimal_organism_mix(&request, &kb).unwrap();
+            assert_eq!(plan.producers["Lutein"], "ORG-A", "{}", objective);
+            assert_eq!(plan.organisms.len(), 1);
+            let (producer, yield_mg_g) = plan.producer("Lutein").unwrap();
+            assert_eq!((producer.organism_id.as_str(), Some(yield_mg_g)), ("ORG-A", producer.yield_for("Lutein")));
+            assert!(plan.producer("Astaxanthin").is_none());
+        }
+
+        let mut astaxanthin = target("MaximizeYield");
+        astaxanthin.molecule_name = "Astaxanthin".to_string();
+        let request = ValorizationRequest { targets: vec![target("MaximizeYield"), astaxanthin], ..request() };
+        let error = select_optimal_organism_mix(&request, &kb).unwrap_err();
+        assert_eq!(error.to_string(), "Optimizer failed to find an organism for 'Astaxanthin'");
+    }
+
+    #[test]
+    fn a_nitrate_consumer_is_dosed_the_nitrate_its_biomass_needs() {
+        const NITRATE_ID: &str = "CHEBI:49740";
+        let kb = knowledge_base();
+        let mut producer = organism(&kb, "ORG-TEST", 0.5, None);
+        producer.static_properties.nitrogen_sources.clear();
+        let exchange = &mut producer.dynamic_parameters.metabolic_exchange;
+        let mut nitrate = exchange.media_consumption[0].clone();
+        nitrate.molecule_id = NITRATE_ID.to_string();
+        nitrate.molecule_name = "nitrate".to_string();
+        nitrate.biomass_yield_g_g = None;
+        exchange.media_consumption.push(nitrate);
+        producer.dynamic_parameters.environmental_tolerances.chemical.clear();
+        assert_eq!(nitrogen_sources(&producer), [NITRATE_ID]);
+
+        let plan = SelectionPlan {
+            required_biomass_g: BTreeMap::from([(producer.organism_id.clone(), 100.0)]),
+            organisms: vec![producer.clone()],
+            producers: BTreeMap::new(),
+            bioreactor: select_bioreactor(20.0, &kb).unwrap(),
+            batches: 1,
+            feedstock_balance: None,
+            purities: Vec::new(),
+        };
+        let output_dir = crate::config::tests::scratch_dir("nitrate_dosing");
+        let media = generate_initial_media(&plan, &request(), &kb, output_dir.to_str().unwrap()).unwrap();
+        let grams = |molecule_id: &str| {
+            media.composition.dissolved_components.iter().find(|c| c.molecule_id == molecule_id).map(|c| c.concentration.value * media.volume.value)
+        };
+        assert_eq!(grams(DEFAULT_NITROGEN_SOURCE), None);
+
+        // The nitrogen of the biomass formed, 1.2 times over, as nitrate, 14.007 / 62.004 nitrogen.
+        let nitrogen_g = (100.0 - producer.initial_biomass.value) * producer.static_properties.elemental_composition.nitrogen * 1.2;
+        let nitrate_g = grams(NITRATE_ID).unwrap();
+        assert!((nitrate_g * 14.007 / 62.004 - nitrogen_g).abs() < 1e-9, "{} g of nitrate for {} g N", nitrate_g, nitrogen_g);
+
+        // The recipe buys it as sodium nitrate, 72.95% nitrate by weight.
+        let recipe = generate_media_recipe(&media, None, &kb, output_dir.to_str().unwrap()).unwrap();
+        let sodium_nitrate = recipe.ingredients.iter().find(|i| i.supplies.iter().any(|id| id == NITRATE_ID)).unwrap();
+        assert_eq!(sodium_nitrate.material_id, "MEDIA-NANO3");
+        assert!((sodium_nitrate.amount.value * 1000.0 * 0.7295 - nitrate_g).abs() < 1e-9);
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+
+    #[test]
+    fn a_molasses_feedstock_caps_the_targets_at_its_scarcest_sugar() {
+        const SUCROSE_ID: &str = "CHEBI:17992";
+        let kb = knowledge_base();
+        let mut producer = organism(&kb, "ORG-TEST", 0.5, None);
+        let exchange = &mut producer.dynamic_parameters.metabolic_exchange;
+        let mut sucrose = exchange.media_consumption[0].clone();
+        sucrose.molecule_id = SUCROSE_ID.to_string();
+        sucrose.molecule_name = "sucrose".to_string();
+        sucrose.biomass_yield_g_g = Some(0.4);
+        exchange.media_consumption.push(sucrose);
+        let formed_g = 100.0 - producer.initial_biomass.value;
+
+        // Half a kilogram of molasses, 40% sucrose and 50% glucose: 200 g of the sucrose the
+        // culture needs 100 g / 0.4 of, and 250 g of the glucose it needs 100 g / 0.5 of.
+        let fit = |scale_targets: bool| {
+            let mut request: ValorizationRequest = serde_yaml::from_str(&format!(
+                "targets:\n- {{ molecule_name: Lutein, objective: MaximizeYield, process_id: PROC-TEST, target_amount_grams: 2.5 }}\n\
+                 available_feedstock:\n  material_id: FEED-MOLASSES\n  amount_kg: 0.5\n  scale_targets: {scale_targets}\n  composition:\n\
+                 \x20 - {{ molecule_id: '{SUCROSE_ID}', molecule_name: sucrose, mass_fraction: 0.4 }}\n\
+                 \x20 - {{ molecule_id: '{GLUCOSE_ID}', molecule_name: D-glucose, mass_fraction: 0.5 }}"
+            ))
+            .unwrap();
+            let mut plan = SelectionPlan {
+                required_biomass_g: BTreeMap::from([(producer.organism_id.clone(), 100.0)]),
+                organisms: vec![producer.clone()],
+                producers: BTreeMap::from([("Lutein".to_string(), producer.organism_id.clone())]),
+                bioreactor: select_bioreactor(20.0, &kb).unwrap(),
+                batches: 1,
+                feedstock_balance: None,
+                purities: Vec::new(),
+            };
+            fit_targets_to_feedstock(&mut request, &mut plan, &kb).unwrap();
+            (request, plan)
+        };
+
+        let (request, plan) = fit(true);
+        let balance = plan.feedstock_balance.as_ref().unwrap();
+        let fraction = 200.0 / (formed_g / 0.4);
+        assert!((balance.achievable_fraction - fraction).abs() < 1e-9, "{}", balance.achievable_fraction);
+        assert!(balance.targets_scaled);
+        let surplus: Vec<(&str, f64)> = balance.components.iter().map(|c| (c.molecule_name.as_str(), c.surplus_g)).collect();
+        assert!(surplus[0].1 < 0.0 && surplus[1].1 > 0.0, "{:?}", surplus);
+        assert!((request.targets[0].target_amount_grams - 2.5 * fraction).abs() < 1e-9);
+        assert!((plan.required_biomass_g["ORG-TEST"] - 100.0 * fraction).abs() < 1e-9);
+
+        // Asked only to warn, the feedstock leaves the targets and the culture as requested.
+        let (request, plan) = fit(false);
+        let balance = plan.feedstock_balance.as_ref().unwrap();
+        assert!((balance.achievable_fraction - fraction).abs() < 1e-9);
+        assert!(!balance.targets_scaled);
+        assert!((balance.targets[0].achievable_g - 2.5 * fraction).abs() < 1e-9);
+        assert_eq!(request.targets[0].target_amount_grams, 2.5);
+        assert_eq!(plan.required_biomass_g["ORG-TEST"], 100.0);
+    }
+
+    #[test]
+    fn the_target_amount_decides_the_bioreactor() {
+        let mut kb = knowledge_base();
+        kb.assets.retain(|_, asset| !asset.asset_type.contains("BIOREACTOR") || ["CULTIVATION-STR-01", "CULTIVATION-LOOP-01"].contains(&asset.asset_id.as_str()));
+        let sizes: Vec<(String, f64)> = bioreactors(&kb).into_iter().map(|b| (b.asset_id, b.working_volume_l)).collect();
+        assert_eq!(sizes, [("CULTIVATION-STR-01".to_string(), 50.0), ("CULTIVATION-LOOP-01".to_string(), 500.0)]);
+
+        assert_eq!(select_bioreactor(50.0, &kb).unwrap().asset_id, "CULTIVATION-STR-01");
+        assert_eq!(select_bioreactor(50.5, &kb).unwrap().asset_id, "CULTIVATION-LOOP-01");
+        let error = select_bioreactor(600.0, &kb).unwrap_err().to_string();
+        assert!(error.contains("the largest, CULTIVATION-LOOP-01, holds 500 L"), "{}", error);
+
+        // 40 L of culture fits the stirred tank, 400 L only the loop.
+        let producer = organism(&kb, "ORG-TEST", 0.5, None);
+        let grams_per_l = producer.dynamic_parameters.max_cell_density_g_l.unwrap_or(DEFAULT_MAX_CELL_DENSITY_G_L)
+            * producer.yield_for("Lutein").unwrap()
+            / 1000.0;
+        let kb = with_organisms(kb.clone(), vec![producer]);
+        let bioreactor = |culture_l: f64| {
+            let mut target = target("MaximizeYield");
+            target.target_amount_grams = culture_l * grams_per_l;
+            let request = ValorizationRequest { targets: vec![target], ..request() };
+            let plan = select_optimal_organism_mix(&request, &kb).unwrap();
+            assert_eq!(plan.batches, 1);
+            plan.bioreactor.asset_id
+        };
+        assert_eq!(bioreactor(40.0), "CULTIVATION-STR-01");
+        assert_eq!(bioreactor(400.0), "CULTIVATION-LOOP-01");
+    }
+
+    #[test]
+    fn three_times_the_largest_culture_runs_as_three_batches() {
+        use bioforge_core::analysis::campaign::{CampaignModel, CampaignProduct};
+
+        let kb = knowledge_base();
+        let producer = organism(&kb, "ORG-TEST", 0.5, None);
+        let largest = bioreactors(&kb).pop().unwrap();
+        let density = producer.dynamic_parameters.max_cell_density_g_l.unwrap_or(DEFAULT_MAX_CELL_DENSITY_G_L);
+        let batch_biomass_g = largest.working_volume_l * density;
+        let mut required = BTreeMap::from([("ORG-TEST".to_string(), 3.0 * batch_biomass_g)]);
+
+        let (bioreactor, batches) = split_into_batches(std::slice::from_ref(&producer), &mut required, &kb).unwrap();
+        assert_eq!(batches, 3);
+        assert_eq!(bioreactor.asset_id, largest.asset_id);
+        assert!((required["ORG-TEST"] - batch_biomass_g).abs() < 1e-9);
+
+        // The cultivation of one batch, repeated for the three.
+        let mut target = target("MinimizeCost");
+        target.target_amount_grams = batch_biomass_g * producer.yield_for("Lutein").unwrap() / 1000.0;
+        let batch = estimate_cultivation(&producer, &target, &kb).unwrap().unwrap().bom;
+        assert_eq!(batch.asset_ticks().keys().collect::<Vec<_>>(), [&largest.asset_id]);
+        let cogs = |bom: &BillOfMaterials| analysis::calculate_cogs(bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions).unwrap();
+        let (batch_cogs, total_cogs) = (cogs(&batch), cogs(&batch.scaled_out(batches)));
+        assert!((total_cogs.material_costs / batch_cogs.material_costs - 3.0).abs() < 1e-9);
+        // Each batch bears the depreciation of its own hours in the vessel...
+        assert!((total_cogs.asset_depreciation_costs / batch_cogs.asset_depreciation_costs - 3.0).abs() < 1e-9);
+
+        // ...but the vessel depreciates by the same amount a year however many batches it runs.
+        let lca = analysis::calculate_lca(&batch, &kb.materials, &kb.assets, &kb.assumptions).unwrap();
+        let products = vec![CampaignProduct { molecule_name: "Lutein".to_string(), grams_per_batch: target.target_amount_grams }];
+        let campaign = CampaignModel::new(&batch, &batch_cogs, &lca, products, &CampaignParameters::default(), &kb.assets, &kb.assumptions)
+            .unwrap()
+            .evaluate();
+        let vessel = kb.assets[&largest.asset_id].techno_economic_and_lca_profile.as_ref().unwrap();
+        let capex = vessel.lifecycle_stages.manufacturing_and_acquisition.costs.iter().find(|c| c.cost_type == "capex").unwrap().value_usd;
+        let lifespan_years = vessel.expected_lifespan.as_ref().unwrap().value as f64;
+        assert!((campaign.annual_cogs.asset_depreciation_costs - capex / lifespan_years).abs() < 1e-9);
+    }
+
+    #[test]
+    fn constraints_parse_from_the_request_and_a_tight_budget_flips_the_organism() {
+        let constrained: ValorizationRequest =
+            serde_yaml::from_str("targets: []\nconstraints:\n  max_total_cost_usd: 1500\n  max_gwp_kg_co2e: 12.5").unwrap();
+        assert_eq!(
+            constrained.constraints,
+            Constraints { max_total_cost_usd: Some(1500.0), max_duration_hours: None, max_gwp_kg_co2e: Some(12.5) }
+        );
+        assert!(request().constraints.is_empty());
+        assert!(serde_yaml::from_str::<ValorizationRequest>("targets: []\nconstraints:\n  max_total_cost_usd: cheap").is_err());
+
+        let kb = rich_and_cheap();
+        let target = target("MaximizeYield");
+        let estimated_cogs = |organism_id: &str| {
+            let estimate = estimate_cultivation(&kb.organisms[organism_id], &target, &kb).unwrap().unwrap();
+            analysis::calculate_cogs(&estimate.bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions).unwrap().total_cogs
+        };
+        let (rich, cheap) = (estimated_cogs("ORG-RICH"), estimated_cogs("ORG-CHEAP"));
+        assert!(cheap < rich);
+        let budget = |max: f64| {
+            serde_yaml::from_str::<ValorizationRequest>(&format!("targets: []\nconstraints: {{ max_total_cost_usd: {max} }}")).unwrap()
+        };
+
+        assert_eq!(highest_yield(&kb, &target, &budget(rich + 1.0)).unwrap().organism_id, "ORG-RICH");
+        // A budget between the two prunes the richer, dearer organism.
+        assert_eq!(highest_yield(&kb, &target, &budget((rich + cheap) / 2.0)).unwrap().organism_id, "ORG-CHEAP");
+        let error = highest_yield(&kb, &target, &budget(cheap - 1.0)).unwrap_err().to_string();
+        assert_eq!(error, "No organism can produce 2.50 g of 'Lutein' within the request's constraints");
+    }
+
+    #[test]
+    fn pareto_front_drops_the_dominated_candidate_and_selects_the_knee() {
+        let kb = knowledge_base();
+        // A yields twice as much lutein on a tenth of the glucose yield; B and C differ only in
+        // that C needs more glucose, so B dominates it.
+        let mut a = organism(&kb, "ORG-A", 0.1, None);
+        a.static_properties.targeted_molecular_classes.terpenoids_and_carotenoids.iter_mut().for_each(|m| m.concentration_mg_g_dw = 5.0);
+        let kb = with_organisms(kb.clone(), vec![a, organism(&kb, "ORG-B", 0.5, None), organism(&kb, "ORG-C", 0.4, None)]);
+        let target = target("ParetoFront");
+
+        let scored = score_candidates(&kb, &target, &request()).unwrap();
+        let [a, b, c] = [0, 1, 2].map(|i| &scored[i].1);
+        assert_eq!([a.organism_id.as_str(), b.organism_id.as_str(), c.organism_id.as_str()], ["ORG-A", "ORG-B", "ORG-C"]);
+        assert!(b.dominates(c));
+        assert_eq!([a.non_dominated, b.non_dominated, c.non_dominated], [true, true, false]);
+
+        // C takes up (1000 - 0.1) g of biomass / 0.4 - / 0.5 more glucose than B, charged per kg.
+        let glucose = analysis::MaterialResolver::new(&kb.materials).resolve(GLUCOSE_ID).unwrap();
+        let glucose_gwp = glucose.techno_economic_and_lca_profile.lifecycle_stages.manufacturing_and_acquisition.impacts.iter().find(|i| i.metric == "gwp").unwrap().value;
+        assert!((c.gwp_kg_co2e - b.gwp_kg_co2e - 999.9 * (1.0 / 0.4 - 1.0 / 0.5) / 1000.0 * glucose_gwp).abs() < 1e-6);
+
+        // GWP scores run from the worst, C, at 0 to the best, B, at 1.
+        let gwp_score = (c.gwp_kg_co2e - a.gwp_kg_co2e) / (c.gwp_kg_co2e - b.gwp_kg_co2e);
+        assert_eq!([a.normalized[0], b.normalized[0], c.normalized[0]], [1.0, 0.0, 0.0]);
+        assert!((a.normalized[2] - gwp_score).abs() < 1e-9);
+        assert_eq!([b.normalized[2], c.normalized[2]], [1.0, 0.0]);
+
+        // A falls short of the ideal on GWP alone, B on yield entirely.
+        assert!(a.distance_from_ideal() < b.distance_from_ideal());
+        assert_eq!(pareto_knee(&kb, &target, &request()).unwrap().organism_id, "ORG-A");
+    }
+
+    #[test]
+    fn the_minimum_purity_decides_the_polishing_and_its_cost() {
+        let kb = knowledge_base();
+        // At 20 mg/g, lutein leaves PROC-LUT-FED-01 at 58% purity; the crystallization brings it to
+        // 97%, and the two-phase extraction after it to 99.4%.
+        let mut producer = organism(&kb, "ORG-TEST", 0.5, None);
+        producer.static_properties.targeted_molecular_classes.terpenoids_and_carotenoids.iter_mut().for_each(|m| m.concentration_mg_g_dw = 20.0);
+        let kb = with_organisms(kb.clone(), vec![producer]);
+        let polished = |minimum: f64| {
+            let mut target = target("MaximizeYield");
+            target.process_id = "PROC-LUT-FED-01".to_string();
+            target.minimum_purity_percent = Some(minimum);
+            let request = ValorizationRequest { targets: vec![target], ..request() };
+            let mut plan = select_optimal_organism_mix(&request, &kb)?;
+            let process = select_downstream_processes(&request, &mut plan, &kb)?.remove(0);
+            let blueprint = analysis::generate_blueprint(&process, &kb.rules)?;
+            let (_, cogs) = analysis::estimate_resources(&blueprint, &kb.assets, &kb.labor_roles, &kb.assumptions)?;
+            Ok::<_, anyhow::Error>((plan.purities.remove(0), cogs.total_cogs))
+        };
+
+        let (food, food_cogs) = polished(80.0).unwrap();
+        let (pharma, pharma_cogs) = polished(99.0).unwrap();
+        assert_eq!(food.polishing_method_ids, ["MTHD-LUT-PUR-CRY-01"]);
+        assert_eq!(pharma.polishing_method_ids, ["MTHD-LUT-PUR-CRY-01", "MTHD-LUT-PUR-ATPS-01"]);
+        assert!(food.purity_percent >= 80.0 && food.purity_percent < 99.0, "{}", food.purity_percent);
+        assert!(pharma.purity_percent >= 99.0, "{}", pharma.purity_percent);
+        assert!(pharma_cogs > food_cogs, "{} vs {}", pharma_cogs, food_cogs);
+
+        let error = polished(99.9).unwrap_err().to_string();
+        assert!(error.contains("'PROC-LUT-FED-01' reaches at most 99.4% purity"), "{}", error);
+        assert!(error.contains("MTHD-LUT-PUR-CRY-01, MTHD-LUT-PUR-ATPS-01"), "{}", error);
+    }
+
+    #[test]
+    fn polishing_candidates_run_from_most_to_least_removal_then_by_id() {
+        let mut kb = knowledge_base();
+        let donor = kb.processes.values().find(|p| workflow_methods(p).any(|m| m.impurity_removal_percent.is_some() && m.stage.contains("Purification"))).unwrap().clone();
+        let purification = workflow_methods(&donor).find(|m| m.impurity_removal_percent.is_some() && m.stage.contains("Purification")).unwrap().clone();
+        // Two methods tie, and a malformed one removes NaN percent.
+        let extra_methods: Vec<Method> = [("MTHD-TIE-B", 50.0), ("MTHD-TIE-A", 50.0), ("MTHD-NAN", f64::NAN)]
+            .into_iter()
+            .map(|(id, removal)| Method { method_id: id.to_string(), impurity_removal_percent: Some(removal), ..purification.clone() })
+            .collect();
+        let extra = Process {
+            process_id: "PROC-EXTRA".to_string(),
+            default_workflow: extra_methods.iter().map(|m| m.method_id.clone()).collect(),
+            methods: extra_methods,
+            ..donor.clone()
+        };
+        kb.processes.insert(extra.process_id.clone(), extra);
+        let target = Process { process_id: "PROC-TARGET".to_string(), default_workflow: vec![], methods: vec![], ..donor };
+
+        let removals: Vec<(f64, String)> = polishing_candidates(&target, &kb)
+            .into_iter()
+            .map(|m| (m.impurity_removal_percent.unwrap(), m.method_id))
+            .collect();
+        assert_eq!(removals[0].1, "MTHD-NAN");
+        assert!(removals[1..].windows(2).all(|w| w[0].0 > w[1].0 || (w[0].0 == w[1].0 && w[0].1 < w[1].1)), "{:?}", removals);
+        let tie_a = removals.iter().position(|(_, id)| id == "MTHD-TIE-A").unwrap();
+        assert_eq!(removals[tie_a + 1].1, "MTHD-TIE-B");
+    }
+}
@@ -1,48 +1,281 @@
 use anyhow::{Context, Result};
+use clap::Parser;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
-use bioforge_core::analysis;
+use crate::cli::{Cli, Command, Inputs, LogFormat, MonteCarloArgs, RequestSource, RunArgs, SweepArgs};
+use crate::config::DataFormat;
 use crate::jit::ValorizationRequest;
+use bioforge_core::analysis::manifest::{RunManifest, MANIFEST_FILE};
 
+mod cli;
 mod config;
 mod jit;
 mod plotting;
+mod report;
+mod sweep;
+mod validation;
 mod workflow;
 
 fn main() -> Result<()> {
+    init_logging();
+    let cli = Cli::parse();
+
     println!("--- Bioforge Application ---");
 
+    match cli.command {
+        Some(Command::Run(args)) => run(args),
+        Some(Command::MonteCarlo(args)) => run_monte_carlo(args),
+        Some(Command::Compare(args)) => run_comparison(&args.run_a, &args.run_b, &args.out),
+        Some(Command::Sweep(args)) => run_sweep(args),
+        None => run(cli.run),
+    }
+}
+
+/// A request's YAML as read, and where it was read from.
+struct LoadedRequest {
+    source: RequestSource,
+    text: String,
+    /// YAML or JSON, by the extension of the file read, or by the text itself for standard input.
+    format: DataFormat,
+}
+
+impl LoadedRequest {
+    /// Reads the request from `source`.
+    fn read(source: RequestSource) -> Result<Self> {
+        let text = source.read()?;
+        let format = source.path().and_then(DataFormat::of).unwrap_or_else(|| DataFormat::sniff(&text));
+        Ok(Self { source, text, format })
+    }
+}
+
+/// Reads the request of `inputs` from wherever [`Inputs::request_source`] finds it.
+fn load_request(inputs: &Inputs) -> Result<LoadedRequest> {
+    let source = inputs.request_source()?;
+    println!("Reading the request from {}", source);
+    LoadedRequest::read(source)
+}
+
+/// Parses `loaded`, checks it against the knowledge base of `inputs`, and selects the organisms
+/// and downstream processes that fulfil it, with its targets fitted to its available feedstock.
+fn prepare(
+    inputs: &Inputs,
+    loaded: &LoadedRequest,
+) -> Result<(ValorizationRequest, config::KnowledgeBase, jit::SelectionPlan)> {
     // --- Target Selection ---
-    // Load the request from the YAML file
-    let request_str = fs::read_to_string("bioforge-app/request.yaml")
-        .context("Failed to read request.yaml")?;
-    let request: ValorizationRequest = serde_yaml::from_str(&request_str)
-        .context("Failed to parse request.yaml")?;
+    let mut request: ValorizationRequest = loaded
+        .format
+        .parse(&loaded.text)
+        .with_context(|| format!("Failed to parse {} from {}", loaded.format, loaded.source))?;
+
+    let kb = load_knowledge_base(inputs)?;
+    validation::validate_request(&request, &kb)?;
 
-    let kb = config::KnowledgeBase::load("./data/knowledge_base")?;
+    let mut plan = jit::select_optimal_organism_mix(&request, &kb)?;
+    jit::fit_targets_to_feedstock(&mut request, &mut plan, &kb)?;
+    Ok((request, kb, plan))
+}
 
-    let upstream_organisms = jit::select_optimal_organism_mix(&request, &kb)?;
-    let downstream_processes = jit::select_downstream_processes(&request, &kb)?;
+/// Loads the knowledge base of `inputs`, with its overlays, and reports its dangling references, failing on errors
+/// among them under `--strict`.
+fn load_knowledge_base(inputs: &Inputs) -> Result<config::KnowledgeBase> {
+    let kb = config::KnowledgeBase::load_with_overlays(&inputs.kb.to_string_lossy(), &inputs.kb_overlays)?;
+    validation::check_knowledge_base(&kb, inputs.strict)?;
+    Ok(kb)
+}
 
-    let output_dir = format!("./data/runs/Lutein_bGlucan_{}", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
+/// Creates a run directory named `name` plus the current time in `out`, and returns its path.
+fn create_run_dir(out: &Path, name: &str, time: chrono::DateTime<chrono::Utc>) -> Result<String> {
+    let output_dir = out.join(format!("{}_{}", name, time.format("%Y%m%d_%H%M%S"))).to_string_lossy().into_owned();
     fs::create_dir_all(&output_dir)
         .with_context(|| format!("Failed to create output directory: {}", output_dir))?;
+    Ok(output_dir)
+}
+
+/// Runs the end-to-end workflow, or the sensitivity study or dry run `args` ask for instead.
+fn run(args: RunArgs) -> Result<()> {
+    let loaded = match &args.resume {
+        Some(run_dir) => LoadedRequest::read(RequestSource::Resumed(report::request_file(run_dir)))?,
+        None => load_request(&args.inputs)?,
+    };
+    let (mut request, kb, mut plan) = prepare(&args.inputs, &loaded)?;
+    let selected_processes = jit::select_downstream_processes(&request, &mut plan, &kb)?;
+    let downstream_processes: Vec<_> = selected_processes.iter().collect();
 
-    // Copy the request file to the output directory for traceability
-    fs::copy("bioforge-app/request.yaml", Path::new(&output_dir).join("request.yaml"))?;
+    let run_name: Vec<String> = request
+        .targets
+        .iter()
+        .map(|t| t.molecule_name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' }).collect())
+        .collect();
 
+    // `--dry-run` plans the workflow in a plan directory of its own and skips the simulations.
+    if args.dry_run {
+        let output_dir = create_run_dir(&args.inputs.out, &format!("{}_plan", run_name.join("_")), chrono::Utc::now())?;
+        workflow::plan_workflow(&request, &kb, &plan, &downstream_processes, &output_dir)?;
+        return Ok(());
+    }
 
-    // Generate the initial media for the selected organisms
-    let initial_media = jit::generate_initial_media(&upstream_organisms, &output_dir)?;
-    
-    // Create a BOM for the initial media
-    let initial_bom = analysis::bom_from_media_state(&initial_media)?;
+    // `--sensitivity` runs the one-at-a-time study of `sensitivity.yaml` instead of the workflow.
+    if let Some(study) = &args.sensitivity {
+        return run_sensitivity(&request, &kb, &plan, study, &args.inputs.out);
+    }
 
-    let upstream_output = workflow::run_upstream_simulations(&upstream_organisms, &kb, &output_dir, initial_media, &request)?;
-    
-    workflow::run_downstream_and_report(&downstream_processes, &upstream_output, &kb, &output_dir, &request, &upstream_organisms, initial_bom)?;
+    if args.no_plots {
+        request.plotting.enabled = Some(Vec::new());
+    }
+    let options = workflow::OutputOptions {
+        date_axis: args.date_axis,
+        wide_log: args.log_format == LogFormat::Wide,
+        log_backend: args.log_backend,
+    };
+
+    let (output_dir, start_time) = match &args.resume {
+        Some(run_dir) => resumed_run(run_dir, &kb)?,
+        None => {
+            // The batch start time anchors both the run directory name and the log timestamps.
+            let start_time = chrono::Utc::now();
+            let output_dir = create_run_dir(&args.inputs.out, &run_name.join("_"), start_time)?;
+
+            // Keep the request as it was read, whichever source it came from, for traceability.
+            let request_file = format!("{}.{}", report::REQUEST_FILE_STEM, loaded.format.extension());
+            fs::write(Path::new(&output_dir).join(request_file), &loaded.text)?;
+            report::write_manifest(&output_dir, &request, &loaded.source.to_string(), &kb, None, start_time)?;
+            // Keep the knowledge base the run is computed from, too, rather than a pointer to a
+            // directory that may change.
+            if !args.no_kb_snapshot {
+                kb.export(&Path::new(&output_dir).join(config::KB_SNAPSHOT_DIR))?;
+            }
+            (output_dir, start_time)
+        }
+    };
+    workflow::run_workflow(&request, &kb, &plan, &downstream_processes, &output_dir, start_time, options)?;
 
     println!("\nEnd-to-end workflow complete. Results are in '{}'", output_dir);
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The directory and batch start time of the interrupted run in `run_dir`, once its manifest shows
+/// that it used the same knowledge base files as `kb`.
+fn resumed_run(run_dir: &Path, kb: &config::KnowledgeBase) -> Result<(String, chrono::DateTime<chrono::Utc>)> {
+    let output_dir = run_dir.to_string_lossy().into_owned();
+    let manifest = RunManifest::read(&output_dir)?
+        .with_context(|| format!("'{}' has no {}, so it cannot be resumed", output_dir, MANIFEST_FILE))?;
+    let changed = manifest.changed_knowledge_base_files(&kb.file_hashes);
+    if !changed.is_empty() {
+        anyhow::bail!(
+            "'{}' cannot be resumed: these knowledge base files changed since it started: {}",
+            output_dir,
+            changed.join(", ")
+        );
+    }
+    let start_time = chrono::DateTime::parse_from_rfc3339(&manifest.started_at)?.with_timezone(&chrono::Utc);
+    println!("Resuming the run in '{}'", output_dir);
+    Ok((output_dir, start_time))
+}
+
+/// Compares two completed runs in a run directory of its own in `out`.
+fn run_comparison(run_a: &Path, run_b: &Path, out: &Path) -> Result<()> {
+    let output_dir = create_run_dir(out, "Comparison", chrono::Utc::now())?;
+    workflow::compare_runs(&run_a.to_string_lossy(), &run_b.to_string_lossy(), &output_dir)?;
+
+    println!("\nRun comparison complete. Results are in '{}'", output_dir);
+    Ok(())
+}
+
+/// Runs the sensitivity study of `study` in a run directory of its own in `out`.
+fn run_sensitivity(
+    request: &ValorizationRequest,
+    kb: &config::KnowledgeBase,
+    plan: &jit::SelectionPlan,
+    study: &Path,
+    out: &Path,
+) -> Result<()> {
+    let study_str = fs::read_to_string(study)
+        .with_context(|| format!("Failed to read sensitivity study {:?}", study))?;
+    let study_request: workflow::SensitivityRequest = serde_yaml::from_str(&study_str)
+        .with_context(|| format!("Failed to parse sensitivity study {:?}", study))?;
+
+    let output_dir = create_run_dir(out, "Sensitivity", chrono::Utc::now())?;
+    fs::copy(study, Path::new(&output_dir).join("sensitivity.yaml"))?;
+
+    let initial_media = jit::generate_initial_media(plan, request, kb, &output_dir)?;
+    workflow::run_sensitivity_study(plan, kb, &output_dir, initial_media, request, &study_request)?;
+
+    println!("\nSensitivity study complete. Results are in '{}'", output_dir);
+    Ok(())
+}
+
+/// Runs the Monte Carlo study `args` name in a run directory of its own.
+fn run_monte_carlo(args: MonteCarloArgs) -> Result<()> {
+    let loaded = load_request(&args.inputs)?;
+    let (request, kb, plan) = prepare(&args.inputs, &loaded)?;
+    let study_str = fs::read_to_string(&args.study)
+        .with_context(|| format!("Failed to read Monte Carlo study {:?}", args.study))?;
+    let mut study_request: workflow::MonteCarloRequest = serde_yaml::from_str(&study_str)
+        .with_context(|| format!("Failed to parse Monte Carlo study {:?}", args.study))?;
+    if let Some(seed) = args.seed {
+        study_request.seed = seed;
+    }
+
+    let start_time = chrono::Utc::now();
+    let output_dir = create_run_dir(&args.inputs.out, "MonteCarlo", start_time)?;
+    fs::copy(&args.study, Path::new(&output_dir).join("monte_carlo.yaml"))?;
+    report::write_manifest(&output_dir, &request, &loaded.source.to_string(), &kb, Some(study_request.seed), start_time)?;
+
+    let initial_media = jit::generate_initial_media(&plan, &request, &kb, &output_dir)?;
+    workflow::run_monte_carlo_study(&plan, &kb, &output_dir, initial_media, &request, &study_request)?;
+
+    println!("\nMonte Carlo study complete. Results are in '{}'", output_dir);
+    Ok(())
+}
+
+/// Runs the sweep `args` name in a run directory of its own.
+fn run_sweep(args: SweepArgs) -> Result<()> {
+    let sweep_str = fs::read_to_string(&args.sweep)
+        .with_context(|| format!("Failed to read sweep {:?}", args.sweep))?;
+    let sweep_request: sweep::SweepRequest = serde_yaml::from_str(&sweep_str)
+        .with_context(|| format!("Failed to parse sweep {:?}", args.sweep))?;
+    let loaded = match &sweep_request.request {
+        Some(path) => LoadedRequest::read(RequestSource::Flag(path.clone()))
+            .with_context(|| format!("Failed to read the sweep's request {:?}", path))?,
+        None => load_request(&args.inputs)?,
+    };
+    let request_source = match &sweep_request.request {
+        Some(path) => format!("the sweep's request {}", path.display()),
+        None => loaded.source.to_string(),
+    };
+    let base_request: serde_yaml::Value = loaded
+        .format
+        .parse(&loaded.text)
+        .with_context(|| format!("Failed to parse {} from {}", loaded.format, request_source))?;
+    let kb = load_knowledge_base(&args.inputs)?;
+
+    let output_dir = create_run_dir(&args.inputs.out, "Sweep", chrono::Utc::now())?;
+    fs::copy(&args.sweep, Path::new(&output_dir).join("sweep.yaml"))?;
+    sweep::run_sweep(
+        &sweep_request,
+        base_request,
+        &request_source,
+        &kb,
+        &output_dir,
+        workflow::OutputOptions::default(),
+        args.no_plots,
+    )?;
+
+    println!("\nScenario sweep complete. Results are in '{}'", output_dir);
+    Ok(())
+}
+
+/// Installs a console logger for the core library's progress messages.
+///
+/// Defaults to the `info` level and prints bare messages so the output matches the
+/// application's own `println!` progress lines. Override with `RUST_LOG` (e.g. `RUST_LOG=warn`).
+fn init_logging() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
+        .format(|buf, record| match record.level() {
+            log::Level::Info => writeln!(buf, "{}", record.args()),
+            level => writeln!(buf, "[{}] {}", level, record.args()),
+        })
+        .init();
+}
@@ -1,476 +1,3036 @@
 //! This module is responsible for generating all visualizations from simulation log data.
 
 use anyhow::Result;
-use bioforge_core::analysis::{CogsResult, LcaResult, LogEntry};
-use bioforge_core::simulation::state::SimulationEvent;
+use bioforge_core::analysis::{
+    carbon_balance::CarbonBalance,
+    impact_category_name,
+    monte_carlo::{MonteCarloReport, METRICS},
+    performance::TargetProduct,
+    sensitivity::SensitivityReport,
+    labor_requirement_hours, material_cost, BillOfMaterials, CogsResult, LcaResult, MaterialResolver,
+};
+use bioforge_core::logger::{series_column, tick_timestamp, EventRecord, LogRecord};
+use bioforge_core::simulation::{engine::TIME_STEP_HR, state::SimulationEvent};
 use bioforge_schemas::{
+    asset::Asset,
     environment::{DissolvedComponent, DissolvedGas},
+    material::Material,
     organism_state::IndividualOrganismState,
-    process::Process,
-    rule::{Condition, Rule},
+    process::{Method, Process},
+    rule::{transition_conditions, Rule},
 };
+use chrono::{DateTime, Utc};
+use plotters::coord::types::{RangedCoordf64, RangedCoordu64};
+use plotters::element::DashedPathElement;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
 use plotters::prelude::*;
-use std::collections::HashMap;
-use csv;
-use serde_json;
-use std::f64::consts::PI;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+
+/// The file format and resolution plots are saved in, e.g. from the `output` of a [`PlotConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "format", rename_all = "snake_case")]
+pub enum PlotFormat {
+    /// Raster images. `width` and `height` are the pixels of the time-series charts; the other
+    /// charts keep their own proportions and scale with them.
+    Png { width: u32, height: u32 },
+    /// Vector images, sized like [`PlotFormat::Png`].
+    Svg { width: u32, height: u32 },
+}
+
+impl Default for PlotFormat {
+    fn default() -> Self {
+        PlotFormat::Png { width: 1024, height: 768 }
+    }
+}
+
+impl PlotFormat {
+    /// The path of the plot named `stem` in `output_dir`, with the format's extension.
+    fn path(&self, output_dir: &str, stem: &str) -> String {
+        let extension = match self {
+            PlotFormat::Png { .. } => "png",
+            PlotFormat::Svg { .. } => "svg",
+        };
+        format!("{}/{}.{}", output_dir, stem, extension)
+    }
+
+    /// Scales the size of a chart drawn at the default 1024 × 768 to the format's resolution.
+    fn scale(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let (PlotFormat::Png { width: w, height: h } | PlotFormat::Svg { width: w, height: h }) = *self;
+        (width * w / 1024, height * h / 768)
+    }
+}
+
+/// The figures a run can plot, named as in the `enabled` list of a [`PlotConfig`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Figure {
+    BiomassGrowth,
+    MediaComposition,
+    EnvironmentalParameters,
+    ProcessFlow,
+    UpstreamTimeline,
+    SpecificGrowthRate,
+    LcaImpacts,
+    SensitivityTornado,
+    MonteCarlo,
+    VolumetricProductivity,
+    CarbonBalance,
+    DissolvedGases,
+    CogsBreakdown,
+    CogsByStage,
+    BomMaterials,
+    TargetProgress,
+    Gantt,
+    SweepBiomass,
+}
+
+/// The figures [`generate_all_plots`] draws from the simulation log.
+const UPSTREAM_FIGURES: [Figure; 7] = [
+    Figure::BiomassGrowth,
+    Figure::TargetProgress,
+    Figure::SpecificGrowthRate,
+    Figure::MediaComposition,
+    Figure::EnvironmentalParameters,
+    Figure::DissolvedGases,
+    Figure::UpstreamTimeline,
+];
+
+/// The figures [`generate_downstream_plots`] draws from a downstream log.
+const DOWNSTREAM_FIGURES: [Figure; 3] =
+    [Figure::MediaComposition, Figure::EnvironmentalParameters, Figure::UpstreamTimeline];
+
+/// The colors of the series of a chart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    /// The eight colors of the media composition chart.
+    Standard,
+    /// The Okabe-Ito colors, told apart with the common forms of color blindness.
+    ColorblindSafe,
+    /// Colors of your own, as `[red, green, blue]`.
+    Custom(Vec<[u8; 3]>),
+}
+
+impl Palette {
+    fn colors(&self) -> Vec<RGBColor> {
+        match self {
+            Palette::Standard => SERIES_PALETTE.to_vec(),
+            Palette::ColorblindSafe => COLORBLIND_SAFE_PALETTE.to_vec(),
+            Palette::Custom(colors) => colors.iter().map(|[r, g, b]| RGBColor(*r, *g, *b)).collect(),
+        }
+    }
+}
+
+/// How plots are drawn and which are drawn, e.g. from the `plotting` section of a request. The
+/// default draws every figure as the charts have always been drawn.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlotConfig {
+    /// The file format and resolution of the plots.
+    pub output: PlotFormat,
+    /// The point size of the captions of the time-series charts; the captions of the other charts
+    /// scale with it.
+    pub caption_font_size: u32,
+    /// The point size of the labels drawn on the charts, such as stage names and event
+    /// annotations.
+    pub label_font_size: u32,
+    /// The colors of every multi-series chart, or `None` for each chart's own colors.
+    pub palette: Option<Palette>,
+    /// The figures to draw, or `None` for all of them. An empty list draws nothing.
+    pub enabled: Option<Vec<Figure>>,
+    /// The number of ticks the specific growth rate chart averages each point over, ending at
+    /// the point, to smooth out tick-to-tick noise. 1 draws the rates unsmoothed.
+    pub growth_rate_window_ticks: usize,
+    /// The share of both the consumed mass and the material costs below which the bill of
+    /// materials chart lumps a material into "Other".
+    pub bom_other_threshold: f64,
+}
+
+impl Default for PlotConfig {
+    fn default() -> Self {
+        PlotConfig {
+            output: PlotFormat::default(),
+            caption_font_size: 50,
+            label_font_size: 12,
+            palette: None,
+            enabled: None,
+            growth_rate_window_ticks: 1,
+            bom_other_threshold: 0.01,
+        }
+    }
+}
+
+impl PlotConfig {
+    /// Whether `figure` is drawn.
+    pub fn enables(&self, figure: Figure) -> bool {
+        self.enabled.as_ref().is_none_or(|enabled| enabled.contains(&figure))
+    }
+
+    /// Scales a caption drawn at `size` points by default to the configured caption size.
+    fn caption_size(&self, size: u32) -> u32 {
+        size * self.caption_font_size / 50
+    }
+
+    /// Scales a label drawn at `size` points by default to the configured label size.
+    fn label_size(&self, size: u32) -> u32 {
+        size * self.label_font_size / 12
+    }
+
+    /// The color and line style of the `i`th series of a chart whose own colors are `colors`:
+    /// each color of the palette solid, then dashed, then dotted, and round again, so that no two
+    /// of the first three rounds look alike.
+    fn series_style(&self, i: usize, colors: &[RGBColor]) -> (RGBColor, LineDash) {
+        let palette = self.palette.as_ref().map(Palette::colors).filter(|p| !p.is_empty());
+        let colors = palette.as_deref().unwrap_or(colors);
+        let color = colors[i % colors.len()];
+        let dash = match (i / colors.len()) % 3 {
+            0 => LineDash::Solid,
+            1 => LineDash::Dashed,
+            _ => LineDash::Dotted,
+        };
+        (color, dash)
+    }
+}
+
+/// Runs `$draw` with `$root` bound to the drawing area of the plot named `$stem` in `$output_dir`,
+/// `$size` pixels at the default resolution, in the backend the output of `$config` asks for. The
+/// backends are different types, so `$draw` is expanded once for each. Returns from the enclosing
+/// function at once if `$config` does not enable `$figure`.
+macro_rules! render {
+    ($config:expr, $figure:expr, $output_dir:expr, $stem:expr, $size:expr, |$root:ident| $draw:block) => {{
+        let config: &PlotConfig = $config;
+        if !config.enables($figure) {
+            return Ok(());
+        }
+        let format = &config.output;
+        let path = format.path($output_dir, $stem);
+        let size = format.scale($size);
+        let drawn: Result<()> = match format {
+            PlotFormat::Png { .. } => {
+                let $root = BitMapBackend::new(&path, size).into_drawing_area();
+                $draw
+            }
+            PlotFormat::Svg { .. } => {
+                let $root = SVGBackend::new(&path, size).into_drawing_area();
+                $draw
+            }
+        };
+        drawn
+    }};
+}
+
+/// The number of media components the media composition chart shows when it picks them itself.
+pub const MEDIA_COMPONENTS_SHOWN: usize = 8;
+
+/// Which media components the media composition chart shows, by name.
+#[derive(Debug, Clone, Default)]
+pub struct MediaSelection {
+    /// Show these components, in this order, instead of picking them.
+    pub components: Option<Vec<String>>,
+    /// Components always shown when they are picked, e.g. those a feed rule adds.
+    pub fed: Vec<String>,
+}
+
+impl MediaSelection {
+    /// The components to show and their peak concentrations: the chosen ones or, without a
+    /// choice, the [`MEDIA_COMPONENTS_SHOWN`] with the highest peak plus the fed ones, highest
+    /// peak first. Components that never rise above zero are left out either way.
+    fn components_in(&self, data: &[PlottingData]) -> Vec<(String, f64)> {
+        let mut peaks: HashMap<&str, f64> = HashMap::new();
+        for (name, concentration) in data.iter().flat_map(|d| &d.dissolved_components) {
+            let peak = peaks.entry(name.as_str()).or_insert(0.0);
+            *peak = peak.max(*concentration);
+        }
+        let peak_of = |name: &str| peaks.get(name).copied().unwrap_or(0.0);
+
+        let names: Vec<&str> = match &self.components {
+            Some(components) => components.iter().map(String::as_str).collect(),
+            None => {
+                let mut by_peak: Vec<&str> = peaks.keys().copied().collect();
+                by_peak.sort_by(|a, b| peak_of(b).total_cmp(&peak_of(a)).then_with(|| a.cmp(b)));
+                by_peak.truncate(MEDIA_COMPONENTS_SHOWN);
+                for fed in &self.fed {
+                    if !by_peak.contains(&fed.as_str()) {
+                        by_peak.push(fed);
+                    }
+                }
+                by_peak.sort_by(|a, b| peak_of(b).total_cmp(&peak_of(a)).then_with(|| a.cmp(b)));
+                by_peak
+            }
+        };
+        names
+            .into_iter()
+            .filter(|name| peak_of(name) > 0.0)
+            .map(|name| (name.to_string(), peak_of(name)))
+            .collect()
+    }
+}
+
+/// The saturation concentration of each gas in mg/L, keyed by gas name, from the parameters of
+/// `assets` named `<gas>_saturation` in mg/L (e.g. `oxygen_saturation` or
+/// `carbon_dioxide_saturation`). Monitored values take precedence over configured ones, and
+/// earlier assets over later ones.
+pub fn gas_saturations<'a>(assets: impl IntoIterator<Item = &'a Asset>) -> BTreeMap<String, f64> {
+    let mut saturations = BTreeMap::new();
+    for parameters in assets.into_iter().filter_map(|a| a.operational_parameters.as_ref()) {
+        let monitored = parameters.monitoring.iter().flatten().map(|v| (&v.key, v.value, &v.unit));
+        let configured = parameters.configuration_and_control.iter().flatten().map(|p| (&p.key, p.value, &p.unit));
+        for (key, value, unit) in monitored.chain(configured) {
+            if let (Some(gas), Some("mg/L")) = (key.strip_suffix("_saturation"), unit.as_deref()) {
+                saturations.entry(gas.replace('_', " ")).or_insert(value);
+            }
+        }
+    }
+    saturations
+}
+
+/// The y-axis scales the biomass growth chart is drawn with, e.g. from the `biomass_scale` of a
+/// request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BiomassScale {
+    #[default]
+    Linear,
+    /// A log10 axis, saved as `1_biomass_growth_log`.
+    Log,
+    /// Both charts, side by side in the run directory.
+    Both,
+}
+
+/// Biomass below this is drawn at it on the log-scale biomass chart, which cannot show zero.
+pub const LOG_BIOMASS_FLOOR_G: f64 = 1e-4;
+
+/// The colors of the charts with a series per organism or product.
+const ORGANISM_COLORS: [RGBColor; 6] = [RED, GREEN, BLUE, YELLOW, CYAN, MAGENTA];
+
+/// The Okabe-Ito palette.
+const COLORBLIND_SAFE_PALETTE: [RGBColor; 8] = [
+    RGBColor(230, 159, 0),
+    RGBColor(86, 180, 233),
+    RGBColor(0, 158, 115),
+    RGBColor(240, 228, 66),
+    RGBColor(0, 114, 178),
+    RGBColor(213, 94, 0),
+    RGBColor(204, 121, 167),
+    RGBColor(0, 0, 0),
+];
+
+/// Colors told apart at a glance, for charts with many series.
+const SERIES_PALETTE: [RGBColor; 8] = [
+    RGBColor(31, 119, 180),
+    RGBColor(255, 127, 14),
+    RGBColor(44, 160, 44),
+    RGBColor(214, 39, 40),
+    RGBColor(148, 103, 189),
+    RGBColor(140, 86, 75),
+    RGBColor(227, 119, 194),
+    RGBColor(127, 127, 127),
+];
+
+/// The line style of a series, varied once the palette's colors are used up.
+#[derive(Debug, Clone, Copy)]
+enum LineDash {
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineDash {
+    /// The dash length and spacing of the style, or `None` for a solid line.
+    fn pattern(self) -> Option<(u32, u32)> {
+        match self {
+            LineDash::Solid => None,
+            LineDash::Dashed => Some((10, 5)),
+            LineDash::Dotted => Some((2, 4)),
+        }
+    }
+
+    /// A 20-pixel legend line in the style at `(x, y)`.
+    fn legend(self, x: i32, y: i32, color: RGBColor) -> DashedPathElement<std::array::IntoIter<(i32, i32), 2>, u32> {
+        let (size, spacing) = self.pattern().unwrap_or((20, 0));
+        DashedPathElement::new([(x, y), (x + 20, y)], size, spacing, color.stroke_width(2))
+    }
+}
 
+/// Draws `points` as a line of `width` in `style`, labelled `label` in the legend.
+fn draw_styled_line<DB: DrawingBackend, X: Ranged, Y: Ranged>(
+    chart: &mut ChartContext<DB, Cartesian2d<X, Y>>,
+    points: Vec<(X::ValueType, Y::ValueType)>,
+    (color, dash): (RGBColor, LineDash),
+    width: u32,
+    label: &str,
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+    X::ValueType: Clone + 'static,
+    Y::ValueType: Clone + 'static,
+{
+    let style = color.stroke_width(width);
+    let series = match dash.pattern() {
+        None => chart.draw_series(LineSeries::new(points, style))?,
+        Some((size, spacing)) => chart.draw_series(DashedLineSeries::new(points, size, spacing, style))?,
+    };
+    series.label(label).legend(move |(x, y)| dash.legend(x, y, color));
+    Ok(())
+}
 
 /// A flattened structure to hold all the parsed data from a single log record for easy plotting.
 #[derive(Clone, Debug)]
 struct PlottingData {
     tick: u64,
+    /// The method the workflow was in at the tick.
+    stage_id: String,
+    timestamp: Option<DateTime<Utc>>,
     biomass: HashMap<String, f64>,
+    /// Specific growth rate of each organism in 1/h, keyed by organism id.
+    growth_rates: HashMap<String, f64>,
     media_ph: f64,
-    temperature: f64,
+    /// Temperature of the asset the record's stage runs on, or `None` if the log has none for it.
+    temperature: Option<f64>,
     dissolved_components: HashMap<String, f64>,
     dissolved_gases: HashMap<String, f64>,
     events: Vec<SimulationEvent>,
+    /// The events of `events` worth marking on a chart, at the tick they happened; consumption is
+    /// continuous and left out.
+    annotations: Vec<EventAnnotation>,
+}
+
+/// A contiguous run of ticks spent in one workflow method.
+#[derive(Clone, Debug, PartialEq)]
+struct StageSpan {
+    method_id: String,
+    /// The method's technique, or its id if the method is unknown.
+    label: String,
+    start_tick: u64,
+    end_tick: u64,
+}
+
+/// A target of the run for the target progress chart: the product and the grams requested.
+#[derive(Debug, Clone)]
+pub struct TargetGoal {
+    pub product: TargetProduct,
+    pub target_grams: f64,
+}
+
+/// How the time axis of the time-series plots is labelled.
+enum TimeAxis {
+    Hours,
+    /// Calendar dates, counted from the wall-clock time of tick zero.
+    Dates(DateTime<Utc>),
+}
+
+impl TimeAxis {
+    fn description(&self) -> &'static str {
+        match self {
+            TimeAxis::Hours => "Time (hours)",
+            TimeAxis::Dates(_) => "Time (UTC)",
+        }
+    }
+
+    fn label(&self, tick: u64) -> String {
+        match self {
+            TimeAxis::Hours => tick.to_string(),
+            TimeAxis::Dates(start) => tick_timestamp(*start, tick).format("%m-%d %H:%M").to_string(),
+        }
+    }
+}
+
+type TimeSeriesChart<'a, DB, Y = RangedCoordf64> = ChartContext<'a, DB, Cartesian2d<RangedCoordu64, Y>>;
+
+/// The kinds of simulation event marked on the charts, each in the same color on every chart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EventCategory {
+    Addition,
+    Removal,
+    Transfer,
+    StageChange,
+}
+
+impl EventCategory {
+    fn color(self) -> RGBColor {
+        match self {
+            EventCategory::Addition => RGBColor(31, 119, 180),
+            EventCategory::Removal => RGBColor(214, 39, 40),
+            EventCategory::Transfer => RGBColor(148, 103, 189),
+            EventCategory::StageChange => RGBColor(255, 127, 14),
+        }
+    }
+}
+
+/// A simulation event marked on the charts at the tick it happened.
+#[derive(Clone, Debug)]
+struct EventAnnotation {
+    tick: u64,
+    category: EventCategory,
+    label: String,
 }
 
-/// The main function to generate and save all plots for a simulation run.
+/// Labels of event annotations closer than this to the previous label are left out; their lines
+/// are still drawn.
+const MIN_ANNOTATION_SPACING_PX: i32 = 20;
+
+/// Labels longer than this are cut short so they stay inside the chart.
+const MAX_ANNOTATION_LABEL_CHARS: usize = 40;
+
+
+/// The main function to generate and save all plots for a simulation run. `events` is the run's
+/// event log, if it wrote one; it places events at the tick they happened rather than the tick
+/// whose row carries them.
+#[allow(clippy::too_many_arguments)]
 pub fn generate_all_plots(
     output_dir: &str,
-    log_path: &str,
+    config: &PlotConfig,
+    records: &[LogRecord],
+    events: Option<&[EventRecord]>,
     _cogs: &CogsResult,
     _lca: &LcaResult,
     organism_names: HashMap<String, String>,
+    max_growth_rates: &HashMap<String, f64>,
+    media_names: HashMap<String, String>,
+    media_selection: &MediaSelection,
+    methods: &[Method],
+    gas_saturations: &BTreeMap<String, f64>,
+    biomass_scale: BiomassScale,
+    targets: &[TargetGoal],
+    date_axis: bool,
 ) -> Result<()> {
+    if !UPSTREAM_FIGURES.iter().any(|figure| config.enables(*figure)) {
+        return Ok(());
+    }
     println!("[Plotting] Generating graphs from simulation data...");
 
-    let data = parse_log_file(log_path)?;
-
-    if data.is_empty() {
+    let Some(Timeline { data, stages, axis }) =
+        timeline_data(records, events, &media_names, &organism_names, methods, date_axis)?
+    else {
         println!("[Plotting] Warning: No data to plot.");
         return Ok(());
-    }
+    };
 
-    plot_biomass_growth(output_dir, &data, &organism_names)?;
-    plot_media_composition(output_dir, &data)?;
-    plot_environmental_parameters(output_dir, &data)?;
-    plot_upstream_timeline(output_dir, &data)?;
+    if biomass_scale != BiomassScale::Log {
+        plot_biomass_growth(output_dir, config, &data, &stages, &axis, &organism_names)?;
+    }
+    if biomass_scale != BiomassScale::Linear {
+        plot_biomass_growth_log(output_dir, config, &data, &stages, &axis, &organism_names)?;
+    }
+    plot_target_progress(output_dir, config, &data, &stages, &axis, targets)?;
+    plot_specific_growth_rate(output_dir, config, &data, &stages, &axis, &organism_names, max_growth_rates)?;
+    plot_media_composition(output_dir, config, &data, &stages, &axis, media_selection)?;
+    plot_environmental_parameters(output_dir, config, &data, &stages, &axis)?;
+    plot_dissolved_gases(output_dir, config, &data, &stages, &axis, gas_saturations)?;
+    plot_upstream_timeline(output_dir, config, &data, events, &stages, &axis)?;
 
     println!("[Plotting] Upstream graphs have been saved to '{}'.", output_dir);
     Ok(())
 }
 
-/// Parses the simulation log CSV file into a vector of `PlottingData` structs.
-fn parse_log_file(log_path: &str) -> Result<Vec<PlottingData>> {
-    let mut reader = csv::Reader::from_path(log_path)?;
-    let mut data = Vec::new();
+/// Generates the plots of a downstream run into `output_dir`: its media composition, the
+/// temperature of its assets, and its additions. The harvested culture no longer grows, so its
+/// biomass is left out. Downstream runs write no event log; their events are placed by the rows
+/// that carry them.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_downstream_plots(
+    output_dir: &str,
+    config: &PlotConfig,
+    records: &[LogRecord],
+    organism_names: &HashMap<String, String>,
+    media_names: &HashMap<String, String>,
+    media_selection: &MediaSelection,
+    methods: &[Method],
+    date_axis: bool,
+) -> Result<()> {
+    if !DOWNSTREAM_FIGURES.iter().any(|figure| config.enables(*figure)) {
+        return Ok(());
+    }
+    let Some(Timeline { data, stages, axis }) = timeline_data(records, None, media_names, organism_names, methods, date_axis)?
+    else {
+        return Ok(());
+    };
 
-    for result in reader.deserialize() {
-        let record: LogEntry = result?;
-        let organisms: HashMap<String, IndividualOrganismState> =
-            serde_json::from_str(&record.organisms_json)?;
-        let dissolved_components: Vec<DissolvedComponent> =
-            serde_json::from_str(&record.dissolved_components_json)?;
-        let dissolved_gases: Vec<DissolvedGas> =
-            serde_json::from_str(&record.dissolved_gases_json)?;
-        let events: Vec<SimulationEvent> =
-            serde_json::from_str(&record.events_json)?;
-
-        let biomass = organisms
-            .into_iter()
-            .map(|(id, state)| (id, state.biomass.value))
-            .collect();
+    plot_media_composition(output_dir, config, &data, &stages, &axis, media_selection)?;
+    plot_environmental_parameters(output_dir, config, &data, &stages, &axis)?;
+    plot_upstream_timeline(output_dir, config, &data, None, &stages, &axis)?;
 
-        let dissolved_components_map = dissolved_components
-            .into_iter()
-            .map(|c| (c.molecule_name, c.concentration.value))
-            .collect();
+    println!("[Plotting] Downstream graphs have been saved to '{}'.", output_dir);
+    Ok(())
+}
 
-        let dissolved_gases_map = dissolved_gases
-            .into_iter()
-            .map(|g| (g.gas_name, g.concentration.value))
-            .collect();
 
-        let asset_states: HashMap<String, serde_json::Value> =
-            serde_json::from_str(&record.asset_states_json)?;
-        let temperature = asset_states
-            .values()
-            .next()
-            .and_then(|v| v["temperature"].as_f64())
-            .unwrap_or(25.0);
+/// The rows of a log to plot, with the stages they span and the time axis to plot them on.
+struct Timeline {
+    data: Vec<PlottingData>,
+    stages: Vec<StageSpan>,
+    axis: TimeAxis,
+}
 
+/// The [`Timeline`] of `records`, or `None` when there are no rows. `events` is the run's event
+/// log, if it wrote one.
+fn timeline_data(
+    records: &[LogRecord],
+    events: Option<&[EventRecord]>,
+    media_names: &HashMap<String, String>,
+    organism_names: &HashMap<String, String>,
+    methods: &[Method],
+    date_axis: bool,
+) -> Result<Option<Timeline>> {
+    let mut data = plotting_data(records, media_names, organism_names, &stage_assets(methods))?;
+    // The event log also holds the events after the last logged row, such as the end of the
+    // workflow, so its events replace those of the rows.
+    if let Some(events) = events {
+        for d in &mut data {
+            d.annotations.clear();
+        }
+        let mut by_tick: BTreeMap<u64, Vec<SimulationEvent>> = BTreeMap::new();
+        for record in events {
+            if let Some(event) = record.to_event() {
+                by_tick.entry(record.tick).or_default().push(event);
+            }
+        }
+        for (&tick, tick_events) in &by_tick {
+            let previous_events = tick.checked_sub(1).and_then(|t| by_tick.get(&t)).map_or(&[][..], |e| &e[..]);
+            for event in tick_events {
+                if previous_events.iter().any(|previous| repeats(event, previous)) {
+                    continue;
+                }
+                let Some(annotation) = event_annotation(event, tick, media_names, organism_names) else {
+                    continue;
+                };
+                let row = data.partition_point(|d| d.tick < tick).min(data.len().saturating_sub(1));
+                if let Some(d) = data.get_mut(row) {
+                    d.annotations.push(annotation);
+                }
+            }
+        }
+    }
+
+    if data.is_empty() {
+        return Ok(None);
+    }
+
+    let stages = stage_spans(&data, methods);
+    // Dates need a logged start time; older logs and runs without one fall back to hours.
+    let axis = match data.first().and_then(|d| d.timestamp) {
+        Some(start) if date_axis => TimeAxis::Dates(start),
+        _ => TimeAxis::Hours,
+    };
+
+    Ok(Some(Timeline { data, stages, axis }))
+}
+
+/// The asset each of `methods` runs on, keyed by method id.
+fn stage_assets(methods: &[Method]) -> HashMap<String, String> {
+    methods.iter().map(|m| (m.method_id.clone(), m.required_asset_id.clone())).collect()
+}
+
+/// The file, in a run or downstream directory, that [`write_tidy_timeseries`] writes.
+pub const TIDY_TIMESERIES_FILE: &str = "timeseries_tidy.csv";
+
+/// One value of one series at one tick of the tidy time series.
+#[derive(Serialize)]
+struct TidyRow<'a> {
+    tick: u64,
+    series_type: &'static str,
+    series_name: &'a str,
+    value: f64,
+}
+
+/// Writes the series the time-series plots are drawn from to [`TIDY_TIMESERIES_FILE`] in
+/// `output_dir` as a long-format CSV of `tick, series_type, series_name, value`, for plotting in
+/// other tools, and returns its path. The series types are `biomass` of each organism in g (by
+/// id), `dissolved_component` in g/L and `dissolved_gas` in mg/L (by name), `ph` of the `media`,
+/// and `temperature` in °C of the asset each stage runs on (by asset id). Ticks at which a series
+/// has no value have no row for it.
+pub fn write_tidy_timeseries(
+    output_dir: &str,
+    records: &[LogRecord],
+    media_names: &HashMap<String, String>,
+    organism_names: &HashMap<String, String>,
+    methods: &[Method],
+) -> Result<PathBuf> {
+    let stage_assets = stage_assets(methods);
+    let data = plotting_data(records, media_names, organism_names, &stage_assets)?;
+    let path = Path::new(output_dir).join(TIDY_TIMESERIES_FILE);
+    let mut writer = csv::Writer::from_path(&path)?;
+    for d in &data {
+        let sorted = |values: &HashMap<String, f64>| -> Vec<(String, f64)> {
+            let mut values: Vec<(String, f64)> = values.iter().map(|(name, value)| (name.clone(), *value)).collect();
+            values.sort_by(|a, b| a.0.cmp(&b.0));
+            values
+        };
+        // Gases are logged in g/L and charted in mg/L.
+        let gases = d.dissolved_gases.iter().map(|(name, value)| (name.clone(), value * 1000.0)).collect();
+        let ph = [("media".to_string(), d.media_ph)];
+        let temperature = d.temperature.zip(stage_assets.get(&d.stage_id)).map(|(value, asset_id)| (asset_id.clone(), value));
+        let series: [(&'static str, Vec<(String, f64)>); 5] = [
+            ("biomass", sorted(&d.biomass)),
+            ("dissolved_component", sorted(&d.dissolved_components)),
+            ("dissolved_gas", sorted(&gases)),
+            ("ph", ph.to_vec()),
+            ("temperature", temperature.into_iter().collect()),
+        ];
+        for (series_type, values) in &series {
+            for (series_name, value) in values {
+                writer.serialize(TidyRow { tick: d.tick, series_type, series_name, value: *value })?;
+            }
+        }
+    }
+    writer.flush()?;
+    Ok(path)
+}
+
+/// Maps the series columns of a wide log back to the keys `PlottingData` uses: molecule and gas
+/// names, and organism ids.
+struct WideColumns {
+    components: HashMap<String, String>,
+    gases: HashMap<String, String>,
+    organisms: HashMap<String, String>,
+    growth_rates: HashMap<String, String>,
+}
+
+impl WideColumns {
+    fn new(media_names: &HashMap<String, String>, organism_names: &HashMap<String, String>) -> Self {
+        let columns = |prefix: &str| -> HashMap<String, String> {
+            media_names
+                .iter()
+                .map(|(id, name)| (series_column(prefix, id), name.clone()))
+                .collect()
+        };
+        Self {
+            components: columns("conc"),
+            gases: columns("gas"),
+            organisms: organism_names
+                .keys()
+                .map(|id| (series_column("biomass", id), id.clone()))
+                .collect(),
+            growth_rates: organism_names
+                .keys()
+                .map(|id| (series_column("mu", id), id.clone()))
+                .collect(),
+        }
+    }
+
+    /// Collects the series with `prefix`, keyed through `keys`. Columns of unknown series fall
+    /// back to the column name without its prefix.
+    fn collect(series: &BTreeMap<String, f64>, prefix: &str, keys: &HashMap<String, String>) -> HashMap<String, f64> {
+        series
+            .iter()
+            .filter_map(|(column, value)| {
+                let fallback = column.strip_prefix(prefix)?;
+                let key = keys.get(column).map_or(fallback, String::as_str);
+                Some((key.to_string(), *value))
+            })
+            .collect()
+    }
+}
+
+/// Parses an embedded JSON column, treating an empty cell as an empty collection.
+fn parse_json_column<T: DeserializeOwned + Default>(column: &str) -> Result<T> {
+    if column.trim().is_empty() {
+        Ok(T::default())
+    } else {
+        Ok(serde_json::from_str(column)?)
+    }
+}
+
+/// Converts the records of a simulation log into a vector of `PlottingData` structs. Wide logs
+/// are read from their per-series columns; other logs from the embedded JSON. The temperature is
+/// that of the asset each record's stage runs on, by `stage_assets` (method id to asset id).
+fn plotting_data(
+    records: &[LogRecord],
+    media_names: &HashMap<String, String>,
+    organism_names: &HashMap<String, String>,
+    stage_assets: &HashMap<String, String>,
+) -> Result<Vec<PlottingData>> {
+    let mut data = Vec::new();
+    let wide_columns = WideColumns::new(media_names, organism_names);
+
+    for record in records {
+        let events: Vec<SimulationEvent> = parse_json_column(&record.events_json)?;
+        let stage_asset = stage_assets.get(&record.stage_id);
+
+        let (biomass, growth_rates, dissolved_components_map, dissolved_gases_map, temperature) = if record.series.is_empty() {
+            let organisms: HashMap<String, IndividualOrganismState> =
+                parse_json_column(&record.organisms_json)?;
+            let dissolved_components: Vec<DissolvedComponent> =
+                parse_json_column(&record.dissolved_components_json)?;
+            let dissolved_gases: Vec<DissolvedGas> =
+                parse_json_column(&record.dissolved_gases_json)?;
+            let asset_states: HashMap<String, serde_json::Value> =
+                parse_json_column(&record.asset_states_json)?;
+
+            (
+                organisms
+                    .into_iter()
+                    .map(|(id, state)| (id, state.biomass.value))
+                    .collect(),
+                parse_json_column(&record.growth_rates_json)?,
+                dissolved_components
+                    .into_iter()
+                    .map(|c| (c.molecule_name, c.concentration.value))
+                    .collect(),
+                dissolved_gases
+                    .into_iter()
+                    .map(|g| (g.gas_name, g.concentration.value))
+                    .collect(),
+                stage_asset.and_then(|id| asset_states.get(id)).and_then(|v| v["temperature"].as_f64()),
+            )
+        } else {
+            (
+                WideColumns::collect(&record.series, "biomass_", &wide_columns.organisms),
+                WideColumns::collect(&record.series, "mu_", &wide_columns.growth_rates),
+                WideColumns::collect(&record.series, "conc_", &wide_columns.components),
+                WideColumns::collect(&record.series, "gas_", &wide_columns.gases),
+                stage_asset
+                    .and_then(|id| record.series.get(&format!("{}_temperature", series_column("asset", id))))
+                    .copied(),
+            )
+        };
+
+        let timestamp = record
+            .timestamp
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc));
+
+        // An event that repeats one of the previous record, as a continuous feed does every
+        // tick, is marked only where it starts.
+        let previous_events = data.last().map_or(&[][..], |d: &PlottingData| &d.events[..]);
+        let annotations = events
+            .iter()
+            .filter(|event| !previous_events.iter().any(|previous| repeats(event, previous)))
+            .filter_map(|event| event_annotation(event, event.tick(record.tick), media_names, organism_names))
+            .collect();
         data.push(PlottingData {
             tick: record.tick,
+            stage_id: record.stage_id.clone(),
+            timestamp,
             biomass,
+            growth_rates,
             media_ph: record.media_ph,
             temperature,
             dissolved_components: dissolved_components_map,
             dissolved_gases: dissolved_gases_map,
             events,
+            annotations,
         });
     }
 
     Ok(data)
 }
 
-/// Generates a stacked area chart of biomass growth for each organism over time.
+/// Whether `event` adds, removes, or transfers the same material, or between the same assets, as
+/// `previous`.
+fn repeats(event: &SimulationEvent, previous: &SimulationEvent) -> bool {
+    match (event, previous) {
+        (SimulationEvent::MaterialAdded { id, .. }, SimulationEvent::MaterialAdded { id: previous_id, .. })
+        | (SimulationEvent::MaterialRemoved { id, .. }, SimulationEvent::MaterialRemoved { id: previous_id, .. }) => {
+            id == previous_id
+        }
+        (
+            SimulationEvent::MaterialTransferred { from_asset_id, to_asset_id, .. },
+            SimulationEvent::MaterialTransferred { from_asset_id: previous_from, to_asset_id: previous_to, .. },
+        ) => from_asset_id == previous_from && to_asset_id == previous_to,
+        _ => false,
+    }
+}
+
+/// The annotation of `event`, which happened at `tick`, or `None` for consumption. Materials and
+/// organisms are named by `media_names` and `organism_names` where they have a name.
+fn event_annotation(
+    event: &SimulationEvent,
+    tick: u64,
+    media_names: &HashMap<String, String>,
+    organism_names: &HashMap<String, String>,
+) -> Option<EventAnnotation> {
+    let name = |id: &String| media_names.get(id).or_else(|| organism_names.get(id)).unwrap_or(id).clone();
+    let (category, label) = match event {
+        SimulationEvent::MaterialConsumed { .. } => return None,
+        SimulationEvent::MaterialAdded { id, amount } => (EventCategory::Addition, format!("+{:.0} g {}", amount, name(id))),
+        SimulationEvent::MaterialRemoved { id, amount } => (EventCategory::Removal, format!("-{:.0} g {}", amount, name(id))),
+        SimulationEvent::MaterialTransferred { from_asset_id, to_asset_id, volume_l } => (
+            EventCategory::Transfer,
+            format!("{:.1} L {} → {}", volume_l, from_asset_id, to_asset_id),
+        ),
+        SimulationEvent::StageChanged { to_method_id, .. } => (
+            EventCategory::StageChange,
+            match to_method_id {
+                Some(method_id) => format!("stage → {}", method_id),
+                None => "workflow complete".to_string(),
+            },
+        ),
+    };
+    Some(EventAnnotation { tick, category, label })
+}
+
+/// Marks every event annotation of `data` on a time-series chart: a dashed vertical line in the
+/// color of its category, labelled with rotated text up from its foot. Labels within
+/// [`MIN_ANNOTATION_SPACING_PX`] of the previous label are skipped so they do not overlap.
+fn draw_event_annotations<DB: DrawingBackend, Y: Ranged<ValueType = f64>>(
+    chart: &mut TimeSeriesChart<DB, Y>,
+    config: &PlotConfig,
+    data: &[PlottingData],
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    let Range { start: y_min, end: y_max } = chart.y_range();
+    let mut annotations: Vec<&EventAnnotation> = data.iter().flat_map(|d| &d.annotations).collect();
+    annotations.sort_by_key(|a| a.tick);
+
+    let font_size = config.label_size(12) as i32;
+    let right_edge = chart.plotting_area().get_pixel_range().0.end;
+    let mut last_label_x: Option<i32> = None;
+    for annotation in annotations {
+        let color = annotation.category.color();
+        chart.draw_series(DashedLineSeries::new(
+            [(annotation.tick, y_min), (annotation.tick, y_max)],
+            6,
+            4,
+            color.stroke_width(1),
+        ))?;
+
+        let x = chart.backend_coord(&(annotation.tick, y_min)).0;
+        if last_label_x.is_some_and(|last| x - last < MIN_ANNOTATION_SPACING_PX) {
+            continue;
+        }
+        last_label_x = Some(x);
+        let label: String = annotation.label.chars().take(MAX_ANNOTATION_LABEL_CHARS).collect();
+        // The label runs up the right of its line, or the left where it would leave the chart.
+        let offset = if x + font_size + 2 > right_edge { -font_size - 2 } else { 2 };
+        chart.draw_series(std::iter::once(
+            EmptyElement::at((annotation.tick, y_min))
+                + Text::new(
+                    label,
+                    (offset, -2),
+                    ("sans-serif", font_size).into_font().transform(FontTransform::Rotate270).color(&color),
+                ),
+        ))?;
+    }
+    Ok(())
+}
+
+/// Rebuilds the sequence of workflow stages from the `StageChanged` events in the log or, in logs
+/// without them, from the rows where the stage changes. Each stage ends at the tick the next one
+/// starts, and the last at the last row. A run that never leaves its first stage has nothing to
+/// tell apart and gets no stages.
+fn stage_spans(data: &[PlottingData], methods: &[Method]) -> Vec<StageSpan> {
+    let max_tick = data.last().map_or(0, |d| d.tick);
+    let label = |method_id: &str| {
+        methods.iter().find(|m| m.method_id == method_id).map_or(method_id.to_string(), |m| m.technique.clone())
+    };
+    let mut starts: Vec<(Option<&str>, u64)> = data
+        .iter()
+        .flat_map(|d| &d.events)
+        .filter_map(|event| match event {
+            SimulationEvent::StageChanged { to_method_id, tick, .. } => Some((to_method_id.as_deref(), *tick)),
+            _ => None,
+        })
+        .collect();
+    if starts.is_empty() {
+        for d in data.iter().filter(|d| !d.stage_id.is_empty()) {
+            if starts.last().is_none_or(|(stage_id, _)| *stage_id != Some(d.stage_id.as_str())) {
+                starts.push((Some(d.stage_id.as_str()), d.tick));
+            }
+        }
+    }
+
+    let mut spans: Vec<StageSpan> = Vec::new();
+    for (method_id, tick) in starts {
+        if let Some(open) = spans.last_mut() {
+            open.end_tick = open.end_tick.min(tick);
+        }
+        if let Some(method_id) = method_id {
+            spans.push(StageSpan {
+                method_id: method_id.to_string(),
+                label: label(method_id),
+                start_tick: tick,
+                end_tick: max_tick,
+            });
+        }
+    }
+    if spans.len() < 2 {
+        spans.clear();
+    }
+    spans
+}
+
+/// Shades alternating background bands behind a time-series chart, one per workflow stage, each
+/// labelled at the top with the stage's technique.
+fn draw_stage_shading<DB: DrawingBackend, Y: Ranged<ValueType = f64>>(
+    chart: &mut TimeSeriesChart<DB, Y>,
+    config: &PlotConfig,
+    stages: &[StageSpan],
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    let Range { start: y_min, end: y_max } = chart.y_range();
+    let shades = [RGBColor(225, 235, 250), RGBColor(245, 240, 225)];
+
+    chart.draw_series(stages.iter().enumerate().map(|(i, stage)| {
+        Rectangle::new(
+            [(stage.start_tick, y_min), (stage.end_tick, y_max)],
+            shades[i % shades.len()].mix(0.5).filled(),
+        )
+    }))?;
+    chart.draw_series(stages.iter().map(|stage| {
+        Text::new(
+            stage.label.clone(),
+            (stage.start_tick, y_max),
+            ("sans-serif", config.label_size(12)).into_font().color(&BLACK.mix(0.6)),
+        )
+    }))?;
+    Ok(())
+}
+
+/// Generates a line chart of the biomass of each organism over time.
 fn plot_biomass_growth(
     output_dir: &str,
+    config: &PlotConfig,
     data: &[PlottingData],
+    stages: &[StageSpan],
+    axis: &TimeAxis,
     organism_names: &HashMap<String, String>,
 ) -> Result<()> {
-    let path = format!("{}/1_biomass_growth.png", output_dir);
-    let root = BitMapBackend::new(&path, (1024, 768)).into_drawing_area();
-    root.fill(&WHITE)?;
+    render!(config, Figure::BiomassGrowth, output_dir, "1_biomass_growth", (1024, 768), |root| {
+        root.fill(&WHITE)?;
+
+        let max_tick = data.last().map_or(1, |d| d.tick);
+        let max_biomass: f64 = data
+            .iter()
+            .map(|d| d.biomass.values().sum::<f64>())
+            .fold(0.0, f64::max);
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Biomass Growth Over Time", ("sans-serif", config.caption_size(50)).into_font())
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(0u64..max_tick, 0f64..max_biomass * 1.1)?;
+
+        chart.configure_mesh()
+            .x_desc(axis.description())
+            .x_label_formatter(&|tick| axis.label(*tick))
+            .y_desc("Biomass (g)")
+            .draw()?;
+
+        draw_stage_shading(&mut chart, config, stages)?;
+        draw_biomass_series(&mut chart, config, data, organism_names, 0.0)?;
+        draw_event_annotations(&mut chart, config, data)?;
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+        Ok(())
+    })
+}
 
-    let max_tick = data.last().map_or(1, |d| d.tick);
-    let max_biomass: f64 = data
-        .iter()
-        .map(|d| d.biomass.values().sum::<f64>())
-        .fold(0.0, f64::max);
-
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Biomass Growth Over Time", ("sans-serif", 50).into_font())
-        .margin(10)
-        .x_label_area_size(30)
-        .y_label_area_size(50)
-        .build_cartesian_2d(0u64..max_tick, 0f64..max_biomass * 1.1)?;
-
-    chart.configure_mesh()
-        .x_desc("Time (hours)")
-        .y_desc("Biomass (g)")
-        .draw()?;
-
-    let colors = [RED, GREEN, BLUE, YELLOW, CYAN, MAGENTA];
-    
+/// The total biomass, in grams, of every organism of a simulation log at each of its hours.
+pub fn total_biomass(records: &[LogRecord]) -> Result<Vec<(f64, f64)>> {
+    let data = plotting_data(records, &HashMap::new(), &HashMap::new(), &HashMap::new())?;
+    Ok(data.iter().map(|d| (d.tick as f64 * TIME_STEP_HR, d.biomass.values().sum())).collect())
+}
+
+/// The grams of `product` held in its organism's biomass at each tick of `data`, as the run counts
+/// them against the target.
+fn product_progress(data: &[PlottingData], product: &TargetProduct) -> Vec<(u64, f64)> {
+    data.iter()
+        .map(|d| {
+            let biomass = d.biomass.get(&product.organism_id).copied().unwrap_or(0.0);
+            (d.tick, biomass * product.content_mg_g / 1000.0) // mg to g
+        })
+        .collect()
+}
+
+/// The first tick of `progress` at which `target_grams` was reached, or `None` if it never was.
+fn target_met_at(progress: &[(u64, f64)], target_grams: f64) -> Option<u64> {
+    progress.iter().find(|(_, grams)| *grams >= target_grams).map(|(tick, _)| *tick)
+}
+
+/// Generates a chart of the grams produced of each target over time against the grams requested,
+/// one panel per target so that each has its own scale, marking the tick the target was met.
+fn plot_target_progress(
+    output_dir: &str,
+    config: &PlotConfig,
+    data: &[PlottingData],
+    stages: &[StageSpan],
+    axis: &TimeAxis,
+    targets: &[TargetGoal],
+) -> Result<()> {
+    if targets.is_empty() {
+        return Ok(());
+    }
+
+    render!(config, Figure::TargetProgress, output_dir, "16_target_progress", (1024, 100 + 300 * targets.len() as u32), |root| {
+        root.fill(&WHITE)?;
+        let root = root.titled("Progress Toward Targets", ("sans-serif", config.caption_size(50)).into_font())?;
+        let max_tick = data.last().map_or(1, |d| d.tick);
+
+        for (i, (panel, target)) in root.split_evenly((targets.len(), 1)).iter().zip(targets).enumerate() {
+            let progress = product_progress(data, &target.product);
+            let met_at = target_met_at(&progress, target.target_grams);
+            let max_grams = progress.iter().map(|(_, grams)| *grams).fold(target.target_grams, f64::max);
+            let status = match met_at {
+                Some(tick) if matches!(axis, TimeAxis::Hours) => format!("met at {} h", tick),
+                Some(tick) => format!("met at {}", axis.label(tick)),
+                None => format!("not met, {:.2} g at the end", progress.last().map_or(0.0, |(_, grams)| *grams)),
+            };
+
+            let mut chart = ChartBuilder::on(panel)
+                .caption(
+                    format!("{}: {:.2} g requested, {}", target.product.molecule_name, target.target_grams, status),
+                    ("sans-serif", config.caption_size(30)).into_font(),
+                )
+                .margin(10)
+                .x_label_area_size(30)
+                .y_label_area_size(60)
+                .build_cartesian_2d(0u64..max_tick, 0f64..max_grams * 1.1)?;
+
+            chart.configure_mesh()
+                .x_desc(axis.description())
+                .x_label_formatter(&|tick| axis.label(*tick))
+                .y_desc("Produced (g)")
+                .draw()?;
+
+            draw_stage_shading(&mut chart, config, stages)?;
+            draw_styled_line(&mut chart, progress, config.series_style(i, &ORGANISM_COLORS), 2, "Produced")?;
+            let target_grams = target.target_grams;
+            chart
+                .draw_series(DashedLineSeries::new([(0, target_grams), (max_tick, target_grams)], 8, 6, BLACK.stroke_width(2)))?
+                .label("Target")
+                .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLACK.stroke_width(2)));
+            if let Some(tick) = met_at {
+                chart.draw_series(std::iter::once(Circle::new((tick, target_grams), 6, BLACK.filled())))?;
+            }
+
+            chart
+                .configure_series_labels()
+                .position(SeriesLabelPosition::LowerRight)
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .draw()?;
+        }
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Like [`plot_biomass_growth`] on a log10 axis, which shows early exponential growth. Biomass
+/// below [`LOG_BIOMASS_FLOOR_G`], such as that of an organism not yet inoculated, is drawn at the
+/// floor, and a note on the chart says so.
+fn plot_biomass_growth_log(
+    output_dir: &str,
+    config: &PlotConfig,
+    data: &[PlottingData],
+    stages: &[StageSpan],
+    axis: &TimeAxis,
+    organism_names: &HashMap<String, String>,
+) -> Result<()> {
+    render!(config, Figure::BiomassGrowth, output_dir, "1_biomass_growth_log", (1024, 768), |root| {
+        root.fill(&WHITE)?;
+
+        let max_tick = data.last().map_or(1, |d| d.tick);
+        let biomass = || data.iter().flat_map(|d| organism_names.keys().map(|id| d.biomass.get(id).copied().unwrap_or(0.0)));
+        let max_biomass = biomass().fold(0.0, f64::max);
+        let min_biomass = biomass().fold(f64::INFINITY, f64::min);
+        // Whole decades, from that of the smallest value shown to that above the largest.
+        let y_min = 10f64.powf(min_biomass.max(LOG_BIOMASS_FLOOR_G).log10().floor());
+        let y_max = 10f64.powf((max_biomass * 1.1).log10().ceil()).max(y_min * 10.0);
+        // The axis places its labels from the decade its start rounds up to, and a whole decade
+        // such as 0.001 can round up to the next one, so the axis starts a hair below it.
+        let axis_min = y_min * (1.0 - 1e-9);
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Biomass Growth Over Time (log scale)", ("sans-serif", config.caption_size(50)).into_font())
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(60)
+            .build_cartesian_2d(0u64..max_tick, (axis_min..y_max).log_scale())?;
+
+        chart.configure_mesh()
+            .x_desc(axis.description())
+            .x_label_formatter(&|tick| axis.label(*tick))
+            .y_label_formatter(&|value| log_axis_label(*value))
+            .y_desc("Biomass (g, log scale)")
+            .draw()?;
+
+        draw_stage_shading(&mut chart, config, stages)?;
+        draw_biomass_series(&mut chart, config, data, organism_names, y_min)?;
+        draw_event_annotations(&mut chart, config, data)?;
+
+        if min_biomass < y_min {
+            let (x_range, y_range) = chart.plotting_area().get_pixel_range();
+            let style = ("sans-serif", config.label_size(14))
+                .into_font()
+                .color(&BLACK.mix(0.7))
+                .pos(Pos::new(HPos::Right, VPos::Top));
+            root.draw(&Text::new(
+                format!("Biomass below {} g is drawn at {} g", log_axis_label(y_min), log_axis_label(y_min)),
+                (x_range.end - 8, y_range.start + 20),
+                style,
+            ))?;
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Draws the biomass of each organism, in the same colors on the linear and log charts. Values
+/// below `floor` are drawn at it.
+fn draw_biomass_series<DB: DrawingBackend, Y: Ranged<ValueType = f64>>(
+    chart: &mut TimeSeriesChart<DB, Y>,
+    config: &PlotConfig,
+    data: &[PlottingData],
+    organism_names: &HashMap<String, String>,
+    floor: f64,
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
     let mut sorted_organism_ids: Vec<_> = organism_names.keys().cloned().collect();
     sorted_organism_ids.sort();
 
     for (i, org_id) in sorted_organism_ids.iter().enumerate() {
         let org_name = organism_names.get(org_id).unwrap();
-        let color = colors[i % colors.len()].clone();
-        
-        chart.draw_series(LineSeries::new(
-            data.iter().map(|d| (d.tick, d.biomass.get(org_id).cloned().unwrap_or(0.0))),
-            color.stroke_width(2),
-        ))?
-            .label(org_name)
-            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.filled()));
-    }
-
-    chart
-        .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
-        .draw()?;
-    root.present()?;
+        let points = data.iter().map(|d| (d.tick, d.biomass.get(org_id).cloned().unwrap_or(0.0).max(floor))).collect();
+        draw_styled_line(chart, points, config.series_style(i, &ORGANISM_COLORS), 2, org_name)?;
+    }
     Ok(())
 }
 
-/// Generates a stacked area chart of key media components over time.
-fn plot_media_composition(output_dir: &str, data: &[PlottingData]) -> Result<()> {
-    let path = format!("{}/2_media_composition.png", output_dir);
-    let root = BitMapBackend::new(&path, (1024, 768)).into_drawing_area();
-    root.fill(&WHITE)?;
+/// Labels a value of a log-scale axis, e.g. `0.001` or `100`, with as many decimals as its
+/// magnitude needs.
+fn log_axis_label(value: f64) -> String {
+    let decimals = (-value.log10().floor()).max(0.0) as usize;
+    format!("{:.*}", decimals, value)
+}
 
-    let max_tick = data.last().map_or(1, |d| d.tick);
-    
-    let components_to_plot = ["D-glucose", "sucrose", "acetate"];
-    let max_concentration: f64 = data
+/// Plots the specific growth rate μ of each organism over time, as computed by the engine or,
+/// for logs without rates, from its biomass (see [`growth_rate_series`]), averaged over the
+/// `growth_rate_window_ticks` of `config`. The maximum rate of each organism in
+/// `max_growth_rates`, keyed by organism id, is drawn as a dashed line of the same color.
+fn plot_specific_growth_rate(
+    output_dir: &str,
+    config: &PlotConfig,
+    data: &[PlottingData],
+    stages: &[StageSpan],
+    axis: &TimeAxis,
+    organism_names: &HashMap<String, String>,
+    max_growth_rates: &HashMap<String, f64>,
+) -> Result<()> {
+    let mut sorted_organism_ids: Vec<_> = organism_names.keys().cloned().collect();
+    sorted_organism_ids.sort();
+    let series: Vec<Vec<(u64, f64)>> = sorted_organism_ids
         .iter()
-        .map(|d| {
-            components_to_plot.iter().map(|&name| d.dissolved_components.get(name).cloned().unwrap_or(0.0)).sum::<f64>()
+        .map(|org_id| rolling_mean(&growth_rate_series(data, org_id), config.growth_rate_window_ticks))
+        .collect();
+
+    render!(config, Figure::SpecificGrowthRate, output_dir, "6_specific_growth_rate", (1024, 768), |root| {
+        root.fill(&WHITE)?;
+
+        let max_tick = data.last().map_or(1, |d| d.tick);
+        let max_rate = series
+            .iter()
+            .flatten()
+            .map(|(_, rate)| *rate)
+            .chain(sorted_organism_ids.iter().filter_map(|id| max_growth_rates.get(id).copied()))
+            .fold(0.0, f64::max);
+        let min_rate = series.iter().flatten().map(|(_, rate)| *rate).fold(0.0, f64::min);
+        // Keep a visible range for runs in which nothing grew.
+        let y_max = if max_rate > 0.0 { max_rate * 1.1 } else { 1.0 };
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Specific Growth Rate Over Time", ("sans-serif", config.caption_size(50)).into_font())
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(0u64..max_tick, min_rate * 1.1..y_max)?;
+
+        chart.configure_mesh()
+            .x_desc(axis.description())
+            .x_label_formatter(&|tick| axis.label(*tick))
+            .y_desc("μ (1/h)")
+            .draw()?;
+
+        draw_stage_shading(&mut chart, config, stages)?;
+
+        for (i, (org_id, points)) in sorted_organism_ids.iter().zip(&series).enumerate() {
+            let org_name = organism_names.get(org_id).unwrap();
+            let style = config.series_style(i, &ORGANISM_COLORS);
+            draw_styled_line(&mut chart, points.clone(), style, 2, org_name)?;
+            if let Some(max_rate) = max_growth_rates.get(org_id) {
+                let line = vec![(0, *max_rate), (max_tick, *max_rate)];
+                draw_styled_line(&mut chart, line, (style.0, LineDash::Dashed), 2, &format!("{} max", org_name))?;
+            }
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// The specific growth rate of `organism_id` at each logged tick. Logs with rates give them as
+/// logged, starting at the first tick since the initial state has none. Older logs get them from
+/// the change in biomass between consecutive rows, ln(X₁/X₀)/Δt; rows before the organism is
+/// inoculated, or in which either biomass is zero, have no rate, so the series of an organism
+/// inoculated mid-run starts late.
+fn growth_rate_series(data: &[PlottingData], organism_id: &str) -> Vec<(u64, f64)> {
+    if data.iter().any(|d| d.growth_rates.contains_key(organism_id)) {
+        return data.iter().filter_map(|d| d.growth_rates.get(organism_id).map(|rate| (d.tick, *rate))).collect();
+    }
+    data.windows(2)
+        .filter_map(|rows| {
+            let before = *rows[0].biomass.get(organism_id)?;
+            let after = *rows[1].biomass.get(organism_id)?;
+            let hours = rows[1].tick.checked_sub(rows[0].tick)? as f64 * TIME_STEP_HR;
+            (before > 0.0 && after > 0.0 && hours > 0.0).then(|| (rows[1].tick, (after / before).ln() / hours))
         })
-        .fold(0.0, f64::max);
+        .collect()
+}
 
+/// The mean of each point of `points` and the points before it within `window` ticks.
+fn rolling_mean(points: &[(u64, f64)], window: usize) -> Vec<(u64, f64)> {
+    let window = window.max(1) as u64;
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, (tick, _))| {
+            let in_window: Vec<f64> = points[..=i]
+                .iter()
+                .rev()
+                .take_while(|(t, _)| tick - t < window)
+                .map(|(_, rate)| *rate)
+                .collect();
+            (*tick, in_window.iter().sum::<f64>() / in_window.len() as f64)
+        })
+        .collect()
+}
 
-    let mut chart = ChartBuilder::on(&root)
-        .caption(
-            "Media Composition Over Time",
-            ("sans-serif", 50).into_font(),
-        )
-        .margin(10)
-        .x_label_area_size(30)
-        .y_label_area_size(50)
-        .build_cartesian_2d(0u64..max_tick, 0f64..max_concentration * 1.1)?;
+/// Plots the concentration of the media components `selection` picks over time.
+fn plot_media_composition(
+    output_dir: &str,
+    config: &PlotConfig,
+    data: &[PlottingData],
+    stages: &[StageSpan],
+    axis: &TimeAxis,
+    selection: &MediaSelection,
+) -> Result<()> {
+    let components = selection.components_in(data);
+    render!(config, Figure::MediaComposition, output_dir, "2_media_composition", (1024, 768), |root| {
+        root.fill(&WHITE)?;
+
+        let max_tick = data.last().map_or(1, |d| d.tick);
+        let max_concentration = components.iter().map(|(_, peak)| *peak).fold(0.0, f64::max);
+        // Keep a visible range for runs whose media held none of the components.
+        let y_max = if max_concentration > 0.0 { max_concentration * 1.1 } else { 1.0 };
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption(
+                "Media Composition Over Time",
+                ("sans-serif", config.caption_size(50)).into_font(),
+            )
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(0u64..max_tick, 0f64..y_max)?;
+
+        chart
+            .configure_mesh()
+            .x_desc(axis.description())
+            .x_label_formatter(&|tick| axis.label(*tick))
+            .y_desc("Concentration (g/L)")
+            .draw()?;
+
+        draw_stage_shading(&mut chart, config, stages)?;
+
+        for (i, (component_name, _)) in components.iter().enumerate() {
+            let points = data
+                .iter()
+                .map(|d| (d.tick, d.dissolved_components.get(component_name).cloned().unwrap_or(0.0)))
+                .collect();
+            draw_styled_line(&mut chart, points, config.series_style(i, &SERIES_PALETTE), 2, component_name)?;
+        }
 
-    chart
-        .configure_mesh()
-        .x_desc("Time (hours)")
-        .y_desc("Concentration (g/L)")
-        .draw()?;
+        draw_event_annotations(&mut chart, config, data)?;
 
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Generates line charts for key environmental parameters over time.
+fn plot_environmental_parameters(
+    output_dir: &str,
+    config: &PlotConfig,
+    data: &[PlottingData],
+    stages: &[StageSpan],
+    axis: &TimeAxis,
+) -> Result<()> {
+    render!(config, Figure::EnvironmentalParameters, output_dir, "3_environmental_parameters", (1024, 768), |root| {
+        root.fill(&WHITE)?;
+
+        let max_tick = data.last().map_or(1, |d| d.tick);
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption(
+                "Environmental Parameters Over Time",
+                ("sans-serif", config.caption_size(50)).into_font(),
+            )
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(0u64..max_tick, 0f64..100f64)?;
+
+        chart
+            .configure_mesh()
+            .x_desc(axis.description())
+            .x_label_formatter(&|tick| axis.label(*tick))
+            .y_desc("Value")
+            .draw()?;
+
+        draw_stage_shading(&mut chart, config, stages)?;
+
+        chart
+            .draw_series(LineSeries::new(
+                data.iter().map(|d| (d.tick, d.media_ph)),
+                RED.stroke_width(3),
+            ))?
+            .label("pH")
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED.filled()));
     
-    let colors = [BLUE, RED, GREEN, YELLOW];
-
-    for (i, &component_name) in components_to_plot.iter().enumerate() {
-        let color = colors[i % colors.len()].clone();
-        
-        chart.draw_series(LineSeries::new(
-            data.iter().map(|d| (d.tick, d.dissolved_components.get(component_name).cloned().unwrap_or(0.0))),
-            color.stroke_width(2),
-        ))?
-            .label(component_name)
-            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color.filled()));
-    }
-
-
-    chart
-        .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
-        .draw()?;
-    root.present()?;
+        // Only logged series are drawn. The log holds no light intensity, so PAR is not shown;
+        // dissolved gases have a chart of their own.
+        chart
+            .draw_series(LineSeries::new(
+                data.iter().filter_map(|d| Some((d.tick, d.temperature?))),
+                BLUE.stroke_width(3),
+            ))?
+            .label("Temperature (°C)")
+            .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE.filled()));
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+        Ok(())
+    })
+}
+
+
+/// Plots the concentration of each dissolved gas in mg/L, with its saturation concentration as a
+/// dashed line of the same color where `saturations` (keyed by gas name) has one. Runs that log
+/// no gases get no chart. No command changes aeration or agitation yet, so there are no such
+/// changes to mark.
+fn plot_dissolved_gases(
+    output_dir: &str,
+    config: &PlotConfig,
+    data: &[PlottingData],
+    stages: &[StageSpan],
+    axis: &TimeAxis,
+    saturations: &BTreeMap<String, f64>,
+) -> Result<()> {
+    let gases: Vec<&String> = data
+        .iter()
+        .flat_map(|d| d.dissolved_gases.keys())
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if gases.is_empty() {
+        return Ok(());
+    }
+    // Gases are logged in g/L.
+    let mg_per_l = |d: &PlottingData, gas: &str| d.dissolved_gases.get(gas).map(|g| g * 1000.0);
+
+    render!(config, Figure::DissolvedGases, output_dir, "12_dissolved_gases", (1024, 768), |root| {
+        root.fill(&WHITE)?;
+
+        let max_tick = data.last().map_or(1, |d| d.tick);
+        let max_concentration = data
+            .iter()
+            .flat_map(|d| gases.iter().filter_map(move |gas| mg_per_l(d, gas)))
+            .chain(gases.iter().filter_map(|gas| saturations.get(*gas).copied()))
+            .fold(0.0, f64::max);
+        let y_max = if max_concentration > 0.0 { max_concentration * 1.1 } else { 1.0 };
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Dissolved Gases Over Time", ("sans-serif", config.caption_size(50)).into_font())
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(0u64..max_tick, 0f64..y_max)?;
+
+        chart
+            .configure_mesh()
+            .x_desc(axis.description())
+            .x_label_formatter(&|tick| axis.label(*tick))
+            .y_desc("Concentration (mg/L)")
+            .draw()?;
+
+        draw_stage_shading(&mut chart, config, stages)?;
+
+        for (i, gas) in gases.iter().enumerate() {
+            // Saturation lines are dashed, so every gas is drawn solid in a color of its own.
+            let (color, _) = config.series_style(i, &SERIES_PALETTE);
+            let points = data.iter().filter_map(|d| Some((d.tick, mg_per_l(d, gas)?))).collect();
+            draw_styled_line(&mut chart, points, (color, LineDash::Solid), 3, &format!("{} (mg/L)", gas))?;
+            if let (Some(saturation), Some((size, spacing))) = (saturations.get(*gas), LineDash::Dashed.pattern()) {
+                chart
+                    .draw_series(DashedLineSeries::new(
+                        [(0, *saturation), (max_tick, *saturation)],
+                        size,
+                        spacing,
+                        color.stroke_width(2),
+                    ))?
+                    .label(format!("{} saturation", gas))
+                    .legend(move |(x, y)| LineDash::Dashed.legend(x, y, color));
+            }
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Plots the total of each LCA impact category as a horizontal bar. The categories have different
+/// units, so the value axis is logarithmic; categories with no positive total are left out.
+pub fn plot_lca_impacts(output_dir: &str, config: &PlotConfig, lca: &LcaResult) -> Result<()> {
+    let impacts: Vec<(&str, f64, &str)> = lca.sorted_impacts().into_iter().filter(|(_, value, _)| *value > 0.0).collect();
+    if impacts.is_empty() {
+        return Ok(());
+    }
+
+    render!(config, Figure::LcaImpacts, output_dir, "7_lca_impacts", (1024, 160 + 60 * impacts.len() as u32), |root| {
+        root.fill(&WHITE)?;
+
+        let min_value = impacts.iter().map(|(_, value, _)| *value).fold(f64::INFINITY, f64::min);
+        let max_value = impacts.iter().map(|(_, value, _)| *value).fold(0.0, f64::max);
+        let x_min = 10f64.powf(min_value.log10().floor());
+        // A decade of headroom keeps the value labels of the longest bars inside the chart.
+        let x_max = 10f64.powf((max_value * 10.0).log10().ceil());
+        // The first category goes at the top.
+        let row = |i: usize| impacts.len() - 1 - i;
+        let labels: Vec<String> = impacts
+            .iter()
+            .rev()
+            .map(|(metric, _, unit)| format!("{} ({})", impact_category_name(metric), unit))
+            .collect();
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Life Cycle Impacts", ("sans-serif", config.caption_size(40)).into_font())
+            .margin(10)
+            .margin_right(40)
+            .x_label_area_size(40)
+            .y_label_area_size(300)
+            .build_cartesian_2d((x_min..x_max).log_scale(), (0..impacts.len() - 1).into_segmented())?;
+
+        chart.configure_mesh()
+            .disable_y_mesh()
+            .y_labels(impacts.len())
+            .y_label_formatter(&|segment| match segment {
+                SegmentValue::CenterOf(i) => labels.get(*i).cloned().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .x_label_formatter(&|value| format!("{}", value))
+            .x_desc("Total (log scale)")
+            .draw()?;
+
+        chart.draw_series(impacts.iter().enumerate().map(|(i, (_, value, _))| {
+            let mut bar = Rectangle::new(
+                [(x_min, SegmentValue::Exact(row(i))), (*value, SegmentValue::Exact(row(i) + 1))],
+                BLUE.mix(0.6).filled(),
+            );
+            bar.set_margin(10, 10, 0, 0);
+            bar
+        }))?;
+        chart.draw_series(impacts.iter().enumerate().map(|(i, (_, value, _))| {
+            Text::new(format!(" {:.2}", value), (*value, SegmentValue::CenterOf(row(i))), ("sans-serif", config.label_size(14)).into_font())
+        }))?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// The lines of a COGS and the color each is drawn in, in the order of the console summary.
+fn cogs_lines(cogs: &CogsResult) -> [(&'static str, RGBColor, f64); 7] {
+    [
+        ("Materials", SERIES_PALETTE[0], cogs.material_costs),
+        ("Labor", SERIES_PALETTE[1], cogs.labor_costs),
+        ("Energy", SERIES_PALETTE[2], cogs.energy_costs),
+        ("Depreciation", SERIES_PALETTE[3], cogs.asset_depreciation_costs),
+        ("Maintenance", SERIES_PALETTE[4], cogs.maintenance_costs),
+        ("End of life", SERIES_PALETTE[5], cogs.end_of_life_costs),
+        ("QC", SERIES_PALETTE[6], cogs.qc_costs),
+    ]
+}
+
+/// Plots the COGS as a horizontal waterfall: each non-zero line, labelled with its cost and its
+/// share of the total, starts where the previous one ends, down to the total. With costs by stage,
+/// also plots the lines of each stage as a stacked bar; see [`plot_cogs_by_stage`].
+pub fn plot_cogs_breakdown(output_dir: &str, config: &PlotConfig, cogs: &CogsResult, currency: &str) -> Result<()> {
+    let lines: Vec<(&str, RGBColor, f64)> = cogs_lines(cogs).into_iter().filter(|(_, _, cost)| *cost != 0.0).collect();
+    if !lines.is_empty() {
+        plot_cogs_waterfall(output_dir, config, &lines, cogs.total_cogs, currency)?;
+    }
+    if !cogs.by_stage.is_empty() {
+        plot_cogs_by_stage(output_dir, config, cogs, currency)?;
+    }
     Ok(())
 }
 
-/// Generates line charts for key environmental parameters over time.
-fn plot_environmental_parameters(output_dir: &str, data: &[PlottingData]) -> Result<()> {
-    let path = format!("{}/3_environmental_parameters.png", output_dir);
-    let root = BitMapBackend::new(&path, (1024, 768)).into_drawing_area();
-    root.fill(&WHITE)?;
+fn plot_cogs_waterfall(
+    output_dir: &str,
+    config: &PlotConfig,
+    lines: &[(&str, RGBColor, f64)],
+    total: f64,
+    currency: &str,
+) -> Result<()> {
+    // Each line starts where the previous one ended; the total is the last row.
+    let mut steps = Vec::new();
+    let mut end = 0.0;
+    for (name, color, cost) in lines {
+        steps.push((name.to_string(), *color, end, end + cost, *cost));
+        end += cost;
+    }
+    steps.push(("Total".to_string(), BLACK, 0.0, total, total));
+    let x_min = steps.iter().map(|(_, _, start, end, _)| start.min(*end)).fold(0.0, f64::min);
+    let x_max = steps.iter().map(|(_, _, start, end, _)| start.max(*end)).fold(0.0, f64::max);
+    // Headroom on the right keeps the labels of the longest bars inside the chart.
+    let x_max = x_max + (x_max - x_min) * 0.35;
+    let rows = steps.len();
+    // The first line goes at the top.
+    let row = |i: usize| rows - 1 - i;
+    let labels: Vec<String> = steps.iter().rev().map(|(name, ..)| name.clone()).collect();
+
+    render!(config, Figure::CogsBreakdown, output_dir, "13_cogs_breakdown", (1024, 160 + 60 * rows as u32), |root| {
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Cost of Goods Sold", ("sans-serif", config.caption_size(40)).into_font())
+            .margin(10)
+            .margin_right(40)
+            .x_label_area_size(40)
+            .y_label_area_size(140)
+            .build_cartesian_2d(x_min..x_max, (0..rows - 1).into_segmented())?;
+
+        chart.configure_mesh()
+            .disable_y_mesh()
+            .y_labels(rows)
+            .y_label_formatter(&|segment| match segment {
+                SegmentValue::CenterOf(i) => labels.get(*i).cloned().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .x_desc(format!("Cost ({})", currency))
+            .draw()?;
+
+        chart.draw_series(steps.iter().enumerate().map(|(i, (_, color, start, end, _))| {
+            let mut bar = Rectangle::new(
+                [(*start, SegmentValue::Exact(row(i))), (*end, SegmentValue::Exact(row(i) + 1))],
+                color.mix(0.6).filled(),
+            );
+            bar.set_margin(10, 10, 0, 0);
+            bar
+        }))?;
+        chart.draw_series(steps.iter().enumerate().map(|(i, (_, _, start, end, cost))| {
+            let share = if total != 0.0 { cost / total * 100.0 } else { 0.0 };
+            Text::new(
+                format!(" {:.2} {} ({:.1}%)", cost, currency, share),
+                (start.max(*end), SegmentValue::CenterOf(row(i))),
+                ("sans-serif", config.label_size(14)).into_font(),
+            )
+        }))?;
+
+        root.present()?;
+        Ok(())
+    })
+}
 
-    let max_tick = data.last().map_or(1, |d| d.tick);
+/// Plots the COGS lines of each stage as a stacked horizontal bar, stages by id, colored as in the
+/// waterfall of [`plot_cogs_breakdown`] and labelled with the stage's total. Negative lines, such
+/// as end-of-life credits, stack leftwards from zero.
+fn plot_cogs_by_stage(output_dir: &str, config: &PlotConfig, cogs: &CogsResult, currency: &str) -> Result<()> {
+    let stages: BTreeMap<&String, &CogsResult> = cogs.by_stage.iter().collect();
+    let rows = stages.len();
+    let row = |i: usize| rows - 1 - i;
+    let labels: Vec<String> = stages.keys().rev().map(|id| id.to_string()).collect();
+    let x_min = stages.values().map(|stage| cogs_lines(stage).iter().map(|(_, _, c)| c.min(0.0)).sum::<f64>()).fold(0.0, f64::min);
+    let x_max = stages.values().map(|stage| cogs_lines(stage).iter().map(|(_, _, c)| c.max(0.0)).sum::<f64>()).fold(0.0, f64::max);
+    if x_max <= x_min {
+        return Ok(());
+    }
+    let x_max = x_max + (x_max - x_min) * 0.25;
+    // Lines that are zero in every stage get no legend entry.
+    let shown: Vec<usize> = (0..7)
+        .filter(|line| stages.values().any(|stage| cogs_lines(stage)[*line].2 != 0.0))
+        .collect();
 
-    let mut chart = ChartBuilder::on(&root)
-        .caption(
-            "Environmental Parameters Over Time",
-            ("sans-serif", 50).into_font(),
-        )
-        .margin(10)
-        .x_label_area_size(30)
-        .y_label_area_size(50)
-        .build_cartesian_2d(0u64..max_tick, 0f64..100f64)?;
-
-    chart
-        .configure_mesh()
-        .x_desc("Time (hours)")
-        .y_desc("Value")
-        .draw()?;
-
-    chart
-        .draw_series(LineSeries::new(
-            data.iter().map(|d| (d.tick, d.media_ph)),
-            RED.stroke_width(3),
-        ))?
-        .label("pH")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], RED.filled()));
-    
-    chart
-        .draw_series(LineSeries::new(
-            data.iter().map(|d| (d.tick, d.temperature)),
-            BLUE.stroke_width(3),
-        ))?
-        .label("Temperature (°C)")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], BLUE.filled()));
-
-    let temp_series = (0..=max_tick).map(|x| {
-        let angle = 2.0 * PI * (x as f64) / 24.0;
-        (x, 25.0 + 5.0 * angle.sin()) // Sine wave for temperature
-    });
-    chart.draw_series(DashedLineSeries::new(temp_series, 5, 5, (&BLUE).into()))?
-        .label("Idealized Temperature (°C)")
-        .legend(|(x, y)| {
-            PathElement::new(vec![(x, y), (x + 20, y)], BLUE.filled())
+    render!(config, Figure::CogsByStage, output_dir, "14_cogs_by_stage", (1024, 200 + 60 * rows as u32), |root| {
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("COGS by Stage", ("sans-serif", config.caption_size(40)).into_font())
+            .margin(10)
+            .margin_right(40)
+            .x_label_area_size(40)
+            .y_label_area_size(260)
+            .build_cartesian_2d(x_min..x_max, (0..rows - 1).into_segmented())?;
+
+        chart.configure_mesh()
+            .disable_y_mesh()
+            .y_labels(rows)
+            .y_label_formatter(&|segment| match segment {
+                SegmentValue::CenterOf(i) => labels.get(*i).cloned().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .x_desc(format!("Cost ({})", currency))
+            .draw()?;
+
+        for line in &shown {
+            let (name, color, _) = cogs_lines(cogs)[*line];
+            let bars = stages.values().enumerate().map(|(i, stage)| {
+                let lines = cogs_lines(stage);
+                let cost = lines[*line].2;
+                // The line starts where the earlier lines of the same sign end.
+                let start: f64 = lines[..*line].iter().map(|(_, _, c)| *c).filter(|c| (*c >= 0.0) == (cost >= 0.0)).sum();
+                let mut bar = Rectangle::new(
+                    [(start, SegmentValue::Exact(row(i))), (start + cost, SegmentValue::Exact(row(i) + 1))],
+                    color.mix(0.6).filled(),
+                );
+                bar.set_margin(10, 10, 0, 0);
+                bar
+            });
+            chart
+                .draw_series(bars)?
+                .label(name)
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.mix(0.6).filled()));
+        }
+        chart.draw_series(stages.values().enumerate().map(|(i, stage)| {
+            let right: f64 = cogs_lines(stage).iter().map(|(_, _, c)| c.max(0.0)).sum();
+            Text::new(
+                format!(" {:.2} {}", stage.total_cogs, currency),
+                (right, SegmentValue::CenterOf(row(i))),
+                ("sans-serif", config.label_size(14)).into_font(),
+            )
+        }))?;
+
+        chart.configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::UpperRight)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Plots the kilograms of each material the run consumed as a horizontal bar, labelled with its
+/// cost, the costliest first. Materials are named from the knowledge base, or by their id when they
+/// match no material, in which case they cost nothing. Materials below
+/// [`PlotConfig::bom_other_threshold`] of both the total mass and `cogs.material_costs` are
+/// lumped into one "Other" bar at the bottom.
+pub fn plot_bom_materials(
+    output_dir: &str,
+    config: &PlotConfig,
+    bom: &BillOfMaterials,
+    materials: &HashMap<String, Material>,
+    cogs: &CogsResult,
+    currency: &str,
+) -> Result<()> {
+    let resolver = MaterialResolver::new(materials);
+    let total_kg: f64 = bom.materials_consumed.values().sum::<f64>() / 1000.0;
+    // The initial media go by ChEBI id and feeds by material id, so one material can appear twice.
+    let mut consumed: HashMap<&str, (String, f64, f64)> = HashMap::new();
+    for (id, grams) in &bom.materials_consumed {
+        let material = resolver.resolve(id);
+        let entry = consumed.entry(material.map_or(id.as_str(), |m| m.material_id.as_str())).or_insert_with(|| {
+            (material.map_or_else(|| id.clone(), |m| m.material_name.clone()), 0.0, 0.0)
         });
+        entry.1 += grams / 1000.0;
+        entry.2 += material_cost(&resolver, id, *grams);
+    }
+    let mut bars: Vec<(String, f64, f64)> = Vec::new();
+    let (mut other_kg, mut other_cost, mut others) = (0.0, 0.0, 0);
+    for (name, kg, cost) in consumed.into_values() {
+        let minor = |value: f64, total: f64| total <= 0.0 || value / total < config.bom_other_threshold;
+        if minor(kg, total_kg) && minor(cost, cogs.material_costs) {
+            other_kg += kg;
+            other_cost += cost;
+            others += 1;
+        } else {
+            bars.push((name, kg, cost));
+        }
+    }
+    bars.sort_by(|a, b| b.2.total_cmp(&a.2).then(b.1.total_cmp(&a.1)).then(a.0.cmp(&b.0)));
+    if others > 0 {
+        bars.push((format!("Other ({})", others), other_kg, other_cost));
+    }
+    if bars.is_empty() {
+        return Ok(());
+    }
 
+    let x_max = bars.iter().map(|(_, kg, _)| *kg).fold(0.0, f64::max);
+    // Headroom on the right keeps the labels of the longest bars inside the chart.
+    let x_max = if x_max > 0.0 { x_max * 1.35 } else { 1.0 };
+    let rows = bars.len();
+    let row = |i: usize| rows - 1 - i;
+    let labels: Vec<String> = bars.iter().rev().map(|(name, ..)| name.clone()).collect();
+
+    render!(config, Figure::BomMaterials, output_dir, "15_bom_materials", (1024, 160 + 50 * rows as u32), |root| {
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Materials Consumed", ("sans-serif", config.caption_size(40)).into_font())
+            .margin(10)
+            .margin_right(40)
+            .x_label_area_size(40)
+            .y_label_area_size(280)
+            .build_cartesian_2d(0.0..x_max, (0..rows - 1).into_segmented())?;
+
+        chart.configure_mesh()
+            .disable_y_mesh()
+            .y_labels(rows)
+            .y_label_formatter(&|segment| match segment {
+                SegmentValue::CenterOf(i) => labels.get(*i).cloned().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .x_desc("Consumed (kg)")
+            .draw()?;
+
+        chart.draw_series(bars.iter().enumerate().map(|(i, (_, kg, _))| {
+            let mut bar = Rectangle::new(
+                [(0.0, SegmentValue::Exact(row(i))), (*kg, SegmentValue::Exact(row(i) + 1))],
+                SERIES_PALETTE[0].mix(0.6).filled(),
+            );
+            bar.set_margin(8, 8, 0, 0);
+            bar
+        }))?;
+        chart.draw_series(bars.iter().enumerate().map(|(i, (_, kg, cost))| {
+            Text::new(
+                format!(" {:.3} kg, {:.2} {}", kg, cost, currency),
+                (*kg, SegmentValue::CenterOf(row(i))),
+                ("sans-serif", config.label_size(14)).into_font(),
+            )
+        }))?;
+
+        root.present()?;
+        Ok(())
+    })
+}
 
-    let light_series = (0..=max_tick).map(|x| {
-        let angle = 2.0 * PI * (x as f64) / 24.0;
-        (x, 50.0 + 50.0 * angle.sin())
-    });
-    chart.draw_series(DashedLineSeries::new(light_series, 5, 5, (&BLACK).into()))?
-        .label("Photosynthetically Active Radiation (PAR)")
-        .legend(|(x,y)| {
-            PathElement::new(vec![(x,y), (x+20,y)], BLACK.filled())
-        });
+/// Stacks the GWP of the run by origin, fossil CO₂e of grid energy, other fossil and material
+/// CO₂e, biogenic emission, and biogenic fixation (negative), beside the net total, in kg CO₂e.
+/// `gwp_kg_co2e` is the GWP of the LCA, which holds the fossil energy but no biogenic flows.
+pub fn plot_carbon_balance(output_dir: &str, config: &PlotConfig, balance: &CarbonBalance, gwp_kg_co2e: f64) -> Result<()> {
+    let other_fossil = gwp_kg_co2e - balance.fossil_energy_kg_co2e;
+    let components = [
+        (BLACK, "Fossil (grid energy)", balance.fossil_energy_kg_co2e),
+        (BLUE, "Fossil (materials and assets)", other_fossil),
+        (RED, "Biogenic emission (respired)", balance.biogenic_emission_kg_co2e),
+        (GREEN, "Biogenic fixation", 0.0 - balance.biogenic_fixation_kg_co2e),
+    ];
+    let net: f64 = components.iter().map(|(_, _, value)| value).sum();
+    let positive: f64 = components.iter().map(|(_, _, value)| value.max(0.0)).sum();
+    let negative: f64 = components.iter().map(|(_, _, value)| value.min(0.0)).sum();
+    if positive == 0.0 && negative == 0.0 {
+        return Ok(());
+    }
+    let margin = (positive - negative) * 0.1;
+    let labels = ["Net", "By origin"];
+
+    render!(config, Figure::CarbonBalance, output_dir, "11_carbon_balance", (1024, 400), |root| {
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Carbon Balance", ("sans-serif", config.caption_size(40)).into_font())
+            .margin(10)
+            .margin_right(40)
+            .x_label_area_size(40)
+            .y_label_area_size(120)
+            .build_cartesian_2d((negative - margin)..(positive + margin), (0..1usize).into_segmented())?;
+
+        chart.configure_mesh()
+            .disable_y_mesh()
+            .y_labels(2)
+            .y_label_formatter(&|segment| match segment {
+                SegmentValue::CenterOf(i) => labels.get(*i).map(|l| l.to_string()).unwrap_or_default(),
+                _ => String::new(),
+            })
+            .x_desc("kg CO2e")
+            .draw()?;
+
+        // Positive components stack rightwards from zero and negative ones leftwards.
+        let (mut right, mut left) = (0.0, 0.0);
+        for (color, label, value) in components {
+            let start = if value >= 0.0 { right } else { left };
+            let end = start + value;
+            if value >= 0.0 {
+                right = end;
+            } else {
+                left = end;
+            }
+            let mut bar = Rectangle::new([(start, SegmentValue::Exact(1)), (end, SegmentValue::Exact(2))], color.mix(0.6).filled());
+            bar.set_margin(10, 10, 0, 0);
+            chart
+                .draw_series(std::iter::once(bar))?
+                .label(format!("{}: {:.2}", label, value))
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.mix(0.6).filled()));
+        }
+        let mut net_bar = Rectangle::new([(0.0, SegmentValue::Exact(0)), (net, SegmentValue::Exact(1))], BLACK.mix(0.3).filled());
+        net_bar.set_margin(10, 10, 0, 0);
+        chart.draw_series(std::iter::once(net_bar))?;
+        chart.draw_series(std::iter::once(Text::new(
+            format!(" {:.2}", net),
+            (net.min(0.0), SegmentValue::CenterOf(0)),
+            ("sans-serif", config.label_size(14)).into_font(),
+        )))?;
+
+        chart.configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::LowerRight)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    })
+}
 
+/// Plots the instantaneous volumetric productivity of each target over the cultivation, from
+/// `series` of (hour, g/L/h) points keyed by molecule name.
+pub fn plot_volumetric_productivity(output_dir: &str, config: &PlotConfig, series: &[(String, Vec<(f64, f64)>)]) -> Result<()> {
+    let points = || series.iter().flat_map(|(_, points)| points.iter());
+    if points().next().is_none() {
+        return Ok(());
+    }
 
-    chart
-        .draw_series(LineSeries::new(
-            data.iter()
-                .map(|d| (d.tick, d.dissolved_gases.get("oxygen").cloned().unwrap_or(0.0) * 1000.0)),
-            GREEN.stroke_width(3),
-        ))?
-        .label("Dissolved O2 (mg/L)")
-        .legend(|(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], GREEN.filled()));
-
-    chart
-        .configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
-        .draw()?;
-    root.present()?;
-    Ok(())
+    render!(config, Figure::VolumetricProductivity, output_dir, "10_volumetric_productivity", (1024, 768), |root| {
+        root.fill(&WHITE)?;
+
+        let max_hour = points().map(|(hour, _)| *hour).fold(1.0, f64::max);
+        let min_rate = points().map(|(_, rate)| *rate).fold(0.0, f64::min);
+        let max_rate = points().map(|(_, rate)| *rate).fold(0.0, f64::max);
+        // Keep a visible range for runs in which nothing was produced.
+        let y_max = if max_rate > 0.0 { max_rate * 1.1 } else { 1.0 };
+        let y_min = min_rate * 1.1;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Volumetric Productivity Over Time", ("sans-serif", config.caption_size(50)).into_font())
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(70)
+            .build_cartesian_2d(0f64..max_hour, y_min..y_max)?;
+
+        chart.configure_mesh()
+            .x_desc("Time (h)")
+            .y_desc("Productivity (g/L/h)")
+            .y_label_formatter(&|rate| format!("{:.1e}", rate))
+            .draw()?;
+
+        for (i, (molecule_name, points)) in series.iter().enumerate() {
+            draw_styled_line(&mut chart, points.clone(), config.series_style(i, &ORGANISM_COLORS), 2, molecule_name)?;
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+        Ok(())
+    })
 }
 
+/// Overlays the total biomass of the cultivation of each scenario of a sweep, from `series` of
+/// (hour, g) points keyed by scenario name.
+pub fn plot_sweep_biomass(output_dir: &str, config: &PlotConfig, series: &[(String, Vec<(f64, f64)>)]) -> Result<()> {
+    let points = || series.iter().flat_map(|(_, points)| points.iter());
+    if points().next().is_none() {
+        return Ok(());
+    }
 
-/// Generates a flowchart of the end-to-end process.
-pub fn plot_process_flow(output_dir: &str, processes: &[&Process], rules: &HashMap<String, Rule>) -> Result<()> {
-    let path = format!("{}/4_process_flow.png", output_dir);
-    let root_area = BitMapBackend::new(&path, (1920, 1080)).into_drawing_area();
-    root_area.fill(&WHITE)?;
-    let title = format!("Process Flow: {}", processes.iter().map(|p| p.process_name.as_str()).collect::<Vec<&str>>().join(" & "));
-    root_area.titled(&title, ("sans-serif", 40))?;
+    render!(config, Figure::SweepBiomass, output_dir, "18_sweep_biomass", (1024, 768), |root| {
+        root.fill(&WHITE)?;
 
-    let drawing_area = root_area.margin(20, 20, 60, 20);
+        let max_hour = points().map(|(hour, _)| *hour).fold(1.0, f64::max);
+        let max_biomass = points().map(|(_, grams)| *grams).fold(0.0, f64::max);
+        let y_max = if max_biomass > 0.0 { max_biomass * 1.1 } else { 1.0 };
 
-    let mut current_y_offset = 100;
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Biomass Growth Across Scenarios", ("sans-serif", config.caption_size(50)).into_font())
+            .margin(10)
+            .x_label_area_size(30)
+            .y_label_area_size(50)
+            .build_cartesian_2d(0f64..max_hour, 0f64..y_max)?;
 
-    for process in processes {
-        let process_title_style = TextStyle::from(("sans-serif", 24).into_font()).color(&BLACK);
-        drawing_area.draw_text(
-            &format!("Process: {}", process.process_name),
-            &process_title_style,
-            (50, current_y_offset as i32),
-        )?;
-        current_y_offset += 40;
+        chart.configure_mesh()
+            .x_desc("Time (h)")
+            .y_desc("Biomass (g)")
+            .draw()?;
+
+        for (i, (scenario, points)) in series.iter().enumerate() {
+            draw_styled_line(&mut chart, points.clone(), config.series_style(i, &ORGANISM_COLORS), 2, scenario)?;
+        }
+
+        chart
+            .configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .draw()?;
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Draws a tornado chart of how far each parameter of a sensitivity study moves the COGS from its
+/// baseline, the most influential parameter at the top.
+pub fn plot_sensitivity_tornado(output_dir: &str, config: &PlotConfig, report: &SensitivityReport, currency: &str) -> Result<()> {
+    let parameters = &report.parameters;
+    if parameters.is_empty() {
+        return Ok(());
+    }
+
+
+    let baseline = report.baseline.total_cogs;
+    let deltas: Vec<(f64, f64)> = parameters
+        .iter()
+        .map(|p| (p.low.total_cogs - baseline, p.high.total_cogs - baseline))
+        .collect();
+    let extent = deltas.iter().flat_map(|(low, high)| [low.abs(), high.abs()]).fold(0.0, f64::max);
+    let extent = if extent > 0.0 { extent * 1.1 } else { 1.0 };
+    // The first parameter goes at the top.
+    let row = |i: usize| parameters.len() - 1 - i;
+    let labels: Vec<String> = parameters.iter().rev().map(|p| format!("{} (±{}%)", p.path, p.percent)).collect();
+    // Parameter paths can be long; size the label area to fit the longest.
+    let label_area = 20 + 7 * labels.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u32;
+
+    render!(config, Figure::SensitivityTornado, output_dir, "8_sensitivity_tornado", (label_area + 700, 160 + 60 * parameters.len() as u32), |root| {
+        root.fill(&WHITE)?;
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("COGS Sensitivity", ("sans-serif", config.caption_size(40)).into_font())
+            .margin(10)
+            .margin_right(40)
+            .x_label_area_size(40)
+            .y_label_area_size(label_area)
+            .build_cartesian_2d(-extent..extent, (0..parameters.len() - 1).into_segmented())?;
+
+        chart.configure_mesh()
+            .disable_y_mesh()
+            .y_labels(parameters.len())
+            .y_label_formatter(&|segment| match segment {
+                SegmentValue::CenterOf(i) => labels.get(*i).cloned().unwrap_or_default(),
+                _ => String::new(),
+            })
+            .x_desc(format!("Change in total COGS from {:.2} {}", baseline, currency))
+            .draw()?;
+
+        for (color, label, pick) in [
+            (RED, "Parameter lowered", (|d: &(f64, f64)| d.0) as fn(&(f64, f64)) -> f64),
+            (BLUE, "Parameter raised", |d: &(f64, f64)| d.1),
+        ] {
+            chart
+                .draw_series(deltas.iter().enumerate().map(|(i, delta)| {
+                    let mut bar = Rectangle::new(
+                        [(0.0, SegmentValue::Exact(row(i))), (pick(delta), SegmentValue::Exact(row(i) + 1))],
+                        color.mix(0.6).filled(),
+                    );
+                    bar.set_margin(10, 10, 0, 0);
+                    bar
+                }))?
+                .label(label)
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 10, y + 5)], color.mix(0.6).filled()));
+        }
 
-        let num_steps = process.default_workflow.len();
-        if num_steps == 0 {
-            current_y_offset += 100;
+        chart.configure_series_labels()
+            .background_style(WHITE.mix(0.8))
+            .border_style(BLACK)
+            .position(SeriesLabelPosition::LowerRight)
+            .draw()?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+
+/// Draws a histogram of each output metric of a Monte Carlo study, marking its P10, P50, and P90.
+pub fn plot_monte_carlo_histograms(output_dir: &str, config: &PlotConfig, report: &MonteCarloReport) -> Result<()> {
+    const BINS: usize = 20;
+
+    for (name, metric) in METRICS {
+        let values = report.values(metric);
+        let Some(percentiles) = report.percentiles(metric) else {
             continue;
+        };
+
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        // Identical samples still get a visible bin.
+        let (min, max) = if max > min { (min, max) } else { (min - 0.5, max + 0.5) };
+        let width = (max - min) / BINS as f64;
+        let mut counts = [0u32; BINS];
+        for value in &values {
+            counts[(((value - min) / width) as usize).min(BINS - 1)] += 1;
         }
+        let max_count = counts.iter().copied().max().unwrap_or(0);
+
+        render!(config, Figure::MonteCarlo, output_dir, &format!("9_monte_carlo_{}", name), (1024, 600), |root| {
+            root.fill(&WHITE)?;
+
+            let mut chart = ChartBuilder::on(&root)
+                .caption(format!("{} ({} samples)", name, values.len()), ("sans-serif", config.caption_size(40)).into_font())
+                .margin(10)
+                .margin_right(40)
+                .x_label_area_size(40)
+                .y_label_area_size(60)
+                .build_cartesian_2d(min..max, 0u32..max_count + 1)?;
+
+            chart.configure_mesh()
+                .disable_x_mesh()
+                .x_desc(name)
+                .y_desc("Samples")
+                .draw()?;
+
+            chart.draw_series(counts.iter().enumerate().map(|(i, count)| {
+                let left = min + width * i as f64;
+                Rectangle::new([(left, 0), (left + width, *count)], BLUE.mix(0.6).filled())
+            }))?;
+
+            for (label, value, color) in [
+                ("P10", percentiles.p10, RED),
+                ("P50", percentiles.p50, BLACK),
+                ("P90", percentiles.p90, RED),
+            ] {
+                chart
+                    .draw_series(LineSeries::new([(value, 0), (value, max_count + 1)], color.stroke_width(2)))?
+                    .label(format!("{} = {:.4}", label, value))
+                    .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], color.stroke_width(2)));
+            }
 
-        let node_min_width = 180;
-        let node_min_height = 80;
-        let x_gap = 70;
-        let x_padding = 10;
-
-        let mut max_text_width_in_node = 0;
-        let text_style = TextStyle::from(("sans-serif", 14).into_font());
-        for method_id in process.default_workflow.iter() {
-            let method = process.methods.iter().find(|m| &m.method_id == method_id).unwrap();
-            let text_lines = vec![
-                format!("Stage: {}", method.stage),
-                format!("Method: {}", method.method_id),
-                format!("Technique: {}", method.technique),
-            ];
-            for line in text_lines {
-                if let Ok((w, _)) = drawing_area.estimate_text_size(&line, &text_style) {
-                    if w > max_text_width_in_node {
-                        max_text_width_in_node = w;
-                    }
-                }
+            chart.configure_series_labels()
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .position(SeriesLabelPosition::UpperRight)
+                .draw()?;
+
+            root.present()?;
+            Ok(())
+        })?;
+    }
+    Ok(())
+}
+
+/// The narrowest and widest a process flow node is drawn; longer text is cut short with an ellipsis.
+const FLOW_NODE_WIDTH: Range<u32> = 180..260;
+const FLOW_NODE_HEIGHT: i32 = 80;
+/// The height of each badge under a node, which lists its QC checks or its materials.
+const FLOW_BADGE_HEIGHT: i32 = 18;
+/// The room under each node for its two badges.
+const FLOW_BADGES_HEIGHT: i32 = 2 * (FLOW_BADGE_HEIGHT + 4);
+/// The horizontal gap between the nodes of a row, which holds the arrow and its conditions.
+const FLOW_X_GAP: i32 = 130;
+/// The vertical gap between the rows of a wrapped workflow, which holds the connector between them.
+const FLOW_ROW_GAP: i32 = 60;
+/// The space left and right of the widest row, which holds the start of wrapped connectors.
+const FLOW_MARGIN: i32 = 80;
+
+/// Where the nodes of one process's workflow go: rows of up to `per_row` nodes, left to right,
+/// each row starting at `x` under the previous one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FlowLayout {
+    node_width: i32,
+    per_row: usize,
+    rows: usize,
+    x: i32,
+}
+
+impl FlowLayout {
+    /// Lays out `steps` nodes of `node_width` in a drawing `width` pixels wide, wrapping onto as
+    /// many rows as needed and centering the widest row.
+    fn new(steps: usize, node_width: i32, width: i32) -> Self {
+        let fits = ((width - 2 * FLOW_MARGIN + FLOW_X_GAP) / (node_width + FLOW_X_GAP)).max(1) as usize;
+        let per_row = fits.min(steps.max(1));
+        let rows = steps.div_ceil(per_row);
+        let row_width = per_row as i32 * (node_width + FLOW_X_GAP) - FLOW_X_GAP;
+        FlowLayout { node_width, per_row, rows, x: ((width - row_width) / 2).max(0) }
+    }
+
+    /// The top-left corner of node `i`, for a workflow whose first row starts at `top`.
+    fn node(&self, i: usize, top: i32) -> (i32, i32) {
+        let (row, column) = (i / self.per_row, i % self.per_row);
+        (
+            self.x + column as i32 * (self.node_width + FLOW_X_GAP),
+            top + row as i32 * (FLOW_NODE_HEIGHT + FLOW_BADGES_HEIGHT + FLOW_ROW_GAP),
+        )
+    }
+
+    /// The height of the rows, with the badges under the nodes.
+    fn height(&self) -> i32 {
+        self.rows as i32 * (FLOW_NODE_HEIGHT + FLOW_BADGES_HEIGHT + FLOW_ROW_GAP) - FLOW_ROW_GAP
+    }
+}
+
+/// The lines of text of the node of `method`.
+fn flow_node_lines(method: &Method) -> [String; 3] {
+    [
+        format!("Stage: {}", method.stage),
+        format!("Method: {}", method.method_id),
+        format!("Technique: {}", method.technique),
+    ]
+}
+
+/// `text`, cut short with an ellipsis if it is wider than `max_width` pixels in `font`.
+fn fit_text(text: &str, font: &FontDesc, max_width: u32) -> String {
+    let width = |text: &str| font.box_size(text).map_or(0, |(w, _)| w);
+    if width(text) <= max_width {
+        return text.to_string();
+    }
+    let mut chars: Vec<char> = text.chars().collect();
+    while !chars.is_empty() {
+        chars.pop();
+        let candidate = format!("{}…", chars.iter().collect::<String>());
+        if width(&candidate) <= max_width {
+            return candidate;
+        }
+    }
+    "…".to_string()
+}
+
+/// The badges under the node of `method`, with the colour of each: its QC checks and its
+/// required materials, each left out when it has none.
+fn flow_badges(method: &Method) -> Vec<(String, RGBColor)> {
+    let qc: Vec<&str> = method.qc_checks.iter().map(|check| check.method_id.as_str()).collect();
+    let materials: Vec<&str> = method.required_materials.iter().map(|material| material.id.as_str()).collect();
+    let mut badges = Vec::new();
+    if !qc.is_empty() {
+        badges.push((format!("QC: {}", qc.join(", ")), RGBColor(255, 224, 178)));
+    }
+    if !materials.is_empty() {
+        badges.push((format!("Materials: {}", materials.join(", ")), RGBColor(220, 220, 220)));
+    }
+    badges
+}
+
+/// `text` broken at spaces into lines no wider than `max_width` pixels in `font`; words that do
+/// not fit on a line of their own are cut short.
+fn wrap_text(text: &str, font: &FontDesc, max_width: u32) -> Vec<String> {
+    let width = |text: &str| font.box_size(text).map_or(0, |(w, _)| w);
+    let mut lines: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if width(&format!("{} {}", line, word)) <= max_width => {
+                line.push(' ');
+                line.push_str(word);
             }
+            _ => lines.push(fit_text(word, font, max_width)),
         }
-        let node_width = (max_text_width_in_node as u32 + x_padding * 2).max(node_min_width as u32) as i32;
-        let node_height = node_min_height;
+    }
+    lines
+}
 
-        let total_flow_width = (num_steps as i32 * node_width) + ((num_steps as i32 - 1) * x_gap);
-        let mut x_pos = (1920 / 2 - total_flow_width / 2) as i32;
+/// Generates a flowchart of the end-to-end process. Each node lists its QC checks and materials in
+/// badges underneath, and each arrow the conditions of the rules that end the previous step. Long
+/// workflows wrap onto further rows, with a connector from the end of each row to the start of the
+/// next, and the canvas grows to fit them.
+pub fn plot_process_flow(output_dir: &str, config: &PlotConfig, processes: &[&Process], rules: &HashMap<String, Rule>) -> Result<()> {
+    let node_font: FontDesc = ("sans-serif", config.label_size(14)).into_font();
+    let x_padding = 10;
+    let (canvas_width, _) = config.output.scale((1920, 1080));
+    // The drawing area is inset 20 px on each side.
+    let width = canvas_width as i32 - 40;
+
+    let layouts: Vec<FlowLayout> = processes
+        .iter()
+        .map(|process| {
+            let text_width = process
+                .methods
+                .iter()
+                .filter(|m| process.default_workflow.contains(&m.method_id))
+                .flat_map(flow_node_lines)
+                .map(|line| node_font.box_size(&line).map_or(0, |(w, _)| w))
+                .max()
+                .unwrap_or(0);
+            let node_width = (text_width + x_padding * 2).clamp(FLOW_NODE_WIDTH.start, FLOW_NODE_WIDTH.end);
+            FlowLayout::new(process.default_workflow.len(), node_width as i32, width)
+        })
+        .collect();
+    // Each process takes its title, the gap above its rows, its rows, and the space below them.
+    let flow_height: i32 = 100 + processes
+        .iter()
+        .zip(&layouts)
+        .map(|(process, layout)| 40 + if process.default_workflow.is_empty() { 100 } else { 50 + layout.height() + 100 })
+        .sum::<i32>();
+    // The drawing area starts 60 px down, below the title; the canvas is at least the usual 1080
+    // px, scaled back from the format's resolution to the size `render!` scales.
+    let (_, default_height) = config.output.scale((1920, 1080));
+    let canvas_height = (flow_height as u32 + 80).max(default_height);
+    let (PlotFormat::Png { height: format_height, .. } | PlotFormat::Svg { height: format_height, .. }) = config.output;
+    let nominal_height = (canvas_height * 768).div_ceil(format_height);
+
+    render!(config, Figure::ProcessFlow, output_dir, "4_process_flow", (1920, nominal_height), |root_area| {
+        root_area.fill(&WHITE)?;
+        let title = format!("Process Flow: {}", processes.iter().map(|p| p.process_name.as_str()).collect::<Vec<&str>>().join(" & "));
+        let title_font: FontDesc = ("sans-serif", config.caption_size(40)).into_font();
+        root_area.titled(&fit_text(&title, &title_font, canvas_width - 40), title_font.clone())?;
+
+        let drawing_area = root_area.margin(20, 20, 60, 20);
+
+        let mut current_y_offset = 100;
+
+        for (process, layout) in processes.iter().zip(&layouts) {
+            let process_title_style = TextStyle::from(("sans-serif", config.label_size(24)).into_font()).color(&BLACK);
+            drawing_area.draw_text(
+                &format!("Process: {}", process.process_name),
+                &process_title_style,
+                (50, current_y_offset),
+            )?;
+            current_y_offset += 40;
+
+            if process.default_workflow.is_empty() {
+                current_y_offset += 100;
+                continue;
+            }
 
-        let mut last_node_end_x = 0;
+            let top = current_y_offset + 50;
+            let node_width = layout.node_width;
+            let node_height = FLOW_NODE_HEIGHT;
+            let text_style = TextStyle::from(node_font.clone()).color(&WHITE);
+            let rule_font: FontDesc = ("sans-serif", config.label_size(12)).into_font();
+            let rule_text_style = TextStyle::from(rule_font.clone()).pos(Pos::new(HPos::Center, VPos::Bottom));
+            let badge_font: FontDesc = ("sans-serif", config.label_size(11)).into_font();
+            let badge_text_style = TextStyle::from(badge_font.clone()).pos(Pos::new(HPos::Left, VPos::Center));
+            let line_height = rule_font.box_size("Hg").map_or(14, |(_, h)| h as i32 + 2);
+            // Draws the lines of `label`, wrapped to `max_width`, centered on `x` with the last
+            // line just above `y`.
+            let draw_label = |label: &str, x: i32, y: i32, max_width: i32| -> Result<()> {
+                let lines = wrap_text(label, &rule_font, max_width.max(1) as u32);
+                for (j, line) in lines.iter().enumerate() {
+                    let above = (lines.len() - 1 - j) as i32;
+                    drawing_area.draw_text(line, &rule_text_style, (x, y - 4 - above * line_height))?;
+                }
+                Ok(())
+            };
+
+            for (i, method_id) in process.default_workflow.iter().enumerate() {
+                let method = process.methods.iter().find(|m| &m.method_id == method_id).unwrap();
+                let top_left = layout.node(i, top);
+
+                let node_color = RGBColor(70, 130, 180);
+                let style = ShapeStyle { color: node_color.into(), filled: true, stroke_width: 2 };
+                drawing_area.draw(&Rectangle::new(
+                    [top_left, (top_left.0 + node_width, top_left.1 + node_height)],
+                    style,
+                ))?;
+
+                for (line_index, line) in flow_node_lines(method).iter().enumerate() {
+                    let text = fit_text(line, &node_font, (node_width - 2 * x_padding as i32) as u32);
+                    drawing_area.draw_text(&text, &text_style, (top_left.0 + 10, top_left.1 + 15 + 20 * line_index as i32))?;
+                }
+                for (badge_index, (badge, color)) in flow_badges(method).iter().enumerate() {
+                    let badge_top = top_left.1 + node_height + 4 + badge_index as i32 * (FLOW_BADGE_HEIGHT + 4);
+                    drawing_area.draw(&Rectangle::new(
+                        [(top_left.0, badge_top), (top_left.0 + node_width, badge_top + FLOW_BADGE_HEIGHT)],
+                        color.filled(),
+                    ))?;
+                    let text = fit_text(badge, &badge_font, (node_width - 2 * x_padding as i32) as u32);
+                    drawing_area.draw_text(&text, &badge_text_style, (top_left.0 + 10, badge_top + FLOW_BADGE_HEIGHT / 2))?;
+                }
 
-        for (i, method_id) in process.default_workflow.iter().enumerate() {
-            let method = process.methods.iter().find(|m| &m.method_id == method_id).unwrap();
-            
-            let top_left = (x_pos, current_y_offset as i32 + 50);
-            
-            let node_color = RGBColor(70, 130, 180);
-            let style = ShapeStyle { color: node_color.into(), filled: true, stroke_width: 2 };
-            drawing_area.draw(&Rectangle::new(
-                [top_left, (top_left.0 + node_width, top_left.1 + node_height)],
-                style,
-            ))?;
-            
-            let text_style = TextStyle::from(("sans-serif", 14).into_font()).color(&WHITE);
-
-            let text_start_x = x_pos + 10;
-            let text_start_y = current_y_offset as i32 + 50 + 15;
-
-            drawing_area.draw_text(&format!("Stage: {}", method.stage), &text_style, (text_start_x, text_start_y))?;
-            drawing_area.draw_text(&format!("Method: {}", method.method_id), &text_style, (text_start_x, text_start_y + 20))?;
-            drawing_area.draw_text(&format!("Technique: {}", method.technique), &text_style, (text_start_x, text_start_y + 40))?;
-            
-            if i > 0 {
-                let arrow_y = current_y_offset as i32 + 50 + node_height / 2;
-                let start_point = (last_node_end_x as i32, arrow_y);
-                let end_point = (x_pos, arrow_y);
-
-                drawing_area.draw(&PathElement::new(vec![start_point, end_point], BLACK.stroke_width(2)))?;
-                
+                if i == 0 {
+                    continue;
+                }
+                let prev_method = process.methods.iter().find(|m| m.method_id == process.default_workflow[i - 1]).unwrap();
+                let rule_text =
+                    transition_conditions(prev_method.required_rule_ids.iter().flatten().filter_map(|id| rules.get(id)));
+                let prev = layout.node(i - 1, top);
                 let arrowhead_size = 10;
-                let arrowhead_points = vec![
-                    end_point,
-                    (end_point.0 - arrowhead_size, end_point.1 - arrowhead_size / 2),
-                    (end_point.0 - arrowhead_size, end_point.1 + arrowhead_size / 2),
-                ];
-                drawing_area.draw(&Polygon::new(arrowhead_points, BLACK.filled()))?;
-
-                let prev_method = process.methods.iter().find(|m| m.method_id == process.default_workflow[i-1]).unwrap();
-                let rule_text_content = if let Some(rule_ids) = &prev_method.required_rule_ids {
-                    if let Some(rule_id) = rule_ids.get(0) {
-                        if let Some(rule) = rules.get(rule_id) {
-                            if let Condition::TimeInStage { ticks } = rule.condition {
-                                format!("{} hours", ticks)
-                            } else {
-                                rule_id.clone()
-                            }
-                        } else {
-                            rule_id.clone()
-                        }
-                    } else {
-                        "".to_string()
-                    }
-                } else {
-                    "".to_string()
-                };
 
-                
-                if !rule_text_content.is_empty() {
-                    let rule_text_style = TextStyle::from(("sans-serif", 12).into_font());
+                if prev.1 == top_left.1 {
+                    // Same row: a straight arrow between the nodes, labelled above.
+                    let arrow_y = top_left.1 + node_height / 2;
+                    let start_point = (prev.0 + node_width, arrow_y);
+                    let end_point = (top_left.0, arrow_y);
+                    drawing_area.draw(&PathElement::new(vec![start_point, end_point], BLACK.stroke_width(2)))?;
+                    drawing_area.draw(&Polygon::new(
+                        vec![
+                            end_point,
+                            (end_point.0 - arrowhead_size, end_point.1 - arrowhead_size / 2),
+                            (end_point.0 - arrowhead_size, end_point.1 + arrowhead_size / 2),
+                        ],
+                        BLACK.filled(),
+                    ))?;
                     let text_mid_x = start_point.0 + (end_point.0 - start_point.0) / 2;
-                    let text_offset_y = -20;
-                    drawing_area.draw_text(&rule_text_content, &rule_text_style, (text_mid_x, arrow_y + text_offset_y))?;
+                    draw_label(&rule_text, text_mid_x, arrow_y, FLOW_X_GAP - 8)?;
+                } else {
+                    // Wrapped: out of the end of the row, down past its badges, back across the gap
+                    // between the rows, and down into the start of the next row, labelled along
+                    // the way back.
+                    let from = (prev.0 + node_width, prev.1 + node_height / 2);
+                    let turn_x = from.0 + FLOW_X_GAP / 2;
+                    let to = (top_left.0 + node_width / 2, top_left.1);
+                    let mid_y = prev.1 + node_height + FLOW_BADGES_HEIGHT + FLOW_ROW_GAP / 2;
+                    drawing_area.draw(&PathElement::new(
+                        vec![from, (turn_x, from.1), (turn_x, mid_y), (to.0, mid_y), to],
+                        BLACK.stroke_width(2),
+                    ))?;
+                    drawing_area.draw(&Polygon::new(
+                        vec![
+                            to,
+                            (to.0 - arrowhead_size / 2, to.1 - arrowhead_size),
+                            (to.0 + arrowhead_size / 2, to.1 - arrowhead_size),
+                        ],
+                        BLACK.filled(),
+                    ))?;
+                    draw_label(&rule_text, (turn_x + to.0) / 2, mid_y, turn_x - to.0)?;
+                }
+            }
+            current_y_offset = top + layout.height() + 100;
+        }
+
+        root_area.present()?;
+        Ok(())
+    })
+}
+
+/// One labor task of one occurrence of a stage, for the Gantt chart.
+struct GanttTask {
+    role_id: String,
+    start_hr: f64,
+    end_hr: f64,
+}
+
+/// The contiguous runs of the `stage_id` column of `records`, as (method id, first tick, tick
+/// after the last). A stage the workflow returns to, as in a campaign, has a span for each visit.
+fn record_stage_spans(records: &[LogRecord]) -> Vec<(&str, u64, u64)> {
+    let mut spans: Vec<(&str, u64, u64)> = Vec::new();
+    for record in records {
+        match spans.last_mut() {
+            Some((stage_id, _, end)) if *stage_id == record.stage_id => *end = record.tick + 1,
+            _ => {
+                if let Some((_, _, end)) = spans.last_mut() {
+                    *end = record.tick;
                 }
+                spans.push((&record.stage_id, record.tick, record.tick + 1));
             }
+        }
+    }
+    spans
+}
 
-            last_node_end_x = x_pos + node_width;
-            x_pos += node_width + x_gap;
+/// Draws a Gantt chart of the run logged in `records`: a row per method of `process` that ran,
+/// with a bar for each time its stage ran and, under it, the labor tasks the stage's asset
+/// requires, from the start of the stage and colored by role. The hours of each task are
+/// accounted as in the bill of materials, for the stage occurrence's length and the media volume
+/// at its start; tasks longer than their stage are cut at its end. Assets have no maintenance
+/// windows to show until maintenance is simulated.
+pub fn plot_gantt(
+    output_dir: &str,
+    config: &PlotConfig,
+    records: &[LogRecord],
+    process: &Process,
+    assets: &HashMap<String, Asset>,
+) -> Result<()> {
+    let method = |stage_id: &str| process.methods.iter().find(|m| m.method_id == stage_id);
+    // Rows logged before the workflow starts belong to no method.
+    let mut spans = record_stage_spans(records);
+    spans.retain(|(stage_id, _, _)| method(stage_id).is_some());
+    if spans.is_empty() {
+        return Ok(());
+    }
+    let mut row_ids: Vec<&str> = Vec::new();
+    for (stage_id, _, _) in &spans {
+        if !row_ids.contains(stage_id) {
+            row_ids.push(stage_id);
         }
-        current_y_offset += node_height as u32 + 100;
     }
 
-    root_area.present()?;
-    Ok(())
+    // The tasks of each row, in lanes by task.
+    let mut tasks: Vec<Vec<Vec<GanttTask>>> = row_ids.iter().map(|_| Vec::new()).collect();
+    let mut roles: Vec<String> = Vec::new();
+    for (stage_id, start, end) in &spans {
+        let row = row_ids.iter().position(|id| id == stage_id).unwrap_or(0);
+        let start_hr = *start as f64 * TIME_STEP_HR;
+        let end_hr = *end as f64 * TIME_STEP_HR;
+        let volume_l = records.iter().find(|r| r.tick == *start).map(|r| r.media_volume_l);
+        let requirements = method(stage_id)
+            .and_then(|m| assets.get(&m.required_asset_id))
+            .and_then(|asset| asset.operational_parameters.as_ref()?.labor_requirements.as_ref());
+        for (lane, requirement) in requirements.into_iter().flatten().enumerate() {
+            let Some(hours) = labor_requirement_hours(&requirement.duration, end_hr - start_hr, volume_l) else {
+                continue;
+            };
+            if !roles.contains(&requirement.required_role_id) {
+                roles.push(requirement.required_role_id.clone());
+            }
+            if tasks[row].len() <= lane {
+                tasks[row].resize_with(lane + 1, Vec::new);
+            }
+            tasks[row][lane].push(GanttTask {
+                role_id: requirement.required_role_id.clone(),
+                start_hr,
+                end_hr: (start_hr + hours).min(end_hr),
+            });
+        }
+    }
+
+    let rows = row_ids.len();
+    let label_area = 220;
+    render!(config, Figure::Gantt, output_dir, "17_gantt", (1024, 160 + 70 * rows as u32), |root| {
+        root.fill(&WHITE)?;
+        let max_hr = spans.last().map_or(1.0, |(_, _, end)| *end as f64 * TIME_STEP_HR);
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption(format!("Schedule: {}", process.process_name), ("sans-serif", config.caption_size(40)).into_font())
+            .margin(10)
+            .x_label_area_size(40)
+            .y_label_area_size(label_area)
+            .build_cartesian_2d(0f64..max_hr, 0f64..rows as f64)?;
+
+        chart.configure_mesh()
+            .disable_y_mesh()
+            .y_labels(0)
+            .x_desc("Time (hours)")
+            .draw()?;
+
+        // Rows run from the top down; each stage bar fills the top of its row and the task
+        // lanes share the rest.
+        let row_top = |row: usize| (rows - row) as f64;
+        let stage_color = SERIES_PALETTE[0];
+        chart.draw_series(spans.iter().map(|(stage_id, start, end)| {
+            let top = row_top(row_ids.iter().position(|id| id == stage_id).unwrap_or(0));
+            Rectangle::new(
+                [(*start as f64 * TIME_STEP_HR, top - 0.1), (*end as f64 * TIME_STEP_HR, top - 0.45)],
+                stage_color.mix(0.8).filled(),
+            )
+        }))?;
+        for (row, lanes) in tasks.iter().enumerate() {
+            let lane_height = 0.45 / lanes.len().max(1) as f64;
+            for (lane, lane_tasks) in lanes.iter().enumerate() {
+                let top = row_top(row) - 0.5 - lane as f64 * lane_height;
+                chart.draw_series(lane_tasks.iter().map(|task| {
+                    let role = roles.iter().position(|r| *r == task.role_id).unwrap_or(0);
+                    let (color, _) = config.series_style(role, &SERIES_PALETTE[1..]);
+                    Rectangle::new([(task.start_hr, top), (task.end_hr, top - lane_height * 0.8)], color.filled())
+                }))?;
+            }
+        }
+        for (i, role_id) in roles.iter().enumerate() {
+            let (color, _) = config.series_style(i, &SERIES_PALETTE[1..]);
+            chart
+                .draw_series(std::iter::empty::<Rectangle<(f64, f64)>>())?
+                .label(role_id.as_str())
+                .legend(move |(x, y)| Rectangle::new([(x, y - 5), (x + 15, y + 5)], color.filled()));
+        }
+
+        // Label the rows in the label area, right-aligned against the plot.
+        let label_font: FontDesc = ("sans-serif", config.label_size(13)).into_font();
+        let label_style = TextStyle::from(label_font.clone()).pos(Pos::new(HPos::Right, VPos::Center));
+        for (row, stage_id) in row_ids.iter().enumerate() {
+            let label = method(stage_id).map_or(stage_id.to_string(), |m| format!("{} ({})", m.technique, m.required_asset_id));
+            let (x, y) = chart.backend_coord(&(0.0, row_top(row) - 0.5));
+            let text = fit_text(&label, &label_font, label_area - 20);
+            root.draw_text(&text, &label_style, (x - 10, y))?;
+        }
+
+        if !roles.is_empty() {
+            chart
+                .configure_series_labels()
+                .position(SeriesLabelPosition::UpperRight)
+                .background_style(WHITE.mix(0.8))
+                .border_style(BLACK)
+                .draw()?;
+        }
+        root.present()?;
+        Ok(())
+    })
 }
 
-/// Generates a timeline graph of the upstream simulation, highlighting material infusion events.
+/// Generates a timeline graph of the upstream simulation, highlighting material infusion events
+/// and marking each stage transition.
 fn plot_upstream_timeline(
     output_dir: &str,
+    config: &PlotConfig,
     data: &[PlottingData],
+    events: Option<&[EventRecord]>,
+    stages: &[StageSpan],
+    axis: &TimeAxis,
 ) -> Result<()> {
-    let path = format!("{}/5_upstream_timeline.png", output_dir);
-    let root = BitMapBackend::new(&path, (1024, 256)).into_drawing_area();
-    root.fill(&WHITE)?;
-
-    let max_tick = data.last().map_or(1, |d| d.tick);
-
-    let mut chart = ChartBuilder::on(&root)
-        .caption("Upstream Infusion Events", ("sans-serif", 30).into_font())
-        .margin(20)
-        .x_label_area_size(40)
-        .y_label_area_size(20)
-        .build_cartesian_2d(0u64..max_tick, 0..2i32)?;
-
-    chart.configure_mesh()
-        .x_desc("Time (hours)")
-        .disable_y_axis()
-        .draw()?;
-
-    // Extract infusion events
-    let infusion_events: Vec<u64> = data.iter()
-        .filter_map(|d| {
-            if d.events.iter().any(|e| matches!(e, SimulationEvent::MaterialAdded { .. })) {
-                Some(d.tick)
-            } else {
-                None
+    render!(config, Figure::UpstreamTimeline, output_dir, "5_upstream_timeline", (1024, 256), |root| {
+        root.fill(&WHITE)?;
+
+        let max_tick = data.last().map_or(1, |d| d.tick);
+
+        let mut chart = ChartBuilder::on(&root)
+            .caption("Infusion Events", ("sans-serif", config.caption_size(30)).into_font())
+            .margin(20)
+            .x_label_area_size(40)
+            .y_label_area_size(20)
+            .build_cartesian_2d(0u64..max_tick, 0..2i32)?;
+
+        chart.configure_mesh()
+            .x_desc(axis.description())
+            .x_label_formatter(&|tick| axis.label(*tick))
+            .disable_y_axis()
+            .draw()?;
+
+        // Extract infusion events, from the event log when there is one
+        let mut infusion_events: Vec<u64> = match events {
+            Some(events) => events
+                .iter()
+                .filter(|e| e.event_type == "material_added")
+                .map(|e| e.tick)
+                .collect(),
+            None => data.iter()
+                .filter_map(|d| {
+                    if d.events.iter().any(|e| matches!(e, SimulationEvent::MaterialAdded { .. })) {
+                        Some(d.tick)
+                    } else {
+                        None
+                    }
+                })
+                .collect(),
+        };
+        infusion_events.dedup();
+
+        // Draw the histogram
+        chart.draw_series(
+            Histogram::vertical(&chart)
+                .style(EventCategory::Addition.color().filled())
+                .data(infusion_events.iter().map(|tick| (*tick, 1))),
+        )?;
+
+        // Mark where each stage begins
+        chart.draw_series(stages.iter().map(|stage| {
+            PathElement::new(
+                vec![(stage.start_tick, 0), (stage.start_tick, 2)],
+                EventCategory::StageChange.color().stroke_width(2),
+            )
+        }))?;
+        chart.draw_series(stages.iter().map(|stage| {
+            Text::new(
+                stage.label.clone(),
+                (stage.start_tick, 2),
+                ("sans-serif", config.label_size(12)).into_font().color(&EventCategory::StageChange.color()),
+            )
+        }))?;
+
+        root.present()?;
+        Ok(())
+    })
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::{scratch_dir, KNOWLEDGE_BASE};
+    use crate::config::KnowledgeBase;
+    use serde_json::json;
+
+    /// The media components of the fixture log: id, name, and concentration in g/L.
+    const COMPONENTS: [(&str, &str, f64); 5] = [
+        ("CHEBI:17234", "D-glucose", 20.0),
+        ("CHEBI:30089", "acetate", 0.5),
+        ("CHEBI:28938", "ammonium", 2.0),
+        ("CHEBI:18367", "phosphate", 1.0),
+        ("YEAST-EXTRACT", "yeast extract", 5.0),
+    ];
+
+    /// The gases of the fixture log, with their concentration in g/L.
+    const GASES: [(&str, f64); 2] = [("oxygen", 0.007), ("carbon dioxide", 0.002)];
+
+    /// The methods of the fixture log, each ten ticks long, and the asset each runs on.
+    const STAGES: [(&str, &str, &str); 3] = [
+        ("MTHD-SEED", "seed", "CULTIVATION-FLASK-01"),
+        ("MTHD-GROW", "fed-batch", "CULTIVATION-STR-01"),
+        ("MTHD-FILTER", "filtration", "TFF-01"),
+    ];
+
+    /// A process with a step for each of `stages` (method id, technique, asset id).
+    fn process(stages: &[(&str, &str, &str)]) -> Process {
+        let methods: Vec<String> = stages
+            .iter()
+            .map(|(method_id, technique, asset_id)| {
+                format!("{{ method_id: {method_id}, stage: {method_id}, technique: {technique}, required_asset_id: {asset_id}, operating_parameters: {{}}, required_materials: [], qc_checks: [], required_rule_ids: [] }}")
+            })
+            .collect();
+        let workflow: Vec<&str> = stages.iter().map(|(method_id, _, _)| *method_id).collect();
+        serde_yaml::from_str(&format!(
+            "{{ process_id: PROC-TEST, process_name: PROC-TEST, component_class: Test, status: Active, notes: '', default_workflow: [{}], methods: [{}] }}",
+            workflow.join(", "),
+            methods.join(", ")
+        ))
+        .unwrap()
+    }
+
+    /// The biomass of the fixture organism at `tick`: 1 mg at the start, growing tenfold every six
+    /// ticks to 100 g at tick 30.
+    fn biomass_at(tick: u64) -> f64 {
+        1e-3 * 10f64.powf(tick as f64 / 6.0)
+    }
+
+    /// A row of the fixture log at `tick`, carrying `events`.
+    fn record(tick: u64, events: Vec<SimulationEvent>) -> LogRecord {
+        let stage = (tick as usize / 10).min(STAGES.len() - 1);
+        let (stage_id, _, asset_id) = STAGES[stage];
+        let components: Vec<_> = COMPONENTS
+            .iter()
+            .map(|(id, name, value)| {
+                json!({ "molecule_id": id, "molecule_name": name, "concentration": { "value": value - 0.01 * tick as f64, "unit": "g/L" } })
+            })
+            .collect();
+        let gases: Vec<_> = GASES
+            .iter()
+            .map(|(name, value)| json!({ "gas_id": name, "gas_name": name, "concentration": { "value": value, "unit": "g/L" } }))
+            .collect();
+        serde_json::from_value(json!({
+            "tick": tick,
+            "stage_id": stage_id,
+            "organisms_json": json!({ "ORG-TEST": { "biomass": { "value": biomass_at(tick), "unit": "g" } } }).to_string(),
+            "media_volume_l": 10.0,
+            "media_ph": 7.0 - 0.01 * tick as f64,
+            "dissolved_components_json": serde_json::to_string(&components).unwrap(),
+            "dissolved_gases_json": serde_json::to_string(&gases).unwrap(),
+            "asset_states_json": json!({ asset_id: { "temperature": 30.0 + stage as f64 } }).to_string(),
+            "events_json": serde_json::to_string(&events).unwrap(),
+            "growth_rates_json": json!({ "ORG-TEST": 10f64.ln() / 6.0 }).to_string(),
+        }))
+        .unwrap()
+    }
+
+    /// Thirty ticks through the three [`STAGES`], with the stage changes logged and three
+    /// additions at tick 5 on top of one another.
+    fn fixture_log() -> Vec<LogRecord> {
+        let stage_changed = |tick: u64, from: Option<usize>, to: usize| SimulationEvent::StageChanged {
+            from_method_id: from.map(|i| STAGES[i].0.to_string()),
+            to_method_id: Some(STAGES[to].0.to_string()),
+            tick,
+        };
+        let added = |i: usize, amount: f64| SimulationEvent::MaterialAdded { id: COMPONENTS[i].0.to_string(), amount };
+        (0..=30)
+            .map(|tick| {
+                let events = match tick {
+                    0 => vec![stage_changed(0, None, 0)],
+                    5 => vec![added(0, 100.0), added(2, 20.0), added(3, 10.0)],
+                    10 => vec![stage_changed(10, Some(0), 1)],
+                    20 => vec![stage_changed(20, Some(1), 2)],
+                    _ => vec![],
+                };
+                record(tick, events)
+            })
+            .collect()
+    }
+
+    fn media_names() -> HashMap<String, String> {
+        COMPONENTS.iter().map(|(id, name, _)| (id.to_string(), name.to_string())).collect()
+    }
+
+    fn organism_names() -> HashMap<String, String> {
+        HashMap::from([("ORG-TEST".to_string(), "Test organism".to_string())])
+    }
+
+    /// Draws every upstream chart of `records` into a scratch directory for the test `name`, in
+    /// `output`, and returns the directory.
+    fn render_upstream(name: &str, output: PlotFormat, records: &[LogRecord], targets: &[TargetGoal]) -> PathBuf {
+        let dir = scratch_dir(name);
+        let config = PlotConfig { output, ..Default::default() };
+        let saturations = BTreeMap::from([("oxygen".to_string(), 7.5)]);
+        generate_all_plots(
+            &dir.to_string_lossy(),
+            &config,
+            records,
+            None,
+            &CogsResult::default(),
+            &LcaResult::default(),
+            organism_names(),
+            &HashMap::from([("ORG-TEST".to_string(), 0.5)]),
+            media_names(),
+            &MediaSelection::default(),
+            &process(&STAGES).methods,
+            &saturations,
+            BiomassScale::Both,
+            targets,
+            false,
+        )
+        .unwrap();
+        dir
+    }
+
+    /// The text drawn on the SVG chart at `path`, which must be well-formed XML.
+    fn svg_texts(path: &Path) -> Vec<String> {
+        let svg = std::fs::read_to_string(path).unwrap();
+        let document = roxmltree::Document::parse(&svg).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+        document
+            .descendants()
+            .filter(|node| node.has_tag_name("text"))
+            .filter_map(|node| Some(node.text()?.trim().to_string()))
+            .collect()
+    }
+
+    /// The charts every upstream run with stages, gases and events draws.
+    const UPSTREAM_STEMS: [&str; 7] = [
+        "1_biomass_growth",
+        "1_biomass_growth_log",
+        "2_media_composition",
+        "3_environmental_parameters",
+        "5_upstream_timeline",
+        "6_specific_growth_rate",
+        "12_dissolved_gases",
+    ];
+
+    #[test]
+    fn every_upstream_chart_renders_as_png_and_as_well_formed_svg() {
+        let records = fixture_log();
+        let png = render_upstream("plots_png", PlotFormat::Png { width: 800, height: 600 }, &records, &[]);
+        for stem in UPSTREAM_STEMS {
+            let bytes = std::fs::read(png.join(format!("{}.png", stem))).unwrap();
+            assert!(bytes.starts_with(b"\x89PNG"), "{} is not a PNG", stem);
+        }
+
+        let svg = render_upstream("plots_svg", PlotFormat::Svg { width: 800, height: 600 }, &records, &[]);
+        for stem in UPSTREAM_STEMS {
+            assert!(!svg_texts(&svg.join(format!("{}.svg", stem))).is_empty(), "{} has no text", stem);
+        }
+        let document = std::fs::read_to_string(svg.join("1_biomass_growth.svg")).unwrap();
+        assert_eq!(roxmltree::Document::parse(&document).unwrap().root_element().attribute("width"), Some("800"));
+    }
+
+    #[test]
+    fn a_media_of_fewer_components_than_the_chart_shows_has_every_one_charted() {
+        let records = fixture_log();
+        let data = plotting_data(&records, &media_names(), &organism_names(), &HashMap::new()).unwrap();
+        let mut picked: Vec<String> = MediaSelection::default().components_in(&data).into_iter().map(|(name, _)| name).collect();
+        picked.sort();
+        let mut names: Vec<&str> = COMPONENTS.iter().map(|(_, name, _)| *name).collect();
+        names.sort();
+        assert_eq!(picked, names);
+
+        let dir = render_upstream("plots_media", PlotFormat::Svg { width: 1024, height: 768 }, &records, &[]);
+        let texts = svg_texts(&dir.join("2_media_composition.svg"));
+        for name in names {
+            assert!(texts.iter().any(|text| text == name), "{} is not in the legend: {:?}", name, texts);
+        }
+    }
+
+    #[test]
+    fn the_gas_legend_has_every_gas_and_each_saturation_known() {
+        let dir = render_upstream("plots_gases", PlotFormat::Svg { width: 1024, height: 768 }, &fixture_log(), &[]);
+        let texts = svg_texts(&dir.join("12_dissolved_gases.svg"));
+        for label in ["oxygen (mg/L)", "carbon dioxide (mg/L)", "oxygen saturation"] {
+            assert!(texts.iter().any(|text| text == label), "{} is not in the legend: {:?}", label, texts);
+        }
+        assert!(!texts.iter().any(|text| text == "carbon dioxide saturation"), "{:?}", texts);
+    }
+
+    #[test]
+    fn events_on_top_of_one_another_are_labelled_once_and_long_labels_are_cut() {
+        let mut records = fixture_log();
+        // A burst of transfers on the last tick, against the right edge of the charts.
+        let transfer = SimulationEvent::MaterialTransferred {
+            from_asset_id: "CULTIVATION-STR-01-WITH-A-VERY-LONG-NAME".to_string(),
+            to_asset_id: "TFF-01".to_string(),
+            volume_l: 10.0,
+        };
+        records[30].events_json = serde_json::to_string(&vec![transfer; 4]).unwrap();
+        render_upstream("plots_events_png", PlotFormat::Png { width: 1024, height: 768 }, &records, &[]);
+        let dir = render_upstream("plots_events_svg", PlotFormat::Svg { width: 1024, height: 768 }, &records, &[]);
+
+        let texts = svg_texts(&dir.join("1_biomass_growth.svg"));
+        let count = |label: &str| texts.iter().filter(|text| *text == label).count();
+        assert_eq!(count("+100 g D-glucose"), 1, "{:?}", texts);
+        assert_eq!(count("+20 g ammonium"), 0, "{:?}", texts);
+        let transfer_label: String = "10.0 L CULTIVATION-STR-01-WITH-A-VERY-LONG-NAME → TFF-01".chars().take(MAX_ANNOTATION_LABEL_CHARS).collect();
+        assert_eq!(count(&transfer_label), 1, "{:?}", texts);
+    }
+
+    #[test]
+    fn the_log_chart_spans_every_decade_of_five_orders_of_magnitude() {
+        let dir = render_upstream("plots_log", PlotFormat::Svg { width: 1024, height: 768 }, &fixture_log(), &[]);
+        let texts = svg_texts(&dir.join("1_biomass_growth_log.svg"));
+        for label in ["0.001", "0.01", "0.1", "1", "10", "100"] {
+            assert!(texts.iter().any(|text| text == label), "no {} on the axis: {:?}", label, texts);
+        }
+        // Everything is above the floor, so nothing is drawn at it.
+        assert!(!texts.iter().any(|text| text.starts_with("Biomass below")), "{:?}", texts);
+    }
+
+    #[test]
+    fn log_axis_labels_have_the_decimals_their_magnitude_needs() {
+        assert_eq!(log_axis_label(1e-4), "0.0001");
+        assert_eq!(log_axis_label(0.5), "0.5");
+        assert_eq!(log_axis_label(1.0), "1");
+        assert_eq!(log_axis_label(1e5), "100000");
+    }
+
+    #[test]
+    fn stage_bands_run_from_each_stage_change_to_the_next() {
+        let methods = process(&STAGES).methods;
+        let spans = |records: &[LogRecord]| {
+            let data = plotting_data(records, &media_names(), &organism_names(), &HashMap::new()).unwrap();
+            stage_spans(&data, &methods)
+                .into_iter()
+                .map(|span| (span.label, span.start_tick, span.end_tick))
+                .collect::<Vec<_>>()
+        };
+        let expected = vec![
+            ("seed".to_string(), 0, 10),
+            ("fed-batch".to_string(), 10, 20),
+            ("filtration".to_string(), 20, 30),
+        ];
+        let mut records = fixture_log();
+        assert_eq!(spans(&records), expected);
+
+        // Without stage change events, the bands come from the stage of each row.
+        for record in &mut records {
+            record.events_json = "[]".to_string();
+        }
+        assert_eq!(spans(&records), expected);
+
+        // A method the process does not know is labelled by its id.
+        for record in &mut records[25..] {
+            record.stage_id = "MTHD-UNKNOWN".to_string();
+        }
+        assert_eq!(spans(&records).last(), Some(&("MTHD-UNKNOWN".to_string(), 25, 30)));
+
+        // The workflow completing ends the last band, though rows are logged after it.
+        let mut records = fixture_log();
+        records[26].events_json = serde_json::to_string(&[SimulationEvent::StageChanged {
+            from_method_id: Some("MTHD-FILTER".to_string()),
+            to_method_id: None,
+            tick: 26,
+        }])
+        .unwrap();
+        assert_eq!(spans(&records).last(), Some(&("filtration".to_string(), 20, 26)));
+
+        // A run that never leaves its first stage has no bands.
+        assert_eq!(spans(&records[..10]), vec![]);
+    }
+
+    #[test]
+    fn process_flow_nodes_stay_on_the_canvas_however_long_the_workflow() {
+        for steps in [2, 6, 15] {
+            let stages: Vec<(String, String)> =
+                (0..steps).map(|i| (format!("MTHD-STEP-{:02}", i), format!("technique with a long name {}", i))).collect();
+            let stages: Vec<(&str, &str, &str)> =
+                stages.iter().map(|(method_id, technique)| (method_id.as_str(), technique.as_str(), "CULTIVATION-STR-01")).collect();
+            let dir = scratch_dir(&format!("plots_flow_{}", steps));
+            let config = PlotConfig { output: PlotFormat::Svg { width: 1024, height: 768 }, ..Default::default() };
+            plot_process_flow(&dir.to_string_lossy(), &config, &[&process(&stages)], &HashMap::new()).unwrap();
+
+            let svg = std::fs::read_to_string(dir.join("4_process_flow.svg")).unwrap();
+            let document = roxmltree::Document::parse(&svg).unwrap();
+            let size = |name: &str| document.root_element().attribute(name).unwrap().parse::<f64>().unwrap();
+            let (width, height) = (size("width"), size("height"));
+            let nodes: Vec<[f64; 4]> = document
+                .descendants()
+                .filter(|node| node.has_tag_name("rect") && node.attribute("fill") == Some("#4682B4"))
+                .map(|node| ["x", "y", "width", "height"].map(|name| node.attribute(name).unwrap().parse().unwrap()))
+                .collect();
+            assert_eq!(nodes.len(), steps);
+            for [x, y, w, h] in nodes {
+                assert!(x >= 0.0 && y >= 0.0 && x + w <= width && y + h <= height, "{} steps: node at {:?} off a {}×{} canvas", steps, [x, y, w, h], width, height);
             }
-        })
-        .collect();
+        }
+    }
 
-    // Draw the histogram
-    chart.draw_series(
-        Histogram::vertical(&chart)
-            .style(BLUE.filled())
-            .data(infusion_events.iter().map(|tick| (*tick, 1))),
-    )?;
+    #[test]
+    fn a_target_is_met_at_the_first_tick_it_is_reached_or_never() {
+        // 10 mg/g of the biomass, 0.1 g at tick 24 and 1 g at the end.
+        let product = TargetProduct { molecule_name: "lutein".to_string(), organism_id: "ORG-TEST".to_string(), content_mg_g: 10.0 };
+        let records = fixture_log();
+        let data = plotting_data(&records, &media_names(), &organism_names(), &HashMap::new()).unwrap();
+        let progress = product_progress(&data, &product);
+        assert_eq!(progress.len(), records.len());
+        assert!((progress[24].1 - 0.1).abs() < 1e-9);
+        assert_eq!(target_met_at(&progress, 0.09), Some(24));
+        assert_eq!(target_met_at(&progress, 5.0), None);
+
+        let targets = [
+            TargetGoal { product: product.clone(), target_grams: 0.09 },
+            TargetGoal { product, target_grams: 5.0 },
+        ];
+        let dir = render_upstream("plots_targets", PlotFormat::Svg { width: 1024, height: 768 }, &records, &targets);
+        let texts = svg_texts(&dir.join("16_target_progress.svg"));
+        for caption in ["lutein: 0.09 g requested, met at 24 h", "lutein: 5.00 g requested, not met, 1.00 g at the end"] {
+            assert!(texts.iter().any(|text| text == caption), "no {:?} in {:?}", caption, texts);
+        }
+    }
 
-    root.present()?;
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn the_tidy_time_series_has_a_row_per_series_and_tick() {
+        let records = fixture_log();
+        let dir = scratch_dir("plots_tidy");
+        let path = write_tidy_timeseries(&dir.to_string_lossy(), &records, &media_names(), &organism_names(), &process(&STAGES).methods).unwrap();
+
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        assert_eq!(reader.headers().unwrap(), vec!["tick", "series_type", "series_name", "value"]);
+        let rows: Vec<(u64, String, String, f64)> = reader.deserialize().map(Result::unwrap).collect();
+        // Biomass, five components, two gases, pH and temperature at each of the 31 ticks.
+        assert_eq!(rows.len(), 31 * (1 + COMPONENTS.len() + GASES.len() + 2));
+
+        let value = |tick: u64, series_type: &str, series_name: &str| {
+            rows.iter()
+                .find(|row| row.0 == tick && row.1 == series_type && row.2 == series_name)
+                .unwrap_or_else(|| panic!("no {} {} at tick {}", series_type, series_name, tick))
+                .3
+        };
+        assert!((value(12, "biomass", "ORG-TEST") - biomass_at(12)).abs() < 1e-12);
+        assert!((value(12, "dissolved_component", "D-glucose") - 19.88).abs() < 1e-12);
+        assert!((value(12, "dissolved_gas", "oxygen") - 7.0).abs() < 1e-12);
+        assert!((value(12, "ph", "media") - 6.88).abs() < 1e-12);
+        assert_eq!(value(12, "temperature", "CULTIVATION-STR-01"), 31.0);
+        assert_eq!(value(25, "temperature", "TFF-01"), 32.0);
+    }
+
+    #[test]
+    fn the_gantt_chart_has_a_row_per_stage_and_a_bar_per_visit() {
+        let mut records = fixture_log();
+        assert_eq!(record_stage_spans(&records), vec![("MTHD-SEED", 0, 10), ("MTHD-GROW", 10, 20), ("MTHD-FILTER", 20, 31)]);
+
+        // The workflow goes back to growing for the last ticks, as a campaign would.
+        for record in &mut records[25..] {
+            record.stage_id = "MTHD-GROW".to_string();
+        }
+        assert_eq!(
+            record_stage_spans(&records),
+            vec![("MTHD-SEED", 0, 10), ("MTHD-GROW", 10, 20), ("MTHD-FILTER", 20, 25), ("MTHD-GROW", 25, 31)]
+        );
+
+        let kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let dir = scratch_dir("plots_gantt");
+        let config = PlotConfig { output: PlotFormat::Svg { width: 1024, height: 768 }, ..Default::default() };
+        plot_gantt(&dir.to_string_lossy(), &config, &records, &process(&STAGES), &kb.assets).unwrap();
+        let texts = svg_texts(&dir.join("17_gantt.svg"));
+        for label in ["seed (CULTIVATION-FLASK-01)", "fed-batch (CULTIVATION-STR-01)", "filtration (TFF-01)"] {
+            assert_eq!(texts.iter().filter(|text| *text == label).count(), 1, "{} in {:?}", label, texts);
+        }
+        assert!(texts.iter().any(|text| text == "LABOR-OPERATOR-2"), "no labor role in the legend: {:?}", texts);
+    }
+}
@@ -0,0 +1,494 @@
+//! A single self-contained HTML report of a run directory, for sharing a run without its loose
+//! files. Plots are embedded, PNGs as base64 and SVGs inline, so the page opens offline.
+use crate::config::{DataFormat, KnowledgeBase};
+use crate::jit::ValorizationRequest;
+use crate::plotting::TIDY_TIMESERIES_FILE;
+use anyhow::{Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use bioforge_core::simulation::engine::TIME_STEP_HR;
+use chrono::{DateTime, Utc};
+use bioforge_core::analysis::{
+    impact_category_name,
+    constraints::ConstraintCheck,
+    manifest::RunManifest,
+    performance::ProcessPerformance,
+    report::{AnalysisReport, NamedBom, Versioned},
+    CogsResult, LcaResult,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The file, in the run directory, that [`RunMetadata`] is written to.
+pub const METADATA_FILE: &str = "run_metadata.json";
+
+/// The file, in the run directory, that [`generate_html`] writes.
+pub const HTML_REPORT_FILE: &str = "report.html";
+
+/// When a run happened, as RFC 3339 times, and which knowledge base it used.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunMetadata {
+    /// The start of the batch.
+    pub started_at: String,
+    /// The simulated end of the upstream cultivation, when downstream processing begins.
+    pub upstream_ended_at: String,
+    /// The wall-clock time the reports were written.
+    pub reported_at: String,
+    /// See [`crate::config::KnowledgeBase::content_hash`].
+    pub knowledge_base_hash: String,
+    /// The currency the costs of the run's reports are in.
+    pub currency: String,
+}
+
+/// Writes the [`RunManifest`] of a run of `request`, read from `request_source`, against `kb`,
+/// started at `start_time` and drawing with `seed` if it draws at all, to
+/// [`MANIFEST_FILE`](bioforge_core::analysis::manifest::MANIFEST_FILE) in `output_dir`.
+pub fn write_manifest(
+    output_dir: &str,
+    request: &ValorizationRequest,
+    request_source: &str,
+    kb: &KnowledgeBase,
+    seed: Option<u64>,
+    start_time: DateTime<Utc>,
+) -> Result<()> {
+    let crate_versions = [
+        ("bioforge-app", env!("CARGO_PKG_VERSION")),
+        ("bioforge-core", bioforge_core::VERSION),
+        ("bioforge-schemas", bioforge_schemas::VERSION),
+    ];
+    let manifest = RunManifest {
+        request: serde_json::to_value(request)?,
+        request_source: request_source.to_string(),
+        knowledge_base_files: kb.file_hashes.clone(),
+        knowledge_base_overlays: kb.overlays.clone(),
+        knowledge_base_overrides: kb.overrides.clone(),
+        crate_versions: crate_versions.into_iter().map(|(name, version)| (name.to_string(), version.to_string())).collect(),
+        seed,
+        time_step_hr: TIME_STEP_HR,
+        started_at: start_time.to_rfc3339(),
+        git_describe: option_env!("BIOFORGE_GIT_DESCRIBE").map(str::to_string),
+    };
+    manifest.write(output_dir)?;
+    Ok(())
+}
+
+/// The sections of the report, by anchor and title, in order.
+const SECTIONS: [(&str, &str); 9] = [
+    ("metadata", "Run"),
+    ("targets", "Targets"),
+    ("kpis", "Cultivation KPIs"),
+    ("bom", "Bill of Materials"),
+    ("cogs", "Cost of Goods Sold"),
+    ("lca", "Life Cycle Impacts"),
+    ("qca", "Quality Control"),
+    ("plots", "Plots"),
+    ("request", "Request"),
+];
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em auto;max-width:1100px;color:#222}\
+table{border-collapse:collapse;margin:1em 0}th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+th{background:#f0f0f0}td.num{text-align:right}figure{margin:2em 0}img,svg{max-width:100%;height:auto}\
+pre{background:#f6f6f6;padding:1em;overflow-x:auto}nav a{margin-right:1em}";
+
+/// The name, without its extension, of the copy of the request a run keeps in its directory.
+pub const REQUEST_FILE_STEM: &str = "request";
+
+/// The request kept in `run_dir`: `request.json` if the run read it as JSON, else
+/// `request.yaml`.
+pub fn request_file(run_dir: &Path) -> PathBuf {
+    let json = run_dir.join(format!("{}.{}", REQUEST_FILE_STEM, DataFormat::Json.extension()));
+    if json.is_file() {
+        json
+    } else {
+        run_dir.join(format!("{}.{}", REQUEST_FILE_STEM, DataFormat::Yaml.extension()))
+    }
+}
+
+/// Writes [`HTML_REPORT_FILE`] into `run_dir` from the reports and plots the workflow wrote there,
+/// and returns its path. Sections whose report is missing, such as the KPIs of a run that
+/// produced none, say so rather than failing.
+pub fn generate_html(run_dir: &str) -> Result<PathBuf> {
+    let dir = Path::new(run_dir);
+    let title = dir.file_name().map_or(run_dir.to_string(), |name| name.to_string_lossy().into_owned());
+    let request_path = request_file(dir);
+    let request_text = fs::read_to_string(&request_path).ok();
+    let request_format = DataFormat::of(&request_path).unwrap_or(DataFormat::Yaml);
+    let request: Option<ValorizationRequest> =
+        request_text.as_deref().and_then(|text| request_format.parse(text).ok());
+    let performance: Option<ProcessPerformance> = read_json(dir, "kpis.json")?;
+    let metadata: Option<RunMetadata> = read_json(dir, METADATA_FILE)?;
+    let analysis: Option<Versioned<AnalysisReport>> = read_json(dir, "report.json")?;
+    let constraints = analysis.map(|a| a.report.constraints).unwrap_or_default();
+    let currency = metadata.as_ref().map_or("USD", |m| m.currency.as_str());
+
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(html, "<title>{}</title>\n<style>{}</style>\n</head>\n<body>", escape(&title), STYLE)?;
+    writeln!(html, "<h1>{}</h1>\n<nav>", escape(&title))?;
+    for (anchor, heading) in SECTIONS {
+        write!(html, "<a href=\"#{}\">{}</a>", anchor, heading)?;
+    }
+    writeln!(html, "</nav>")?;
+
+    for (anchor, heading) in SECTIONS {
+        writeln!(html, "<section id=\"{}\">\n<h2>{}</h2>", anchor, heading)?;
+        let body = match anchor {
+            "metadata" => metadata_section(metadata.as_ref()),
+            "targets" => targets_section(request.as_ref(), performance.as_ref(), &constraints),
+            "kpis" => kpis_section(performance.as_ref()),
+            "bom" => bom_section(read_json(dir, "bom.json")?),
+            "cogs" => cogs_section(read_json(dir, "cogs.json")?, currency),
+            "lca" => lca_section(read_json(dir, "lca.json")?),
+            "qca" => fs::read_to_string(dir.join("qca_report.md")).ok().map_or_else(missing, |md| markdown_table(&md)),
+            "plots" => plots_section(dir)?,
+            _ => request_text.as_deref().map_or_else(missing, |text| format!("<pre>{}</pre>", escape(text))),
+        };
+        writeln!(html, "{}\n</section>", body)?;
+    }
+    writeln!(html, "</body>\n</html>")?;
+
+    let path = dir.join(HTML_REPORT_FILE);
+    fs::write(&path, html).with_context(|| format!("Failed to write {:?}", path))?;
+    Ok(path)
+}
+
+/// The report `name` of `dir`, or `None` if the workflow did not write it.
+fn read_json<T: DeserializeOwned>(dir: &Path, name: &str) -> Result<Option<T>> {
+    let path = dir.join(name);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&contents).with_context(|| format!("Failed to parse {:?}", path))?))
+}
+
+fn missing() -> String {
+    "<p><em>Not available for this run.</em></p>".to_string()
+}
+
+/// Escapes `text` for HTML content and attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// A table with a header row of `headers` and a row per entry of `rows`. Cells that parse as
+/// numbers are right-aligned.
+fn table(headers: &[&str], rows: &[Vec<String>]) -> String {
+    let mut html = String::from("<table>\n<tr>");
+    for header in headers {
+        let _ = write!(html, "<th>{}</th>", escape(header));
+    }
+    html.push_str("</tr>\n");
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            let class = if cell.trim_end_matches('%').parse::<f64>().is_ok() { " class=\"num\"" } else { "" };
+            let _ = write!(html, "<td{}>{}</td>", class, escape(cell));
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn metadata_section(metadata: Option<&RunMetadata>) -> String {
+    let Some(metadata) = metadata else {
+        return missing();
+    };
+    let rows = vec![
+        vec!["Batch start".to_string(), metadata.started_at.clone()],
+        vec!["Downstream start".to_string(), metadata.upstream_ended_at.clone()],
+        vec!["Reported at".to_string(), metadata.reported_at.clone()],
+        vec!["Knowledge base hash".to_string(), metadata.knowledge_base_hash.clone()],
+    ];
+    table(&["", "Value"], &rows)
+}
+
+fn targets_section(
+    request: Option<&ValorizationRequest>,
+    performance: Option<&ProcessPerformance>,
+    constraints: &[ConstraintCheck],
+) -> String {
+    let Some(request) = request else {
+        return missing();
+    };
+    let rows: Vec<Vec<String>> = request
+        .targets
+        .iter()
+        .map(|target| {
+            let produced: f64 = performance
+                .iter()
+                .flat_map(|p| &p.products)
+                .filter(|p| p.molecule_name.eq_ignore_ascii_case(&target.molecule_name))
+                .map(|p| p.produced_g)
+                .sum();
+            vec![
+                target.molecule_name.clone(),
+                target.process_id.clone(),
+                format!("{:.2}", target.target_amount_grams),
+                format!("{:.2}", produced),
+                format!("{:.1}%", produced / target.target_amount_grams * 100.0),
+            ]
+        })
+        .collect();
+    let mut html = table(&["Target", "Process", "Requested (g)", "Produced (g)", "Of target"], &rows);
+    if !constraints.is_empty() {
+        let rows: Vec<Vec<String>> = constraints
+            .iter()
+            .map(|check| {
+                vec![
+                    check.quantity.to_string(),
+                    format!("{:.2}", check.limit),
+                    format!("{:.2}", check.actual),
+                    if check.passed { "PASS" } else { "FAIL" }.to_string(),
+                ]
+            })
+            .collect();
+        html.push_str("\n<h3>Constraints</h3>\n");
+        html.push_str(&table(&["Constraint", "Limit", "Actual", "Result"], &rows));
+    }
+    html
+}
+
+fn kpis_section(performance: Option<&ProcessPerformance>) -> String {
+    let Some(performance) = performance else {
+        return missing();
+    };
+    let optional = |value: Option<f64>| value.map_or("n/a".to_string(), |v| format!("{:.4}", v));
+    let rows: Vec<Vec<String>> = performance
+        .products
+        .iter()
+        .map(|p| {
+            vec![
+                p.molecule_name.clone(),
+                p.organism_id.clone(),
+                format!("{:.2}", p.produced_g),
+                format!("{:.4}", p.titer_g_l),
+                format!("{:.6}", p.space_time_yield_g_l_h),
+                optional(p.yield_product_per_biomass),
+                optional(p.yield_biomass_per_substrate),
+            ]
+        })
+        .collect();
+    let mut html = format!("<p>Batch of {:.0} h.</p>\n", performance.batch_duration_hr);
+    html += &table(
+        &["Product", "Organism", "Produced (g)", "Titer (g/L)", "Space-time yield (g/L/h)", "Yp/x (g/g)", "Yx/s (g/g)"],
+        &rows,
+    );
+    html
+}
+
+fn bom_section(bom: Option<Versioned<NamedBom>>) -> String {
+    let Some(Versioned { report: named, .. }) = bom else {
+        return missing();
+    };
+    let name_of = |id: &String| named.material_names.get(id).cloned().unwrap_or_else(|| id.clone());
+    let mut materials: Vec<(&String, &f64)> = named.bom.materials_consumed.iter().collect();
+    materials.sort_by(|a, b| b.1.total_cmp(a.1).then(a.0.cmp(b.0)));
+    let rows: Vec<Vec<String>> = materials
+        .into_iter()
+        .map(|(id, grams)| vec![name_of(id), id.clone(), format!("{:.4}", grams / 1000.0)])
+        .collect();
+    let mut html = format!(
+        "<p>{:.2} kWh of energy over {} hours.</p>\n",
+        named.bom.total_energy_kwh, named.bom.total_ticks
+    );
+    html += &table(&["Material", "Id", "Consumed (kg)"], &rows);
+    html
+}
+
+fn cogs_section(cogs: Option<Versioned<CogsResult>>, currency: &str) -> String {
+    let Some(Versioned { report: cogs, .. }) = cogs else {
+        return missing();
+    };
+    let lines = [
+        ("Materials", cogs.material_costs),
+        ("Labor", cogs.labor_costs),
+        ("Energy", cogs.energy_costs),
+        ("Depreciation", cogs.asset_depreciation_costs),
+        ("Maintenance", cogs.maintenance_costs),
+        ("End of life", cogs.end_of_life_costs),
+        ("QC", cogs.qc_costs),
+        ("Total", cogs.total_cogs),
+    ];
+    let rows: Vec<Vec<String>> = lines
+        .iter()
+        .map(|(name, cost)| {
+            let share = if cogs.total_cogs != 0.0 { cost / cogs.total_cogs * 100.0 } else { 0.0 };
+            vec![name.to_string(), format!("{:.2}", cost), format!("{:.1}%", share)]
+        })
+        .collect();
+    table(&["Line", &format!("Cost ({})", currency), "Share"], &rows)
+}
+
+fn lca_section(lca: Option<Versioned<LcaResult>>) -> String {
+    let Some(Versioned { report: lca, .. }) = lca else {
+        return missing();
+    };
+    let mut impacts: Vec<(&String, &(f64, String))> = lca.impacts.iter().collect();
+    impacts.sort_by_key(|(metric, _)| metric.as_str());
+    let rows: Vec<Vec<String>> = impacts
+        .into_iter()
+        .map(|(metric, (value, unit))| vec![impact_category_name(metric).to_string(), format!("{:.4}", value), unit.clone()])
+        .collect();
+    let mut html = table(&["Impact category", "Total", "Unit"], &rows);
+    if !lca.unmatched_materials.is_empty() {
+        let _ = write!(
+            html,
+            "\n<p>Materials without impact data: {}.</p>",
+            escape(&lca.unmatched_materials.join(", "))
+        );
+    }
+    html
+}
+
+/// Renders the pipe table of a Markdown report, such as `qca_report.md`, as an HTML table.
+fn markdown_table(markdown: &str) -> String {
+    let mut rows: Vec<Vec<String>> = markdown
+        .lines()
+        .filter(|line| line.trim_start().starts_with('|'))
+        .filter(|line| !line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')))
+        .map(|line| {
+            line.trim().trim_matches('|').split('|').map(|cell| cell.trim().trim_matches('*').to_string()).collect()
+        })
+        .collect();
+    if rows.is_empty() {
+        return missing();
+    }
+    let headers = rows.remove(0);
+    table(&headers.iter().map(String::as_str).collect::<Vec<_>>(), &rows)
+}
+
+/// Embeds every plot of `dir` and of its downstream subdirectories, the run's own first, each
+/// directory's plots after a link to the data they were drawn from.
+fn plots_section(dir: &Path) -> Result<String> {
+    let mut dirs = vec![dir.to_path_buf()];
+    let mut subdirs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir() && path.file_name().is_some_and(|name| name.to_string_lossy().starts_with("downstream_")))
+        .collect();
+    subdirs.sort();
+    dirs.extend(subdirs);
+
+    let mut html = String::new();
+    for plot_dir in dirs {
+        let mut plots: Vec<PathBuf> = fs::read_dir(&plot_dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "png" || ext == "svg"))
+            .collect();
+        // Plots are numbered by figure; sort by that number so 10 follows 9.
+        plots.sort_by_key(|path| {
+            let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+            let number = stem.split('_').next().and_then(|n| n.parse::<u32>().ok()).unwrap_or(u32::MAX);
+            (number, stem)
+        });
+        if plots.is_empty() {
+            continue;
+        }
+        let subdir = plot_dir.strip_prefix(dir).unwrap_or(&plot_dir).to_string_lossy().into_owned();
+        if !subdir.is_empty() {
+            writeln!(html, "<h3>{}</h3>", escape(&subdir))?;
+        }
+        if plot_dir.join(TIDY_TIMESERIES_FILE).is_file() {
+            let href = Path::new(&subdir).join(TIDY_TIMESERIES_FILE);
+            writeln!(html, "<p>Time series data: <a href=\"{0}\">{0}</a></p>", escape(&href.to_string_lossy()))?;
+        }
+        for plot in plots {
+            let name = plot.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            let image = if plot.extension().is_some_and(|ext| ext == "svg") {
+                fs::read_to_string(&plot)?
+            } else {
+                format!("<img alt=\"{}\" src=\"data:image/png;base64,{}\">", escape(&name), STANDARD.encode(fs::read(&plot)?))
+            };
+            writeln!(html, "<figure>\n{}\n<figcaption>{}</figcaption>\n</figure>", image, escape(&name))?;
+        }
+    }
+    Ok(if html.is_empty() { missing() } else { html })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::{knowledge_base_copy, scratch_dir, KNOWLEDGE_BASE};
+    use bioforge_core::analysis::{calculate_cogs, manifest::MANIFEST_FILE, BillOfMaterials};
+
+    const MATERIALS_FILE: &str = "1_materials/1.a.upstream_media_dictionary.yaml";
+
+    fn request() -> ValorizationRequest {
+        serde_yaml::from_str(&fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/request.yaml")).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn the_manifest_records_the_run_and_hashes_every_knowledge_base_file() {
+        let kb_dir = knowledge_base_copy("manifest_kb");
+        let kb = KnowledgeBase::load_with_overlays(&kb_dir.to_string_lossy(), &[]).unwrap();
+        let run_dir = scratch_dir("manifest_run");
+        let run_dir = run_dir.to_string_lossy();
+        let start_time = DateTime::parse_from_rfc3339("2026-03-01T08:00:00Z").unwrap().with_timezone(&Utc);
+        write_manifest(&run_dir, &request(), "--request request.yaml", &kb, Some(42), start_time).unwrap();
+
+        let text = fs::read_to_string(Path::new(run_dir.as_ref()).join(MANIFEST_FILE)).unwrap();
+        assert!(serde_json::from_str::<serde_json::Value>(&text).is_ok());
+        let manifest = RunManifest::read(&run_dir).unwrap().unwrap();
+        assert_eq!(manifest.request, serde_json::to_value(request()).unwrap());
+        assert_eq!(manifest.request_source, "--request request.yaml");
+        assert_eq!(manifest.seed, Some(42));
+        assert_eq!(manifest.time_step_hr, TIME_STEP_HR);
+        assert_eq!(DateTime::parse_from_rfc3339(&manifest.started_at).unwrap(), start_time);
+        assert_eq!(manifest.crate_versions.keys().collect::<Vec<_>>(), ["bioforge-app", "bioforge-core", "bioforge-schemas"]);
+        assert_eq!(manifest.knowledge_base_files, kb.file_hashes);
+        assert!(manifest.knowledge_base_files.contains_key(MATERIALS_FILE));
+        assert!(manifest.knowledge_base_files.values().all(|hash| hash.len() == 64));
+        assert!(manifest.knowledge_base_overrides.is_empty());
+
+        // Editing one file changes its hash alone.
+        let path = kb_dir.join(MATERIALS_FILE);
+        let edited = fs::read_to_string(&path).unwrap() + "\n# Edited.\n";
+        fs::write(&path, edited).unwrap();
+        let edited_kb = KnowledgeBase::load_with_overlays(&kb_dir.to_string_lossy(), &[]).unwrap();
+        assert_eq!(manifest.changed_knowledge_base_files(&edited_kb.file_hashes), [MATERIALS_FILE]);
+        assert_ne!(edited_kb.content_hash, kb.content_hash);
+
+        fs::remove_dir_all(&kb_dir).unwrap();
+        fs::remove_dir_all(run_dir.as_ref()).unwrap();
+    }
+
+    #[test]
+    fn an_overlay_changes_the_cost_of_its_material_alone_and_is_recorded_in_the_manifest() {
+        let base = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let mut hexane = base.materials["CONS-HEXANE-01"].clone();
+        let costs = &mut hexane.techno_economic_and_lca_profile.lifecycle_stages.manufacturing_and_acquisition.costs;
+        let base_cost = costs[0].value_usd;
+        costs[0].value_usd = base_cost + 10.0;
+        let overlay = scratch_dir("overlay_site");
+        fs::create_dir_all(overlay.join("1_materials")).unwrap();
+        let file = serde_json::json!({ "schema_version": "1.0", "materials": [hexane] });
+        fs::write(overlay.join("1_materials/hexane.yaml"), serde_yaml::to_string(&file).unwrap()).unwrap();
+        let kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, std::slice::from_ref(&overlay)).unwrap();
+
+        // Two kilograms of hexane cost 20 more; the glucose beside it and every other line do not change.
+        let bom = BillOfMaterials {
+            materials_consumed: [("CONS-HEXANE-01".to_string(), 2000.0), ("MEDIA-GLUCOSE".to_string(), 1000.0)].into(),
+            ..Default::default()
+        };
+        let cogs = |kb: &KnowledgeBase| calculate_cogs(&bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions).unwrap();
+        let (base_cogs, site_cogs) = (cogs(&base), cogs(&kb));
+        assert!((site_cogs.material_costs - base_cogs.material_costs - 20.0).abs() < 1e-9);
+        assert!((site_cogs.total_cogs - base_cogs.total_cogs - 20.0).abs() < 1e-9);
+        let other_lines = |cogs: CogsResult| CogsResult { material_costs: 0.0, total_cogs: 0.0, ..cogs };
+        assert_eq!(other_lines(site_cogs), other_lines(base_cogs));
+        assert!(base.materials.iter().all(|(id, material)| id == "CONS-HEXANE-01" || kb.materials[id] == *material));
+
+        let run_dir = scratch_dir("overlay_run");
+        let run_dir = run_dir.to_string_lossy();
+        write_manifest(&run_dir, &request(), "--request request.yaml", &kb, None, Utc::now()).unwrap();
+        let manifest = RunManifest::read(&run_dir).unwrap().unwrap();
+        let overlay_name = overlay.to_string_lossy().into_owned();
+        assert_eq!(manifest.knowledge_base_overrides, [("1_materials/CONS-HEXANE-01".to_string(), overlay_name.clone())].into());
+        assert_eq!(manifest.knowledge_base_overlays, [overlay_name]);
+
+        fs::remove_dir_all(&overlay).unwrap();
+        fs::remove_dir_all(run_dir.as_ref()).unwrap();
+    }
+}
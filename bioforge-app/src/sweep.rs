@@ -0,0 +1,318 @@
+//! Scenario sweeps: the end-to-end workflow run for every combination of the values of a few
+//! parameters, with the headline results of each scenario tabulated side by side.
+use crate::config::KnowledgeBase;
+use crate::jit::{self, ValorizationRequest};
+use crate::plotting;
+use crate::report;
+use crate::validation;
+use crate::workflow::{self, OutputOptions, RunOutcome};
+use anyhow::{bail, Context, Result};
+use bioforge_core::analysis::sensitivity::set_field;
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A sweep of the end-to-end workflow, as read from `sweep.yaml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SweepRequest {
+    /// The request every scenario starts from. Defaults to the request given on the command line.
+    #[serde(default)]
+    pub request: Option<PathBuf>,
+    pub axes: Vec<SweepAxis>,
+    /// The `max_duration_hours` constraint of scenarios whose request sets none, so that a
+    /// scenario that misses its targets ends, and fails the constraint, instead of cultivating on.
+    #[serde(default = "default_max_hours")]
+    pub max_hours: f64,
+}
+
+fn default_max_hours() -> f64 {
+    1000.0
+}
+
+/// A parameter of a [`SweepRequest`] and the values it takes.
+///
+/// The path names a field of the request as `request.` followed by the field (e.g.
+/// `request.targets.Lutein.target_amount_grams`, with list elements selected by index or by a
+/// value they carry), or a parameter of the knowledge base as in a sensitivity study (e.g.
+/// `organism.ORG-CPROT.growth_rate_per_hr`).
+#[derive(Debug, Clone, Deserialize)]
+pub struct SweepAxis {
+    pub path: String,
+    pub values: Vec<f64>,
+}
+
+/// A combination of axis values and the request and knowledge base it makes.
+struct Scenario {
+    name: String,
+    values: Vec<f64>,
+    request: Value,
+    kb: KnowledgeBase,
+}
+
+/// Runs the workflow of the `base` request, read from `request_source`, with `kb` for every
+/// combination of the axes of `sweep`, in parallel and each in a subdirectory of `output_dir`,
+/// then writes `sweep_results.csv` and an overlay of the biomass of every scenario to
+/// `output_dir` and prints the results. A scenario that fails is reported with its error and
+/// does not stop the others.
+///
+/// # Errors
+///
+/// Fails before any scenario runs if an axis has no values or a path names no numeric parameter.
+pub fn run_sweep(
+    sweep: &SweepRequest,
+    base: Value,
+    request_source: &str,
+    kb: &KnowledgeBase,
+    output_dir: &str,
+    options: OutputOptions,
+    no_plots: bool,
+) -> Result<()> {
+    println!("\n--- [Workflow] Starting Scenario Sweep ---");
+    if sweep.axes.is_empty() {
+        bail!("The sweep has no axes");
+    }
+    if let Some(axis) = sweep.axes.iter().find(|axis| axis.values.is_empty()) {
+        bail!("Sweep axis '{}' has no values", axis.path);
+    }
+    let base_request: ValorizationRequest =
+        serde_yaml::from_value(base.clone()).context("Failed to parse the sweep's request")?;
+
+    let combinations = sweep.axes.iter().fold(vec![Vec::new()], |combinations, axis| {
+        combinations
+            .iter()
+            .flat_map(|values| {
+                axis.values.iter().map(move |value| {
+                    let mut values = values.clone();
+                    values.push(*value);
+                    values
+                })
+            })
+            .collect()
+    });
+    let width = combinations.len().to_string().len();
+    let scenarios = combinations
+        .into_iter()
+        .enumerate()
+        .map(|(i, values)| {
+            let (mut request, mut kb) = (base.clone(), kb.clone());
+            for (axis, value) in sweep.axes.iter().zip(&values) {
+                set_parameter(&mut request, &mut kb, &axis.path, *value)?;
+            }
+            Ok(Scenario { name: format!("scenario_{:0width$}", i + 1), values, request, kb })
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    println!("Running {} scenarios...", scenarios.len());
+    let results: Vec<(Scenario, Result<RunOutcome>)> = scenarios
+        .into_par_iter()
+        .map(|scenario| {
+            let outcome = run_scenario(&scenario, request_source, sweep.max_hours, output_dir, options, no_plots);
+            if let Err(e) = &outcome {
+                println!("Scenario {} failed: {:#}", scenario.name, e);
+            }
+            (scenario, outcome)
+        })
+        .collect();
+
+    write_results_csv(&Path::new(output_dir).join("sweep_results.csv"), sweep, &base_request, &results)?;
+    let biomass: Vec<(String, Vec<(f64, f64)>)> = results
+        .iter()
+        .filter_map(|(scenario, outcome)| Some((scenario.name.clone(), outcome.as_ref().ok()?.biomass.clone())))
+        .collect();
+    plotting::plot_sweep_biomass(output_dir, &base_request.plotting, &biomass)?;
+
+    print_results(sweep, &results, &kb.assumptions.currency);
+    Ok(())
+}
+
+/// Sets the parameter at `path` of `request` or `kb` to `value`.
+fn set_parameter(request: &mut Value, kb: &mut KnowledgeBase, path: &str, value: f64) -> Result<()> {
+    let (kind, rest) = path.split_once('.').unwrap_or((path, ""));
+    let set = match kind {
+        "request" => {
+            let field: Vec<&str> = rest.split('.').collect();
+            set_request_field(request, &field, value).map_err(anyhow::Error::msg)
+        }
+        "assumptions" => set_field(&mut kb.assumptions, rest, value).map(drop).map_err(Into::into),
+        "organism" | "asset" | "material" | "labor_role" => {
+            let (id, field) = rest.split_once('.').unwrap_or((rest, ""));
+            let set = match kind {
+                "organism" => kb.organisms.get_mut(id).map(|item| set_field(item, field, value)),
+                "asset" => kb.assets.get_mut(id).map(|item| set_field(item, field, value)),
+                "material" => kb.materials.get_mut(id).map(|item| set_field(item, field, value)),
+                _ => kb.labor_roles.get_mut(id).map(|item| set_field(item, field, value)),
+            };
+            match set {
+                Some(set) => set.map(drop).map_err(Into::into),
+                None => Err(anyhow::anyhow!("no {} '{}'", kind.replace('_', " "), id)),
+            }
+        }
+        _ => Err(anyhow::anyhow!(
+            "expected it to start with request, organism.<id>, asset.<id>, material.<id>, labor_role.<id>, or assumptions"
+        )),
+    };
+    set.with_context(|| format!("Invalid sweep parameter '{}'", path))
+}
+
+/// Sets the number at `field` within the request `value` to `number`. List elements are selected
+/// by index or by a string they carry, and a missing last field is added, so that optional fields
+/// can be swept too.
+fn set_request_field(value: &mut Value, field: &[&str], number: f64) -> Result<(), String> {
+    let Some((last, parents)) = field.split_last() else {
+        return Err("no field".to_string());
+    };
+    let mut current = value;
+    for segment in parents {
+        current = child(untagged(current), segment).ok_or_else(|| format!("no field '{}'", segment))?;
+    }
+    let parent = untagged(current);
+    if let Value::Mapping(fields) = parent {
+        fields.entry(Value::from(*last)).or_insert(Value::Null);
+    }
+    let existing = child(parent, last).ok_or_else(|| format!("no field '{}'", last))?;
+    // Integer fields stay integers, as a float would not parse into them.
+    *existing = match existing {
+        Value::Number(n) if !n.is_f64() => Value::from(number.round() as i64),
+        Value::Number(_) | Value::Null => Value::from(number),
+        _ => return Err(format!("'{}' is not a number", field.join("."))),
+    };
+    Ok(())
+}
+
+/// The value of a tagged enum variant, e.g. the weights of `!Weighted`, or `value` itself.
+fn untagged(value: &mut Value) -> &mut Value {
+    match value {
+        Value::Tagged(tagged) => &mut tagged.value,
+        value => value,
+    }
+}
+
+/// The direct child `segment` of `value`: a field, or a list element by index or by one of its
+/// string values.
+fn child<'a>(value: &'a mut Value, segment: &str) -> Option<&'a mut Value> {
+    match value {
+        Value::Mapping(fields) => fields.get_mut(segment),
+        Value::Sequence(elements) => {
+            let index = segment.parse::<usize>().ok().filter(|i| *i < elements.len()).or_else(|| {
+                elements.iter().position(|element| {
+                    element.as_mapping().is_some_and(|fields| fields.values().any(|v| v.as_str() == Some(segment)))
+                })
+            })?;
+            elements.get_mut(index)
+        }
+        _ => None,
+    }
+}
+
+/// Runs the end-to-end workflow of `scenario` in its subdirectory of `output_dir`, for at most
+/// `max_hours` unless its request sets its own limit.
+fn run_scenario(
+    scenario: &Scenario,
+    request_source: &str,
+    max_hours: f64,
+    output_dir: &str,
+    options: OutputOptions,
+    no_plots: bool,
+) -> Result<RunOutcome> {
+    let scenario_dir = Path::new(output_dir).join(&scenario.name);
+    fs::create_dir_all(&scenario_dir)?;
+    fs::write(scenario_dir.join("request.yaml"), serde_yaml::to_string(&scenario.request)?)?;
+
+    let mut request: ValorizationRequest = serde_yaml::from_value(scenario.request.clone())?;
+    if no_plots {
+        request.plotting.enabled = Some(Vec::new());
+    }
+    request.constraints.max_duration_hours.get_or_insert(max_hours);
+    validation::validate_request(&request, &scenario.kb)?;
+    let mut plan = jit::select_optimal_organism_mix(&request, &scenario.kb)?;
+    jit::fit_targets_to_feedstock(&mut request, &mut plan, &scenario.kb)?;
+    let processes = jit::select_downstream_processes(&request, &mut plan, &scenario.kb)?;
+    let start_time = chrono::Utc::now();
+    let scenario_source = format!("scenario {} of {}", scenario.name, request_source);
+    report::write_manifest(&scenario_dir.to_string_lossy(), &request, &scenario_source, &scenario.kb, None, start_time)?;
+    workflow::run_workflow(
+        &request,
+        &scenario.kb,
+        &plan,
+        &processes.iter().collect::<Vec<_>>(),
+        &scenario_dir.to_string_lossy(),
+        start_time,
+        options,
+    )
+}
+
+/// Writes a row per scenario of `results` to `path`: its axis values, whether it ran, and its
+/// duration, COGS, GWP, and grams of each target of `request`.
+fn write_results_csv(
+    path: &Path,
+    sweep: &SweepRequest,
+    request: &ValorizationRequest,
+    results: &[(Scenario, Result<RunOutcome>)],
+) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    let mut header = vec!["scenario".to_string()];
+    header.extend(sweep.axes.iter().map(|axis| axis.path.clone()));
+    header.extend(["status", "error", "duration_hours", "total_cogs", "gwp_kg_co2e"].map(String::from));
+    header.extend(request.targets.iter().map(|t| format!("{}_produced_g", t.molecule_name)));
+    writer.write_record(&header)?;
+
+    for (scenario, outcome) in results {
+        let mut row = vec![scenario.name.clone()];
+        row.extend(scenario.values.iter().map(f64::to_string));
+        match outcome {
+            Ok(outcome) => {
+                row.extend([
+                    "ok".to_string(),
+                    String::new(),
+                    outcome.duration_hours.to_string(),
+                    outcome.total_cogs.to_string(),
+                    outcome.gwp_kg_co2e.to_string(),
+                ]);
+                row.extend(request.targets.iter().map(|t| {
+                    outcome.produced_grams.get(&t.molecule_name).map_or(String::new(), f64::to_string)
+                }));
+            }
+            Err(e) => {
+                row.extend(["failed".to_string(), format!("{:#}", e)]);
+                row.extend(std::iter::repeat_n(String::new(), 3 + request.targets.len()));
+            }
+        }
+        writer.write_record(&row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Prints the axis values and the duration, COGS, and GWP of each scenario of `results`.
+fn print_results(sweep: &SweepRequest, results: &[(Scenario, Result<RunOutcome>)], currency: &str) {
+    println!("\n--- [Sweep Report] ---");
+    for (i, axis) in sweep.axes.iter().enumerate() {
+        println!("  Axis {}: {}", i + 1, axis.path);
+    }
+    let name_width = results.iter().map(|(s, _)| s.name.len()).max().unwrap_or(0).max("Scenario".len());
+    let axes: String = (1..=sweep.axes.len()).map(|i| format!(" {:>10}", format!("Axis {}", i))).collect();
+    println!(
+        "\n  {:<name_width$}{} {:>12} {:>14} {:>14}",
+        "Scenario",
+        axes,
+        "Duration (h)",
+        format!("COGS ({})", currency),
+        "GWP (kg CO₂e)"
+    );
+    for (scenario, outcome) in results {
+        let values: String = scenario.values.iter().map(|v| format!(" {:>10}", v)).collect();
+        match outcome {
+            Ok(o) => println!(
+                "  {:<name_width$}{} {:>12} {:>14.2} {:>14.2}",
+                scenario.name, values, o.duration_hours, o.total_cogs, o.gwp_kg_co2e
+            ),
+            Err(_) => println!("  {:<name_width$}{} {:>12}", scenario.name, values, "failed"),
+        }
+    }
+    let failed = results.iter().filter(|(_, outcome)| outcome.is_err()).count();
+    if failed > 0 {
+        println!("\n{} of {} scenarios failed; see sweep_results.csv for their errors.", failed, results.len());
+    }
+}
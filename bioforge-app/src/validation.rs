@@ -0,0 +1,637 @@
+//! Checks of a request against the knowledge base before anything is simulated, so that a typo
+//! fails in seconds, with a hint, rather than hours into a run.
+use crate::config::KnowledgeBase;
+use crate::jit::{CultivationStrategy, FeedAmount, Objective, ValorizationRequest};
+use anyhow::{bail, Result};
+use bioforge_core::analysis::CostAllocation;
+use bioforge_schemas::{command::Command, rule::Condition};
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
+
+/// The most near-matches suggested for an unknown name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Checks that every target of `request` names a molecule some organism of `kb` makes and a
+/// process `kb` holds, asks for a positive, finite amount, and has a sensible objective, and that
+/// the request's allocation, constraints, organism filter, and feedstock fit its targets and `kb`.
+///
+/// # Errors
+///
+/// Fails with every problem found, one per line, with near-matches suggested for unknown
+/// molecules and processes.
+pub fn validate_request(request: &ValorizationRequest, kb: &KnowledgeBase) -> Result<()> {
+    let mut problems = Vec::new();
+    if request.targets.is_empty() {
+        problems.push("The request has no targets".to_string());
+    }
+
+    let molecules: BTreeSet<&str> = kb
+        .organisms
+        .values()
+        .flat_map(|organism| organism.all_yields().map(|(molecule, _)| molecule))
+        .collect();
+    let process_ids: BTreeSet<&str> = kb.processes.keys().map(String::as_str).collect();
+
+    let mut seen = HashSet::new();
+    for target in &request.targets {
+        let name = &target.molecule_name;
+        if !seen.insert(name.as_str()) {
+            problems.push(format!("Target '{}' is listed more than once", name));
+        }
+        if !kb.organisms.values().any(|organism| organism.yield_for(name).is_some()) {
+            problems.push(format!("No organism makes target '{}'{}", name, suggestion(name, &molecules)));
+        }
+        if !kb.processes.contains_key(&target.process_id) {
+            problems.push(format!(
+                "Target '{}' names unknown process_id '{}'{}",
+                name,
+                target.process_id,
+                suggestion(&target.process_id, &process_ids)
+            ));
+        }
+        if !(target.target_amount_grams.is_finite() && target.target_amount_grams > 0.0) {
+            problems.push(format!(
+                "Target '{}' has target_amount_grams {}; it must be a positive number",
+                name, target.target_amount_grams
+            ));
+        }
+        if let Some(price) = target.selling_price_per_gram.filter(|p| !(p.is_finite() && *p >= 0.0)) {
+            problems.push(format!("Target '{}' has selling_price_per_gram {}; it must not be negative", name, price));
+        }
+        if let Some(purity) = target.minimum_purity_percent.filter(|p| !(*p > 0.0 && *p < 100.0)) {
+            problems.push(format!(
+                "Target '{}' has minimum_purity_percent {}; it must be above 0 and below 100",
+                name, purity
+            ));
+        }
+        if let Objective::Weighted { yield_w, cost_w, lca_w } = target.objective {
+            let weights = [yield_w, cost_w, lca_w];
+            if weights.iter().any(|w| !(w.is_finite() && *w >= 0.0)) || weights.iter().sum::<f64>() <= 0.0 {
+                problems.push(format!(
+                    "Target '{}' has Weighted weights {}, {}, {}; they must not be negative and at least one must be positive",
+                    name, yield_w, cost_w, lca_w
+                ));
+            }
+        }
+    }
+
+    let allocation_keys = match &request.cost_allocation {
+        CostAllocation::Mass => None,
+        CostAllocation::EnergyContent { mj_per_kg } => Some(("mj_per_kg", mj_per_kg)),
+        CostAllocation::Economic { ratios } => Some(("ratios", ratios)),
+    };
+    if let Some((field, values)) = allocation_keys {
+        for target in &request.targets {
+            if !values.contains_key(&target.molecule_name) {
+                problems.push(format!(
+                    "cost_allocation {} has no entry for target '{}'",
+                    field, target.molecule_name
+                ));
+            }
+        }
+    }
+
+    let constraints = &request.constraints;
+    for (field, limit) in [
+        ("max_total_cost_usd", constraints.max_total_cost_usd),
+        ("max_duration_hours", constraints.max_duration_hours),
+        ("max_gwp_kg_co2e", constraints.max_gwp_kg_co2e),
+    ] {
+        if let Some(limit) = limit.filter(|l| !(l.is_finite() && *l > 0.0)) {
+            problems.push(format!("constraints {} is {}; it must be a positive number", field, limit));
+        }
+    }
+
+    let fraction = request.initial_charge_fraction;
+    if !(fraction > 0.0 && fraction <= 1.0) {
+        problems.push(format!("initial_charge_fraction is {}; it must be above 0 and at most 1", fraction));
+    }
+    let excess = request.nitrogen_excess_factor;
+    if !(excess.is_finite() && excess >= 1.0) {
+        problems.push(format!("nitrogen_excess_factor is {}; it must be at least 1", excess));
+    }
+    match &request.cultivation_strategy {
+        CultivationStrategy::Batch => {}
+        CultivationStrategy::FedBatch { trigger_g_per_l, feed } => {
+            if !(trigger_g_per_l.is_finite() && *trigger_g_per_l > 0.0) {
+                problems.push(format!("cultivation_strategy trigger_g_per_l is {}; it must be positive", trigger_g_per_l));
+            }
+            match feed {
+                FeedAmount::Bolus { additions: 0 } => {
+                    problems.push("cultivation_strategy feed additions is 0; it must be at least 1".to_string())
+                }
+                FeedAmount::ToConcentration { g_per_l } if !(g_per_l.is_finite() && g_per_l > trigger_g_per_l) => problems.push(format!(
+                    "cultivation_strategy feed g_per_l is {}; it must be above trigger_g_per_l ({})",
+                    g_per_l, trigger_g_per_l
+                )),
+                _ => {}
+            }
+        }
+        CultivationStrategy::Continuous { dilution_rate_per_hr } => {
+            if !(dilution_rate_per_hr.is_finite() && *dilution_rate_per_hr > 0.0) {
+                problems.push(format!(
+                    "cultivation_strategy dilution_rate_per_hr is {}; it must be positive",
+                    dilution_rate_per_hr
+                ));
+            }
+        }
+    }
+    let filter = &request.organism_filter;
+    let organism_ids: BTreeSet<&str> = kb.organisms.keys().map(String::as_str).collect();
+    for (field, ids) in [
+        ("excluded_organism_ids", &filter.excluded_organism_ids),
+        ("required_organism_ids", &filter.required_organism_ids),
+    ] {
+        for id in ids.iter().filter(|id| !kb.organisms.contains_key(*id)) {
+            problems.push(format!("{} names unknown organism '{}'{}", field, id, suggestion(id, &organism_ids)));
+        }
+    }
+    for id in &filter.required_organism_ids {
+        if filter.excluded_organism_ids.contains(id) {
+            problems.push(format!("Organism '{}' is both required and excluded", id));
+        }
+        let engineered = kb.organisms.get(id).and_then(|o| o.strain_details.as_ref()).is_some_and(|s| s.is_engineered);
+        if engineered && !filter.allow_engineered {
+            problems.push(format!("Required organism '{}' is engineered, but allow_engineered is false", id));
+        }
+    }
+    if let Some(feedstock) = &request.available_feedstock {
+        match kb.materials.get(&feedstock.material_id) {
+            None => {
+                let material_ids: BTreeSet<&str> = kb.materials.keys().map(String::as_str).collect();
+                problems.push(format!(
+                    "available_feedstock names unknown material '{}'{}",
+                    feedstock.material_id,
+                    suggestion(&feedstock.material_id, &material_ids)
+                ))
+            }
+            Some(material) if material.unit != "kg" => problems.push(format!(
+                "available_feedstock material '{}' is sold by the {}; it must be sold by the kg",
+                feedstock.material_id, material.unit
+            )),
+            Some(_) => {}
+        }
+        if !(feedstock.amount_kg.is_finite() && feedstock.amount_kg > 0.0) {
+            problems.push(format!("available_feedstock amount_kg is {}; it must be positive", feedstock.amount_kg));
+        }
+        let mut seen = HashSet::new();
+        for component in &feedstock.composition {
+            if !seen.insert(component.molecule_id.as_str()) {
+                problems.push(format!("available_feedstock lists '{}' more than once", component.molecule_id));
+            }
+            if !(component.mass_fraction > 0.0 && component.mass_fraction <= 1.0) {
+                problems.push(format!(
+                    "available_feedstock mass_fraction of '{}' is {}; it must be above 0 and at most 1",
+                    component.molecule_name, component.mass_fraction
+                ));
+            }
+        }
+        let total: f64 = feedstock.composition.iter().map(|c| c.mass_fraction).sum();
+        if total > 1.0 + 1e-9 {
+            problems.push(format!("available_feedstock mass fractions add up to {}; they must not exceed 1", total));
+        }
+        if !kb.organisms.values().any(|o| feedstock.feeds(o)) {
+            problems.push("No organism of the knowledge base takes up any component of available_feedstock".to_string());
+        }
+    }
+    if let Some(celsius) = request.cultivation_temperature_celsius.filter(|c| !c.is_finite()) {
+        problems.push(format!("cultivation_temperature_celsius is {}; it must be a number", celsius));
+    }
+    if let Some(seed_train) = request.seed_train.as_ref().filter(|t| t.scale_up_ratio.is_nan() || t.scale_up_ratio <= 1.0) {
+        problems.push(format!("seed_train scale_up_ratio is {}; it must be above 1", seed_train.scale_up_ratio));
+    }
+
+    if !problems.is_empty() {
+        bail!("The request has {} problem(s):\n  - {}", problems.len(), problems.join("\n  - "));
+    }
+    Ok(())
+}
+
+/// How much a [`ValidationIssue`] matters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The reference is used when simulating or costing, and would turn into a silent zero or a
+    /// failure hours into a run.
+    Error,
+    /// The reference is only informative, or the knowledge base can do without it.
+    Warning,
+}
+
+/// A reference in the knowledge base to an id nothing in it defines.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// The entity holding the reference, e.g. `process 'PROC-LUT-NUT-01', method 'MTHD-LUT-EXT-SFE-01'`.
+    pub entity: String,
+    /// The kind of entity the reference names, e.g. `asset`.
+    pub kind: &'static str,
+    pub missing_id: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} names unknown {} '{}'", self.entity, self.kind, self.missing_id)
+    }
+}
+
+impl KnowledgeBase {
+    /// Cross-checks every reference between the entities of the knowledge base: the assets, rules,
+    /// materials, and labor roles of each process method; the assets, organisms, and materials of
+    /// each rule; the labor roles of each asset; and the media each organism takes up. Molecules
+    /// count as materials when some material is, holds, or is identified by them. Issues come
+    /// errors first, then by entity.
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        let mut check = |severity, entity: &dyn Fn() -> String, kind, id: &str, known: bool| {
+            if !known {
+                issues.push(ValidationIssue { severity, entity: entity(), kind, missing_id: id.to_string() });
+            }
+        };
+        let molecules: HashSet<&str> = self
+            .materials
+            .values()
+            .flat_map(|material| {
+                let chebi_id = material.metadata.identifiers.as_ref().and_then(|i| i.chebi_id.as_deref());
+                let components = material.formulation.iter().flat_map(|f| &f.components).map(|c| c.component_id.as_str());
+                std::iter::once(material.material_id.as_str()).chain(chebi_id).chain(components)
+            })
+            .collect();
+
+        for process in self.processes.values() {
+            let entity = || format!("process '{}'", process.process_id);
+            for method_id in &process.default_workflow {
+                let known = process.methods.iter().any(|m| &m.method_id == method_id);
+                check(Severity::Error, &entity, "workflow method", method_id, known);
+            }
+            for method in &process.methods {
+                let entity = || format!("process '{}', method '{}'", process.process_id, method.method_id);
+                check(Severity::Error, &entity, "asset", &method.required_asset_id, self.assets.contains_key(&method.required_asset_id));
+                for rule_id in method.required_rule_ids.iter().flatten() {
+                    check(Severity::Error, &entity, "rule", rule_id, self.rules.contains_key(rule_id));
+                }
+                for material in &method.required_materials {
+                    check(Severity::Error, &entity, "material", &material.id, self.materials.contains_key(&material.id));
+                }
+                for qc in &method.qc_checks {
+                    if let Some(role_id) = &qc.required_role_id {
+                        check(Severity::Error, &entity, "labor role", role_id, self.labor_roles.contains_key(role_id));
+                    }
+                    for consumable in &qc.consumables {
+                        let id = &consumable.material_id;
+                        check(Severity::Error, &entity, "material", id, self.materials.contains_key(id));
+                    }
+                }
+                for transfer in method.transfers.iter().flatten() {
+                    for asset_id in [&transfer.from_asset_id, &transfer.to_asset_id] {
+                        check(Severity::Error, &entity, "asset", asset_id, self.assets.contains_key(asset_id));
+                    }
+                    if let Some(id) = &transfer.material_id {
+                        check(Severity::Warning, &entity, "material", id, molecules.contains(id.as_str()));
+                    }
+                }
+            }
+        }
+
+        for rule in self.rules.values() {
+            let entity = || format!("rule '{}'", rule.name);
+            match &rule.condition {
+                Condition::AssetValue { asset_id, .. } => {
+                    check(Severity::Error, &entity, "asset", asset_id, self.assets.contains_key(asset_id));
+                }
+                Condition::ProductAmount { organism_id: Some(id), .. } => {
+                    check(Severity::Error, &entity, "organism", id, self.organisms.contains_key(id));
+                }
+                Condition::MediaValue { molecule_id, .. } => {
+                    check(Severity::Warning, &entity, "material", molecule_id, molecules.contains(molecule_id.as_str()));
+                }
+                _ => {}
+            }
+            match &rule.action {
+                Command::SetTemperature { asset_id, .. }
+                | Command::AdjustPh { asset_id, .. }
+                | Command::DrainMedia { asset_id, .. } => {
+                    check(Severity::Error, &entity, "asset", asset_id, self.assets.contains_key(asset_id));
+                }
+                Command::AddMaterial { asset_id, material_id, .. } | Command::TopUpMaterial { asset_id, material_id, .. } => {
+                    check(Severity::Error, &entity, "asset", asset_id, self.assets.contains_key(asset_id));
+                    check(Severity::Error, &entity, "material", material_id, molecules.contains(material_id.as_str()));
+                }
+                Command::SetOrganismGrowthMultiplier { organism_id, .. } => {
+                    check(Severity::Error, &entity, "organism", organism_id, self.organisms.contains_key(organism_id));
+                }
+                Command::DiluteMedia { asset_id, feed, harvest_asset_id, .. } => {
+                    for asset_id in std::iter::once(asset_id).chain(harvest_asset_id) {
+                        check(Severity::Error, &entity, "asset", asset_id, self.assets.contains_key(asset_id));
+                    }
+                    for component in feed {
+                        let id = &component.molecule_id;
+                        check(Severity::Error, &entity, "material", id, molecules.contains(id.as_str()));
+                    }
+                }
+                Command::AdvanceToNextStep => {}
+            }
+        }
+
+        for asset in self.assets.values() {
+            let entity = || format!("asset '{}'", asset.asset_id);
+            let requirements = asset.operational_parameters.as_ref().and_then(|p| p.labor_requirements.as_ref());
+            for requirement in requirements.into_iter().flatten() {
+                let role_id = &requirement.required_role_id;
+                check(Severity::Error, &entity, "labor role", role_id, self.labor_roles.contains_key(role_id));
+            }
+        }
+
+        // An organism that takes up a molecule no material supplies cannot be fed it, but may
+        // still grow on the rest of its media.
+        for organism in self.organisms.values() {
+            let entity = || format!("organism '{}'", organism.organism_id);
+            for consumption in &organism.dynamic_parameters.metabolic_exchange.media_consumption {
+                let id = &consumption.molecule_id;
+                check(Severity::Warning, &entity, "material", id, molecules.contains(id.as_str()));
+            }
+        }
+
+        issues.sort();
+        issues.dedup();
+        issues
+    }
+}
+
+/// Prints the issues [`KnowledgeBase::validate`] finds in `kb`.
+///
+/// # Errors
+///
+/// Fails when `strict` and any issue is an error.
+pub fn check_knowledge_base(kb: &KnowledgeBase, strict: bool) -> Result<()> {
+    let issues = kb.validate();
+    if issues.is_empty() {
+        return Ok(());
+    }
+    let errors = issues.iter().filter(|issue| issue.severity == Severity::Error).count();
+    println!("The knowledge base has {} dangling reference(s), {} of them errors:", issues.len(), errors);
+    for issue in &issues {
+        let label = match issue.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        println!("  - {}: {}", label, issue);
+    }
+    if strict && errors > 0 {
+        bail!("The knowledge base has {} dangling reference error(s) and --strict was given", errors);
+    }
+    Ok(())
+}
+
+/// A hint naming the entries of `known` closest to `name`, e.g. ` (did you mean 'Lutein'?)`, or
+/// an empty string if none is close. Names are compared ignoring case, and an entry is close when
+/// it is at most a third of its length, and no fewer than two edits, away.
+fn suggestion(name: &str, known: &BTreeSet<&str>) -> String {
+    let name_lower = name.to_lowercase();
+    let mut close: Vec<(usize, &str)> = known
+        .iter()
+        .map(|candidate| (edit_distance(&name_lower, &candidate.to_lowercase()), *candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.chars().count() / 3).max(2))
+        .collect();
+    close.sort();
+    let names: Vec<String> = close.iter().take(MAX_SUGGESTIONS).map(|(_, candidate)| format!("'{}'", candidate)).collect();
+    match names.len() {
+        0 => String::new(),
+        _ => format!(" (did you mean {}?)", names.join(" or ")),
+    }
+}
+
+/// The Levenshtein distance between `a` and `b`: the fewest single-character insertions,
+/// deletions, and substitutions that turn one into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a_char != *b_char);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::{scratch_dir, KNOWLEDGE_BASE};
+    use std::fs;
+
+    /// An overlay adding a rule that watches an asset and scales an organism the knowledge base
+    /// does not define.
+    const DANGLING_RULE: &str = r#"schema_version: "1.0"
+rules:
+  - name: ruleDangling
+    condition:
+      type: asset_value
+      asset_id: ASSET-MISSING
+      parameter: temperature
+      operator: greater_than
+      value: 40.0
+    action:
+      type: set_organism_growth_multiplier
+      organism_id: ORG-MISSING
+      multiplier: 0.5
+"#;
+
+    #[test]
+    fn dangling_references_fail_only_the_strict_check() {
+        let overlay = scratch_dir("dangling_overlay");
+        fs::create_dir_all(overlay.join("6_rules")).unwrap();
+        fs::write(overlay.join("6_rules/dangling.yaml"), DANGLING_RULE).unwrap();
+        let mut kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, std::slice::from_ref(&overlay)).unwrap();
+        // Keep the overlay's rule and one organism, fed a molecule no material supplies, among
+        // the materials and labor roles, so that theirs are the only references.
+        kb.assets.clear();
+        kb.processes.clear();
+        kb.rules.retain(|name, _| name == "ruleDangling");
+        kb.organisms.retain(|id, _| id == "ORG-ECOLI");
+        let organism = kb.organisms.get_mut("ORG-ECOLI").unwrap();
+        organism.dynamic_parameters.metabolic_exchange.media_consumption.truncate(1);
+        organism.dynamic_parameters.metabolic_exchange.media_consumption[0].molecule_id = "CHEBI:0".to_string();
+
+        let issues: Vec<(Severity, String)> = kb.validate().iter().map(|i| (i.severity, i.to_string())).collect();
+        assert_eq!(
+            issues,
+            [
+                (Severity::Error, "rule 'ruleDangling' names unknown asset 'ASSET-MISSING'".to_string()),
+                (Severity::Error, "rule 'ruleDangling' names unknown organism 'ORG-MISSING'".to_string()),
+                (Severity::Warning, "organism 'ORG-ECOLI' names unknown material 'CHEBI:0'".to_string()),
+            ]
+        );
+        check_knowledge_base(&kb, false).unwrap();
+        let error = check_knowledge_base(&kb, true).unwrap_err().to_string();
+        assert!(error.contains("2 dangling reference error(s)") && error.contains("--strict"), "{}", error);
+
+        // Warnings alone pass even the strict check.
+        kb.rules.clear();
+        check_knowledge_base(&kb, true).unwrap();
+        fs::remove_dir_all(&overlay).unwrap();
+    }
+    /// The problems of a request for 2.5 g of the repository's lutein, with the target's keys
+    /// overridden by `target` and the request's by `extra`.
+    fn problems(target: &[(&str, &str)], extra: &str) -> Vec<String> {
+        let kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let mut fields = vec![
+            ("molecule_name", "Lutein"),
+            ("objective", "MaximizeYield"),
+            ("process_id", "PROC-LUT-FED-01"),
+            ("target_amount_grams", "2.5"),
+        ];
+        for (key, value) in target {
+            match fields.iter_mut().find(|(k, _)| k == key) {
+                Some(field) => field.1 = value,
+                None => fields.push((key, value)),
+            }
+        }
+        let fields: Vec<String> = fields.iter().map(|(key, value)| format!("{key}: {value}")).collect();
+        let request: ValorizationRequest = serde_yaml::from_str(&format!("targets:\n- {{ {} }}\n{extra}", fields.join(", "))).unwrap();
+        match validate_request(&request, &kb) {
+            Ok(()) => Vec::new(),
+            Err(error) => error.to_string().lines().skip(1).map(|line| line.trim_start_matches("  - ").to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn a_valid_request_has_no_problems() {
+        assert_eq!(problems(&[], ""), Vec::<String>::new());
+    }
+
+    #[test]
+    fn targets_must_be_made_processed_and_positive() {
+        assert_eq!(problems(&[("molecule_name", "lutien")], ""), ["No organism makes target 'lutien' (did you mean 'Lutein'?)"]);
+        assert_eq!(
+            problems(&[("process_id", "PROC-LUT-FED-1")], ""),
+            ["Target 'Lutein' names unknown process_id 'PROC-LUT-FED-1' (did you mean 'PROC-LUT-FED-01' or 'PROC-LUT-FOD-01' or 'PROC-LUT-NUT-01'?)"]
+        );
+        assert_eq!(
+            problems(&[("target_amount_grams", "-1"), ("selling_price_per_gram", "-2"), ("minimum_purity_percent", "100")], ""),
+            [
+                "Target 'Lutein' has target_amount_grams -1; it must be a positive number",
+                "Target 'Lutein' has selling_price_per_gram -2; it must not be negative",
+                "Target 'Lutein' has minimum_purity_percent 100; it must be above 0 and below 100",
+            ]
+        );
+        assert_eq!(
+            problems(&[("objective", "!Weighted { yield_w: 0, cost_w: 0, lca_w: 0 }")], ""),
+            ["Target 'Lutein' has Weighted weights 0, 0, 0; they must not be negative and at least one must be positive"]
+        );
+        let duplicated = "- { molecule_name: Lutein, objective: MinimizeCost, process_id: PROC-LUT-FED-01, target_amount_grams: 1 }";
+        assert_eq!(problems(&[], duplicated), ["Target 'Lutein' is listed more than once"]);
+    }
+
+    #[test]
+    fn an_allocation_needs_an_entry_for_every_target() {
+        assert_eq!(
+            problems(&[], "cost_allocation: { method: economic, ratios: { Astaxanthin: 1.0 } }"),
+            ["cost_allocation ratios has no entry for target 'Lutein'"]
+        );
+        assert_eq!(problems(&[], "cost_allocation: { method: economic, ratios: { Lutein: 1.0 } }"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn constraints_must_be_positive() {
+        assert_eq!(
+            problems(&[], "constraints: { max_total_cost_usd: 0, max_gwp_kg_co2e: -1 }"),
+            [
+                "constraints max_total_cost_usd is 0; it must be a positive number",
+                "constraints max_gwp_kg_co2e is -1; it must be a positive number",
+            ]
+        );
+    }
+
+    #[test]
+    fn the_cultivation_settings_must_be_in_range() {
+        assert_eq!(
+            problems(&[], "initial_charge_fraction: 0\nnitrogen_excess_factor: 0.5\ncultivation_temperature_celsius: .nan\nseed_train: { scale_up_ratio: 1 }"),
+            [
+                "initial_charge_fraction is 0; it must be above 0 and at most 1",
+                "nitrogen_excess_factor is 0.5; it must be at least 1",
+                "cultivation_temperature_celsius is NaN; it must be a number",
+                "seed_train scale_up_ratio is 1; it must be above 1",
+            ]
+        );
+        assert_eq!(
+            problems(&[], "cultivation_strategy: { mode: fed_batch, trigger_g_per_l: 0, feed: { amount: bolus, additions: 0 } }"),
+            [
+                "cultivation_strategy trigger_g_per_l is 0; it must be positive",
+                "cultivation_strategy feed additions is 0; it must be at least 1",
+            ]
+        );
+        assert_eq!(
+            problems(&[], "cultivation_strategy: { mode: fed_batch, trigger_g_per_l: 2, feed: { amount: to_concentration, g_per_l: 2 } }"),
+            ["cultivation_strategy feed g_per_l is 2; it must be above trigger_g_per_l (2)"]
+        );
+        assert_eq!(
+            problems(&[], "cultivation_strategy: { mode: continuous, dilution_rate_per_hr: 0 }"),
+            ["cultivation_strategy dilution_rate_per_hr is 0; it must be positive"]
+        );
+    }
+
+    #[test]
+    fn the_organism_filter_must_name_known_consistent_organisms() {
+        assert_eq!(
+            problems(&[], "excluded_organism_ids: [ORG-ECOL]"),
+            ["excluded_organism_ids names unknown organism 'ORG-ECOL' (did you mean 'ORG-ECOLI' or 'ORG-LEDOD' or 'ORG-NOCUL'?)"]
+        );
+        assert_eq!(
+            problems(&[], "allow_engineered: false\nexcluded_organism_ids: [ORG-ECOLI]\nrequired_organism_ids: [ORG-ECOLI]"),
+            [
+                "Organism 'ORG-ECOLI' is both required and excluded",
+                "Required organism 'ORG-ECOLI' is engineered, but allow_engineered is false",
+            ]
+        );
+    }
+
+    #[test]
+    fn the_feedstock_must_be_a_known_material_with_a_feasible_composition() {
+        let feedstock = |material_id: &str, amount_kg: f64, composition: &str| {
+            problems(&[], &format!("available_feedstock: {{ material_id: {material_id}, amount_kg: {amount_kg}, composition: [{composition}] }}"))
+        };
+        let glucose = |fraction: f64| format!("{{ molecule_id: 'CHEBI:17234', molecule_name: D-glucose, mass_fraction: {fraction} }}");
+        assert_eq!(feedstock("MEDIA-GLUCOSE", 10.0, &glucose(0.9)), Vec::<String>::new());
+        assert_eq!(
+            feedstock("MEDIA-GLUCOS", 10.0, &glucose(0.9)),
+            ["available_feedstock names unknown material 'MEDIA-GLUCOS' (did you mean 'MEDIA-GLUCOSE' or 'MEDIA-GLYCEROL' or 'MEDIA-SUCROSE'?)"]
+        );
+        assert_eq!(
+            feedstock("CONS-HCL-1M-01", 0.0, &glucose(0.9)),
+            [
+                "available_feedstock material 'CONS-HCL-1M-01' is sold by the L; it must be sold by the kg",
+                "available_feedstock amount_kg is 0; it must be positive",
+            ]
+        );
+        assert_eq!(
+            feedstock("MEDIA-GLUCOSE", 10.0, &format!("{}, {}", glucose(0.6), glucose(1.5))),
+            [
+                "available_feedstock lists 'CHEBI:17234' more than once",
+                "available_feedstock mass_fraction of 'D-glucose' is 1.5; it must be above 0 and at most 1",
+                "available_feedstock mass fractions add up to 2.1; they must not exceed 1",
+            ]
+        );
+        assert_eq!(
+            feedstock("MEDIA-GLUCOSE", 10.0, "{ molecule_id: 'CHEBI:0', molecule_name: unknown, mass_fraction: 0.5 }"),
+            ["No organism of the knowledge base takes up any component of available_feedstock"]
+        );
+    }
+
+    #[test]
+    fn suggestions_are_the_closest_names_within_a_third_of_their_length() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("lutein", "lutein"), 0);
+
+        let known = BTreeSet::from(["Lutein", "Lycopene", "Astaxanthin", "Zeaxanthin", "Beta-carotene"]);
+        // Case is ignored; a near-miss of a short name is allowed two edits.
+        assert_eq!(suggestion("LUTEIN", &known), " (did you mean 'Lutein'?)");
+        assert_eq!(suggestion("Lutien", &known), " (did you mean 'Lutein'?)");
+        // Several close names come closest first.
+        assert_eq!(suggestion("Axaxanthin", &known), " (did you mean 'Astaxanthin' or 'Zeaxanthin'?)");
+        assert_eq!(suggestion("Chlorophyll", &known), "");
+    }
+}
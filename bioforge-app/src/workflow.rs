@@ -1,231 +1,1995 @@
 use crate::config::KnowledgeBase;
 use crate::jit;
 use crate::plotting;
-use anyhow::Result;
+use crate::report::{self, RunMetadata};
+use anyhow::{bail, Result};
 use bioforge_core::{
-    analysis::{self, BillOfMaterials},
-    simulation::builder::SimulationBuilder,
+    analysis::{
+        self,
+        campaign::{CampaignModel, CampaignProduct, CampaignReport},
+        carbon_balance::{carbon_balance_from_records, carbon_mass_fraction, CarbonBalance, CarbonProduct},
+        comparison::{self, Delta, RunDiff},
+        constraints::{labor_feasibility, ConstrainedQuantity, ConstraintCheck, OrganismFilter},
+        financial::{breakeven, FinancialModel, FinancialResult, ProductSale},
+        mass_balance::{mass_balance_from_records, MassBalanceReport},
+        performance::{process_performance_from_records, volumetric_productivity, ProcessPerformance, TargetProduct},
+        monte_carlo::{MonteCarloStudy, UncertainParameter, METRICS},
+        report::{AnalysisReport, NamedBom, Versioned},
+        sensitivity::{BaseConfiguration, SensitivityStudy},
+        BillOfMaterials, DurationSource, GrowthMetrics,
+    },
+    error::BioforgeError,
+    logger::{tick_timestamp, InMemorySink, LogFormat, TimeSeriesLogger, WideSeries},
+    simulation::{
+        builder::SimulationBuilder,
+        engine::TIME_STEP_HR,
+        state::{HandoffState, RunSummary},
+    },
 };
+#[cfg(feature = "sqlite")]
+use bioforge_core::sqlite_log::{self, SqliteSink};
 use bioforge_schemas::{
     command::Command,
     environment::MediaState,
     organism::Organism,
-    process::{Method, Process},
-    rule::{Condition, Rule},
+    process::{MaterialTransfer, Method, Process},
+    rule::{transition_conditions, Condition, Rule},
     rule::ComparisonOperator,
 };
-use std::{collections::HashMap, fs, path::Path};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// File name of the run database written by the SQLite log backend.
+#[cfg(feature = "sqlite")]
+const RUN_DATABASE: &str = "runs.db";
+
+/// File name, in the run directory, of the [`WorkflowProgress`] of the run.
+const PROGRESS_FILE: &str = "workflow_progress.json";
+
+/// The simulations of a run that have completed, with their summaries, so that a run that was
+/// interrupted can be resumed in its run directory without simulating them again.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WorkflowProgress {
+    upstream: Option<RunSummary>,
+    /// The downstream runs, keyed by process id.
+    downstream: BTreeMap<String, RunSummary>,
+}
+
+impl WorkflowProgress {
+    /// Reads the progress of the run in `output_dir`, or none for a run that has not started.
+    fn load(output_dir: &str) -> Result<Self> {
+        let path = Path::new(output_dir).join(PROGRESS_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    fn save(&self, output_dir: &str) -> Result<()> {
+        fs::write(Path::new(output_dir).join(PROGRESS_FILE), serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Whether the log at `log_path` is that of a completed run of `process`: it reads back in full
+/// and its last row is of the last method of the workflow.
+fn log_completed(log_path: &Path, process: &Process) -> bool {
+    let last_stage = analysis::read_log(&log_path.to_string_lossy())
+        .ok()
+        .and_then(|records| records.last().map(|record| record.stage_id.clone()));
+    last_stage.is_some() && last_stage.as_ref() == process.default_workflow.last()
+}
 
 /// Represents the output of the combined upstream simulations.
 #[derive(Debug, Clone)]
 pub struct UpstreamOutput {
     pub biomass_produced: HashMap<String, f64>,
     pub combined_bom: BillOfMaterials,
+    /// The final broth of the cultivation, used as the starting state of each downstream run.
+    pub handoff: HandoffState,
+    pub summary: RunSummary,
+    /// Peak growth rates and time to stationary phase of the cultivation.
+    pub growth: GrowthMetrics,
+    /// Titer, space-time yield, and yields of each target the cultivation produced.
+    pub performance: ProcessPerformance,
+    /// Where the carbon of the cultivation came from and went, without the fossil CO₂e of energy.
+    pub carbon_balance: CarbonBalance,
+    /// Wall-clock time at which the batch, and so the cultivation, started.
+    pub start_time: DateTime<Utc>,
+    /// Wall-clock time at which the cultivation ended and downstream processing begins.
+    pub end_time: DateTime<Utc>,
+    /// The SQLite database the upstream run was logged to, which the downstream runs join.
+    pub run_database: Option<PathBuf>,
+    /// The total biomass of the cultivation at each hour, in grams.
+    pub biomass: Vec<(f64, f64)>,
+    /// The generated cultivation process, its seed train included, and the rules it ran under.
+    pub process: Process,
+    pub rules: HashMap<String, Rule>,
+}
+
+/// The headline results of an end-to-end run.
+#[derive(Debug, Clone)]
+pub struct RunOutcome {
+    /// Hours of the batch, upstream and downstream together.
+    pub duration_hours: u64,
+    pub total_cogs: f64,
+    pub gwp_kg_co2e: f64,
+    /// The grams of each target the cultivation produced, by molecule name.
+    pub produced_grams: BTreeMap<String, f64>,
+    /// The total biomass of the cultivation at each hour, in grams.
+    pub biomass: Vec<(f64, f64)>,
+}
+
+/// A run's entry in `run_summary.json`: the engine's summary plus growth milestones from its log.
+#[derive(Serialize)]
+struct RunReport<'a> {
+    #[serde(flatten)]
+    summary: &'a RunSummary,
+    growth: &'a GrowthMetrics,
+}
+
+/// Where the time-series logs of the simulations go.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogBackend {
+    /// A CSV file per upstream run; downstream logs stay in memory.
+    #[default]
+    Csv,
+    /// Every run in one SQLite database in the run directory.
+    #[cfg(feature = "sqlite")]
+    Sqlite,
+}
+
+impl FromStr for LogBackend {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "csv" => Ok(LogBackend::Csv),
+            #[cfg(feature = "sqlite")]
+            "sqlite" => Ok(LogBackend::Sqlite),
+            #[cfg(not(feature = "sqlite"))]
+            "sqlite" => bail!("This build has no SQLite log backend; rebuild with `--features sqlite`"),
+            other => bail!("Unknown log backend '{}'; expected 'csv' or 'sqlite'", other),
+        }
+    }
+}
+
+/// How the upstream run's log and the plots are written, as chosen on the command line.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OutputOptions {
+    /// Label the time-series plots with calendar dates instead of elapsed hours.
+    pub date_axis: bool,
+    /// Write a wide log with one column per series instead of embedded JSON. CSV backend only.
+    pub wide_log: bool,
+    pub log_backend: LogBackend,
+}
+
+/// A one-at-a-time sensitivity study of the upstream cultivation, as read from `sensitivity.yaml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SensitivityRequest {
+    /// The perturbation, in percent, of parameters that give none.
+    #[serde(default = "default_perturbation")]
+    pub percent: f64,
+    pub parameters: Vec<SensitivityParameter>,
+    /// Also draw a tornado chart of the COGS deltas.
+    #[serde(default)]
+    pub tornado_chart: bool,
+    /// Hours after which a variant's cultivation ends even if its targets were not met.
+    #[serde(default = "default_max_hours")]
+    pub max_hours: u64,
+}
+
+/// A parameter of a [`SensitivityRequest`], addressed as described in
+/// [`analysis::sensitivity::Perturbation`].
+#[derive(Debug, Clone, Deserialize)]
+pub struct SensitivityParameter {
+    pub path: String,
+    pub percent: Option<f64>,
+}
+
+/// A Monte Carlo study of the upstream cultivation, as read from `monte_carlo.yaml`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MonteCarloRequest {
+    pub samples: usize,
+    /// Seeds the draws; the same seed reproduces the same samples.
+    pub seed: u64,
+    pub parameters: Vec<UncertainParameter>,
+    /// Hours after which a run's cultivation ends even if its targets were not met.
+    #[serde(default = "default_max_hours")]
+    pub max_hours: u64,
+}
+
+fn default_perturbation() -> f64 {
+    10.0
+}
+
+fn default_max_hours() -> u64 {
+    1000
+}
+
+/// Runs the end-to-end workflow of `request` in `output_dir`: the candidate table, the initial
+/// media, the upstream cultivation of the organisms of `plan`, and the downstream `processes`
+/// with the reports.
+pub fn run_workflow(
+    request: &jit::ValorizationRequest,
+    kb: &KnowledgeBase,
+    plan: &jit::SelectionPlan,
+    processes: &[&Process],
+    output_dir: &str,
+    start_time: DateTime<Utc>,
+    options: OutputOptions,
+) -> Result<RunOutcome> {
+    jit::write_pareto_candidates(request, kb, output_dir)?;
+    jit::write_feedstock_balance(plan, output_dir)?;
+    jit::write_purity_report(plan, output_dir)?;
+
+    // Generate the initial media for the selected organisms
+    let initial_media = jit::generate_initial_media(plan, request, kb, output_dir)?;
+    // Create a BOM for the initial media from the materials it is made of
+    let initial_bom = jit::generate_media_recipe(&initial_media, request.available_feedstock.as_ref(), kb, output_dir)?.bom();
+
+    let upstream_output = run_upstream_simulations(plan, kb, output_dir, initial_media, request, start_time, options)?;
+    run_downstream_and_report(processes, &upstream_output, kb, output_dir, request, plan, initial_bom, options)
 }
 
 /// Orchestrates a single upstream cultivation simulation for the selected consortium of organisms.
 pub fn run_upstream_simulations(
-    organisms: &[Organism],
+    plan: &jit::SelectionPlan,
     kb: &KnowledgeBase,
     output_dir: &str,
     initial_media: MediaState,
     request: &jit::ValorizationRequest,
+    start_time: DateTime<Utc>,
+    options: OutputOptions,
 ) -> Result<UpstreamOutput> {
     println!("\n--- [Workflow] Starting Upstream Consortium Simulation ---");
 
+    let organisms = &plan.organisms;
     let organism_clones: Vec<Organism> = organisms.to_vec();
     let organism_names: HashMap<String, String> = organism_clones
         .iter()
         .map(|o| (o.organism_id.clone(), o.organism_name.clone()))
         .collect();
+
+    // Molecules that can be dissolved in the broth: the initial media plus the organisms'
+    // secretions. Together with the gases, these name the media series in wide logs.
+    let molecule_names: BTreeMap<String, String> = initial_media
+        .composition
+        .dissolved_components
+        .iter()
+        .map(|c| (c.molecule_id.clone(), c.molecule_name.clone()))
+        .chain(organisms.iter().flat_map(|o| {
+            o.dynamic_parameters
+                .metabolic_exchange
+                .media_secretion
+                .iter()
+                .map(|s| (s.molecule_id.clone(), s.molecule_name.clone()))
+        }))
+        .collect();
+    let substrate_ids: BTreeSet<String> = organisms
+        .iter()
+        .flat_map(|o| o.dynamic_parameters.metabolic_exchange.media_consumption.iter().map(|c| c.molecule_id.clone()))
+        .collect();
+    let gas_names: BTreeMap<String, String> = initial_media
+        .composition
+        .dissolved_gases
+        .iter()
+        .map(|g| (g.gas_id.clone(), g.gas_name.clone()))
+        .collect();
     
     let log_path = Path::new(output_dir).join("upstream_consortium.csv");
 
-    let mut rules = Vec::new();
+    let seeds = request
+        .seed_train
+        .as_ref()
+        .map_or_else(Vec::new, |seed_train| jit::seed_train_vessels(&plan.bioreactor, seed_train, kb));
+    let (upstream_process, sim_rules) = upstream_cultivation(request, kb, plan, &initial_media, &seeds)?;
+    let process_rules: HashMap<String, Rule> = sim_rules.iter().map(|r| (r.name.clone(), r.clone())).collect();
+    // The molecules the cultivation's feed rules add are always charted.
+    let cultivation_rule_ids: BTreeSet<&String> =
+        upstream_process.methods.iter().filter_map(|m| m.required_rule_ids.as_ref()).flatten().collect();
+    let fed_components: Vec<String> = sim_rules
+        .iter()
+        .filter(|rule| cultivation_rule_ids.contains(&rule.name))
+        .flat_map(|rule| match &rule.action {
+            Command::AddMaterial { material_id, .. } | Command::TopUpMaterial { material_id, .. } => {
+                vec![material_id]
+            }
+            Command::DiluteMedia { feed, .. } => feed.iter().map(|c| &c.molecule_id).collect(),
+            _ => vec![],
+        })
+        .filter_map(|molecule_id| molecule_names.get(molecule_id).cloned())
+        .collect();
+    let methods = upstream_process.methods.clone();
 
-    // Rule to stop the entire simulation when the slowest target is met
-    let lutein_target = request.targets.iter().find(|t| t.molecule_name == "Lutein").unwrap();
-    rules.push(Rule {
-        name: "rule_stop_on_lutein".to_string(),
-        condition: Condition::ProductAmount {
-            molecule_name: lutein_target.molecule_name.clone(),
-            target_grams: lutein_target.target_amount_grams,
-        },
+    let mut organism_ids: Vec<String> = organism_names.keys().cloned().collect();
+    organism_ids.sort();
+    let run_database = match options.log_backend {
+        LogBackend::Csv => None,
+        #[cfg(feature = "sqlite")]
+        LogBackend::Sqlite => Some(Path::new(output_dir).join(RUN_DATABASE)),
+    };
+    let mut progress = WorkflowProgress::load(output_dir)?;
+    // Only the JSON CSV log holds the state a completed cultivation is rebuilt from.
+    let resumable = run_database.is_none() && !options.wide_log;
+    let completed = progress.upstream.clone().filter(|_| resumable && log_completed(&log_path, &upstream_process));
+    let mut builder = SimulationBuilder::new()
+        .with_organisms(organism_clones)
+        .with_assets(kb.assets.values().cloned().collect())
+        .with_rules(sim_rules)
+        .with_process(upstream_process.clone())
+        .with_start_time(start_time);
+    let (mut engine, summary, sqlite_run_id) = if let Some(summary) = completed {
+        // The cultivation completed before the run was interrupted: its final state is rebuilt
+        // from its log, and everything else is derived from the log as after a fresh run.
+        println!("Resuming: the cultivation already completed; reading it from '{}'", log_path.display());
+        let point = analysis::state_from_log(log_path.to_str().unwrap(), &upstream_process, &kb.assets)?;
+        (builder.with_resume_point(point).build()?, summary, None)
+    } else {
+        builder = builder.with_event_log();
+        let (mut builder, sqlite_run_id): (SimulationBuilder, Option<String>) = match &run_database {
+            #[cfg(feature = "sqlite")]
+            Some(db_path) => {
+                let sink = SqliteSink::open(db_path.to_str().unwrap(), "upstream_consortium")?;
+                let run_id = sink.run_id().to_string();
+                (builder.with_timeseries_sink(Box::new(sink)), Some(run_id))
+            }
+            _ if options.wide_log => {
+                let series = WideSeries {
+                    molecule_ids: molecule_names.keys().cloned().collect(),
+                    gas_ids: gas_names.keys().cloned().collect(),
+                    organism_ids: organism_ids.clone(),
+                    substrate_ids: substrate_ids.into_iter().collect(),
+                    asset_ids: seeds.iter().chain([&plan.bioreactor]).map(|b| b.asset_id.clone()).collect(),
+                };
+                (builder.with_wide_timeseries_logging(log_path.to_str().unwrap(), series), None)
+            }
+            _ => (builder.with_timeseries_logging_to_file(log_path.to_str().unwrap()), None),
+        };
+        let mut fills = vessel_fills(&seeds, plan, &initial_media).into_iter();
+        if let Some((_, media)) = fills.next() {
+            builder = builder.with_initial_media(media);
+        }
+        for (asset_id, media) in fills {
+            builder = builder.with_vessel(&asset_id, media, Vec::new());
+        }
+        let mut engine = builder.build()?;
+        let summary = engine.run()?;
+        // The downstream runs of an earlier attempt started from another broth.
+        progress.upstream = Some(summary.clone());
+        progress.downstream.clear();
+        progress.save(output_dir)?;
+        (engine, summary, sqlite_run_id)
+    };
+    // A continuous culture hands on the broth it collected along with what is left in the vessel.
+    engine.pool_into_active_vessel(jit::HARVEST_VESSEL_ID);
+    let biomass_produced = summary
+        .final_biomass
+        .iter()
+        .map(|(id, grams)| (id.clone(), *grams))
+        .collect::<HashMap<_, _>>();
+
+    let (bom, records) = match (&run_database, &sqlite_run_id) {
+        #[cfg(feature = "sqlite")]
+        (Some(db_path), Some(run_id)) => {
+            let db_path = db_path.to_str().unwrap();
+            (
+                analysis::generate_bom_from_sqlite(db_path, run_id, engine.get_process(), &kb.assets, &kb.materials)?,
+                sqlite_log::read_sqlite_run(db_path, run_id)?,
+            )
+        }
+        _ => {
+            let log_path = log_path.to_str().unwrap();
+            (
+                analysis::generate_bom(log_path, engine.get_process(), &kb.assets, &kb.materials)?,
+                analysis::read_log(log_path)?,
+            )
+        }
+    };
+    // SQLite runs write no event log; their plots place events by the rows that carry them.
+    let events = analysis::read_event_log(log_path.to_str().unwrap())?;
+    let growth = analysis::growth_metrics(&records, &organism_ids)?;
+    let balance = mass_balance_from_records(&records, organisms, &kb.materials)?;
+    fs::write(Path::new(output_dir).join("mass_balance.json"), serde_json::to_string_pretty(&balance)?)?;
+    print_mass_balance(&balance, request.mass_balance_tolerance);
+    let targets = target_products(request, plan);
+    let substrate_consumed_g: HashMap<String, f64> = bom
+        .materials_by_consumer
+        .iter()
+        .map(|(organism_id, consumed)| (organism_id.clone(), consumed.values().sum()))
+        .collect();
+    let performance = process_performance_from_records(&records, &targets, &substrate_consumed_g)?;
+    fs::write(Path::new(output_dir).join("kpis.json"), serde_json::to_string_pretty(&performance)?)?;
+    let carbon_products = carbon_products(request, &performance);
+    let carbon_balance = carbon_balance_from_records(&records, organisms, &kb.materials, &carbon_products)?;
+    let handoff = engine.handoff_state();
+
+    let media_names: HashMap<String, String> = molecule_names.into_iter().chain(gas_names).collect();
+    let placeholder_cogs = analysis::CogsResult::default();
+    let placeholder_lca = analysis::LcaResult::default();
+    let media_selection = plotting::MediaSelection {
+        components: request.plotted_media_components.clone(),
+        fed: fed_components,
+    };
+    let max_growth_rates: HashMap<String, f64> =
+        organisms.iter().map(|o| (o.organism_id.clone(), o.dynamic_parameters.growth_rate_per_hr)).collect();
+    let target_goals: Vec<plotting::TargetGoal> = targets
+        .iter()
+        .filter_map(|product| {
+            let target = request.targets.iter().find(|t| t.molecule_name == product.molecule_name)?;
+            Some(plotting::TargetGoal { product: product.clone(), target_grams: plan.batch_grams(target.target_amount_grams) })
+        })
+        .collect();
+    let stage_asset_ids: BTreeSet<&String> = methods.iter().map(|m| &m.required_asset_id).collect();
+    let gas_saturations = plotting::gas_saturations(stage_asset_ids.into_iter().filter_map(|id| kb.assets.get(id)));
+    plotting::write_tidy_timeseries(output_dir, &records, &media_names, &organism_names, &methods)?;
+    plotting::generate_all_plots(output_dir, &request.plotting, &records, events.as_deref(), &placeholder_cogs, &placeholder_lca, organism_names, &max_growth_rates, media_names, &media_selection, &methods, &gas_saturations, request.biomass_scale, &target_goals, options.date_axis)?;
+    plotting::plot_gantt(output_dir, &request.plotting, &records, engine.get_process(), &kb.assets)?;
+    let productivity = targets
+        .iter()
+        .map(|target| Ok((target.molecule_name.clone(), volumetric_productivity(&records, target)?)))
+        .collect::<Result<Vec<_>>>()?;
+    plotting::plot_volumetric_productivity(output_dir, &request.plotting, &productivity)?;
+    let biomass = plotting::total_biomass(&records)?;
+
+    Ok(UpstreamOutput {
+        biomass_produced,
+        combined_bom: bom,
+        handoff,
+        start_time,
+        end_time: tick_timestamp(start_time, summary.total_ticks),
+        summary,
+        growth,
+        performance,
+        carbon_balance,
+        run_database,
+        biomass,
+        process: engine.get_process().clone(),
+        rules: process_rules,
+    })
+}
+
+/// The media each vessel of the cultivation starts with, in workflow order: the seed vessels,
+/// smallest first, then the production bioreactor of `plan`. Each is `initial_media` filled to
+/// the vessel's working volume less that of the seed culture transferred into it, so that the
+/// train ends with the production bioreactor's working volume of `initial_media` between them.
+fn vessel_fills(seeds: &[jit::Bioreactor], plan: &jit::SelectionPlan, initial_media: &MediaState) -> Vec<(String, MediaState)> {
+    let mut seeded_l = 0.0;
+    seeds
+        .iter()
+        .chain([&plan.bioreactor])
+        .map(|vessel| {
+            let mut media = initial_media.clone();
+            media.volume.value = vessel.working_volume_l - seeded_l;
+            seeded_l = vessel.working_volume_l;
+            (vessel.asset_id.clone(), media)
+        })
+        .collect()
+}
+
+/// The targets of `request` with a readable `formula`, with the grams of each the cultivation
+/// produced.
+fn carbon_products(request: &jit::ValorizationRequest, performance: &ProcessPerformance) -> Vec<CarbonProduct> {
+    request
+        .targets
+        .iter()
+        .filter_map(|target| {
+            let formula = target.formula.as_deref()?;
+            let Some(carbon_fraction) = carbon_mass_fraction(formula) else {
+                log::warn!("Cannot read the formula '{}' of {}", formula, target.molecule_name);
+                return None;
+            };
+            let grams = performance
+                .products
+                .iter()
+                .find(|p| p.molecule_name == target.molecule_name)
+                .map_or(0.0, |p| p.produced_g);
+            Some(CarbonProduct { molecule_name: target.molecule_name.clone(), grams, carbon_fraction })
+        })
+        .collect()
+}
+
+/// The targets of `request`, each with the organism `plan` assigns to produce it.
+fn target_products(request: &jit::ValorizationRequest, plan: &jit::SelectionPlan) -> Vec<TargetProduct> {
+    request
+        .targets
+        .iter()
+        .filter_map(|target| {
+            let (organism, content_mg_g) = plan.producer(&target.molecule_name)?;
+            Some(TargetProduct {
+                molecule_name: target.molecule_name.clone(),
+                organism_id: organism.organism_id.clone(),
+                content_mg_g,
+            })
+        })
+        .collect()
+}
+
+
+/// A batch ends once its biomass grows by less than this fraction per hour over
+/// `BATCH_STATIONARY_WINDOW` hours.
+const BATCH_STATIONARY_GROWTH: f64 = 0.001;
+const BATCH_STATIONARY_WINDOW: usize = 10;
+
+/// The cultivation process of the upstream run and the rules it runs under: the knowledge base's
+/// rules plus those that stop growth and the run once the targets are met, end the run once the
+/// request's `max_duration_hours` has passed, and feed the vessel by the request's
+/// `cultivation_strategy`, and one that holds the vessel at the request's
+/// `cultivation_temperature_celsius`, or else at the compromise temperature of the organisms of
+/// `plan` if their shared window is narrow.
+///
+/// The cultivation is preceded by a stage in each of the `seeds`, smallest first, that grows the
+/// culture until its biomass is stationary and is then transferred whole into the next vessel.
+fn upstream_cultivation(
+    request: &jit::ValorizationRequest,
+    kb: &KnowledgeBase,
+    plan: &jit::SelectionPlan,
+    initial_media: &MediaState,
+    seeds: &[jit::Bioreactor],
+) -> Result<(Process, Vec<Rule>)> {
+    let mut rules = Vec::new();
+    let mut seed_rules = vec![Rule {
+        name: "rule_seed_stationary".to_string(),
+        condition: Condition::BiomassStationary { threshold: BATCH_STATIONARY_GROWTH, window: BATCH_STATIONARY_WINDOW },
         action: Command::AdvanceToNextStep,
-    });
-    
-    // Rule to stop the growth of the faster organism when its target is met
-    let beta_glucan_target = request.targets.iter().find(|t| t.molecule_name == "beta-glucans").unwrap();
-    let agrobacterium_id = "ORG-AGROSP";
-    rules.push(Rule {
-        name: "rule_stop_agrobacterium_growth".to_string(),
-        condition: Condition::ProductAmount {
-            molecule_name: beta_glucan_target.molecule_name.clone(),
-            target_grams: beta_glucan_target.target_amount_grams,
-        },
-        action: Command::SetOrganismGrowthMultiplier {
-            organism_id: agrobacterium_id.to_string(),
-            multiplier: 0.0,
-        },
-    });
+    }];
 
+    let setpoint = match request.cultivation_temperature_celsius {
+        Some(celsius) => Some(celsius),
+        None => jit::consortium_window(&plan.organisms)?.temperature_setpoint,
+    };
+    if let Some(celsius) = setpoint {
+        rules.push(Rule {
+            name: "rule_cultivation_temperature".to_string(),
+            condition: Condition::AssetValue {
+                asset_id: plan.bioreactor.asset_id.clone(),
+                parameter: "temperature".to_string(),
+                operator: ComparisonOperator::NotEqualTo,
+                value: celsius,
+            },
+            action: Command::SetTemperature { asset_id: plan.bioreactor.asset_id.clone(), celsius },
+        });
+        for seed in seeds {
+            seed_rules.push(Rule {
+                name: format!("rule_seed_temperature_{}", rule_suffix(&seed.asset_id)),
+                condition: Condition::AssetValue {
+                    asset_id: seed.asset_id.clone(),
+                    parameter: "temperature".to_string(),
+                    operator: ComparisonOperator::NotEqualTo,
+                    value: celsius,
+                },
+                action: Command::SetTemperature { asset_id: seed.asset_id.clone(), celsius },
+            });
+        }
+    }
 
-    let feed_rule = Rule {
-        name: "rule_feed_sucrose".to_string(),
-        condition: Condition::MediaValue {
-            molecule_id: "CHEBI:17992".to_string(), // Correctly targeting Sucrose now
-            operator: ComparisonOperator::LessThan,
-            value: 1.0, // g/L
-        },
-        action: Command::AddMaterial {
-            asset_id: "CULTIVATION-LOOP-01".to_string(),
-            material_id: "CHEBI:17992".to_string(),
-            amount_grams: 2500.0, // Increased amount for a visible spike
-        },
+    rules.extend(target_rules(request, kb, plan)?);
+    let technique = match &request.cultivation_strategy {
+        jit::CultivationStrategy::Batch => {
+            rules.push(Rule {
+                name: "rule_batch_stationary".to_string(),
+                condition: Condition::BiomassStationary {
+                    threshold: BATCH_STATIONARY_GROWTH,
+                    window: BATCH_STATIONARY_WINDOW,
+                },
+                action: Command::AdvanceToNextStep,
+            });
+            "batch"
+        }
+        jit::CultivationStrategy::FedBatch { trigger_g_per_l, feed } => {
+            rules.extend(feed_rules(request, kb, plan, initial_media, *trigger_g_per_l, feed)?);
+            "fed-batch"
+        }
+        jit::CultivationStrategy::Continuous { dilution_rate_per_hr } => {
+            rules.push(dilution_rule(request, kb, plan, initial_media, *dilution_rate_per_hr)?);
+            "continuous"
+        }
     };
-    rules.push(feed_rule);
+    // A batch that cannot meet its targets within the time it may take is cut short rather than
+    // left to run on.
+    if let Some(max_hours) = request.constraints.max_duration_hours {
+        let rule = Rule {
+            name: "rule_max_duration".to_string(),
+            condition: Condition::TimeInStage { ticks: (max_hours / TIME_STEP_HR).ceil() as u64 },
+            action: Command::AdvanceToNextStep,
+        };
+        seed_rules.push(rule.clone());
+        rules.push(rule);
+    }
 
-    let cultivation_method = Method {
+    let seed_rule_ids: Vec<String> = seed_rules.iter().map(|r| r.name.clone()).collect();
+    let mut methods: Vec<Method> = seeds
+        .iter()
+        .enumerate()
+        .map(|(i, seed)| Method {
+            method_id: format!("MTHD-UP-SEED-{:02}", i + 1),
+            stage: format!("Seed {}", i + 1),
+            technique: "batch".to_string(),
+            required_asset_id: seed.asset_id.clone(),
+            operating_parameters: BTreeMap::new(),
+            required_materials: vec![],
+            qc_checks: vec![],
+            required_rule_ids: Some(seed_rule_ids.clone()),
+            transfers: i.checked_sub(1).map(|previous| vec![seed_transfer(&seeds[previous], seed)]),
+            expected_duration_ticks: None,
+            impurity_removal_percent: None,
+        })
+        .collect();
+    methods.push(Method {
         method_id: "MTHD-UP-CULT-DYNAMIC-01".to_string(),
         stage: "Cultivation".to_string(),
-        technique: "fed-batch".to_string(),
-        required_asset_id: "CULTIVATION-LOOP-01".to_string(),
-        operating_parameters: HashMap::new(),
+        technique: technique.to_string(),
+        required_asset_id: plan.bioreactor.asset_id.clone(),
+        operating_parameters: BTreeMap::new(),
         required_materials: vec![],
         qc_checks: vec![],
         required_rule_ids: Some(rules.iter().map(|r| r.name.clone()).collect()),
-    };
+        transfers: seeds.last().map(|seed| vec![seed_transfer(seed, &plan.bioreactor)]),
+        expected_duration_ticks: None,
+        impurity_removal_percent: None,
+    });
 
+    let notes = match seeds.len() {
+        0 => "A dynamically generated, single-stage cultivation process.".to_string(),
+        n => format!("A dynamically generated cultivation process with a {}-stage seed train.", n),
+    };
     let upstream_process = Process {
         process_id: "PROC-UPSTREAM-CULTIVATION-DYNAMIC".to_string(),
         process_name: "Dynamic Upstream Cultivation".to_string(),
         component_class: "Cultivation".to_string(),
         status: "Active".to_string(),
-        notes: "A dynamically generated, single-stage cultivation process.".to_string(),
-        default_workflow: vec![cultivation_method.method_id.clone()],
-        methods: vec![cultivation_method],
+        notes,
+        default_workflow: methods.iter().map(|m| m.method_id.clone()).collect(),
+        methods,
     };
 
     let mut sim_rules = kb.rules.clone();
-    for rule in rules {
+    for rule in rules.into_iter().chain(seed_rules) {
         sim_rules.insert(rule.name.clone(), rule);
     }
+    Ok((upstream_process, sim_rules.into_values().collect()))
+}
 
-    let mut engine = SimulationBuilder::new()
-        .with_organisms(organism_clones)
-        .with_assets(kb.assets.values().cloned().collect())
-        .with_rules(sim_rules.values().cloned().collect())
-        .with_process(upstream_process)
-        .with_initial_media(initial_media)
-        .with_timeseries_logging_to_file(log_path.to_str().unwrap())
-        .build()?;
+/// Moves the whole culture of the seed vessel `from` into the vessel `to` it seeds.
+fn seed_transfer(from: &jit::Bioreactor, to: &jit::Bioreactor) -> MaterialTransfer {
+    MaterialTransfer {
+        from_asset_id: from.asset_id.clone(),
+        from_port_id: jit::SEED_OUTLET_PORT.to_string(),
+        to_asset_id: to.asset_id.clone(),
+        to_port_id: jit::SEED_INLET_PORT.to_string(),
+        volume_l: None,
+        material_id: None,
+    }
+}
 
-    engine.run()?;
-    
-    let final_biomass_states = engine.get_organism_states();
-    let biomass_produced = final_biomass_states
+/// Each target of `request` with the organism `plan` assigns to produce it and the cultivation
+/// estimated to reach it, in request order.
+fn producing_organisms<'a>(
+    request: &'a jit::ValorizationRequest,
+    kb: &KnowledgeBase,
+    plan: &'a jit::SelectionPlan,
+) -> Result<Vec<(&'a jit::TargetRequest, &'a Organism, Option<jit::CultivationEstimate>)>> {
+    let mut producers = Vec::new();
+    for target in &request.targets {
+        let Some((organism, _)) = plan.producer(&target.molecule_name) else {
+            bail!("No organism is assigned to produce '{}'", target.molecule_name);
+        };
+        producers.push((target, organism, jit::estimate_cultivation(organism, target, kb)?));
+    }
+    Ok(producers)
+}
+
+/// The estimated hours to reach a target, with targets that cannot be estimated last.
+fn estimated_hours(estimate: &Option<jit::CultivationEstimate>) -> f64 {
+    estimate.as_ref().map_or(f64::INFINITY, |e| e.duration_hours)
+}
+
+/// `molecule_name` as part of a rule name, e.g. `beta_glucans`.
+fn rule_suffix(molecule_name: &str) -> String {
+    molecule_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// The rules that end the cultivation once a batch's share of the targets of `request` is met.
+/// Each producing organism stops growing at the target it is estimated to reach last, and the run
+/// moves on at the target estimated to take longest overall, whose producer is left to grow until
+/// then.
+fn target_rules(request: &jit::ValorizationRequest, kb: &KnowledgeBase, plan: &jit::SelectionPlan) -> Result<Vec<Rule>> {
+    let producers = producing_organisms(request, kb, plan)?;
+    let Some(slowest) = producers
         .iter()
-        .map(|(id, state)| (id.clone(), state.biomass.value))
-        .collect::<HashMap<_,_>>();
+        .max_by(|(_, _, a), (_, _, b)| estimated_hours(a).total_cmp(&estimated_hours(b)))
+    else {
+        bail!("None of the selected organisms produces a requested target");
+    };
 
-    let bom = analysis::generate_bom(log_path.to_str().unwrap(), engine.get_process(), &kb.assets, &kb.materials)?;
+    let mut rules = vec![Rule {
+        name: format!("rule_stop_on_{}", rule_suffix(&slowest.0.molecule_name)),
+        condition: Condition::ProductAmount {
+            molecule_name: slowest.0.molecule_name.clone(),
+            target_grams: plan.batch_grams(slowest.0.target_amount_grams),
+            organism_id: Some(slowest.1.organism_id.clone()),
+        },
+        action: Command::AdvanceToNextStep,
+    }];
+    for (target, organism, estimate) in &producers {
+        if organism.organism_id == slowest.1.organism_id {
+            continue;
+        }
+        let last_of_organism = producers
+            .iter()
+            .filter(|(_, other, _)| other.organism_id == organism.organism_id)
+            .all(|(_, _, other)| estimated_hours(other) <= estimated_hours(estimate));
+        if !last_of_organism {
+            continue;
+        }
+        rules.push(Rule {
+            name: format!("rule_stop_growth_on_{}", rule_suffix(&target.molecule_name)),
+            condition: Condition::ProductAmount {
+                molecule_name: target.molecule_name.clone(),
+                target_grams: plan.batch_grams(target.target_amount_grams),
+                organism_id: Some(organism.organism_id.clone()),
+            },
+            action: Command::SetOrganismGrowthMultiplier { organism_id: organism.organism_id.clone(), multiplier: 0.0 },
+        });
+    }
+    Ok(rules)
+}
 
-    let placeholder_cogs = analysis::CogsResult::default();
-    let placeholder_lca = analysis::LcaResult::default();
-    plotting::generate_all_plots(output_dir, log_path.to_str().unwrap(), &placeholder_cogs, &placeholder_lca, organism_names)?;
+/// Rules that top up each substrate the organisms of `plan` take up once it falls below
+/// `trigger_g_per_l`, by `feed`. What the producing organisms are estimated to take up on the way
+/// to their targets, beyond what `initial_media` holds, is fed in equal boluses, or each addition
+/// brings the substrate back up to a concentration; a substrate the initial media already covers
+/// is not fed.
+fn feed_rules(
+    request: &jit::ValorizationRequest,
+    kb: &KnowledgeBase,
+    plan: &jit::SelectionPlan,
+    initial_media: &MediaState,
+    trigger_g_per_l: f64,
+    feed: &jit::FeedAmount,
+) -> Result<Vec<Rule>> {
+    // The demand of each organism is that of the cultivation to the last of its targets.
+    let mut organism_estimates: BTreeMap<&str, jit::CultivationEstimate> = BTreeMap::new();
+    for (_, organism, estimate) in producing_organisms(request, kb, plan)? {
+        let Some(estimate) = estimate else { continue };
+        match organism_estimates.get(organism.organism_id.as_str()) {
+            Some(longest) if longest.duration_hours >= estimate.duration_hours => {}
+            _ => {
+                organism_estimates.insert(&organism.organism_id, estimate);
+            }
+        }
+    }
 
-    Ok(UpstreamOutput {
-        biomass_produced,
-        combined_bom: bom,
+    let substrates: BTreeMap<&str, &str> = plan
+        .organisms
+        .iter()
+        .flat_map(|o| &o.dynamic_parameters.metabolic_exchange.media_consumption)
+        .map(|c| (c.molecule_id.as_str(), c.molecule_name.as_str()))
+        .collect();
+    let mut rules = Vec::new();
+    for (molecule_id, molecule_name) in substrates {
+        let demand_g: f64 = organism_estimates
+            .values()
+            .filter_map(|estimate| estimate.bom.materials_consumed.get(molecule_id))
+            .sum();
+        let initial_g = initial_media
+            .composition
+            .dissolved_components
+            .iter()
+            .find(|c| c.molecule_id == molecule_id)
+            .map_or(0.0, |c| c.concentration.value * initial_media.volume.value);
+        let shortfall_g = demand_g - initial_g;
+        if shortfall_g <= 0.0 {
+            continue;
+        }
+        let action = match *feed {
+            jit::FeedAmount::Bolus { additions } => {
+                let amount_grams = shortfall_g / additions as f64;
+                println!(
+                    "Feeding {}: {:.0} g estimated demand, {:.0} g in the initial media, {:.0} g per addition below {} g/L",
+                    molecule_name, demand_g, initial_g, amount_grams, trigger_g_per_l
+                );
+                Command::AddMaterial {
+                    asset_id: plan.bioreactor.asset_id.clone(),
+                    material_id: molecule_id.to_string(),
+                    amount_grams,
+                }
+            }
+            jit::FeedAmount::ToConcentration { g_per_l } => {
+                println!(
+                    "Feeding {}: {:.0} g estimated demand, {:.0} g in the initial media, back up to {} g/L below {} g/L",
+                    molecule_name, demand_g, initial_g, g_per_l, trigger_g_per_l
+                );
+                Command::TopUpMaterial {
+                    asset_id: plan.bioreactor.asset_id.clone(),
+                    material_id: molecule_id.to_string(),
+                    concentration_g_l: g_per_l,
+                }
+            }
+        };
+        rules.push(Rule {
+            name: format!("rule_feed_{}", rule_suffix(molecule_name)),
+            condition: Condition::MediaValue {
+                molecule_id: molecule_id.to_string(),
+                operator: ComparisonOperator::LessThan,
+                value: trigger_g_per_l,
+            },
+            action,
+        });
+    }
+    Ok(rules)
+}
+
+/// The rule that exchanges `dilution_rate_per_hr` of the vessel's volume for fresh
+/// `initial_media` every hour, collecting the broth taken out in [`jit::HARVEST_VESSEL_ID`]. The
+/// rate must stay below the growth rate of every producing organism, which would otherwise wash
+/// out of the vessel.
+fn dilution_rule(
+    request: &jit::ValorizationRequest,
+    kb: &KnowledgeBase,
+    plan: &jit::SelectionPlan,
+    initial_media: &MediaState,
+    dilution_rate_per_hr: f64,
+) -> Result<Rule> {
+    for (_, organism, _) in producing_organisms(request, kb, plan)? {
+        let growth_rate = organism.dynamic_parameters.growth_rate_per_hr;
+        if dilution_rate_per_hr >= growth_rate {
+            bail!(
+                "A dilution rate of {}/h washes out {}, which grows at most {}/h",
+                dilution_rate_per_hr,
+                organism.organism_name,
+                growth_rate
+            );
+        }
+    }
+    let volume_l = dilution_rate_per_hr * initial_media.volume.value * TIME_STEP_HR;
+    println!(
+        "Diluting continuously at {}/h: {:.1} L of broth exchanged for fresh media every hour",
+        dilution_rate_per_hr, volume_l
+    );
+    Ok(Rule {
+        name: "rule_continuous_dilution".to_string(),
+        condition: Condition::TimeInStage { ticks: 0 },
+        action: Command::DiluteMedia {
+            asset_id: plan.bioreactor.asset_id.clone(),
+            volume_l,
+            feed: initial_media.composition.dissolved_components.clone(),
+            harvest_asset_id: Some(jit::HARVEST_VESSEL_ID.to_string()),
+        },
     })
 }
 
+/// The upstream cultivation as the base of a parameter study. Each run's cultivation ends after
+/// `max_hours` even if its targets were not met.
+fn study_base(
+    plan: &jit::SelectionPlan,
+    kb: &KnowledgeBase,
+    initial_media: MediaState,
+    request: &jit::ValorizationRequest,
+    max_hours: u64,
+) -> Result<BaseConfiguration> {
+    // A study's configuration holds a single vessel, so its cultivation is inoculated directly,
+    // without the request's seed train.
+    let (mut process, mut rules) = upstream_cultivation(request, kb, plan, &initial_media, &[])?;
+    // A variant that never reaches the targets, e.g. with a lowered yield, would otherwise
+    // cultivate forever.
+    let time_limit = Rule {
+        name: "rule_study_time_limit".to_string(),
+        condition: Condition::TimeInStage { ticks: max_hours },
+        action: Command::AdvanceToNextStep,
+    };
+    for method in &mut process.methods {
+        method.required_rule_ids.get_or_insert_with(Vec::new).push(time_limit.name.clone());
+    }
+    rules.push(time_limit);
+    Ok(BaseConfiguration {
+        organisms: plan.organisms.clone(),
+        assets: kb.assets.clone(),
+        rules,
+        process,
+        initial_media,
+        materials: kb.materials.clone(),
+        labor_roles: kb.labor_roles.clone(),
+        assumptions: kb.assumptions.clone(),
+        target_molecules: request.targets.iter().map(|t| t.molecule_name.clone()).collect(),
+    })
+}
+
+/// Runs a sensitivity study of the upstream cultivation, writing `sensitivity_report.csv` (and
+/// the tornado chart, if requested) to `output_dir` and printing the tornado table.
+pub fn run_sensitivity_study(
+    plan: &jit::SelectionPlan,
+    kb: &KnowledgeBase,
+    output_dir: &str,
+    initial_media: MediaState,
+    request: &jit::ValorizationRequest,
+    study_request: &SensitivityRequest,
+) -> Result<()> {
+    println!("\n--- [Workflow] Starting Sensitivity Study ---");
+    let base = study_base(plan, kb, initial_media, request, study_request.max_hours)?;
+    let study = study_request.parameters.iter().fold(SensitivityStudy::new(base), |study, parameter| {
+        study.with_parameter(&parameter.path, parameter.percent.unwrap_or(study_request.percent))
+    });
+    println!("Running {} variants...", 1 + 2 * study.parameters.len());
+    let report = study.run()?;
+
+    let csv_path = Path::new(output_dir).join("sensitivity_report.csv");
+    report.write_csv(csv_path.to_str().unwrap())?;
+    if study_request.tornado_chart {
+        plotting::plot_sensitivity_tornado(output_dir, &request.plotting, &report, &kb.assumptions.currency)?;
+    }
+
+    let currency = &kb.assumptions.currency;
+    let baseline = &report.baseline;
+    println!("\n--- [Sensitivity Report] ---");
+    println!(
+        "Baseline: {:.2} {} COGS, {:.2} kg CO₂e, {} hours",
+        baseline.total_cogs, currency, baseline.gwp_kg_co2e, baseline.duration_hours
+    );
+    let width = report.parameters.iter().map(|p| p.path.len()).max().unwrap_or(0).max("Parameter".len());
+    println!(
+        "\n  {:<width$} {:>6} {:>14} {:>14} {:>14} {:>14}",
+        "Parameter", "±%", "COGS Δ low", "COGS Δ high", "GWP Δ low", "GWP Δ high"
+    );
+    for parameter in &report.parameters {
+        println!(
+            "  {:<width$} {:>6.1} {:>14.2} {:>14.2} {:>14.2} {:>14.2}",
+            parameter.path,
+            parameter.percent,
+            parameter.low.total_cogs - baseline.total_cogs,
+            parameter.high.total_cogs - baseline.total_cogs,
+            parameter.low.gwp_kg_co2e - baseline.gwp_kg_co2e,
+            parameter.high.gwp_kg_co2e - baseline.gwp_kg_co2e
+        );
+    }
+    Ok(())
+}
+
+/// Runs a Monte Carlo study of the upstream cultivation, writing the samples to
+/// `monte_carlo_samples.csv` and a histogram per output metric to `output_dir`, and printing the
+/// percentiles of each metric.
+pub fn run_monte_carlo_study(
+    plan: &jit::SelectionPlan,
+    kb: &KnowledgeBase,
+    output_dir: &str,
+    initial_media: MediaState,
+    request: &jit::ValorizationRequest,
+    study_request: &MonteCarloRequest,
+) -> Result<()> {
+    println!("\n--- [Workflow] Starting Monte Carlo Study ---");
+    let base = study_base(plan, kb, initial_media, request, study_request.max_hours)?;
+    let study = study_request
+        .parameters
+        .iter()
+        .fold(MonteCarloStudy::new(base, study_request.samples, study_request.seed), |study, parameter| {
+            study.with_parameter(&parameter.path, parameter.distribution.clone())
+        });
+    println!("Running {} samples (seed {})...", study.samples, study.seed);
+    let report = study.run()?;
+
+    let csv_path = Path::new(output_dir).join("monte_carlo_samples.csv");
+    report.write_samples_csv(csv_path.to_str().unwrap())?;
+    plotting::plot_monte_carlo_histograms(output_dir, &request.plotting, &report)?;
+
+    println!("\n--- [Monte Carlo Report] ---");
+    println!("  {:<16} {:>14} {:>14} {:>14}", "Metric", "P10", "P50", "P90");
+    for (name, metric) in METRICS {
+        match report.percentiles(metric) {
+            Some(p) => println!("  {:<16} {:>14.4} {:>14.4} {:>14.4}", name, p.p10, p.p50, p.p90),
+            None => println!("  {:<16} {:>14}", name, "n/a"),
+        }
+    }
+    println!("  (COGS in {}, GWP in kg CO₂e, cost per gram of target, duration in hours)", kb.assumptions.currency);
+    Ok(())
+}
 
 /// Orchestrates the downstream processing simulations and generates the final reports.
+#[allow(clippy::too_many_arguments)]
 pub fn run_downstream_and_report(
     processes: &[&Process],
     upstream_output: &UpstreamOutput,
     kb: &KnowledgeBase,
     output_dir: &str,
     request: &jit::ValorizationRequest,
-    upstream_organisms: &[Organism],
+    plan: &jit::SelectionPlan,
     initial_bom: BillOfMaterials,
-) -> Result<()> {
+    options: OutputOptions,
+) -> Result<RunOutcome> {
     println!("\n--- [Workflow] Starting Downstream Simulations ---");
     let mut all_boms = vec![initial_bom, upstream_output.combined_bom.clone()];
+    let upstream_organisms = &plan.organisms;
+    let organism_ids: Vec<String> = upstream_organisms.iter().map(|o| o.organism_id.clone()).collect();
+    let mut run_summaries: BTreeMap<String, RunReport> = BTreeMap::new();
+    run_summaries.insert(
+        "upstream_consortium".to_string(),
+        RunReport { summary: &upstream_output.summary, growth: &upstream_output.growth },
+    );
+    let mut downstream_summaries = Vec::new();
+    let organism_names: HashMap<String, String> =
+        upstream_organisms.iter().map(|o| (o.organism_id.clone(), o.organism_name.clone())).collect();
+    // Downstream media start as the harvested broth and gain the materials the processes add.
+    let handoff_media = &upstream_output.handoff.media.composition;
+    let media_names: HashMap<String, String> = kb
+        .materials
+        .values()
+        .map(|m| (m.material_id.clone(), m.material_name.clone()))
+        .chain(handoff_media.dissolved_components.iter().map(|c| (c.molecule_id.clone(), c.molecule_name.clone())))
+        .chain(handoff_media.dissolved_gases.iter().map(|g| (g.gas_id.clone(), g.gas_name.clone())))
+        .collect();
+    let media_selection =
+        plotting::MediaSelection { components: request.plotted_media_components.clone(), fed: Vec::new() };
 
+    let mut progress = WorkflowProgress::load(output_dir)?;
     for process in processes {
         println!("\nProcessing for: {}", process.process_name);
-        let log_path =
-            Path::new(output_dir).join(format!("downstream_{}.csv", process.process_id));
-
-        let placeholder_org = kb.organisms.values().next().unwrap().clone();
-
-        let initial_media = jit::generate_initial_media(&[placeholder_org.clone()], output_dir)?;
-
-        let mut engine = SimulationBuilder::new()
-            .with_organisms(vec![placeholder_org])
-            .with_assets(kb.assets.values().cloned().collect())
-            .with_rules(kb.rules.values().cloned().collect())
-            .with_process((*process).clone())
-            .with_initial_media(initial_media)
-            .with_timeseries_logging_to_file(log_path.to_str().unwrap())
-            .build()?;
 
-        engine.run()?;
+        let log_path = Path::new(output_dir).join(format!("downstream_{}.csv", process.process_id));
+        let completed =
+            progress.downstream.get(&process.process_id).filter(|_| log_completed(&log_path, process)).cloned();
+        let (summary, records) = if let Some(summary) = completed {
+            println!("Resuming: '{}' already completed; reading it from '{}'", process.process_id, log_path.display());
+            (summary, analysis::read_log(log_path.to_str().unwrap())?)
+        } else {
+            // Downstream starts from the harvested broth: the real media, biomass, and product
+            // amounts from upstream, with growth stopped. The BOM and the plots are made from
+            // its log in memory; the copy on disk lets an interrupted run resume without it.
+            let builder = SimulationBuilder::new()
+                .with_organisms(upstream_organisms.to_vec())
+                .with_assets(kb.assets.values().cloned().collect())
+                .with_rules(kb.rules.values().cloned().collect())
+                .with_process((*process).clone())
+                .with_handoff(upstream_output.handoff.clone().harvested())
+                .with_start_time(upstream_output.end_time)
+                .with_timeseries_sink(Box::new(InMemorySink::new()));
+            let builder = match &upstream_output.run_database {
+                #[cfg(feature = "sqlite")]
+                Some(db_path) => {
+                    let label = format!("downstream_{}", process.process_id);
+                    builder.with_additional_sink(Box::new(SqliteSink::open(db_path.to_str().unwrap(), &label)?))
+                }
+                _ => builder.with_additional_sink(Box::new(TimeSeriesLogger::new(
+                    log_path.to_str().unwrap(),
+                    LogFormat::Csv,
+                )?)),
+            };
+            let mut engine = builder.build()?;
 
-        let bom =
-            analysis::generate_bom(log_path.to_str().unwrap(), process, &kb.assets, &kb.materials)?;
+            let summary = engine.run()?;
+            progress.downstream.insert(process.process_id.clone(), summary.clone());
+            progress.save(output_dir)?;
+            (summary, engine.logged_records().unwrap_or_default().to_vec())
+        };
+        let records = records.as_slice();
+        let plot_dir = Path::new(output_dir).join(format!("downstream_{}", process.process_id));
+        fs::create_dir_all(&plot_dir)?;
+        plotting::generate_downstream_plots(
+            plot_dir.to_str().unwrap(),
+            &request.plotting,
+            records,
+            &organism_names,
+            &media_names,
+            &media_selection,
+            &process.methods,
+            options.date_axis,
+        )?;
+        plotting::write_tidy_timeseries(plot_dir.to_str().unwrap(), records, &media_names, &organism_names, &process.methods)?;
+        plotting::plot_gantt(plot_dir.to_str().unwrap(), &request.plotting, records, process, &kb.assets)?;
+        let growth = analysis::growth_metrics(records, &organism_ids)?;
+        downstream_summaries.push((format!("downstream_{}", process.process_id), summary, growth));
+        let bom = analysis::generate_bom_from_records(records, process, &kb.assets, &kb.materials)?;
+        let blueprint =
+            analysis::generate_blueprint_from_records(process, &kb.rules, records)?.with_material_estimates(&bom);
+        let blueprint_path = Path::new(output_dir).join(format!("blueprint_{}.yaml", process.process_id));
+        analysis::write_blueprint_yaml(&blueprint, blueprint_path.to_str().unwrap())?;
         all_boms.push(bom);
     }
 
     println!("\n--- [Workflow] Aggregating Reports ---");
-    let final_bom = aggregate_boms(all_boms);
+    run_summaries.extend(
+        downstream_summaries
+            .iter()
+            .map(|(name, summary, growth)| (name.clone(), RunReport { summary, growth })),
+    );
+    fs::write(
+        Path::new(output_dir).join("run_summary.json"),
+        serde_json::to_string_pretty(&run_summaries)?,
+    )?;
+    let batch_bom = aggregate_boms(all_boms);
+    let batch_cogs = analysis::calculate_cogs(&batch_bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions)?;
+    let batch_lca = analysis::calculate_lca(&batch_bom, &kb.materials, &kb.assets, &kb.assumptions)?;
 
-    let final_cogs = analysis::calculate_cogs(&final_bom, &kb.materials, &kb.labor_roles, &kb.assets)?;
-    let final_lca = analysis::calculate_lca(&final_bom, &kb.materials, &kb.assets)?;
+    // A scale-out repeats the simulated batch: its materials, energy, labor, and hours, and the
+    // share of each asset's depreciation its hours bear, but releases the pooled product once.
+    // Annual figures stay those of one batch, so the assets' yearly ownership costs do not grow.
+    let final_bom = batch_bom.scaled_out(plan.batches);
+    let final_cogs = analysis::calculate_cogs(&final_bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions)?;
+    let final_lca = analysis::calculate_lca(&final_bom, &kb.materials, &kb.assets, &kb.assumptions)?;
 
     let qca_table = generate_qca_table(processes);
     fs::write(Path::new(output_dir).join("qca_report.md"), qca_table)?;
+    let constraint_checks = constraint_checks(request, &final_bom, &final_cogs, &final_lca);
+    write_analysis_reports(
+        output_dir,
+        &final_bom,
+        &final_cogs,
+        &final_lca,
+        kb,
+        constraint_checks,
+        request.organism_filter.clone(),
+        plan.batches,
+    )?;
+
+    let flow: Vec<&Process> = std::iter::once(&upstream_output.process).chain(processes.iter().copied()).collect();
+    plotting::plot_process_flow(output_dir, &request.plotting, &flow, &upstream_output.rules)?;
+    plotting::plot_lca_impacts(output_dir, &request.plotting, &final_lca)?;
+    plotting::plot_cogs_breakdown(output_dir, &request.plotting, &final_cogs, &kb.assumptions.currency)?;
+    plotting::plot_bom_materials(
+        output_dir,
+        &request.plotting,
+        &final_bom,
+        &kb.materials,
+        &final_cogs,
+        &kb.assumptions.currency,
+    )?;
+    let carbon_balance =
+        upstream_output.carbon_balance.clone().with_fossil_energy(final_bom.total_energy_kwh, &kb.assumptions);
+    fs::write(Path::new(output_dir).join("carbon_balance.json"), serde_json::to_string_pretty(&carbon_balance)?)?;
+    plotting::plot_carbon_balance(output_dir, &request.plotting, &carbon_balance, final_lca.gwp_kg_co2e())?;
 
-    plotting::plot_process_flow(output_dir, processes, &kb.rules)?;
+    let allocation =
+        allocate_targets(&batch_bom, &batch_cogs, &batch_lca, processes, request, upstream_output, plan);
+    if let Ok(report) = &allocation {
+        fs::write(Path::new(output_dir).join("allocation_report.json"), serde_json::to_string_pretty(report)?)?;
+    }
+    write_labor_report(&Path::new(output_dir).join("labor_report.csv"), &final_bom, kb)?;
+    let campaign =
+        evaluate_campaign(&batch_bom, &batch_cogs, &batch_lca, request, upstream_output, kb, plan);
+    if let Ok(report) = &campaign {
+        fs::write(Path::new(output_dir).join("campaign_report.json"), serde_json::to_string_pretty(report)?)?;
+    }
+    let financials =
+        evaluate_financials(&batch_bom, &batch_cogs, &campaign, request, upstream_output, kb, plan);
+    if let Some(financials) = &financials {
+        fs::write(Path::new(output_dir).join("financials.json"), serde_json::to_string_pretty(financials)?)?;
+    }
+
+    print_summary_report(
+        &final_bom,
+        &final_cogs,
+        &final_lca,
+        &allocation,
+        financials.as_ref(),
+        &campaign,
+        &carbon_balance,
+        processes,
+        request,
+        upstream_output,
+        kb,
+        plan,
+    );
+
+    let metadata = RunMetadata {
+        started_at: upstream_output.start_time.to_rfc3339(),
+        upstream_ended_at: upstream_output.end_time.to_rfc3339(),
+        reported_at: Utc::now().to_rfc3339(),
+        knowledge_base_hash: kb.content_hash.clone(),
+        currency: kb.assumptions.currency.clone(),
+    };
+    fs::write(Path::new(output_dir).join(report::METADATA_FILE), serde_json::to_string_pretty(&metadata)?)?;
+    let html_path = report::generate_html(output_dir)?;
+    println!("\nHTML report written to '{}'.", html_path.display());
+
+    Ok(RunOutcome {
+        duration_hours: final_bom.total_ticks,
+        total_cogs: final_cogs.total_cogs,
+        gwp_kg_co2e: final_lca.gwp_kg_co2e(),
+        produced_grams: request
+            .targets
+            .iter()
+            .map(|t| {
+                let grams = produced_target(&t.molecule_name, upstream_output, plan)
+                    .map_or(0.0, |(_, grams)| grams * f64::from(plan.batches));
+                (t.molecule_name.clone(), grams)
+            })
+            .collect(),
+        biomass: upstream_output.biomass.clone(),
+    })
+}
+
+/// Prints the KPIs of the cultivation.
+fn print_performance(performance: &ProcessPerformance, upstream_organisms: &[Organism]) {
+    let organism_name = |id: &str| {
+        upstream_organisms.iter().find(|o| o.organism_id == id).map_or(id.to_string(), |o| o.organism_name.clone())
+    };
+    let yield_text = |value: Option<f64>| value.map_or("n/a".to_string(), |v| format!("{:.4} g/g", v));
+
+    println!("\nCultivation KPIs (batch of {:.0} h):", performance.batch_duration_hr);
+    for (organism_id, rate) in &performance.peak_specific_growth_rates {
+        println!("  - Peak μ of {}: {:.4} 1/h", organism_name(organism_id), rate);
+    }
+    for product in &performance.products {
+        println!(
+            "  - {:<12} | Titer: {:.4} g/L | Space-time yield: {:.6} g/L/h | Yp/x: {} | Yx/s: {}",
+            product.molecule_name,
+            product.titer_g_l,
+            product.space_time_yield_g_l_h,
+            yield_text(product.yield_product_per_biomass),
+            yield_text(product.yield_biomass_per_substrate)
+        );
+    }
+}
+
+/// Prints where the carbon of the run came from and went, and its biogenic and fossil CO₂e,
+/// warning when the carbon does not reconcile within `tolerance`.
+fn print_carbon_balance(balance: &CarbonBalance, tolerance: f64) {
+    println!("\nCarbon Balance:");
+    println!("  {:<28}{:>14}{:>14}", "", "g C", "kg CO2e");
+    let row = |label: &str, carbon_g: Option<f64>, co2e: Option<f64>| {
+        let cell = |value: Option<f64>| value.map_or(String::new(), |v| format!("{:.2}", v));
+        println!("  {:<28}{:>14}{:>14}", label, cell(carbon_g), cell(co2e));
+    };
+    row("Feedstock (organic)", Some(balance.feedstock_carbon_g), None);
+    row("Biogenic fixation (CO2)", Some(balance.fixed_carbon_g), Some(0.0 - balance.biogenic_fixation_kg_co2e));
+    row("Into biomass", Some(balance.biomass_carbon_g), None);
+    for (molecule_name, carbon_g) in &balance.product_carbon_g {
+        row(&format!("  of which {}", molecule_name), Some(*carbon_g), None);
+    }
+    row("Secreted", Some(balance.secreted_carbon_g), None);
+    row("Biogenic emission (respired)", Some(balance.respired_carbon_g), Some(balance.biogenic_emission_kg_co2e));
+    row("Net biogenic", None, Some(balance.net_biogenic_kg_co2e));
+    row("Fossil (grid energy)", None, Some(balance.fossil_energy_kg_co2e));
+    if !balance.reconciles(tolerance) {
+        println!(
+            "  WARNING: {:.2} g C gained by the organisms is not accounted for by their uptake, or the products hold more carbon than the biomass",
+            -balance.unreconciled_carbon_g
+        );
+    }
+}
+
+/// Prints the upstream elemental balance, warning of every element whose closure error exceeds
+/// `tolerance`.
+fn print_mass_balance(balance: &MassBalanceReport, tolerance: f64) {
+    println!("\nUpstream Mass Balance:");
+    for element in &balance.elements {
+        println!(
+            "  - {}: {:.2} g taken up, {:.2} g in biomass, {:.2} g secreted, closure error {:.2} g{}",
+            element.element,
+            element.uptake_g,
+            element.biomass_g,
+            element.secreted_g,
+            element.closure_error_g,
+            element.relative_error.map_or(String::new(), |e| format!(" ({:.1}%)", e * 100.0))
+        );
+    }
+    if !balance.unmatched_molecules.is_empty() {
+        println!("  - Not balanced (no material data): {}", balance.unmatched_molecules.join(", "));
+    }
+    for element in balance.exceeding(tolerance) {
+        println!(
+            "  Warning: the {} balance misses by {:.1}%, more than the {:.1}% tolerance.",
+            element.element,
+            element.relative_error.unwrap_or_default() * 100.0,
+            tolerance * 100.0
+        );
+    }
+}
 
-    print_summary_report(&final_bom, &final_cogs, &final_lca, processes, request, upstream_output, kb, upstream_organisms);
+/// The run, at the COGS, duration, and GWP of `cogs`, `bom`, and `lca`, against each limit
+/// `request` sets.
+fn constraint_checks(
+    request: &jit::ValorizationRequest,
+    bom: &BillOfMaterials,
+    cogs: &analysis::CogsResult,
+    lca: &analysis::LcaResult,
+) -> Vec<ConstraintCheck> {
+    request.constraints.check(Some(cogs.total_cogs), Some(bom.total_ticks as f64), Some(lca.gwp_kg_co2e()))
+}
 
+/// Writes the BOM, COGS, and LCA of the run to `bom.json`, `cogs.json`, and `lca.json`, all three
+/// with the labor feasibility check, the `constraints` checks, the `organism_filter` the
+/// organisms were selected under, and the number of `batches` they total over to `report.json`,
+/// and the materials of each stage to `bom.csv`.
+#[allow(clippy::too_many_arguments)]
+fn write_analysis_reports(
+    output_dir: &str,
+    bom: &BillOfMaterials,
+    cogs: &analysis::CogsResult,
+    lca: &analysis::LcaResult,
+    kb: &KnowledgeBase,
+    constraints: Vec<ConstraintCheck>,
+    organism_filter: OrganismFilter,
+    batches: u32,
+) -> Result<()> {
+    let dir = Path::new(output_dir);
+    let report = AnalysisReport {
+        bom: NamedBom::new(bom.clone(), &kb.materials),
+        cogs: cogs.clone(),
+        lca: lca.clone(),
+        labor_feasibility: labor_feasibility(&bom.by_stage, &kb.labor_roles),
+        constraints,
+        organism_filter,
+        batches,
+    };
+    fs::write(dir.join("bom.json"), serde_json::to_string_pretty(&Versioned::new(&report.bom))?)?;
+    fs::write(dir.join("cogs.json"), serde_json::to_string_pretty(&Versioned::new(&report.cogs))?)?;
+    fs::write(dir.join("lca.json"), serde_json::to_string_pretty(&Versioned::new(&report.lca))?)?;
+    report.bom.write_csv(dir.join("bom.csv").to_str().unwrap())?;
+    fs::write(dir.join("report.json"), serde_json::to_string_pretty(&Versioned::new(report))?)?;
     Ok(())
 }
 
+/// The file in the run directory a dry run writes its [`RunPlan`] to.
+pub const PLAN_FILE: &str = "plan.json";
+
+/// What the JIT layer decided for a request, as a dry run prints and writes it without simulating.
+#[derive(Debug, Serialize)]
+pub struct RunPlan {
+    pub organisms: Vec<PlannedOrganism>,
+    /// The id of the organism producing each target, by molecule name.
+    pub producers: BTreeMap<String, String>,
+    pub bioreactor: jit::Bioreactor,
+    pub batches: u32,
+    /// The purchasable materials the initial media of a batch is made of.
+    pub media: Vec<PlannedIngredient>,
+    /// The estimated cost of the initial media of a batch.
+    pub media_cost: f64,
+    pub currency: String,
+    pub processes: Vec<ProcessEstimate>,
+    pub purities: Vec<jit::PurityEstimate>,
+}
+
+/// An organism of a [`RunPlan`].
+#[derive(Debug, Serialize)]
+pub struct PlannedOrganism {
+    pub organism_id: String,
+    pub organism_name: String,
+    /// The inoculum, in g of dry biomass, scaled to the targets.
+    pub inoculum_g: f64,
+    /// The organism's share of the consortium's total inoculum.
+    pub inoculum_ratio: f64,
+    /// The g of dry biomass the organism must reach in a batch.
+    pub required_biomass_g: f64,
+    /// The organism's yield of each target it produces, in mg/g, by molecule name.
+    pub yields: BTreeMap<String, f64>,
+}
+
+/// A material of the initial media of a [`RunPlan`], with its estimated cost.
+#[derive(Debug, Serialize)]
+pub struct PlannedIngredient {
+    pub material_id: String,
+    pub material_name: String,
+    pub amount: f64,
+    pub unit: String,
+    pub cost: f64,
+}
+
+/// The blueprint-based estimate of a downstream process.
+#[derive(Debug, Serialize)]
+pub struct ProcessEstimate {
+    pub process_id: String,
+    pub process_name: String,
+    /// The hours of the stages whose duration is known in advance.
+    pub duration_hours: u64,
+    pub energy_kwh: f64,
+    /// The estimated COGS, excluding materials.
+    pub cogs: f64,
+}
+
+/// Plans the workflow of `request` without building any engine: prints the organisms of `plan`
+/// with their yields and inocula, the initial media and its recipe, and the estimate of the
+/// downstream `processes`, then writes the plan to [`PLAN_FILE`] in `output_dir`, next to the
+/// initial media and recipe files.
+pub fn plan_workflow(
+    request: &jit::ValorizationRequest,
+    kb: &KnowledgeBase,
+    plan: &jit::SelectionPlan,
+    processes: &[&Process],
+    output_dir: &str,
+) -> Result<RunPlan> {
+    println!("\n--- [Dry Run] Planning the Workflow ---");
+    let total_inoculum: f64 = plan.organisms.iter().map(|o| o.initial_biomass.value).sum();
+    let organisms: Vec<PlannedOrganism> = plan
+        .organisms
+        .iter()
+        .map(|org| PlannedOrganism {
+            organism_id: org.organism_id.clone(),
+            organism_name: org.organism_name.clone(),
+            inoculum_g: org.initial_biomass.value,
+            inoculum_ratio: if total_inoculum > 0.0 { org.initial_biomass.value / total_inoculum } else { 0.0 },
+            required_biomass_g: plan.required_biomass_g.get(&org.organism_id).copied().unwrap_or(0.0),
+            yields: plan
+                .producers
+                .iter()
+                .filter(|(_, producer)| **producer == org.organism_id)
+                .filter_map(|(molecule, _)| Some((molecule.clone(), org.yield_for(molecule)?)))
+                .collect(),
+        })
+        .collect();
+    println!("Bioreactor: {} ({:.0} L working volume), {} batch(es)", plan.bioreactor.asset_id, plan.bioreactor.working_volume_l, plan.batches);
+    for org in &organisms {
+        println!(
+            "  {} ({}): inoculum {:.4} g ({:.1}%), required biomass {:.1} g",
+            org.organism_name,
+            org.organism_id,
+            org.inoculum_g,
+            org.inoculum_ratio * 100.0,
+            org.required_biomass_g
+        );
+        for (molecule, yield_mg_g) in &org.yields {
+            println!("    - produces {} at {:.2} mg/g", molecule, yield_mg_g);
+        }
+    }
+
+    let initial_media = jit::generate_initial_media(plan, request, kb, output_dir)?;
+    let recipe = jit::generate_media_recipe(&initial_media, request.available_feedstock.as_ref(), kb, output_dir)?;
+    let materials = analysis::MaterialResolver::new(&kb.materials);
+    let media: Vec<PlannedIngredient> = recipe
+        .ingredients
+        .iter()
+        .map(|ingredient| PlannedIngredient {
+            material_id: ingredient.material_id.clone(),
+            material_name: ingredient.material_name.clone(),
+            amount: ingredient.amount.value,
+            unit: ingredient.amount.unit.clone(),
+            cost: analysis::material_cost(&materials, &ingredient.material_id, ingredient.amount.value * 1000.0),
+        })
+        .collect();
+    let media_cost = media.iter().map(|i| i.cost).sum();
+    println!("Estimated media cost: {:.2} {}", media_cost, kb.assumptions.currency);
+
+    let run_plan = RunPlan {
+        organisms,
+        producers: plan.producers.clone(),
+        bioreactor: plan.bioreactor.clone(),
+        batches: plan.batches,
+        media,
+        media_cost,
+        currency: kb.assumptions.currency.clone(),
+        processes: estimate_downstream(processes, kb)?,
+        purities: plan.purities.clone(),
+    };
+    let plan_path = Path::new(output_dir).join(PLAN_FILE);
+    fs::write(&plan_path, serde_json::to_string_pretty(&run_plan)?)?;
+    println!("Plan written to '{}'.", plan_path.display());
+    Ok(run_plan)
+}
+
+/// Prints a blueprint-based estimate of the downstream processes without running any simulation,
+/// and returns the estimate of each.
+///
+/// The upstream cultivation stops on product targets rather than elapsed time, so its duration
+/// cannot be known in advance and it is left out of the estimate.
+pub fn estimate_downstream(processes: &[&Process], kb: &KnowledgeBase) -> Result<Vec<ProcessEstimate>> {
+    println!("\n--- [Dry Run] Estimating Downstream Resources ---");
+    println!("Assumes every stage lasts its TimeInStage or expected duration at nominal power; materials are not estimated.");
+    println!("The upstream cultivation is product-gated and is not included.");
+    println!("Economic assumptions: {}", kb.assumptions.name);
+    let currency = &kb.assumptions.currency;
+
+    let mut boms = Vec::new();
+    let mut estimates = Vec::new();
+    for process in processes {
+        let blueprint = analysis::generate_blueprint(process, &kb.rules)?;
+        let (bom, cogs) = analysis::estimate_resources(&blueprint, &kb.assets, &kb.labor_roles, &kb.assumptions)?;
+
+        println!("\n{} ({}):", blueprint.process_name, blueprint.process_id);
+        for step in &blueprint.workflow {
+            let duration = match step.duration_source {
+                DurationSource::TimeInStageRule => format!("{:>5} h", step.duration_ticks),
+                DurationSource::ExpectedDuration => format!("{:>5} h (expected)", step.duration_ticks),
+                DurationSource::ObservedRun => format!("{:>5} h (observed)", step.duration_ticks),
+                DurationSource::Dynamic => match transition_conditions(&step.transition_rules) {
+                    conditions if conditions.is_empty() => "dynamic, not estimated".to_string(),
+                    conditions => format!("dynamic, until {}", conditions),
+                },
+            };
+            println!("  {}. {:<28} on {:<24} {}", step.step, step.method_id, step.asset_id, duration);
+        }
+        println!("  - Duration: {} hours", bom.total_ticks);
+        println!("  - Energy:   {:.2} kWh", bom.total_energy_kwh);
+        println!("  - COGS:     {:.2} {}", cogs.total_cogs, currency);
+        estimates.push(ProcessEstimate {
+            process_id: blueprint.process_id.clone(),
+            process_name: blueprint.process_name.clone(),
+            duration_hours: bom.total_ticks,
+            energy_kwh: bom.total_energy_kwh,
+            cogs: cogs.total_cogs,
+        });
+        boms.push(bom);
+    }
+
+    let total_bom = aggregate_boms(boms);
+    let total_cogs = analysis::calculate_cogs(&total_bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions)?;
+
+    println!("\n========================================");
+    println!("Estimated Duration: {} hours", total_bom.total_ticks);
+    println!("Estimated Energy:   {:.2} kWh", total_bom.total_energy_kwh);
+    println!("Estimated Labor:");
+    let mut labor: Vec<_> = total_bom.labor_hours.iter().collect();
+    labor.sort_by(|a, b| a.0.cmp(b.0));
+    for (role_id, hours) in labor {
+        let role_name = kb.labor_roles.get(role_id).map_or(role_id.as_str(), |r| r.role_name.as_str());
+        println!("  - {}: {:.2} h", role_name, hours);
+    }
+    println!("\nEstimated COGS (excluding materials):");
+    println!("  - Labor Costs:              {:.2} {}", total_cogs.labor_costs, currency);
+    println!("  - Energy Costs:             {:.2} {}", total_cogs.energy_costs, currency);
+    println!("  - Asset Depreciation:       {:.2} {}", total_cogs.asset_depreciation_costs, currency);
+    println!("  - Maintenance Costs:        {:.2} {}", total_cogs.maintenance_costs, currency);
+    println!("  - End-of-Life Costs:        {:.2} {}", total_cogs.end_of_life_costs, currency);
+    println!("  --------------------------------------");
+    println!("  - Total:                    {:.2} {}", total_cogs.total_cogs, currency);
+    println!("========================================");
+    Ok(estimates)
+}
+
 fn aggregate_boms(boms: Vec<BillOfMaterials>) -> BillOfMaterials {
     let mut combined_bom = BillOfMaterials::default();
-    for bom in boms {
-        combined_bom.total_energy_kwh += bom.total_energy_kwh;
-        combined_bom.total_ticks += bom.total_ticks;
-        for (material, quantity) in bom.materials_consumed {
-            *combined_bom
-                .materials_consumed
-                .entry(material)
-                .or_insert(0.0) += quantity;
+    for bom in &boms {
+        combined_bom.merge(bom);
+    }
+    combined_bom
+}
+
+/// Prints the COGS of each stage, most expensive first. Costs that belong to no stage, such as the
+/// initial media, are shown as an unstaged remainder.
+fn print_stage_costs(bom: &BillOfMaterials, cogs: &analysis::CogsResult) {
+    if cogs.by_stage.is_empty() {
+        return;
+    }
+    let mut stages: Vec<(&String, &analysis::CogsResult)> = cogs.by_stage.iter().collect();
+    stages.sort_by(|a, b| b.1.total_cogs.total_cmp(&a.1.total_cogs).then_with(|| a.0.cmp(b.0)));
+
+    println!("\nCost by Stage:");
+    println!(
+        "  {:<28} {:>7} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12} {:>12}",
+        "Stage", "Hours", "Materials", "Labor", "Energy", "Assets", "End-of-Life", "QC", "Total"
+    );
+    let mut staged_total = 0.0;
+    for (stage_id, stage_cogs) in stages {
+        let hours = bom.by_stage.get(stage_id).map_or(0, |stage| stage.ticks);
+        println!(
+            "  {:<28} {:>7} {:>12.2} {:>12.2} {:>12.2} {:>12.2} {:>12.2} {:>12.2} {:>12.2}",
+            stage_id,
+            hours,
+            stage_cogs.material_costs,
+            stage_cogs.labor_costs,
+            stage_cogs.energy_costs,
+            stage_cogs.asset_depreciation_costs + stage_cogs.maintenance_costs,
+            stage_cogs.end_of_life_costs,
+            stage_cogs.qc_costs,
+            stage_cogs.total_cogs
+        );
+        staged_total += stage_cogs.total_cogs;
+    }
+    let unstaged = cogs.total_cogs - staged_total;
+    if unstaged.abs() >= 0.005 {
+        println!("  {:<28} {:>7} {:>90.2}", "(unstaged)", "", unstaged);
+    }
+}
+
+/// The labor tasks of `bom` with the hourly rate of their role, sorted by stage, role, and task.
+fn labor_tasks_with_rates<'a>(bom: &'a BillOfMaterials, kb: &KnowledgeBase) -> Vec<(&'a analysis::LaborTask, f64)> {
+    let mut tasks: Vec<(&analysis::LaborTask, f64)> = bom
+        .labor_tasks
+        .iter()
+        .map(|task| {
+            let rate = kb.labor_roles.get(&task.role_id).map_or(0.0, |role| role.techno_economic_profile.cost_per_hour_usd);
+            (task, rate)
+        })
+        .collect();
+    tasks.sort_by(|(a, _), (b, _)| (&a.stage_id, &a.role_id, &a.task_id).cmp(&(&b.stage_id, &b.role_id, &b.task_id)));
+    tasks
+}
+
+/// Prints the hours and cost of every labor task, by stage and role.
+fn print_labor(bom: &BillOfMaterials, kb: &KnowledgeBase, currency: &str) {
+    let tasks = labor_tasks_with_rates(bom, kb);
+    if tasks.is_empty() {
+        return;
+    }
+    println!("\nLabor by Stage, Role & Task ({}):", currency);
+    println!("  {:<28} {:<18} {:<28} {:>9} {:>12}", "Stage", "Role", "Task", "Hours", "Cost");
+    for (task, rate) in tasks {
+        println!(
+            "  {:<28} {:<18} {:<28} {:>9.2} {:>12.2}",
+            task.stage_id,
+            task.role_id,
+            task.task_id,
+            task.hours,
+            task.hours * rate
+        );
+    }
+    for demand in labor_feasibility(&bom.by_stage, &kb.labor_roles).iter().filter(|d| d.is_violation()) {
+        println!(
+            "  WARNING: {} needs {:.1} {} at once ({:.2} h over {:.0} staffed h), but only {} are on duty",
+            demand.stage_id,
+            demand.concurrent_demand,
+            demand.role_name,
+            demand.hours,
+            demand.staffed_hours,
+            demand.available_headcount.unwrap_or_default()
+        );
+    }
+}
+
+/// One row of `labor_report.csv`.
+#[derive(Serialize)]
+struct LaborReportRow<'a> {
+    stage_id: &'a str,
+    role_id: &'a str,
+    role_name: &'a str,
+    task_id: &'a str,
+    task_description: &'a str,
+    hours: f64,
+    cost: f64,
+}
+
+/// Writes the hours and cost of every labor task of `bom` to a CSV file at `path`.
+fn write_labor_report(path: &Path, bom: &BillOfMaterials, kb: &KnowledgeBase) -> Result<()> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for (task, rate) in labor_tasks_with_rates(bom, kb) {
+        writer.serialize(LaborReportRow {
+            stage_id: &task.stage_id,
+            role_id: &task.role_id,
+            role_name: kb.labor_roles.get(&task.role_id).map_or("", |role| role.role_name.as_str()),
+            task_id: &task.task_id,
+            task_description: &task.task_description,
+            hours: task.hours,
+            cost: task.hours * rate,
+        })?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Prints the waste streams that left the process and the end-of-life costs they and the assets
+/// incurred.
+fn print_waste(bom: &BillOfMaterials, cogs: &analysis::CogsResult, kb: &KnowledgeBase, currency: &str) {
+    println!("\nWaste & Disposal:");
+    if bom.waste_generated.is_empty() {
+        println!("  - No waste streams recorded.");
+    }
+    let mut waste: Vec<(&String, &f64)> = bom.waste_generated.iter().collect();
+    waste.sort_by(|a, b| a.0.cmp(b.0));
+    for (id, grams) in waste {
+        let name = kb
+            .materials
+            .get(id)
+            .map(|m| m.material_name.as_str())
+            .or_else(|| kb.organisms.get(id).map(|o| o.organism_name.as_str()))
+            .unwrap_or(id.as_str());
+        println!("  - {}: {:.4} kg", name, grams / 1000.0);
+    }
+    println!("  - End-of-Life Costs: {:.2} {} (disposal and amortized asset end of life)", cogs.end_of_life_costs, currency);
+}
+
+/// The organism `plan` assigns to produce `molecule_name` and the grams of it the cultivation
+/// produced, from the organism's final biomass and product yield.
+fn produced_target<'a>(
+    molecule_name: &str,
+    upstream_output: &UpstreamOutput,
+    plan: &'a jit::SelectionPlan,
+) -> Option<(&'a Organism, f64)> {
+    let (producing_organism, yield_mg_g) = plan.producer(molecule_name)?;
+    let biomass = upstream_output.biomass_produced.get(&producing_organism.organism_id).copied().unwrap_or(0.0);
+    Some((producing_organism, biomass * yield_mg_g / 1000.0)) // Convert mg to g
+}
+
+/// Prints whether the run kept to each limit of its request.
+fn print_constraints(checks: &[ConstraintCheck], currency: &str) {
+    if checks.is_empty() {
+        return;
+    }
+    println!("Constraints:");
+    for check in checks {
+        let unit = if check.quantity == ConstrainedQuantity::TotalCost { format!(" {}", currency) } else { String::new() };
+        println!(
+            "  - {:<16} {:>12.2} of {:>12.2}{} | {}",
+            check.quantity,
+            check.actual,
+            check.limit,
+            unit,
+            if check.passed { "PASS" } else { "FAIL" }
+        );
+    }
+}
+
+/// Prints the organism filter the organisms were selected under, unless it allowed any.
+fn print_organism_filter(filter: &OrganismFilter) {
+    if filter.is_empty() {
+        return;
+    }
+    println!("Organism Selection:");
+    if !filter.allow_engineered {
+        println!("  - Engineered organisms not allowed");
+    }
+    if !filter.excluded_organism_ids.is_empty() {
+        println!("  - Excluded: {}", filter.excluded_organism_ids.join(", "));
+    }
+    if !filter.required_organism_ids.is_empty() {
+        println!("  - Required: {}", filter.required_organism_ids.join(", "));
+    }
+}
+
+/// Prints the cost of the materials each target's producing organism consumed, and that cost per
+/// gram of the target produced.
+fn print_feed_costs_by_product(
+    cogs: &analysis::CogsResult,
+    request: &jit::ValorizationRequest,
+    upstream_output: &UpstreamOutput,
+    plan: &jit::SelectionPlan,
+    currency: &str,
+) {
+    if cogs.material_costs_by_consumer.is_empty() {
+        return;
+    }
+    println!("\nFeed Cost by Product:");
+    for target in &request.targets {
+        let Some((organism, produced_grams)) =
+            produced_target(&target.molecule_name, upstream_output, plan)
+        else {
+            continue;
+        };
+        let feed_cost = cogs.material_costs_by_consumer.get(&organism.organism_id).copied().unwrap_or(0.0);
+        let per_gram = if produced_grams > 0.0 {
+            format!("{:.4} {}/g", feed_cost / produced_grams, currency)
+        } else {
+            "n/a".to_string()
+        };
+        println!(
+            "  - {:<12} ({}): {:.2} {} | {}",
+            target.molecule_name, organism.organism_name, feed_cost, currency, per_gram
+        );
+    }
+}
+
+/// Splits the run's BOM, COGS, and LCA between the targets, charging each its downstream process
+/// and allocating the shared quantities as the request prescribes.
+#[allow(clippy::too_many_arguments)]
+fn allocate_targets(
+    bom: &analysis::BillOfMaterials,
+    cogs: &analysis::CogsResult,
+    lca: &analysis::LcaResult,
+    processes: &[&Process],
+    request: &jit::ValorizationRequest,
+    upstream_output: &UpstreamOutput,
+    plan: &jit::SelectionPlan,
+) -> Result<analysis::AllocationReport, BioforgeError> {
+    let products: Vec<analysis::ProductOutput> = request
+        .targets
+        .iter()
+        .map(|target| analysis::ProductOutput {
+            molecule_name: target.molecule_name.clone(),
+            produced_grams: produced_target(&target.molecule_name, upstream_output, plan)
+                .map_or(0.0, |(_, grams)| grams),
+            stage_ids: processes
+                .iter()
+                .filter(|p| p.process_id == target.process_id)
+                .flat_map(|p| p.methods.iter().map(|m| m.method_id.clone()))
+                .collect(),
+        })
+        .collect();
+    analysis::allocate_coproducts(bom, cogs, lca, &products, &request.cost_allocation)
+}
+
+/// Prints the cost per gram and the impacts of each target.
+fn print_allocation(allocation: &Result<analysis::AllocationReport, BioforgeError>, currency: &str) {
+    let report = match allocation {
+        Ok(report) => report,
+        Err(e) => {
+            println!("\nCo-Product Allocation: not available ({})", e);
+            return;
         }
-        for (role, hours) in bom.labor_hours {
-            *combined_bom.labor_hours.entry(role).or_insert(0.0) += hours;
+    };
+
+    println!(
+        "\nCo-Product Allocation ({} allocation of {:.2} {} shared costs):",
+        report.basis.label(),
+        report.shared.cogs,
+        currency
+    );
+    for product in &report.products {
+        let per_unit = match product.cost_per_gram {
+            Some(per_gram) => format!("{:.4} {currency}/g | {:.2} {currency}/kg", per_gram, per_gram * 1000.0),
+            None => "n/a (nothing produced)".to_string(),
+        };
+        println!(
+            "  - {:<12} {:>12.2} {} ({:.2} direct + {:.2} shared, {:.1}% share) | {}",
+            product.molecule_name,
+            product.total.cogs,
+            currency,
+            product.direct.cogs,
+            product.allocated.cogs,
+            product.share * 100.0,
+            per_unit
+        );
+        println!("      Energy: {:.2} kWh", product.total.energy_kwh);
+        for (metric, value) in &product.total.impacts {
+            let unit = report.impact_units.get(metric).map_or("", String::as_str);
+            println!("      {}: {:.4} {}", analysis::impact_category_name(metric), value, unit);
         }
     }
-    combined_bom
+}
+
+/// Appraises repeating the run batch after batch at the request's selling prices, with the
+/// break-even prices and scale of the campaign, or `None` if no target has a price.
+#[allow(clippy::too_many_arguments)]
+fn evaluate_financials(
+    bom: &analysis::BillOfMaterials,
+    cogs: &analysis::CogsResult,
+    campaign: &Result<CampaignReport, BioforgeError>,
+    request: &jit::ValorizationRequest,
+    upstream_output: &UpstreamOutput,
+    kb: &KnowledgeBase,
+    plan: &jit::SelectionPlan,
+) -> Option<FinancialResult> {
+    let sales: Vec<ProductSale> = request
+        .targets
+        .iter()
+        .filter_map(|target| {
+            Some(ProductSale {
+                molecule_name: target.molecule_name.clone(),
+                grams_per_batch: produced_target(&target.molecule_name, upstream_output, plan)
+                    .map_or(0.0, |(_, grams)| grams),
+                price_per_gram: target.selling_price_per_gram?,
+            })
+        })
+        .collect();
+    if sales.is_empty() {
+        return None;
+    }
+    let prices = sales.iter().map(|s| (s.molecule_name.clone(), s.price_per_gram)).collect();
+    let mut financials = FinancialModel::new(bom, cogs, sales, &kb.assets, &kb.assumptions).evaluate();
+    financials.break_even = campaign.as_ref().ok().map(|report| breakeven(&report.model, &prices));
+    Some(financials)
+}
+
+/// Models a year of the run's batches as the request's campaign asks.
+#[allow(clippy::too_many_arguments)]
+fn evaluate_campaign(
+    bom: &analysis::BillOfMaterials,
+    cogs: &analysis::CogsResult,
+    lca: &analysis::LcaResult,
+    request: &jit::ValorizationRequest,
+    upstream_output: &UpstreamOutput,
+    kb: &KnowledgeBase,
+    plan: &jit::SelectionPlan,
+) -> Result<CampaignReport, BioforgeError> {
+    let products = request
+        .targets
+        .iter()
+        .map(|target| CampaignProduct {
+            molecule_name: target.molecule_name.clone(),
+            grams_per_batch: produced_target(&target.molecule_name, upstream_output, plan)
+                .map_or(0.0, |(_, grams)| grams),
+        })
+        .collect();
+    let model = CampaignModel::new(bom, cogs, lca, products, &request.campaign, &kb.assets, &kb.assumptions)?;
+    Ok(model.evaluate())
+}
+
+/// Prints the annual output, costs, and impacts of the campaign and how busy it keeps each asset.
+fn print_campaign(campaign: &Result<CampaignReport, BioforgeError>, currency: &str) {
+    let report = match campaign {
+        Ok(report) => report,
+        Err(e) => {
+            println!("\nAnnual Campaign: not available ({})", e);
+            return;
+        }
+    };
+    let model = &report.model;
+    let per_gram = |value: Option<f64>| value.map_or("n/a".to_string(), |v| format!("{:.2} {}/g", v, currency));
+
+    println!(
+        "\nAnnual Campaign ({} of {} possible batches of {:.0} h, {:.0} h turnaround):",
+        model.batches_per_year, model.capacity_batches_per_year, model.batch_hours, model.turnaround_hours
+    );
+    for (molecule_name, grams) in &report.annual_output_g {
+        println!("  - {:<26}{:.2} g/year", format!("{}:", molecule_name), grams);
+    }
+    println!("  - Annual COGS:              {:.2} {}", report.annual_cogs.total_cogs, currency);
+    println!("  - COGS per Gram:            {}", per_gram(report.cogs_per_gram));
+    println!("  - Material Cost per Gram:   {}", per_gram(report.material_cost_per_gram));
+    println!("  - Depreciation per Gram:    {}", per_gram(report.depreciation_per_gram));
+    if let Some((value, unit)) = report.annual_impacts.get("gwp") {
+        println!("  - Annual GWP:               {:.2} {}", value, unit);
+    }
+    for (asset_id, utilization) in &report.asset_utilization {
+        println!("  - Utilization of {}: {:.1}%", asset_id, utilization * 100.0);
+    }
+}
+
+/// Prints the cash flows and returns of the financial model.
+fn print_financials(financials: Option<&FinancialResult>, currency: &str) {
+    let Some(financials) = financials else {
+        println!("\nFinancials: not evaluated (no selling_price_per_gram in the request)");
+        return;
+    };
+    let model = &financials.model;
+    println!("\nFinancials ({} years at {:.1}% discount rate):", model.horizon_years, model.discount_rate * 100.0);
+    println!(
+        "  - Batches per Year:         {} ({:.0} h cycle)",
+        model.batches_per_year, model.batch_cycle_hours
+    );
+    println!("  - Revenue per Batch:        {:.2} {}", model.revenue_per_batch, currency);
+    println!("  - Cash Cost per Batch:      {:.2} {}", model.cash_cost_per_batch, currency);
+    println!("  - Annual Operating Cash:    {:.2} {}", financials.annual_operating_cash_flow, currency);
+    println!("  - CAPEX:                    {:.2} {}", -financials.cash_flows.first().copied().unwrap_or(0.0), currency);
+    println!("  - NPV:                      {:.2} {}", financials.npv, currency);
+    match financials.irr {
+        Some(irr) => println!("  - IRR:                      {:.1}%", irr * 100.0),
+        None => println!("  - IRR:                      n/a"),
+    }
+    match financials.payback_years {
+        Some(years) => println!("  - Payback Period:           {:.1} years", years),
+        None => println!("  - Payback Period:           not within {} years", model.horizon_years),
+    }
+
+    let Some(break_even) = &financials.break_even else {
+        return;
+    };
+    println!(
+        "\nBreak-Even ({:.2} {} fixed per year, {:.2} {} variable per batch):",
+        break_even.fixed_annual_costs, currency, break_even.variable_cost_per_batch, currency
+    );
+    for (molecule_name, price) in &break_even.break_even_prices {
+        match price {
+            Some(price) => println!(
+                "  - {:<26}{:.4} {}/g at {} batches/year, other products at their price",
+                format!("{} Price:", molecule_name),
+                price,
+                currency,
+                break_even.batches_per_year
+            ),
+            None => println!("  - {:<26}n/a (not produced)", format!("{} Price:", molecule_name)),
+        }
+    }
+    match break_even.break_even_batches_per_year {
+        Some(batches) => {
+            let output: Vec<String> = break_even
+                .minimum_annual_output_g
+                .iter()
+                .map(|(molecule_name, grams)| format!("{:.2} g {}", grams, molecule_name))
+                .collect();
+            println!(
+                "  - Minimum Scale:            {:.1} batches/year ({}){}",
+                batches,
+                output.join(", "),
+                if break_even.within_capacity { "" } else { ", beyond capacity" }
+            );
+        }
+        None => println!("  - Minimum Scale:            none (a batch earns less than it costs)"),
+    }
 }
 
 fn generate_qca_table(processes: &[&Process]) -> String {
-    let mut table = String::from("| Process Stage | QC Method ID | Timing |\n");
-    table.push_str("|---------------|--------------|----------|\n");
+    let mut table = String::from("| Process Stage | QC Method ID | Timing | Cost (USD) | Turnaround |\n");
+    table.push_str("|---------------|--------------|----------|------------|------------|\n");
 
     for process in processes {
         for method in &process.methods {
             if method.qc_checks.is_empty() {
-                table.push_str(&format!("| {} | *None* | N/A |\n", method.stage));
+                table.push_str(&format!("| {} | *None* | N/A | N/A | N/A |\n", method.stage));
             }
             for qc in &method.qc_checks {
+                let cost = qc.cost_usd.map_or("N/A".to_string(), |cost| format!("{:.2}", cost));
+                let turnaround = qc
+                    .duration
+                    .as_ref()
+                    .map_or("N/A".to_string(), |duration| format!("{} {}", duration.value, duration.unit));
                 table.push_str(&format!(
-                    "| {} | {} | {} |\n",
-                    method.stage, qc.method_id, qc.timing
+                    "| {} | {} | {} | {} | {} |\n",
+                    method.stage, qc.method_id, qc.timing, cost, turnaround
                 ));
             }
         }
@@ -233,46 +1997,65 @@ fn generate_qca_table(processes: &[&Process]) -> String {
     table
 }
 
+#[allow(clippy::too_many_arguments)]
 fn print_summary_report(
     bom: &analysis::BillOfMaterials,
     cogs: &analysis::CogsResult,
     lca: &analysis::LcaResult,
+    allocation: &Result<analysis::AllocationReport, BioforgeError>,
+    financials: Option<&FinancialResult>,
+    campaign: &Result<CampaignReport, BioforgeError>,
+    carbon_balance: &CarbonBalance,
     processes: &[&Process],
     request: &jit::ValorizationRequest,
     upstream_output: &UpstreamOutput,
     kb: &KnowledgeBase,
-    upstream_organisms: &[Organism],
+    plan: &jit::SelectionPlan,
 ) {
     let process_names: Vec<&str> = processes.iter().map(|p| p.process_name.as_str()).collect();
+    let assumptions = &kb.assumptions;
+    let currency = &assumptions.currency;
     
     println!("\n\n--- [Final Summary Report] ---");
     println!("========================================");
     println!("Request & Production Summary:");
     
+    if plan.batches > 1 {
+        println!(
+            "  - Scale-out: {} batches required in {} ({:.0} L each); totals are for all of them",
+            plan.batches, plan.bioreactor.asset_id, plan.bioreactor.working_volume_l
+        );
+    }
     for target in &request.targets {
-        let mut produced_grams = 0.0;
-        // Find which of the selected organisms produces the target molecule
-        if let Some(producing_organism) = upstream_organisms.iter().find(|org| jit::find_yield(org, &target.molecule_name).is_some()) {
-            if let Some(biomass) = upstream_output.biomass_produced.get(&producing_organism.organism_id) {
-                if let Some(yield_mg_g) = jit::find_yield(producing_organism, &target.molecule_name) {
-                    produced_grams = biomass * yield_mg_g / 1000.0; // Convert mg to g
-                }
-            }
-        }
+        let produced_grams = produced_target(&target.molecule_name, upstream_output, plan)
+            .map_or(0.0, |(_, grams)| grams * f64::from(plan.batches));
+
+        let purity = plan.purities.iter().find(|p| p.molecule_name == target.molecule_name).map_or_else(
+            String::new,
+            |p| match p.minimum_purity_percent {
+                Some(minimum) => format!(" | Purity: {:.1}% (min {:.1}%)", p.purity_percent, minimum),
+                None => format!(" | Purity: {:.1}%", p.purity_percent),
+            },
+        );
 
         println!(
-            "  - Target: {:<12} | Produced: {:>8.2} g / Requested: {:>8.2} g ({:.1}% of target)",
+            "  - Target: {:<12} | Produced: {:>8.2} g / Requested: {:>8.2} g ({:.1}% of target){}",
             target.molecule_name,
             produced_grams,
             target.target_amount_grams,
-            (produced_grams / target.target_amount_grams) * 100.0
+            (produced_grams / target.target_amount_grams) * 100.0,
+            purity
         );
     }
     
     println!("\nProcesses Used: {}", process_names.join(", "));
     println!("Simulation Duration: {} hours", bom.total_ticks);
+    print_constraints(&constraint_checks(request, bom, cogs, lca), currency);
+    print_organism_filter(&request.organism_filter);
     println!("----------------------------------------");
 
+    print_performance(&upstream_output.performance, &plan.organisms);
+
     println!("\nCombined Bill of Materials (BOM):");
     println!("  - Energy Consumed: {:.2} kWh", bom.total_energy_kwh);
     println!("  - Materials Consumed:");
@@ -281,24 +2064,384 @@ fn print_summary_report(
         println!("    - {}: {:.4} kg", material_name, qty / 1000.0); // Convert grams to kg
     }
 
+    println!("\nEconomic Assumptions: {}", assumptions.name);
+    println!(
+        "  - Electricity: {:.3} {}/kWh, {:.3} kg CO₂e/kWh, {:.2} MJ/kWh fossil",
+        assumptions.electricity_price_per_kwh,
+        currency,
+        assumptions.grid_gwp_kg_co2e_per_kwh,
+        assumptions.grid_adp_fossil_mj_per_kwh
+    );
+    println!("  - Operating Hours: {:.0} h/year", assumptions.operating_hours_per_year);
+
     println!("\nCombined Cost of Goods Sold (COGS):");
-    println!("  - Material Costs:           ${:.2} USD", cogs.material_costs);
-    println!("  - Labor Costs:              ${:.2} USD", cogs.labor_costs);
-    println!("  - Energy Costs:             ${:.2} USD", cogs.energy_costs);
-    println!("  - Asset Depreciation:       ${:.2} USD", cogs.asset_depreciation_costs);
-    println!("  - Maintenance Costs:        ${:.2} USD", cogs.maintenance_costs);
+    println!("  - Material Costs:           {:.2} {}", cogs.material_costs, currency);
+    println!("  - Labor Costs:              {:.2} {}", cogs.labor_costs, currency);
+    println!("  - Energy Costs:             {:.2} {}", cogs.energy_costs, currency);
+    println!("  - Asset Depreciation:       {:.2} {}", cogs.asset_depreciation_costs, currency);
+    println!("  - Maintenance Costs:        {:.2} {}", cogs.maintenance_costs, currency);
+    println!("  - End-of-Life Costs:        {:.2} {}", cogs.end_of_life_costs, currency);
+    println!("  - QC Costs:                 {:.2} {}", cogs.qc_costs, currency);
     println!("  --------------------------------------");
-    println!("  - Total COGS:               ${:.2} USD", cogs.total_cogs);
+    println!("  - Total COGS:               {:.2} {}", cogs.total_cogs, currency);
+    if !bom.qc_assays.is_empty() && cogs.total_cogs > 0.0 {
+        println!(
+            "  - QC Share:                 {:.1}% of COGS ({} assays, {:.2} analyst h)",
+            cogs.qc_costs / cogs.total_cogs * 100.0,
+            bom.qc_assays.len(),
+            bom.qc_assays.iter().map(|assay| assay.labor_hours).sum::<f64>()
+        );
+    }
+
+    print_stage_costs(bom, cogs);
+    print_labor(bom, kb, currency);
+    print_waste(bom, cogs, kb, currency);
+    print_feed_costs_by_product(cogs, request, upstream_output, plan, currency);
 
     println!("\nCombined Life Cycle Assessment (LCA):");
-    println!(
-        "  - Global Warming Potential: {:.2} kg CO₂e",
-        lca.gwp_kg_co2e
-    );
-    println!(
-        "  - Abiotic Depletion (fossil): {:.2} MJ",
-        lca.adp_fossil_mj
-    );
+    for (metric, value, unit) in lca.sorted_impacts() {
+        println!("  - {}: {:.2} {}", analysis::impact_category_name(metric), value, unit);
+    }
+    if !lca.unmatched_materials.is_empty() {
+        println!("  - Not assessed (no material data): {}", lca.unmatched_materials.join(", "));
+    }
+    print_carbon_balance(carbon_balance, request.mass_balance_tolerance);
+
+    print_allocation(allocation, currency);
+    print_financials(financials, currency);
+    print_campaign(campaign, currency);
 
     println!("========================================");
-}
\ No newline at end of file
+}
+
+/// Compares the run in `run_dir_b` against the one in `run_dir_a`, prints the differences, and
+/// writes them to `comparison.md` in `output_dir`.
+pub fn compare_runs(run_dir_a: &str, run_dir_b: &str, output_dir: &str) -> Result<()> {
+    let diff = comparison::compare_runs(run_dir_a, run_dir_b)?;
+    let markdown = comparison_markdown(&diff);
+    println!("\n{}", markdown);
+    fs::write(Path::new(output_dir).join("comparison.md"), markdown)?;
+    Ok(())
+}
+
+/// Renders `diff` as Markdown, one table per section.
+fn comparison_markdown(diff: &RunDiff) -> String {
+    let value = |v: Option<f64>| v.map_or("—".to_string(), |v| format!("{:.4}", v));
+    let row = |item: &str, delta: &Delta| {
+        format!(
+            "| {} | {} | {} | {:+.4} | {} |\n",
+            item,
+            value(delta.a),
+            value(delta.b),
+            delta.change,
+            delta.relative_change.map_or("—".to_string(), |r| format!("{:+.1}%", r * 100.0))
+        )
+    };
+    let table = |title: &str, rows: Vec<String>| {
+        if rows.is_empty() {
+            return String::new();
+        }
+        let mut table = format!("## {}\n\n| Item | A | B | Δ (B − A) | Δ % |\n|------|---|---|-----------|-----|\n", title);
+        table.extend(rows);
+        table.push('\n');
+        table
+    };
+    let rows = |deltas: &BTreeMap<String, Delta>| deltas.iter().map(|(item, delta)| row(item, delta)).collect();
+
+    let mut markdown = format!(
+        "# Run Comparison\n\n- A: `{}`\n- B: `{}`\n\nDeltas are B − A, so a negative delta of a cost, impact, or consumption means B needs less of it. \
+         A value missing from one run (—) counts as zero there.\n\n",
+        diff.run_a, diff.run_b
+    );
+    if !diff.changed_knowledge_base_files.is_empty() {
+        let files: Vec<String> = diff.changed_knowledge_base_files.iter().map(|file| format!("`{}`", file)).collect();
+        markdown.push_str(&format!(
+            "**Warning:** the runs used different knowledge bases, so their differences may come from these files rather than the requests: {}.\n\n",
+            files.join(", ")
+        ));
+    }
+    markdown.push_str(&table("Costs", rows(&diff.cost_lines)));
+    markdown.push_str(&table("Costs by Stage", rows(&diff.stage_costs)));
+    markdown.push_str(&table("Labor Costs by Role", rows(&diff.labor_costs_by_role)));
+    markdown.push_str(&table(
+        "Impacts",
+        diff.impacts
+            .iter()
+            .map(|(metric, impact)| {
+                row(&format!("{} ({})", analysis::impact_category_name(metric), impact.unit), &impact.delta)
+            })
+            .collect(),
+    ));
+    markdown.push_str(&table("KPIs", rows(&diff.kpis)));
+    markdown.push_str(&table(
+        "Materials Consumed (g)",
+        diff.materials
+            .iter()
+            .map(|(id, material)| match &material.material_name {
+                Some(name) => row(&format!("{} ({})", name, id), &material.delta),
+                None => row(id, &material.delta),
+            })
+            .collect(),
+    ));
+    markdown.push_str(&table("Stage Durations (ticks)", rows(&diff.stage_ticks)));
+    markdown.push_str(&table("Resources", rows(&diff.resources)));
+
+    let unmatched: Vec<String> = diff
+        .unmatched_stages()
+        .map(|(stage_id, delta)| format!("- `{}` ran only in {}\n", stage_id, if delta.a.is_some() { "A" } else { "B" }))
+        .collect();
+    if !unmatched.is_empty() {
+        markdown.push_str("## Stages in One Run Only\n\n");
+        markdown.extend(unmatched);
+    }
+    markdown
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::tests::{scratch_dir, KNOWLEDGE_BASE};
+
+    /// The knowledge base of the repository with three bioreactors in place of its own, copies of
+    /// its stirred tank with the ports of a seed train: a 1 L and a 10 L seed vessel and a 100 L
+    /// production vessel.
+    fn three_vessel_knowledge_base() -> KnowledgeBase {
+        let mut kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let tank = kb.assets["CULTIVATION-STR-01"].clone();
+        kb.assets.retain(|_, asset| !asset.asset_type.contains("BIOREACTOR"));
+        for (asset_id, volume_l) in [("SEED-1L", 1.0), ("SEED-10L", 10.0), ("PRODUCTION-100L", 100.0)] {
+            let mut vessel = tank.clone();
+            vessel.asset_id = asset_id.to_string();
+            let parameters = vessel.operational_parameters.as_mut().unwrap().configuration_and_control.as_mut().unwrap();
+            parameters.iter_mut().find(|p| p.key == "working_volume").unwrap().value = volume_l;
+            kb.assets.insert(asset_id.to_string(), vessel);
+        }
+        kb
+    }
+
+    /// A cultivation of E. coli to 400 g of biomass in the production vessel, through the seed
+    /// vessels `seeds`.
+    struct Cultivation {
+        biomass_g: f64,
+        /// The cost of the run: its feeds, labor, and asset time.
+        cogs: analysis::CogsResult,
+        /// Liters of broth collected in the harvest vessel.
+        harvested_l: f64,
+    }
+
+    /// Runs the cultivation with the request's `cultivation_strategy` given as YAML.
+    fn cultivate(kb: &KnowledgeBase, seeds: &[jit::Bioreactor], strategy: &str, name: &str) -> Cultivation {
+        let organism = kb.organisms["ORG-ECOLI"].clone();
+        let grams = 400.0 * organism.yield_for("Lutein").unwrap() / 1000.0;
+        let request: jit::ValorizationRequest = serde_yaml::from_str(&format!(
+            "targets:\n- {{ molecule_name: Lutein, objective: MaximizeYield, process_id: PROC-LUT-FED-01, target_amount_grams: {grams} }}\ncultivation_strategy: {strategy}"
+        ))
+        .unwrap();
+        let plan = jit::SelectionPlan {
+            required_biomass_g: BTreeMap::from([(organism.organism_id.clone(), 400.0)]),
+            producers: BTreeMap::from([("Lutein".to_string(), organism.organism_id.clone())]),
+            organisms: vec![organism],
+            bioreactor: jit::select_bioreactor(80.0, kb).unwrap(),
+            batches: 1,
+            feedstock_balance: None,
+            purities: Vec::new(),
+        };
+        let output_dir = scratch_dir(name);
+        let initial_media = jit::generate_initial_media(&plan, &request, kb, output_dir.to_str().unwrap()).unwrap();
+        let (process, rules) = upstream_cultivation(&request, kb, &plan, &initial_media, seeds).unwrap();
+        assert_eq!(process.default_workflow.len(), seeds.len() + 1);
+
+        let mut builder = SimulationBuilder::new()
+            .with_organisms(plan.organisms.clone())
+            .with_assets(kb.assets.values().cloned().collect())
+            .with_rules(rules)
+            .with_process(process)
+            .with_timeseries_sink(Box::new(InMemorySink::new()));
+        let mut fills = vessel_fills(seeds, &plan, &initial_media).into_iter();
+        builder = builder.with_initial_media(fills.next().unwrap().1);
+        for (asset_id, media) in fills {
+            builder = builder.with_vessel(&asset_id, media, Vec::new());
+        }
+        let mut engine = builder.build().unwrap();
+        let summary = engine.run().unwrap();
+        let records = engine.logged_records().unwrap();
+        let bom = analysis::generate_bom_from_records(records, engine.get_process(), &kb.assets, &kb.materials).unwrap();
+        fs::remove_dir_all(output_dir).unwrap();
+        Cultivation {
+            biomass_g: summary.final_biomass.values().sum(),
+            cogs: analysis::calculate_cogs(&bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions).unwrap(),
+            harvested_l: engine.get_vessels().get(jit::HARVEST_VESSEL_ID).map_or(0.0, |v| v.media.volume.value),
+        }
+    }
+
+    #[test]
+    fn a_seed_train_grows_the_same_culture_as_direct_inoculation() {
+        let kb = three_vessel_knowledge_base();
+        let production = jit::select_bioreactor(80.0, &kb).unwrap();
+        assert_eq!(production.asset_id, "PRODUCTION-100L");
+        let seeds = jit::seed_train_vessels(&production, &jit::SeedTrain { scale_up_ratio: 10.0 }, &kb);
+        let seed_ids: Vec<&str> = seeds.iter().map(|s| s.asset_id.as_str()).collect();
+        assert_eq!(seed_ids, ["SEED-1L", "SEED-10L"]);
+
+        // The train runs the same media and inoculum up through three stages, so it ends with about
+        // the culture the production vessel grows on its own. Each seed runs its share of the media
+        // dry before it is transferred, so the culture takes its substrates up along another path,
+        // and the two agree to within 15%.
+        let direct = cultivate(&kb, &[], "{ mode: batch }", "seed_train_direct").biomass_g;
+        let trained = cultivate(&kb, &seeds, "{ mode: batch }", "seed_train_trained").biomass_g;
+        assert!(direct > 100.0, "{}", direct);
+        assert!((trained - direct).abs() / direct < 0.15, "{} vs {}", trained, direct);
+    }
+    #[test]
+    fn the_cultivation_strategies_cost_differently() {
+        let kb = three_vessel_knowledge_base();
+        let batch = cultivate(&kb, &[], "{ mode: batch }", "strategy_batch");
+        let fed_batch = cultivate(&kb, &[], "{ mode: fed_batch }", "strategy_fed_batch");
+        let continuous = cultivate(&kb, &[], "{ mode: continuous, dilution_rate_per_hr: 0.1 }", "strategy_continuous");
+
+        // The fed-batch buys the substrate it is fed, the continuous culture the fresh media it
+        // exchanges for broth, and each occupies the bioreactor for a different time.
+        let [batch, fed_batch, continuous] = [&batch, &fed_batch, &continuous].map(|c| (c.cogs.material_costs, c.cogs.total_cogs, c.harvested_l));
+        assert!(batch.0 != fed_batch.0 && fed_batch.0 != continuous.0 && batch.0 != continuous.0, "{:?}", [batch, fed_batch, continuous]);
+        assert!(batch.1 != fed_batch.1 && fed_batch.1 != continuous.1 && batch.1 != continuous.1, "{:?}", [batch, fed_batch, continuous]);
+        assert_eq!((batch.2, fed_batch.2), (0.0, 0.0));
+        assert!(continuous.2 > 0.0);
+    }
+    /// Six ticks of growth in the repository's stirred tank, then four of crystallization, each
+    /// stage ended by a `time_in_stage` rule.
+    fn grow_then_crystallize() -> (Process, Vec<Rule>) {
+        let method = |method_id: &str, asset_id: &str, rule_id: &str| {
+            format!("{{ method_id: {method_id}, stage: {method_id}, technique: batch, required_asset_id: {asset_id}, operating_parameters: {{}}, required_materials: [], qc_checks: [], required_rule_ids: [{rule_id}] }}")
+        };
+        let process: Process = serde_yaml::from_str(&format!(
+            "{{ process_id: PROC-TEST, process_name: PROC-TEST, component_class: Test, status: Active, notes: '', default_workflow: [MTHD-GROW, MTHD-CRYST], methods: [{}, {}] }}",
+            method("MTHD-GROW", "CULTIVATION-STR-01", "grow.duration"),
+            method("MTHD-CRYST", "CRYSTALLIZER-01", "cryst.duration")
+        ))
+        .unwrap();
+        let rule = |name: &str, ticks: u64| -> Rule {
+            serde_yaml::from_str(&format!("{{ name: {name}, condition: {{ type: time_in_stage, ticks: {ticks} }}, action: {{ type: advance_to_next_step }} }}")).unwrap()
+        };
+        (process, vec![rule("grow.duration", 6), rule("cryst.duration", 4)])
+    }
+
+    /// Simulates the process `builder` is given for E. coli on 10 L of 10 g/L glucose, returning
+    /// the BOM of the run.
+    fn simulate(kb: &KnowledgeBase, builder: SimulationBuilder) -> BillOfMaterials {
+        let media: MediaState = serde_yaml::from_str(
+            "{ volume: { value: 10.0, unit: L }, ph: 7.0, composition: { dissolved_components: [{ molecule_id: 'CHEBI:17234', molecule_name: D-glucose, concentration: { value: 10.0, unit: g/L } }], dissolved_gases: [] } }",
+        )
+        .unwrap();
+        let mut engine = builder
+            .with_organisms(vec![kb.organisms["ORG-ECOLI"].clone()])
+            .with_assets(kb.assets.values().cloned().collect())
+            .with_initial_media(media)
+            .with_timeseries_sink(Box::new(InMemorySink::new()))
+            .build()
+            .unwrap();
+        engine.run().unwrap();
+        analysis::generate_bom_from_records(engine.logged_records().unwrap(), engine.get_process(), &kb.assets, &kb.materials).unwrap()
+    }
+
+    #[test]
+    fn a_dry_run_estimate_matches_the_time_gated_run_it_stands_for() {
+        let kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let (process, rules) = grow_then_crystallize();
+        let blueprint = analysis::generate_blueprint(&process, &rules.iter().map(|r| (r.name.clone(), r.clone())).collect()).unwrap();
+        let (estimated_bom, estimated) = analysis::estimate_resources(&blueprint, &kb.assets, &kb.labor_roles, &kb.assumptions).unwrap();
+
+        let simulated_bom = simulate(&kb, SimulationBuilder::new().with_process(process).with_rules(rules));
+        let simulated = analysis::calculate_cogs(&simulated_bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions).unwrap();
+
+        // Ten ticks and the initial state.
+        assert_eq!(estimated_bom.total_ticks, 11);
+        assert_eq!(estimated_bom.total_ticks, simulated_bom.total_ticks);
+        assert!((estimated_bom.total_energy_kwh - simulated_bom.total_energy_kwh).abs() < 1e-9);
+        assert_eq!(estimated_bom.labor_hours, simulated_bom.labor_hours);
+        // The estimate leaves out only what the biology consumes.
+        assert!(simulated.material_costs > 0.0);
+        assert_eq!(estimated.material_costs, 0.0);
+        for (estimated, simulated) in [
+            (estimated.labor_costs, simulated.labor_costs),
+            (estimated.energy_costs, simulated.energy_costs),
+            (estimated.asset_depreciation_costs, simulated.asset_depreciation_costs),
+            (estimated.maintenance_costs, simulated.maintenance_costs),
+        ] {
+            assert!(estimated > 0.0 && (estimated - simulated).abs() < 1e-9, "estimated {} vs simulated {}", estimated, simulated);
+        }
+    }
+
+    #[test]
+    fn the_versioned_reports_read_back_as_written() {
+        let kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let (process, rules) = grow_then_crystallize();
+        let bom = simulate(&kb, SimulationBuilder::new().with_process(process).with_rules(rules));
+        let cogs = analysis::calculate_cogs(&bom, &kb.materials, &kb.labor_roles, &kb.assets, &kb.assumptions).unwrap();
+        let lca = analysis::calculate_lca(&bom, &kb.materials, &kb.assets, &kb.assumptions).unwrap();
+        let request: jit::ValorizationRequest = serde_yaml::from_str(
+            "targets:\n- { molecule_name: Lutein, objective: MaximizeYield, process_id: PROC-TEST, target_amount_grams: 1.0 }\nconstraints: { max_total_cost_usd: 1.0, max_duration_hours: 100.0 }",
+        )
+        .unwrap();
+        let constraints = constraint_checks(&request, &bom, &cogs, &lca);
+        assert_eq!(constraints.len(), 2);
+
+        let output_dir = scratch_dir("report_round_trip");
+        write_analysis_reports(output_dir.to_str().unwrap(), &bom, &cogs, &lca, &kb, constraints.clone(), OrganismFilter::default(), 3).unwrap();
+        fn read<T: serde::de::DeserializeOwned>(path: PathBuf) -> Versioned<T> {
+            serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+        }
+
+        let named_bom = NamedBom::new(bom.clone(), &kb.materials);
+        let written_bom: Versioned<NamedBom> = read(output_dir.join("bom.json"));
+        assert_eq!(written_bom, Versioned::new(named_bom.clone()));
+        assert_eq!(written_bom.schema_version, analysis::report::REPORT_SCHEMA_VERSION);
+        assert_eq!(read::<analysis::CogsResult>(output_dir.join("cogs.json")), Versioned::new(cogs.clone()));
+        assert_eq!(read::<analysis::LcaResult>(output_dir.join("lca.json")), Versioned::new(lca.clone()));
+
+        let report: Versioned<AnalysisReport> = read(output_dir.join("report.json"));
+        assert_eq!(report.schema_version, analysis::report::REPORT_SCHEMA_VERSION);
+        assert_eq!((&report.report.bom, &report.report.cogs, &report.report.lca), (&named_bom, &cogs, &lca));
+        assert_eq!((report.report.constraints, report.report.batches), (constraints, 3));
+
+        // A header and one row for each material of each stage.
+        let rows = fs::read_to_string(output_dir.join("bom.csv")).unwrap().lines().count();
+        let materials: usize = bom.by_stage.values().map(|stage| stage.materials_consumed.len()).sum();
+        assert!(materials > 0);
+        assert_eq!(rows, materials + 1);
+        fs::remove_dir_all(output_dir).unwrap();
+    }
+
+    #[test]
+    fn a_blueprint_with_a_longer_step_simulates_a_longer_run() {
+        let kb = KnowledgeBase::load_with_overlays(KNOWLEDGE_BASE, &[]).unwrap();
+        let (process, rules) = grow_then_crystallize();
+        let blueprint = analysis::generate_blueprint(&process, &rules.iter().map(|r| (r.name.clone(), r.clone())).collect()).unwrap();
+        let bom = simulate(&kb, SimulationBuilder::new().with_process(process).with_rules(rules));
+
+        let output_dir = scratch_dir("blueprint_perturbation");
+        let path = output_dir.join("blueprint.yaml");
+        analysis::write_blueprint_yaml(&blueprint, path.to_str().unwrap()).unwrap();
+        let mut edited = analysis::read_blueprint_yaml(path.to_str().unwrap()).unwrap();
+        fs::remove_dir_all(output_dir).unwrap();
+        // The execution system grows the culture three hours longer.
+        edited.workflow[0].duration_ticks += 3;
+        let rerun = simulate(&kb, SimulationBuilder::new().with_blueprint(&edited));
+
+        assert_eq!(rerun.total_ticks, bom.total_ticks + 3);
+        let grow_kwh = bom.by_stage["MTHD-GROW"].energy_kwh;
+        assert!(grow_kwh > 0.0);
+        assert!((rerun.by_stage["MTHD-GROW"].energy_kwh - grow_kwh * 9.0 / 6.0).abs() < 1e-9);
+        assert!((rerun.total_energy_kwh - bom.total_energy_kwh - grow_kwh / 2.0).abs() < 1e-9);
+        let crystallization = |bom: &BillOfMaterials| (bom.by_stage["MTHD-CRYST"].ticks, bom.by_stage["MTHD-CRYST"].energy_kwh);
+        assert_eq!(crystallization(&rerun), crystallization(&bom));
+
+        edited.workflow[1].asset_id = "CRYSTALLIZER-99".to_string();
+        let error = SimulationBuilder::new()
+            .with_organisms(vec![kb.organisms["ORG-ECOLI"].clone()])
+            .with_assets(kb.assets.values().cloned().collect())
+            .with_blueprint(&edited)
+            .build()
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains("runs on asset 'CRYSTALLIZER-99', which is not defined"), "{}", error);
+    }
+}
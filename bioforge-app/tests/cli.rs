@@ -0,0 +1,234 @@
+//! The binary run from a working directory of its own, as it is outside the repository.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const REPOSITORY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/..");
+
+/// A fresh, empty working directory under the system temp dir for the test `name`.
+fn working_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("bioforge_cli_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn a_dry_run_plans_the_request_of_the_working_directory() {
+    let dir = working_dir("dry_run");
+    fs::copy(format!("{}/bioforge-app/request.yaml", REPOSITORY), dir.join("request.yaml")).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bioforge-app"))
+        .current_dir(&dir)
+        .args(["run", "--dry-run", "--out", "runs", "--kb"])
+        .arg(format!("{}/data/knowledge_base", REPOSITORY))
+        .env_remove("BIOFORGE_REQUEST")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}\n{}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("the working directory's request.yaml"), "{}", stdout);
+
+    // The plan directory is named after the request's targets.
+    let plans: Vec<PathBuf> = fs::read_dir(dir.join("runs")).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(plans.len(), 1);
+    let name = plans[0].file_name().unwrap().to_string_lossy().into_owned();
+    assert!(name.starts_with("Lutein_beta-glucans_plan_"), "{}", name);
+    let plan: serde_json::Value = serde_json::from_str(&fs::read_to_string(plans[0].join("plan.json")).unwrap()).unwrap();
+    assert!(plan.is_object());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_missing_request_fails_with_a_hint() {
+    let dir = working_dir("no_request");
+    let output = Command::new(env!("CARGO_BIN_EXE_bioforge-app"))
+        .current_dir(&dir)
+        .args(["--dry-run", "--out", "runs"])
+        .env_remove("BIOFORGE_REQUEST")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--request"));
+    assert!(!dir.join("runs").exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Runs the workflow, without plots, in `dir` with `args` and `request_env` (the
+/// `BIOFORGE_REQUEST` to set, if any) into the directory `out` of `dir`, and returns its run
+/// directory.
+fn run_workflow(dir: &Path, out: &str, args: &[&str], request_env: Option<&str>) -> PathBuf {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_bioforge-app"));
+    command
+        .current_dir(dir)
+        .args(["run", "--no-plots", "--no-kb-snapshot", "--out", out, "--kb"])
+        .arg(format!("{}/data/knowledge_base", REPOSITORY))
+        .args(args)
+        .env_remove("BIOFORGE_REQUEST");
+    if let Some(path) = request_env {
+        command.env("BIOFORGE_REQUEST", path);
+    }
+    let output = command.output().unwrap();
+    assert!(output.status.success(), "{}\n{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr));
+    let runs: Vec<PathBuf> = fs::read_dir(dir.join(out)).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(runs.len(), 1);
+    runs[0].clone()
+}
+
+#[test]
+fn the_request_flag_wins_over_the_environment_which_wins_over_the_working_directory() {
+    let dir = working_dir("request_precedence");
+    let request = fs::read_to_string(format!("{}/bioforge-app/request.yaml", REPOSITORY)).unwrap();
+    // Each source that loses holds no request, so the run fails if it is read.
+    let not_a_request = "targets: [";
+    let source_of = |run_dir: &Path| -> String {
+        assert_eq!(fs::read_to_string(run_dir.join("request.yaml")).unwrap(), request);
+        let manifest: serde_json::Value = serde_json::from_str(&fs::read_to_string(run_dir.join("manifest.json")).unwrap()).unwrap();
+        manifest["request_source"].as_str().unwrap().to_string()
+    };
+
+    fs::write(dir.join("request.yaml"), &request).unwrap();
+    assert_eq!(source_of(&run_workflow(&dir, "cwd", &[], None)), "the working directory's request.yaml");
+
+    fs::write(dir.join("request.yaml"), not_a_request).unwrap();
+    fs::write(dir.join("env.yaml"), &request).unwrap();
+    assert_eq!(source_of(&run_workflow(&dir, "env", &[], Some("env.yaml"))), "BIOFORGE_REQUEST=env.yaml");
+
+    fs::write(dir.join("env.yaml"), not_a_request).unwrap();
+    fs::write(dir.join("flag.yaml"), &request).unwrap();
+    assert_eq!(source_of(&run_workflow(&dir, "flag", &["--request", "flag.yaml"], Some("env.yaml"))), "--request flag.yaml");
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// The reports a run writes once all of its simulations are done: JSON, then CSV.
+const FINAL_REPORTS: [&str; 13] = [
+    "report.json",
+    "bom.json",
+    "cogs.json",
+    "lca.json",
+    "kpis.json",
+    "mass_balance.json",
+    "carbon_balance.json",
+    "campaign_report.json",
+    "financials.json",
+    "allocation_report.json",
+    "purity_report.json",
+    "bom.csv",
+    "labor_report.csv",
+];
+
+/// Asserts that the JSON reports `a` and `b` hold the same values at `path`. Costs summed over
+/// maps in a different order can differ in their last bits, so numbers need only agree to 1e-9.
+fn assert_same_report(a: &serde_json::Value, b: &serde_json::Value, path: &str) {
+    use serde_json::Value;
+    match (a, b) {
+        (Value::Object(a), Value::Object(b)) => {
+            assert_eq!(a.keys().collect::<Vec<_>>(), b.keys().collect::<Vec<_>>(), "{}", path);
+            for (key, value) in a {
+                assert_same_report(value, &b[key], &format!("{}/{}", path, key));
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            assert_eq!(a.len(), b.len(), "{}", path);
+            for (i, (a, b)) in a.iter().zip(b).enumerate() {
+                assert_same_report(a, b, &format!("{}/{}", path, i));
+            }
+        }
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (a.as_f64().unwrap(), b.as_f64().unwrap());
+            assert!((a - b).abs() <= 1e-9 * a.abs().max(1.0), "{}: {} != {}", path, a, b);
+        }
+        _ => assert_eq!(a, b, "{}", path),
+    }
+}
+
+#[test]
+fn a_run_interrupted_between_downstream_runs_resumes_to_the_same_reports() {
+    let dir = working_dir("resume");
+    fs::copy(format!("{}/bioforge-app/request.yaml", REPOSITORY), dir.join("request.yaml")).unwrap();
+    let run_dir = run_workflow(&dir, "runs", &[], None);
+    let uninterrupted: Vec<String> = FINAL_REPORTS.iter().map(|name| fs::read_to_string(run_dir.join(name)).unwrap()).collect();
+
+    // Interrupt it after the cultivation and the first downstream run: the second downstream run
+    // and the reports never happened.
+    let progress_path = run_dir.join("workflow_progress.json");
+    let mut progress: serde_json::Value = serde_json::from_str(&fs::read_to_string(&progress_path).unwrap()).unwrap();
+    let downstream = progress["downstream"].as_object_mut().unwrap();
+    assert_eq!(downstream.len(), 2);
+    let interrupted = downstream.keys().next_back().unwrap().clone();
+    downstream.remove(&interrupted);
+    fs::write(&progress_path, progress.to_string()).unwrap();
+    fs::remove_file(run_dir.join(format!("downstream_{}.csv", interrupted))).unwrap();
+    for name in FINAL_REPORTS {
+        fs::remove_file(run_dir.join(name)).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_bioforge-app"))
+        .current_dir(&dir)
+        .args(["run", "--no-plots", "--no-kb-snapshot", "--kb"])
+        .arg(format!("{}/data/knowledge_base", REPOSITORY))
+        .arg("--resume")
+        .arg(&run_dir)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "{}\n{}", stdout, String::from_utf8_lossy(&output.stderr));
+    assert!(stdout.contains("Resuming: the cultivation already completed"), "{}", stdout);
+    assert_eq!(stdout.matches("already completed; reading it from").count(), 2, "{}", stdout);
+    assert!(!stdout.contains(&format!("'{}' already completed", interrupted)), "{}", stdout);
+
+    for (name, before) in FINAL_REPORTS.iter().zip(&uninterrupted) {
+        let after = fs::read_to_string(run_dir.join(name)).unwrap();
+        if name.ends_with(".json") {
+            assert_same_report(&serde_json::from_str(before).unwrap(), &serde_json::from_str(&after).unwrap(), name);
+        } else {
+            assert_eq!(&after, before, "{} differs after resuming", name);
+        }
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_dry_run_selects_what_a_full_run_of_the_request_runs() {
+    let dir = working_dir("plan_matches_run");
+    fs::copy(format!("{}/bioforge-app/request.yaml", REPOSITORY), dir.join("request.yaml")).unwrap();
+    let plan_dir = run_workflow(&dir, "plans", &["--dry-run"], None);
+    let run_dir = run_workflow(&dir, "runs", &[], None);
+    let read_json = |path: PathBuf| -> serde_json::Value { serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap() };
+    let plan = read_json(plan_dir.join("plan.json"));
+    let summary = read_json(run_dir.join("run_summary.json"));
+    let kpis = read_json(run_dir.join("kpis.json"));
+
+    // The organisms the cultivation grew, and the product each target came from.
+    let planned: Vec<&str> = plan["organisms"].as_array().unwrap().iter().map(|o| o["organism_id"].as_str().unwrap()).collect();
+    let grown: Vec<&str> = summary["upstream_consortium"]["final_biomass"].as_object().unwrap().keys().map(String::as_str).collect();
+    assert_eq!(planned, grown);
+    for product in kpis["products"].as_array().unwrap() {
+        let molecule = product["molecule_name"].as_str().unwrap();
+        assert_eq!(plan["producers"][molecule], product["organism_id"], "{}", molecule);
+        let organism = plan["organisms"].as_array().unwrap().iter().find(|o| o["organism_id"] == product["organism_id"]).unwrap();
+        let planned_mg_g = organism["yields"][molecule].as_f64().unwrap();
+        assert!((planned_mg_g - 1000.0 * product["yield_product_per_biomass"].as_f64().unwrap()).abs() < 1e-9, "{}", molecule);
+    }
+
+    // The downstream processes that ran.
+    let mut planned: Vec<String> =
+        plan["processes"].as_array().unwrap().iter().map(|p| format!("downstream_{}", p["process_id"].as_str().unwrap())).collect();
+    planned.sort();
+    let ran: Vec<&String> = summary.as_object().unwrap().keys().filter(|run| run.starts_with("downstream_")).collect();
+    assert_eq!(planned.iter().collect::<Vec<_>>(), ran);
+
+    // The media recipe the cultivation was filled with.
+    let recipe: serde_yaml::Value = serde_yaml::from_str(&fs::read_to_string(run_dir.join("media_recipe.yaml")).unwrap()).unwrap();
+    let ingredients: Vec<(&str, f64)> = recipe["ingredients"]
+        .as_sequence()
+        .unwrap()
+        .iter()
+        .map(|i| (i["material_id"].as_str().unwrap(), i["amount"]["value"].as_f64().unwrap()))
+        .collect();
+    let media: Vec<(&str, f64)> =
+        plan["media"].as_array().unwrap().iter().map(|m| (m["material_id"].as_str().unwrap(), m["amount"].as_f64().unwrap())).collect();
+    assert_eq!(media, ingredients);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,124 @@
+//! Synthetic organisms, media, and processes shared by the benchmarks.
+
+use bioforge_schemas::{
+    environment::{DissolvedComponent, MediaComposition, MediaState, Measurement},
+    organism::Organism,
+    process::Process,
+    rule::Rule,
+};
+use serde_json::json;
+
+pub fn molecule_id(i: usize) -> String {
+    format!("BENCH:{:03}", i)
+}
+
+/// Builds a synthetic organism that consumes a handful of `components` media components and
+/// secretes one.
+pub fn organism(i: usize, components: usize) -> Organism {
+    let consumption: Vec<_> = (0..8)
+        .map(|k| {
+            let id = (i * 7 + k * 5) % components;
+            json!({
+                "molecule_id": molecule_id(id),
+                "molecule_name": format!("component-{}", id),
+                "max_exchange_rate": { "value": 0.05, "unit": "mmol/g_dw/hr" },
+                "conditions": { "aeration": "Aerobic", "light": null, "notes": null }
+            })
+        })
+        .collect();
+
+    serde_json::from_value(json!({
+        "organism_id": format!("ORG-BENCH-{:02}", i),
+        "organism_name": format!("Bench organism {}", i),
+        "organism_type": "Bacteria",
+        "strain_details": null,
+        "initial_biomass": { "value": 0.1, "unit": "g" },
+        "static_properties": {
+            "elemental_composition": {
+                "carbon": 0.5, "hydrogen": 0.07, "oxygen": 0.24,
+                "nitrogen": 0.13, "phosphorus": 0.03, "sulfur": 0.01
+            },
+            "macromolecular_summary": {
+                "protein": 55.0, "carbohydrate": 20.0, "lipid": 10.0, "nucleic_acid": 10.0, "ash": 5.0
+            },
+            "morphology": { "nominal_diameter": { "value": 1.0, "unit": "um" } },
+            "targeted_molecular_classes": { "terpenoids_and_carotenoids": [], "cell_wall_components": [] }
+        },
+        "dynamic_parameters": {
+            "growth_rate_per_hr": 0.05,
+            "environmental_tolerances": {
+                "photosynthetic_light_response": null,
+                "temperature": { "optimal": { "value": 25.0, "unit": "celsius" }, "range": { "min": 10.0, "max": 40.0 } },
+                "ph": { "optimal": 7.0, "range": { "min": 5.0, "max": 9.0 } },
+                "chemical": []
+            },
+            "metabolic_exchange": {
+                "media_consumption": consumption,
+                "media_secretion": [{
+                    "molecule_id": molecule_id(components + i % 8),
+                    "molecule_name": format!("byproduct-{}", i),
+                    "max_exchange_rate": { "value": 0.01, "unit": "mmol/g_dw/hr" },
+                    "conditions": { "aeration": "Aerobic", "light": null, "notes": null }
+                }],
+                "gas_consumption": [],
+                "gas_secretion": []
+            }
+        }
+    }))
+    .expect("bench organism should deserialize")
+}
+
+/// A 500 L medium holding `components` dissolved components at 20 g/L.
+pub fn media(components: usize) -> MediaState {
+    MediaState {
+        volume: Measurement { value: 500.0, unit: "L".to_string() },
+        ph: 7.0,
+        composition: MediaComposition {
+            dissolved_components: (0..components)
+                .map(|i| DissolvedComponent {
+                    molecule_id: molecule_id(i),
+                    molecule_name: format!("component-{}", i),
+                    concentration: Measurement { value: 20.0, unit: "g/L".to_string() },
+                })
+                .collect(),
+            dissolved_gases: vec![],
+        },
+    }
+}
+
+/// A single-stage cultivation; `rule_id` names the rule that ends it, if any.
+pub fn process(rule_id: Option<&str>) -> Process {
+    serde_json::from_value(json!({
+        "process_id": "PROC-BENCH",
+        "process_name": "Benchmark cultivation",
+        "component_class": "Cultivation",
+        "status": "Active",
+        "notes": "",
+        "default_workflow": ["MTHD-BENCH-CULT"],
+        "methods": [{
+            "method_id": "MTHD-BENCH-CULT",
+            "stage": "Cultivation",
+            "technique": "batch",
+            "required_asset_id": "BENCH-REACTOR",
+            "operating_parameters": {},
+            "required_materials": [],
+            "qc_checks": [],
+            "required_rule_ids": rule_id.map(|id| vec![id])
+        }]
+    }))
+    .expect("bench process should deserialize")
+}
+
+#[allow(dead_code)]
+pub const STOP_RULE_ID: &str = "rule_stop_bench";
+
+/// A rule that ends the stage after `ticks` ticks, for use with `process(Some(STOP_RULE_ID))`.
+#[allow(dead_code)] // Not every benchmark runs to a fixed length.
+pub fn stop_rule(ticks: u64) -> Rule {
+    serde_json::from_value(json!({
+        "name": STOP_RULE_ID,
+        "condition": { "type": "time_in_stage", "ticks": ticks },
+        "action": { "type": "advance_to_next_step" }
+    }))
+    .expect("bench rule should deserialize")
+}
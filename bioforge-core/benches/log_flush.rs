@@ -0,0 +1,52 @@
+//! Compares flushing the CSV log after every row with flushing every few hundred rows.
+//!
+//! Run with `cargo bench -p bioforge-core --bench log_flush`. The gap widens considerably on
+//! network filesystems; point `TMPDIR` at one to measure it there.
+
+mod common;
+
+use bioforge_core::{
+    logger::{FlushPolicy, LogFormat},
+    simulation::{builder::SimulationBuilder, engine::SimulationEngine},
+};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use std::path::Path;
+
+const COMPONENTS: usize = 10;
+const ORGANISMS: usize = 3;
+const TICKS: u64 = 20_000;
+
+fn build_engine(path: &Path, policy: FlushPolicy) -> SimulationEngine {
+    SimulationBuilder::new()
+        .with_organisms((0..ORGANISMS).map(|i| common::organism(i, COMPONENTS)).collect())
+        .with_process(common::process(Some(common::STOP_RULE_ID)))
+        .with_rules(vec![common::stop_rule(TICKS)])
+        .with_initial_media(common::media(COMPONENTS))
+        .with_timeseries_logging(path.to_str().unwrap(), LogFormat::Csv)
+        .with_log_flush_policy(policy)
+        .build()
+        .expect("bench engine should build")
+}
+
+fn bench_log_flush(c: &mut Criterion) {
+    let path = std::env::temp_dir().join("bioforge_bench_flush.csv");
+    let mut group = c.benchmark_group("csv_log_20k_ticks");
+    group.sample_size(10);
+    for (name, policy) in [
+        ("flush_every_row", FlushPolicy::every_row()),
+        ("flush_every_256_rows", FlushPolicy::every_rows(256)),
+    ] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || build_engine(&path, policy),
+                |mut engine| engine.run().expect("run should succeed"),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+    let _ = std::fs::remove_file(path);
+}
+
+criterion_group!(benches, bench_log_flush);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+//! Compares the time-series log backends on a long run: write throughput while simulating, and
+//! the cost of reading the log back in `generate_bom`.
+//!
+//! Run with `cargo bench -p bioforge-core --features parquet --bench log_formats`.
+
+mod common;
+
+use bioforge_core::{
+    analysis::generate_bom,
+    logger::LogFormat,
+    simulation::{builder::SimulationBuilder, engine::SimulationEngine},
+};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const COMPONENTS: usize = 10;
+const ORGANISMS: usize = 3;
+const TICKS: u64 = 100_000;
+const FORMATS: [(&str, LogFormat); 3] = [
+    ("csv", LogFormat::Csv),
+    ("jsonl", LogFormat::JsonLines),
+    ("parquet", LogFormat::Parquet),
+];
+
+fn log_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bioforge_bench_log.{}", name))
+}
+
+fn build_engine(path: &Path, format: LogFormat) -> SimulationEngine {
+    SimulationBuilder::new()
+        .with_organisms((0..ORGANISMS).map(|i| common::organism(i, COMPONENTS)).collect())
+        .with_process(common::process(Some(common::STOP_RULE_ID)))
+        .with_rules(vec![common::stop_rule(TICKS)])
+        .with_initial_media(common::media(COMPONENTS))
+        .with_timeseries_logging(path.to_str().unwrap(), format)
+        .build()
+        .expect("bench engine should build")
+}
+
+fn bench_log_formats(c: &mut Criterion) {
+    let mut write = c.benchmark_group("log_write_100k_ticks");
+    write.sample_size(10);
+    for (name, format) in FORMATS {
+        let path = log_path(name);
+        write.bench_function(name, |b| {
+            b.iter_batched(
+                || build_engine(&path, format),
+                |mut engine| engine.run().expect("run should succeed"),
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    write.finish();
+
+    // The write benchmark leaves a complete 100k-tick log of each format behind.
+    let process = common::process(Some(common::STOP_RULE_ID));
+    let mut read = c.benchmark_group("generate_bom_100k_ticks");
+    read.sample_size(10);
+    for (name, _) in FORMATS {
+        let path = log_path(name);
+        read.bench_function(name, |b| {
+            b.iter(|| {
+                generate_bom(path.to_str().unwrap(), &process, &HashMap::new(), &HashMap::new())
+                    .expect("log should parse")
+            })
+        });
+    }
+    read.finish();
+
+    for (name, _) in FORMATS {
+        let _ = std::fs::remove_file(log_path(name));
+    }
+}
+
+criterion_group!(benches, bench_log_formats);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+//! Benchmarks the per-tick cost of the simulation engine with a realistic media size.
+//!
+//! Run with `cargo bench -p bioforge-core --bench media_tick`. Add `--no-default-features` to
+//! compare against the sequential biological tick.
+
+mod common;
+
+use bioforge_core::simulation::{builder::SimulationBuilder, engine::SimulationEngine};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+const COMPONENTS: usize = 50;
+const TICKS: usize = 5_000;
+
+fn build_engine(organisms: usize) -> SimulationEngine {
+    SimulationBuilder::new()
+        .with_organisms((0..organisms).map(|i| common::organism(i, COMPONENTS)).collect())
+        .with_process(common::process(None))
+        .with_initial_media(common::media(COMPONENTS))
+        .build()
+        .expect("bench engine should build")
+}
+
+fn bench_media_tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("media_tick");
+    group.sample_size(10);
+    for organisms in [5, 16] {
+        group.bench_function(format!("50_components_{}_organisms_5000_ticks", organisms), |b| {
+            b.iter_batched(
+                || build_engine(organisms),
+                |mut engine| {
+                    for _ in 0..TICKS {
+                        engine.tick().expect("tick should succeed");
+                    }
+                    engine
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_media_tick);
+criterion_main!(benches);
@@ -0,0 +1,335 @@
+//! A year-long campaign of a batch run back to back, with turnaround between batches: annual
+//! output, costs, and impacts, and how busy the campaign keeps each asset.
+
+use super::{is_additive, BillOfMaterials, CogsResult, LcaResult};
+use crate::error::BioforgeError;
+use bioforge_schemas::{
+    asset::Asset,
+    tea_lca::{CostEntry, EconomicAssumptions},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// How many batches a campaign runs per year.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "basis", rename_all = "snake_case")]
+pub enum CampaignSize {
+    /// As many batches as fit into the operating hours of a year.
+    #[default]
+    Capacity,
+    /// A fixed number of batches.
+    BatchesPerYear { batches: u32 },
+    /// The fewest batches that produce `grams` of `molecule_name` a year.
+    AnnualOutput { molecule_name: String, grams: f64 },
+}
+
+/// The parameters of a campaign, e.g. from the `campaign` section of a request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CampaignParameters {
+    #[serde(default)]
+    pub size: CampaignSize,
+    /// Hours of cleaning and setup (CIP/SIP) between batches. Defaults to the
+    /// `batch_turnaround_hours` of the assumptions.
+    #[serde(default)]
+    pub turnaround_hours: Option<f64>,
+    /// Labor paid for the year however many batches run, e.g. salaried staff, in the currency of
+    /// the assumptions.
+    #[serde(default)]
+    pub fixed_labor_costs_per_year: f64,
+}
+
+/// A product of a batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CampaignProduct {
+    pub molecule_name: String,
+    pub grams_per_batch: f64,
+}
+
+/// The costs of owning an asset for a year, whether or not it is busy.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetOwnership {
+    /// The asset's `capex` spread evenly over its lifespan.
+    pub annual_depreciation: f64,
+    /// The asset's `opex_per_year` maintenance.
+    pub annual_maintenance: f64,
+}
+
+/// A batch repeated for a year.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CampaignModel {
+    /// Hours from the start to the end of a batch.
+    pub batch_hours: f64,
+    pub turnaround_hours: f64,
+    pub batches_per_year: u32,
+    /// Whole batches that fit into the operating hours of a year.
+    pub capacity_batches_per_year: u32,
+    pub operating_hours_per_year: f64,
+    pub products: Vec<CampaignProduct>,
+    /// The costs of one batch. Its depreciation and maintenance are replaced in the campaign by
+    /// the yearly costs of `assets`.
+    pub batch_cogs: CogsResult,
+    pub batch_lca: LcaResult,
+    /// Hours one batch occupies each asset, keyed by asset id.
+    pub asset_hours_per_batch: BTreeMap<String, f64>,
+    /// The ownership costs of the assets the batch runs on, keyed by asset id.
+    pub assets: BTreeMap<String, AssetOwnership>,
+    pub fixed_labor_costs_per_year: f64,
+}
+
+/// The yearly totals of a [`CampaignModel`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CampaignReport {
+    #[serde(flatten)]
+    pub model: CampaignModel,
+    /// Grams of each product a year, keyed by molecule name.
+    pub annual_output_g: BTreeMap<String, f64>,
+    /// The costs of a year: those of every batch, with the depreciation and maintenance of the
+    /// assets for the whole year instead of per batch, plus the fixed labor.
+    pub annual_cogs: CogsResult,
+    /// `annual_cogs` per gram of all products together, or `None` if nothing is produced. The
+    /// same goes for the per-gram lines below.
+    pub cogs_per_gram: Option<f64>,
+    pub material_cost_per_gram: Option<f64>,
+    pub depreciation_per_gram: Option<f64>,
+    /// The fraction of the operating hours of a year each asset is occupied, keyed by asset id.
+    pub asset_utilization: BTreeMap<String, f64>,
+    /// The impacts of every batch of a year, with their units, keyed by metric. Impacts that are
+    /// not additive, such as a `%` recycling rate, are those of one batch.
+    pub annual_impacts: BTreeMap<String, (f64, String)>,
+}
+
+impl CampaignModel {
+    /// Builds the campaign of the batch described by `bom`, `cogs`, and `lca`. The batch runs for
+    /// the total ticks (hours) of the BOM, and the campaign owns every asset the batch occupies.
+    ///
+    /// Fails if the campaign needs more batches than fit into a year, or if its
+    /// [`CampaignSize::AnnualOutput`] names a product the batch does not make.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        bom: &BillOfMaterials,
+        cogs: &CogsResult,
+        lca: &LcaResult,
+        products: Vec<CampaignProduct>,
+        parameters: &CampaignParameters,
+        assets: &HashMap<String, Asset>,
+        assumptions: &EconomicAssumptions,
+    ) -> Result<Self, BioforgeError> {
+        let batch_hours = bom.total_ticks as f64;
+        let turnaround_hours = parameters.turnaround_hours.unwrap_or(assumptions.batch_turnaround_hours);
+        let cycle_hours = batch_hours + turnaround_hours;
+        let capacity_batches_per_year = if cycle_hours > 0.0 {
+            (assumptions.operating_hours_per_year / cycle_hours).floor() as u32
+        } else {
+            0
+        };
+
+        let batches_per_year = match &parameters.size {
+            CampaignSize::Capacity => capacity_batches_per_year,
+            CampaignSize::BatchesPerYear { batches } => *batches,
+            CampaignSize::AnnualOutput { molecule_name, grams } => {
+                let grams_per_batch = products
+                    .iter()
+                    .filter(|p| &p.molecule_name == molecule_name)
+                    .map(|p| p.grams_per_batch)
+                    .sum::<f64>();
+                if grams_per_batch <= 0.0 {
+                    return Err(BioforgeError::ConfigError(format!(
+                        "Campaign targets {} g/year of '{}', which the batch does not produce",
+                        grams, molecule_name
+                    )));
+                }
+                (grams / grams_per_batch).ceil() as u32
+            }
+        };
+        if batches_per_year > capacity_batches_per_year {
+            return Err(BioforgeError::ConfigError(format!(
+                "Campaign needs {} batches a year, but only {} batches of {:.0} h plus {:.0} h turnaround fit into {:.0} operating hours",
+                batches_per_year, capacity_batches_per_year, batch_hours, turnaround_hours, assumptions.operating_hours_per_year
+            )));
+        }
+
+        let asset_hours_per_batch: BTreeMap<String, f64> =
+            bom.asset_ticks().into_iter().map(|(asset_id, ticks)| (asset_id, ticks as f64)).collect();
+        let owned_assets = asset_hours_per_batch
+            .keys()
+            .filter_map(|asset_id| {
+                let tea = assets.get(asset_id)?.techno_economic_and_lca_profile.as_ref()?;
+                let lifespan_years = tea.expected_lifespan.as_ref().map_or(1, |l| l.value) as f64;
+                let cost = |costs: &[CostEntry], cost_type: &str| {
+                    costs.iter().find(|c| c.cost_type == cost_type).map_or(0.0, |c| c.value_usd)
+                };
+                let stages = &tea.lifecycle_stages;
+                Some((
+                    asset_id.clone(),
+                    AssetOwnership {
+                        annual_depreciation: cost(&stages.manufacturing_and_acquisition.costs, "capex") / lifespan_years,
+                        annual_maintenance: cost(&stages.maintenance.costs, "opex_per_year"),
+                    },
+                ))
+            })
+            .collect();
+
+        Ok(CampaignModel {
+            batch_hours,
+            turnaround_hours,
+            batches_per_year,
+            capacity_batches_per_year,
+            operating_hours_per_year: assumptions.operating_hours_per_year,
+            products,
+            batch_cogs: cogs.clone(),
+            batch_lca: lca.clone(),
+            asset_hours_per_batch,
+            assets: owned_assets,
+            fixed_labor_costs_per_year: parameters.fixed_labor_costs_per_year,
+        })
+    }
+
+    /// The costs of a year that do not depend on the number of batches: the depreciation and
+    /// maintenance of the assets and the fixed labor.
+    pub fn fixed_annual_costs(&self) -> f64 {
+        self.assets.values().map(|a| a.annual_depreciation + a.annual_maintenance).sum::<f64>()
+            + self.fixed_labor_costs_per_year
+    }
+
+    /// The costs each batch adds: its COGS without depreciation and maintenance.
+    pub fn variable_cost_per_batch(&self) -> f64 {
+        self.batch_cogs.total_cogs - self.batch_cogs.asset_depreciation_costs - self.batch_cogs.maintenance_costs
+    }
+
+    /// Accumulates the batches of a year.
+    pub fn evaluate(&self) -> CampaignReport {
+        let batches = self.batches_per_year as f64;
+        let batch = &self.batch_cogs;
+
+        let mut annual_output_g = BTreeMap::new();
+        for product in &self.products {
+            *annual_output_g.entry(product.molecule_name.clone()).or_insert(0.0) += product.grams_per_batch * batches;
+        }
+        let total_output_g: f64 = annual_output_g.values().sum();
+
+        let scale = |costs: &HashMap<String, f64>| costs.iter().map(|(id, cost)| (id.clone(), cost * batches)).collect();
+        let mut annual_cogs = CogsResult {
+            material_costs: batch.material_costs * batches,
+            labor_costs: batch.labor_costs * batches + self.fixed_labor_costs_per_year,
+            energy_costs: batch.energy_costs * batches,
+            asset_depreciation_costs: self.assets.values().map(|a| a.annual_depreciation).sum(),
+            maintenance_costs: self.assets.values().map(|a| a.annual_maintenance).sum(),
+            end_of_life_costs: batch.end_of_life_costs * batches,
+            qc_costs: batch.qc_costs * batches,
+            material_costs_by_consumer: scale(&batch.material_costs_by_consumer),
+            labor_costs_by_role: scale(&batch.labor_costs_by_role),
+            ..Default::default()
+        };
+        annual_cogs.total_cogs = annual_cogs.material_costs
+            + annual_cogs.labor_costs
+            + annual_cogs.energy_costs
+            + annual_cogs.asset_depreciation_costs
+            + annual_cogs.maintenance_costs
+            + annual_cogs.end_of_life_costs
+            + annual_cogs.qc_costs;
+        let per_gram = |cost: f64| (total_output_g > 0.0).then(|| cost / total_output_g);
+
+        let asset_utilization = self
+            .asset_hours_per_batch
+            .iter()
+            .map(|(asset_id, hours)| {
+                let utilization = if self.operating_hours_per_year > 0.0 {
+                    hours * batches / self.operating_hours_per_year
+                } else {
+                    0.0
+                };
+                (asset_id.clone(), utilization)
+            })
+            .collect();
+
+        let annual_impacts = self
+            .batch_lca
+            .impacts
+            .iter()
+            .map(|(metric, (value, unit))| {
+                let value = if is_additive(unit) { value * batches } else { *value };
+                (metric.clone(), (value, unit.clone()))
+            })
+            .collect();
+
+        CampaignReport {
+            model: self.clone(),
+            annual_output_g,
+            cogs_per_gram: per_gram(annual_cogs.total_cogs),
+            material_cost_per_gram: per_gram(annual_cogs.material_costs),
+            depreciation_per_gram: per_gram(annual_cogs.asset_depreciation_costs),
+            annual_cogs,
+            asset_utilization,
+            annual_impacts,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{
+        calculate_cogs, calculate_lca,
+        tests::{asset, material, materials},
+    };
+
+    /// A campaign of `batches` a year of a 48 h batch on a reactor bought for 87,600 and lasting
+    /// ten years, consuming a kilogram of glucose and making 10 g of lutein.
+    fn campaign(batches: u32) -> CampaignReport {
+        let mut bom = BillOfMaterials::default();
+        bom.add_stage_ticks("MTHD-CULT", "REACTOR", 48);
+        bom.total_ticks = 48;
+        bom.materials_consumed.insert("GLUCOSE".to_string(), 1000.0);
+        let materials = materials(vec![material("GLUCOSE", 0.5, 1.4, 0.0, 0.0)]);
+        let assets = HashMap::from([("REACTOR".to_string(), asset("REACTOR", 87_600.0, 8760.0))]);
+        let assumptions = EconomicAssumptions::default();
+        let cogs = calculate_cogs(&bom, &materials, &HashMap::new(), &assets, &assumptions).unwrap();
+        let lca = calculate_lca(&bom, &materials, &assets, &assumptions).unwrap();
+        let products = vec![CampaignProduct { molecule_name: "Lutein".to_string(), grams_per_batch: 10.0 }];
+        let parameters = CampaignParameters { size: CampaignSize::BatchesPerYear { batches }, ..Default::default() };
+        CampaignModel::new(&bom, &cogs, &lca, products, &parameters, &assets, &assumptions).unwrap().evaluate()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn doubling_the_batches_halves_depreciation_per_gram_but_not_material_cost_per_gram() {
+        let (ten, twenty) = (campaign(10), campaign(20));
+        assert_eq!(ten.annual_output_g["Lutein"], 100.0);
+        assert_eq!(twenty.annual_output_g["Lutein"], 200.0);
+
+        // The reactor depreciates by 8,760 a year however many batches it runs.
+        assert_close(ten.annual_cogs.asset_depreciation_costs, 8760.0);
+        assert_close(twenty.annual_cogs.asset_depreciation_costs, 8760.0);
+        assert_close(ten.depreciation_per_gram.unwrap(), 87.6);
+        assert_close(twenty.depreciation_per_gram.unwrap(), 43.8);
+
+        // Each batch buys its own kilogram of glucose.
+        assert_close(ten.material_cost_per_gram.unwrap(), 0.05);
+        assert_close(twenty.material_cost_per_gram.unwrap(), 0.05);
+        assert_close(twenty.asset_utilization["REACTOR"], 2.0 * ten.asset_utilization["REACTOR"]);
+    }
+
+    #[test]
+    fn an_annual_output_runs_the_fewest_batches_that_make_it_within_capacity() {
+        let mut bom = BillOfMaterials::default();
+        bom.add_stage_ticks("MTHD-CULT", "REACTOR", 48);
+        bom.total_ticks = 48;
+        let products = vec![CampaignProduct { molecule_name: "Lutein".to_string(), grams_per_batch: 10.0 }];
+        let assumptions = EconomicAssumptions::default();
+        let model = |molecule_name: &str, grams: f64| {
+            let size = CampaignSize::AnnualOutput { molecule_name: molecule_name.to_string(), grams };
+            let parameters = CampaignParameters { size, ..Default::default() };
+            let cogs = CogsResult::default();
+            CampaignModel::new(&bom, &cogs, &LcaResult::default(), products.clone(), &parameters, &HashMap::new(), &assumptions)
+        };
+
+        assert_eq!(model("Lutein", 105.0).unwrap().batches_per_year, 11);
+        // 8,760 h fit 121 cycles of 48 h and 24 h of turnaround.
+        assert_eq!(model("Lutein", 1210.0).unwrap().capacity_batches_per_year, 121);
+        assert!(model("Lutein", 1211.0).unwrap_err().to_string().contains("needs 122 batches a year, but only 121"));
+        assert!(model("Astaxanthin", 10.0).unwrap_err().to_string().contains("which the batch does not produce"));
+    }
+}
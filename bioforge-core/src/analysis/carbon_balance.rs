@@ -0,0 +1,223 @@
+//! Carbon accounting of a run that keeps biogenic carbon, fixed from and respired to the
+//! atmosphere, apart from the fossil CO₂ of the energy it uses.
+
+use super::{
+    mass_balance::{element_content, Flows},
+    read_log, MaterialResolver,
+};
+use crate::{error::BioforgeError, logger::LogRecord};
+use bioforge_schemas::{material::Material, organism::Organism, tea_lca::EconomicAssumptions};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// The molecule id of carbon dioxide, whose uptake is carbon fixation.
+pub const CARBON_DIOXIDE_ID: &str = "CHEBI:16526";
+
+/// Kilograms of CO₂ per gram of carbon it holds, from the molar masses of CO₂ and C.
+pub const KG_CO2_PER_G_CARBON: f64 = 44.009 / 12.011 / 1000.0;
+
+/// A product held in the organisms' biomass.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CarbonProduct {
+    pub molecule_name: String,
+    pub grams: f64,
+    /// The mass fraction of carbon in the product, e.g. from [`carbon_mass_fraction`].
+    pub carbon_fraction: f64,
+}
+
+/// Where the carbon of a run came from and went, in grams of carbon, and the CO₂e of each flow
+/// to or from the atmosphere.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CarbonBalance {
+    /// Taken up by the organisms in organic molecules of the media, e.g. glucose.
+    pub feedstock_carbon_g: f64,
+    /// Taken up by the organisms as CO₂: biogenic fixation.
+    pub fixed_carbon_g: f64,
+    /// Net gain of the organisms' biomass, by their elemental composition.
+    pub biomass_carbon_g: f64,
+    /// The carbon of each product, part of `biomass_carbon_g`, keyed by molecule name.
+    pub product_carbon_g: BTreeMap<String, f64>,
+    /// Net gain of the molecules the organisms secrete.
+    pub secreted_carbon_g: f64,
+    /// Taken up but found in neither biomass nor secreted molecules. Gas exchange is not
+    /// simulated, so this is taken to be respired as CO₂: biogenic emission.
+    pub respired_carbon_g: f64,
+    /// The carbon taken up less everything it was found in or respired. Non-zero only when the
+    /// organisms gained more carbon than they took up, e.g. from CO₂ fixation the log does not
+    /// record.
+    pub unreconciled_carbon_g: f64,
+    /// CO₂ taken from the atmosphere by fixation, in kg CO₂e.
+    pub biogenic_fixation_kg_co2e: f64,
+    /// CO₂ respired, in kg CO₂e.
+    pub biogenic_emission_kg_co2e: f64,
+    /// `biogenic_emission_kg_co2e` less `biogenic_fixation_kg_co2e`; negative when the run
+    /// removes more CO₂ from the atmosphere than it returns.
+    pub net_biogenic_kg_co2e: f64,
+    /// CO₂e of the grid electricity the run used, by the assumptions' grid factor; see
+    /// [`CarbonBalance::with_fossil_energy`].
+    pub fossil_energy_kg_co2e: f64,
+    /// Molecules taken up or secreted that match no material, and so contribute no carbon.
+    pub unmatched_molecules: Vec<String>,
+}
+
+impl CarbonBalance {
+    /// Whether the carbon taken up is accounted for within `tolerance`, as a fraction of it, and
+    /// the products hold no more carbon than the biomass.
+    pub fn reconciles(&self, tolerance: f64) -> bool {
+        let uptake_g = self.feedstock_carbon_g + self.fixed_carbon_g;
+        let product_g: f64 = self.product_carbon_g.values().sum();
+        self.unreconciled_carbon_g.abs() <= tolerance * uptake_g.max(f64::EPSILON)
+            && product_g <= self.biomass_carbon_g.max(0.0) * (1.0 + tolerance)
+    }
+
+    /// Sets the fossil CO₂e of `energy_kwh` of grid electricity, e.g. that of the whole run,
+    /// upstream and downstream.
+    pub fn with_fossil_energy(mut self, energy_kwh: f64, assumptions: &EconomicAssumptions) -> Self {
+        self.fossil_energy_kg_co2e = energy_kwh * assumptions.grid_gwp_kg_co2e_per_kwh;
+        self
+    }
+}
+
+/// The carbon balance of the cultivation logged at `log_path`; see
+/// [`carbon_balance_from_records`].
+pub fn carbon_balance(
+    log_path: &str,
+    organisms: &[Organism],
+    materials: &HashMap<String, Material>,
+    products: &[CarbonProduct],
+) -> Result<CarbonBalance, BioforgeError> {
+    carbon_balance_from_records(&read_log(log_path)?, organisms, materials, products)
+}
+
+/// Like [`carbon_balance`], from records already in memory.
+///
+/// The organisms' flows are integrated as in [`super::mass_balance::mass_balance_from_records`]
+/// and converted to carbon by the `carbon_content` specification of each molecule's material.
+/// The fossil CO₂e is left at zero.
+pub fn carbon_balance_from_records(
+    records: &[LogRecord],
+    organisms: &[Organism],
+    materials: &HashMap<String, Material>,
+    products: &[CarbonProduct],
+) -> Result<CarbonBalance, BioforgeError> {
+    let Some(flows) = Flows::of(records, organisms)? else {
+        return Ok(CarbonBalance::default());
+    };
+    let resolver = MaterialResolver::new(materials);
+
+    let mut unmatched_molecules = Vec::new();
+    let mut carbon_g = |id: &str, grams: f64| match resolver.resolve(id) {
+        Some(material) => grams * element_content(material, "carbon"),
+        None => {
+            unmatched_molecules.push(id.to_string());
+            0.0
+        }
+    };
+    let (mut feedstock_carbon_g, mut fixed_carbon_g) = (0.0, 0.0);
+    for (id, grams) in &flows.uptake {
+        let carbon = carbon_g(id, *grams);
+        if id == CARBON_DIOXIDE_ID {
+            fixed_carbon_g += carbon;
+        } else {
+            feedstock_carbon_g += carbon;
+        }
+    }
+    let secreted_carbon_g: f64 = flows.secreted.iter().map(|(id, grams)| carbon_g(id, *grams)).sum();
+    let biomass_carbon_g = flows.biomass_element_g(organisms, "carbon");
+    unmatched_molecules.sort();
+    unmatched_molecules.dedup();
+
+    let unaccounted_g = feedstock_carbon_g + fixed_carbon_g - biomass_carbon_g - secreted_carbon_g;
+    let respired_carbon_g = unaccounted_g.max(0.0);
+    let biogenic_fixation_kg_co2e = fixed_carbon_g * KG_CO2_PER_G_CARBON;
+    let biogenic_emission_kg_co2e = respired_carbon_g * KG_CO2_PER_G_CARBON;
+
+    Ok(CarbonBalance {
+        feedstock_carbon_g,
+        fixed_carbon_g,
+        biomass_carbon_g,
+        product_carbon_g: products
+            .iter()
+            .map(|p| (p.molecule_name.clone(), p.grams * p.carbon_fraction))
+            .collect(),
+        secreted_carbon_g,
+        respired_carbon_g,
+        unreconciled_carbon_g: unaccounted_g - respired_carbon_g,
+        biogenic_fixation_kg_co2e,
+        biogenic_emission_kg_co2e,
+        net_biogenic_kg_co2e: biogenic_emission_kg_co2e - biogenic_fixation_kg_co2e,
+        fossil_energy_kg_co2e: 0.0,
+        unmatched_molecules,
+    })
+}
+
+/// The mass fraction of carbon in a molecule of `formula`, e.g. `C40H56O2` or `(C6H10O5)n`, or
+/// `None` if the formula holds an element of unknown mass or cannot be read. A repeat count that
+/// is not a number, such as the `n` of a polymer, leaves the fraction unchanged and is ignored.
+pub fn carbon_mass_fraction(formula: &str) -> Option<f64> {
+    let counts = element_counts(&mut formula.chars().peekable(), 0)?;
+    let mass = |(symbol, count): (&String, &f64)| Some(atomic_mass(symbol)? * count);
+    let total: f64 = counts.iter().map(mass).sum::<Option<f64>>()?;
+    let carbon = counts.get("C").map_or(0.0, |count| count * 12.011);
+    (total > 0.0).then(|| carbon / total)
+}
+
+/// Counts the atoms of each element in a formula, recursing into parenthesized groups.
+fn element_counts(chars: &mut std::iter::Peekable<std::str::Chars>, depth: usize) -> Option<BTreeMap<String, f64>> {
+    let mut counts = BTreeMap::new();
+    while let Some(&c) = chars.peek() {
+        let group = match c {
+            '(' => {
+                chars.next();
+                element_counts(chars, depth + 1)?
+            }
+            ')' if depth > 0 => {
+                chars.next();
+                return Some(counts);
+            }
+            c if c.is_ascii_uppercase() => {
+                let mut symbol = chars.next()?.to_string();
+                while let Some(&lower) = chars.peek().filter(|c| c.is_ascii_lowercase() && **c != 'n') {
+                    symbol.push(lower);
+                    chars.next();
+                }
+                BTreeMap::from([(symbol, 1.0)])
+            }
+            // Polymer repeat counts.
+            'n' => {
+                chars.next();
+                continue;
+            }
+            _ => return None,
+        };
+        let mut digits = String::new();
+        while let Some(&digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+            digits.push(digit);
+            chars.next();
+        }
+        let multiplier: f64 = if digits.is_empty() { 1.0 } else { digits.parse().ok()? };
+        for (symbol, count) in group {
+            *counts.entry(symbol).or_insert(0.0) += count * multiplier;
+        }
+    }
+    (depth == 0).then_some(counts)
+}
+
+/// Standard atomic masses of the elements found in biomolecules, in g/mol.
+fn atomic_mass(symbol: &str) -> Option<f64> {
+    Some(match symbol {
+        "H" => 1.008,
+        "C" => 12.011,
+        "N" => 14.007,
+        "O" => 15.999,
+        "Na" => 22.990,
+        "Mg" => 24.305,
+        "P" => 30.974,
+        "S" => 32.06,
+        "Cl" => 35.45,
+        "K" => 39.098,
+        "Ca" => 40.078,
+        "Fe" => 55.845,
+        _ => return None,
+    })
+}
@@ -0,0 +1,250 @@
+//! Differences between two completed runs, read back from the reports written to their run
+//! directories, to see what a change to a run moved.
+
+use super::{
+    manifest::RunManifest,
+    performance::ProcessPerformance,
+    report::{AnalysisReport, Versioned, REPORT_SCHEMA_VERSION},
+};
+use crate::error::BioforgeError;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+/// How one quantity changed from run A to run B.
+///
+/// Every delta is B less A, so a negative delta of a cost, impact, or consumption means that B
+/// needs less of it. A quantity missing from one run counts as zero there.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delta {
+    /// The value in run A, or `None` if run A lacks the quantity.
+    pub a: Option<f64>,
+    /// The value in run B, or `None` if run B lacks the quantity.
+    pub b: Option<f64>,
+    /// B less A.
+    pub change: f64,
+    /// `change` as a fraction of A, or `None` if A is missing or zero.
+    pub relative_change: Option<f64>,
+}
+
+impl Delta {
+    pub fn new(a: Option<f64>, b: Option<f64>) -> Self {
+        let change = b.unwrap_or(0.0) - a.unwrap_or(0.0);
+        let relative_change = a.filter(|a| *a != 0.0).map(|a| change / a.abs());
+        Self { a, b, change, relative_change }
+    }
+
+    /// Whether both runs have the quantity.
+    pub fn in_both(&self) -> bool {
+        self.a.is_some() && self.b.is_some()
+    }
+}
+
+/// The change in one impact category.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImpactDelta {
+    pub unit: String,
+    #[serde(flatten)]
+    pub delta: Delta,
+}
+
+/// The change in the grams of one material consumed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaterialDelta {
+    /// The material's name, from whichever run names it, or `None` if neither does.
+    pub material_name: Option<String>,
+    #[serde(flatten)]
+    pub delta: Delta,
+}
+
+/// The changes between two runs, keyed in each section by what changed.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunDiff {
+    pub run_a: String,
+    pub run_b: String,
+    /// Each line of the COGS (`material_costs`, ..., `total_cogs`).
+    pub cost_lines: BTreeMap<String, Delta>,
+    /// The total COGS of each stage, keyed by stage id.
+    pub stage_costs: BTreeMap<String, Delta>,
+    /// The labor costs of each role, keyed by role id.
+    pub labor_costs_by_role: BTreeMap<String, Delta>,
+    /// The total of each impact category, keyed by metric.
+    pub impacts: BTreeMap<String, ImpactDelta>,
+    /// Each cultivation KPI, keyed by a readable name such as `titer_g_l[Lutein]`. Empty if either
+    /// run has no KPIs.
+    pub kpis: BTreeMap<String, Delta>,
+    /// The grams of each material consumed, keyed by material id.
+    pub materials: BTreeMap<String, MaterialDelta>,
+    /// The ticks each stage ran for, keyed by stage id.
+    pub stage_ticks: BTreeMap<String, Delta>,
+    /// Energy, labor, and duration totals of the BOM.
+    pub resources: BTreeMap<String, Delta>,
+    /// The knowledge-base files whose hashes differ between the manifests of the two runs, so
+    /// that their differences may come from the knowledge base rather than the request. Empty if
+    /// either run has no manifest.
+    #[serde(default)]
+    pub changed_knowledge_base_files: Vec<String>,
+}
+
+impl RunDiff {
+    /// The stages that ran in only one of the two runs.
+    pub fn unmatched_stages(&self) -> impl Iterator<Item = (&String, &Delta)> {
+        self.stage_ticks.iter().filter(|(_, delta)| !delta.in_both())
+    }
+}
+
+/// Compares the run in `run_dir_b` against the one in `run_dir_a`.
+///
+/// Both directories must hold the `report.json` of the workflow, written with the current
+/// [`REPORT_SCHEMA_VERSION`]; `kpis.json` and the run manifest are optional. Warns when the
+/// manifests show that the runs used different knowledge bases.
+pub fn compare_runs(run_dir_a: &str, run_dir_b: &str) -> Result<RunDiff, BioforgeError> {
+    let (report_a, kpis_a) = read_run(run_dir_a)?;
+    let (report_b, kpis_b) = read_run(run_dir_b)?;
+    let mut diff = compare_reports(&report_a, &report_b);
+    if let (Some(kpis_a), Some(kpis_b)) = (kpis_a, kpis_b) {
+        diff.kpis = diff_maps(&kpi_values(&kpis_a), &kpi_values(&kpis_b));
+    }
+    if let (Some(manifest_a), Some(manifest_b)) = (RunManifest::read(run_dir_a)?, RunManifest::read(run_dir_b)?) {
+        diff.changed_knowledge_base_files = manifest_a.changed_knowledge_base_files(&manifest_b.knowledge_base_files);
+        if !diff.changed_knowledge_base_files.is_empty() {
+            log::warn!(
+                "'{}' and '{}' used different knowledge bases; these files differ: {}",
+                run_dir_a,
+                run_dir_b,
+                diff.changed_knowledge_base_files.join(", ")
+            );
+        }
+    }
+    diff.run_a = run_dir_a.to_string();
+    diff.run_b = run_dir_b.to_string();
+    Ok(diff)
+}
+
+/// Like [`compare_runs`], from reports already in memory. KPIs are left empty.
+pub fn compare_reports(a: &AnalysisReport, b: &AnalysisReport) -> RunDiff {
+    let stage_costs = diff_maps(
+        &a.cogs.by_stage.iter().map(|(id, cogs)| (id.clone(), cogs.total_cogs)).collect(),
+        &b.cogs.by_stage.iter().map(|(id, cogs)| (id.clone(), cogs.total_cogs)).collect(),
+    );
+    let stage_ticks = diff_maps(
+        &a.bom.bom.by_stage.iter().map(|(id, stage)| (id.clone(), stage.ticks as f64)).collect(),
+        &b.bom.bom.by_stage.iter().map(|(id, stage)| (id.clone(), stage.ticks as f64)).collect(),
+    );
+
+    let impacts = diff_maps(
+        &a.lca.impacts.iter().map(|(metric, (value, _))| (metric.clone(), *value)).collect(),
+        &b.lca.impacts.iter().map(|(metric, (value, _))| (metric.clone(), *value)).collect(),
+    )
+    .into_iter()
+    .map(|(metric, delta)| {
+        let unit = b.lca.impacts.get(&metric).or_else(|| a.lca.impacts.get(&metric)).map(|(_, unit)| unit.clone());
+        (metric, ImpactDelta { unit: unit.unwrap_or_default(), delta })
+    })
+    .collect();
+
+    let materials = diff_maps(&a.bom.bom.materials_consumed, &b.bom.bom.materials_consumed)
+        .into_iter()
+        .map(|(id, delta)| {
+            let material_name = b.bom.material_names.get(&id).or_else(|| a.bom.material_names.get(&id)).cloned();
+            (id, MaterialDelta { material_name, delta })
+        })
+        .collect();
+
+    RunDiff {
+        run_a: String::new(),
+        run_b: String::new(),
+        cost_lines: diff_maps(&cost_lines(a), &cost_lines(b)),
+        stage_costs,
+        labor_costs_by_role: diff_maps(&a.cogs.labor_costs_by_role, &b.cogs.labor_costs_by_role),
+        impacts,
+        kpis: BTreeMap::new(),
+        materials,
+        stage_ticks,
+        resources: diff_maps(&resources(a), &resources(b)),
+        changed_knowledge_base_files: Vec::new(),
+    }
+}
+
+fn read_run(run_dir: &str) -> Result<(AnalysisReport, Option<ProcessPerformance>), BioforgeError> {
+    let dir = Path::new(run_dir);
+    let report_path = dir.join("report.json");
+    let report_str = report_path.to_string_lossy().to_string();
+    let contents = fs::read_to_string(&report_path).map_err(|e| BioforgeError::FileIO(report_str.clone(), e))?;
+    let report: Versioned<AnalysisReport> = serde_json::from_str(&contents)?;
+    if report.schema_version != REPORT_SCHEMA_VERSION {
+        return Err(BioforgeError::ConfigError(format!(
+            "'{}' was written with report schema {}, but this version reads schema {}",
+            report_str, report.schema_version, REPORT_SCHEMA_VERSION
+        )));
+    }
+
+    let kpis_path = dir.join("kpis.json");
+    let kpis = if kpis_path.exists() {
+        let contents = fs::read_to_string(&kpis_path)
+            .map_err(|e| BioforgeError::FileIO(kpis_path.to_string_lossy().to_string(), e))?;
+        Some(serde_json::from_str(&contents)?)
+    } else {
+        None
+    };
+    Ok((report.report, kpis))
+}
+
+/// Pairs up the entries of `a` and `b` by key, over the keys of both.
+fn diff_maps<K: Ord + Clone + std::hash::Hash>(a: &HashMap<K, f64>, b: &HashMap<K, f64>) -> BTreeMap<K, Delta> {
+    let keys: BTreeSet<&K> = a.keys().chain(b.keys()).collect();
+    keys.into_iter().map(|key| (key.clone(), Delta::new(a.get(key).copied(), b.get(key).copied()))).collect()
+}
+
+fn cost_lines(report: &AnalysisReport) -> HashMap<String, f64> {
+    let cogs = &report.cogs;
+    [
+        ("material_costs", cogs.material_costs),
+        ("labor_costs", cogs.labor_costs),
+        ("energy_costs", cogs.energy_costs),
+        ("asset_depreciation_costs", cogs.asset_depreciation_costs),
+        ("maintenance_costs", cogs.maintenance_costs),
+        ("end_of_life_costs", cogs.end_of_life_costs),
+        ("qc_costs", cogs.qc_costs),
+        ("total_cogs", cogs.total_cogs),
+    ]
+    .into_iter()
+    .map(|(line, value)| (line.to_string(), value))
+    .collect()
+}
+
+fn resources(report: &AnalysisReport) -> HashMap<String, f64> {
+    let bom = &report.bom.bom;
+    let mut resources = HashMap::from([
+        ("total_energy_kwh".to_string(), bom.total_energy_kwh),
+        ("total_ticks".to_string(), bom.total_ticks as f64),
+        ("total_waste_g".to_string(), bom.waste_generated.values().sum()),
+    ]);
+    for (role_id, hours) in &bom.labor_hours {
+        resources.insert(format!("labor_hours[{}]", role_id), *hours);
+    }
+    resources
+}
+
+fn kpi_values(performance: &ProcessPerformance) -> HashMap<String, f64> {
+    let mut values = HashMap::from([("batch_duration_hr".to_string(), performance.batch_duration_hr)]);
+    for (organism_id, rate) in &performance.peak_specific_growth_rates {
+        values.insert(format!("peak_specific_growth_rate[{}]", organism_id), *rate);
+    }
+    for product in &performance.products {
+        let name = &product.molecule_name;
+        values.insert(format!("produced_g[{}]", name), product.produced_g);
+        values.insert(format!("titer_g_l[{}]", name), product.titer_g_l);
+        values.insert(format!("space_time_yield_g_l_h[{}]", name), product.space_time_yield_g_l_h);
+        if let Some(value) = product.yield_product_per_biomass {
+            values.insert(format!("yield_product_per_biomass[{}]", name), value);
+        }
+        if let Some(value) = product.yield_biomass_per_substrate {
+            values.insert(format!("yield_biomass_per_substrate[{}]", name), value);
+        }
+    }
+    values
+}
@@ -0,0 +1,245 @@
+//! Limits a request sets on the cost, duration, and impact of a run, and checks of a run or an
+//! estimate against them, and against the headcount of its labor roles, and the organisms a
+//! request lets the optimizer select.
+use super::StageBom;
+use crate::simulation::engine::TIME_STEP_HR;
+use bioforge_schemas::labor::LaborRole;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The most a run may cost, take, and emit. Every limit is optional; a request without any sets
+/// no constraint.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Constraints {
+    /// The highest total COGS of a batch, in the currency of the economic assumptions.
+    #[serde(default)]
+    pub max_total_cost_usd: Option<f64>,
+    /// The longest a batch may take, upstream and downstream together, in hours.
+    #[serde(default)]
+    pub max_duration_hours: Option<f64>,
+    /// The highest GWP of a batch, in kg CO2e.
+    #[serde(default)]
+    pub max_gwp_kg_co2e: Option<f64>,
+}
+
+/// A quantity a [`Constraints`] limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConstrainedQuantity {
+    TotalCost,
+    Duration,
+    Gwp,
+}
+
+impl fmt::Display for ConstrainedQuantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            ConstrainedQuantity::TotalCost => "total cost",
+            ConstrainedQuantity::Duration => "duration (h)",
+            ConstrainedQuantity::Gwp => "GWP (kg CO2e)",
+        })
+    }
+}
+
+/// A quantity of a run or an estimate against the limit a request sets on it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConstraintCheck {
+    pub quantity: ConstrainedQuantity,
+    pub limit: f64,
+    pub actual: f64,
+    pub passed: bool,
+}
+
+impl Constraints {
+    /// Whether the request sets any limit.
+    pub fn is_empty(&self) -> bool {
+        self.max_total_cost_usd.is_none() && self.max_duration_hours.is_none() && self.max_gwp_kg_co2e.is_none()
+    }
+
+    /// Checks a total cost, a duration in hours, and a GWP in kg CO2e against the limits that are
+    /// set, in that order. A quantity that is `None`, e.g. one an estimate does not cover, is
+    /// not checked.
+    pub fn check(&self, total_cost: Option<f64>, duration_hours: Option<f64>, gwp_kg_co2e: Option<f64>) -> Vec<ConstraintCheck> {
+        [
+            (ConstrainedQuantity::TotalCost, self.max_total_cost_usd, total_cost),
+            (ConstrainedQuantity::Duration, self.max_duration_hours, duration_hours),
+            (ConstrainedQuantity::Gwp, self.max_gwp_kg_co2e, gwp_kg_co2e),
+        ]
+        .into_iter()
+        .filter_map(|(quantity, limit, actual)| {
+            let (limit, actual) = (limit?, actual?);
+            Some(ConstraintCheck { quantity, limit, actual, passed: actual <= limit })
+        })
+        .collect()
+    }
+}
+
+/// Which organisms of the knowledge base a request lets the optimizer select. The default lets it
+/// select any.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrganismFilter {
+    /// Whether genetically engineered organisms may be selected.
+    #[serde(default = "default_allow_engineered")]
+    pub allow_engineered: bool,
+    /// Organisms that are never selected.
+    #[serde(default)]
+    pub excluded_organism_ids: Vec<String>,
+    /// Organisms that are selected for every target they make, in place of the others.
+    #[serde(default)]
+    pub required_organism_ids: Vec<String>,
+}
+
+impl Default for OrganismFilter {
+    fn default() -> Self {
+        Self { allow_engineered: true, excluded_organism_ids: Vec::new(), required_organism_ids: Vec::new() }
+    }
+}
+
+impl OrganismFilter {
+    /// Whether the filter lets the optimizer select any organism.
+    pub fn is_empty(&self) -> bool {
+        self.allow_engineered && self.excluded_organism_ids.is_empty() && self.required_organism_ids.is_empty()
+    }
+}
+
+fn default_allow_engineered() -> bool {
+    true
+}
+
+/// The labor one role must put into one stage, against the people it has on duty.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaborDemand {
+    pub stage_id: String,
+    pub role_id: String,
+    /// The role's name, or its id if the role is unknown.
+    pub role_name: String,
+    pub hours: f64,
+    /// Hours the stage runs for.
+    pub stage_hours: f64,
+    /// Hours of the stage the role is on shift.
+    pub staffed_hours: f64,
+    /// People of the role needed at once to put in `hours` over `staffed_hours`.
+    pub concurrent_demand: f64,
+    pub available_headcount: Option<u32>,
+}
+
+impl LaborDemand {
+    /// Whether the role has too few people on duty to meet the demand.
+    pub fn is_violation(&self) -> bool {
+        self.available_headcount.is_some_and(|headcount| self.concurrent_demand > headcount as f64 + 1e-9)
+    }
+}
+
+/// Checks the labor hours of each stage of `by_stage` against the headcount and shift length of
+/// its roles, returning the demand of every role in every stage, sorted by stage and role id.
+///
+/// A stage shorter than a tick counts as a tick, and a role with a shift works it once every 24
+/// hours of the stage, from the stage's start; a shift of zero hours is ignored. Roles without
+/// `available_headcount` never violate.
+pub fn labor_feasibility(
+    by_stage: &HashMap<String, StageBom>,
+    labor_roles: &HashMap<String, LaborRole>,
+) -> Vec<LaborDemand> {
+    let mut demands = Vec::new();
+    for (stage_id, stage) in by_stage {
+        let stage_hours = stage.ticks.max(1) as f64 * TIME_STEP_HR;
+        for (role_id, hours) in &stage.labor_hours {
+            if *hours <= 0.0 {
+                continue;
+            }
+            let role = labor_roles.get(role_id);
+            let staffed_hours = match role.and_then(|r| r.shift_length_hours) {
+                Some(shift) if shift > 0.0 => {
+                    let shift = shift.min(24.0);
+                    (stage_hours / 24.0).floor() * shift + (stage_hours % 24.0).min(shift)
+                }
+                _ => stage_hours,
+            };
+            demands.push(LaborDemand {
+                stage_id: stage_id.clone(),
+                role_id: role_id.clone(),
+                role_name: role.map_or(role_id.clone(), |r| r.role_name.clone()),
+                hours: *hours,
+                stage_hours,
+                staffed_hours,
+                concurrent_demand: hours / staffed_hours,
+                available_headcount: role.and_then(|r| r.available_headcount),
+            });
+        }
+    }
+    demands.sort_by(|a, b| a.stage_id.cmp(&b.stage_id).then_with(|| a.role_id.cmp(&b.role_id)));
+    demands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn role(id: &str, headcount: Option<u32>, shift_length_hours: Option<f64>) -> LaborRole {
+        LaborRole {
+            labor_role_id: id.to_string(),
+            role_name: format!("{id} name"),
+            skill_level: None,
+            description: None,
+            techno_economic_profile: bioforge_schemas::labor::TechnoEconomicProfile { cost_per_hour_usd: 50.0 },
+            available_headcount: headcount,
+            shift_length_hours,
+        }
+    }
+
+    /// A stage of `ticks` hours demanding `hours` of each role.
+    fn stage(ticks: u64, hours: &[(&str, f64)]) -> StageBom {
+        StageBom {
+            ticks,
+            labor_hours: hours.iter().map(|(role_id, hours)| (role_id.to_string(), *hours)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn an_overloaded_stage_is_reported_with_its_stage_and_role() {
+        let roles = HashMap::from([
+            ("OPERATOR".to_string(), role("OPERATOR", Some(2), None)),
+            ("ANALYST".to_string(), role("ANALYST", None, None)),
+        ]);
+        // 30 operator-hours in a 10 h stage take three operators at once; the analyst's role
+        // limits no headcount.
+        let by_stage = HashMap::from([("MTHD-HARVEST".to_string(), stage(10, &[("OPERATOR", 30.0), ("ANALYST", 50.0)]))]);
+        let demands = labor_feasibility(&by_stage, &roles);
+
+        let violations: Vec<&LaborDemand> = demands.iter().filter(|d| d.is_violation()).collect();
+        assert_eq!(
+            violations,
+            [&LaborDemand {
+                stage_id: "MTHD-HARVEST".to_string(),
+                role_id: "OPERATOR".to_string(),
+                role_name: "OPERATOR name".to_string(),
+                hours: 30.0,
+                stage_hours: 10.0,
+                staffed_hours: 10.0,
+                concurrent_demand: 3.0,
+                available_headcount: Some(2),
+            }]
+        );
+        assert_eq!(demands.len(), 2);
+    }
+
+    #[test]
+    fn a_shift_shorter_than_the_stage_concentrates_its_demand() {
+        let roles = HashMap::from([("OPERATOR".to_string(), role("OPERATOR", Some(2), Some(8.0)))]);
+        // 30 h of a day and a half, worked in two 8 h shifts.
+        let by_stage = HashMap::from([
+            ("MTHD-CULT".to_string(), stage(36, &[("OPERATOR", 30.0)])),
+            ("MTHD-HARVEST".to_string(), stage(8, &[("OPERATOR", 16.0)])),
+        ]);
+        let demands = labor_feasibility(&by_stage, &roles);
+
+        assert_eq!(demands.iter().map(|d| d.stage_id.as_str()).collect::<Vec<_>>(), ["MTHD-CULT", "MTHD-HARVEST"]);
+        assert_eq!((demands[0].staffed_hours, demands[0].concurrent_demand), (16.0, 1.875));
+        assert!(!demands[0].is_violation());
+        // Exactly the two operators on duty.
+        assert_eq!(demands[1].concurrent_demand, 2.0);
+        assert!(!demands[1].is_violation());
+    }
+}
@@ -0,0 +1,356 @@
+//! Investment appraisal of a process run batch after batch: annual cash flows, NPV, IRR, and
+//! simple payback over the lifespan of its assets, and the prices and scale at which it breaks even.
+
+use super::{
+    campaign::{CampaignModel, CampaignProduct},
+    BillOfMaterials, CogsResult,
+};
+use bioforge_schemas::{asset::Asset, tea_lca::EconomicAssumptions};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// A product of a batch and the price it sells at.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProductSale {
+    pub molecule_name: String,
+    pub grams_per_batch: f64,
+    /// In the currency of the assumptions.
+    pub price_per_gram: f64,
+}
+
+/// The purchase of an asset the batch runs on.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CapitalItem {
+    pub capex: f64,
+    pub lifespan_years: u32,
+}
+
+/// The economics of running a batch repeatedly for the lifespan of its assets.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FinancialModel {
+    /// Hours from the start of a batch to the start of the next, turnaround included.
+    pub batch_cycle_hours: f64,
+    /// Whole batches that fit into the operating hours of a year.
+    pub batches_per_year: u32,
+    /// The batch's COGS excluding asset depreciation, which the CAPEX below stands for.
+    pub cash_cost_per_batch: f64,
+    pub revenue_per_batch: f64,
+    pub sales: Vec<ProductSale>,
+    /// The assets the batch occupied, keyed by asset id. Each is bought at year 0 and bought
+    /// again whenever its lifespan runs out before the horizon.
+    pub capital: BTreeMap<String, CapitalItem>,
+    /// Years of operation: the longest lifespan among the assets, at least one.
+    pub horizon_years: u32,
+    pub discount_rate: f64,
+}
+
+/// The cash flows and returns of a [`FinancialModel`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct FinancialResult {
+    #[serde(flatten)]
+    pub model: FinancialModel,
+    /// Net operating cash flow of a year of batches.
+    pub annual_operating_cash_flow: f64,
+    /// Net cash flow of each year, year 0 holding the initial CAPEX.
+    pub cash_flows: Vec<f64>,
+    pub npv: f64,
+    /// The discount rate at which the NPV is zero, or `None` if the cash flows have none.
+    pub irr: Option<f64>,
+    /// Years until the operating cash flows repay the initial CAPEX, or `None` if they never do
+    /// within the horizon.
+    pub payback_years: Option<f64>,
+    /// The break-even prices and scale of the campaign, if one was modeled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub break_even: Option<BreakEven>,
+}
+
+/// Where the revenue of a year of batches covers its costs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BreakEven {
+    /// Costs of the year that do not depend on the number of batches.
+    pub fixed_annual_costs: f64,
+    pub variable_cost_per_batch: f64,
+    /// Revenue of a batch at the given prices.
+    pub revenue_per_batch: f64,
+    pub batches_per_year: u32,
+    /// For each product, keyed by molecule name, the price per gram at which the campaign breaks
+    /// even with the other products sold at their given prices, or `None` if the campaign makes
+    /// none of the product. Negative when the other products alone more than cover the costs.
+    pub break_even_prices: BTreeMap<String, Option<f64>>,
+    /// Batches per year at which the given prices break even, or `None` if a batch earns no more
+    /// than it costs. Fractional; a real campaign needs the next whole batch.
+    pub break_even_batches_per_year: Option<f64>,
+    /// Grams of each product a year at `break_even_batches_per_year`, keyed by molecule name.
+    pub minimum_annual_output_g: BTreeMap<String, f64>,
+    /// Whether `break_even_batches_per_year` fits into the operating hours of a year.
+    pub within_capacity: bool,
+}
+
+impl FinancialModel {
+    /// Builds the model of repeating the batch described by `bom` and `cogs`. The assets are
+    /// those the batch occupied, with the `capex` and lifespan of their
+    /// `techno_economic_and_lca_profile`.
+    pub fn new(
+        bom: &BillOfMaterials,
+        cogs: &CogsResult,
+        sales: Vec<ProductSale>,
+        assets: &HashMap<String, Asset>,
+        assumptions: &EconomicAssumptions,
+    ) -> Self {
+        let batch_cycle_hours = bom.total_ticks as f64 + assumptions.batch_turnaround_hours;
+        let batches_per_year = if batch_cycle_hours > 0.0 {
+            (assumptions.operating_hours_per_year / batch_cycle_hours).floor() as u32
+        } else {
+            0
+        };
+
+        let capital: BTreeMap<String, CapitalItem> = bom
+            .asset_ticks()
+            .keys()
+            .filter_map(|asset_id| {
+                let tea = assets.get(asset_id)?.techno_economic_and_lca_profile.as_ref()?;
+                let capex = tea.lifecycle_stages.manufacturing_and_acquisition.costs.iter().find(|c| c.cost_type == "capex")?;
+                let lifespan_years = tea.expected_lifespan.as_ref().map_or(1, |l| l.value).max(1) as u32;
+                Some((asset_id.clone(), CapitalItem { capex: capex.value_usd, lifespan_years }))
+            })
+            .collect();
+        let horizon_years = capital.values().map(|item| item.lifespan_years).max().unwrap_or(1);
+
+        FinancialModel {
+            batch_cycle_hours,
+            batches_per_year,
+            cash_cost_per_batch: cogs.total_cogs - cogs.asset_depreciation_costs,
+            revenue_per_batch: sales.iter().map(|s| s.grams_per_batch * s.price_per_gram).sum(),
+            sales,
+            capital,
+            horizon_years,
+            discount_rate: assumptions.discount_rate,
+        }
+    }
+
+    /// Computes the yearly cash flows and the NPV, IRR, and payback they yield.
+    pub fn evaluate(&self) -> FinancialResult {
+        let annual_operating_cash_flow =
+            self.batches_per_year as f64 * (self.revenue_per_batch - self.cash_cost_per_batch);
+        let initial_capex: f64 = self.capital.values().map(|item| item.capex).sum();
+
+        let cash_flows: Vec<f64> = (0..=self.horizon_years)
+            .map(|year| {
+                if year == 0 {
+                    return -initial_capex;
+                }
+                let replacements: f64 = self
+                    .capital
+                    .values()
+                    .filter(|item| year % item.lifespan_years == 0 && year < self.horizon_years)
+                    .map(|item| item.capex)
+                    .sum();
+                annual_operating_cash_flow - replacements
+            })
+            .collect();
+
+        let payback_years = (annual_operating_cash_flow > 0.0)
+            .then(|| initial_capex / annual_operating_cash_flow)
+            .filter(|years| *years <= self.horizon_years as f64);
+
+        FinancialResult {
+            model: self.clone(),
+            annual_operating_cash_flow,
+            npv: npv(&cash_flows, self.discount_rate),
+            irr: irr(&cash_flows),
+            payback_years,
+            cash_flows,
+            break_even: None,
+        }
+    }
+}
+
+/// Solves `campaign` for break-even at `prices` per gram, keyed by molecule name; products
+/// without a price earn nothing.
+///
+/// With fixed annual costs `F`, a variable cost per batch `V`, `n` batches a year, and `g_i` grams
+/// of product `i` per batch sold at `p_i`, the campaign breaks even when `n·Σ g_i·p_i = F + n·V`.
+/// The break-even price of a product solves this for its `p_i`, and the break-even scale for `n`.
+pub fn breakeven(campaign: &CampaignModel, prices: &HashMap<String, f64>) -> BreakEven {
+    let fixed_annual_costs = campaign.fixed_annual_costs();
+    let variable_cost_per_batch = campaign.variable_cost_per_batch();
+    let batches = campaign.batches_per_year as f64;
+    let revenue = |product: &CampaignProduct| {
+        product.grams_per_batch * prices.get(&product.molecule_name).copied().unwrap_or(0.0)
+    };
+    let revenue_per_batch: f64 = campaign.products.iter().map(revenue).sum();
+
+    let break_even_prices = campaign
+        .products
+        .iter()
+        .map(|product| {
+            let price = (batches > 0.0 && product.grams_per_batch > 0.0).then(|| {
+                let other_revenue = revenue_per_batch - revenue(product);
+                (fixed_annual_costs / batches + variable_cost_per_batch - other_revenue) / product.grams_per_batch
+            });
+            (product.molecule_name.clone(), price)
+        })
+        .collect();
+
+    let margin_per_batch = revenue_per_batch - variable_cost_per_batch;
+    let break_even_batches_per_year = (margin_per_batch > 0.0).then(|| fixed_annual_costs / margin_per_batch);
+    let minimum_annual_output_g = break_even_batches_per_year
+        .map(|n| campaign.products.iter().map(|p| (p.molecule_name.clone(), p.grams_per_batch * n)).collect())
+        .unwrap_or_default();
+
+    BreakEven {
+        fixed_annual_costs,
+        variable_cost_per_batch,
+        revenue_per_batch,
+        batches_per_year: campaign.batches_per_year,
+        break_even_prices,
+        break_even_batches_per_year,
+        minimum_annual_output_g,
+        within_capacity: break_even_batches_per_year.is_some_and(|n| n <= campaign.capacity_batches_per_year as f64),
+    }
+}
+
+/// The net present value of yearly `cash_flows`, the first undiscounted.
+pub fn npv(cash_flows: &[f64], rate: f64) -> f64 {
+    cash_flows.iter().enumerate().map(|(year, flow)| flow / (1.0 + rate).powi(year as i32)).sum()
+}
+
+/// The rate between -99% and 1000% at which the NPV of `cash_flows` is zero, found by bisection,
+/// or `None` if the NPV does not change sign over that range.
+pub fn irr(cash_flows: &[f64]) -> Option<f64> {
+    let (mut low, mut high) = (-0.99, 10.0);
+    let (npv_low, npv_high) = (npv(cash_flows, low), npv(cash_flows, high));
+    if npv_low == 0.0 {
+        return Some(low);
+    }
+    if npv_low.signum() == npv_high.signum() {
+        return None;
+    }
+    for _ in 0..200 {
+        let mid = (low + high) / 2.0;
+        let npv_mid = npv(cash_flows, mid);
+        if npv_mid.signum() == npv_low.signum() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+        if high - low < 1e-10 {
+            break;
+        }
+    }
+    Some((low + high) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::campaign::AssetOwnership;
+
+    /// Ten batches a year, each earning 1,500 and costing 500 in cash, on a reactor of 15,000
+    /// that lasts the two-year horizon, discounted at 10%.
+    fn two_year_model() -> FinancialModel {
+        FinancialModel {
+            batch_cycle_hours: 100.0,
+            batches_per_year: 10,
+            cash_cost_per_batch: 500.0,
+            revenue_per_batch: 1500.0,
+            sales: vec![],
+            capital: BTreeMap::from([("REACTOR".to_string(), CapitalItem { capex: 15_000.0, lifespan_years: 2 })]),
+            horizon_years: 2,
+            discount_rate: 0.1,
+        }
+    }
+
+    #[test]
+    fn npv_irr_and_payback_match_a_hand_computed_two_year_case() {
+        let result = two_year_model().evaluate();
+
+        assert_eq!(result.annual_operating_cash_flow, 10_000.0);
+        assert_eq!(result.cash_flows, [-15_000.0, 10_000.0, 10_000.0]);
+        // -15,000 + 10,000 / 1.1 + 10,000 / 1.21
+        assert!((result.npv - 2_355.371_900_8).abs() < 1e-6, "{}", result.npv);
+        // 10,000·x² + 10,000·x = 15,000 with x = 1 / (1 + r), so x = (√7 - 1) / 2.
+        let irr = 2.0 / (7f64.sqrt() - 1.0) - 1.0;
+        assert!((result.irr.unwrap() - irr).abs() < 1e-8, "{:?}", result.irr);
+        assert_eq!(result.payback_years, Some(1.5));
+    }
+
+    #[test]
+    fn an_asset_that_wears_out_is_bought_again_within_the_horizon() {
+        let mut model = two_year_model();
+        model.capital.insert("PUMP".to_string(), CapitalItem { capex: 1_000.0, lifespan_years: 1 });
+        let result = model.evaluate();
+
+        // The pump is replaced after year 1, but not at the end of the horizon.
+        assert_eq!(result.cash_flows, [-16_000.0, 9_000.0, 10_000.0]);
+        // -16,000 + 9,000 / 1.1 + 10,000 / 1.21
+        assert!((result.npv - 446.280_991_7).abs() < 1e-6, "{}", result.npv);
+        assert_eq!(result.payback_years, Some(1.6));
+    }
+
+    /// Twenty batches a year of 10 g of lutein and 5 g of astaxanthin, each costing 500 on top of
+    /// 12,000 a year of reactor ownership and salaried labor.
+    fn coproduct_campaign() -> CampaignModel {
+        CampaignModel {
+            batch_hours: 48.0,
+            turnaround_hours: 24.0,
+            batches_per_year: 20,
+            capacity_batches_per_year: 121,
+            operating_hours_per_year: 8760.0,
+            products: vec![
+                CampaignProduct { molecule_name: "Lutein".to_string(), grams_per_batch: 10.0 },
+                CampaignProduct { molecule_name: "Astaxanthin".to_string(), grams_per_batch: 5.0 },
+            ],
+            batch_cogs: CogsResult {
+                total_cogs: 700.0,
+                asset_depreciation_costs: 150.0,
+                maintenance_costs: 50.0,
+                ..Default::default()
+            },
+            batch_lca: Default::default(),
+            asset_hours_per_batch: BTreeMap::from([("REACTOR".to_string(), 48.0)]),
+            assets: BTreeMap::from([(
+                "REACTOR".to_string(),
+                AssetOwnership { annual_depreciation: 9_000.0, annual_maintenance: 1_000.0 },
+            )]),
+            fixed_labor_costs_per_year: 2_000.0,
+        }
+    }
+
+    fn prices(entries: &[(&str, f64)]) -> HashMap<String, f64> {
+        entries.iter().map(|(name, price)| (name.to_string(), *price)).collect()
+    }
+
+    #[test]
+    fn each_break_even_price_solves_for_its_product_with_the_other_price_fixed() {
+        let break_even = breakeven(&coproduct_campaign(), &prices(&[("Lutein", 30.0), ("Astaxanthin", 20.0)]));
+        assert_eq!((break_even.fixed_annual_costs, break_even.variable_cost_per_batch), (12_000.0, 500.0));
+        // 12,000 / 20 + 500 = 1,100 a batch: lutein covers what 100 of astaxanthin leave, and
+        // astaxanthin what 300 of lutein leave.
+        assert_eq!(break_even.break_even_prices["Lutein"], Some(100.0));
+        assert_eq!(break_even.break_even_prices["Astaxanthin"], Some(160.0));
+
+        // Astaxanthin alone at 250/g more than covers the costs.
+        let break_even = breakeven(&coproduct_campaign(), &prices(&[("Astaxanthin", 250.0)]));
+        assert_eq!(break_even.break_even_prices["Lutein"], Some(-15.0));
+    }
+
+    #[test]
+    fn the_break_even_scale_is_the_fixed_costs_over_the_margin_of_a_batch() {
+        // 1,000 + 100 a batch against 500 of variable cost covers 12,000 in 20 batches.
+        let break_even = breakeven(&coproduct_campaign(), &prices(&[("Lutein", 100.0), ("Astaxanthin", 20.0)]));
+        assert_eq!(break_even.revenue_per_batch, 1_100.0);
+        assert_eq!(break_even.break_even_batches_per_year, Some(20.0));
+        assert_eq!(break_even.minimum_annual_output_g, BTreeMap::from([("Lutein".to_string(), 200.0), ("Astaxanthin".to_string(), 100.0)]));
+        assert!(break_even.within_capacity);
+
+        // Lutein alone at 52.5/g clears 25 a batch, which takes more batches than fit in a year;
+        // at 50/g it clears nothing.
+        let break_even = breakeven(&coproduct_campaign(), &prices(&[("Lutein", 52.5)]));
+        assert_eq!(break_even.break_even_batches_per_year, Some(480.0));
+        assert!(!break_even.within_capacity);
+
+        let break_even = breakeven(&coproduct_campaign(), &prices(&[("Lutein", 50.0)]));
+        assert_eq!((break_even.break_even_batches_per_year, break_even.minimum_annual_output_g.len()), (None, 0));
+    }
+}
@@ -0,0 +1,71 @@
+//! The manifest a run writes into its directory as it starts: the resolved request, the hash of
+//! every knowledge-base file, and the versions and settings it ran with, so that a result can be
+//! traced back to exactly what produced it.
+
+use crate::error::BioforgeError;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// The file, in the run directory, that [`RunManifest`] is written to.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// What a run was made from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunManifest {
+    /// The request as the run resolved it, defaults filled in.
+    pub request: serde_json::Value,
+    /// Where the request was read from, such as `--request path/to/request.yaml` or standard
+    /// input.
+    pub request_source: String,
+    /// The SHA-256 of each knowledge-base file, in hex, keyed by its path relative to the
+    /// knowledge-base directory, or by the overlay's path joined to it for an overlay's files.
+    pub knowledge_base_files: BTreeMap<String, String>,
+    /// The overlays loaded on top of the knowledge base, in order.
+    #[serde(default)]
+    pub knowledge_base_overlays: Vec<String>,
+    /// The overlay that supplied each entity it replaced or added, keyed by `<directory>/<id>`.
+    #[serde(default)]
+    pub knowledge_base_overrides: BTreeMap<String, String>,
+    /// The version of each bioforge crate the run was built from, keyed by crate name.
+    pub crate_versions: BTreeMap<String, String>,
+    /// The seed of the run's random draws, or `None` if it draws none.
+    pub seed: Option<u64>,
+    /// The hours each simulation tick stands for.
+    pub time_step_hr: f64,
+    /// The start of the run, as an RFC 3339 time.
+    pub started_at: String,
+    /// `git describe` of the source tree the binary was built from, if it was built in one.
+    pub git_describe: Option<String>,
+}
+
+impl RunManifest {
+    /// Writes the manifest to [`MANIFEST_FILE`] in `run_dir`.
+    pub fn write(&self, run_dir: &str) -> Result<(), BioforgeError> {
+        let path = Path::new(run_dir).join(MANIFEST_FILE);
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| BioforgeError::FileIO(path.to_string_lossy().to_string(), e))
+    }
+
+    /// Reads the manifest of the run in `run_dir`, or `None` if it has none, as runs made before
+    /// manifests were written do not.
+    pub fn read(run_dir: &str) -> Result<Option<Self>, BioforgeError> {
+        let path = Path::new(run_dir).join(MANIFEST_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let contents =
+            fs::read_to_string(&path).map_err(|e| BioforgeError::FileIO(path.to_string_lossy().to_string(), e))?;
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    /// The knowledge-base files of this run that differ from `files`, hashes keyed by relative
+    /// path like [`RunManifest::knowledge_base_files`]: changed, added, or removed.
+    pub fn changed_knowledge_base_files(&self, files: &BTreeMap<String, String>) -> Vec<String> {
+        let paths: BTreeSet<&String> = self.knowledge_base_files.keys().chain(files.keys()).collect();
+        paths.into_iter().filter(|path| self.knowledge_base_files.get(*path) != files.get(*path)).cloned().collect()
+    }
+}
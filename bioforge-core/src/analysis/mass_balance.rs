@@ -0,0 +1,267 @@
+//! Elemental mass balance of the organisms of a run: does the carbon (and nitrogen) they took up
+//! from the media reappear in their biomass and the products they secreted?
+
+use super::{read_log, LoggedVessel, MaterialResolver};
+use crate::{error::BioforgeError, logger::{series_column, LogRecord}, simulation::state::SimulationEvent};
+use bioforge_schemas::{
+    environment::DissolvedComponent,
+    material::Material,
+    organism::{ElementalComposition, Organism},
+    organism_state::IndividualOrganismState,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// The elements balanced, by symbol and by the name of their `<name>_content` material
+/// specification, given in % w/w.
+pub const ELEMENTS: [(&str, &str); 2] = [("C", "carbon"), ("N", "nitrogen")];
+
+/// The balance of one element over a run, in grams of the element.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementBalance {
+    pub element: String,
+    /// Taken up from the media by the organisms.
+    pub uptake_g: f64,
+    /// Net gain of the organisms' biomass, including biomass later removed from the process.
+    pub biomass_g: f64,
+    /// Net gain of the molecules the organisms secrete, including amounts later consumed or removed.
+    pub secreted_g: f64,
+    /// `uptake_g` less everything it was found in. Gas exchange is not simulated, so carbon
+    /// respired as CO₂ shows up here.
+    pub closure_error_g: f64,
+    /// `closure_error_g` as a fraction of `uptake_g`, or `None` if nothing was taken up.
+    pub relative_error: Option<f64>,
+}
+
+/// How well the elements taken up by a run's organisms are accounted for.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MassBalanceReport {
+    pub elements: Vec<ElementBalance>,
+    /// Molecules taken up or secreted that match no material, and so contribute no elements.
+    pub unmatched_molecules: Vec<String>,
+}
+
+impl MassBalanceReport {
+    /// The balances whose relative closure error exceeds `tolerance` either way.
+    pub fn exceeding(&self, tolerance: f64) -> impl Iterator<Item = &ElementBalance> {
+        self.elements.iter().filter(move |b| b.relative_error.is_some_and(|e| e.abs() > tolerance))
+    }
+}
+
+/// Balances the elements of [`ELEMENTS`] over the run logged at `log_path`; see
+/// [`mass_balance_from_records`].
+pub fn mass_balance(
+    log_path: &str,
+    organisms: &[Organism],
+    materials: &HashMap<String, Material>,
+) -> Result<MassBalanceReport, BioforgeError> {
+    mass_balance_from_records(&read_log(log_path)?, organisms, materials)
+}
+
+/// Like [`mass_balance`], from records already in memory.
+///
+/// Uptake is integrated from the `MaterialConsumed` events of the organisms, biomass from the
+/// first and last rows together with biomass added and removed, and secretion from the media
+/// contents of the first and last rows together with every event that added, consumed, or removed
+/// a secreted molecule. Molecules are converted to elements by the `<element>_content`
+/// specifications of their material, and biomass by the organism's elemental composition.
+pub fn mass_balance_from_records(
+    records: &[LogRecord],
+    organisms: &[Organism],
+    materials: &HashMap<String, Material>,
+) -> Result<MassBalanceReport, BioforgeError> {
+    let Some(flows) = Flows::of(records, organisms)? else {
+        return Ok(MassBalanceReport::default());
+    };
+    let resolver = MaterialResolver::new(materials);
+
+    let mut unmatched = BTreeSet::new();
+    let mut elements = Vec::new();
+    for (symbol, name) in ELEMENTS {
+        let mut in_molecules = |amounts: &BTreeMap<String, f64>| -> f64 {
+            let mut total = 0.0;
+            for (id, grams) in amounts {
+                match resolver.resolve(id) {
+                    Some(material) => total += grams * element_content(material, name),
+                    None => {
+                        unmatched.insert(id.clone());
+                    }
+                }
+            }
+            total
+        };
+        let uptake_g = in_molecules(&flows.uptake);
+        let secreted_g = in_molecules(&flows.secreted);
+        let biomass_g = flows.biomass_element_g(organisms, name);
+        let closure_error_g = uptake_g - biomass_g - secreted_g;
+        elements.push(ElementBalance {
+            element: symbol.to_string(),
+            uptake_g,
+            biomass_g,
+            secreted_g,
+            closure_error_g,
+            relative_error: (uptake_g > 0.0).then(|| closure_error_g / uptake_g),
+        });
+    }
+    Ok(MassBalanceReport { elements, unmatched_molecules: unmatched.into_iter().collect() })
+}
+
+/// The grams moved by the organisms of a run, by molecule or organism id.
+pub(super) struct Flows {
+    /// Taken up from the media.
+    pub(super) uptake: BTreeMap<String, f64>,
+    /// Net gain of each organism's biomass, including biomass later removed from the process.
+    pub(super) grown: BTreeMap<String, f64>,
+    /// Net gain of each molecule the organisms secrete, including amounts later consumed or
+    /// removed. Molecules with no net gain are left out.
+    pub(super) secreted: BTreeMap<String, f64>,
+}
+
+impl Flows {
+    /// Integrates the flows of `organisms` over `records`, or `None` if there are no records.
+    ///
+    /// Uptake is integrated from the `MaterialConsumed` events of the organisms, biomass from the
+    /// first and last rows together with biomass added and removed, and secretion from the media
+    /// contents of the first and last rows together with every event that added, consumed, or
+    /// removed a secreted molecule.
+    pub(super) fn of(records: &[LogRecord], organisms: &[Organism]) -> Result<Option<Self>, BioforgeError> {
+        let (Some(first), Some(last)) = (records.first(), records.last()) else {
+            return Ok(None);
+        };
+        let organism_ids: Vec<&str> = organisms.iter().map(|o| o.organism_id.as_str()).collect();
+        let secreted_ids: BTreeSet<&str> = organisms
+            .iter()
+            .flat_map(|o| o.dynamic_parameters.metabolic_exchange.media_secretion.iter())
+            .map(|s| s.molecule_id.as_str())
+            .collect();
+
+        // Grams taken up by the organisms, and the net flow of each id into the process by events.
+        let mut uptake: BTreeMap<String, f64> = BTreeMap::new();
+        let mut net_added: BTreeMap<String, f64> = BTreeMap::new();
+        for record in records {
+            let events: Vec<SimulationEvent> = serde_json::from_str(&record.events_json)?;
+            for event in events {
+                match event {
+                    SimulationEvent::MaterialConsumed { id, amount, consumer_id } => {
+                        if consumer_id.is_some() {
+                            *uptake.entry(id.clone()).or_insert(0.0) += amount;
+                        }
+                        *net_added.entry(id).or_insert(0.0) -= amount;
+                    }
+                    SimulationEvent::MaterialAdded { id, amount } => *net_added.entry(id).or_insert(0.0) += amount,
+                    SimulationEvent::MaterialRemoved { id, amount } => *net_added.entry(id).or_insert(0.0) -= amount,
+                    _ => {}
+                }
+            }
+        }
+        let added = |id: &str| net_added.get(id).copied().unwrap_or(0.0);
+
+        let start = Contents::of(first, &organism_ids, &secreted_ids)?;
+        let end = Contents::of(last, &organism_ids, &secreted_ids)?;
+        // Biomass removed counts as grown, and biomass added as not.
+        let grown = organism_ids
+            .iter()
+            .map(|id| (id.to_string(), end.biomass_g(id) - start.biomass_g(id) - added(id)))
+            .collect();
+        // Secreted molecules consumed or removed count as secreted, and those added as not.
+        let secreted = secreted_ids
+            .iter()
+            .map(|id| (id.to_string(), end.media_g(id) - start.media_g(id) - added(id)))
+            .filter(|(_, grams)| *grams != 0.0)
+            .collect();
+        Ok(Some(Flows { uptake, grown, secreted }))
+    }
+
+    /// Grams of the element named `name` in the biomass the organisms grew, by their elemental
+    /// composition.
+    pub(super) fn biomass_element_g(&self, organisms: &[Organism], name: &str) -> f64 {
+        organisms
+            .iter()
+            .map(|o| {
+                self.grown.get(&o.organism_id).copied().unwrap_or(0.0)
+                    * biomass_content(&o.static_properties.elemental_composition, name)
+            })
+            .sum()
+    }
+}
+
+/// The mass fraction of the element named `name` in `material`, from its `<name>_content`
+/// specification. Materials without one are taken to hold none of the element.
+pub(super) fn element_content(material: &Material, name: &str) -> f64 {
+    let key = format!("{}_content", name);
+    material.specifications.iter().find(|s| s.key == key).map_or(0.0, |s| s.value / 100.0)
+}
+
+/// The mass fraction of the element named `name` in biomass of `composition`.
+fn biomass_content(composition: &ElementalComposition, name: &str) -> f64 {
+    match name {
+        "carbon" => composition.carbon,
+        "hydrogen" => composition.hydrogen,
+        "oxygen" => composition.oxygen,
+        "nitrogen" => composition.nitrogen,
+        "phosphorus" => composition.phosphorus,
+        "sulfur" => composition.sulfur,
+        _ => 0.0,
+    }
+}
+
+/// Grams of each organism's biomass and of each secreted molecule in every vessel at one row.
+pub(super) struct Contents {
+    biomass: BTreeMap<String, f64>,
+    media: BTreeMap<String, f64>,
+}
+
+impl Contents {
+    pub(super) fn of(
+        record: &LogRecord,
+        organism_ids: &[&str],
+        molecule_ids: &BTreeSet<&str>,
+    ) -> Result<Self, BioforgeError> {
+        let mut contents = Contents { biomass: BTreeMap::new(), media: BTreeMap::new() };
+        if !record.vessels_json.is_empty() {
+            for vessel in serde_json::from_str::<BTreeMap<String, LoggedVessel>>(&record.vessels_json)?.into_values() {
+                contents.add_organisms(&vessel.organisms.states);
+                contents.add_media(&vessel.media.composition.dissolved_components, vessel.media.volume.value);
+            }
+        } else if !record.organisms_json.is_empty() {
+            // Logs written before multi-vessel support describe the active vessel only.
+            let organisms: BTreeMap<String, IndividualOrganismState> = serde_json::from_str(&record.organisms_json)?;
+            let components: Vec<DissolvedComponent> = serde_json::from_str(&record.dissolved_components_json)?;
+            contents.add_organisms(&organisms);
+            contents.add_media(&components, record.media_volume_l);
+        } else {
+            // Wide logs carry the active vessel as series.
+            for id in organism_ids {
+                if let Some(grams) = record.series.get(&series_column("biomass", id)) {
+                    contents.biomass.insert(id.to_string(), *grams);
+                }
+            }
+            for id in molecule_ids {
+                if let Some(concentration) = record.series.get(&series_column("conc", id)) {
+                    contents.media.insert(id.to_string(), concentration * record.media_volume_l);
+                }
+            }
+        }
+        Ok(contents)
+    }
+
+    fn add_organisms(&mut self, organisms: &BTreeMap<String, IndividualOrganismState>) {
+        for (id, state) in organisms {
+            *self.biomass.entry(id.clone()).or_insert(0.0) += state.biomass.value;
+        }
+    }
+
+    fn add_media(&mut self, components: &[DissolvedComponent], volume_l: f64) {
+        for component in components {
+            *self.media.entry(component.molecule_id.clone()).or_insert(0.0) += component.concentration.value * volume_l;
+        }
+    }
+
+    pub(super) fn biomass_g(&self, id: &str) -> f64 {
+        self.biomass.get(id).copied().unwrap_or(0.0)
+    }
+
+    fn media_g(&self, id: &str) -> f64 {
+        self.media.get(id).copied().unwrap_or(0.0)
+    }
+}
@@ -0,0 +1,370 @@
+//! Turns the dissolved components of a media into a recipe of purchasable materials: powders
+//! weighed out by mass and stock solutions measured out by volume, as a media is actually made.
+
+use super::BillOfMaterials;
+use crate::error::BioforgeError;
+use bioforge_schemas::{
+    environment::{MediaState, Measurement},
+    material::{FormulationComponent, Material, MaterialCategory},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// The purchasable materials, and the amount of each, that make up a media.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MediaRecipe {
+    pub volume: Measurement<f64>,
+    /// Sorted by material id.
+    pub ingredients: Vec<RecipeIngredient>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipeIngredient {
+    pub material_id: String,
+    pub material_name: String,
+    /// In the material's own unit, e.g. kg of a powder or L of a stock solution.
+    pub amount: Measurement<f64>,
+    /// The molecule ids of the dissolved components the ingredient supplies.
+    pub supplies: Vec<String>,
+}
+
+/// A material that can supply a dissolved component.
+struct Supplier<'a> {
+    material: &'a Material,
+    /// Grams of the component in one unit of the material.
+    grams_per_unit: f64,
+    /// The highest concentration, in g/L, the material can bring the component to: the
+    /// solubility of a solid, or the concentration of a stock solution.
+    max_concentration: f64,
+}
+
+impl MediaRecipe {
+    /// Selects, for each dissolved component of `media`, the purchasable material of `materials`
+    /// that supplies it most cheaply, and the amount of it that brings the media's volume to the
+    /// component's concentration.
+    ///
+    /// A material supplies a component that it is, by ChEBI id, or that its formulation lists,
+    /// by ChEBI id or by the material id of a material that is. A material that supplies several
+    /// components is measured out for the one that needs the most of it.
+    pub fn resolve(media: &MediaState, materials: &HashMap<String, Material>) -> Result<Self, BioforgeError> {
+        let sorted = purchasable(materials);
+        let volume_l = media.volume.value;
+        let mut ingredients: BTreeMap<&str, RecipeIngredient> = BTreeMap::new();
+        for component in &media.composition.dissolved_components {
+            let concentration = component.concentration.value;
+            if concentration <= 0.0 {
+                continue;
+            }
+            let suppliers: Vec<Supplier> =
+                sorted.iter().filter_map(|m| supplier(m, &component.molecule_id, materials)).collect();
+            if suppliers.is_empty() {
+                return Err(BioforgeError::UnsuppliedMediaComponent {
+                    molecule_id: component.molecule_id.clone(),
+                    molecule_name: component.molecule_name.clone(),
+                });
+            }
+            let best = suppliers
+                .iter()
+                .filter(|s| s.max_concentration >= concentration)
+                .min_by(|a, b| cost_per_gram(a).total_cmp(&cost_per_gram(b)))
+                .ok_or_else(|| {
+                    let most = suppliers.iter().max_by(|a, b| a.max_concentration.total_cmp(&b.max_concentration));
+                    let (material_id, max_concentration) =
+                        most.map_or((String::new(), 0.0), |s| (s.material.material_id.clone(), s.max_concentration));
+                    BioforgeError::InsolubleMediaComponent {
+                        molecule_name: component.molecule_name.clone(),
+                        concentration,
+                        material_id,
+                        max_concentration,
+                    }
+                })?;
+
+            let amount = concentration * volume_l / best.grams_per_unit;
+            let ingredient = ingredients.entry(&best.material.material_id).or_insert_with(|| RecipeIngredient {
+                material_id: best.material.material_id.clone(),
+                material_name: best.material.material_name.clone(),
+                amount: Measurement { value: 0.0, unit: best.material.unit.clone() },
+                supplies: Vec::new(),
+            });
+            ingredient.amount.value = ingredient.amount.value.max(amount);
+            ingredient.supplies.push(component.molecule_id.clone());
+        }
+
+        Ok(Self { volume: media.volume.clone(), ingredients: ingredients.into_values().collect() })
+    }
+
+    /// The bill of materials of the recipe, keyed by material id. Like every BOM quantity, an
+    /// amount is kept in thousandths of the material's unit (grams of a material sold by the kg),
+    /// as [`super::material_cost`] prices it.
+    pub fn bom(&self) -> BillOfMaterials {
+        let mut bom = BillOfMaterials::default();
+        for ingredient in &self.ingredients {
+            *bom.materials_consumed.entry(ingredient.material_id.clone()).or_insert(0.0) +=
+                ingredient.amount.value * 1000.0;
+        }
+        bom
+    }
+}
+
+/// The purchase cost of a gram of `molecule_id` from the purchasable material of `materials`
+/// that supplies it most cheaply, or `None` if none supplies it.
+pub fn supply_cost(molecule_id: &str, materials: &HashMap<String, Material>) -> Option<f64> {
+    purchasable(materials)
+        .into_iter()
+        .filter_map(|m| supplier(m, molecule_id, materials))
+        .map(|s| cost_per_gram(&s))
+        .reduce(f64::min)
+}
+
+/// The purchasable materials of `materials`, sorted by material id.
+fn purchasable(materials: &HashMap<String, Material>) -> Vec<&Material> {
+    let mut sorted: Vec<&Material> = materials
+        .values()
+        .filter(|m| m.material_category == MaterialCategory::PurchasedRawMaterial)
+        .collect();
+    sorted.sort_by(|a, b| a.material_id.cmp(&b.material_id));
+    sorted
+}
+
+/// `material` as a supplier of `molecule_id`, or `None` if it holds none of the molecule or its
+/// amount per unit cannot be worked out.
+fn supplier<'a>(
+    material: &'a Material,
+    molecule_id: &str,
+    materials: &HashMap<String, Material>,
+) -> Option<Supplier<'a>> {
+    match &material.formulation {
+        None => {
+            (chebi_id(material)? == molecule_id).then_some(())?;
+            Some(Supplier {
+                material,
+                grams_per_unit: unit_grams(material)?,
+                max_concentration: specification(material, "solubility").unwrap_or(f64::INFINITY),
+            })
+        }
+        Some(formulation) => {
+            let component = formulation.components.iter().find(|c| {
+                c.component_id == molecule_id
+                    || materials.get(&c.component_id).and_then(chebi_id) == Some(molecule_id)
+            })?;
+            let grams_per_unit = component_grams_per_unit(material, component, molecule_id, materials)?;
+            // A liquid stock dilutes the media it is added to, so it cannot bring the media past
+            // its own concentration.
+            let max_concentration = unit_liters(material)
+                .map_or_else(|| specification(material, "solubility").unwrap_or(f64::INFINITY), |l| grams_per_unit / l);
+            Some(Supplier { material, grams_per_unit, max_concentration })
+        }
+    }
+}
+
+/// Grams of the formulation `component`, the molecule `molecule_id`, in one unit of `material`.
+fn component_grams_per_unit(
+    material: &Material,
+    component: &FormulationComponent,
+    molecule_id: &str,
+    materials: &HashMap<String, Material>,
+) -> Option<f64> {
+    match component.unit.as_str() {
+        "%_by_weight" => Some(unit_grams(material)? * component.value / 100.0),
+        "g/L" => Some(unit_liters(material)? * component.value),
+        "mol/L" | "M" => Some(unit_liters(material)? * component.value * molecular_weight(component, molecule_id, materials)?),
+        // Moles of the component per mole of a hydrate or salt.
+        "mol/mol" => Some(
+            unit_grams(material)? * component.value * molecular_weight(component, molecule_id, materials)?
+                / specification(material, "molecular_weight")?,
+        ),
+        _ => None,
+    }
+}
+
+/// The molecular weight of a formulation component: that of the material it names, or else that
+/// of a pure material of the molecule.
+fn molecular_weight(component: &FormulationComponent, molecule_id: &str, materials: &HashMap<String, Material>) -> Option<f64> {
+    materials.get(&component.component_id).and_then(|m| specification(m, "molecular_weight")).or_else(|| {
+        materials
+            .values()
+            .filter(|m| m.formulation.is_none() && chebi_id(m) == Some(molecule_id))
+            .find_map(|m| specification(m, "molecular_weight"))
+    })
+}
+
+/// Grams in one unit of `material`: its mass, or its volume by its density in kg/L.
+fn unit_grams(material: &Material) -> Option<f64> {
+    match material.unit.as_str() {
+        "g" => Some(1.0),
+        "kg" => Some(1000.0),
+        _ => Some(unit_liters(material)? * specification(material, "density")? * 1000.0),
+    }
+}
+
+/// Liters in one unit of `material`, or `None` if it is not sold by volume.
+fn unit_liters(material: &Material) -> Option<f64> {
+    match material.unit.as_str() {
+        "L" => Some(1.0),
+        "mL" => Some(0.001),
+        _ => None,
+    }
+}
+
+fn specification(material: &Material, key: &str) -> Option<f64> {
+    material.specifications.iter().find(|s| s.key == key).map(|s| s.value)
+}
+
+fn chebi_id(material: &Material) -> Option<&str> {
+    material.metadata.identifiers.as_ref()?.chebi_id.as_deref()
+}
+
+/// The purchase cost of a gram of the component a supplier supplies.
+fn cost_per_gram(supplier: &Supplier) -> f64 {
+    let cost_per_unit = supplier
+        .material
+        .techno_economic_and_lca_profile
+        .lifecycle_stages
+        .manufacturing_and_acquisition
+        .costs
+        .first()
+        .map_or(0.0, |c| c.value_usd);
+    cost_per_unit / supplier.grams_per_unit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bioforge_schemas::environment::{DissolvedComponent, MediaComposition};
+
+    const SODIUM_HYDROXIDE_ID: &str = "CHEBI:32145";
+    const GLUCOSE_ID: &str = "CHEBI:17234";
+
+    /// A material sold by `unit` at `cost` a unit, with `chebi_id`, `specifications` as
+    /// `key: value` pairs, and the YAML `formulation`, which is purchasable if `purchased`.
+    fn material(id: &str, unit: &str, cost: f64, chebi_id: Option<&str>, specifications: &[(&str, f64)], formulation: Option<&str>, purchased: bool) -> Material {
+        let mut material: Material = serde_yaml::from_str(&format!(
+            r#"
+material_id: {id}
+material_name: {id}
+material_class: Chemical
+material_subtype: Reagent
+material_category: {category}
+unit: {unit}
+metadata:
+  process_role: Test
+  vendor: null
+  part_number: null
+  notes: null
+  identifiers: null
+specifications: []
+formulation: null
+techno_economic_and_lca_profile:
+  lifecycle_stages:
+    manufacturing_and_acquisition:
+      costs:
+      - cost_type: purchase_cost
+        value_usd: {cost}
+      impacts: []
+    use_and_operation:
+      costs: []
+      impacts: []
+    maintenance:
+      costs: []
+      impacts: []
+    end_of_life:
+      costs: []
+      impacts: []
+"#,
+            category = if purchased { "PurchasedRawMaterial" } else { "InternalSimulationState" },
+        ))
+        .unwrap();
+        material.metadata.identifiers = chebi_id.map(|id| serde_yaml::from_str(&format!("chebi_id: '{id}'")).unwrap());
+        material.specifications = specifications
+            .iter()
+            .map(|(key, value)| serde_yaml::from_str(&format!("key: {key}\nvalue: {value}\nunit: null")).unwrap())
+            .collect();
+        material.formulation = formulation.map(|yaml| serde_yaml::from_str(yaml).unwrap());
+        material
+    }
+
+    /// A 1 M sodium hydroxide stock sold by the liter, and glucose sold as its monohydrate by the
+    /// kg, with the pure substances they are made of, which are not for sale.
+    fn materials() -> HashMap<String, Material> {
+        [
+            material("NAOH", "kg", 0.0, Some(SODIUM_HYDROXIDE_ID), &[("molecular_weight", 39.997)], None, false),
+            material(
+                "NAOH-1M",
+                "L",
+                4.0,
+                None,
+                &[],
+                Some("formulation_type: Solution\nsolvent_id: WATER\ncomponents:\n- component_id: NAOH\n  value: 1.0\n  unit: M"),
+                true,
+            ),
+            material("GLUCOSE", "kg", 0.0, Some(GLUCOSE_ID), &[("molecular_weight", 180.156)], None, false),
+            material(
+                "GLUCOSE-MONOHYDRATE",
+                "kg",
+                1.0,
+                None,
+                &[("molecular_weight", 198.171), ("solubility", 900.0)],
+                Some("formulation_type: Hydrate\nsolvent_id: null\ncomponents:\n- component_id: GLUCOSE\n  value: 1.0\n  unit: mol/mol"),
+                true,
+            ),
+        ]
+        .into_iter()
+        .map(|m| (m.material_id.clone(), m))
+        .collect()
+    }
+
+    /// 10 L of media holding `components` as (molecule id, g/L).
+    fn media(components: &[(&str, f64)]) -> MediaState {
+        MediaState {
+            volume: Measurement { value: 10.0, unit: "L".to_string() },
+            ph: 7.0,
+            composition: MediaComposition {
+                dissolved_components: components
+                    .iter()
+                    .map(|(id, g_l)| DissolvedComponent {
+                        molecule_id: id.to_string(),
+                        molecule_name: id.to_string(),
+                        concentration: Measurement { value: *g_l, unit: "g/L".to_string() },
+                    })
+                    .collect(),
+                dissolved_gases: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn stocks_are_measured_by_volume_and_hydrates_by_the_anhydrous_mass() {
+        let recipe = MediaRecipe::resolve(&media(&[(SODIUM_HYDROXIDE_ID, 2.0), (GLUCOSE_ID, 20.0)]), &materials()).unwrap();
+        let amounts: Vec<(&str, f64, &str)> =
+            recipe.ingredients.iter().map(|i| (i.material_id.as_str(), i.amount.value, i.amount.unit.as_str())).collect();
+        let [(glucose_id, glucose_kg, glucose_unit), (naoh_id, naoh_l, naoh_unit)] = amounts[..] else { panic!("{:?}", amounts) };
+
+        // 200 g of glucose is 198.171 / 180.156 as much monohydrate.
+        assert_eq!((glucose_id, glucose_unit), ("GLUCOSE-MONOHYDRATE", "kg"));
+        assert!((glucose_kg - 0.2 * 198.171 / 180.156).abs() < 1e-12, "{}", glucose_kg);
+        // 20 g of sodium hydroxide is half a mole: half a liter of the 1 M stock.
+        assert_eq!((naoh_id, naoh_unit), ("NAOH-1M", "L"));
+        assert!((naoh_l - 20.0 / 39.997).abs() < 1e-12, "{}", naoh_l);
+
+        // Kept in thousandths of each unit, as the BOM is priced.
+        let bom = recipe.bom();
+        assert!((bom.materials_consumed["NAOH-1M"] - 1000.0 * naoh_l).abs() < 1e-9);
+        assert!((bom.materials_consumed["GLUCOSE-MONOHYDRATE"] - 1000.0 * glucose_kg).abs() < 1e-9);
+    }
+
+    #[test]
+    fn components_beyond_every_supplier_or_without_one_fail() {
+        // The stock cannot bring the media past its own 39.997 g/L.
+        match MediaRecipe::resolve(&media(&[(SODIUM_HYDROXIDE_ID, 50.0)]), &materials()) {
+            Err(BioforgeError::InsolubleMediaComponent { material_id, max_concentration, .. }) => {
+                assert_eq!(material_id, "NAOH-1M");
+                assert!((max_concentration - 39.997).abs() < 1e-12);
+            }
+            other => panic!("{:?}", other),
+        }
+        assert!(matches!(
+            MediaRecipe::resolve(&media(&[("CHEBI:15377", 1.0)]), &materials()),
+            Err(BioforgeError::UnsuppliedMediaComponent { molecule_id, .. }) if molecule_id == "CHEBI:15377"
+        ));
+    }
+}
@@ -0,0 +1,2453 @@
+pub mod campaign;
+pub mod carbon_balance;
+pub mod comparison;
+pub mod constraints;
+pub mod financial;
+pub mod manifest;
+pub mod mass_balance;
+pub mod media_recipe;
+pub mod monte_carlo;
+pub mod performance;
+pub mod report;
+pub mod sensitivity;
+
+use bioforge_schemas::{
+    asset::Asset,
+    environment::{MediaComposition, MediaState, Measurement},
+    labor::LaborRole,
+    material::{Material},
+    organism_state::{IndividualOrganismState, OrganismState},
+    process::{MaterialTransfer, Method, Process, QcCheck, RequiredMaterial},
+    rule::Rule,
+    tea_lca::EconomicAssumptions,
+};
+use crate::{
+    error::BioforgeError,
+    logger::{
+        event_log_path, is_series_column, log_segments, series_column, tick_timestamp, EventRecord, LogFormat,
+        LogRecord,
+    },
+    simulation::{
+        engine::TIME_STEP_HR,
+        state::{LiveAsset, ResumePoint, SimulationEvent, SimulationState, TickMetrics, Vessel},
+    },
+};
+use chrono::{DateTime, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use flate2::read::GzDecoder;
+use serde_json::value::RawValue;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+/// A record of a JSON Lines log, whose nested fields are real JSON rather than strings.
+///
+/// Nested fields are kept as raw JSON text so they convert to exactly the strings a CSV log of
+/// the same run would hold.
+#[derive(Debug, Deserialize)]
+struct JsonLogRecord {
+    tick: u64,
+    stage_id: String,
+    organisms: Box<RawValue>,
+    media_volume_l: f64,
+    media_ph: f64,
+    dissolved_components: Box<RawValue>,
+    dissolved_gases: Box<RawValue>,
+    asset_states: Box<RawValue>,
+    events: Box<RawValue>,
+    #[serde(default)]
+    vessels: Option<Box<RawValue>>,
+    #[serde(default)]
+    timestamp: Option<String>,
+    #[serde(default)]
+    total_biomass_g: f64,
+    #[serde(default)]
+    growth_rates: Option<Box<RawValue>>,
+    #[serde(default)]
+    uptake_rates: Option<Box<RawValue>>,
+}
+
+impl From<JsonLogRecord> for LogRecord {
+    fn from(entry: JsonLogRecord) -> Self {
+        Self {
+            tick: entry.tick,
+            stage_id: entry.stage_id,
+            organisms_json: entry.organisms.get().to_string(),
+            media_volume_l: entry.media_volume_l,
+            media_ph: entry.media_ph,
+            dissolved_components_json: entry.dissolved_components.get().to_string(),
+            dissolved_gases_json: entry.dissolved_gases.get().to_string(),
+            asset_states_json: entry.asset_states.get().to_string(),
+            events_json: entry.events.get().to_string(),
+            vessels_json: entry.vessels.map_or_else(String::new, |v| v.get().to_string()),
+            timestamp: entry.timestamp,
+            total_biomass_g: entry.total_biomass_g,
+            growth_rates_json: entry.growth_rates.map_or_else(String::new, |v| v.get().to_string()),
+            uptake_rates_json: entry.uptake_rates.map_or_else(String::new, |v| v.get().to_string()),
+            series: BTreeMap::new(),
+        }
+    }
+}
+
+/// Detects the format of a time-series log from its contents: JSON Lines logs start with an
+/// object, CSV logs with a header row.
+pub fn detect_log_format(contents: &str) -> LogFormat {
+    if contents.trim_start().starts_with('{') {
+        LogFormat::JsonLines
+    } else {
+        LogFormat::Csv
+    }
+}
+
+/// Reads every record of a time-series log written in any of the supported formats.
+///
+/// A rotated log is read from its base path, concatenating its segments in order whether or not
+/// they are gzipped. Parquet logs are recognised by their magic bytes and need the `parquet`
+/// feature.
+pub fn read_log(log_path: &str) -> Result<Vec<LogRecord>, BioforgeError> {
+    let segments = log_segments(log_path);
+    if segments.is_empty() {
+        // Report the missing log the way opening it would.
+        return read_log_segment(Path::new(log_path));
+    }
+    let mut records = Vec::new();
+    for segment in segments {
+        records.extend(read_log_segment(&segment)?);
+    }
+    Ok(records)
+}
+
+/// Reads a single log file, decompressing it if it is gzipped.
+fn read_log_segment(path: &Path) -> Result<Vec<LogRecord>, BioforgeError> {
+    let log_path: &str = &path.to_string_lossy();
+    let file_error = |e| BioforgeError::FileIO(log_path.to_string(), e);
+    if path.extension().is_some_and(|extension| extension == "gz") {
+        let mut contents = String::new();
+        fs::File::open(path)
+            .and_then(|file| GzDecoder::new(file).read_to_string(&mut contents))
+            .map_err(file_error)?;
+        return read_text_log(log_path, &contents);
+    }
+
+    let mut magic = [0u8; 4];
+    let magic_len = fs::File::open(log_path).and_then(|mut f| f.read(&mut magic)).map_err(file_error)?;
+    if magic_len == magic.len() && &magic == b"PAR1" {
+        #[cfg(feature = "parquet")]
+        return crate::parquet_log::read_parquet_log(log_path);
+        #[cfg(not(feature = "parquet"))]
+        return Err(BioforgeError::ConfigError(format!(
+            "'{}' is a Parquet log, but bioforge-core was built without the `parquet` feature",
+            log_path
+        )));
+    }
+
+    let contents = fs::read_to_string(log_path).map_err(file_error)?;
+    read_text_log(log_path, &contents)
+}
+
+/// Parses the contents of a CSV or JSON Lines log.
+fn read_text_log(log_path: &str, contents: &str) -> Result<Vec<LogRecord>, BioforgeError> {
+    match detect_log_format(contents) {
+        LogFormat::Csv => read_csv_log(log_path, contents),
+        LogFormat::JsonLines => contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str::<JsonLogRecord>(line).map(LogRecord::from))
+            .collect::<Result<_, _>>()
+            .map_err(BioforgeError::from),
+        #[cfg(feature = "parquet")]
+        LogFormat::Parquet => Err(BioforgeError::ConfigError(format!("'{}' is not a text log", log_path))),
+    }
+}
+
+/// Reads a CSV log in either layout, collecting the numeric columns of a wide log into
+/// [`LogRecord::series`].
+fn read_csv_log(log_path: &str, contents: &str) -> Result<Vec<LogRecord>, BioforgeError> {
+    let csv_error = |e| BioforgeError::CsvError(log_path.to_string(), e);
+    let mut reader = csv::Reader::from_reader(contents.as_bytes());
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let series_columns: Vec<(usize, &str)> =
+        headers.iter().enumerate().filter(|(_, name)| is_series_column(name)).collect();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(csv_error)?;
+            let mut entry: LogRecord = record.deserialize(Some(&headers)).map_err(csv_error)?;
+            for &(i, column) in &series_columns {
+                let Some(cell) = record.get(i).filter(|cell| !cell.is_empty()) else {
+                    continue;
+                };
+                let value = cell.parse().map_err(|_| {
+                    BioforgeError::ConfigError(format!(
+                        "'{}': column '{}' at tick {} is not a number: '{}'",
+                        log_path, column, entry.tick, cell
+                    ))
+                })?;
+                entry.series.insert(column.to_string(), value);
+            }
+            Ok(entry)
+        })
+        .collect()
+}
+
+/// Reads the event log written next to the log at `log_path` (see
+/// `SimulationBuilder::with_event_log`), or returns `None` if the run wrote none.
+pub fn read_event_log(log_path: &str) -> Result<Option<Vec<EventRecord>>, BioforgeError> {
+    let path = event_log_path(log_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let events_path: &str = &path.to_string_lossy();
+    let contents = fs::read_to_string(&path).map_err(|e| BioforgeError::FileIO(events_path.to_string(), e))?;
+    let events = match detect_log_format(&contents) {
+        LogFormat::JsonLines => contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?,
+        _ => csv::Reader::from_reader(contents.as_bytes())
+            .deserialize()
+            .collect::<Result<_, _>>()
+            .map_err(|e| BioforgeError::CsvError(events_path.to_string(), e))?,
+    };
+    Ok(Some(events))
+}
+
+/// How many of the last logged totals are handed back as the biomass history of a resumed run;
+/// matches the window the engine keeps for `BiomassStationary` conditions.
+const RESUME_BIOMASS_HISTORY: usize = 10;
+
+/// The contents of one vessel as written to `vessels_json`.
+#[derive(Debug, Deserialize)]
+struct LoggedVessel {
+    media: MediaState,
+    organisms: OrganismState,
+}
+
+/// The state of one asset as written to `asset_states_json`.
+#[derive(Debug, Deserialize)]
+struct LoggedAssetState {
+    temperature: f64,
+    ph: f64,
+}
+
+/// Rebuilds the position a run had reached from its CSV or JSON Lines log, so that
+/// `SimulationBuilder::with_resume_point` can continue the run from the log alone.
+///
+/// The vessels, asset temperatures and pH, tick, and derived metrics come from the last row. The
+/// workflow position, ticks spent in the current stage, transfer progress, and event counts are
+/// replayed from the events of every row, falling back to counting rows per `stage_id` for logs
+/// written before stage events. `process` and `assets` supply the definitions the log refers to
+/// by id. A last line cut short, e.g. by a crash mid-write, is skipped in favour of the row
+/// before it.
+///
+/// The resume is exact only for logs that hold a row for every tick: the biomass history of
+/// `BiomassStationary` conditions is taken from the last rows, and row counts stand in for
+/// missing stage events. Logs written before multi-vessel support resume with the active vessel
+/// only, and wide logs do not hold enough state to resume from.
+pub fn state_from_log(
+    log_path: &str,
+    process: &Process,
+    assets: &HashMap<String, Asset>,
+) -> Result<ResumePoint, BioforgeError> {
+    let mut records = read_log_allowing_truncation(log_path)?;
+    match resume_point_from_records(log_path, &records, process, assets) {
+        Err(BioforgeError::JsonParsing(e)) if records.len() > 1 => {
+            warn!("'{}': the row at tick {} is incomplete ({}); resuming from the row before it", log_path, records[records.len() - 1].tick, e);
+            records.pop();
+            resume_point_from_records(log_path, &records, process, assets)
+        }
+        result => result,
+    }
+}
+
+/// Like [`state_from_log`], from records already in memory, e.g. those of an
+/// [`crate::logger::InMemorySink`]. Every record must be complete.
+pub fn state_from_records(
+    records: &[LogRecord],
+    process: &Process,
+    assets: &HashMap<String, Asset>,
+) -> Result<ResumePoint, BioforgeError> {
+    resume_point_from_records("in-memory log", records, process, assets)
+}
+
+/// Reads a log like [`read_log`], but drops the last line of a plain-text log when it does not
+/// parse.
+fn read_log_allowing_truncation(log_path: &str) -> Result<Vec<LogRecord>, BioforgeError> {
+    let error = match read_log(log_path) {
+        Ok(records) => return Ok(records),
+        Err(error) => error,
+    };
+    let mut segments = log_segments(log_path);
+    let Some(last) = segments.pop().filter(|path| path.extension().is_none_or(|extension| extension != "gz")) else {
+        return Err(error);
+    };
+    // Parquet logs are not text and are never left with a partial row.
+    let Ok(contents) = fs::read_to_string(&last) else {
+        return Err(error);
+    };
+    let Some(end) = contents.trim_end().rfind('\n') else {
+        return Err(error);
+    };
+
+    let mut records = Vec::new();
+    for segment in &segments {
+        records.extend(read_log_segment(segment)?);
+    }
+    records.extend(read_text_log(&last.to_string_lossy(), &contents[..=end])?);
+    warn!("'{}': ignoring an incomplete last line ({})", last.display(), error);
+    Ok(records)
+}
+
+/// Rebuilds the position of the run at the last of `records`, naming them `source` in errors.
+fn resume_point_from_records(
+    source: &str,
+    records: &[LogRecord],
+    process: &Process,
+    assets: &HashMap<String, Asset>,
+) -> Result<ResumePoint, BioforgeError> {
+    let last = records
+        .last()
+        .ok_or_else(|| BioforgeError::ConfigError(format!("'{}' holds no rows to resume from", source)))?;
+    if last.organisms_json.is_empty() && last.vessels_json.is_empty() {
+        return Err(BioforgeError::ConfigError(format!(
+            "'{}' is a wide log, which does not hold enough state to resume from",
+            source
+        )));
+    }
+
+    let mut event_counts = BTreeMap::new();
+    let mut advances = 0;
+    let mut stage_start = None;
+    let mut transferred_l = BTreeMap::new();
+    for record in records {
+        let events: Vec<SimulationEvent> = serde_json::from_str(&record.events_json)?;
+        for event in events {
+            *event_counts.entry(event.kind().to_string()).or_insert(0) += 1;
+            match event {
+                SimulationEvent::StageChanged { from_method_id, tick, .. } => {
+                    if from_method_id.is_some() {
+                        advances += 1;
+                    }
+                    stage_start = Some(tick);
+                    transferred_l.clear();
+                }
+                SimulationEvent::MaterialTransferred { from_asset_id, to_asset_id, volume_l } => {
+                    *transferred_l.entry((from_asset_id, to_asset_id)).or_insert(0.0) += volume_l;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Row t shows the stage that ran tick t, entered by the commands of tick t - 1.
+    let workflow = &process.default_workflow;
+    let initial = last.stage_id == "INITIAL";
+    let (step_index, ticks_in_current_stage) = match stage_start {
+        Some(start) => (advances, last.tick - start),
+        None => {
+            let step_index = if initial {
+                0
+            } else {
+                workflow
+                    .iter()
+                    .position(|id| *id == last.stage_id)
+                    .ok_or_else(|| BioforgeError::MethodNotFound(last.stage_id.clone()))?
+            };
+            let stage_rows = records.iter().rev().take_while(|r| r.stage_id == last.stage_id).count();
+            (step_index, if initial { 0 } else { stage_rows as u64 })
+        }
+    };
+    let method_id = workflow.get(step_index).ok_or_else(|| {
+        BioforgeError::ConfigError(format!(
+            "'{}' reaches workflow step {}, but process '{}' has {} steps",
+            source,
+            step_index,
+            process.process_id,
+            workflow.len()
+        ))
+    })?;
+    if !initial && *method_id != last.stage_id {
+        return Err(BioforgeError::ConfigError(format!(
+            "'{}' ran '{}' at tick {}, but step {} of process '{}' is '{}'",
+            source, last.stage_id, last.tick, step_index, process.process_id, method_id
+        )));
+    }
+    let active_vessel_id = process
+        .methods
+        .iter()
+        .find(|m| m.method_id == *method_id)
+        .ok_or_else(|| BioforgeError::MethodNotFound(method_id.clone()))?
+        .required_asset_id
+        .clone();
+
+    let vessels: BTreeMap<String, Vessel> = if last.vessels_json.is_empty() {
+        // Logs written before multi-vessel support describe the active vessel only.
+        let media = MediaState {
+            volume: Measurement { value: last.media_volume_l, unit: "L".to_string() },
+            ph: last.media_ph,
+            composition: MediaComposition {
+                dissolved_components: serde_json::from_str(&last.dissolved_components_json)?,
+                dissolved_gases: serde_json::from_str(&last.dissolved_gases_json)?,
+            },
+        };
+        let organisms = OrganismState { states: serde_json::from_str(&last.organisms_json)? };
+        BTreeMap::from([(active_vessel_id.clone(), Vessel::new(media, organisms))])
+    } else {
+        serde_json::from_str::<BTreeMap<String, LoggedVessel>>(&last.vessels_json)?
+            .into_iter()
+            .map(|(asset_id, vessel)| (asset_id, Vessel::new(vessel.media, vessel.organisms)))
+            .collect()
+    };
+    if !vessels.contains_key(&active_vessel_id) {
+        return Err(BioforgeError::AssetNotFound(active_vessel_id));
+    }
+
+    let mut live_assets = BTreeMap::new();
+    for (asset_id, logged) in serde_json::from_str::<BTreeMap<String, LoggedAssetState>>(&last.asset_states_json)? {
+        let definition = assets
+            .get(&asset_id)
+            .cloned()
+            .ok_or_else(|| BioforgeError::AssetNotFound(asset_id.clone()))?;
+        live_assets.insert(
+            asset_id,
+            LiveAsset { definition, temperature: logged.temperature, ph: logged.ph },
+        );
+    }
+
+    let parse_rates = |json: &str| -> Result<BTreeMap<String, f64>, BioforgeError> {
+        if json.is_empty() {
+            Ok(BTreeMap::new())
+        } else {
+            Ok(serde_json::from_str(json)?)
+        }
+    };
+    let metrics = TickMetrics {
+        total_biomass_g: last.total_biomass_g,
+        specific_growth_rates: parse_rates(&last.growth_rates_json)?,
+        uptake_rates: parse_rates(&last.uptake_rates_json)?,
+    };
+
+    // The engine records the active vessel's biomass after every tick in which anything grew.
+    let mut recent_biomass = Vec::new();
+    for record in records.iter().rev().filter(|r| r.tick > 0 && r.media_volume_l > 0.0) {
+        if recent_biomass.len() == RESUME_BIOMASS_HISTORY {
+            break;
+        }
+        let organisms: BTreeMap<String, IndividualOrganismState> = serde_json::from_str(&record.organisms_json)?;
+        recent_biomass.push(organisms.values().map(|o| o.biomass.value).sum());
+    }
+    recent_biomass.reverse();
+
+    let start_time = match records.first().and_then(|r| Some((r.tick, r.timestamp.as_deref()?))) {
+        Some((tick, timestamp)) if !timestamp.is_empty() => {
+            let at = DateTime::parse_from_rfc3339(timestamp).map_err(|e| {
+                BioforgeError::ConfigError(format!("'{}': invalid timestamp '{}': {}", source, timestamp, e))
+            })?;
+            let at = at.with_timezone(&Utc);
+            Some(at - (tick_timestamp(at, tick) - at))
+        }
+        _ => None,
+    };
+
+    Ok(ResumePoint {
+        state: SimulationState {
+            tick: last.tick,
+            ticks_in_current_stage,
+            assets: live_assets,
+            vessels,
+            active_vessel_id,
+            events: Vec::new(),
+            metrics,
+        },
+        step_index,
+        recent_biomass,
+        transferred_l,
+        event_counts,
+        start_time,
+    })
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BillOfMaterials {
+    pub materials_consumed: HashMap<String, f64>,
+    pub total_energy_kwh: f64,
+    pub labor_hours: HashMap<String, f64>,
+    pub total_ticks: u64,
+    /// The same quantities broken down by the stage (method id) that incurred them. Quantities
+    /// that belong to no stage, such as the initial media, appear only in the totals.
+    pub by_stage: HashMap<String, StageBom>,
+    /// Grams of each material taken up by each organism, keyed by organism id and then material
+    /// id. Materials consumed by unit operations appear only in the totals.
+    pub materials_by_consumer: HashMap<String, HashMap<String, f64>>,
+    /// Grams of each material that left the process as waste, keyed by material id, or by the
+    /// logged id (a ChEBI or organism id) for waste that matches no material.
+    pub waste_generated: HashMap<String, f64>,
+    /// The labor hours behind `labor_hours`, by stage, role, and task.
+    pub labor_tasks: Vec<LaborTask>,
+    /// The QC checks run by the stages, whose labor and consumables are part of the totals above.
+    #[serde(default)]
+    pub qc_assays: Vec<QcAssay>,
+}
+
+/// The hours one role spent on one task of a stage.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LaborTask {
+    pub stage_id: String,
+    pub role_id: String,
+    pub task_id: String,
+    pub task_description: String,
+    pub hours: f64,
+}
+
+/// The [`QcAssay::timing`] of an assay that releases the final product.
+pub const RELEASE_QC_TIMING: &str = "final_product_release";
+
+/// One QC check run during a stage, with what it took.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QcAssay {
+    pub stage_id: String,
+    /// The QC method's id.
+    pub qc_method_id: String,
+    pub timing: String,
+    /// The role that ran the assay, or `None` if the check names no role and duration.
+    pub role_id: Option<String>,
+    pub labor_hours: f64,
+    /// Grams of each consumable the assay used, keyed by material id.
+    pub materials_consumed: HashMap<String, f64>,
+    /// The check's `cost_usd`.
+    pub cost_usd: f64,
+}
+
+impl BillOfMaterials {
+    /// Adds every quantity of `other` to this BOM, merging the breakdowns of stages that share an
+    /// id (e.g. a packaging method used by several processes).
+    pub fn merge(&mut self, other: &BillOfMaterials) {
+        self.total_energy_kwh += other.total_energy_kwh;
+        self.total_ticks += other.total_ticks;
+        add_quantities(&mut self.materials_consumed, &other.materials_consumed);
+        add_quantities(&mut self.labor_hours, &other.labor_hours);
+        for (stage_id, stage) in &other.by_stage {
+            self.by_stage.entry(stage_id.clone()).or_default().merge(stage);
+        }
+        for (consumer_id, consumed) in &other.materials_by_consumer {
+            add_quantities(self.materials_by_consumer.entry(consumer_id.clone()).or_default(), consumed);
+        }
+        add_quantities(&mut self.waste_generated, &other.waste_generated);
+        for task in &other.labor_tasks {
+            self.merge_labor_task(task);
+        }
+        self.qc_assays.extend(other.qc_assays.iter().cloned());
+    }
+
+    /// The BOM of `batches` runs of this one, one after another, whose product is pooled: each
+    /// repeats every quantity, and so the hours each asset is occupied, except the QC assays run at
+    /// [`RELEASE_QC_TIMING`], which release the pooled product once.
+    pub fn scaled_out(&self, batches: u32) -> BillOfMaterials {
+        let mut repeated = self.clone();
+        for assay in self.qc_assays.iter().filter(|assay| assay.timing == RELEASE_QC_TIMING) {
+            repeated.remove_qc_assay(assay);
+        }
+        let mut scaled = self.clone();
+        for _ in 1..batches {
+            scaled.merge(&repeated);
+        }
+        scaled
+    }
+
+    /// Takes the labor and consumables of `assay` out of every quantity, and the assay out of the
+    /// QC assays.
+    fn remove_qc_assay(&mut self, assay: &QcAssay) {
+        let negated = |quantities: &HashMap<String, f64>| quantities.iter().map(|(id, q)| (id.clone(), -q)).collect();
+        let materials = negated(&assay.materials_consumed);
+        add_quantities(&mut self.materials_consumed, &materials);
+        let stage = self.by_stage.entry(assay.stage_id.clone()).or_default();
+        add_quantities(&mut stage.materials_consumed, &materials);
+        if let Some(role_id) = &assay.role_id {
+            let labor = HashMap::from([(role_id.clone(), -assay.labor_hours)]);
+            add_quantities(&mut stage.labor_hours, &labor);
+            add_quantities(&mut self.labor_hours, &labor);
+            let same_task =
+                |t: &LaborTask| t.stage_id == assay.stage_id && &t.role_id == role_id && t.task_id == assay.qc_method_id;
+            if let Some(position) = self.labor_tasks.iter().position(same_task) {
+                self.labor_tasks[position].hours -= assay.labor_hours;
+                if self.labor_tasks[position].hours <= 1e-12 {
+                    self.labor_tasks.remove(position);
+                }
+            }
+        }
+        if let Some(position) = self.qc_assays.iter().position(|a| a == assay) {
+            self.qc_assays.remove(position);
+        }
+    }
+
+    fn add_material(&mut self, stage_id: &str, consumer_id: Option<&str>, material_id: &str, grams: f64) {
+        *self.materials_consumed.entry(material_id.to_string()).or_insert(0.0) += grams;
+        let stage = self.by_stage.entry(stage_id.to_string()).or_default();
+        *stage.materials_consumed.entry(material_id.to_string()).or_insert(0.0) += grams;
+        if let Some(consumer_id) = consumer_id {
+            let consumed = self.materials_by_consumer.entry(consumer_id.to_string()).or_default();
+            *consumed.entry(material_id.to_string()).or_insert(0.0) += grams;
+        }
+    }
+
+    fn add_waste(&mut self, stage_id: &str, id: &str, grams: f64) {
+        *self.waste_generated.entry(id.to_string()).or_insert(0.0) += grams;
+        let stage = self.by_stage.entry(stage_id.to_string()).or_default();
+        *stage.waste_generated.entry(id.to_string()).or_insert(0.0) += grams;
+    }
+
+    fn add_energy(&mut self, stage_id: &str, kwh: f64) {
+        self.total_energy_kwh += kwh;
+        self.by_stage.entry(stage_id.to_string()).or_default().energy_kwh += kwh;
+    }
+
+    fn add_stage_ticks(&mut self, stage_id: &str, asset_id: &str, ticks: u64) {
+        let stage = self.by_stage.entry(stage_id.to_string()).or_default();
+        stage.ticks += ticks;
+        stage.asset_id = Some(asset_id.to_string());
+    }
+
+    /// Ticks each asset was occupied by a stage, keyed by asset id. Assets no stage ran on are
+    /// absent, and are neither depreciated nor charged use-phase impacts.
+    pub fn asset_ticks(&self) -> HashMap<String, u64> {
+        let mut asset_ticks = HashMap::new();
+        for stage in self.by_stage.values() {
+            if let Some(asset_id) = &stage.asset_id {
+                *asset_ticks.entry(asset_id.clone()).or_insert(0) += stage.ticks;
+            }
+        }
+        asset_ticks
+    }
+
+    fn add_labor_task(&mut self, task: &LaborTask) {
+        *self.labor_hours.entry(task.role_id.clone()).or_insert(0.0) += task.hours;
+        let stage = self.by_stage.entry(task.stage_id.clone()).or_default();
+        *stage.labor_hours.entry(task.role_id.clone()).or_insert(0.0) += task.hours;
+        self.merge_labor_task(task);
+    }
+
+    /// Adds `task` to the task breakdown alone, combining it with an entry of the same stage,
+    /// role, and task.
+    fn merge_labor_task(&mut self, task: &LaborTask) {
+        let same_task = |t: &&mut LaborTask| {
+            t.stage_id == task.stage_id && t.role_id == task.role_id && t.task_id == task.task_id
+        };
+        match self.labor_tasks.iter_mut().find(same_task) {
+            Some(existing) => existing.hours += task.hours,
+            None => self.labor_tasks.push(task.clone()),
+        }
+    }
+}
+
+/// The part of a [`BillOfMaterials`] incurred while one workflow stage ran.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageBom {
+    pub materials_consumed: HashMap<String, f64>,
+    pub energy_kwh: f64,
+    pub labor_hours: HashMap<String, f64>,
+    /// Ticks the stage ran for.
+    pub ticks: u64,
+    /// The asset the stage ran on, or `None` if its method is unknown.
+    pub asset_id: Option<String>,
+    pub waste_generated: HashMap<String, f64>,
+}
+
+impl StageBom {
+    /// Adds the quantities of `other`, e.g. the same stage in another run.
+    pub fn merge(&mut self, other: &StageBom) {
+        self.energy_kwh += other.energy_kwh;
+        self.ticks += other.ticks;
+        add_quantities(&mut self.materials_consumed, &other.materials_consumed);
+        add_quantities(&mut self.labor_hours, &other.labor_hours);
+        add_quantities(&mut self.waste_generated, &other.waste_generated);
+        if self.asset_id.is_none() {
+            self.asset_id = other.asset_id.clone();
+        }
+    }
+}
+
+fn add_quantities(into: &mut HashMap<String, f64>, from: &HashMap<String, f64>) {
+    for (id, quantity) in from {
+        *into.entry(id.clone()).or_insert(0.0) += quantity;
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CogsResult {
+    pub material_costs: f64,
+    pub labor_costs: f64,
+    pub energy_costs: f64,
+    pub asset_depreciation_costs: f64,
+    pub maintenance_costs: f64,
+    /// Disposal of waste plus the end-of-life costs of the assets used, amortized over their
+    /// lifespan like depreciation. Negative when the assets' terminal value outweighs disposal.
+    pub end_of_life_costs: f64,
+    pub total_cogs: f64,
+    /// The costs of each stage of [`BillOfMaterials::by_stage`], keyed by stage id. Each stage
+    /// bears the depreciation and maintenance of its own asset for the stage's duration.
+    pub by_stage: HashMap<String, CogsResult>,
+    /// The cost of the materials each organism consumed, keyed by organism id; see
+    /// [`BillOfMaterials::materials_by_consumer`].
+    pub material_costs_by_consumer: HashMap<String, f64>,
+    /// The labor costs of each role, keyed by role id, including the QC assays the role ran.
+    pub labor_costs_by_role: HashMap<String, f64>,
+    /// What the QC assays of [`BillOfMaterials::qc_assays`] cost: their own costs plus their
+    /// labor and consumables, which are left out of `labor_costs` and `material_costs`.
+    #[serde(default)]
+    pub qc_costs: f64,
+}
+
+/// How quantities shared by several products, such as the upstream cultivation, are split between them.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+pub enum CostAllocation {
+    /// In proportion to the grams of each product.
+    #[default]
+    Mass,
+    /// In proportion to the energy content of each product, i.e. its grams times its energy
+    /// content in MJ/kg keyed by product (molecule) name.
+    EnergyContent { mj_per_kg: HashMap<String, f64> },
+    /// In proportion to user-provided ratios keyed by product (molecule) name, e.g. relative
+    /// market values. The ratios need not sum to one.
+    Economic { ratios: HashMap<String, f64> },
+}
+
+impl CostAllocation {
+    /// The allocation method's name, for reports.
+    pub fn label(&self) -> &'static str {
+        match self {
+            CostAllocation::Mass => "mass",
+            CostAllocation::EnergyContent { .. } => "energy content",
+            CostAllocation::Economic { .. } => "economic",
+        }
+    }
+
+    /// The weight `product` is allocated shared quantities by.
+    fn weight(&self, product: &ProductOutput) -> Result<f64, BioforgeError> {
+        let per_product = |values: &HashMap<String, f64>, what: &str| {
+            let value = values.get(&product.molecule_name).copied().ok_or_else(|| {
+                BioforgeError::ConfigError(format!("No {} for '{}'", what, product.molecule_name))
+            })?;
+            if value < 0.0 || !value.is_finite() {
+                return Err(BioforgeError::ConfigError(format!(
+                    "The {} for '{}' must be non-negative, got {}",
+                    what, product.molecule_name, value
+                )));
+            }
+            Ok(value)
+        };
+        let grams = product.produced_grams.max(0.0);
+        match self {
+            CostAllocation::Mass => Ok(grams),
+            CostAllocation::EnergyContent { mj_per_kg } => {
+                Ok(grams / 1000.0 * per_product(mj_per_kg, "energy content")?)
+            }
+            CostAllocation::Economic { ratios } => per_product(ratios, "economic allocation ratio"),
+        }
+    }
+}
+
+/// One product of a run, as input to [`allocate_coproducts`].
+#[derive(Debug, Clone)]
+pub struct ProductOutput {
+    pub molecule_name: String,
+    /// Grams of the product the run yielded.
+    pub produced_grams: f64,
+    /// The stages (method ids) that process this product alone, e.g. its downstream process.
+    /// They are charged to the product directly unless another product lists them too.
+    pub stage_ids: Vec<String>,
+}
+
+/// The quantities charged to a product, or shared between products.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AllocatedQuantities {
+    pub cogs: f64,
+    pub energy_kwh: f64,
+    pub materials_consumed: BTreeMap<String, f64>,
+    /// Impact totals keyed by metric, in the units of [`AllocationReport::impact_units`].
+    pub impacts: BTreeMap<String, f64>,
+}
+
+impl AllocatedQuantities {
+    fn of_stage(bom: &StageBom, cogs: &CogsResult, lca: &LcaResult) -> Self {
+        AllocatedQuantities {
+            cogs: cogs.total_cogs,
+            energy_kwh: bom.energy_kwh,
+            materials_consumed: bom.materials_consumed.iter().map(|(id, q)| (id.clone(), *q)).collect(),
+            impacts: lca.impacts.iter().map(|(metric, (value, _))| (metric.clone(), *value)).collect(),
+        }
+    }
+
+    /// Adds `factor` times the quantities of `other`.
+    fn add_scaled(&mut self, other: &AllocatedQuantities, factor: f64) {
+        self.cogs += other.cogs * factor;
+        self.energy_kwh += other.energy_kwh * factor;
+        for (id, quantity) in &other.materials_consumed {
+            *self.materials_consumed.entry(id.clone()).or_insert(0.0) += quantity * factor;
+        }
+        for (metric, value) in &other.impacts {
+            *self.impacts.entry(metric.clone()).or_insert(0.0) += value * factor;
+        }
+    }
+}
+
+/// The quantities borne by one product.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ProductAllocation {
+    pub molecule_name: String,
+    pub produced_grams: f64,
+    /// The product's fraction of the shared quantities.
+    pub share: f64,
+    /// The quantities of the product's own stages.
+    pub direct: AllocatedQuantities,
+    /// `share` of [`AllocationReport::shared`].
+    pub allocated: AllocatedQuantities,
+    /// `direct + allocated`.
+    pub total: AllocatedQuantities,
+    /// `total.cogs` per gram produced, or `None` if nothing was produced.
+    pub cost_per_gram: Option<f64>,
+}
+
+/// The BOM, COGS, and LCA of a run split between its products. The products' totals sum to the
+/// run's totals.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AllocationReport {
+    pub basis: CostAllocation,
+    /// The quantities no single product incurred, such as the upstream cultivation.
+    pub shared: AllocatedQuantities,
+    pub products: Vec<ProductAllocation>,
+    pub impact_units: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LcaResult {
+    /// Each impact category found in the material and asset impact entries or the grid factors,
+    /// keyed by metric (e.g. `gwp`, `water_use`), with its total and unit.
+    pub impacts: HashMap<String, (f64, String)>,
+    /// The impacts of each stage of [`BillOfMaterials::by_stage`], keyed by stage id.
+    pub by_stage: HashMap<String, LcaResult>,
+    /// BOM entries that match no material and so contribute no impacts; see
+    /// [`MaterialResolver::unmatched`].
+    pub unmatched_materials: Vec<String>,
+}
+
+/// Whether impacts in `unit` can be summed. Rates such as a `%` recycling rate cannot.
+fn is_additive(unit: &str) -> bool {
+    unit != "%"
+}
+
+/// A readable name for the impact category `metric`, falling back to the metric itself.
+pub fn impact_category_name(metric: &str) -> &str {
+    match metric {
+        "gwp" => "Global Warming Potential",
+        "adp_fossil" => "Abiotic Depletion (fossil)",
+        "water_use" => "Water Use",
+        "ep" => "Eutrophication Potential",
+        "ap" => "Acidification Potential",
+        "pocp" => "Photochemical Ozone Creation",
+        "land_use" => "Land Use",
+        "e_waste_burden" => "E-Waste Burden",
+        "hazardous_waste" => "Hazardous Waste",
+        other => other,
+    }
+}
+
+impl LcaResult {
+    /// Global warming potential in kg CO₂e.
+    pub fn gwp_kg_co2e(&self) -> f64 {
+        self.value("gwp")
+    }
+
+    /// Abiotic depletion (fossil) in MJ.
+    pub fn adp_fossil_mj(&self) -> f64 {
+        self.value("adp_fossil")
+    }
+
+    /// The total of `metric`, or zero if nothing contributed to it.
+    pub fn value(&self, metric: &str) -> f64 {
+        self.impacts.get(metric).map_or(0.0, |(value, _)| *value)
+    }
+
+    /// The impacts as `(metric, value, unit)`, GWP and ADP (fossil) first and the rest by metric.
+    pub fn sorted_impacts(&self) -> Vec<(&str, f64, &str)> {
+        let rank = |metric: &str| match metric {
+            "gwp" => 0,
+            "adp_fossil" => 1,
+            _ => 2,
+        };
+        let mut impacts: Vec<(&str, f64, &str)> = self
+            .impacts
+            .iter()
+            .map(|(metric, (value, unit))| (metric.as_str(), *value, unit.as_str()))
+            .collect();
+        impacts.sort_by(|a, b| rank(a.0).cmp(&rank(b.0)).then_with(|| a.0.cmp(b.0)));
+        impacts
+    }
+
+    /// Adds `value` in `unit` to `metric`. The first unit seen for a metric is kept.
+    fn add(&mut self, metric: &str, value: f64, unit: &str) {
+        self.impacts.entry(metric.to_string()).or_insert_with(|| (0.0, unit.to_string())).0 += value;
+    }
+}
+
+/// Where the duration of a [`BlueprintStep`] came from, in the order they are tried.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationSource {
+    /// The method's `TimeInStage` rule.
+    #[default]
+    TimeInStageRule,
+    /// The method's `expected_duration_ticks`.
+    ExpectedDuration,
+    /// How long the stage ran in a logged run.
+    ObservedRun,
+    /// None of the above: the method ends on a condition whose timing depends on the run, and
+    /// the step's `duration_ticks` is zero.
+    Dynamic,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlueprintStep {
+    pub step: usize,
+    pub method_id: String,
+    #[serde(default)]
+    pub stage: String,
+    pub technique: String,
+    pub asset_id: String,
+    pub duration_ticks: u64,
+    /// Blueprints written before the field existed read as [`DurationSource::TimeInStageRule`],
+    /// the only source there was.
+    #[serde(default)]
+    pub duration_source: DurationSource,
+    pub control_parameters: BTreeMap<String, serde_json::Value>,
+    /// The method's rules: the conditions that end the step and those that act during it.
+    #[serde(default)]
+    pub transition_rules: Vec<Rule>,
+    #[serde(default)]
+    pub required_materials: Vec<BlueprintMaterial>,
+    #[serde(default)]
+    pub qc_checks: Vec<QcCheck>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transfers: Option<Vec<MaterialTransfer>>,
+}
+
+/// A material a blueprint step requires.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlueprintMaterial {
+    pub r#type: String,
+    pub id: String,
+    /// Grams the stage consumed in a simulated run; see
+    /// [`ExecutableBlueprint::with_material_estimates`].
+    pub estimated_grams: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutableBlueprint {
+    pub process_id: String,
+    pub process_name: String,
+    pub workflow: Vec<BlueprintStep>,
+}
+
+impl ExecutableBlueprint {
+    /// Estimates the quantity of each step's required materials from what its stage consumed in
+    /// `bom`, a simulated run of the same process. Materials the stage did not consume keep no
+    /// estimate.
+    pub fn with_material_estimates(mut self, bom: &BillOfMaterials) -> Self {
+        for step in &mut self.workflow {
+            let consumed = bom.by_stage.get(&step.method_id).map(|stage| &stage.materials_consumed);
+            for material in &mut step.required_materials {
+                material.estimated_grams = consumed.and_then(|consumed| consumed.get(&material.id)).copied();
+            }
+        }
+        self
+    }
+
+    /// Rebuilds the process the blueprint describes and the rules its steps run under, so that an
+    /// edited blueprint can be simulated again.
+    ///
+    /// Control parameters become operating parameters. Each step whose duration is known gets a
+    /// `TimeInStage` rule of `duration_ticks` named `<method_id>.duration` in place of the
+    /// `TimeInStage` advances among its transition rules; its other rules are kept as they are.
+    pub fn to_process(&self) -> (Process, Vec<Rule>) {
+        let mut rules: Vec<Rule> = Vec::new();
+        let mut methods = Vec::new();
+        for step in &self.workflow {
+            let mut rule_ids = Vec::new();
+            let timed = step.duration_source != DurationSource::Dynamic;
+            for rule in &step.transition_rules {
+                let time_advance = matches!(rule.condition, bioforge_schemas::rule::Condition::TimeInStage { .. })
+                    && matches!(rule.action, bioforge_schemas::command::Command::AdvanceToNextStep);
+                if timed && time_advance {
+                    continue;
+                }
+                rule_ids.push(rule.name.clone());
+                if !rules.iter().any(|r| r.name == rule.name) {
+                    rules.push(rule.clone());
+                }
+            }
+            if timed {
+                let name = format!("{}.duration", step.method_id);
+                rules.push(Rule {
+                    name: name.clone(),
+                    condition: bioforge_schemas::rule::Condition::TimeInStage { ticks: step.duration_ticks },
+                    action: bioforge_schemas::command::Command::AdvanceToNextStep,
+                });
+                rule_ids.push(name);
+            }
+            methods.push(Method {
+                method_id: step.method_id.clone(),
+                stage: step.stage.clone(),
+                technique: step.technique.clone(),
+                required_asset_id: step.asset_id.clone(),
+                operating_parameters: step.control_parameters.clone(),
+                required_materials: step
+                    .required_materials
+                    .iter()
+                    .map(|m| RequiredMaterial { r#type: m.r#type.clone(), id: m.id.clone() })
+                    .collect(),
+                qc_checks: step.qc_checks.clone(),
+                required_rule_ids: Some(rule_ids),
+                transfers: step.transfers.clone(),
+                expected_duration_ticks: timed.then_some(step.duration_ticks),
+                impurity_removal_percent: None,
+            });
+        }
+        let process = Process {
+            process_id: self.process_id.clone(),
+            process_name: self.process_name.clone(),
+            component_class: String::new(),
+            status: "blueprint".to_string(),
+            notes: format!("Rebuilt from the executable blueprint of '{}'.", self.process_id),
+            default_workflow: self.workflow.iter().map(|step| step.method_id.clone()).collect(),
+            methods,
+        };
+        (process, rules)
+    }
+}
+
+/// Writes `blueprint` to a YAML file at `path`, e.g. for a manufacturing execution system.
+pub fn write_blueprint_yaml(blueprint: &ExecutableBlueprint, path: &str) -> Result<(), BioforgeError> {
+    let yaml = serde_yaml::to_string(blueprint).map_err(|e| BioforgeError::YamlWriting(path.to_string(), e))?;
+    fs::write(path, yaml).map_err(|e| BioforgeError::FileIO(path.to_string(), e))
+}
+
+/// Reads a blueprint written by [`write_blueprint_yaml`].
+pub fn read_blueprint_yaml(path: &str) -> Result<ExecutableBlueprint, BioforgeError> {
+    let yaml = fs::read_to_string(path).map_err(|e| BioforgeError::FileIO(path.to_string(), e))?;
+    serde_yaml::from_str(&yaml).map_err(|e| BioforgeError::YamlParsing(path.to_string(), e))
+}
+
+/// The bill of materials of making `media_state` from the purchasable `materials`; see
+/// [`media_recipe::MediaRecipe::resolve`].
+pub fn bom_from_media_state(
+    media_state: &MediaState,
+    materials: &HashMap<String, Material>,
+) -> Result<BillOfMaterials, BioforgeError> {
+    Ok(media_recipe::MediaRecipe::resolve(media_state, materials)?.bom())
+}
+
+/// Builds the bill of materials of the run logged at `log_path`.
+///
+/// When the run wrote an event log and ran to completion, the BOM is built from the event log
+/// alone, which is much faster than deserializing the events of every row. Runs whose assets
+/// need labor per liter of media are built from the rows, which log the media volume.
+pub fn generate_bom(
+    log_path: &str,
+    process: &Process,
+    assets: &HashMap<String, Asset>,
+    materials: &HashMap<String, Material>,
+) -> Result<BillOfMaterials, BioforgeError> {
+    if let Some(events) = read_event_log(log_path)? {
+        // Only the stage change that completes the workflow pins down the length of the run.
+        let completed = events.iter().any(|e| e.event_type == "stage_changed" && e.target_id.is_empty());
+        if completed && !needs_media_volumes(process, assets) {
+            return generate_bom_from_events(&events, process, assets, materials);
+        }
+    }
+    generate_bom_from_records(&read_log(log_path)?, process, assets, materials)
+}
+
+/// Like [`generate_bom`], from the event log of a completed run rather than its rows.
+///
+/// A stage's asset runs for every tick of the stage, so stage durations stand in for the rows of
+/// the log; the run lasts until the stage change that completes the workflow.
+pub fn generate_bom_from_events(
+    events: &[EventRecord],
+    process: &Process,
+    assets: &HashMap<String, Asset>,
+    materials: &HashMap<String, Material>,
+) -> Result<BillOfMaterials, BioforgeError> {
+    let materials = MaterialResolver::new(materials);
+    let mut bom = BillOfMaterials::default();
+    let mut ticks_in_stage: HashMap<String, u64> = HashMap::new();
+    let mut open_stage: Option<(String, u64)> = None;
+    let mut last_tick = 0;
+    let methods_by_id: HashMap<&str, &Method> = process
+        .methods
+        .iter()
+        .map(|m| (m.method_id.as_str(), m))
+        .collect();
+
+    for record in events {
+        last_tick = last_tick.max(record.tick);
+        let event = record.to_event().ok_or_else(|| {
+            BioforgeError::ConfigError(format!(
+                "Event log entry at tick {} has an unknown type '{}' or lacks an amount",
+                record.tick, record.event_type
+            ))
+        })?;
+        match event {
+            SimulationEvent::MaterialConsumed { id, amount, consumer_id } => {
+                add_consumed_material(&mut bom, &materials, &record.stage_id, consumer_id.as_deref(), &id, amount);
+            }
+            SimulationEvent::MaterialAdded { .. } => {}
+            SimulationEvent::MaterialRemoved { id, amount } => {
+                add_waste(&mut bom, &materials, &record.stage_id, &id, amount);
+            }
+            SimulationEvent::StageChanged { to_method_id, tick, .. } => {
+                if let Some((stage_id, start)) = open_stage.take() {
+                    *ticks_in_stage.entry(stage_id).or_insert(0) += tick - start;
+                }
+                open_stage = to_method_id.map(|id| (id, tick));
+            }
+            SimulationEvent::MaterialTransferred { from_asset_id, .. } => {
+                add_transfer_energy(&mut bom, assets, &methods_by_id, &record.stage_id, &from_asset_id);
+            }
+        }
+    }
+    if let Some((stage_id, start)) = open_stage {
+        *ticks_in_stage.entry(stage_id).or_insert(0) += last_tick.saturating_sub(start);
+    }
+    // The log holds one row per tick plus the initial state.
+    bom.total_ticks = last_tick + 1;
+
+    // Stages in id order keep the labor tasks and summed quantities of the BOM reproducible.
+    for (stage_id, total_ticks) in ticks_in_stage.into_iter().collect::<BTreeMap<_, _>>() {
+        let Some(method) = methods_by_id.get(stage_id.as_str()) else {
+            continue;
+        };
+        bom.add_stage_ticks(&stage_id, &method.required_asset_id, total_ticks);
+        if let Some(asset) = assets.get(&method.required_asset_id) {
+            if let Some(power_model) = asset.operational_parameters.as_ref().and_then(|p| p.power_model.as_ref()) {
+                bom.add_energy(&stage_id, power_model.operating_power.value * total_ticks as f64);
+            }
+            add_labor_hours(&mut bom, asset, &stage_id, total_ticks, None);
+        }
+        add_qc_assays(&mut bom, &materials, method, &stage_id, total_ticks);
+    }
+
+    Ok(bom)
+}
+
+/// Like [`generate_bom`], for one run stored in a SQLite log database.
+#[cfg(feature = "sqlite")]
+pub fn generate_bom_from_sqlite(
+    db_path: &str,
+    run_id: &str,
+    process: &Process,
+    assets: &HashMap<String, Asset>,
+    materials: &HashMap<String, Material>,
+) -> Result<BillOfMaterials, BioforgeError> {
+    let records = crate::sqlite_log::read_sqlite_run(db_path, run_id)?;
+    generate_bom_from_records(&records, process, assets, materials)
+}
+
+/// The BOM and COGS of one run stored in a SQLite log database.
+#[cfg(feature = "sqlite")]
+pub fn calculate_cogs_from_sqlite(
+    db_path: &str,
+    run_id: &str,
+    process: &Process,
+    materials: &HashMap<String, Material>,
+    labor_roles: &HashMap<String, LaborRole>,
+    assets: &HashMap<String, Asset>,
+    assumptions: &EconomicAssumptions,
+) -> Result<(BillOfMaterials, CogsResult), BioforgeError> {
+    let bom = generate_bom_from_sqlite(db_path, run_id, process, assets, materials)?;
+    let cogs = calculate_cogs(&bom, materials, labor_roles, assets, assumptions)?;
+    Ok((bom, cogs))
+}
+
+/// Like [`generate_bom`], but from records already in memory, such as those of an `InMemorySink`.
+pub fn generate_bom_from_records(
+    records: &[LogRecord],
+    process: &Process,
+    assets: &HashMap<String, Asset>,
+    materials: &HashMap<String, Material>,
+) -> Result<BillOfMaterials, BioforgeError> {
+    let materials = MaterialResolver::new(materials);
+    let mut bom = BillOfMaterials::default();
+    // Stage durations come from `StageChanged` events; logs written before those events existed
+    // fall back to counting rows per `stage_id`.
+    let mut rows_per_stage: HashMap<String, u64> = HashMap::new();
+    let mut ticks_from_events: HashMap<String, u64> = HashMap::new();
+    let mut open_stage: Option<(String, u64)> = None;
+    let mut saw_stage_events = false;
+    let mut last_tick = 0;
+    // The most media each stage held, which per-volume labor scales with.
+    let mut volume_by_stage: HashMap<String, f64> = HashMap::new();
+    let methods_by_id: HashMap<&str, &Method> = process
+        .methods
+        .iter()
+        .map(|m| (m.method_id.as_str(), m))
+        .collect();
+
+    for record in records {
+        *rows_per_stage.entry(record.stage_id.clone()).or_insert(0) += 1;
+        let volume = volume_by_stage.entry(record.stage_id.clone()).or_insert(0.0);
+        *volume = volume.max(record.media_volume_l);
+        last_tick = record.tick;
+        bom.total_ticks +=1;
+
+        let events: Vec<SimulationEvent> = serde_json::from_str(&record.events_json)?;
+        for event in events {
+            match event {
+                SimulationEvent::MaterialConsumed { id, amount, consumer_id } => {
+                    add_consumed_material(&mut bom, &materials, &record.stage_id, consumer_id.as_deref(), &id, amount);
+                }
+                SimulationEvent::MaterialAdded { .. } => {
+                    // Not currently tracking added materials in the BOM
+                }
+                SimulationEvent::MaterialRemoved { id, amount } => {
+                    add_waste(&mut bom, &materials, &record.stage_id, &id, amount);
+                }
+                SimulationEvent::StageChanged { to_method_id, tick, .. } => {
+                    saw_stage_events = true;
+                    if let Some((stage_id, start)) = open_stage.take() {
+                        *ticks_from_events.entry(stage_id).or_insert(0) += tick - start;
+                    }
+                    open_stage = to_method_id.map(|id| (id, tick));
+                }
+                SimulationEvent::MaterialTransferred { from_asset_id, .. } => {
+                    add_transfer_energy(&mut bom, assets, &methods_by_id, &record.stage_id, &from_asset_id);
+                }
+            }
+        }
+
+        if let Some(method) = methods_by_id.get(record.stage_id.as_str()) {
+            if let Some(asset) = assets.get(&method.required_asset_id) {
+                if let Some(params) = &asset.operational_parameters {
+                    if let Some(power_model) = &params.power_model {
+                        bom.add_energy(&record.stage_id, power_model.operating_power.value);
+                    }
+                }
+            }
+        }
+    }
+    if let Some((stage_id, start)) = open_stage {
+        *ticks_from_events.entry(stage_id).or_insert(0) += last_tick.saturating_sub(start);
+    }
+    let ticks_in_stage = if saw_stage_events { ticks_from_events } else { rows_per_stage };
+
+    // Stages in id order keep the labor tasks and summed quantities of the BOM reproducible.
+    for (stage_id, total_ticks) in ticks_in_stage.into_iter().collect::<BTreeMap<_, _>>() {
+        if let Some(method) = methods_by_id.get(stage_id.as_str()) {
+            bom.add_stage_ticks(&stage_id, &method.required_asset_id, total_ticks);
+            if let Some(asset) = assets.get(&method.required_asset_id) {
+                let volume_l = volume_by_stage.get(&stage_id).copied();
+                add_labor_hours(&mut bom, asset, &stage_id, total_ticks, volume_l);
+            }
+            add_qc_assays(&mut bom, &materials, method, &stage_id, total_ticks);
+        }
+    }
+
+    Ok(bom)
+}
+
+/// Adds `amount` grams of the material consumed as `id`, a material id or ChEBI id, during
+/// `stage_id` by `consumer_id` to the BOM. Consumption of unknown materials is not costed.
+fn add_consumed_material(
+    bom: &mut BillOfMaterials,
+    materials: &MaterialResolver,
+    stage_id: &str,
+    consumer_id: Option<&str>,
+    id: &str,
+    amount: f64,
+) {
+    if let Some(material) = materials.resolve(id) {
+        bom.add_material(stage_id, consumer_id, &material.material_id, amount);
+    }
+}
+
+/// Adds `amount` grams of waste logged as `id` during `stage_id` to the BOM, under its material id
+/// if it matches a material.
+fn add_waste(bom: &mut BillOfMaterials, materials: &MaterialResolver, stage_id: &str, id: &str, amount: f64) {
+    let waste_id = materials.resolve(id).map_or(id, |material| material.material_id.as_str());
+    bom.add_waste(stage_id, waste_id, amount);
+}
+
+/// Resolves the ids materials go by in a BOM to knowledge-base materials. Consumption is keyed by
+/// material id, while the initial media and molecules named by ChEBI id keep their ChEBI ids.
+pub struct MaterialResolver<'a> {
+    materials: &'a HashMap<String, Material>,
+    by_chebi_id: HashMap<&'a str, &'a Material>,
+}
+
+impl<'a> MaterialResolver<'a> {
+    /// Where several materials share a ChEBI id, the id resolves to the pure substance, one
+    /// without a formulation, over the solutions made from it, and then to the lowest material id.
+    pub fn new(materials: &'a HashMap<String, Material>) -> Self {
+        fn preference(material: &Material) -> (bool, &str) {
+            (material.formulation.is_some(), material.material_id.as_str())
+        }
+        let mut by_chebi_id: HashMap<&'a str, &'a Material> = HashMap::new();
+        for material in materials.values() {
+            let Some(chebi_id) = material.metadata.identifiers.as_ref().and_then(|i| i.chebi_id.as_deref()) else {
+                continue;
+            };
+            by_chebi_id
+                .entry(chebi_id)
+                .and_modify(|current| {
+                    if preference(material) < preference(current) {
+                        *current = material;
+                    }
+                })
+                .or_insert(material);
+        }
+        Self { materials, by_chebi_id }
+    }
+
+    /// The material with material id or ChEBI id `id`.
+    pub fn resolve(&self, id: &str) -> Option<&'a Material> {
+        self.materials.get(id).or_else(|| self.by_chebi_id.get(id).copied())
+    }
+
+    /// The ids of `bom`'s consumed materials that match no material, sorted. Their costs and
+    /// impacts are left out of the COGS and LCA.
+    pub fn unmatched(&self, bom: &BillOfMaterials) -> Vec<String> {
+        let mut unmatched: Vec<String> =
+            bom.materials_consumed.keys().filter(|id| self.resolve(id).is_none()).cloned().collect();
+        unmatched.sort();
+        unmatched
+    }
+}
+
+/// The cost of `grams` of the material with material id or ChEBI id `id`, or zero if it is unknown.
+pub fn material_cost(materials: &MaterialResolver, id: &str, grams: f64) -> f64 {
+    materials.resolve(id).map_or(0.0, |material| {
+        let cost_per_unit = material
+            .techno_economic_and_lca_profile
+            .lifecycle_stages
+            .manufacturing_and_acquisition
+            .costs
+            .first()
+            .map_or(0.0, |c| c.value_usd);
+        (grams / 1000.0) * cost_per_unit
+    })
+}
+
+/// Adds the energy of one tick of a transfer out of `from_asset_id` during `stage_id`: a
+/// transfer out of another asset than the stage's own runs that asset's pump for the tick.
+fn add_transfer_energy(
+    bom: &mut BillOfMaterials,
+    assets: &HashMap<String, Asset>,
+    methods_by_id: &HashMap<&str, &Method>,
+    stage_id: &str,
+    from_asset_id: &str,
+) {
+    let stage_asset = methods_by_id.get(stage_id).map(|m| m.required_asset_id.as_str());
+    if stage_asset != Some(from_asset_id) {
+        if let Some(power_model) = assets
+            .get(from_asset_id)
+            .and_then(|a| a.operational_parameters.as_ref())
+            .and_then(|p| p.power_model.as_ref())
+        {
+            bom.add_energy(stage_id, power_model.operating_power.value);
+        }
+    }
+}
+
+/// How the duration of a labor requirement scales, as given by the denominator of its unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LaborBasis {
+    /// Once per run of the stage, e.g. `min` or `h`.
+    PerOperation,
+    /// For every operating hour of the stage, e.g. `min/hr_op`.
+    PerOperatingHour,
+    /// For every `liters` of media in the stage's vessel, e.g. `min/10L` or `min/500mL`.
+    PerVolume { liters: f64 },
+    /// For every lot of items handled, e.g. `min/box`, `min/vessel`, or `min/100_images`. A stage
+    /// handles one lot.
+    PerItem,
+}
+
+/// Splits a labor duration unit into the hours per unit of its numerator (`s`, `min`, or `h`) and
+/// the basis its denominator names, or `None` if the numerator is not a time.
+pub fn parse_labor_unit(unit: &str) -> Option<(f64, LaborBasis)> {
+    let (time, per) = match unit.split_once('/') {
+        Some((time, per)) => (time.trim(), Some(per.trim())),
+        None => (unit.trim(), None),
+    };
+    let hours_per_unit = match time {
+        "s" | "sec" => 1.0 / 3600.0,
+        "min" => 1.0 / 60.0,
+        "h" | "hr" | "hours" => 1.0,
+        _ => return None,
+    };
+    let basis = match per {
+        None => LaborBasis::PerOperation,
+        Some("hr_op") => LaborBasis::PerOperatingHour,
+        Some(per) => {
+            let split = per.find(|c: char| !(c.is_ascii_digit() || c == '.')).unwrap_or(per.len());
+            let (count, volume_unit) = per.split_at(split);
+            let count = if count.is_empty() { Some(1.0) } else { count.parse::<f64>().ok() };
+            let liters_per_unit = match volume_unit {
+                "L" | "l" => Some(1.0),
+                "mL" | "ml" => Some(0.001),
+                "m3" => Some(1000.0),
+                _ => None,
+            };
+            match (count, liters_per_unit) {
+                (Some(count), Some(liters)) if count > 0.0 => LaborBasis::PerVolume { liters: count * liters },
+                _ => LaborBasis::PerItem,
+            }
+        }
+    };
+    Some((hours_per_unit, basis))
+}
+
+/// Hours a labor requirement of `duration` takes in a stage of `stage_hours` whose vessel holds
+/// `volume_l` liters, or `None` if its unit is not understood or it scales with an unknown volume.
+pub fn labor_requirement_hours(duration: &Measurement<f64>, stage_hours: f64, volume_l: Option<f64>) -> Option<f64> {
+    let (hours_per_unit, basis) = parse_labor_unit(&duration.unit)?;
+    let repetitions = match basis {
+        LaborBasis::PerOperation | LaborBasis::PerItem => 1.0,
+        LaborBasis::PerOperatingHour => stage_hours,
+        LaborBasis::PerVolume { liters } => volume_l? / liters,
+    };
+    Some(duration.value * hours_per_unit * repetitions)
+}
+
+/// Adds the labor an asset requires for a stage of `stage_ticks` hours with `volume_l` liters of
+/// media to the BOM. Requirements that cannot be converted to hours are reported and left out.
+fn add_labor_hours(bom: &mut BillOfMaterials, asset: &Asset, stage_id: &str, stage_ticks: u64, volume_l: Option<f64>) {
+    let Some(labor_reqs) = asset
+        .operational_parameters
+        .as_ref()
+        .and_then(|params| params.labor_requirements.as_ref())
+    else {
+        return;
+    };
+    for req in labor_reqs {
+        let Some(hours) = labor_requirement_hours(&req.duration, stage_ticks as f64 * TIME_STEP_HR, volume_l) else {
+            warn!(
+                "Labor task '{}' of asset '{}' has duration unit '{}', which is not a time or scales with an unknown media volume; its hours are not counted",
+                req.linked_task_id, asset.asset_id, req.duration.unit
+            );
+            continue;
+        };
+        bom.add_labor_task(&LaborTask {
+            stage_id: stage_id.to_string(),
+            role_id: req.required_role_id.clone(),
+            task_id: req.linked_task_id.clone(),
+            task_description: req.task_description.clone(),
+            hours,
+        });
+    }
+}
+
+/// Adds the QC checks of `method` to the BOM of a stage of `stage_ticks` hours that ran it: each
+/// check runs once per stage, its labor is added to the stage's labor and its consumables to the
+/// stage's materials. Labor that cannot be converted to hours is reported and left out, and
+/// consumables that match no material are not costed.
+fn add_qc_assays(bom: &mut BillOfMaterials, materials: &MaterialResolver, method: &Method, stage_id: &str, stage_ticks: u64) {
+    for check in &method.qc_checks {
+        let mut assay = QcAssay {
+            stage_id: stage_id.to_string(),
+            qc_method_id: check.method_id.clone(),
+            timing: check.timing.clone(),
+            role_id: None,
+            labor_hours: 0.0,
+            materials_consumed: HashMap::new(),
+            cost_usd: check.cost_usd.unwrap_or(0.0),
+        };
+        if let (Some(role_id), Some(duration)) = (&check.required_role_id, &check.duration) {
+            match labor_requirement_hours(duration, stage_ticks as f64 * TIME_STEP_HR, None) {
+                Some(hours) => {
+                    bom.add_labor_task(&LaborTask {
+                        stage_id: stage_id.to_string(),
+                        role_id: role_id.clone(),
+                        task_id: check.method_id.clone(),
+                        task_description: format!("QC assay ({})", check.timing),
+                        hours,
+                    });
+                    assay.role_id = Some(role_id.clone());
+                    assay.labor_hours = hours;
+                }
+                None => warn!(
+                    "QC check '{}' of method '{}' has duration unit '{}', which is not a time; its hours are not counted",
+                    check.method_id, method.method_id, duration.unit
+                ),
+            }
+        }
+        for consumable in &check.consumables {
+            if let Some(material) = materials.resolve(&consumable.material_id) {
+                bom.add_material(stage_id, None, &material.material_id, consumable.grams);
+                *assay.materials_consumed.entry(material.material_id.clone()).or_insert(0.0) += consumable.grams;
+            }
+        }
+        bom.qc_assays.push(assay);
+    }
+}
+
+/// Whether any labor requirement of the assets `process` uses scales with the media volume,
+/// which only the rows of a log record, not its events.
+fn needs_media_volumes(process: &Process, assets: &HashMap<String, Asset>) -> bool {
+    process
+        .methods
+        .iter()
+        .filter_map(|method| assets.get(&method.required_asset_id))
+        .filter_map(|asset| asset.operational_parameters.as_ref()?.labor_requirements.as_ref())
+        .flatten()
+        .any(|req| matches!(parse_labor_unit(&req.duration.unit), Some((_, LaborBasis::PerVolume { .. }))))
+}
+
+/// An organism counts as no longer growing once its specific growth rate falls below this
+/// fraction of its peak.
+pub const STATIONARY_GROWTH_FRACTION: f64 = 0.05;
+
+/// Growth milestones of a run, derived from its logged specific growth rates.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GrowthMetrics {
+    /// Highest specific growth rate of each organism over the run, in 1/h.
+    pub peak_specific_growth_rates: BTreeMap<String, f64>,
+    /// Hours from the start of the run to the start of its final stationary phase: the first
+    /// tick from which every organism stays below [`STATIONARY_GROWTH_FRACTION`] of its peak
+    /// rate. `None` if the culture never grew or was still growing when the run ended.
+    pub time_to_stationary_hr: Option<f64>,
+}
+
+/// Computes the growth milestones of `organism_ids` from a run's records. Wide logs are read from
+/// their `mu_` columns; logs written before derived metrics yield no rates.
+pub fn growth_metrics(records: &[LogRecord], organism_ids: &[String]) -> Result<GrowthMetrics, BioforgeError> {
+    let mut metrics = GrowthMetrics::default();
+    let mut stationary_since: Option<u64> = None;
+
+    for record in records {
+        let rates: BTreeMap<String, f64> = if !record.series.is_empty() {
+            organism_ids
+                .iter()
+                .filter_map(|id| record.series.get(&series_column("mu", id)).map(|rate| (id.clone(), *rate)))
+                .collect()
+        } else if record.growth_rates_json.is_empty() {
+            BTreeMap::new()
+        } else {
+            let mut rates: BTreeMap<String, f64> = serde_json::from_str(&record.growth_rates_json)?;
+            rates.retain(|id, _| organism_ids.contains(id));
+            rates
+        };
+        if rates.is_empty() {
+            continue;
+        }
+
+        for (id, rate) in &rates {
+            let peak = metrics.peak_specific_growth_rates.entry(id.clone()).or_insert(*rate);
+            *peak = peak.max(*rate);
+        }
+        let peaks = &metrics.peak_specific_growth_rates;
+        let growing = rates
+            .iter()
+            .any(|(id, rate)| peaks[id] > 0.0 && *rate >= STATIONARY_GROWTH_FRACTION * peaks[id]);
+        if growing {
+            stationary_since = None;
+        } else if stationary_since.is_none() && peaks.values().any(|peak| *peak > 0.0) {
+            stationary_since = Some(record.tick);
+        }
+    }
+    metrics.time_to_stationary_hr = stationary_since.map(|tick| tick as f64 * TIME_STEP_HR);
+    Ok(metrics)
+}
+
+pub fn calculate_cogs(
+    bom: &BillOfMaterials,
+    materials: &HashMap<String, Material>,
+    labor_roles: &HashMap<String, LaborRole>,
+    assets: &HashMap<String, Asset>,
+    assumptions: &EconomicAssumptions,
+) -> Result<CogsResult, BioforgeError> {
+    let basis = CostBasis { materials: MaterialResolver::new(materials), labor_roles, assets, assumptions };
+    let mut result = basis.cost_of(
+        &bom.materials_consumed,
+        &bom.labor_hours,
+        bom.total_energy_kwh,
+        &bom.asset_ticks(),
+        &bom.waste_generated,
+        bom.qc_assays.iter(),
+    );
+    result.by_stage = bom
+        .by_stage
+        .iter()
+        .map(|(stage_id, stage)| {
+            let asset_ticks = stage.asset_id.iter().map(|id| (id.clone(), stage.ticks)).collect();
+            let cogs = basis.cost_of(
+                &stage.materials_consumed,
+                &stage.labor_hours,
+                stage.energy_kwh,
+                &asset_ticks,
+                &stage.waste_generated,
+                bom.qc_assays.iter().filter(|assay| &assay.stage_id == stage_id),
+            );
+            (stage_id.clone(), cogs)
+        })
+        .collect();
+    result.material_costs_by_consumer = bom
+        .materials_by_consumer
+        .iter()
+        .map(|(consumer_id, consumed)| {
+            let cost = consumed.iter().map(|(id, grams)| material_cost(&basis.materials, id, *grams)).sum();
+            (consumer_id.clone(), cost)
+        })
+        .collect();
+    Ok(result)
+}
+
+/// Splits a run's BOM, COGS, and LCA between `products`: each product bears its own stages, and
+/// the remaining shared quantities are allocated as `basis` prescribes. Shares that cannot be
+/// weighed, such as a mass allocation when nothing was produced, are split evenly.
+pub fn allocate_coproducts(
+    bom: &BillOfMaterials,
+    cogs: &CogsResult,
+    lca: &LcaResult,
+    products: &[ProductOutput],
+    basis: &CostAllocation,
+) -> Result<AllocationReport, BioforgeError> {
+    let stage_sets: Vec<HashSet<&str>> =
+        products.iter().map(|p| p.stage_ids.iter().map(String::as_str).collect()).collect();
+    let mut claims: HashMap<&str, usize> = HashMap::new();
+    for stage_id in stage_sets.iter().flatten() {
+        *claims.entry(stage_id).or_insert(0) += 1;
+    }
+
+    let empty_cogs = CogsResult::default();
+    let empty_lca = LcaResult::default();
+    let direct: Vec<AllocatedQuantities> = stage_sets
+        .iter()
+        .map(|stage_ids| {
+            let mut direct = AllocatedQuantities::default();
+            for stage_id in stage_ids.iter().filter(|id| claims.get(*id) == Some(&1)) {
+                if let Some(stage) = bom.by_stage.get(*stage_id) {
+                    let stage_cogs = cogs.by_stage.get(*stage_id).unwrap_or(&empty_cogs);
+                    let stage_lca = lca.by_stage.get(*stage_id).unwrap_or(&empty_lca);
+                    direct.add_scaled(&AllocatedQuantities::of_stage(stage, stage_cogs, stage_lca), 1.0);
+                }
+            }
+            direct
+        })
+        .collect();
+
+    let run_bom = StageBom {
+        materials_consumed: bom.materials_consumed.clone(),
+        energy_kwh: bom.total_energy_kwh,
+        ..Default::default()
+    };
+    let mut shared = AllocatedQuantities::of_stage(&run_bom, cogs, lca);
+    for quantities in &direct {
+        shared.add_scaled(quantities, -1.0);
+    }
+
+    let weights = products.iter().map(|p| basis.weight(p)).collect::<Result<Vec<_>, _>>()?;
+    let total_weight: f64 = weights.iter().sum();
+
+    let products = products
+        .iter()
+        .zip(direct)
+        .zip(weights)
+        .map(|((product, direct), weight)| {
+            let share = if total_weight > 0.0 { weight / total_weight } else { 1.0 / products.len() as f64 };
+            let mut allocated = AllocatedQuantities::default();
+            allocated.add_scaled(&shared, share);
+            let mut total = direct.clone();
+            total.add_scaled(&allocated, 1.0);
+            ProductAllocation {
+                molecule_name: product.molecule_name.clone(),
+                produced_grams: product.produced_grams,
+                share,
+                cost_per_gram: (product.produced_grams > 0.0).then(|| total.cogs / product.produced_grams),
+                direct,
+                allocated,
+                total,
+            }
+        })
+        .collect();
+
+    Ok(AllocationReport {
+        basis: basis.clone(),
+        shared,
+        products,
+        impact_units: lca.impacts.iter().map(|(metric, (_, unit))| (metric.clone(), unit.clone())).collect(),
+    })
+}
+
+/// The prices and assumptions quantities are costed with.
+struct CostBasis<'a> {
+    materials: MaterialResolver<'a>,
+    labor_roles: &'a HashMap<String, LaborRole>,
+    assets: &'a HashMap<String, Asset>,
+    assumptions: &'a EconomicAssumptions,
+}
+
+impl CostBasis<'_> {
+    /// Costs the given quantities, charging each asset of `asset_ticks` depreciation,
+    /// maintenance, and end-of-life costs for the hours it was occupied. The labor and
+    /// consumables of `qc_assays`, part of the quantities, are moved to the QC costs.
+    fn cost_of<'b>(
+        &self,
+        materials_consumed: &HashMap<String, f64>,
+        labor_hours: &HashMap<String, f64>,
+        energy_kwh: f64,
+        asset_ticks: &HashMap<String, u64>,
+        waste_generated: &HashMap<String, f64>,
+        qc_assays: impl Iterator<Item = &'b QcAssay>,
+    ) -> CogsResult {
+        let CostBasis { materials, labor_roles, assets, assumptions } = self;
+        let mut result = CogsResult::default();
+        let cost_per_kwh = assumptions.electricity_price_per_kwh;
+        let hours_per_year = assumptions.operating_hours_per_year;
+
+        for (material_id, quantity) in materials_consumed {
+            result.material_costs += material_cost(materials, material_id, *quantity);
+        }
+
+        for (role_id, hours) in labor_hours {
+            if let Some(role) = labor_roles.get(role_id) {
+                let cost = hours * role.techno_economic_profile.cost_per_hour_usd;
+                result.labor_costs += cost;
+                *result.labor_costs_by_role.entry(role_id.clone()).or_insert(0.0) += cost;
+            }
+        }
+
+        for (asset_id, ticks) in asset_ticks {
+            let occupied_hours = *ticks as f64;
+            if let Some(tea) = assets.get(asset_id).and_then(|asset| asset.techno_economic_and_lca_profile.as_ref()) {
+                let lifespan_years = tea.expected_lifespan.as_ref().map_or(1, |l| l.value) as f64;
+                if let Some(capex) = tea.lifecycle_stages.manufacturing_and_acquisition.costs.iter().find(|c| c.cost_type == "capex") {
+                    let annual_depreciation = capex.value_usd / lifespan_years;
+                    result.asset_depreciation_costs += (annual_depreciation / hours_per_year) * occupied_hours;
+                }
+                if let Some(maintenance_cost) = tea.lifecycle_stages.maintenance.costs.iter().find(|c| c.cost_type == "opex_per_year") {
+                    result.maintenance_costs += (maintenance_cost.value_usd / hours_per_year) * occupied_hours;
+                }
+                let end_of_life: f64 = tea.lifecycle_stages.end_of_life.costs.iter().map(|c| c.value_usd).sum();
+                result.end_of_life_costs += (end_of_life / lifespan_years / hours_per_year) * occupied_hours;
+            }
+        }
+
+        // Disposal costs are per kg of waste, like purchase costs.
+        for (waste_id, grams) in waste_generated {
+            if let Some(material) = materials.resolve(waste_id) {
+                let disposal: f64 = material.techno_economic_and_lca_profile.lifecycle_stages.end_of_life.costs.iter().map(|c| c.value_usd).sum();
+                result.end_of_life_costs += (grams / 1000.0) * disposal;
+            }
+        }
+
+        for assay in qc_assays {
+            let rate = assay.role_id.as_ref().and_then(|id| labor_roles.get(id)).map_or(0.0, |role| role.techno_economic_profile.cost_per_hour_usd);
+            let labor_cost = assay.labor_hours * rate;
+            let consumables_cost: f64 = assay.materials_consumed.iter().map(|(id, grams)| material_cost(materials, id, *grams)).sum();
+            result.labor_costs -= labor_cost;
+            result.material_costs -= consumables_cost;
+            result.qc_costs += assay.cost_usd + labor_cost + consumables_cost;
+        }
+
+        result.energy_costs = energy_kwh * cost_per_kwh;
+        result.total_cogs = result.material_costs + result.labor_costs + result.energy_costs + result.asset_depreciation_costs + result.maintenance_costs + result.end_of_life_costs + result.qc_costs;
+
+        result
+    }
+}
+
+pub fn calculate_lca(
+    bom: &BillOfMaterials,
+    materials: &HashMap<String, Material>,
+    assets: &HashMap<String, Asset>,
+    assumptions: &EconomicAssumptions,
+) -> Result<LcaResult, BioforgeError> {
+    let basis = ImpactBasis { materials: MaterialResolver::new(materials), assets, assumptions };
+    let mut result = basis.impacts_of(&bom.materials_consumed, &bom.waste_generated, &bom.asset_ticks(), bom.total_energy_kwh);
+    result.by_stage = bom
+        .by_stage
+        .iter()
+        .map(|(stage_id, stage)| {
+            let asset_ticks = stage.asset_id.iter().map(|id| (id.clone(), stage.ticks)).collect();
+            let lca = basis.impacts_of(&stage.materials_consumed, &stage.waste_generated, &asset_ticks, stage.energy_kwh);
+            (stage_id.clone(), lca)
+        })
+        .collect();
+
+    result.unmatched_materials = basis.materials.unmatched(bom);
+    if !result.unmatched_materials.is_empty() {
+        warn!(
+            "No material found for BOM entries {}; their impacts are not counted",
+            result.unmatched_materials.join(", ")
+        );
+    }
+
+    Ok(result)
+}
+
+/// The impact factors quantities are assessed with.
+struct ImpactBasis<'a> {
+    materials: MaterialResolver<'a>,
+    assets: &'a HashMap<String, Asset>,
+    assumptions: &'a EconomicAssumptions,
+}
+
+impl ImpactBasis<'_> {
+    /// Assesses the given quantities, charging each asset of `asset_ticks` use-phase and
+    /// end-of-life impacts for the hours it was occupied.
+    fn impacts_of(
+        &self,
+        materials_consumed: &HashMap<String, f64>,
+        waste_generated: &HashMap<String, f64>,
+        asset_ticks: &HashMap<String, u64>,
+        energy_kwh: f64,
+    ) -> LcaResult {
+        let ImpactBasis { materials, assets, assumptions } = self;
+        let mut result = LcaResult::default();
+        let hours_per_year = assumptions.operating_hours_per_year;
+
+        // Material impacts are per kg, like purchase costs, while quantities are in grams.
+        for (material_id, grams) in materials_consumed {
+            if let Some(material) = materials.resolve(material_id) {
+                let impacts = &material.techno_economic_and_lca_profile.lifecycle_stages.manufacturing_and_acquisition.impacts;
+                for impact in impacts {
+                    result.add(&impact.metric, (grams / 1000.0) * impact.value, &impact.unit);
+                }
+            }
+        }
+
+        // Disposal impacts are per kg of waste, like disposal costs.
+        for (waste_id, grams) in waste_generated {
+            if let Some(material) = materials.resolve(waste_id) {
+                for impact in &material.techno_economic_and_lca_profile.lifecycle_stages.end_of_life.impacts {
+                    if is_additive(&impact.unit) {
+                        result.add(&impact.metric, (grams / 1000.0) * impact.value, &impact.unit);
+                    }
+                }
+            }
+        }
+
+        // Use-phase impacts are given per year (e.g. `gwp_per_year`) and charged only for the
+        // hours each asset was occupied. End-of-life impacts are amortized over the asset's lifespan.
+        for (asset_id, ticks) in asset_ticks {
+            let occupied_hours = *ticks as f64;
+            if let Some(tea) = assets.get(asset_id).and_then(|asset| asset.techno_economic_and_lca_profile.as_ref()) {
+                for impact in &tea.lifecycle_stages.use_and_operation.impacts {
+                    if let Some(metric) = impact.metric.strip_suffix("_per_year") {
+                        result.add(metric, (impact.value / hours_per_year) * occupied_hours, &impact.unit);
+                    }
+                }
+                let lifespan_hours = tea.expected_lifespan.as_ref().map_or(1, |l| l.value) as f64 * hours_per_year;
+                for impact in &tea.lifecycle_stages.end_of_life.impacts {
+                    if is_additive(&impact.unit) {
+                        result.add(&impact.metric, (impact.value / lifespan_hours) * occupied_hours, &impact.unit);
+                    }
+                }
+            }
+        }
+
+        for factor in assumptions.grid_impact_factors() {
+            result.add(&factor.metric, energy_kwh * factor.value, &factor.unit);
+        }
+
+        result
+    }
+}
+
+/// Builds the blueprint of `process`, taking each step's duration from its `TimeInStage` rule or
+/// else its `expected_duration_ticks`. Steps with neither are marked
+/// [`DurationSource::Dynamic`].
+pub fn generate_blueprint(
+    process: &Process,
+    rules: &HashMap<String, Rule>,
+) -> Result<ExecutableBlueprint, BioforgeError> {
+    blueprint_with_observed_durations(process, rules, &HashMap::new())
+}
+
+/// Like [`generate_blueprint`], falling back to the duration each stage actually ran in the run
+/// logged at `log_path` before marking it dynamic.
+pub fn generate_blueprint_from_run(
+    process: &Process,
+    rules: &HashMap<String, Rule>,
+    log_path: &str,
+) -> Result<ExecutableBlueprint, BioforgeError> {
+    generate_blueprint_from_records(process, rules, &read_log(log_path)?)
+}
+
+/// Like [`generate_blueprint_from_run`], from records already in memory.
+pub fn generate_blueprint_from_records(
+    process: &Process,
+    rules: &HashMap<String, Rule>,
+    records: &[LogRecord],
+) -> Result<ExecutableBlueprint, BioforgeError> {
+    // Row t shows the stage that ran tick t, so each stage ran for as many ticks as it has rows.
+    let mut observed: HashMap<String, u64> = HashMap::new();
+    for record in records.iter().filter(|record| record.stage_id != "INITIAL") {
+        *observed.entry(record.stage_id.clone()).or_insert(0) += 1;
+    }
+    blueprint_with_observed_durations(process, rules, &observed)
+}
+
+fn blueprint_with_observed_durations(
+    process: &Process,
+    rules: &HashMap<String, Rule>,
+    observed_ticks: &HashMap<String, u64>,
+) -> Result<ExecutableBlueprint, BioforgeError> {
+    let mut workflow = Vec::new();
+
+    for (i, method_id) in process.default_workflow.iter().enumerate() {
+        let method = process
+            .methods
+            .iter()
+            .find(|m| m.method_id == *method_id)
+            .ok_or_else(|| BioforgeError::MethodNotFound(method_id.clone()))?;
+
+        let rule_ticks = method.required_rule_ids.iter().flatten().find_map(|id| match rules.get(id)?.condition {
+            bioforge_schemas::rule::Condition::TimeInStage { ticks } => Some(ticks),
+            _ => None,
+        });
+        let (duration_ticks, duration_source) = if let Some(ticks) = rule_ticks {
+            (ticks, DurationSource::TimeInStageRule)
+        } else if let Some(ticks) = method.expected_duration_ticks {
+            (ticks, DurationSource::ExpectedDuration)
+        } else if let Some(ticks) = observed_ticks.get(method_id) {
+            (*ticks, DurationSource::ObservedRun)
+        } else {
+            (0, DurationSource::Dynamic)
+        };
+
+        let step = BlueprintStep {
+            step: i + 1,
+            method_id: method.method_id.clone(),
+            stage: method.stage.clone(),
+            technique: method.technique.clone(),
+            asset_id: method.required_asset_id.clone(),
+            duration_ticks,
+            duration_source,
+            control_parameters: method.operating_parameters.clone(),
+            transition_rules: method.required_rule_ids.iter().flatten().filter_map(|id| rules.get(id).cloned()).collect(),
+            required_materials: method
+                .required_materials
+                .iter()
+                .map(|m| BlueprintMaterial { r#type: m.r#type.clone(), id: m.id.clone(), estimated_grams: None })
+                .collect(),
+            qc_checks: method.qc_checks.clone(),
+            transfers: method.transfers.clone(),
+        };
+        workflow.push(step);
+    }
+
+    Ok(ExecutableBlueprint {
+        process_id: process.process_id.clone(),
+        process_name: process.process_name.clone(),
+        workflow,
+    })
+}
+
+/// Estimates the resources of a purely time-gated run without simulating it.
+///
+/// Each blueprint step is assumed to last exactly its duration, with its asset drawing nominal
+/// operating power for every tick; [`DurationSource::Dynamic`] steps contribute nothing. The
+/// total ticks count the initial state, as those of a logged run do. Material consumption
+/// depends on the biology and is not estimated, so the returned COGS covers energy, labor,
+/// depreciation, and maintenance.
+pub fn estimate_resources(
+    blueprint: &ExecutableBlueprint,
+    assets: &HashMap<String, Asset>,
+    labor_roles: &HashMap<String, LaborRole>,
+    assumptions: &EconomicAssumptions,
+) -> Result<(BillOfMaterials, CogsResult), BioforgeError> {
+    let mut bom = BillOfMaterials {
+        total_ticks: blueprint.workflow.iter().map(|step| step.duration_ticks).sum::<u64>() + 1,
+        ..Default::default()
+    };
+
+    for step in blueprint.workflow.iter().filter(|step| step.duration_source != DurationSource::Dynamic) {
+        let asset = assets
+            .get(&step.asset_id)
+            .ok_or_else(|| BioforgeError::AssetNotFound(step.asset_id.clone()))?;
+        if let Some(power_model) = asset
+            .operational_parameters
+            .as_ref()
+            .and_then(|params| params.power_model.as_ref())
+        {
+            bom.add_energy(&step.method_id, power_model.operating_power.value * step.duration_ticks as f64);
+        }
+        add_labor_hours(&mut bom, asset, &step.method_id, step.duration_ticks, None);
+        bom.add_stage_ticks(&step.method_id, &step.asset_id, step.duration_ticks);
+    }
+
+    let cogs = calculate_cogs(&bom, &HashMap::new(), labor_roles, assets, assumptions)?;
+    Ok((bom, cogs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A purchased material costing `cost_per_kg` with a `gwp` of `gwp_per_kg`, and a disposal
+    /// cost and `gwp` when it leaves the process as waste.
+    pub(super) fn material(id: &str, cost_per_kg: f64, gwp_per_kg: f64, disposal_per_kg: f64, disposal_gwp_per_kg: f64) -> Material {
+        serde_yaml::from_str(&format!(
+            r#"
+material_id: {id}
+material_name: {id}
+material_class: Chemical
+material_subtype: Reagent
+material_category: PurchasedRawMaterial
+unit: kg
+metadata:
+  process_role: Test
+  vendor: null
+  part_number: null
+  notes: null
+  identifiers: null
+specifications: []
+formulation: null
+techno_economic_and_lca_profile:
+  lifecycle_stages:
+    manufacturing_and_acquisition:
+      costs:
+      - cost_type: purchase_cost
+        value_usd: {cost_per_kg}
+      impacts:
+      - metric: gwp
+        value: {gwp_per_kg}
+        unit: kg_CO2e
+    use_and_operation:
+      costs: []
+      impacts: []
+    maintenance:
+      costs: []
+      impacts: []
+    end_of_life:
+      costs:
+      - cost_type: disposal_cost
+        value_usd: {disposal_per_kg}
+      impacts:
+      - metric: gwp
+        value: {disposal_gwp_per_kg}
+        unit: kg_CO2e
+      - metric: recycling_rate
+        value: 40.0
+        unit: "%"
+"#
+        ))
+        .unwrap()
+    }
+
+    pub(super) fn materials(entries: Vec<Material>) -> HashMap<String, Material> {
+        entries.into_iter().map(|m| (m.material_id.clone(), m)).collect()
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "expected {expected}, got {actual}");
+    }
+
+    #[test]
+    fn lca_charges_material_impacts_per_kg() {
+        let materials = materials(vec![material("GLUCOSE", 0.5, 1.4, 0.0, 0.0), material("YEAST-EXTRACT", 6.0, 3.2, 0.0, 0.0)]);
+        let mut bom = BillOfMaterials::default();
+        bom.materials_consumed.insert("GLUCOSE".to_string(), 2500.0);
+        bom.materials_consumed.insert("YEAST-EXTRACT".to_string(), 125.0);
+        bom.total_energy_kwh = 10.0;
+
+        let lca = calculate_lca(&bom, &materials, &HashMap::new(), &EconomicAssumptions::default()).unwrap();
+
+        // 2.5 kg * 1.4 + 0.125 kg * 3.2 + 10 kWh * 0.4 = 3.5 + 0.4 + 4.0
+        assert_close(lca.gwp_kg_co2e(), 7.9);
+        // 10 kWh * 8 MJ/kWh from the grid; the materials carry no fossil depletion.
+        assert_close(lca.adp_fossil_mj(), 80.0);
+        assert!(lca.unmatched_materials.is_empty());
+    }
+
+    #[test]
+    fn waste_is_costed_and_assessed_per_kg() {
+        let materials = materials(vec![material("SPENT-MEDIA", 0.2, 0.9, 0.35, 0.05)]);
+        let mut bom = BillOfMaterials::default();
+        bom.waste_generated.insert("SPENT-MEDIA".to_string(), 4000.0);
+        let assumptions = EconomicAssumptions::default();
+
+        let cogs = calculate_cogs(&bom, &materials, &HashMap::new(), &HashMap::new(), &assumptions).unwrap();
+        let lca = calculate_lca(&bom, &materials, &HashMap::new(), &assumptions).unwrap();
+
+        // 4 kg of waste at $0.35/kg and 0.05 kg CO2e/kg; the `%` recycling rate is not summed.
+        assert_close(cogs.end_of_life_costs, 1.4);
+        assert_close(cogs.material_costs, 0.0);
+        assert_close(lca.gwp_kg_co2e(), 0.2);
+        assert!(!lca.impacts.contains_key("recycling_rate"));
+    }
+
+    /// An asset bought for `capex` and lasting ten years, with `opex` of yearly maintenance, a
+    /// yearly use-phase GWP, and an end-of-life GWP.
+    pub(super) fn asset(id: &str, capex: f64, opex: f64) -> Asset {
+        serde_yaml::from_str(&format!(
+            r#"
+asset_id: {id}
+display_name: null
+asset_type: TEST
+group: null
+description: null
+connection_points: null
+operational_parameters: null
+techno_economic_and_lca_profile:
+  expected_lifespan: {{ value: 10, unit: years }}
+  lifecycle_stages:
+    manufacturing_and_acquisition:
+      costs: [{{ cost_type: capex, value_usd: {capex} }}]
+      impacts: []
+    use_and_operation:
+      costs: []
+      impacts: [{{ metric: gwp_per_year, value: 876.0, unit: kg_CO2e }}]
+    maintenance:
+      costs: [{{ cost_type: opex_per_year, value_usd: {opex} }}]
+      impacts: []
+    end_of_life:
+      costs: [{{ cost_type: disposal_cost, value_usd: 2000.0 }}]
+      impacts: [{{ metric: gwp, value: 876.0, unit: kg_CO2e }}]
+"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn an_asset_no_stage_occupies_adds_no_cost_or_impact() {
+        let mut bom = BillOfMaterials::default();
+        bom.add_stage_ticks("MTHD-CULT", "REACTOR", 48);
+        bom.total_ticks = 48;
+        let assumptions = EconomicAssumptions::default();
+        let mut assets: HashMap<String, Asset> = HashMap::from([("REACTOR".to_string(), asset("REACTOR", 87600.0, 8760.0))]);
+        let cogs = calculate_cogs(&bom, &HashMap::new(), &HashMap::new(), &assets, &assumptions).unwrap();
+        let lca = calculate_lca(&bom, &HashMap::new(), &assets, &assumptions).unwrap();
+        // $8760 a year over 8760 hours is a dollar an hour of the reactor's 48; its use phase
+        // emits 0.1 kg CO2e an hour, and its end of life 0.01 spread over its ten years.
+        assert_close(cogs.asset_depreciation_costs, 48.0);
+        assert_close(cogs.maintenance_costs, 48.0);
+        assert_close(lca.gwp_kg_co2e(), 48.0 * 0.11);
+
+        assets.insert("CENTRIFUGE".to_string(), asset("CENTRIFUGE", 1_000_000.0, 50_000.0));
+        let with_idle = calculate_cogs(&bom, &HashMap::new(), &HashMap::new(), &assets, &assumptions).unwrap();
+        assert_eq!(with_idle, cogs);
+        assert_eq!(calculate_lca(&bom, &HashMap::new(), &assets, &assumptions).unwrap(), lca);
+    }
+
+    /// A run of 20 g of lutein and 10 g of astaxanthin: a shared cultivation, a downstream stage
+    /// of each product, and initial media charged to no stage, with its COGS and LCA.
+    fn coproduct_run() -> (BillOfMaterials, CogsResult, LcaResult, Vec<ProductOutput>) {
+        let materials = materials(vec![
+            material("GLUCOSE", 0.5, 1.4, 0.0, 0.0),
+            material("HEXANE", 3.0, 2.1, 0.0, 0.0),
+            material("ETHANOL", 1.2, 0.8, 0.0, 0.0),
+        ]);
+        let mut bom = BillOfMaterials::default();
+        bom.materials_consumed.insert("GLUCOSE".to_string(), 1000.0);
+        bom.add_material("MTHD-CULT", Some("ORG-TEST"), "GLUCOSE", 2000.0);
+        bom.add_energy("MTHD-CULT", 40.0);
+        bom.add_material("MTHD-LUT-EXT", None, "HEXANE", 500.0);
+        bom.add_energy("MTHD-LUT-EXT", 5.0);
+        bom.add_material("MTHD-AST-EXT", None, "ETHANOL", 800.0);
+        bom.add_energy("MTHD-AST-EXT", 3.0);
+        let assumptions = EconomicAssumptions::default();
+        let cogs = calculate_cogs(&bom, &materials, &HashMap::new(), &HashMap::new(), &assumptions).unwrap();
+        let lca = calculate_lca(&bom, &materials, &HashMap::new(), &assumptions).unwrap();
+        let product = |name: &str, grams: f64, stage_id: &str| ProductOutput {
+            molecule_name: name.to_string(),
+            produced_grams: grams,
+            stage_ids: vec![stage_id.to_string()],
+        };
+        let products = vec![product("Lutein", 20.0, "MTHD-LUT-EXT"), product("Astaxanthin", 10.0, "MTHD-AST-EXT")];
+        (bom, cogs, lca, products)
+    }
+
+    #[test]
+    fn costs_per_gram_add_back_up_to_the_cogs() {
+        let (bom, cogs, lca, products) = coproduct_run();
+        let economic = CostAllocation::Economic { ratios: HashMap::from([("Lutein".to_string(), 1.0), ("Astaxanthin".to_string(), 3.0)]) };
+        for basis in [CostAllocation::Mass, economic] {
+            let report = allocate_coproducts(&bom, &cogs, &lca, &products, &basis).unwrap();
+            let recovered: f64 = report.products.iter().map(|p| p.cost_per_gram.unwrap() * p.produced_grams).sum();
+            assert_close(recovered, cogs.total_cogs);
+        }
+
+        // By mass, lutein bears two thirds of the cultivation and the initial media besides its
+        // own extraction.
+        let report = allocate_coproducts(&bom, &cogs, &lca, &products, &CostAllocation::Mass).unwrap();
+        let lutein = &report.products[0];
+        let shared = cogs.total_cogs - cogs.by_stage["MTHD-LUT-EXT"].total_cogs - cogs.by_stage["MTHD-AST-EXT"].total_cogs;
+        assert_close(lutein.share, 2.0 / 3.0);
+        assert_close(lutein.cost_per_gram.unwrap(), (cogs.by_stage["MTHD-LUT-EXT"].total_cogs + shared * 2.0 / 3.0) / 20.0);
+
+        // A product that made nothing has no cost per gram, and the others bear the whole COGS.
+        let mut products = products;
+        products[1].produced_grams = 0.0;
+        let report = allocate_coproducts(&bom, &cogs, &lca, &products, &CostAllocation::Mass).unwrap();
+        assert_eq!(report.products[1].cost_per_gram, None);
+        assert_close(report.products[0].share, 1.0);
+    }
+
+    #[test]
+    fn coproduct_splits_add_up_to_the_run_under_every_basis() {
+        let (bom, cogs, lca, products) = coproduct_run();
+        let per_product = |values: [f64; 2]| HashMap::from([("Lutein".to_string(), values[0]), ("Astaxanthin".to_string(), values[1])]);
+        for basis in [
+            CostAllocation::Mass,
+            CostAllocation::EnergyContent { mj_per_kg: per_product([39.0, 35.0]) },
+            CostAllocation::Economic { ratios: per_product([1.0, 3.0]) },
+        ] {
+            let report = allocate_coproducts(&bom, &cogs, &lca, &products, &basis).unwrap();
+            let mut summed = AllocatedQuantities::default();
+            for product in &report.products {
+                summed.add_scaled(&product.total, 1.0);
+            }
+            assert_close(report.products.iter().map(|p| p.share).sum(), 1.0);
+            assert_close(summed.cogs, cogs.total_cogs);
+            assert_close(summed.energy_kwh, bom.total_energy_kwh);
+            assert_eq!(summed.materials_consumed.len(), bom.materials_consumed.len());
+            for (material_id, grams) in &bom.materials_consumed {
+                assert_close(summed.materials_consumed[material_id], *grams);
+            }
+            assert_eq!(summed.impacts.len(), lca.impacts.len());
+            for (metric, (value, _)) in &lca.impacts {
+                assert_close(summed.impacts[metric], *value);
+            }
+            // Each product's own extraction is charged to it alone.
+            assert_close(report.products[0].direct.materials_consumed["HEXANE"], 500.0);
+            assert!(!report.products[1].direct.materials_consumed.contains_key("HEXANE"));
+        }
+    }
+
+    #[test]
+    fn scaled_out_batches_release_their_pooled_product_once() {
+        let assay = |timing: &str, method_id: &str| QcAssay {
+            stage_id: "MTHD-PACK".to_string(),
+            qc_method_id: method_id.to_string(),
+            timing: timing.to_string(),
+            role_id: Some("ROLE-QC".to_string()),
+            labor_hours: 2.0,
+            materials_consumed: HashMap::from([("STANDARD".to_string(), 1.0)]),
+            cost_usd: 50.0,
+        };
+        let mut batch = BillOfMaterials::default();
+        batch.add_material("MTHD-CULT", Some("ORG-TEST"), "GLUCOSE", 1000.0);
+        batch.add_energy("MTHD-CULT", 10.0);
+        batch.add_stage_ticks("MTHD-CULT", "REACTOR", 48);
+        batch.total_ticks = 48;
+        for assay in [assay("in-process_control", "QC-IPC"), assay(RELEASE_QC_TIMING, "QC-RELEASE")] {
+            for (material_id, grams) in &assay.materials_consumed {
+                batch.add_material(&assay.stage_id, None, material_id, *grams);
+            }
+            batch.add_labor_task(&LaborTask {
+                stage_id: assay.stage_id.clone(),
+                role_id: "ROLE-QC".to_string(),
+                task_id: assay.qc_method_id.clone(),
+                task_description: "QC assay".to_string(),
+                hours: assay.labor_hours,
+            });
+            batch.qc_assays.push(assay);
+        }
+
+        let scaled = batch.scaled_out(3);
+        assert_close(scaled.materials_consumed["GLUCOSE"], 3000.0);
+        assert_close(scaled.materials_by_consumer["ORG-TEST"]["GLUCOSE"], 3000.0);
+        assert_close(scaled.total_energy_kwh, 30.0);
+        assert_eq!(scaled.total_ticks, 144);
+        assert_eq!(scaled.asset_ticks()["REACTOR"], 144);
+        // Three in-process assays, and one release of the pooled product.
+        assert_close(scaled.materials_consumed["STANDARD"], 4.0);
+        assert_close(scaled.by_stage["MTHD-PACK"].materials_consumed["STANDARD"], 4.0);
+        assert_close(scaled.labor_hours["ROLE-QC"], 8.0);
+        assert_close(scaled.by_stage["MTHD-PACK"].labor_hours["ROLE-QC"], 8.0);
+        let task_hours = |task_id: &str| scaled.labor_tasks.iter().find(|t| t.task_id == task_id).unwrap().hours;
+        assert_close(task_hours("QC-IPC"), 6.0);
+        assert_close(task_hours("QC-RELEASE"), 2.0);
+        let timings: Vec<&str> = scaled.qc_assays.iter().map(|a| a.timing.as_str()).collect();
+        assert_eq!(timings, ["in-process_control", RELEASE_QC_TIMING, "in-process_control", "in-process_control"]);
+
+        assert_eq!(batch.scaled_out(1), batch);
+    }
+
+    #[test]
+    fn chebi_ids_resolve_to_the_pure_substance() {
+        let with_chebi_id = |mut material: Material, formulation: Option<&str>| {
+            material.metadata.identifiers = serde_yaml::from_str("cas_number: 64-17-5\nchebi_id: CHEBI:16236\npubchem_cid: '702'").unwrap();
+            material.formulation = formulation.map(|yaml| serde_yaml::from_str(yaml).unwrap());
+            material
+        };
+        let solution = "formulation_type: Solution\nsolvent_id: WATER\ncomponents:\n- component_id: ETHANOL\n  value: 70.0\n  unit: '%_by_weight'";
+        let materials = materials(vec![
+            with_chebi_id(material("A-ETHANOL-70", 2.2, 1.0, 0.0, 0.0), Some(solution)),
+            with_chebi_id(material("ETHANOL", 1.1, 1.2, 0.0, 0.0), None),
+        ]);
+        let resolver = MaterialResolver::new(&materials);
+
+        assert_eq!(resolver.resolve("CHEBI:16236").unwrap().material_id, "ETHANOL");
+        assert_eq!(resolver.resolve("A-ETHANOL-70").unwrap().material_id, "A-ETHANOL-70");
+        assert!(resolver.resolve("CHEBI:15377").is_none());
+    }
+
+    #[test]
+    fn labor_units_split_into_hours_and_basis() {
+        let parse = |unit: &str| parse_labor_unit(unit).unwrap();
+        assert_eq!(parse("s"), (1.0 / 3600.0, LaborBasis::PerOperation));
+        assert_eq!(parse("min"), (1.0 / 60.0, LaborBasis::PerOperation));
+        assert_eq!(parse("h"), (1.0, LaborBasis::PerOperation));
+        assert_eq!(parse("hr"), (1.0, LaborBasis::PerOperation));
+        assert_eq!(parse("min/hr_op"), (1.0 / 60.0, LaborBasis::PerOperatingHour));
+        assert_eq!(parse("min/L"), (1.0 / 60.0, LaborBasis::PerVolume { liters: 1.0 }));
+        assert_eq!(parse("min/10L"), (1.0 / 60.0, LaborBasis::PerVolume { liters: 10.0 }));
+        assert_eq!(parse("min/500mL"), (1.0 / 60.0, LaborBasis::PerVolume { liters: 0.5 }));
+        assert_eq!(parse("h/2m3"), (1.0, LaborBasis::PerVolume { liters: 2000.0 }));
+        assert_eq!(parse("min/box"), (1.0 / 60.0, LaborBasis::PerItem));
+        assert_eq!(parse("min/100_images"), (1.0 / 60.0, LaborBasis::PerItem));
+        // A volume of nothing cannot scale labor.
+        assert_eq!(parse("min/0L"), (1.0 / 60.0, LaborBasis::PerItem));
+        assert_eq!(parse_labor_unit("kg"), None);
+        assert_eq!(parse_labor_unit("kg/L"), None);
+    }
+
+    #[test]
+    fn labor_hours_scale_with_their_basis() {
+        let duration = |value: f64, unit: &str| Measurement { value, unit: unit.to_string() };
+        // Once per operation, whatever the stage's length or volume.
+        assert_eq!(labor_requirement_hours(&duration(90.0, "min"), 24.0, Some(250.0)), Some(1.5));
+        assert_eq!(labor_requirement_hours(&duration(2.0, "min/box"), 24.0, None), Some(2.0 / 60.0));
+        // Every operating hour of the stage.
+        assert_eq!(labor_requirement_hours(&duration(5.0, "min/hr_op"), 24.0, None), Some(2.0));
+        // Every 10 L of the stage's 250 L of media.
+        assert_eq!(labor_requirement_hours(&duration(30.0, "min/10L"), 24.0, Some(250.0)), Some(12.5));
+        assert_eq!(labor_requirement_hours(&duration(30.0, "min/10L"), 24.0, None), None);
+        assert_eq!(labor_requirement_hours(&duration(1.0, "kg"), 24.0, Some(250.0)), None);
+    }
+
+    /// A process of one step, `MTHD-A` on `REACTOR`, ended by the rule `rule` and expected to take
+    /// `expected_ticks`, if given; with the rules of every condition a step can end on.
+    fn one_step_process(rule: &str, expected_ticks: Option<u64>) -> (Process, HashMap<String, Rule>) {
+        let expected = expected_ticks.map_or("null".to_string(), |ticks| ticks.to_string());
+        let process = serde_yaml::from_str(&format!(
+            "{{ process_id: PROC-A, process_name: PROC-A, component_class: Test, status: Active, notes: '', default_workflow: [MTHD-A], methods: [{{ method_id: MTHD-A, stage: MTHD-A, technique: batch, required_asset_id: REACTOR, operating_parameters: {{}}, required_materials: [], qc_checks: [], required_rule_ids: [{rule}], transfers: null, expected_duration_ticks: {expected} }}] }}"
+        ))
+        .unwrap();
+        let rules = [
+            ("timed", "{ type: time_in_stage, ticks: 6 }"),
+            ("stationary", "{ type: biomass_stationary, threshold: 0.01, window: 3 }"),
+            ("enough_lutein", "{ type: product_amount, molecule_name: Lutein, target_grams: 5.0 }"),
+        ]
+        .into_iter()
+        .map(|(name, condition)| {
+            let rule: Rule = serde_yaml::from_str(&format!("{{ name: {name}, condition: {condition}, action: {{ type: advance_to_next_step }} }}")).unwrap();
+            (name.to_string(), rule)
+        })
+        .collect();
+        (process, rules)
+    }
+
+    /// A log of the initial state and `ticks` ticks of `MTHD-A`.
+    fn ran_for(ticks: u64) -> Vec<LogRecord> {
+        (0..=ticks)
+            .map(|tick| {
+                let stage_id = if tick == 0 { "INITIAL" } else { "MTHD-A" };
+                serde_json::from_value(serde_json::json!({
+                    "tick": tick, "stage_id": stage_id, "media_volume_l": 1.0, "media_ph": 7.0, "events_json": "[]"
+                }))
+                .unwrap()
+            })
+            .collect()
+    }
+
+    fn duration(blueprint: &ExecutableBlueprint) -> (u64, DurationSource) {
+        (blueprint.workflow[0].duration_ticks, blueprint.workflow[0].duration_source)
+    }
+
+    #[test]
+    fn a_time_in_stage_rule_sets_the_duration_before_anything_else() {
+        let (process, rules) = one_step_process("timed", Some(3));
+        assert_eq!(duration(&generate_blueprint(&process, &rules).unwrap()), (6, DurationSource::TimeInStageRule));
+        let observed = generate_blueprint_from_records(&process, &rules, &ran_for(9)).unwrap();
+        assert_eq!(duration(&observed), (6, DurationSource::TimeInStageRule));
+    }
+
+    #[test]
+    fn a_step_without_a_time_rule_takes_its_expected_duration() {
+        let (process, rules) = one_step_process("enough_lutein", Some(3));
+        assert_eq!(duration(&generate_blueprint(&process, &rules).unwrap()), (3, DurationSource::ExpectedDuration));
+        let observed = generate_blueprint_from_records(&process, &rules, &ran_for(9)).unwrap();
+        assert_eq!(duration(&observed), (3, DurationSource::ExpectedDuration));
+    }
+
+    #[test]
+    fn a_step_without_a_time_rule_or_expected_duration_takes_the_one_it_ran_for() {
+        let (process, rules) = one_step_process("stationary", None);
+        // Nine rows of the stage after the initial state.
+        let observed = generate_blueprint_from_records(&process, &rules, &ran_for(9)).unwrap();
+        assert_eq!(duration(&observed), (9, DurationSource::ObservedRun));
+    }
+
+    #[test]
+    fn a_step_with_no_known_duration_is_marked_dynamic() {
+        let (process, rules) = one_step_process("enough_lutein", None);
+        let blueprint = generate_blueprint(&process, &rules).unwrap();
+        assert_eq!(duration(&blueprint), (0, DurationSource::Dynamic));
+        assert_eq!(blueprint.workflow[0].transition_rules, [rules["enough_lutein"].clone()]);
+        // A log in which the stage never ran leaves it dynamic too.
+        let unobserved = generate_blueprint_from_records(&process, &rules, &ran_for(0)).unwrap();
+        assert_eq!(duration(&unobserved), (0, DurationSource::Dynamic));
+    }
+
+    #[test]
+    fn a_blueprint_reads_back_from_yaml_as_written() {
+        let (mut process, rules) = one_step_process("enough_lutein", Some(3));
+        let method = &mut process.methods[0];
+        method.required_materials.push(RequiredMaterial { r#type: "material".to_string(), id: "GLUCOSE".to_string() });
+        method.qc_checks.push(serde_yaml::from_str("{ method_id: QC-HPLC, timing: end_of_stage, cost_usd: 40.0, duration: { value: 2.0, unit: h } }").unwrap());
+        let mut bom = BillOfMaterials::default();
+        bom.by_stage.entry("MTHD-A".to_string()).or_default().materials_consumed.insert("GLUCOSE".to_string(), 250.0);
+        let blueprint = generate_blueprint(&process, &rules).unwrap().with_material_estimates(&bom);
+
+        let path = std::env::temp_dir().join(format!("bioforge_blueprint_{}.yaml", std::process::id()));
+        let path = path.to_str().unwrap();
+        write_blueprint_yaml(&blueprint, path).unwrap();
+        let read = read_blueprint_yaml(path).unwrap();
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(read, blueprint);
+        let step = &read.workflow[0];
+        assert_eq!(step.transition_rules, [rules["enough_lutein"].clone()]);
+        assert_eq!(step.required_materials[0].estimated_grams, Some(250.0));
+        assert_eq!(step.qc_checks, process.methods[0].qc_checks);
+    }
+
+    #[test]
+    fn three_timed_qc_checks_add_their_labor_consumables_and_fees() {
+        let (mut process, _) = one_step_process("timed", None);
+        process.methods[0].qc_checks = serde_yaml::from_str(
+            r#"
+- { method_id: QC-HPLC, timing: end_of_stage, cost_usd: 80.0, required_role_id: ANALYST, duration: { value: 2.0, unit: h }, consumables: [{ material_id: STANDARD, grams: 10.0 }] }
+- { method_id: QC-PH, timing: every_shift, required_role_id: ANALYST, duration: { value: 15.0, unit: min } }
+- { method_id: QC-STERILITY, timing: end_of_stage, cost_usd: 40.0 }
+"#,
+        )
+        .unwrap();
+        let records = ran_for(6);
+        let materials = materials(vec![material("STANDARD", 1000.0, 0.0, 0.0, 0.0)]);
+        let bom = generate_bom_from_records(&records, &process, &HashMap::new(), &materials).unwrap();
+        let analyst: LaborRole = serde_yaml::from_str(
+            "{ labor_role_id: ANALYST, role_name: QC Analyst, skill_level: 2, description: null, techno_economic_profile: { cost_per_hour_usd: 50.0 } }",
+        )
+        .unwrap();
+        let labor_roles = HashMap::from([("ANALYST".to_string(), analyst)]);
+        let cogs = calculate_cogs(&bom, &materials, &labor_roles, &HashMap::new(), &EconomicAssumptions::default()).unwrap();
+
+        assert_eq!(bom.qc_assays.iter().map(|a| a.qc_method_id.as_str()).collect::<Vec<_>>(), ["QC-HPLC", "QC-PH", "QC-STERILITY"]);
+        assert_close(bom.labor_hours["ANALYST"], 2.25);
+        assert_eq!(bom.by_stage["MTHD-A"].materials_consumed["STANDARD"], 10.0);
+        // Fees of 120, 2.25 h of the analyst at 50/h, and 10 g of the standard at 1,000/kg.
+        assert_close(cogs.qc_costs, 120.0 + 112.5 + 10.0);
+        assert_close(cogs.labor_costs, 0.0);
+        assert_close(cogs.material_costs, 0.0);
+        assert_close(cogs.total_cogs, cogs.qc_costs);
+    }
+}
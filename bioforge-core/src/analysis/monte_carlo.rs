@@ -0,0 +1,199 @@
+//! Monte Carlo uncertainty propagation: the distributions of COGS, cost per gram, GWP, and batch
+//! duration when parameters are drawn from distributions instead of fixed at point estimates.
+
+use super::sensitivity::{evaluate, set_parameter, BaseConfiguration, Outcome};
+use crate::error::BioforgeError;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution as _, Normal, Triangular, Uniform};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The distribution a parameter is drawn from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "distribution", rename_all = "snake_case")]
+pub enum Distribution {
+    Normal { mean: f64, std_dev: f64 },
+    Uniform { min: f64, max: f64 },
+    Triangular { min: f64, mode: f64, max: f64 },
+}
+
+impl Distribution {
+    /// Draws `count` values from the distribution.
+    fn sample(&self, rng: &mut ChaCha8Rng, count: usize) -> Result<Vec<f64>, String> {
+        Ok(match *self {
+            Distribution::Normal { mean, std_dev } => {
+                let normal = Normal::new(mean, std_dev).map_err(|e| e.to_string())?;
+                normal.sample_iter(rng).take(count).collect()
+            }
+            Distribution::Uniform { min, max } => {
+                if !(min.is_finite() && max.is_finite()) || min > max {
+                    return Err(format!("expected finite min <= max, got {} and {}", min, max));
+                }
+                Uniform::new_inclusive(min, max).sample_iter(rng).take(count).collect()
+            }
+            Distribution::Triangular { min, mode, max } => {
+                let triangular = Triangular::new(min, max, mode).map_err(|e| e.to_string())?;
+                triangular.sample_iter(rng).take(count).collect()
+            }
+        })
+    }
+}
+
+/// A parameter of the study, addressed as for a sensitivity study (see
+/// [`Perturbation`](super::sensitivity::Perturbation)), and the distribution of its value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UncertainParameter {
+    pub path: String,
+    #[serde(flatten)]
+    pub distribution: Distribution,
+}
+
+/// The parameter values of one run and its outcome.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonteCarloSample {
+    /// The drawn value of each parameter, in the order of [`MonteCarloReport::parameters`].
+    pub values: Vec<f64>,
+    pub outcome: Outcome,
+}
+
+/// The 10th, 50th, and 90th percentiles of an output metric.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Percentiles {
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+/// An output metric of a run, or `None` if the run has no value for it.
+pub type Metric = fn(&Outcome) -> Option<f64>;
+
+/// The output metrics summarized by a Monte Carlo study, with their names in reports.
+pub const METRICS: [(&str, Metric); 4] = [
+    ("total_cogs", |o| Some(o.total_cogs)),
+    ("cost_per_gram", Outcome::cost_per_gram),
+    ("gwp_kg_co2e", |o| Some(o.gwp_kg_co2e)),
+    ("duration_hours", |o| Some(o.duration_hours as f64)),
+];
+
+/// Every sample of a Monte Carlo study.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct MonteCarloReport {
+    pub parameters: Vec<String>,
+    pub samples: Vec<MonteCarloSample>,
+}
+
+impl MonteCarloReport {
+    /// The values of `metric` over the samples that have one, in sample order.
+    pub fn values(&self, metric: Metric) -> Vec<f64> {
+        self.samples.iter().filter_map(|s| metric(&s.outcome)).collect()
+    }
+
+    /// The percentiles of `metric`, or `None` if no sample has a value for it.
+    pub fn percentiles(&self, metric: Metric) -> Option<Percentiles> {
+        let mut values = self.values(metric);
+        if values.is_empty() {
+            return None;
+        }
+        values.sort_by(f64::total_cmp);
+        Some(Percentiles {
+            p10: percentile(&values, 0.1),
+            p50: percentile(&values, 0.5),
+            p90: percentile(&values, 0.9),
+        })
+    }
+
+    /// Writes one row per sample: its parameter values, then its outcome.
+    pub fn write_samples_csv(&self, path: &str) -> Result<(), BioforgeError> {
+        let csv_error = |e| BioforgeError::CsvError(path.to_string(), e);
+        let mut writer = csv::Writer::from_path(path).map_err(csv_error)?;
+        let header = ["sample"]
+            .into_iter()
+            .chain(self.parameters.iter().map(String::as_str))
+            .chain(["product_grams"])
+            .chain(METRICS.iter().map(|(name, _)| *name));
+        writer.write_record(header).map_err(csv_error)?;
+        for (i, sample) in self.samples.iter().enumerate() {
+            let row = [i.to_string()]
+                .into_iter()
+                .chain(sample.values.iter().map(f64::to_string))
+                .chain([sample.outcome.product_grams.to_string()])
+                .chain(METRICS.iter().map(|(_, metric)| metric(&sample.outcome).map_or_else(String::new, |v| v.to_string())));
+            writer.write_record(row).map_err(csv_error)?;
+        }
+        writer.flush().map_err(|e| BioforgeError::FileIO(path.to_string(), e))
+    }
+}
+
+/// The `p` quantile of the sorted, non-empty `values`, interpolated linearly between ranks.
+fn percentile(values: &[f64], p: f64) -> f64 {
+    let rank = p * (values.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    values[lower] + (values[upper] - values[lower]) * (rank - lower as f64)
+}
+
+/// A Monte Carlo study around a base configuration. The same seed draws the same samples, so a
+/// study is reproducible regardless of how its runs are scheduled.
+#[derive(Debug, Clone)]
+pub struct MonteCarloStudy {
+    pub base: BaseConfiguration,
+    pub parameters: Vec<UncertainParameter>,
+    pub samples: usize,
+    pub seed: u64,
+}
+
+impl MonteCarloStudy {
+    pub fn new(base: BaseConfiguration, samples: usize, seed: u64) -> Self {
+        Self { base, parameters: Vec::new(), samples, seed }
+    }
+
+    /// Adds a parameter drawn from `distribution` in every run.
+    pub fn with_parameter(mut self, path: &str, distribution: Distribution) -> Self {
+        self.parameters.push(UncertainParameter { path: path.to_string(), distribution });
+        self
+    }
+
+    /// Draws every parameter for each sample, then simulates and costs the samples. With the
+    /// `parallel` feature, the runs execute concurrently.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BioforgeError::ConfigError` if a path names no numeric parameter or a
+    /// distribution is invalid, before any run.
+    pub fn run(&self) -> Result<MonteCarloReport, BioforgeError> {
+        // Each parameter draws its whole column in turn from one generator, so the samples depend
+        // only on the seed and the order of the parameters.
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let columns = self
+            .parameters
+            .iter()
+            .map(|parameter| {
+                parameter.distribution.sample(&mut rng, self.samples).map_err(|reason| {
+                    BioforgeError::ConfigError(format!("Invalid distribution for '{}': {}", parameter.path, reason))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let variants = (0..self.samples)
+            .map(|i| {
+                let mut variant = self.base.clone();
+                let values: Vec<f64> = columns.iter().map(|column| column[i]).collect();
+                for (parameter, value) in self.parameters.iter().zip(&values) {
+                    set_parameter(&mut variant, &parameter.path, |_| *value)?;
+                }
+                Ok((values, variant))
+            })
+            .collect::<Result<Vec<_>, BioforgeError>>()?;
+
+        let run = |(values, variant): &(Vec<f64>, BaseConfiguration)| {
+            evaluate(variant).map(|outcome| MonteCarloSample { values: values.clone(), outcome })
+        };
+        #[cfg(feature = "parallel")]
+        let samples = variants.par_iter().map(run).collect::<Result<Vec<_>, _>>()?;
+        #[cfg(not(feature = "parallel"))]
+        let samples = variants.iter().map(run).collect::<Result<Vec<_>, _>>()?;
+
+        Ok(MonteCarloReport { parameters: self.parameters.iter().map(|p| p.path.clone()).collect(), samples })
+    }
+}
@@ -0,0 +1,208 @@
+//! Standard bioprocess KPIs of a cultivation: titer, space-time yield, and the product and
+//! biomass yields, derived the same way for every report.
+
+use super::{growth_metrics, mass_balance::Contents, read_log};
+use crate::{error::BioforgeError, logger::LogRecord, simulation::engine::TIME_STEP_HR};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// A product of the cultivation, held in the biomass of the organism that makes it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetProduct {
+    pub molecule_name: String,
+    pub organism_id: String,
+    /// Milligrams of the product per gram of the organism's dry biomass.
+    pub content_mg_g: f64,
+}
+
+/// The KPIs of one product.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProductPerformance {
+    pub molecule_name: String,
+    pub organism_id: String,
+    /// Grams of the product formed over the run.
+    pub produced_g: f64,
+    /// Grams of the product at the end of the run per liter of broth.
+    pub titer_g_l: f64,
+    /// The titer formed per hour of the batch, in g/L/h.
+    pub space_time_yield_g_l_h: f64,
+    /// Grams of product formed per gram of biomass formed (Yp/x).
+    pub yield_product_per_biomass: Option<f64>,
+    /// Grams of biomass formed per gram of substrate the organism consumed (Yx/s).
+    pub yield_biomass_per_substrate: Option<f64>,
+}
+
+/// The KPIs of a cultivation.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessPerformance {
+    /// Hours from the first to the last row of the log.
+    pub batch_duration_hr: f64,
+    /// The highest specific growth rate of each organism, in 1/h.
+    pub peak_specific_growth_rates: BTreeMap<String, f64>,
+    pub products: Vec<ProductPerformance>,
+}
+
+/// The KPIs of the cultivation logged at `log_path`; see [`process_performance_from_records`].
+pub fn process_performance(
+    log_path: &str,
+    products: &[TargetProduct],
+    substrate_consumed_g: &HashMap<String, f64>,
+) -> Result<ProcessPerformance, BioforgeError> {
+    process_performance_from_records(&read_log(log_path)?, products, substrate_consumed_g)
+}
+
+/// Like [`process_performance`], from records already in memory.
+///
+/// Biomass formed is the change between the first and last rows, and the broth volume is that of
+/// the last row. `substrate_consumed_g` holds the grams of substrate each organism consumed, keyed
+/// by organism id, e.g. summed from [`super::BillOfMaterials::materials_by_consumer`].
+pub fn process_performance_from_records(
+    records: &[LogRecord],
+    products: &[TargetProduct],
+    substrate_consumed_g: &HashMap<String, f64>,
+) -> Result<ProcessPerformance, BioforgeError> {
+    let (Some(first), Some(last)) = (records.first(), records.last()) else {
+        return Ok(ProcessPerformance::default());
+    };
+    let organism_ids: Vec<&str> = products.iter().map(|p| p.organism_id.as_str()).collect();
+    let start = Contents::of(first, &organism_ids, &BTreeSet::new())?;
+    let end = Contents::of(last, &organism_ids, &BTreeSet::new())?;
+    let batch_duration_hr = (last.tick - first.tick) as f64 * TIME_STEP_HR;
+    let volume_l = last.media_volume_l;
+
+    let products = products
+        .iter()
+        .map(|product| {
+            let biomass_formed_g = end.biomass_g(&product.organism_id) - start.biomass_g(&product.organism_id);
+            let produced_g = biomass_formed_g * product.content_mg_g / 1000.0;
+            let titer_g_l = if volume_l > 0.0 {
+                end.biomass_g(&product.organism_id) * product.content_mg_g / 1000.0 / volume_l
+            } else {
+                0.0
+            };
+            let substrate_g = substrate_consumed_g.get(&product.organism_id).copied().unwrap_or(0.0);
+            ProductPerformance {
+                molecule_name: product.molecule_name.clone(),
+                organism_id: product.organism_id.clone(),
+                produced_g,
+                titer_g_l,
+                space_time_yield_g_l_h: if batch_duration_hr > 0.0 { titer_g_l / batch_duration_hr } else { 0.0 },
+                yield_product_per_biomass: (biomass_formed_g > 0.0).then(|| produced_g / biomass_formed_g),
+                yield_biomass_per_substrate: (substrate_g > 0.0).then(|| biomass_formed_g / substrate_g),
+            }
+        })
+        .collect();
+
+    let mut ids: Vec<String> = organism_ids.iter().map(|id| id.to_string()).collect();
+    ids.sort();
+    ids.dedup();
+    Ok(ProcessPerformance {
+        batch_duration_hr,
+        peak_specific_growth_rates: growth_metrics(records, &ids)?.peak_specific_growth_rates,
+        products,
+    })
+}
+
+/// The instantaneous volumetric productivity of `product` between each pair of consecutive rows,
+/// in g/L/h, as the product formed over the interval per liter of broth at its end, against the
+/// hour the interval ends.
+pub fn volumetric_productivity(
+    records: &[LogRecord],
+    product: &TargetProduct,
+) -> Result<Vec<(f64, f64)>, BioforgeError> {
+    let organism_ids = [product.organism_id.as_str()];
+    let mut series = Vec::new();
+    let mut previous: Option<(u64, f64)> = None;
+    for record in records {
+        let biomass_g = Contents::of(record, &organism_ids, &BTreeSet::new())?.biomass_g(&product.organism_id);
+        let product_g = biomass_g * product.content_mg_g / 1000.0;
+        if let Some((tick, previous_g)) = previous {
+            let hours = (record.tick - tick) as f64 * TIME_STEP_HR;
+            if hours > 0.0 && record.media_volume_l > 0.0 {
+                let productivity = (product_g - previous_g) / record.media_volume_l / hours;
+                series.push((record.tick as f64 * TIME_STEP_HR, productivity));
+            }
+        }
+        previous = Some((record.tick, product_g));
+    }
+    Ok(series)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A row of 2 L of broth holding `biomass_g` of ORG-A, growing at `rate` per hour.
+    fn record(tick: u64, biomass_g: f64, rate: f64) -> LogRecord {
+        LogRecord {
+            tick,
+            stage_id: "MTHD-CULT".to_string(),
+            organisms_json: json!({ "ORG-A": { "biomass": { "value": biomass_g, "unit": "g" } } }).to_string(),
+            media_volume_l: 2.0,
+            media_ph: 7.0,
+            dissolved_components_json: "[]".to_string(),
+            dissolved_gases_json: "[]".to_string(),
+            asset_states_json: String::new(),
+            events_json: "[]".to_string(),
+            vessels_json: String::new(),
+            timestamp: None,
+            total_biomass_g: biomass_g,
+            growth_rates_json: json!({ "ORG-A": rate }).to_string(),
+            uptake_rates_json: String::new(),
+            series: BTreeMap::new(),
+        }
+    }
+
+    /// Biomass doubling every hour from 2 g to 32 g over four hours.
+    fn doubling() -> Vec<LogRecord> {
+        let rate = 2f64.ln();
+        vec![record(0, 2.0, 0.0), record(1, 4.0, rate), record(2, 8.0, rate), record(3, 16.0, rate), record(4, 32.0, 0.5 * rate)]
+    }
+
+    fn lutein() -> TargetProduct {
+        TargetProduct { molecule_name: "Lutein".to_string(), organism_id: "ORG-A".to_string(), content_mg_g: 50.0 }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-12, "expected {}, got {}", expected, actual);
+    }
+
+    #[test]
+    fn the_kpis_of_a_known_run_match_their_analytic_values() {
+        let substrate = HashMap::from([("ORG-A".to_string(), 60.0)]);
+        let performance = process_performance_from_records(&doubling(), &[lutein()], &substrate).unwrap();
+
+        assert_eq!(performance.batch_duration_hr, 4.0);
+        assert_close(performance.peak_specific_growth_rates["ORG-A"], 2f64.ln());
+        let [product] = performance.products.as_slice() else { panic!("{:?}", performance.products) };
+        // 30 g of biomass formed at 50 mg/g, and 32 g at the end in 2 L.
+        assert_close(product.produced_g, 1.5);
+        assert_close(product.titer_g_l, 0.8);
+        assert_close(product.space_time_yield_g_l_h, 0.2);
+        assert_close(product.yield_product_per_biomass.unwrap(), 0.05);
+        assert_close(product.yield_biomass_per_substrate.unwrap(), 0.5);
+    }
+
+    #[test]
+    fn yields_without_growth_or_substrate_are_left_out() {
+        let flat = vec![record(0, 2.0, 0.0), record(3, 2.0, 0.0)];
+        let performance = process_performance_from_records(&flat, &[lutein()], &HashMap::new()).unwrap();
+        let product = &performance.products[0];
+        assert_eq!((product.produced_g, product.yield_product_per_biomass, product.yield_biomass_per_substrate), (0.0, None, None));
+        assert_close(product.titer_g_l, 0.05);
+
+        assert_eq!(process_performance_from_records(&[], &[lutein()], &HashMap::new()).unwrap(), ProcessPerformance::default());
+    }
+
+    #[test]
+    fn volumetric_productivity_is_the_product_formed_per_liter_and_hour() {
+        let series = volumetric_productivity(&doubling(), &lutein()).unwrap();
+        let expected = [(1.0, 0.05), (2.0, 0.1), (3.0, 0.2), (4.0, 0.4)];
+        assert_eq!(series.len(), expected.len());
+        for ((hour, productivity), (expected_hour, expected_productivity)) in series.into_iter().zip(expected) {
+            assert_eq!(hour, expected_hour);
+            assert_close(productivity, expected_productivity);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+//! Versioned, serializable records of a run's bill of materials, costs, and impacts, for
+//! traceability and for tools that read the run directory.
+use super::{constraints::{ConstraintCheck, LaborDemand, OrganismFilter}, BillOfMaterials, CogsResult, LcaResult, MaterialResolver};
+use crate::error::BioforgeError;
+use bioforge_schemas::material::Material;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// The version of the layout of [`Versioned`] reports. Bump it whenever a field of a report, or
+/// of the BOM, COGS, or LCA it holds, is renamed, removed, or changes meaning.
+pub const REPORT_SCHEMA_VERSION: &str = "1.0";
+
+/// A report tagged with the [`REPORT_SCHEMA_VERSION`] it was written with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub schema_version: String,
+    #[serde(flatten)]
+    pub report: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(report: T) -> Self {
+        Self { schema_version: REPORT_SCHEMA_VERSION.to_string(), report }
+    }
+}
+
+/// A bill of materials with the names of the materials it refers to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamedBom {
+    /// The name of every material and waste id of the BOM that matches a material, keyed by id.
+    pub material_names: BTreeMap<String, String>,
+    #[serde(flatten)]
+    pub bom: BillOfMaterials,
+}
+
+impl NamedBom {
+    pub fn new(bom: BillOfMaterials, materials: &HashMap<String, Material>) -> Self {
+        let resolver = MaterialResolver::new(materials);
+        let stage_ids = bom.by_stage.values().flat_map(|stage| stage.materials_consumed.keys());
+        let consumer_ids = bom.materials_by_consumer.values().flat_map(|consumed| consumed.keys());
+        let material_names = bom
+            .materials_consumed
+            .keys()
+            .chain(bom.waste_generated.keys())
+            .chain(stage_ids)
+            .chain(consumer_ids)
+            .filter_map(|id| Some((id.clone(), resolver.resolve(id)?.material_name.clone())))
+            .collect();
+        Self { material_names, bom }
+    }
+
+    /// Writes the materials each stage consumed to a CSV file at `path`, one row per stage and
+    /// material, sorted by stage and material id.
+    pub fn write_csv(&self, path: &str) -> Result<(), BioforgeError> {
+        #[derive(Serialize)]
+        struct Row<'a> {
+            stage_id: &'a str,
+            material_id: &'a str,
+            material_name: &'a str,
+            grams: f64,
+        }
+
+        let csv_error = |e| BioforgeError::CsvError(path.to_string(), e);
+        let mut writer = csv::Writer::from_path(path).map_err(csv_error)?;
+        let stages: BTreeMap<&String, _> = self.bom.by_stage.iter().collect();
+        for (stage_id, stage) in stages {
+            let consumed: BTreeMap<&String, &f64> = stage.materials_consumed.iter().collect();
+            for (material_id, grams) in consumed {
+                writer
+                    .serialize(Row {
+                        stage_id,
+                        material_id,
+                        material_name: self.material_names.get(material_id).map_or("", String::as_str),
+                        grams: *grams,
+                    })
+                    .map_err(csv_error)?;
+            }
+        }
+        writer.flush().map_err(|e| BioforgeError::FileIO(path.to_string(), e))
+    }
+}
+
+/// The bill of materials, costs, and impacts of a run together.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisReport {
+    pub bom: NamedBom,
+    pub cogs: CogsResult,
+    pub lca: LcaResult,
+    /// The labor demand of every role in every stage against its headcount; see
+    /// [`super::constraints::labor_feasibility`].
+    #[serde(default)]
+    pub labor_feasibility: Vec<LaborDemand>,
+    /// The run against each limit its request sets; see [`super::constraints::Constraints`].
+    #[serde(default)]
+    pub constraints: Vec<ConstraintCheck>,
+    /// The organisms the request let the optimizer select.
+    #[serde(default)]
+    pub organism_filter: OrganismFilter,
+    /// The batches the BOM, COGS, and LCA total over, alike and run one after another.
+    #[serde(default = "default_batches")]
+    pub batches: u32,
+}
+
+fn default_batches() -> u32 {
+    1
+}
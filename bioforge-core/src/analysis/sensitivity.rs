@@ -0,0 +1,375 @@
+//! One-at-a-time sensitivity analysis: how much the COGS and GWP of a run move when each
+//! parameter is perturbed by ±X% with all others held at their base values.
+
+use super::{bom_from_media_state, calculate_cogs, calculate_lca, generate_bom_from_records};
+use crate::{error::BioforgeError, logger::InMemorySink, simulation::builder::SimulationBuilder};
+use bioforge_schemas::{
+    asset::Asset,
+    environment::MediaState,
+    labor::LaborRole,
+    material::Material,
+    organism::Organism,
+    process::Process,
+    rule::Rule,
+    tea_lca::EconomicAssumptions,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Everything a variant of the study is simulated and costed with.
+#[derive(Debug, Clone)]
+pub struct BaseConfiguration {
+    pub organisms: Vec<Organism>,
+    pub assets: HashMap<String, Asset>,
+    pub rules: Vec<Rule>,
+    pub process: Process,
+    pub initial_media: MediaState,
+    pub materials: HashMap<String, Material>,
+    pub labor_roles: HashMap<String, LaborRole>,
+    pub assumptions: EconomicAssumptions,
+    /// The molecules the run produces for sale, whose grams the cost per gram is taken over.
+    pub target_molecules: Vec<String>,
+}
+
+/// A parameter of the study and how far it is perturbed either way.
+///
+/// The path names the parameter within the base configuration: `organism.<id>`, `asset.<id>`,
+/// `material.<id>`, `labor_role.<id>`, or `assumptions`, followed by the field. Fields may skip
+/// the levels above them when their name is unique below the last one (e.g.
+/// `organism.ORG-CHLOVUL.growth_rate_per_hr`), list elements are selected by index or by an id
+/// they carry (e.g. `media_consumption.CHEBI:17234`), and a measurement stands for its value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Perturbation {
+    pub path: String,
+    /// The perturbation in percent of the base value, applied downwards and upwards.
+    pub percent: f64,
+}
+
+/// The results of one simulated variant.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Outcome {
+    pub total_cogs: f64,
+    pub gwp_kg_co2e: f64,
+    pub duration_hours: u64,
+    /// Grams of the target molecules in the final biomass.
+    pub product_grams: f64,
+}
+
+impl Outcome {
+    /// `total_cogs` per gram of product, or `None` if nothing was produced.
+    pub fn cost_per_gram(&self) -> Option<f64> {
+        (self.product_grams > 0.0).then(|| self.total_cogs / self.product_grams)
+    }
+}
+
+/// The outcomes of one parameter perturbed downwards and upwards.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParameterSensitivity {
+    pub path: String,
+    pub percent: f64,
+    pub base_value: f64,
+    pub low: Outcome,
+    pub high: Outcome,
+}
+
+impl ParameterSensitivity {
+    /// The spread of the total COGS between the low and high variants.
+    pub fn cogs_swing(&self) -> f64 {
+        (self.high.total_cogs - self.low.total_cogs).abs()
+    }
+}
+
+/// The baseline and each parameter's outcomes, the parameter moving the COGS most first.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SensitivityReport {
+    pub baseline: Outcome,
+    pub parameters: Vec<ParameterSensitivity>,
+}
+
+/// A row of `sensitivity_report.csv`.
+#[derive(Serialize)]
+struct ReportRow<'a> {
+    parameter: &'a str,
+    percent: f64,
+    base_value: f64,
+    low_value: f64,
+    high_value: f64,
+    cogs_low: f64,
+    cogs_high: f64,
+    cogs_delta_low: f64,
+    cogs_delta_high: f64,
+    gwp_low: f64,
+    gwp_high: f64,
+    gwp_delta_low: f64,
+    gwp_delta_high: f64,
+}
+
+impl SensitivityReport {
+    /// Writes one row per parameter, in tornado order, with the outcomes and their deltas from
+    /// the baseline.
+    pub fn write_csv(&self, path: &str) -> Result<(), BioforgeError> {
+        let csv_error = |e| BioforgeError::CsvError(path.to_string(), e);
+        let mut writer = csv::Writer::from_path(path).map_err(csv_error)?;
+        let baseline = &self.baseline;
+        for parameter in &self.parameters {
+            writer
+                .serialize(ReportRow {
+                    parameter: &parameter.path,
+                    percent: parameter.percent,
+                    base_value: parameter.base_value,
+                    low_value: parameter.base_value * (1.0 - parameter.percent / 100.0),
+                    high_value: parameter.base_value * (1.0 + parameter.percent / 100.0),
+                    cogs_low: parameter.low.total_cogs,
+                    cogs_high: parameter.high.total_cogs,
+                    cogs_delta_low: parameter.low.total_cogs - baseline.total_cogs,
+                    cogs_delta_high: parameter.high.total_cogs - baseline.total_cogs,
+                    gwp_low: parameter.low.gwp_kg_co2e,
+                    gwp_high: parameter.high.gwp_kg_co2e,
+                    gwp_delta_low: parameter.low.gwp_kg_co2e - baseline.gwp_kg_co2e,
+                    gwp_delta_high: parameter.high.gwp_kg_co2e - baseline.gwp_kg_co2e,
+                })
+                .map_err(csv_error)?;
+        }
+        writer.flush().map_err(|e| BioforgeError::FileIO(path.to_string(), e))
+    }
+}
+
+/// A one-at-a-time sensitivity study around a base configuration.
+#[derive(Debug, Clone)]
+pub struct SensitivityStudy {
+    pub base: BaseConfiguration,
+    pub parameters: Vec<Perturbation>,
+}
+
+impl SensitivityStudy {
+    pub fn new(base: BaseConfiguration) -> Self {
+        Self { base, parameters: Vec::new() }
+    }
+
+    /// Adds a parameter perturbed by `percent` of its base value either way.
+    pub fn with_parameter(mut self, path: &str, percent: f64) -> Self {
+        self.parameters.push(Perturbation { path: path.to_string(), percent });
+        self
+    }
+
+    /// Simulates and costs the baseline and a low and high variant of every parameter. With the
+    /// `parallel` feature, the variants run concurrently.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `BioforgeError::ConfigError` if a path names no numeric parameter or a
+    /// perturbation is not positive, before any variant is run.
+    pub fn run(&self) -> Result<SensitivityReport, BioforgeError> {
+        let mut variants = vec![self.base.clone()];
+        let mut base_values = Vec::with_capacity(self.parameters.len());
+        for parameter in &self.parameters {
+            if !(parameter.percent > 0.0 && parameter.percent.is_finite()) {
+                return Err(BioforgeError::ConfigError(format!(
+                    "The perturbation of '{}' must be a positive percentage, got {}",
+                    parameter.path, parameter.percent
+                )));
+            }
+            let mut low = self.base.clone();
+            let low_factor = 1.0 - parameter.percent / 100.0;
+            base_values.push(set_parameter(&mut low, &parameter.path, |value| value * low_factor)?);
+            let mut high = self.base.clone();
+            let high_factor = 1.0 + parameter.percent / 100.0;
+            set_parameter(&mut high, &parameter.path, |value| value * high_factor)?;
+            variants.extend([low, high]);
+        }
+
+        #[cfg(feature = "parallel")]
+        let outcomes = variants.par_iter().map(evaluate).collect::<Result<Vec<_>, _>>()?;
+        #[cfg(not(feature = "parallel"))]
+        let outcomes = variants.iter().map(evaluate).collect::<Result<Vec<_>, _>>()?;
+
+        let mut outcomes = outcomes.into_iter();
+        let baseline = outcomes.next().unwrap_or_default();
+        let mut parameters: Vec<ParameterSensitivity> = self
+            .parameters
+            .iter()
+            .zip(base_values)
+            .map(|(parameter, base_value)| ParameterSensitivity {
+                path: parameter.path.clone(),
+                percent: parameter.percent,
+                base_value,
+                low: outcomes.next().unwrap_or_default(),
+                high: outcomes.next().unwrap_or_default(),
+            })
+            .collect();
+        parameters.sort_by(|a, b| b.cogs_swing().total_cmp(&a.cogs_swing()));
+
+        Ok(SensitivityReport { baseline, parameters })
+    }
+}
+
+/// Simulates a variant with an in-memory log and costs its bill of materials, initial media
+/// included.
+pub(super) fn evaluate(config: &BaseConfiguration) -> Result<Outcome, BioforgeError> {
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(config.organisms.clone())
+        .with_assets(config.assets.values().cloned().collect())
+        .with_rules(config.rules.clone())
+        .with_process(config.process.clone())
+        .with_initial_media(config.initial_media.clone())
+        .with_timeseries_sink(Box::new(InMemorySink::new()))
+        .build()?;
+    let summary = engine.run()?;
+
+    let records = engine.logged_records().unwrap_or_default();
+    let mut bom = bom_from_media_state(&config.initial_media, &config.materials)?;
+    bom.merge(&generate_bom_from_records(records, engine.get_process(), &config.assets, &config.materials)?);
+    let cogs = calculate_cogs(&bom, &config.materials, &config.labor_roles, &config.assets, &config.assumptions)?;
+    let lca = calculate_lca(&bom, &config.materials, &config.assets, &config.assumptions)?;
+
+    let product_grams = config
+        .organisms
+        .iter()
+        .flat_map(|organism| {
+            let biomass = summary.final_biomass.get(&organism.organism_id).copied().unwrap_or(0.0);
+            organism
+                .all_yields()
+                .filter(|(molecule, _)| config.target_molecules.iter().any(|t| t == molecule))
+                .map(move |(_, yield_mg_g)| biomass * yield_mg_g / 1000.0)
+        })
+        .sum();
+
+    Ok(Outcome {
+        total_cogs: cogs.total_cogs,
+        gwp_kg_co2e: lca.gwp_kg_co2e(),
+        duration_hours: summary.total_ticks,
+        product_grams,
+    })
+}
+
+/// Replaces the parameter at `path` with `update` of its value, returning its base value.
+pub(super) fn set_parameter(
+    config: &mut BaseConfiguration,
+    path: &str,
+    update: impl Fn(f64) -> f64,
+) -> Result<f64, BioforgeError> {
+    let segments: Vec<&str> = path.split('.').collect();
+    let not_found = |kind: &str, id: &str| BioforgeError::ConfigError(format!("No {} '{}' for parameter '{}'", kind, id, path));
+    let scaled = match segments.as_slice() {
+        ["organism", id, field @ ..] => {
+            let organism = config
+                .organisms
+                .iter_mut()
+                .find(|o| o.organism_id == *id)
+                .ok_or_else(|| not_found("organism", id))?;
+            update_field(organism, field, &update)
+        }
+        ["asset", id, field @ ..] => {
+            update_field(config.assets.get_mut(*id).ok_or_else(|| not_found("asset", id))?, field, &update)
+        }
+        ["material", id, field @ ..] => {
+            update_field(config.materials.get_mut(*id).ok_or_else(|| not_found("material", id))?, field, &update)
+        }
+        ["labor_role", id, field @ ..] => {
+            update_field(config.labor_roles.get_mut(*id).ok_or_else(|| not_found("labor role", id))?, field, &update)
+        }
+        ["assumptions", field @ ..] => update_field(&mut config.assumptions, field, &update),
+        _ => Err("expected it to start with organism.<id>, asset.<id>, material.<id>, labor_role.<id>, or assumptions"
+            .to_string()),
+    };
+    scaled.map_err(|reason| BioforgeError::ConfigError(format!("Invalid parameter '{}': {}", path, reason)))
+}
+
+/// Sets the number at `field` within `item` to `value`, returning its previous value. `field` is
+/// addressed as the part of a [`Perturbation`] path after the item's id, e.g.
+/// `growth_rate_per_hr` of an organism.
+///
+/// # Errors
+///
+/// Returns a `BioforgeError::ConfigError` if `field` names no numeric field of `item`.
+pub fn set_field<T: Serialize + DeserializeOwned>(item: &mut T, field: &str, value: f64) -> Result<f64, BioforgeError> {
+    let segments: Vec<&str> = field.split('.').collect();
+    update_field(item, &segments, |_| value)
+        .map_err(|reason| BioforgeError::ConfigError(format!("Invalid field '{}': {}", field, reason)))
+}
+
+/// Replaces the number at `field` within `item` with `update` of it, returning its original value.
+fn update_field<T: Serialize + DeserializeOwned>(
+    item: &mut T,
+    field: &[&str],
+    update: impl Fn(f64) -> f64,
+) -> Result<f64, String> {
+    let mut value = serde_json::to_value(&*item).map_err(|e| e.to_string())?;
+    let mut pointer = locate(&value, field)?;
+    if value.pointer(&pointer).and_then(|v| v.get("value")).is_some_and(Value::is_number) {
+        pointer.push_str("/value");
+    }
+    let target = value.pointer_mut(&pointer).ok_or("the field vanished")?;
+    let base_value = target.as_f64().ok_or_else(|| format!("'{}' is not a number", field.join(".")))?;
+    *target = if target.is_f64() {
+        Value::from(update(base_value))
+    } else {
+        Value::from(update(base_value).round() as i64)
+    };
+    *item = serde_json::from_value(value).map_err(|e| e.to_string())?;
+    Ok(base_value)
+}
+
+/// Resolves `field` to a JSON pointer within `value`.
+fn locate(value: &Value, field: &[&str]) -> Result<String, String> {
+    let mut pointer = String::new();
+    for segment in field {
+        let current = value.pointer(&pointer).unwrap_or(&Value::Null);
+        let next = match child(current, segment) {
+            Some(child) => child,
+            None => {
+                let mut matches = Vec::new();
+                descendants_named(current, segment, String::new(), &mut matches);
+                match matches.len() {
+                    1 => matches.remove(0),
+                    0 => return Err(format!("no field '{}'", segment)),
+                    n => return Err(format!("'{}' is ambiguous ({} fields of that name)", segment, n)),
+                }
+            }
+        };
+        pointer.push_str(&next);
+    }
+    Ok(pointer)
+}
+
+/// The pointer suffix of the direct child `segment` of `value`: a field, or a list element by
+/// index or by one of its string values.
+fn child(value: &Value, segment: &str) -> Option<String> {
+    match value {
+        Value::Object(fields) => fields.contains_key(segment).then(|| format!("/{}", escape(segment))),
+        Value::Array(elements) => segment.parse::<usize>().ok().filter(|i| *i < elements.len()).or_else(|| {
+            elements.iter().position(|element| {
+                element.as_object().is_some_and(|fields| fields.values().any(|v| v.as_str() == Some(segment)))
+            })
+        }).map(|i| format!("/{}", i)),
+        _ => None,
+    }
+}
+
+/// Collects the pointer suffixes of every field named `name` below `value`.
+fn descendants_named(value: &Value, name: &str, prefix: String, matches: &mut Vec<String>) {
+    match value {
+        Value::Object(fields) => {
+            for (key, child) in fields {
+                let pointer = format!("{}/{}", prefix, escape(key));
+                if key == name {
+                    matches.push(pointer.clone());
+                }
+                descendants_named(child, name, pointer, matches);
+            }
+        }
+        Value::Array(elements) => {
+            for (i, element) in elements.iter().enumerate() {
+                descendants_named(element, name, format!("{}/{}", prefix, i), matches);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
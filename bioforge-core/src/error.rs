@@ -20,6 +20,26 @@ pub enum BioforgeError {
     #[error("Could not find method '{0}' in process definition")]
     MethodNotFound(String),
 
+    #[error("Invalid connection at asset '{asset_id}', port '{port_id}': {reason}")]
+    ConnectionError {
+        asset_id: String,
+        port_id: String,
+        reason: String,
+    },
+
+    #[error("No purchasable material in the knowledge base supplies media component '{molecule_name}' ({molecule_id})")]
+    UnsuppliedMediaComponent { molecule_id: String, molecule_name: String },
+
+    #[error(
+        "Media component '{molecule_name}' cannot be brought to {concentration:.2} g/L: its most concentrated supplier, '{material_id}', reaches {max_concentration:.2} g/L"
+    )]
+    InsolubleMediaComponent {
+        molecule_name: String,
+        concentration: f64,
+        material_id: String,
+        max_concentration: f64,
+    },
+
     #[error("Configuration error: {0}")]
     ConfigError(String), // Added the missing variant
 
@@ -29,6 +49,9 @@ pub enum BioforgeError {
     #[error("Failed to parse YAML from '{0}': {1}")]
     YamlParsing(String, #[source] serde_yaml::Error),
 
+    #[error("Failed to write YAML to '{0}': {1}")]
+    YamlWriting(String, #[source] serde_yaml::Error),
+
     // Correctly handle different error types from external crates
     #[error("Failed to parse JSON: {0}")]
     JsonParsing(#[from] serde_json::Error),
@@ -36,6 +59,14 @@ pub enum BioforgeError {
     #[error("Failed to process CSV file '{0}': {1}")]
     CsvError(String, #[source] csv::Error), // Correctly structured for context
 
+    #[cfg(feature = "parquet")]
+    #[error("Failed to process Parquet log '{0}': {1}")]
+    ParquetError(String, #[source] parquet::errors::ParquetError),
+
+    #[cfg(feature = "sqlite")]
+    #[error("Failed to access SQLite log database '{0}': {1}")]
+    SqliteError(String, #[source] rusqlite::Error),
+
     #[error("An error occurred during logging: {0}")]
     LoggingError(#[from] anyhow::Error), // Handles errors from the logger
 }
\ No newline at end of file
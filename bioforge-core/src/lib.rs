@@ -1,4 +1,11 @@
 pub mod analysis;
 pub mod error;
 pub mod logger;
-pub mod simulation;
\ No newline at end of file
+#[cfg(feature = "parquet")]
+pub mod parquet_log;
+pub mod simulation;
+#[cfg(feature = "sqlite")]
+pub mod sqlite_log;
+
+/// The version of this crate, for the run manifest.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
\ No newline at end of file
@@ -1,33 +1,852 @@
-use crate::simulation::state::SimulationState;
-use csv::Writer;
-use serde::Serialize;
+use crate::simulation::{
+    engine::TIME_STEP_HR,
+    state::{SimulationEvent, SimulationState},
+};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use csv::{Writer, WriterBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use flate2::{write::GzEncoder, Compression};
 use std::fs;
-use std::io;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Instant;
+#[cfg(feature = "parquet")]
+use crate::parquet_log::ParquetLogWriter;
 
-#[derive(Debug, Serialize)]
-struct LogEntry {
-    tick: u64,
-    stage_id: String,
-    organisms_json: String,
-    media_volume_l: f64,
-    media_ph: f64,
-    dissolved_components_json: String,
-    dissolved_gases_json: String,
-    asset_states_json: String,
-    events_json: String,
+/// The on-disk format of the time-series log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One CSV row per tick, with nested data embedded as JSON strings.
+    #[default]
+    Csv,
+    /// One self-contained JSON object per line, with nested data as real JSON.
+    JsonLines,
+    /// Columnar Parquet, with organisms and media as list-of-struct columns.
+    #[cfg(feature = "parquet")]
+    Parquet,
 }
 
+/// When a file log pushes its buffered rows to disk.
+///
+/// Whatever the policy, rows are flushed when the stage changes, when a row carries an event other
+/// than routine material consumption, on [`TimeSeriesSink::flush`], and when the log is finished
+/// or dropped. Parquet logs ignore the policy: they buffer whole row groups and flush only when
+/// asked to or when finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlushPolicy {
+    /// Flush once this many rows are buffered.
+    pub max_rows: usize,
+    /// Flush once this long has passed since the last flush, if set.
+    pub max_interval: Option<std::time::Duration>,
+}
+
+impl FlushPolicy {
+    /// Flush after every row, so a crash loses nothing.
+    pub fn every_row() -> Self {
+        Self { max_rows: 1, max_interval: None }
+    }
+
+    /// Flush every `rows` rows.
+    pub fn every_rows(rows: usize) -> Self {
+        Self { max_rows: rows.max(1), max_interval: None }
+    }
+
+    /// Flush every `interval`, and additionally every `max_rows` rows.
+    pub fn every(interval: std::time::Duration, max_rows: usize) -> Self {
+        Self { max_rows: max_rows.max(1), max_interval: Some(interval) }
+    }
+
+    fn is_due(&self, buffered_rows: usize, last_flush: Instant) -> bool {
+        buffered_rows >= self.max_rows || self.max_interval.is_some_and(|interval| last_flush.elapsed() >= interval)
+    }
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        Self::every_row()
+    }
+}
+
+/// One logged tick: the active vessel's media and organisms, plus the run's assets, events, and
+/// vessels, with nested data embedded as JSON strings. This is the row layout of CSV logs, and
+/// what `analysis::read_log` returns for every format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogRecord {
+    pub tick: u64,
+    pub stage_id: String,
+    /// The nested JSON columns are empty in wide logs, which carry `series` instead.
+    #[serde(default)]
+    pub organisms_json: String,
+    pub media_volume_l: f64,
+    pub media_ph: f64,
+    #[serde(default)]
+    pub dissolved_components_json: String,
+    #[serde(default)]
+    pub dissolved_gases_json: String,
+    #[serde(default)]
+    pub asset_states_json: String,
+    pub events_json: String,
+    /// Contents of every vessel keyed by asset id. Absent in logs written before multi-vessel support.
+    #[serde(default)]
+    pub vessels_json: String,
+    /// RFC 3339 wall-clock time of the tick. Absent in logs written before batch start times and
+    /// empty for runs without one.
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    /// Biomass of the active vessel's organisms in grams. Zero in logs written before derived
+    /// metrics.
+    #[serde(default)]
+    pub total_biomass_g: f64,
+    /// Specific growth rate of each organism in 1/h, a JSON object keyed by organism id.
+    #[serde(default)]
+    pub growth_rates_json: String,
+    /// Volumetric uptake rate of each substrate in g/L/h, a JSON object keyed by molecule id.
+    #[serde(default)]
+    pub uptake_rates_json: String,
+    /// The per-series columns of a wide log (e.g. `conc_CHEBI_17234`); empty for other logs.
+    /// Cells left empty by the logger are omitted.
+    #[serde(skip)]
+    pub series: BTreeMap<String, f64>,
+}
+
+/// One event of the event log written next to a file log (see
+/// [`TimeSeriesLogger::with_event_log`]). Unlike the events embedded in [`LogRecord`], each event
+/// is placed at the tick and stage in which it happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// The tick at which the event happened; see [`SimulationEvent::tick`].
+    pub tick: u64,
+    /// The method that was running when the event happened, or `INITIAL`.
+    pub stage_id: String,
+    /// The event's [`SimulationEvent::kind`].
+    pub event_type: String,
+    /// The material consumed or added, the source asset of a transfer, or the method a stage
+    /// change left (empty for the first stage).
+    pub id: String,
+    /// The target asset of a transfer or the method a stage change entered (empty once the
+    /// workflow is complete); empty for other events.
+    pub target_id: String,
+    /// Grams consumed or added, or litres transferred; absent for stage changes.
+    pub amount: Option<f64>,
+    /// The organism that consumed the material; absent for other events, for consumption by a
+    /// unit operation, and in event logs written before consumers were recorded.
+    #[serde(default)]
+    pub consumer_id: Option<String>,
+}
+
+impl EventRecord {
+    /// Describes `event`, which happened at `tick` during `stage_id`.
+    pub fn from_event(event: &SimulationEvent, tick: u64, stage_id: &str) -> Self {
+        let mut consumer_id = None;
+        let (id, target_id, amount) = match event {
+            SimulationEvent::MaterialConsumed { id, amount, consumer_id: consumer } => {
+                consumer_id = consumer.clone();
+                (id.clone(), String::new(), Some(*amount))
+            }
+            SimulationEvent::MaterialAdded { id, amount } | SimulationEvent::MaterialRemoved { id, amount } => {
+                (id.clone(), String::new(), Some(*amount))
+            }
+            SimulationEvent::MaterialTransferred { from_asset_id, to_asset_id, volume_l } => {
+                (from_asset_id.clone(), to_asset_id.clone(), Some(*volume_l))
+            }
+            SimulationEvent::StageChanged { from_method_id, to_method_id, .. } => (
+                from_method_id.clone().unwrap_or_default(),
+                to_method_id.clone().unwrap_or_default(),
+                None,
+            ),
+        };
+        Self {
+            tick,
+            stage_id: stage_id.to_string(),
+            event_type: event.kind().to_string(),
+            id,
+            target_id,
+            amount,
+            consumer_id,
+        }
+    }
+
+    /// The event this record describes, or `None` if its type is unknown or it lacks an amount
+    /// its type requires.
+    pub fn to_event(&self) -> Option<SimulationEvent> {
+        let non_empty = |id: &str| (!id.is_empty()).then(|| id.to_string());
+        Some(match self.event_type.as_str() {
+            "material_consumed" => SimulationEvent::MaterialConsumed {
+                id: self.id.clone(),
+                amount: self.amount?,
+                consumer_id: self.consumer_id.clone(),
+            },
+            "material_added" => SimulationEvent::MaterialAdded { id: self.id.clone(), amount: self.amount? },
+            "material_removed" => SimulationEvent::MaterialRemoved { id: self.id.clone(), amount: self.amount? },
+            "material_transferred" => SimulationEvent::MaterialTransferred {
+                from_asset_id: self.id.clone(),
+                to_asset_id: self.target_id.clone(),
+                volume_l: self.amount?,
+            },
+            "stage_changed" => SimulationEvent::StageChanged {
+                from_method_id: non_empty(&self.id),
+                to_method_id: non_empty(&self.target_id),
+                tick: self.tick,
+            },
+            _ => return None,
+        })
+    }
+}
+
+/// A destination for the time-series log, handed the state at the end of every tick.
+pub trait TimeSeriesSink: Send {
+    /// Records `state` as it stands at the end of a tick of `stage_id`. `timestamp` is the
+    /// tick's wall-clock time when the run is anchored to a start time.
+    fn log_state(
+        &mut self,
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error>;
+
+    /// Pushes any buffered records to their destination, e.g. at a checkpoint.
+    fn flush(&mut self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    /// Records the events raised after the last logged tick by the commands that ended the run.
+    /// No row of the log carries them, so most sinks ignore them; the event log of a
+    /// `TimeSeriesLogger` records them to cover the whole run.
+    fn log_trailing_events(&mut self, _state: &SimulationState) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    /// Completes the log once the run is over.
+    fn finish(&mut self) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+
+    /// Starts writing to a new file at `path` in the same format, as `SimulationEngine::reset`
+    /// requests. Sinks that do not write files ignore it.
+    fn redirect(&mut self, _path: &str) -> Result<(), io::Error> {
+        Ok(())
+    }
+
+    /// The records logged so far, for sinks that keep them in memory.
+    fn records(&self) -> Option<&[LogRecord]> {
+        None
+    }
+}
+
+/// When a text log closes its current file and continues in a new segment.
+///
+/// Segments after the first are named by inserting their index before the extension
+/// (`upstream_consortium.csv`, `upstream_consortium.1.csv`, …; see [`segment_path`]). Each segment
+/// is a complete log with its own header, and `analysis::read_log` reads the whole set, in
+/// order, from the base path. Parquet logs ignore the policy.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RotationPolicy {
+    /// Start a new segment once the current one holds this many rows.
+    pub max_rows: Option<usize>,
+    /// Start a new segment once this many bytes of the current one have reached the file. Rows
+    /// still buffered under the flush policy are not counted yet, so a segment may overshoot by
+    /// up to the buffered rows.
+    pub max_bytes: Option<u64>,
+    /// Gzip each segment once it is closed, replacing it with `<segment>.gz`. The last segment
+    /// is compressed when the log is finished.
+    pub compress: bool,
+}
+
+impl RotationPolicy {
+    /// Keep the whole log in one file. This is the default.
+    pub fn never() -> Self {
+        Self::default()
+    }
+
+    /// Start a new segment every `rows` rows.
+    pub fn every_rows(rows: usize) -> Self {
+        Self { max_rows: Some(rows.max(1)), ..Self::default() }
+    }
+
+    /// Start a new segment every `megabytes` MB (10^6 bytes).
+    pub fn every_megabytes(megabytes: u64) -> Self {
+        Self { max_bytes: Some(megabytes.max(1) * 1_000_000), ..Self::default() }
+    }
+
+    /// Also gzip closed segments.
+    pub fn compressed(mut self) -> Self {
+        self.compress = true;
+        self
+    }
+
+    fn is_due(&self, rows: usize, bytes: u64) -> bool {
+        self.max_rows.is_some_and(|max_rows| rows >= max_rows) || self.max_bytes.is_some_and(|max_bytes| bytes >= max_bytes)
+    }
+}
+
+/// The path of segment `index` of a log rotated from `base`: `base` itself for the first segment,
+/// and the index inserted before the extension for the rest (`run.csv` → `run.2.csv`).
+pub fn segment_path(base: &str, index: usize) -> PathBuf {
+    let base = Path::new(base);
+    if index == 0 {
+        return base.to_path_buf();
+    }
+    let stem = base.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match base.extension() {
+        Some(extension) => format!("{}.{}.{}", stem, index, extension.to_string_lossy()),
+        None => format!("{}.{}", stem, index),
+    };
+    base.with_file_name(name)
+}
+
+/// `path` with `.gz` appended, the name of a compressed segment.
+fn gz_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+/// The segments of the log at `base` in order, each either plain or gzipped. Empty if the log
+/// does not exist.
+pub fn log_segments(base: &str) -> Vec<PathBuf> {
+    let mut segments = Vec::new();
+    for index in 0.. {
+        let path = segment_path(base, index);
+        let compressed = gz_path(&path);
+        if path.exists() {
+            segments.push(path);
+        } else if compressed.exists() {
+            segments.push(compressed);
+        } else {
+            break;
+        }
+    }
+    segments
+}
+
+/// The path of the event log written next to the log at `log_path`: `.events` inserted before
+/// the extension (`run.csv` → `run.events.csv`). Parquet logs get a CSV event log.
+pub fn event_log_path(log_path: &str) -> PathBuf {
+    let path = Path::new(log_path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension().map(|extension| extension.to_string_lossy()) {
+        Some(extension) if extension == "parquet" => format!("{}.events.csv", stem),
+        Some(extension) => format!("{}.events.{}", stem, extension),
+        None => format!("{}.events", stem),
+    };
+    path.with_file_name(name)
+}
+
+fn remove_if_present(path: &Path) -> Result<(), io::Error> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Removes the segments and event log a previous log left at `base`, so they are not read as
+/// part of a new log written there.
+fn remove_stale_segments(base: &str) -> Result<(), io::Error> {
+    remove_if_present(&gz_path(Path::new(base)))?;
+    remove_if_present(&event_log_path(base))?;
+    for index in 1.. {
+        let path = segment_path(base, index);
+        let compressed = gz_path(&path);
+        if !path.exists() && !compressed.exists() {
+            break;
+        }
+        remove_if_present(&path)?;
+        remove_if_present(&compressed)?;
+    }
+    Ok(())
+}
+
+/// Replaces the closed segment at `path` with a gzipped copy.
+fn compress_segment(path: &Path) -> Result<(), io::Error> {
+    let mut encoder = GzEncoder::new(BufWriter::new(fs::File::create(gz_path(path))?), Compression::default());
+    io::copy(&mut fs::File::open(path)?, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    fs::remove_file(path)
+}
+
+/// A log file that counts the bytes written to it, for size-based rotation.
+struct SegmentFile {
+    file: fs::File,
+    bytes: u64,
+}
+
+impl SegmentFile {
+    fn create(path: &Path) -> Result<Self, io::Error> {
+        Ok(Self { file: fs::File::create(path)?, bytes: 0 })
+    }
+}
+
+impl Write for SegmentFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// The series a wide log writes as their own numeric columns, identified by id.
+///
+/// Columns are named `conc_<molecule>`, `gas_<gas>`, `biomass_<organism>` and `mu_<organism>`,
+/// `uptake_<substrate>`, and `asset_<asset>_temperature` / `asset_<asset>_ph`; see
+/// [`series_column`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WideSeries {
+    pub molecule_ids: Vec<String>,
+    pub gas_ids: Vec<String>,
+    pub organism_ids: Vec<String>,
+    /// Molecule ids of the substrates whose uptake rates are logged.
+    pub substrate_ids: Vec<String>,
+    pub asset_ids: Vec<String>,
+}
+
+/// Prefixes of the per-series columns of a wide log.
+pub const SERIES_PREFIXES: [&str; 6] = ["conc_", "gas_", "biomass_", "mu_", "uptake_", "asset_"];
+
+/// The column name of one series in a wide log: `prefix_id`, with every character of the id
+/// that is not ASCII alphanumeric replaced by `_` (e.g. `conc_CHEBI_17234`).
+pub fn series_column(prefix: &str, id: &str) -> String {
+    let id: String = id.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    format!("{}_{}", prefix, id)
+}
+
+/// Whether a log column holds a single numeric series rather than one of the standard columns.
+pub fn is_series_column(name: &str) -> bool {
+    !name.ends_with("_json") && SERIES_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+}
+
+impl WideSeries {
+    /// The full header of a wide log.
+    fn header(&self) -> Vec<String> {
+        let mut header: Vec<String> =
+            ["tick", "stage_id", "timestamp", "media_volume_l", "media_ph", "total_biomass_g"]
+                .iter()
+                .map(|c| c.to_string())
+                .collect();
+        header.extend(self.molecule_ids.iter().map(|id| series_column("conc", id)));
+        header.extend(self.gas_ids.iter().map(|id| series_column("gas", id)));
+        header.extend(self.organism_ids.iter().map(|id| series_column("biomass", id)));
+        header.extend(self.organism_ids.iter().map(|id| series_column("mu", id)));
+        header.extend(self.substrate_ids.iter().map(|id| series_column("uptake", id)));
+        for id in &self.asset_ids {
+            header.push(format!("{}_temperature", series_column("asset", id)));
+            header.push(format!("{}_ph", series_column("asset", id)));
+        }
+        header.push("events_json".to_string());
+        header
+    }
+
+    /// One wide row of `state`. Series that are absent from the state are left empty.
+    fn row(
+        &self,
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Vec<String>, serde_json::Error> {
+        let number = |value: Option<f64>| value.map_or_else(String::new, |v| v.to_string());
+        let vessel = state.active_vessel();
+        let composition = &vessel.media.composition;
+
+        let mut row = vec![
+            state.tick.to_string(),
+            stage_id.to_string(),
+            timestamp.map(format_timestamp).unwrap_or_default(),
+            vessel.media.volume.value.to_string(),
+            vessel.media.ph.to_string(),
+            state.metrics.total_biomass_g.to_string(),
+        ];
+        row.extend(self.molecule_ids.iter().map(|id| {
+            number(
+                composition
+                    .dissolved_components
+                    .iter()
+                    .find(|c| &c.molecule_id == id)
+                    .map(|c| c.concentration.value),
+            )
+        }));
+        row.extend(self.gas_ids.iter().map(|id| {
+            number(composition.dissolved_gases.iter().find(|g| &g.gas_id == id).map(|g| g.concentration.value))
+        }));
+        row.extend(
+            self.organism_ids
+                .iter()
+                .map(|id| number(vessel.organisms.states.get(id).map(|o| o.biomass.value))),
+        );
+        let metrics = &state.metrics;
+        row.extend(self.organism_ids.iter().map(|id| number(metrics.specific_growth_rates.get(id).copied())));
+        row.extend(self.substrate_ids.iter().map(|id| number(metrics.uptake_rates.get(id).copied())));
+        for id in &self.asset_ids {
+            let asset = state.assets.get(id);
+            row.push(number(asset.map(|a| a.temperature)));
+            row.push(number(asset.map(|a| a.ph)));
+        }
+        row.push(serde_json::to_string(&state.events)?);
+        Ok(row)
+    }
+}
+
+enum LogSink {
+    Csv(Box<Writer<SegmentFile>>),
+    JsonLines(BufWriter<SegmentFile>),
+    #[cfg(feature = "parquet")]
+    Parquet(Box<ParquetLogWriter>),
+}
+
+impl LogSink {
+    /// Creates the file at `path` for a log in `format`, writing the header of a wide log.
+    fn create(path: &Path, format: LogFormat, wide: Option<&WideSeries>) -> Result<Self, io::Error> {
+        Ok(match format {
+            LogFormat::Csv => {
+                let mut writer = csv_writer(SegmentFile::create(path)?);
+                if let Some(series) = wide {
+                    writer.write_record(series.header())?;
+                    writer.flush()?;
+                }
+                LogSink::Csv(Box::new(writer))
+            }
+            LogFormat::JsonLines => {
+                LogSink::JsonLines(BufWriter::with_capacity(WRITE_BUFFER_BYTES, SegmentFile::create(path)?))
+            }
+            #[cfg(feature = "parquet")]
+            LogFormat::Parquet => LogSink::Parquet(Box::new(
+                ParquetLogWriter::new(fs::File::create(path)?).map_err(io::Error::other)?,
+            )),
+        })
+    }
+
+    /// Bytes of the current file written so far, excluding buffered rows.
+    fn bytes_written(&self) -> u64 {
+        match self {
+            LogSink::Csv(writer) => writer.get_ref().bytes,
+            LogSink::JsonLines(writer) => writer.get_ref().bytes,
+            #[cfg(feature = "parquet")]
+            LogSink::Parquet(_) => 0,
+        }
+    }
+}
+
+/// The event log of a [`TimeSeriesLogger`]: one row per event, as CSV or JSON Lines.
+struct EventLog {
+    sink: LogSink,
+    /// The stage of the last logged tick, during which the commands whose events are logged with
+    /// the next tick ran.
+    previous_stage_id: Option<String>,
+}
+
+impl EventLog {
+    fn append(&mut self, event: &SimulationEvent, tick: u64, stage_id: &str) -> Result<(), anyhow::Error> {
+        let record = EventRecord::from_event(event, tick, stage_id);
+        match &mut self.sink {
+            LogSink::Csv(writer) => writer.serialize(record)?,
+            LogSink::JsonLines(writer) => writeln!(writer, "{}", serde_json::to_string(&record)?)?,
+            #[cfg(feature = "parquet")]
+            LogSink::Parquet(_) => unreachable!("event logs are written as text"),
+        }
+        Ok(())
+    }
+
+    /// Appends the events logged with a tick of `stage_id`.
+    fn log_state(&mut self, state: &SimulationState, stage_id: &str) -> Result<(), anyhow::Error> {
+        let previous_stage_id = self.previous_stage_id.replace(stage_id.to_string());
+        for event in &state.events {
+            let tick = event.tick(state.tick);
+            let stage_id = match &previous_stage_id {
+                Some(previous) if tick < state.tick => previous.as_str(),
+                _ => stage_id,
+            };
+            self.append(event, tick, stage_id)?;
+        }
+        Ok(())
+    }
+
+    /// Appends the events raised by the final commands, during the last logged tick.
+    fn log_trailing_events(&mut self, state: &SimulationState) -> Result<(), anyhow::Error> {
+        let stage_id = self.previous_stage_id.clone().unwrap_or_default();
+        for event in &state.events {
+            self.append(event, state.tick, &stage_id)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        match &mut self.sink {
+            LogSink::Csv(writer) => writer.flush(),
+            LogSink::JsonLines(writer) => writer.flush(),
+            #[cfg(feature = "parquet")]
+            LogSink::Parquet(_) => Ok(()),
+        }
+    }
+}
+
+/// The file-backed time-series log, in any [`LogFormat`] or the wide CSV layout.
 pub struct TimeSeriesLogger {
-    writer: Writer<fs::File>,
+    sink: LogSink,
+    /// The path of the first segment, from which later segments are named.
+    path: String,
+    /// Set for wide CSV logs, which write these series as columns instead of nested JSON.
+    wide: Option<WideSeries>,
+    flush_policy: FlushPolicy,
+    buffered_rows: usize,
+    last_flush: Instant,
+    last_stage_id: Option<String>,
+    rotation: RotationPolicy,
+    /// Index and row count of the segment being written.
+    segment: usize,
+    segment_rows: usize,
+    /// Set once the log has been finished, after which it takes no more rows.
+    finished: bool,
+    events: Option<EventLog>,
+}
+
+/// The wall-clock time at which `tick` occurs in a batch that started at `start_time`.
+pub fn tick_timestamp(start_time: DateTime<Utc>, tick: u64) -> DateTime<Utc> {
+    start_time + Duration::milliseconds((tick as f64 * TIME_STEP_HR * 3_600_000.0).round() as i64)
+}
+
+/// Capacity of the text writers' buffers: large enough to hold many rows, so that the flush
+/// policy rather than the buffer decides when rows reach the file.
+const WRITE_BUFFER_BYTES: usize = 1 << 20;
+
+fn csv_writer(file: SegmentFile) -> Writer<SegmentFile> {
+    WriterBuilder::new().buffer_capacity(WRITE_BUFFER_BYTES).from_writer(file)
+}
+
+/// Formats a tick timestamp the way every log format stores it: RFC 3339, whole seconds, `Z`.
+fn format_timestamp(timestamp: DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
 }
 
 impl TimeSeriesLogger {
-    pub fn new(path: &str) -> Result<Self, io::Error> {
-        let writer = Writer::from_path(path)?;
-        Ok(Self { writer })
+    /// Creates a log at `path`, replacing any segments a previous log left there.
+    pub fn new(path: &str, format: LogFormat) -> Result<Self, io::Error> {
+        Self::create(path, format, None)
+    }
+
+    /// Creates a wide CSV log with one numeric column per series in `series` and a minimal
+    /// `events_json` column, for use in spreadsheets and dataframes.
+    pub fn new_wide(path: &str, series: WideSeries) -> Result<Self, io::Error> {
+        Self::create(path, LogFormat::Csv, Some(series))
+    }
+
+    fn create(path: &str, format: LogFormat, wide: Option<WideSeries>) -> Result<Self, io::Error> {
+        remove_stale_segments(path)?;
+        Ok(Self {
+            sink: LogSink::create(Path::new(path), format, wide.as_ref())?,
+            path: path.to_string(),
+            wide,
+            flush_policy: FlushPolicy::default(),
+            buffered_rows: 0,
+            last_flush: Instant::now(),
+            last_stage_id: None,
+            rotation: RotationPolicy::default(),
+            segment: 0,
+            segment_rows: 0,
+            finished: false,
+            events: None,
+        })
+    }
+
+    /// Also writes one row per event to [`event_log_path`] of the log's path, as JSON Lines for a
+    /// JSON Lines log and as CSV otherwise. The log itself keeps its embedded events.
+    ///
+    /// The event log places every event at the tick and stage in which it happened, and also
+    /// records the events raised by the commands that end the run, which no row of the log
+    /// carries. It is flushed with the log but never rotated.
+    pub fn with_event_log(mut self) -> Result<Self, io::Error> {
+        let format = match self.format() {
+            LogFormat::JsonLines => LogFormat::JsonLines,
+            _ => LogFormat::Csv,
+        };
+        self.events = Some(EventLog {
+            sink: LogSink::create(&event_log_path(&self.path), format, None)?,
+            previous_stage_id: None,
+        });
+        Ok(self)
+    }
+
+    /// Whether the logger writes an event log.
+    pub fn has_event_log(&self) -> bool {
+        self.events.is_some()
+    }
+
+    /// Sets when the log moves on to a new file segment. By default it never does.
+    pub fn with_rotation(mut self, policy: RotationPolicy) -> Self {
+        self.rotation = policy;
+        self
+    }
+
+    pub fn rotation(&self) -> RotationPolicy {
+        self.rotation
+    }
+
+    /// Closes the current segment, compressing it if the policy asks for that, and continues in
+    /// the next one.
+    fn rotate(&mut self) -> Result<(), anyhow::Error> {
+        self.flush()?;
+        let next = LogSink::create(&segment_path(&self.path, self.segment + 1), self.format(), self.wide.as_ref())?;
+        // Replacing the sink drops, and so closes, the current file.
+        self.sink = next;
+        if self.rotation.compress {
+            compress_segment(&segment_path(&self.path, self.segment))?;
+        }
+        self.segment += 1;
+        self.segment_rows = 0;
+        Ok(())
+    }
+
+    /// Sets when buffered rows are flushed. The default flushes after every row.
+    pub fn with_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
+        self
     }
 
-    pub fn log_state(&mut self, state: &SimulationState, stage_id: &str) -> Result<(), anyhow::Error> {
+    pub fn flush_policy(&self) -> FlushPolicy {
+        self.flush_policy
+    }
+
+    /// Records that a row was written and flushes if the policy, a stage change, or a notable
+    /// event calls for it.
+    fn row_written(&mut self, state: &SimulationState, stage_id: &str) -> Result<(), anyhow::Error> {
+        self.buffered_rows += 1;
+        self.segment_rows += 1;
+        let stage_changed = self.last_stage_id.as_deref() != Some(stage_id);
+        if stage_changed {
+            self.last_stage_id = Some(stage_id.to_string());
+        }
+        let notable_event = state
+            .events
+            .iter()
+            .any(|event| !matches!(event, SimulationEvent::MaterialConsumed { .. }));
+        if stage_changed || notable_event || self.flush_policy.is_due(self.buffered_rows, self.last_flush) {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// The series of a wide log, or `None` for the standard layout.
+    pub fn wide_series(&self) -> Option<&WideSeries> {
+        self.wide.as_ref()
+    }
+
+    pub fn format(&self) -> LogFormat {
+        match self.sink {
+            LogSink::Csv(_) => LogFormat::Csv,
+            LogSink::JsonLines(_) => LogFormat::JsonLines,
+            #[cfg(feature = "parquet")]
+            LogSink::Parquet(_) => LogFormat::Parquet,
+        }
+    }
+}
+
+impl TimeSeriesSink for TimeSeriesLogger {
+    fn log_state(
+        &mut self,
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error> {
+        if self.finished {
+            anyhow::bail!("the time-series log '{}' has already been finished", self.path);
+        }
+        // Rotating only once another row arrives means no segment is ever left empty.
+        if self.rotation.is_due(self.segment_rows, self.sink.bytes_written()) {
+            self.rotate()?;
+        }
+        if let Some(events) = &mut self.events {
+            events.log_state(state, stage_id)?;
+        }
+        #[cfg(feature = "parquet")]
+        if let LogSink::Parquet(writer) = &mut self.sink {
+            return writer.append(state, stage_id, timestamp.map(format_timestamp));
+        }
+        if let (Some(series), LogSink::Csv(writer)) = (&self.wide, &mut self.sink) {
+            writer.write_record(series.row(state, stage_id, timestamp)?)?;
+            return self.row_written(state, stage_id);
+        }
+
+        let entry = LogRecord::from_state(state, stage_id, timestamp)?;
+
+        match &mut self.sink {
+            LogSink::Csv(writer) => writer.serialize(entry)?,
+            LogSink::JsonLines(writer) => writeln!(writer, "{}", entry.to_json_line()?)?,
+            #[cfg(feature = "parquet")]
+            LogSink::Parquet(_) => unreachable!("Parquet rows are appended above"),
+        }
+        self.row_written(state, stage_id)
+    }
+
+    /// Writes every buffered row to the file. For Parquet this closes the current row group.
+    fn flush(&mut self) -> Result<(), anyhow::Error> {
+        match &mut self.sink {
+            LogSink::Csv(writer) => writer.flush()?,
+            LogSink::JsonLines(writer) => writer.flush()?,
+            #[cfg(feature = "parquet")]
+            LogSink::Parquet(writer) => writer.flush()?,
+        }
+        if let Some(events) = &mut self.events {
+            events.flush()?;
+        }
+        self.buffered_rows = 0;
+        self.last_flush = Instant::now();
+        Ok(())
+    }
+
+    /// Completes the log file: flushes the text formats, compressing the last segment of a
+    /// compressed rotation, and writes the Parquet footer.
+    fn finish(&mut self) -> Result<(), anyhow::Error> {
+        if self.finished {
+            return Ok(());
+        }
+        #[cfg(feature = "parquet")]
+        if let LogSink::Parquet(writer) = &mut self.sink {
+            writer.finish()?;
+            if let Some(events) = &mut self.events {
+                events.flush()?;
+            }
+            self.finished = true;
+            return Ok(());
+        }
+        self.flush()?;
+        self.finished = true;
+        if self.rotation.compress {
+            compress_segment(&segment_path(&self.path, self.segment))?;
+        }
+        Ok(())
+    }
+
+    fn redirect(&mut self, path: &str) -> Result<(), io::Error> {
+        // Finish the current file before a new log, possibly at the same path, replaces it.
+        self.finish().map_err(io::Error::other)?;
+        let logger = match self.wide.clone() {
+            Some(series) => Self::new_wide(path, series)?,
+            None => Self::new(path, self.format())?,
+        }
+        .with_flush_policy(self.flush_policy)
+        .with_rotation(self.rotation);
+        *self = if self.has_event_log() { logger.with_event_log()? } else { logger };
+        Ok(())
+    }
+
+    fn log_trailing_events(&mut self, state: &SimulationState) -> Result<(), anyhow::Error> {
+        if let Some(events) = &mut self.events {
+            events.log_trailing_events(state)?;
+        }
+        Ok(())
+    }
+}
+
+impl LogRecord {
+    /// Builds the record of `state` at the end of a tick of `stage_id`.
+    pub fn from_state(
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<Self, serde_json::Error> {
         let asset_states_json = serde_json::to_string(
             &state
                 .assets
@@ -41,25 +860,363 @@ impl TimeSeriesLogger {
                 .collect::<serde_json::Map<String, serde_json::Value>>(),
         )?;
 
-        let organisms_json = serde_json::to_string(&state.organisms.states)?;
+        // The media and organism columns describe the active vessel; `vessels_json` holds the
+        // contents of every vessel keyed by asset id.
+        let vessel = state.active_vessel();
+        let organisms_json = serde_json::to_string(&vessel.organisms.states)?;
         let events_json = serde_json::to_string(&state.events)?;
-        let dissolved_components_json = serde_json::to_string(&state.media.composition.dissolved_components)?;
-        let dissolved_gases_json = serde_json::to_string(&state.media.composition.dissolved_gases)?;
+        let dissolved_components_json = serde_json::to_string(&vessel.media.composition.dissolved_components)?;
+        let dissolved_gases_json = serde_json::to_string(&vessel.media.composition.dissolved_gases)?;
+        let vessels_json = serde_json::to_string(&state.vessels)?;
 
-        let entry = LogEntry {
+        Ok(Self {
             tick: state.tick,
             stage_id: stage_id.to_string(),
             organisms_json,
-            media_volume_l: state.media.volume.value,
-            media_ph: state.media.ph,
+            media_volume_l: vessel.media.volume.value,
+            media_ph: vessel.media.ph,
             dissolved_components_json,
             dissolved_gases_json,
             asset_states_json,
             events_json,
-        };
+            vessels_json,
+            timestamp: timestamp.map(format_timestamp),
+            total_biomass_g: state.metrics.total_biomass_g,
+            growth_rates_json: serde_json::to_string(&state.metrics.specific_growth_rates)?,
+            uptake_rates_json: serde_json::to_string(&state.metrics.uptake_rates)?,
+            series: BTreeMap::new(),
+        })
+    }
+
+    /// Renders the entry as one JSON object, nesting the embedded JSON columns as real JSON.
+    /// Field names match the CSV columns without the `_json` suffix.
+    pub(crate) fn to_json_line(&self) -> Result<String, serde_json::Error> {
+        Ok(format!(
+            concat!(
+                r#"{{"tick":{},"stage_id":{},"organisms":{},"media_volume_l":{},"media_ph":{},"#,
+                r#""dissolved_components":{},"dissolved_gases":{},"asset_states":{},"events":{},"#,
+                r#""vessels":{},"timestamp":{},"total_biomass_g":{},"growth_rates":{},"uptake_rates":{}}}"#
+            ),
+            self.tick,
+            serde_json::to_string(&self.stage_id)?,
+            self.organisms_json,
+            serde_json::to_string(&self.media_volume_l)?,
+            serde_json::to_string(&self.media_ph)?,
+            self.dissolved_components_json,
+            self.dissolved_gases_json,
+            self.asset_states_json,
+            self.events_json,
+            self.vessels_json,
+            serde_json::to_string(&self.timestamp)?,
+            serde_json::to_string(&self.total_biomass_g)?,
+            self.growth_rates_json,
+            self.uptake_rates_json,
+        ))
+    }
+}
+
+impl Drop for TimeSeriesLogger {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            log::warn!("Failed to finish the time-series log: {}", e);
+        }
+    }
+}
 
-        self.writer.serialize(entry)?;
-        self.writer.flush()?;
+/// A sink that keeps every record in memory, for inspecting a run programmatically without
+/// writing and re-reading a file. Retrieve the records with `SimulationEngine::logged_records`.
+#[derive(Debug, Default)]
+pub struct InMemorySink {
+    records: Vec<LogRecord>,
+}
+
+impl InMemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TimeSeriesSink for InMemorySink {
+    fn log_state(
+        &mut self,
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error> {
+        self.records.push(LogRecord::from_state(state, stage_id, timestamp)?);
         Ok(())
     }
-}
\ No newline at end of file
+
+    fn records(&self) -> Option<&[LogRecord]> {
+        Some(&self.records)
+    }
+}
+
+/// A sink that sends every record down a channel, so another thread (e.g. a live dashboard) can
+/// consume the run as it happens. The channel is unbounded; once the receiver is dropped, logging
+/// to this sink fails.
+#[derive(Debug)]
+pub struct ChannelSink {
+    sender: Sender<LogRecord>,
+}
+
+impl ChannelSink {
+    /// Creates the sink and the receiving end of its channel.
+    pub fn new() -> (Self, Receiver<LogRecord>) {
+        let (sender, receiver) = mpsc::channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl TimeSeriesSink for ChannelSink {
+    fn log_state(
+        &mut self,
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error> {
+        self.sender
+            .send(LogRecord::from_state(state, stage_id, timestamp)?)
+            .map_err(|_| anyhow::anyhow!("the receiver of the channel sink has been dropped"))
+    }
+}
+
+/// A sink registered with the engine, and whether its failures abort the run.
+///
+/// A lenient sink that fails is reported once and then skipped for the rest of the run.
+pub(crate) struct RegisteredSink {
+    pub(crate) sink: Box<dyn TimeSeriesSink>,
+    pub(crate) strict: bool,
+    pub(crate) failed: bool,
+}
+
+impl RegisteredSink {
+    pub(crate) fn new(sink: Box<dyn TimeSeriesSink>, strict: bool) -> Self {
+        Self { sink, strict, failed: false }
+    }
+
+    /// Runs `op` on the sink, applying its failure policy to the result.
+    pub(crate) fn apply(
+        &mut self,
+        op: impl FnOnce(&mut dyn TimeSeriesSink) -> Result<(), anyhow::Error>,
+    ) -> Result<(), anyhow::Error> {
+        if self.failed {
+            return Ok(());
+        }
+        match op(self.sink.as_mut()) {
+            Err(e) if !self.strict => {
+                log::warn!("A time-series sink failed and is disabled for the rest of the run: {}", e);
+                self.failed = true;
+                Ok(())
+            }
+            result => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis::{generate_bom, read_log}, simulation::state::{TickMetrics, Vessel}};
+    use bioforge_schemas::{
+        environment::{DissolvedComponent, MediaComposition, MediaState, Measurement},
+        material::Material,
+        organism_state::{IndividualOrganismState, OrganismState},
+        process::Process,
+    };
+    use std::collections::HashMap;
+
+    /// A log path under the system temp dir, unique to this test binary.
+    fn log_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("bioforge_logger_{}_{}", std::process::id(), name))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    /// The state of one vessel of glucose and a growing organism at the end of `tick`, which
+    /// consumed a gram of glucose.
+    fn state(tick: u64) -> SimulationState {
+        let biomass = 1.0 + 0.25 * tick as f64;
+        let media = MediaState {
+            volume: Measurement { value: 2.0, unit: "L".to_string() },
+            ph: 7.0,
+            composition: MediaComposition {
+                dissolved_components: vec![DissolvedComponent {
+                    molecule_id: "CHEBI:17234".to_string(),
+                    molecule_name: "D-glucose".to_string(),
+                    concentration: Measurement { value: 20.0 - 0.5 * tick as f64, unit: "g/L".to_string() },
+                }],
+                dissolved_gases: vec![],
+            },
+        };
+        let organisms = OrganismState {
+            states: BTreeMap::from([(
+                "ORG-TEST".to_string(),
+                IndividualOrganismState { biomass: Measurement { value: biomass, unit: "g".to_string() } },
+            )]),
+        };
+        SimulationState {
+            tick,
+            ticks_in_current_stage: tick,
+            assets: BTreeMap::new(),
+            vessels: BTreeMap::from([("REACTOR".to_string(), Vessel::new(media, organisms))]),
+            active_vessel_id: "REACTOR".to_string(),
+            events: vec![SimulationEvent::MaterialConsumed {
+                id: "CHEBI:17234".to_string(),
+                amount: 1.0,
+                consumer_id: Some("ORG-TEST".to_string()),
+            }],
+            metrics: TickMetrics {
+                total_biomass_g: biomass,
+                specific_growth_rates: BTreeMap::from([("ORG-TEST".to_string(), 0.1 / (1.0 + tick as f64))]),
+                uptake_rates: BTreeMap::from([("CHEBI:17234".to_string(), 0.5)]),
+            },
+        }
+    }
+
+    /// Logs ticks `1..=ticks` with `logger`, finishes it, and returns the records logged.
+    fn write(mut logger: TimeSeriesLogger, ticks: u64) -> Vec<LogRecord> {
+        let start = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let mut written = Vec::new();
+        for tick in 1..=ticks {
+            let state = state(tick);
+            let timestamp = Some(tick_timestamp(start, tick));
+            logger.log_state(&state, "MTHD-CULT", timestamp).unwrap();
+            written.push(LogRecord::from_state(&state, "MTHD-CULT", timestamp).unwrap());
+        }
+        logger.finish().unwrap();
+        written
+    }
+
+    /// Asserts `read` holds the same data as `written`, comparing the nested JSON columns as
+    /// JSON, since formats that nest them do not keep their key order.
+    fn assert_same(read: &[LogRecord], written: &[LogRecord]) {
+        let json = |text: &str| serde_json::from_str::<serde_json::Value>(text).unwrap();
+        assert_eq!(read.len(), written.len());
+        for (read, written) in read.iter().zip(written) {
+            assert_eq!(
+                (read.tick, &read.stage_id, read.media_volume_l, read.media_ph, &read.timestamp, read.total_biomass_g),
+                (written.tick, &written.stage_id, written.media_volume_l, written.media_ph, &written.timestamp, written.total_biomass_g)
+            );
+            for (read, written) in [
+                (&read.organisms_json, &written.organisms_json),
+                (&read.dissolved_components_json, &written.dissolved_components_json),
+                (&read.dissolved_gases_json, &written.dissolved_gases_json),
+                (&read.asset_states_json, &written.asset_states_json),
+                (&read.events_json, &written.events_json),
+                (&read.vessels_json, &written.vessels_json),
+                (&read.growth_rates_json, &written.growth_rates_json),
+                (&read.uptake_rates_json, &written.uptake_rates_json),
+            ] {
+                assert_eq!(json(read), json(written));
+            }
+        }
+    }
+
+    fn round_trip(format: LogFormat, extension: &str) {
+        let path = log_path(&format!("round_trip.{}", extension));
+        let written = write(TimeSeriesLogger::new(&path, format).unwrap(), 5);
+        assert_same(&read_log(&path).unwrap(), &written);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn csv_logs_read_back_as_written() {
+        round_trip(LogFormat::Csv, "csv");
+    }
+
+    #[test]
+    fn json_lines_logs_read_back_as_written() {
+        round_trip(LogFormat::JsonLines, "jsonl");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_logs_read_back_as_written() {
+        round_trip(LogFormat::Parquet, "parquet");
+    }
+
+    #[test]
+    fn a_log_killed_mid_run_loses_only_the_rows_its_flush_policy_buffers() {
+        let start = DateTime::parse_from_rfc3339("2026-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        for rows in [1, 4] {
+            let path = log_path(&format!("killed_{}.csv", rows));
+            let mut logger = TimeSeriesLogger::new(&path, LogFormat::Csv).unwrap().with_flush_policy(FlushPolicy::every_rows(rows));
+            for tick in 1..=10 {
+                logger.log_state(&state(tick), "MTHD-CULT", Some(tick_timestamp(start, tick))).unwrap();
+                let on_disk = read_log(&path).unwrap().len() as u64;
+                assert!(on_disk <= tick && tick - on_disk < rows as u64, "{} of {} rows on disk", on_disk, tick);
+            }
+            // A process killed outright never runs the logger's drop, which would flush the rest.
+            std::mem::forget(logger);
+            let read = read_log(&path).unwrap();
+            assert!(10 - read.len() < rows, "{} rows survived", read.len());
+            assert_eq!(read.last().unwrap().tick, read.len() as u64);
+            fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn a_rotated_log_reads_back_every_row_in_order() {
+        let whole_path = log_path("whole.csv");
+        let whole = write(TimeSeriesLogger::new(&whole_path, LogFormat::Csv).unwrap(), 10);
+        let process: Process = serde_json::from_value(serde_json::json!({
+            "process_id": "PROC-TEST", "process_name": "PROC-TEST", "component_class": "Test", "status": "Active",
+            "notes": "", "default_workflow": ["MTHD-CULT"],
+            "methods": [{
+                "method_id": "MTHD-CULT", "stage": "MTHD-CULT", "technique": "batch", "required_asset_id": "REACTOR",
+                "operating_parameters": {}, "required_materials": [], "qc_checks": [], "required_rule_ids": []
+            }]
+        }))
+        .unwrap();
+        let no_costs = serde_json::json!({ "costs": [], "impacts": [] });
+        let glucose: Material = serde_json::from_value(serde_json::json!({
+            "material_id": "MEDIA-GLUCOSE", "material_name": "D-glucose", "material_class": "Chemical",
+            "material_subtype": "Carbon Source", "material_category": "PurchasedRawMaterial", "unit": "kg",
+            "metadata": {
+                "process_role": "Test", "vendor": null, "part_number": null, "notes": null,
+                "identifiers": { "cas_number": null, "chebi_id": "CHEBI:17234", "pubchem_cid": null }
+            },
+            "specifications": [], "formulation": null,
+            "techno_economic_and_lca_profile": { "lifecycle_stages": {
+                "manufacturing_and_acquisition": no_costs, "use_and_operation": no_costs, "maintenance": no_costs, "end_of_life": no_costs
+            } }
+        }))
+        .unwrap();
+        let materials = HashMap::from([(glucose.material_id.clone(), glucose)]);
+        let bom = |path: &str| generate_bom(path, &process, &HashMap::new(), &materials).unwrap();
+        // A gram of glucose on each of the ten ticks.
+        let whole_bom = bom(&whole_path);
+        assert_eq!((whole_bom.materials_consumed["MEDIA-GLUCOSE"], whole_bom.total_ticks), (10.0, 10));
+
+        for (name, policy) in [("rotated.csv", RotationPolicy::every_rows(3)), ("gzipped.jsonl", RotationPolicy::every_rows(3).compressed())] {
+            let path = log_path(name);
+            let format = if name.ends_with(".jsonl") { LogFormat::JsonLines } else { LogFormat::Csv };
+            let written = write(TimeSeriesLogger::new(&path, format).unwrap().with_rotation(policy), 10);
+            let segments = log_segments(&path);
+            assert_eq!(segments.len(), 4, "{:?}", segments);
+            assert_eq!(segments.iter().all(|s| s.extension().is_some_and(|e| e == "gz")), policy.compress);
+
+            let read = read_log(&path).unwrap();
+            assert_same(&read, &written);
+            assert_same(&read, &read_log(&whole_path).unwrap());
+            let consumed = |records: &[LogRecord]| -> f64 {
+                records
+                    .iter()
+                    .flat_map(|r| serde_json::from_str::<Vec<SimulationEvent>>(&r.events_json).unwrap())
+                    .map(|event| match event {
+                        SimulationEvent::MaterialConsumed { amount, .. } => amount,
+                        _ => 0.0,
+                    })
+                    .sum()
+            };
+            assert_eq!(consumed(&read), consumed(&whole));
+            assert_eq!(bom(&path), whole_bom);
+            for segment in segments {
+                fs::remove_file(segment).unwrap();
+            }
+        }
+        fs::remove_file(&whole_path).unwrap();
+    }
+}
@@ -0,0 +1,403 @@
+//! Columnar Parquet backend for the time-series log.
+//!
+//! Scalars are stored as plain columns and the organisms, media components, gases, asset
+//! states, and derived rates as lists of structs, so long runs can be scanned without parsing a JSON blob per row.
+//! Events and vessel contents are heterogeneous and stay JSON strings, as in the CSV log.
+
+use crate::{error::BioforgeError, logger::LogRecord, simulation::state::SimulationState};
+use arrow_array::{
+    builder::{Float64Builder, ListBuilder, StringBuilder, StructBuilder, UInt64Builder},
+    Array, ArrayRef, Float64Array, ListArray, RecordBatch, StringArray, StructArray, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef};
+use bioforge_schemas::{
+    environment::{DissolvedComponent, DissolvedGas, Measurement},
+    organism_state::IndividualOrganismState,
+};
+use parquet::arrow::{arrow_reader::ParquetRecordBatchReaderBuilder, ArrowWriter};
+use parquet::errors::ParquetError;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::sync::Arc;
+
+/// Rows buffered before they are written out as a record batch.
+const BATCH_ROWS: usize = 8192;
+
+fn organism_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("organism_id", DataType::Utf8, false),
+        Field::new("biomass", DataType::Float64, false),
+        Field::new("unit", DataType::Utf8, false),
+    ])
+}
+
+/// Fields shared by dissolved components and gases: an id, a name, and a concentration.
+fn dissolved_fields(id: &str, name: &str) -> Fields {
+    Fields::from(vec![
+        Field::new(id, DataType::Utf8, false),
+        Field::new(name, DataType::Utf8, false),
+        Field::new("concentration", DataType::Float64, false),
+        Field::new("unit", DataType::Utf8, false),
+    ])
+}
+
+fn asset_fields() -> Fields {
+    Fields::from(vec![
+        Field::new("asset_id", DataType::Utf8, false),
+        Field::new("temperature", DataType::Float64, false),
+        Field::new("ph", DataType::Float64, false),
+    ])
+}
+
+/// Fields of a derived rate: the id it applies to and its value.
+fn rate_fields(id: &str) -> Fields {
+    Fields::from(vec![Field::new(id, DataType::Utf8, false), Field::new("rate", DataType::Float64, false)])
+}
+
+fn list_of(fields: Fields) -> DataType {
+    DataType::List(Arc::new(Field::new("item", DataType::Struct(fields), true)))
+}
+
+fn schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        Field::new("tick", DataType::UInt64, false),
+        Field::new("stage_id", DataType::Utf8, false),
+        Field::new("timestamp", DataType::Utf8, true),
+        Field::new("media_volume_l", DataType::Float64, false),
+        Field::new("media_ph", DataType::Float64, false),
+        Field::new("organisms", list_of(organism_fields()), false),
+        Field::new("dissolved_components", list_of(dissolved_fields("molecule_id", "molecule_name")), false),
+        Field::new("dissolved_gases", list_of(dissolved_fields("gas_id", "gas_name")), false),
+        Field::new("asset_states", list_of(asset_fields()), false),
+        Field::new("events_json", DataType::Utf8, false),
+        Field::new("vessels_json", DataType::Utf8, false),
+        Field::new("total_biomass_g", DataType::Float64, false),
+        Field::new("specific_growth_rates", list_of(rate_fields("organism_id")), false),
+        Field::new("uptake_rates", list_of(rate_fields("molecule_id")), false),
+    ]))
+}
+
+fn list_builder(fields: Fields) -> ListBuilder<StructBuilder> {
+    ListBuilder::new(StructBuilder::from_fields(fields, 0))
+}
+
+fn string_field(builder: &mut StructBuilder, i: usize) -> &mut StringBuilder {
+    builder.field_builder(i).expect("field layout is fixed by the schema")
+}
+
+fn float_field(builder: &mut StructBuilder, i: usize) -> &mut Float64Builder {
+    builder.field_builder(i).expect("field layout is fixed by the schema")
+}
+
+/// Buffers log rows column by column and writes them to a Parquet file in batches.
+pub struct ParquetLogWriter {
+    writer: ArrowWriter<File>,
+    finished: bool,
+    rows: usize,
+    tick: UInt64Builder,
+    stage_id: StringBuilder,
+    timestamp: StringBuilder,
+    media_volume_l: Float64Builder,
+    media_ph: Float64Builder,
+    organisms: ListBuilder<StructBuilder>,
+    dissolved_components: ListBuilder<StructBuilder>,
+    dissolved_gases: ListBuilder<StructBuilder>,
+    asset_states: ListBuilder<StructBuilder>,
+    events_json: StringBuilder,
+    vessels_json: StringBuilder,
+    total_biomass_g: Float64Builder,
+    specific_growth_rates: ListBuilder<StructBuilder>,
+    uptake_rates: ListBuilder<StructBuilder>,
+}
+
+impl ParquetLogWriter {
+    pub fn new(file: File) -> Result<Self, ParquetError> {
+        Ok(Self {
+            writer: ArrowWriter::try_new(file, schema(), None)?,
+            finished: false,
+            rows: 0,
+            tick: UInt64Builder::new(),
+            stage_id: StringBuilder::new(),
+            timestamp: StringBuilder::new(),
+            media_volume_l: Float64Builder::new(),
+            media_ph: Float64Builder::new(),
+            organisms: list_builder(organism_fields()),
+            dissolved_components: list_builder(dissolved_fields("molecule_id", "molecule_name")),
+            dissolved_gases: list_builder(dissolved_fields("gas_id", "gas_name")),
+            asset_states: list_builder(asset_fields()),
+            events_json: StringBuilder::new(),
+            vessels_json: StringBuilder::new(),
+            total_biomass_g: Float64Builder::new(),
+            specific_growth_rates: list_builder(rate_fields("organism_id")),
+            uptake_rates: list_builder(rate_fields("molecule_id")),
+        })
+    }
+
+    pub fn append(
+        &mut self,
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<String>,
+    ) -> Result<(), anyhow::Error> {
+        let vessel = state.active_vessel();
+        self.tick.append_value(state.tick);
+        self.stage_id.append_value(stage_id);
+        self.timestamp.append_option(timestamp);
+        self.media_volume_l.append_value(vessel.media.volume.value);
+        self.media_ph.append_value(vessel.media.ph);
+
+        let organisms = self.organisms.values();
+        for (org_id, org_state) in &vessel.organisms.states {
+            string_field(organisms, 0).append_value(org_id);
+            float_field(organisms, 1).append_value(org_state.biomass.value);
+            string_field(organisms, 2).append_value(&org_state.biomass.unit);
+            organisms.append(true);
+        }
+        self.organisms.append(true);
+
+        let components = self.dissolved_components.values();
+        for component in &vessel.media.composition.dissolved_components {
+            string_field(components, 0).append_value(&component.molecule_id);
+            string_field(components, 1).append_value(&component.molecule_name);
+            float_field(components, 2).append_value(component.concentration.value);
+            string_field(components, 3).append_value(&component.concentration.unit);
+            components.append(true);
+        }
+        self.dissolved_components.append(true);
+
+        let gases = self.dissolved_gases.values();
+        for gas in &vessel.media.composition.dissolved_gases {
+            string_field(gases, 0).append_value(&gas.gas_id);
+            string_field(gases, 1).append_value(&gas.gas_name);
+            float_field(gases, 2).append_value(gas.concentration.value);
+            string_field(gases, 3).append_value(&gas.concentration.unit);
+            gases.append(true);
+        }
+        self.dissolved_gases.append(true);
+
+        let assets = self.asset_states.values();
+        for (asset_id, asset) in &state.assets {
+            string_field(assets, 0).append_value(asset_id);
+            float_field(assets, 1).append_value(asset.temperature);
+            float_field(assets, 2).append_value(asset.ph);
+            assets.append(true);
+        }
+        self.asset_states.append(true);
+
+        self.events_json.append_value(serde_json::to_string(&state.events)?);
+        self.vessels_json.append_value(serde_json::to_string(&state.vessels)?);
+
+        self.total_biomass_g.append_value(state.metrics.total_biomass_g);
+        for (builder, rates) in [
+            (&mut self.specific_growth_rates, &state.metrics.specific_growth_rates),
+            (&mut self.uptake_rates, &state.metrics.uptake_rates),
+        ] {
+            let entries = builder.values();
+            for (id, rate) in rates {
+                string_field(entries, 0).append_value(id);
+                float_field(entries, 1).append_value(*rate);
+                entries.append(true);
+            }
+            builder.append(true);
+        }
+
+        self.rows += 1;
+        if self.rows >= BATCH_ROWS {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn write_batch(&mut self) -> Result<(), ParquetError> {
+        if self.rows == 0 {
+            return Ok(());
+        }
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.tick.finish()),
+            Arc::new(self.stage_id.finish()),
+            Arc::new(self.timestamp.finish()),
+            Arc::new(self.media_volume_l.finish()),
+            Arc::new(self.media_ph.finish()),
+            Arc::new(self.organisms.finish()),
+            Arc::new(self.dissolved_components.finish()),
+            Arc::new(self.dissolved_gases.finish()),
+            Arc::new(self.asset_states.finish()),
+            Arc::new(self.events_json.finish()),
+            Arc::new(self.vessels_json.finish()),
+            Arc::new(self.total_biomass_g.finish()),
+            Arc::new(self.specific_growth_rates.finish()),
+            Arc::new(self.uptake_rates.finish()),
+        ];
+        let batch = RecordBatch::try_new(schema(), columns)?;
+        self.writer.write(&batch)?;
+        self.rows = 0;
+        Ok(())
+    }
+
+    /// Writes any buffered rows as a row group. The file stays unreadable until [`Self::finish`].
+    pub fn flush(&mut self) -> Result<(), ParquetError> {
+        if self.finished {
+            return Ok(());
+        }
+        self.write_batch()?;
+        self.writer.flush()
+    }
+
+    /// Writes any buffered rows and the file footer. The file is unreadable until this runs;
+    /// calling it again is a no-op.
+    pub fn finish(&mut self) -> Result<(), ParquetError> {
+        if self.finished {
+            return Ok(());
+        }
+        self.write_batch()?;
+        self.writer.finish()?;
+        self.finished = true;
+        Ok(())
+    }
+}
+
+fn column<'a, T: Array + 'static>(batch: &'a RecordBatch, name: &str) -> Result<&'a T, ParquetError> {
+    batch
+        .column_by_name(name)
+        .and_then(|c| c.as_any().downcast_ref::<T>())
+        .ok_or_else(|| ParquetError::General(format!("log column '{}' is missing or mistyped", name)))
+}
+
+fn struct_column<T: Array + 'static>(array: &StructArray, i: usize) -> Result<&T, ParquetError> {
+    array
+        .column(i)
+        .as_any()
+        .downcast_ref::<T>()
+        .ok_or_else(|| ParquetError::General(format!("log struct field {} is mistyped", i)))
+}
+
+fn list_entries(list: &ListArray, row: usize) -> Result<StructArray, ParquetError> {
+    list.value(row)
+        .as_any()
+        .downcast_ref::<StructArray>()
+        .cloned()
+        .ok_or_else(|| ParquetError::General("log list column does not hold structs".to_string()))
+}
+
+/// Reads a Parquet log back into the row layout shared with the CSV and JSON Lines logs.
+pub fn read_parquet_log(log_path: &str) -> Result<Vec<LogRecord>, BioforgeError> {
+    let parquet_error = |e: ParquetError| BioforgeError::ParquetError(log_path.to_string(), e);
+    let file = File::open(log_path).map_err(|e| BioforgeError::FileIO(log_path.to_string(), e))?;
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(|builder| builder.build())
+        .map_err(parquet_error)?;
+
+    let mut entries = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| parquet_error(e.into()))?;
+        read_batch(&batch, &mut entries).map_err(parquet_error)?;
+    }
+    Ok(entries)
+}
+
+fn read_batch(batch: &RecordBatch, entries: &mut Vec<LogRecord>) -> Result<(), ParquetError> {
+    let json_error = |e: serde_json::Error| ParquetError::External(Box::new(e));
+
+    let tick = column::<UInt64Array>(batch, "tick")?;
+    let stage_id = column::<StringArray>(batch, "stage_id")?;
+    let timestamp = column::<StringArray>(batch, "timestamp")?;
+    let media_volume_l = column::<Float64Array>(batch, "media_volume_l")?;
+    let media_ph = column::<Float64Array>(batch, "media_ph")?;
+    let organisms = column::<ListArray>(batch, "organisms")?;
+    let dissolved_components = column::<ListArray>(batch, "dissolved_components")?;
+    let dissolved_gases = column::<ListArray>(batch, "dissolved_gases")?;
+    let asset_states = column::<ListArray>(batch, "asset_states")?;
+    let events_json = column::<StringArray>(batch, "events_json")?;
+    let vessels_json = column::<StringArray>(batch, "vessels_json")?;
+    let total_biomass_g = column::<Float64Array>(batch, "total_biomass_g")?;
+    let specific_growth_rates = column::<ListArray>(batch, "specific_growth_rates")?;
+    let uptake_rates = column::<ListArray>(batch, "uptake_rates")?;
+
+    for row in 0..batch.num_rows() {
+        let entries_of = |list| list_entries(list, row);
+
+        let orgs = entries_of(organisms)?;
+        let (ids, biomass, units) = (
+            struct_column::<StringArray>(&orgs, 0)?,
+            struct_column::<Float64Array>(&orgs, 1)?,
+            struct_column::<StringArray>(&orgs, 2)?,
+        );
+        let organism_states: BTreeMap<String, IndividualOrganismState> = (0..orgs.len())
+            .map(|i| {
+                let state = IndividualOrganismState {
+                    biomass: Measurement { value: biomass.value(i), unit: units.value(i).to_string() },
+                };
+                (ids.value(i).to_string(), state)
+            })
+            .collect();
+
+        let comps = entries_of(dissolved_components)?;
+        let (ids, names, values, units) = (
+            struct_column::<StringArray>(&comps, 0)?,
+            struct_column::<StringArray>(&comps, 1)?,
+            struct_column::<Float64Array>(&comps, 2)?,
+            struct_column::<StringArray>(&comps, 3)?,
+        );
+        let components: Vec<DissolvedComponent> = (0..comps.len())
+            .map(|i| DissolvedComponent {
+                molecule_id: ids.value(i).to_string(),
+                molecule_name: names.value(i).to_string(),
+                concentration: Measurement { value: values.value(i), unit: units.value(i).to_string() },
+            })
+            .collect();
+
+        let gas_entries = entries_of(dissolved_gases)?;
+        let (ids, names, values, units) = (
+            struct_column::<StringArray>(&gas_entries, 0)?,
+            struct_column::<StringArray>(&gas_entries, 1)?,
+            struct_column::<Float64Array>(&gas_entries, 2)?,
+            struct_column::<StringArray>(&gas_entries, 3)?,
+        );
+        let gases: Vec<DissolvedGas> = (0..gas_entries.len())
+            .map(|i| DissolvedGas {
+                gas_id: ids.value(i).to_string(),
+                gas_name: names.value(i).to_string(),
+                concentration: Measurement { value: values.value(i), unit: units.value(i).to_string() },
+            })
+            .collect();
+
+        let assets = entries_of(asset_states)?;
+        let (ids, temperatures, phs) = (
+            struct_column::<StringArray>(&assets, 0)?,
+            struct_column::<Float64Array>(&assets, 1)?,
+            struct_column::<Float64Array>(&assets, 2)?,
+        );
+        let asset_map: serde_json::Map<String, serde_json::Value> = (0..assets.len())
+            .map(|i| {
+                let values = serde_json::json!({ "temperature": temperatures.value(i), "ph": phs.value(i) });
+                (ids.value(i).to_string(), values)
+            })
+            .collect();
+
+        let rates_json = |list| -> Result<String, ParquetError> {
+            let rates = entries_of(list)?;
+            let (ids, values) = (struct_column::<StringArray>(&rates, 0)?, struct_column::<Float64Array>(&rates, 1)?);
+            let rates: BTreeMap<&str, f64> = (0..rates.len()).map(|i| (ids.value(i), values.value(i))).collect();
+            serde_json::to_string(&rates).map_err(json_error)
+        };
+
+        entries.push(LogRecord {
+            tick: tick.value(row),
+            stage_id: stage_id.value(row).to_string(),
+            organisms_json: serde_json::to_string(&organism_states).map_err(json_error)?,
+            media_volume_l: media_volume_l.value(row),
+            media_ph: media_ph.value(row),
+            dissolved_components_json: serde_json::to_string(&components).map_err(json_error)?,
+            dissolved_gases_json: serde_json::to_string(&gases).map_err(json_error)?,
+            asset_states_json: serde_json::to_string(&asset_map).map_err(json_error)?,
+            events_json: events_json.value(row).to_string(),
+            vessels_json: vessels_json.value(row).to_string(),
+            timestamp: (!timestamp.is_null(row)).then(|| timestamp.value(row).to_string()),
+            total_biomass_g: total_biomass_g.value(row),
+            growth_rates_json: rates_json(specific_growth_rates)?,
+            uptake_rates_json: rates_json(uptake_rates)?,
+            series: BTreeMap::new(),
+        });
+    }
+    Ok(())
+}
@@ -1,9 +1,16 @@
 use crate::{
+    analysis::ExecutableBlueprint,
     error::BioforgeError,
-    logger::TimeSeriesLogger,
+    logger::{
+        event_log_path, FlushPolicy, LogFormat, RegisteredSink, RotationPolicy, TimeSeriesLogger,
+        TimeSeriesSink, WideSeries,
+    },
     simulation::{
         engine::SimulationEngine,
-        state::{LiveAsset, SimulationState},
+        flow::resolve_transfers,
+        state::{
+            HandoffState, LiveAsset, ResumePoint, SimulationEvent, SimulationState, TickMetrics, Vessel,
+        },
     },
 };
 use bioforge_schemas::{
@@ -14,7 +21,8 @@ use bioforge_schemas::{
     process::Process,
     rule::Rule,
 };
-use std::collections::{HashMap, VecDeque};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap, VecDeque};
 
 /// A fluent builder for constructing a `SimulationEngine`.
 ///
@@ -25,9 +33,23 @@ pub struct SimulationBuilder {
     assets: Vec<Asset>,
     rules: Vec<Rule>,
     process: Option<Process>,
+    /// Rules of the blueprint set with `with_blueprint`, which replace same-named `rules`.
+    blueprint_rules: Option<Vec<Rule>>,
     organisms: Vec<Organism>,
     initial_media: Option<MediaState>,
+    handoff: Option<HandoffState>,
+    resume_point: Option<ResumePoint>,
+    vessels: Vec<(String, MediaState, Vec<Organism>)>,
     log_path: Option<String>,
+    log_format: LogFormat,
+    wide_series: Option<WideSeries>,
+    flush_policy: FlushPolicy,
+    rotation: RotationPolicy,
+    event_log: bool,
+    sink: Option<Box<dyn TimeSeriesSink>>,
+    additional_sinks: Vec<Box<dyn TimeSeriesSink>>,
+    lenient_logging: bool,
+    start_time: Option<DateTime<Utc>>,
 }
 
 impl SimulationBuilder {
@@ -54,6 +76,18 @@ impl SimulationBuilder {
         self
     }
 
+    /// Simulates `blueprint`, e.g. one edited after export with `analysis::write_blueprint_yaml`.
+    ///
+    /// The process of [`ExecutableBlueprint::to_process`] replaces any set with `with_process`,
+    /// and its rules replace same-named rules set with `with_rules`. Building fails if a step
+    /// runs on an asset not given to `with_assets`.
+    pub fn with_blueprint(mut self, blueprint: &ExecutableBlueprint) -> Self {
+        let (process, rules) = blueprint.to_process();
+        self.process = Some(process);
+        self.blueprint_rules = Some(rules);
+        self
+    }
+
     /// Sets the collection of `Organism` definitions to participate in the simulation.
     pub fn with_organisms(mut self, organisms: Vec<Organism>) -> Self {
         self.organisms = organisms;
@@ -66,9 +100,119 @@ impl SimulationBuilder {
         self
     }
 
+    /// Sets the wall-clock time of tick zero. The time-series log then carries a `timestamp`
+    /// column for every tick.
+    pub fn with_start_time(mut self, start_time: DateTime<Utc>) -> Self {
+        self.start_time = Some(start_time);
+        self
+    }
+
+    /// Starts the simulation from the final culture of another engine.
+    ///
+    /// Shorthand for `with_handoff(engine.handoff_state())`.
+    pub fn with_initial_state_from(self, engine: &SimulationEngine) -> Self {
+        self.with_handoff(engine.handoff_state())
+    }
+
+    /// Starts the simulation from a previously captured `HandoffState`.
+    ///
+    /// The handoff media replaces any media set with `with_initial_media`, and organisms present in
+    /// the handoff start with their handed-off biomass and growth multipliers instead of their
+    /// `initial_biomass`.
+    pub fn with_handoff(mut self, handoff: HandoffState) -> Self {
+        self.handoff = Some(handoff);
+        self
+    }
+
+    /// Continues a run from `point`, e.g. one rebuilt from its log by `analysis::state_from_log`.
+    ///
+    /// Assets, rules, the process, and organisms must be configured as for the original run; the
+    /// point's vessels and asset states replace any initial media, vessels, or handoff. The run
+    /// picks up after the logged tick: the commands that tick queued are run at build time, the
+    /// initial state is not logged again, and the start time defaults to the point's. Log the
+    /// continuation to a new path, as opening a log at the original path truncates it.
+    pub fn with_resume_point(mut self, point: ResumePoint) -> Self {
+        self.resume_point = Some(point);
+        self
+    }
+
+    /// Adds a vessel with its own media and organism population, held by the asset `asset_id`.
+    ///
+    /// The media and organisms given to `with_initial_media`/`with_organisms` are placed in the
+    /// asset used by the first workflow step; use this to occupy additional assets (e.g. a seed
+    /// fermenter alongside the production fermenter). Each step's `required_asset_id` selects the
+    /// vessel its biological and unit-operation ticks act on.
+    pub fn with_vessel(mut self, asset_id: &str, media: MediaState, organisms: Vec<Organism>) -> Self {
+        self.vessels.push((asset_id.to_string(), media, organisms));
+        self
+    }
+
     /// Configures the simulation to write time-series data to the specified CSV file.
-    pub fn with_timeseries_logging_to_file(mut self, path: &str) -> Self {
+    pub fn with_timeseries_logging_to_file(self, path: &str) -> Self {
+        self.with_timeseries_logging(path, LogFormat::Csv)
+    }
+
+    /// Configures the simulation to write time-series data to `path` in the given format.
+    pub fn with_timeseries_logging(mut self, path: &str, format: LogFormat) -> Self {
+        self.log_path = Some(path.to_string());
+        self.log_format = format;
+        self.wide_series = None;
+        self.sink = None;
+        self
+    }
+
+    /// Configures the simulation to write a wide CSV log to `path`, with one numeric column per
+    /// declared molecule, gas, organism, and asset instead of embedded JSON.
+    pub fn with_wide_timeseries_logging(mut self, path: &str, series: WideSeries) -> Self {
         self.log_path = Some(path.to_string());
+        self.log_format = LogFormat::Csv;
+        self.wide_series = Some(series);
+        self.sink = None;
+        self
+    }
+
+    /// Sets how often the log file is flushed. The default flushes after every row, which is
+    /// safest but can dominate run time on network filesystems.
+    pub fn with_log_flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
+        self
+    }
+
+    /// Splits the log file into segments by row count or size, optionally gzipping each closed
+    /// segment. `analysis::read_log` reads the segments back as one log from the base path.
+    pub fn with_log_rotation(mut self, policy: RotationPolicy) -> Self {
+        self.rotation = policy;
+        self
+    }
+
+    /// Also writes one row per event to a file next to the log (`run.csv` → `run.events.csv`),
+    /// which `analysis::generate_bom` reads in place of the log once the run has completed. Only
+    /// file logs write an event log.
+    pub fn with_event_log(mut self) -> Self {
+        self.event_log = true;
+        self
+    }
+
+    /// Sends the time-series log to `sink` instead of a file, e.g. an `InMemorySink` whose
+    /// records are read back with `SimulationEngine::logged_records`.
+    pub fn with_timeseries_sink(mut self, sink: Box<dyn TimeSeriesSink>) -> Self {
+        self.log_path = None;
+        self.sink = Some(sink);
+        self
+    }
+
+    /// Registers a sink that receives every logged record alongside the main log, e.g. a
+    /// `ChannelSink` feeding a live dashboard. Its failures are reported as warnings and it is
+    /// skipped from then on, but the run continues.
+    pub fn with_additional_sink(mut self, sink: Box<dyn TimeSeriesSink>) -> Self {
+        self.additional_sinks.push(sink);
+        self
+    }
+
+    /// Sets whether a failure of the main log aborts the run (the default). When `false`, it is
+    /// treated like an additional sink: reported, then skipped.
+    pub fn with_strict_logging(mut self, strict: bool) -> Self {
+        self.lenient_logging = !strict;
         self
     }
 
@@ -79,11 +223,11 @@ impl SimulationBuilder {
     /// Returns a `BioforgeError` if essential components like organisms, media, or a process
     /// have not been provided.
     pub fn build(self) -> Result<SimulationEngine, BioforgeError> {
-        if self.organisms.is_empty() {
+        if self.organisms.is_empty() && self.vessels.iter().all(|(_, _, orgs)| orgs.is_empty()) {
             return Err(BioforgeError::NoOrganismProvided);
         }
 
-        let mut initial_assets = HashMap::new();
+        let mut initial_assets = BTreeMap::new();
         for asset_def in self.assets {
             initial_assets.insert(
                 asset_def.asset_id.clone(),
@@ -95,59 +239,203 @@ impl SimulationBuilder {
             );
         }
 
-        let rules_map = self.rules.into_iter().map(|r| (r.name.clone(), r)).collect();
-        let organism_defs = self
+        let process = self.process.ok_or(BioforgeError::ProcessNotDefined)?;
+        let method_index = index_methods(&process)?;
+        let mut rules = self.rules;
+        if let Some(blueprint_rules) = self.blueprint_rules {
+            if let Some(method) = process.methods.iter().find(|m| !initial_assets.contains_key(&m.required_asset_id)) {
+                return Err(BioforgeError::ConfigError(format!(
+                    "Blueprint step '{}' runs on asset '{}', which is not defined",
+                    method.method_id, method.required_asset_id
+                )));
+            }
+            rules.retain(|rule| !blueprint_rules.iter().any(|r| r.name == rule.name));
+            rules.extend(blueprint_rules);
+        }
+
+        // The primary vessel is the asset used by the first step of the workflow.
+        let primary_vessel_id = process
+            .default_workflow
+            .first()
+            .map(|id| process.methods[method_index[id]].required_asset_id.clone())
+            .unwrap_or_default();
+
+        let rules_map = rules.into_iter().map(|r| (r.name.clone(), r)).collect();
+        let mut organism_defs = self
             .organisms
             .iter()
             .map(|o| (o.organism_id.clone(), o.clone()))
             .collect::<HashMap<_, _>>();
 
-        let initial_organism_states = self
-            .organisms
-            .iter()
-            .map(|org| {
-                let state = IndividualOrganismState {
-                    biomass: Measurement {
-                        value: org.initial_biomass.value,
-                        unit: org.initial_biomass.unit.clone(),
-                    },
-                };
-                (org.organism_id.clone(), state)
-            })
-            .collect();
+        let mut vessels = BTreeMap::new();
+        for (asset_id, media, organisms) in self.vessels {
+            let states = organisms
+                .iter()
+                .map(|org| (org.organism_id.clone(), initial_organism_state(org)))
+                .collect();
+            organism_defs.extend(organisms.into_iter().map(|o| (o.organism_id.clone(), o)));
+            if vessels
+                .insert(asset_id.clone(), Vessel::new(media, OrganismState { states }))
+                .is_some()
+            {
+                return Err(BioforgeError::ConfigError(format!(
+                    "Vessel '{}' was configured more than once",
+                    asset_id
+                )));
+            }
+        }
 
-        let organism_state = OrganismState {
-            states: initial_organism_states,
-        };
+        if self.resume_point.is_some() {
+            // The resume point brings its own vessels; the organisms only supply definitions.
+        } else if !vessels.contains_key(&primary_vessel_id) {
+            let initial_organism_states = self
+                .organisms
+                .iter()
+                .map(|org| {
+                    let handed_off = self
+                        .handoff
+                        .as_ref()
+                        .and_then(|h| h.organism_states.get(&org.organism_id));
+                    let state = match handed_off {
+                        Some(state) => state.clone(),
+                        None => initial_organism_state(org),
+                    };
+                    (org.organism_id.clone(), state)
+                })
+                .collect();
+
+            let organism_state = OrganismState {
+                states: initial_organism_states,
+            };
+
+            let media = match &self.handoff {
+                Some(handoff) => handoff.media.clone(),
+                None => self.initial_media.ok_or(BioforgeError::MediaNotDefined)?,
+            };
+            vessels.insert(primary_vessel_id.clone(), Vessel::new(media, organism_state));
+        } else if !self.organisms.is_empty() || self.initial_media.is_some() || self.handoff.is_some() {
+            return Err(BioforgeError::ConfigError(format!(
+                "Vessel '{}' is configured with with_vessel but also receives the initial media and organisms",
+                primary_vessel_id
+            )));
+        }
+
+        let transfers = resolve_transfers(&process, &initial_assets)?;
 
         let state = SimulationState {
             tick: 0,
             ticks_in_current_stage: 0,
             assets: initial_assets,
-            media: self.initial_media.ok_or(BioforgeError::MediaNotDefined)?,
-            organisms: organism_state,
-            events: Vec::new(),
+            vessels,
+            active_vessel_id: primary_vessel_id,
+            events: process
+                .default_workflow
+                .first()
+                .map(|first_method_id| SimulationEvent::StageChanged {
+                    from_method_id: None,
+                    to_method_id: Some(first_method_id.clone()),
+                    tick: 0,
+                })
+                .into_iter()
+                .collect(),
+            metrics: TickMetrics::default(),
         };
 
-        let logger = match self.log_path {
-            Some(path) => Some(
-                TimeSeriesLogger::new(&path)
-                    .map_err(|e| BioforgeError::FileIO(path.clone(), e))?,
-            ),
-            None => None,
+        let sink: Option<Box<dyn TimeSeriesSink>> = match self.log_path {
+            Some(path) => {
+                let logger = match self.wide_series {
+                    Some(series) => TimeSeriesLogger::new_wide(&path, series),
+                    None => TimeSeriesLogger::new(&path, self.log_format),
+                }
+                .map_err(|e| BioforgeError::FileIO(path.clone(), e))?
+                .with_flush_policy(self.flush_policy)
+                .with_rotation(self.rotation);
+                let logger = if self.event_log {
+                    logger.with_event_log().map_err(|e| {
+                        BioforgeError::FileIO(event_log_path(&path).to_string_lossy().into_owned(), e)
+                    })?
+                } else {
+                    logger
+                };
+                Some(Box::new(logger))
+            }
+            None => self.sink,
         };
+        let sink = sink.map(|sink| RegisteredSink::new(sink, !self.lenient_logging));
+        let additional_sinks = self
+            .additional_sinks
+            .into_iter()
+            .map(|sink| RegisteredSink::new(sink, false))
+            .collect();
 
-        let growth_multipliers = organism_defs.keys().map(|id| (id.clone(), 1.0)).collect();
+        let growth_multipliers = organism_defs
+            .keys()
+            .map(|id| {
+                let multiplier = self
+                    .handoff
+                    .as_ref()
+                    .and_then(|h| h.growth_multipliers.get(id))
+                    .copied()
+                    .unwrap_or(1.0);
+                (id.clone(), multiplier)
+            })
+            .collect::<HashMap<_, _>>();
 
-        Ok(SimulationEngine {
+        let mut engine = SimulationEngine {
+            initial_state: state.clone(),
+            initial_growth_multipliers: growth_multipliers.clone(),
+            start_time: self.start_time.or(self.resume_point.as_ref().and_then(|p| p.start_time)),
+            resume_point: self.resume_point,
+            initial_logged: false,
+            observers: Vec::new(),
             state,
-            process: self.process.ok_or(BioforgeError::ProcessNotDefined)?,
+            process,
             rules: rules_map,
             organism_defs,
             current_step_index: 0,
-            logger,
+            method_index,
+            transfers,
+            transfer_progress_l: Vec::new(),
+            sink,
+            additional_sinks,
             biomass_history: VecDeque::new(),
             growth_multipliers,
-        })
+            event_counts: BTreeMap::new(),
+        };
+        engine.transfer_progress_l = vec![0.0; engine.first_method_transfer_count()];
+        if let Some(point) = engine.resume_point.clone() {
+            engine.apply_resume_point(&point)?;
+        }
+        Ok(engine)
     }
-}
\ No newline at end of file
+}
+
+fn initial_organism_state(org: &Organism) -> IndividualOrganismState {
+    IndividualOrganismState {
+        biomass: Measurement {
+            value: org.initial_biomass.value,
+            unit: org.initial_biomass.unit.clone(),
+        },
+    }
+}
+
+/// Maps every method id in `process` to its position in `process.methods`, verifying that each
+/// step of the default workflow refers to a defined method.
+fn index_methods(process: &Process) -> Result<HashMap<String, usize>, BioforgeError> {
+    let method_index: HashMap<String, usize> = process
+        .methods
+        .iter()
+        .enumerate()
+        .map(|(i, m)| (m.method_id.clone(), i))
+        .collect();
+
+    if let Some(missing) = process
+        .default_workflow
+        .iter()
+        .find(|id| !method_index.contains_key(*id))
+    {
+        return Err(BioforgeError::MethodNotFound(missing.clone()));
+    }
+
+    Ok(method_index)
+}
@@ -1,16 +1,51 @@
 use super::{
-    state::{LiveAsset, SimulationEvent, SimulationState},
+    flow::{self, ResolvedTransfer},
+    observer::TickObserver,
+    state::{
+        ComponentIndex, HandoffState, LiveAsset, ResumePoint, RunStatus, RunSummary,
+        SimulationEvent, SimulationState, Vessel,
+    },
+};
+use crate::{
+    error::BioforgeError,
+    logger::{tick_timestamp, LogRecord, RegisteredSink, TimeSeriesLogger},
 };
-use crate::{error::BioforgeError, logger::TimeSeriesLogger};
 use bioforge_schemas::{
     command::Command,
-    environment::{DissolvedComponent, MediaState, Measurement},
-    organism::Organism,
-    organism_state::IndividualOrganismState,
-    process::Process,
+    environment::{DissolvedComponent, MediaComposition, MediaState, Measurement},
+    organism::{MediaExchangeRate, Organism},
+    organism_state::{IndividualOrganismState, OrganismState},
+    process::{Method, Process},
     rule::{ComparisonOperator, Condition, Rule},
 };
-use std::collections::{HashMap, VecDeque};
+use chrono::{DateTime, Utc};
+use log::{info, trace, warn};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::time::Instant;
+
+/// Simulated time covered by one tick, in hours.
+pub const TIME_STEP_HR: f64 = 1.0;
+
+/// The molar mass in g/mol the simulation gives a consumed nutrient: that of D-glucose, or of
+/// sucrose for any other.
+pub fn nutrient_molar_mass(molecule_id: &str) -> f64 {
+    if molecule_id == "CHEBI:17234" { 180.16 } else { 342.3 }
+}
+
+/// Below this many organisms the per-tick work is too small to amortize rayon's scheduling cost.
+#[cfg(feature = "parallel")]
+const PARALLEL_MIN_ORGANISMS: usize = 8;
+
+/// The technique of the unit operation that saponifies the harvested biomass.
+const SAPONIFICATION: &str = "saponification";
+/// The material a saponification doses.
+const NAOH_MATERIAL_ID: &str = "CONS-NAOH-1M-01";
+/// Grams of NaOH a saponification takes per gram of dry biomass, an excess over the lipids.
+const SAPONIFICATION_NAOH_G_PER_G_DW: f64 = 1.0;
+/// Grams of NaOH per litre of broth a saponification doses each tick.
+const SAPONIFICATION_DOSE_G_PER_L: f64 = 0.5;
 
 pub struct SimulationEngine {
     pub(super) state: SimulationState,
@@ -18,20 +53,38 @@ pub struct SimulationEngine {
     pub(super) rules: HashMap<String, Rule>,
     pub(super) organism_defs: HashMap<String, Organism>,
     pub(super) current_step_index: usize,
-    pub(super) logger: Option<TimeSeriesLogger>,
+    /// Maps each `method_id` in the process to its index in `process.methods`.
+    pub(super) method_index: HashMap<String, usize>,
+    /// Validated transfers keyed by the method that performs them.
+    pub(super) transfers: HashMap<String, Vec<ResolvedTransfer>>,
+    /// Litres moved so far by each transfer of the current method.
+    pub(super) transfer_progress_l: Vec<f64>,
+    /// The main time-series log, strict unless configured otherwise.
+    pub(super) sink: Option<RegisteredSink>,
+    /// Lenient sinks that receive every record alongside the main log.
+    pub(super) additional_sinks: Vec<RegisteredSink>,
     pub(super) biomass_history: VecDeque<f64>,
     pub(super) growth_multipliers: HashMap<String, f64>,
+    /// Events raised so far, keyed by [`SimulationEvent::kind`].
+    pub(super) event_counts: BTreeMap<String, u64>,
+    /// The state and growth multipliers captured at build time, restored by [`Self::reset`].
+    pub(super) initial_state: SimulationState,
+    pub(super) initial_growth_multipliers: HashMap<String, f64>,
+    /// The log position the engine was resumed from, restored by [`Self::reset`] in place of the
+    /// initial state.
+    pub(super) resume_point: Option<ResumePoint>,
+    /// Wall-clock time of tick zero, if the run is anchored to one.
+    pub(super) start_time: Option<DateTime<Utc>>,
+    /// Set once the initial state has been logged, so a resumed run does not log it again.
+    pub(super) initial_logged: bool,
+    pub(super) observers: Vec<Box<dyn TickObserver + Send>>,
 }
 
 impl SimulationEngine {
-    pub fn run(&mut self) -> Result<(), BioforgeError> {
-        if let Some(initial_method_id) = self.process.default_workflow.get(self.current_step_index) {
-            println!("--- Entering stage: {} ---", initial_method_id);
-        }
-
-        if let Some(logger) = &mut self.logger {
-            logger.log_state(&self.state, "INITIAL")?;
-        }
+    /// Runs the workflow to completion and summarizes the outcome.
+    pub fn run(&mut self) -> Result<RunSummary, BioforgeError> {
+        let started = Instant::now();
+        self.log_initial_state()?;
 
         loop {
             // The tick method will return false when the simulation is complete
@@ -39,32 +92,139 @@ impl SimulationEngine {
                 break;
             }
         }
-        println!("Simulation Complete.");
+        self.finish(started)
+    }
+
+    /// Logs the initial state once, before the first tick of the run.
+    pub(super) fn log_initial_state(&mut self) -> Result<(), BioforgeError> {
+        if self.initial_logged {
+            return Ok(());
+        }
+        if let Some(initial_method_id) = self.process.default_workflow.get(self.current_step_index) {
+            info!("--- Entering stage: {} ---", initial_method_id);
+        }
+
+        self.log_state("INITIAL")?;
+        self.flush_events();
+        self.initial_logged = true;
+        Ok(())
+    }
+
+    /// Hands the current state to every time-series sink.
+    fn log_state(&mut self, stage_id: &str) -> Result<(), BioforgeError> {
+        // Transfers and commands move biomass between vessels, so the total is taken as logged.
+        self.state.metrics.total_biomass_g =
+            self.state.active_vessel().organisms.states.values().map(|o| o.biomass.value).sum();
+        let timestamp = self.start_time.map(|start| tick_timestamp(start, self.state.tick));
+        for sink in self.sink.iter_mut().chain(&mut self.additional_sinks) {
+            sink.apply(|sink| sink.log_state(&self.state, stage_id, timestamp))?;
+        }
+        Ok(())
+    }
+
+    /// Flushes every time-series sink, so all ticks logged so far are on disk.
+    pub fn flush_logs(&mut self) -> Result<(), BioforgeError> {
+        for sink in self.sink.iter_mut().chain(&mut self.additional_sinks) {
+            sink.apply(|sink| sink.flush())?;
+        }
         Ok(())
     }
 
+    /// The records logged so far by the first sink that keeps them in memory.
+    pub fn logged_records(&self) -> Option<&[LogRecord]> {
+        self.sink
+            .iter()
+            .chain(&self.additional_sinks)
+            .find_map(|registered| registered.sink.records())
+    }
+
+    /// Wraps up a run whose workflow has completed.
+    pub(super) fn finish(&mut self, started: Instant) -> Result<RunSummary, BioforgeError> {
+        // The events raised by the final commands belong to no logged tick; hand them to the
+        // sinks that record them anyway, then count them.
+        for sink in self.sink.iter_mut().chain(&mut self.additional_sinks) {
+            sink.apply(|sink| sink.log_trailing_events(&self.state))?;
+        }
+        self.flush_events();
+        for sink in self.sink.iter_mut().chain(&mut self.additional_sinks) {
+            sink.apply(|sink| sink.finish())?;
+        }
+        info!("Simulation Complete.");
+        Ok(self.summarize(RunStatus::Completed, started))
+    }
+
+    /// Describes the engine's current position as the outcome of a run that began at `started`.
+    pub(super) fn summarize(&self, status: RunStatus, started: Instant) -> RunSummary {
+        let mut final_biomass = BTreeMap::new();
+        for vessel in self.state.vessels.values() {
+            for (org_id, org_state) in &vessel.organisms.states {
+                *final_biomass.entry(org_id.clone()).or_insert(0.0) += org_state.biomass.value;
+            }
+        }
+        let workflow = &self.process.default_workflow;
+        RunSummary {
+            status,
+            total_ticks: self.state.tick,
+            final_stage: workflow.get(self.current_step_index).or(workflow.last()).cloned(),
+            final_biomass,
+            event_counts: self.event_counts.clone(),
+            wall_clock_secs: started.elapsed().as_secs_f64(),
+        }
+    }
+
+    /// Registers an observer that is handed the state after every tick.
+    pub fn add_observer(&mut self, observer: Box<dyn TickObserver + Send>) {
+        self.observers.push(observer);
+    }
+
+    /// Tallies the events of the tick that was just logged and clears them.
+    fn flush_events(&mut self) {
+        for event in self.state.events.drain(..) {
+            *self.event_counts.entry(event.kind().to_string()).or_insert(0) += 1;
+        }
+    }
+
     pub fn tick(&mut self) -> Result<bool, BioforgeError> {
         if self.current_step_index >= self.process.default_workflow.len() {
             return Ok(false);
         }
 
-        self.state.events.clear();
         self.state.tick += 1;
         self.state.ticks_in_current_stage += 1;
 
         self.execute_biological_tick()?;
         self.execute_unit_operation_tick()?;
+        self.execute_transfer_tick()?;
+
+        let current_method_id = self.current_method()?.method_id.clone();
+        let command_queue = self.queued_commands()?;
+
+        trace!(
+            "tick {} [{}]: {} command(s) queued, {} event(s)",
+            self.state.tick,
+            current_method_id,
+            command_queue.len(),
+            self.state.events.len()
+        );
+
+        self.log_state(&current_method_id)?;
+        for observer in &mut self.observers {
+            observer.on_tick(&self.state, &current_method_id);
+        }
+        // Events raised by commands are logged with the next tick, whose state they shaped.
+        self.flush_events();
 
-        let current_method_id = self.process.default_workflow[self.current_step_index].clone();
-        let current_method = self
-            .process
-            .methods
-            .iter()
-            .find(|m| m.method_id == current_method_id)
-            .ok_or_else(|| BioforgeError::MethodNotFound(current_method_id.clone()))?;
+        for command in command_queue {
+            self.execute_command(command)?;
+        }
+
+        Ok(true)
+    }
 
-        let mut command_queue: Vec<Command> = Vec::new();
-        if let Some(rule_ids) = &current_method.required_rule_ids {
+    /// The actions of the current method's rules whose conditions hold.
+    fn queued_commands(&self) -> Result<Vec<Command>, BioforgeError> {
+        let mut command_queue = Vec::new();
+        if let Some(rule_ids) = &self.current_method()?.required_rule_ids {
             for rule_id in rule_ids {
                 if let Some(rule) = self.rules.get(rule_id) {
                     if self.evaluate_condition(&rule.condition)? {
@@ -73,131 +233,245 @@ impl SimulationEngine {
                 }
             }
         }
+        Ok(command_queue)
+    }
 
-        if let Some(logger) = &mut self.logger {
-            logger.log_state(&self.state, &current_method_id)?;
+    /// Moves the engine to `point`, as if it had just logged the point's tick, and runs the
+    /// commands that tick queued after it was logged.
+    pub(super) fn apply_resume_point(&mut self, point: &ResumePoint) -> Result<(), BioforgeError> {
+        let workflow = &self.process.default_workflow;
+        if point.step_index >= workflow.len() {
+            return Err(BioforgeError::ConfigError(format!(
+                "Cannot resume at workflow step {}: the workflow has {} steps",
+                point.step_index,
+                workflow.len()
+            )));
         }
-
-        for command in command_queue {
-            self.execute_command(command)?;
+        if !point.state.vessels.contains_key(&point.state.active_vessel_id) {
+            return Err(BioforgeError::AssetNotFound(point.state.active_vessel_id.clone()));
         }
 
-        Ok(true)
+        self.state = point.state.clone();
+        self.current_step_index = point.step_index;
+        self.transfer_progress_l = self
+            .transfers
+            .get(&workflow[point.step_index])
+            .into_iter()
+            .flatten()
+            .map(|t| {
+                let key = (t.from_asset_id.clone(), t.to_asset_id.clone());
+                point.transferred_l.get(&key).copied().unwrap_or(0.0)
+            })
+            .collect();
+        self.biomass_history = point.recent_biomass.iter().copied().collect();
+        self.growth_multipliers = self.initial_growth_multipliers.clone();
+        self.event_counts = point.event_counts.clone();
+        self.initial_logged = true;
+
+        // The initial state queues no commands; any other logged tick ran its rules after it was
+        // logged, so their effects are not in the log yet.
+        if self.state.tick > 0 {
+            for command in self.queued_commands()? {
+                self.execute_command(command)?;
+            }
+        }
+        Ok(())
     }
 
     fn execute_unit_operation_tick(&mut self) -> Result<(), BioforgeError> {
-        let current_method = &self.process.methods[self.current_step_index];
+        if self.current_method()?.technique.as_str() == SAPONIFICATION {
+            let vessel = self.state.active_vessel();
+            let dose_g = (SAPONIFICATION_DOSE_G_PER_L * vessel.media.volume.value)
+                .min(self.saponification_naoh_remaining_g(self.state.ticks_in_current_stage - 1));
+            if dose_g > 0.0 {
+                self.state.events.push(SimulationEvent::MaterialConsumed {
+                    id: NAOH_MATERIAL_ID.to_string(),
+                    amount: dose_g,
+                    consumer_id: None,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Grams of NaOH the saponification in the active vessel still takes after dosing for
+    /// `ticks_dosed` ticks: that of the biomass the vessel holds, less a full dose per tick. The
+    /// biomass and volume of a harvested broth hold still, so the dosing so far follows from
+    /// the ticks alone.
+    fn saponification_naoh_remaining_g(&self, ticks_dosed: u64) -> f64 {
+        let vessel = self.state.active_vessel();
+        let biomass_g: f64 = vessel.organisms.states.values().map(|s| s.biomass.value).sum();
+        let dosed_g = SAPONIFICATION_DOSE_G_PER_L * vessel.media.volume.value * ticks_dosed as f64;
+        (biomass_g * SAPONIFICATION_NAOH_G_PER_G_DW - dosed_g).max(0.0)
+    }
 
-        match current_method.technique.as_str() {
-            "saponification" => {
-                let naoh_id = "CHEBI:32145";
-                let consumption_rate = 0.5_f64;
+    /// Advances the current method's transfers by one tick, limited by the connection's rated flow.
+    fn execute_transfer_tick(&mut self) -> Result<(), BioforgeError> {
+        let method_id = self.current_method()?.method_id.clone();
+        let Some(transfers) = self.transfers.get(&method_id) else {
+            return Ok(());
+        };
 
-                if let Some(naoh) = self
+        for (i, transfer) in transfers.iter().enumerate() {
+            let Some(mut source) = self.state.vessels.remove(&transfer.from_asset_id) else {
+                continue;
+            };
+            let available_l = source.media.volume.value;
+            let remaining_l = match transfer.volume_l {
+                Some(volume_l) => (volume_l - self.transfer_progress_l[i]).max(0.0),
+                None => available_l,
+            };
+            let step_l = transfer
+                .max_rate_l_per_hr
+                .map_or(remaining_l, |rate| remaining_l.min(rate * TIME_STEP_HR))
+                .min(available_l);
+
+            if step_l > 0.0 {
+                let target = self
                     .state
-                    .media
-                    .composition
-                    .dissolved_components
-                    .iter_mut()
-                    .find(|c| c.molecule_id == naoh_id)
-                {
-                    if naoh.concentration.value > 0.0 {
-                        let consumed_conc = consumption_rate.min(naoh.concentration.value);
-                        naoh.concentration.value -= consumed_conc;
-
-                        let consumed_amount_g = consumed_conc * self.state.media.volume.value;
-                        self.state.events.push(SimulationEvent::MaterialConsumed {
-                            id: "CONS-NAOH-1M-01".to_string(),
-                            amount: consumed_amount_g,
-                        });
-                    }
-                }
+                    .vessels
+                    .entry(transfer.to_asset_id.clone())
+                    .or_insert_with(Vessel::empty);
+                flow::move_volume(&mut source, target, step_l);
+                self.transfer_progress_l[i] += step_l;
+                self.state.events.push(SimulationEvent::MaterialTransferred {
+                    from_asset_id: transfer.from_asset_id.clone(),
+                    to_asset_id: transfer.to_asset_id.clone(),
+                    volume_l: step_l,
+                });
+                trace!(
+                    "tick {}: transferred {:.3} L from {}:{} to {}:{}",
+                    self.state.tick,
+                    step_l,
+                    transfer.from_asset_id,
+                    transfer.from_port_id,
+                    transfer.to_asset_id,
+                    transfer.to_port_id
+                );
             }
-            _ => {}
+            self.state.vessels.insert(transfer.from_asset_id.clone(), source);
         }
         Ok(())
     }
 
     fn execute_biological_tick(&mut self) -> Result<(), BioforgeError> {
-        let time_step_hr = 1.0;
-        let mut media_deltas: HashMap<String, f64> = HashMap::new();
+        let bioreactor_temp = self.state.assets.get(&self.state.active_vessel_id).map(|a| a.temperature);
+        let vessel = self
+            .state
+            .vessels
+            .get_mut(&self.state.active_vessel_id)
+            .ok_or_else(|| BioforgeError::AssetNotFound(self.state.active_vessel_id.clone()))?;
+        let metrics = &mut self.state.metrics;
+        metrics.specific_growth_rates.clear();
+        metrics.uptake_rates.clear();
+        if vessel.media.volume.value <= 0.0 {
+            // Nothing grows in an empty vessel.
+            return Ok(());
+        }
+
+        // Phase 1: compute each organism's growth and media exchange against the start-of-tick
+        // media. Organisms are independent here, so this phase may run in parallel.
+        let inputs: Vec<(&String, f64)> = vessel
+            .organisms
+            .states
+            .iter()
+            .map(|(id, state)| (id, state.biomass.value))
+            .collect();
+        let compute = |&(org_id, biomass): &(&String, f64)| -> Result<OrganismTickOutcome, BioforgeError> {
+            let org_def = self
+                .organism_defs
+                .get(org_id)
+                .ok_or_else(|| BioforgeError::OrganismNotFound(org_id.clone()))?;
+            let growth_multiplier = *self.growth_multipliers.get(org_id).unwrap_or(&1.0);
+            Ok(compute_organism_tick(
+                org_def,
+                biomass,
+                bioreactor_temp,
+                growth_multiplier,
+                &vessel.media,
+                &vessel.component_index,
+            ))
+        };
+        #[cfg(feature = "parallel")]
+        let outcomes = if inputs.len() >= PARALLEL_MIN_ORGANISMS && rayon::current_num_threads() > 1 {
+            inputs.par_iter().map(compute).collect::<Result<Vec<_>, _>>()?
+        } else {
+            inputs.iter().map(compute).collect::<Result<Vec<_>, _>>()?
+        };
+        #[cfg(not(feature = "parallel"))]
+        let outcomes = inputs.iter().map(compute).collect::<Result<Vec<_>, _>>()?;
+
+        // Phase 2: reconcile sequentially in organism id order so results are identical with
+        // or without parallelism. Organisms judged their substrates against the start-of-tick
+        // media each on its own, so a substrate they over-demand together is shared among them
+        // in proportion to their demand.
+        let mut demand_g: BTreeMap<&str, f64> = BTreeMap::new();
+        for outcome in &outcomes {
+            for (molecule_id, consumed_g) in &outcome.consumed {
+                *demand_g.entry(molecule_id.as_str()).or_insert(0.0) += consumed_g;
+            }
+        }
+        let tick = self.state.tick;
+        let shares: BTreeMap<String, f64> = demand_g
+            .into_iter()
+            .map(|(molecule_id, demand)| {
+                let available = vessel
+                    .component_index
+                    .get(&vessel.media, molecule_id)
+                    .map_or(0.0, |c| c.concentration.value * vessel.media.volume.value);
+                let share = if demand > available { available / demand } else { 1.0 };
+                if share < 1.0 {
+                    warn!(
+                        "tick {}: demand for '{}' ({:.6} g) exceeds the {:.6} g in the media; sharing it proportionally",
+                        tick, molecule_id, demand, available
+                    );
+                }
+                (molecule_id.to_string(), share)
+            })
+            .collect();
+
+        let mut media_deltas: BTreeMap<String, f64> = BTreeMap::new();
+        // Concentration taken up from each substrate present in the media, in g/L.
+        let mut uptake: BTreeMap<String, f64> = BTreeMap::new();
         let mut new_byproducts: Vec<DissolvedComponent> = Vec::new();
         let mut total_biomass_this_tick = 0.0;
+        let media_volume = vessel.media.volume.value;
 
-        for (org_id, org_state) in self.state.organisms.states.iter_mut() {
-            let org_def = self.organism_defs.get(org_id).ok_or_else(|| BioforgeError::OrganismNotFound(org_id.clone()))?;
-            let bioreactor_id = &self.process.methods[self.current_step_index].required_asset_id;
-            let asset = self.state.assets.get(bioreactor_id);
-            let bioreactor_temp = asset.map_or(
-                org_def.dynamic_parameters.environmental_tolerances.temperature.optimal.value,
-                |a| a.temperature,
-            );
-
-            let temp_tolerance = &org_def.dynamic_parameters.environmental_tolerances.temperature;
-            let stress_factor = if bioreactor_temp < temp_tolerance.range.min || bioreactor_temp > temp_tolerance.range.max {
-                0.1
-            } else if bioreactor_temp <= temp_tolerance.optimal.value {
-                0.1 + 0.9 * (bioreactor_temp - temp_tolerance.range.min) / (temp_tolerance.optimal.value - temp_tolerance.range.min)
-            } else {
-                1.0 - 0.9 * (bioreactor_temp - temp_tolerance.optimal.value) / (temp_tolerance.range.max - temp_tolerance.optimal.value)
-            };
-            
-            let k_s = 0.5; 
-            let primary_carbon_source_name = org_def.dynamic_parameters.metabolic_exchange.media_consumption.get(0).map_or("", |c| &c.molecule_name);
-            let nutrient_concentration = self.state.media.composition.dissolved_components.iter()
-                .find(|c| c.molecule_name == primary_carbon_source_name)
-                .map_or(0.0, |c| c.concentration.value);
-            
-            let nutrient_limitation_factor = nutrient_concentration / (k_s + nutrient_concentration);
-            
-            let growth_multiplier = *self.growth_multipliers.get(org_id).unwrap_or(&1.0);
-            let growth_rate = org_def.dynamic_parameters.growth_rate_per_hr * stress_factor * nutrient_limitation_factor * growth_multiplier;
-            let growth = org_state.biomass.value * ((growth_rate * time_step_hr).exp() - 1.0);
-            org_state.biomass.value += growth;
-
-            total_biomass_this_tick += org_state.biomass.value;
-
-            for consumption_def in &org_def.dynamic_parameters.metabolic_exchange.media_consumption {
-                if let Some(nutrient) = self.state.media.composition.dissolved_components.iter().find(|c| c.molecule_id == consumption_def.molecule_id) {
-                    if nutrient.concentration.value > 0.0 {
-                        let nutrient_mw = if consumption_def.molecule_id == "CHEBI:17234" { 180.16 } else { 342.3 };
-                        
-                        let consumption_rate_g_gdw_hr = consumption_def.max_exchange_rate.value * nutrient_mw / 1000.0 * growth_multiplier;
-                        let max_consumption_g = consumption_rate_g_gdw_hr * org_state.biomass.value * time_step_hr;
-                        let available_nutrient_g = nutrient.concentration.value * self.state.media.volume.value;
-                        let actual_consumption_g = max_consumption_g.min(available_nutrient_g);
-
-                        if actual_consumption_g > 0.0 {
-                            let delta_conc = actual_consumption_g / self.state.media.volume.value;
-                            *media_deltas.entry(consumption_def.molecule_id.clone()).or_insert(0.0) -= delta_conc;
-
-                            self.state.events.push(SimulationEvent::MaterialConsumed {
-                                id: consumption_def.molecule_id.clone(),
-                                amount: actual_consumption_g,
-                            });
-                        }
+        for ((org_id, org_state), outcome) in vessel.organisms.states.iter_mut().zip(outcomes) {
+            org_state.biomass.value = outcome.biomass;
+            total_biomass_this_tick += outcome.biomass;
+            metrics.specific_growth_rates.insert(org_id.clone(), outcome.growth_rate);
+
+            if let Some(org_def) = self.organism_defs.get(org_id) {
+                for consumption_def in &org_def.dynamic_parameters.metabolic_exchange.media_consumption {
+                    if vessel.component_index.contains(&consumption_def.molecule_id) {
+                        uptake.entry(consumption_def.molecule_id.clone()).or_insert(0.0);
                     }
                 }
             }
 
-            for secretion_def in &org_def.dynamic_parameters.metabolic_exchange.media_secretion {
-                let byproduct_mw = if secretion_def.molecule_id == "CHEBI:30089" { 60.05 } else { 1.0 }; 
-                let secretion_rate_g_gdw_hr = secretion_def.max_exchange_rate.value * byproduct_mw / 1000.0;
-                let secreted_amount_g = secretion_rate_g_gdw_hr * org_state.biomass.value * time_step_hr * stress_factor;
-
-                if secreted_amount_g > 0.0 {
-                    let delta_conc = secreted_amount_g / self.state.media.volume.value;
-                    *media_deltas.entry(secretion_def.molecule_id.clone()).or_insert(0.0) += delta_conc;
-
-                    if self.state.media.composition.dissolved_components.iter().find(|c| c.molecule_id == secretion_def.molecule_id).is_none() {
-                        if !new_byproducts.iter().any(|b| b.molecule_id == secretion_def.molecule_id) {
-                            new_byproducts.push(DissolvedComponent {
-                                molecule_id: secretion_def.molecule_id.clone(),
-                                molecule_name: secretion_def.molecule_name.clone(),
-                                concentration: Measurement { value: 0.0, unit: "g/L".to_string() },
-                            });
-                        }
-                    }
+            for (molecule_id, demanded_g) in outcome.consumed {
+                let consumed_g = demanded_g * shares[&molecule_id];
+                *media_deltas.entry(molecule_id.clone()).or_insert(0.0) -= consumed_g / media_volume;
+                *uptake.entry(molecule_id.clone()).or_insert(0.0) += consumed_g / media_volume;
+                self.state.events.push(SimulationEvent::MaterialConsumed {
+                    id: molecule_id,
+                    amount: consumed_g,
+                    consumer_id: Some(org_id.clone()),
+                });
+            }
+
+            for (secretion_def, secreted_g) in outcome.secreted {
+                *media_deltas.entry(secretion_def.molecule_id.clone()).or_insert(0.0) += secreted_g / media_volume;
+
+                if !vessel.component_index.contains(&secretion_def.molecule_id)
+                    && !new_byproducts.iter().any(|b| b.molecule_id == secretion_def.molecule_id)
+                {
+                    new_byproducts.push(DissolvedComponent {
+                        molecule_id: secretion_def.molecule_id.clone(),
+                        molecule_name: secretion_def.molecule_name.clone(),
+                        concentration: Measurement { value: 0.0, unit: "g/L".to_string() },
+                    });
                 }
             }
         }
@@ -207,13 +481,20 @@ impl SimulationEngine {
             self.biomass_history.pop_front();
         }
 
-        self.state.media.composition.dissolved_components.extend(new_byproducts);
+        for byproduct in new_byproducts {
+            vessel.component_index.push(&mut vessel.media, byproduct);
+        }
 
         for (molecule_id, delta) in media_deltas {
-            if let Some(component) = self.state.media.composition.dissolved_components.iter_mut().find(|c| c.molecule_id == molecule_id) {
+            if let Some(component) = vessel.dissolved_component_mut(&molecule_id) {
+                // No more than was in the media is consumed; the floor only absorbs rounding.
                 component.concentration.value = (component.concentration.value + delta).max(0.0);
             }
         }
+        metrics.uptake_rates = uptake
+            .into_iter()
+            .map(|(molecule_id, taken_up)| (molecule_id, taken_up / TIME_STEP_HR))
+            .collect();
 
         Ok(())
     }
@@ -221,14 +502,28 @@ impl SimulationEngine {
     fn execute_command(&mut self, command: Command) -> Result<(), BioforgeError> {
         match command {
             Command::AdvanceToNextStep => {
+                let from_method_id = self.current_method()?.method_id.clone();
                 self.current_step_index += 1;
                 self.state.ticks_in_current_stage = 0;
-                if let Some(next_method_id) =
-                    self.process.default_workflow.get(self.current_step_index)
-                {
-                    println!("--- Entering stage: {} ---", next_method_id);
+                // Whether a stage's biomass is stationary is judged on its own ticks alone, not on
+                // those of the stage, or the vessel, before it.
+                self.biomass_history.clear();
+                let to_method_id = self.process.default_workflow.get(self.current_step_index);
+                self.state.events.push(SimulationEvent::StageChanged {
+                    from_method_id: Some(from_method_id),
+                    to_method_id: to_method_id.cloned(),
+                    tick: self.state.tick,
+                });
+                if let Some(next_method_id) = to_method_id {
+                    info!("--- Entering stage: {} ---", next_method_id);
+                    let next_asset_id = self.current_method()?.required_asset_id.clone();
+                    let next_transfers = self.transfers.get(next_method_id).map_or(0, Vec::len);
+                    // Methods with explicit transfers move material themselves; otherwise the
+                    // batch follows the workflow into the next asset.
+                    self.state.activate_vessel(&next_asset_id, next_transfers == 0);
+                    self.transfer_progress_l = vec![0.0; next_transfers];
                 } else {
-                    println!("--- Reached end of process workflow ---");
+                    info!("--- Reached end of process workflow ---");
                 }
             }
             Command::SetTemperature { asset_id, celsius } => {
@@ -241,9 +536,16 @@ impl SimulationEngine {
                     asset.ph = target_ph;
                 }
             }
-            Command::AddMaterial { asset_id: _, material_id, amount_grams } => {
-                if let Some(component) = self.state.media.composition.dissolved_components.iter_mut().find(|c| c.molecule_id == material_id) {
-                    let concentration_increase = amount_grams / self.state.media.volume.value;
+            Command::AddMaterial { asset_id, material_id, amount_grams } => {
+                // A vessel with no media, such as one a transfer has yet to fill, has nothing to
+                // dissolve the material in.
+                let Some(vessel) = self.state.vessels.get_mut(&asset_id).filter(|v| v.media.volume.value > 0.0) else {
+                    warn!("Cannot add {} to {}: it holds no media", material_id, asset_id);
+                    return Ok(());
+                };
+                let media_volume = vessel.media.volume.value;
+                if let Some(component) = vessel.dissolved_component_mut(&material_id) {
+                    let concentration_increase = amount_grams / media_volume;
                     component.concentration.value += concentration_increase;
                     self.state.events.push(SimulationEvent::MaterialAdded {
                         id: material_id.clone(),
@@ -251,13 +553,120 @@ impl SimulationEngine {
                     });
                 }
             }
+            Command::TopUpMaterial { asset_id, material_id, concentration_g_l } => {
+                let Some(vessel) = self.state.vessels.get_mut(&asset_id).filter(|v| v.media.volume.value > 0.0) else {
+                    warn!("Cannot top up {} in {}: it holds no media", material_id, asset_id);
+                    return Ok(());
+                };
+                let media_volume = vessel.media.volume.value;
+                if let Some(component) = vessel.dissolved_component_mut(&material_id) {
+                    let amount_grams = (concentration_g_l - component.concentration.value) * media_volume;
+                    if amount_grams > 0.0 {
+                        component.concentration.value = concentration_g_l;
+                        self.state.events.push(SimulationEvent::MaterialAdded { id: material_id, amount: amount_grams });
+                    }
+                }
+            }
             Command::SetOrganismGrowthMultiplier { organism_id, multiplier } => {
                 self.growth_multipliers.insert(organism_id, multiplier);
             }
+            Command::DrainMedia { asset_id, volume_l } => {
+                let Some(vessel) = self.state.vessels.get_mut(&asset_id) else {
+                    warn!("Cannot drain {}: it holds no media", asset_id);
+                    return Ok(());
+                };
+                let mut waste = Vessel::empty();
+                let volume_l = volume_l.unwrap_or(vessel.media.volume.value);
+                flow::move_volume(vessel, &mut waste, volume_l);
+                self.record_waste(&waste);
+                info!("Drained {:.2} L from {} to waste", waste.media.volume.value, asset_id);
+            }
+            Command::DiluteMedia { asset_id, volume_l, feed, harvest_asset_id } => {
+                let Some(mut vessel) = self.state.vessels.remove(&asset_id) else {
+                    warn!("Cannot dilute {}: it holds no media", asset_id);
+                    return Ok(());
+                };
+                let mut effluent = harvest_asset_id
+                    .as_ref()
+                    .and_then(|id| self.state.vessels.remove(id))
+                    .unwrap_or_else(Vessel::empty);
+                let collected_l = effluent.media.volume.value;
+                flow::move_volume(&mut vessel, &mut effluent, volume_l);
+                let removed_l = effluent.media.volume.value - collected_l;
+                match harvest_asset_id {
+                    Some(harvest_asset_id) => {
+                        self.state.events.push(SimulationEvent::MaterialTransferred {
+                            from_asset_id: asset_id.clone(),
+                            to_asset_id: harvest_asset_id.clone(),
+                            volume_l: removed_l,
+                        });
+                        self.state.vessels.insert(harvest_asset_id, effluent);
+                    }
+                    None => self.record_waste(&effluent),
+                }
+
+                // The fresh media is aerated like the broth it replaces.
+                let mut fresh = Vessel::new(
+                    MediaState {
+                        volume: Measurement { value: removed_l, unit: "L".to_string() },
+                        ph: vessel.media.ph,
+                        composition: MediaComposition {
+                            dissolved_components: feed,
+                            dissolved_gases: vessel.media.composition.dissolved_gases.clone(),
+                        },
+                    },
+                    OrganismState { states: BTreeMap::new() },
+                );
+                for component in &fresh.media.composition.dissolved_components {
+                    let grams = component.concentration.value * removed_l;
+                    if grams > 0.0 {
+                        self.state.events.push(SimulationEvent::MaterialAdded {
+                            id: component.molecule_id.clone(),
+                            amount: grams,
+                        });
+                    }
+                }
+                flow::move_volume(&mut fresh, &mut vessel, removed_l);
+                self.state.vessels.insert(asset_id, vessel);
+            }
         }
         Ok(())
     }
 
+    /// Records the media and organisms of `waste` as removed from the process.
+    fn record_waste(&mut self, waste: &Vessel) {
+        let volume_l = waste.media.volume.value;
+        for component in &waste.media.composition.dissolved_components {
+            let grams = component.concentration.value * volume_l;
+            if grams > 0.0 {
+                self.state.events.push(SimulationEvent::MaterialRemoved {
+                    id: component.molecule_id.clone(),
+                    amount: grams,
+                });
+            }
+        }
+        for (org_id, org_state) in &waste.organisms.states {
+            if org_state.biomass.value > 0.0 {
+                self.state.events.push(SimulationEvent::MaterialRemoved {
+                    id: org_id.clone(),
+                    amount: org_state.biomass.value,
+                });
+            }
+        }
+    }
+
+    /// Empties the vessel of `asset_id` into the active vessel, e.g. the broth a continuous culture
+    /// collected, so that [`Self::handoff_state`] hands it on with the rest.
+    pub fn pool_into_active_vessel(&mut self, asset_id: &str) {
+        if asset_id == self.state.active_vessel_id {
+            return;
+        }
+        if let Some(mut vessel) = self.state.vessels.remove(asset_id) {
+            let volume_l = vessel.media.volume.value;
+            flow::move_volume(&mut vessel, self.state.active_vessel_mut(), volume_l);
+        }
+    }
+
     fn evaluate_condition(&self, condition: &Condition) -> Result<bool, BioforgeError> {
         Ok(match condition {
             Condition::TimeInStage { ticks } => self.state.ticks_in_current_stage >= *ticks,
@@ -278,11 +687,14 @@ impl SimulationEngine {
             Condition::ProductAmount {
                 molecule_name,
                 target_grams,
+                organism_id,
             } => {
                 let mut produced_grams = 0.0;
-                for (org_id, org_state) in &self.state.organisms.states {
+                let all_organisms = self.state.vessels.values().flat_map(|v| &v.organisms.states);
+                let producers = all_organisms.filter(|(org_id, _)| organism_id.as_ref().is_none_or(|id| id == *org_id));
+                for (org_id, org_state) in producers {
                     if let Some(org_def) = self.organism_defs.get(org_id) {
-                        if let Some(yield_mg_g) = find_yield(org_def, molecule_name) {
+                        if let Some(yield_mg_g) = org_def.yield_for(molecule_name) {
                             produced_grams += org_state.biomass.value * yield_mg_g / 1000.0;
                         }
                     }
@@ -294,7 +706,7 @@ impl SimulationEngine {
                 operator,
                 value,
             } => {
-                if let Some(component) = self.state.media.composition.dissolved_components.iter().find(|c| c.molecule_id == *molecule_id) {
+                if let Some(component) = self.state.active_vessel().dissolved_component(molecule_id) {
                     let current_value = component.concentration.value;
                     match operator {
                         ComparisonOperator::LessThan => current_value < *value,
@@ -306,6 +718,10 @@ impl SimulationEngine {
                     false
                 }
             }
+            Condition::UnitOperationComplete => match self.current_method()?.technique.as_str() {
+                SAPONIFICATION => self.saponification_naoh_remaining_g(self.state.ticks_in_current_stage) <= 0.0,
+                _ => true,
+            },
             Condition::AssetValue {
                 asset_id,
                 parameter,
@@ -333,46 +749,220 @@ impl SimulationEngine {
         })
     }
 
+    /// Returns the method for the current step of the workflow.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BioforgeError::MethodNotFound` if the workflow has already finished. Unknown method
+    /// ids are rejected by `SimulationBuilder::build`, so they cannot surface here.
+    pub fn current_method(&self) -> Result<&Method, BioforgeError> {
+        let method_id = self
+            .process
+            .default_workflow
+            .get(self.current_step_index)
+            .ok_or_else(|| BioforgeError::MethodNotFound(format!("workflow step {}", self.current_step_index)))?;
+        self.method_index
+            .get(method_id)
+            .map(|&i| &self.process.methods[i])
+            .ok_or_else(|| BioforgeError::MethodNotFound(method_id.clone()))
+    }
+
+    /// Rewinds the engine to the state it was built with so the same configuration can run again.
+    ///
+    /// Media, organisms, assets, tick counters, the workflow position, biomass history, and growth
+    /// multipliers are all restored; an engine built with a resume point returns to that point.
+    /// When `log_path` is given, a file-backed main log is redirected to a new file in the same
+    /// format and layout (sinks that do not write files ignore it); otherwise logging continues
+    /// into the current one. Additional sinks disabled by a failure are given another chance.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BioforgeError::FileIO` if the new log file cannot be created.
+    pub fn reset(&mut self, log_path: Option<&str>) -> Result<(), BioforgeError> {
+        if let Some(path) = log_path {
+            let redirected = match &mut self.sink {
+                Some(registered) => registered.sink.redirect(path),
+                None => TimeSeriesLogger::new(path, Default::default())
+                    .map(|logger| self.sink = Some(RegisteredSink::new(Box::new(logger), true))),
+            };
+            redirected.map_err(|e| BioforgeError::FileIO(path.to_string(), e))?;
+        }
+        for sink in self.sink.iter_mut().chain(&mut self.additional_sinks) {
+            sink.failed = false;
+        }
+        self.state = self.initial_state.clone();
+        self.current_step_index = 0;
+        self.transfer_progress_l = vec![0.0; self.first_method_transfer_count()];
+        self.biomass_history.clear();
+        self.growth_multipliers = self.initial_growth_multipliers.clone();
+        self.event_counts.clear();
+        self.initial_logged = false;
+        if let Some(point) = self.resume_point.clone() {
+            self.apply_resume_point(&point)?;
+        }
+        Ok(())
+    }
+
+    /// Number of transfers performed by the first method of the workflow.
+    pub(super) fn first_method_transfer_count(&self) -> usize {
+        self.process
+            .default_workflow
+            .first()
+            .and_then(|id| self.transfers.get(id))
+            .map_or(0, Vec::len)
+    }
+
+    /// Sets an organism's growth multiplier for the current run. [`Self::reset`] restores the
+    /// value the engine was built with, so sweeps should call this after resetting.
+    pub fn set_growth_multiplier(&mut self, organism_id: &str, multiplier: f64) {
+        self.growth_multipliers.insert(organism_id.to_string(), multiplier);
+    }
+
+    /// Replaces the rule set evaluated by the process methods. Rules are matched to methods by
+    /// name, so the new set should use the names listed in each method's `required_rule_ids`.
+    pub fn replace_rules(&mut self, rules: Vec<Rule>) {
+        self.rules = rules.into_iter().map(|r| (r.name.clone(), r)).collect();
+    }
+
+    /// Returns the wall-clock time of tick zero, if one was set with `with_start_time`.
+    pub fn start_time(&self) -> Option<DateTime<Utc>> {
+        self.start_time
+    }
+
     pub fn get_tick(&self) -> u64 {
         self.state.tick
     }
 
-    pub fn get_assets(&self) -> &HashMap<String, LiveAsset> {
+    pub fn get_assets(&self) -> &BTreeMap<String, LiveAsset> {
         &self.state.assets
     }
 
-    pub fn get_assets_mut(&mut self) -> &mut HashMap<String, LiveAsset> {
+    pub fn get_assets_mut(&mut self) -> &mut BTreeMap<String, LiveAsset> {
         &mut self.state.assets
     }
 
-    pub fn get_organism_states(&self) -> &HashMap<String, IndividualOrganismState> {
-        &self.state.organisms.states
+    /// Returns the organisms in the active vessel.
+    pub fn get_organism_states(&self) -> &BTreeMap<String, IndividualOrganismState> {
+        &self.state.active_vessel().organisms.states
     }
 
+    /// Returns the media in the active vessel.
     pub fn get_media_state(&self) -> &MediaState {
-        &self.state.media
+        &self.state.active_vessel().media
+    }
+
+    /// Returns the contents of every occupied vessel, keyed by asset id.
+    pub fn get_vessels(&self) -> &BTreeMap<String, Vessel> {
+        &self.state.vessels
     }
 
     pub fn get_process(&self) -> &Process {
         &self.process
     }
+
+    /// Captures the culture in the active vessel so another simulation can continue from it.
+    ///
+    /// Product pools are derived from each organism's biomass and its targeted molecule yields.
+    pub fn handoff_state(&self) -> HandoffState {
+        let vessel = self.state.active_vessel();
+        let mut product_pools = BTreeMap::new();
+        for (org_id, org_state) in &vessel.organisms.states {
+            if let Some(org_def) = self.organism_defs.get(org_id) {
+                for (molecule, yield_mg_g) in org_def.all_yields() {
+                    *product_pools.entry(molecule.to_string()).or_insert(0.0) +=
+                        org_state.biomass.value * yield_mg_g / 1000.0;
+                }
+            }
+        }
+
+        HandoffState {
+            media: vessel.media.clone(),
+            organism_states: vessel.organisms.states.clone(),
+            growth_multipliers: self.growth_multipliers.clone(),
+            product_pools,
+        }
+    }
 }
 
-fn find_yield(organism: &Organism, molecule_name: &str) -> Option<f64> {
-    organism
-        .static_properties
-        .targeted_molecular_classes
-        .terpenoids_and_carotenoids
-        .iter()
-        .find(|m| m.molecule == molecule_name)
-        .map(|m| m.concentration_mg_g_dw)
-        .or_else(|| {
-            organism
-                .static_properties
-                .targeted_molecular_classes
-                .cell_wall_components
-                .iter()
-                .find(|m| m.molecule == molecule_name)
-                .map(|m| m.concentration_mg_g_dw)
-        })
+/// The result of advancing a single organism by one tick, before it is applied to shared state.
+struct OrganismTickOutcome<'a> {
+    /// Biomass at the end of the tick.
+    biomass: f64,
+    /// Specific growth rate over the tick, in 1/h.
+    growth_rate: f64,
+    /// Grams demanded from the media, in the order of the organism's consumption definitions;
+    /// organisms over-demanding a substrate together share what there is.
+    consumed: Vec<(String, f64)>,
+    /// Grams secreted into the media, paired with the secretion definition that produced them.
+    secreted: Vec<(&'a MediaExchangeRate, f64)>,
+}
+
+/// Computes one organism's growth, consumption, and secretion for a one-hour tick.
+///
+/// This reads the start-of-tick media only and has no side effects, so it can be evaluated for
+/// every organism concurrently. Substrate availability is judged per organism against the
+/// start-of-tick concentrations; a substrate over-allocated among organisms is shared in
+/// proportion to their demand when the outcomes are reconciled.
+fn compute_organism_tick<'a>(
+    org_def: &'a Organism,
+    biomass: f64,
+    bioreactor_temp: Option<f64>,
+    growth_multiplier: f64,
+    media: &MediaState,
+    component_index: &ComponentIndex,
+) -> OrganismTickOutcome<'a> {
+    let temp_tolerance = &org_def.dynamic_parameters.environmental_tolerances.temperature;
+    let bioreactor_temp = bioreactor_temp.unwrap_or(temp_tolerance.optimal.value);
+
+    let stress_factor = if bioreactor_temp < temp_tolerance.range.min || bioreactor_temp > temp_tolerance.range.max {
+        0.1
+    } else if bioreactor_temp <= temp_tolerance.optimal.value {
+        0.1 + 0.9 * (bioreactor_temp - temp_tolerance.range.min) / (temp_tolerance.optimal.value - temp_tolerance.range.min)
+    } else {
+        1.0 - 0.9 * (bioreactor_temp - temp_tolerance.optimal.value) / (temp_tolerance.range.max - temp_tolerance.optimal.value)
+    };
+
+    let k_s = 0.5;
+    let nutrient_concentration = org_def.dynamic_parameters.metabolic_exchange.media_consumption.first()
+        .and_then(|c| component_index.get_by_name(media, &c.molecule_name))
+        .map_or(0.0, |c| c.concentration.value);
+
+    let nutrient_limitation_factor = nutrient_concentration / (k_s + nutrient_concentration);
+
+    let growth_rate = org_def.dynamic_parameters.growth_rate_per_hr * stress_factor * nutrient_limitation_factor * growth_multiplier;
+    let growth = biomass * ((growth_rate * TIME_STEP_HR).exp() - 1.0);
+    let biomass = biomass + growth;
+
+    let mut consumed = Vec::new();
+    for consumption_def in &org_def.dynamic_parameters.metabolic_exchange.media_consumption {
+        if let Some(nutrient) = component_index.get(media, &consumption_def.molecule_id) {
+            if nutrient.concentration.value > 0.0 {
+                let nutrient_mw = nutrient_molar_mass(&consumption_def.molecule_id);
+
+                let consumption_rate_g_gdw_hr = consumption_def.max_exchange_rate.value * nutrient_mw / 1000.0 * growth_multiplier;
+                let max_consumption_g = consumption_rate_g_gdw_hr * biomass * TIME_STEP_HR;
+                let available_nutrient_g = nutrient.concentration.value * media.volume.value;
+                let actual_consumption_g = max_consumption_g.min(available_nutrient_g);
+
+                if actual_consumption_g > 0.0 {
+                    consumed.push((consumption_def.molecule_id.clone(), actual_consumption_g));
+                }
+            }
+        }
+    }
+
+    let mut secreted = Vec::new();
+    for secretion_def in &org_def.dynamic_parameters.metabolic_exchange.media_secretion {
+        let byproduct_mw = if secretion_def.molecule_id == "CHEBI:30089" { 60.05 } else { 1.0 };
+        let secretion_rate_g_gdw_hr = secretion_def.max_exchange_rate.value * byproduct_mw / 1000.0;
+        // Secretion stops with growth, as uptake does: neither a harvested culture nor one whose
+        // growth a rule stopped, such as on reaching its target, keeps secreting.
+        let secreted_amount_g = secretion_rate_g_gdw_hr * biomass * TIME_STEP_HR * stress_factor * growth_multiplier;
+
+        if secreted_amount_g > 0.0 {
+            secreted.push((secretion_def, secreted_amount_g));
+        }
+    }
+
+    OrganismTickOutcome { biomass, growth_rate, consumed, secreted }
 }
\ No newline at end of file
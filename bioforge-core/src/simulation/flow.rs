@@ -0,0 +1,198 @@
+//! Material transfers between vessels, validated against the assets' connection points.
+
+use super::state::{LiveAsset, Vessel};
+use crate::error::BioforgeError;
+use bioforge_schemas::{
+    asset::FlowCapacity,
+    environment::Measurement,
+    organism_state::IndividualOrganismState,
+    process::{MaterialTransfer, Process},
+};
+use std::collections::{BTreeMap, HashMap};
+
+/// A transfer whose ports have been checked and whose flow limit has been resolved.
+#[derive(Debug, Clone)]
+pub struct ResolvedTransfer {
+    pub from_asset_id: String,
+    pub from_port_id: String,
+    pub to_asset_id: String,
+    pub to_port_id: String,
+    /// Litres to move, or `None` to move the entire contents of the source vessel.
+    pub volume_l: Option<f64>,
+    /// The slowest rated flow along the connection, or `None` if neither port is rated.
+    pub max_rate_l_per_hr: Option<f64>,
+}
+
+/// Validates every transfer in `process` against the connection points of `assets`, returning
+/// the resolved transfers keyed by method id.
+///
+/// # Errors
+///
+/// Returns `BioforgeError::AssetNotFound` for transfers that reference an unknown asset and
+/// `BioforgeError::ConnectionError` for missing ports, ports that do not allow flow in the
+/// required direction, or ports whose flow capacities are restricted to a different material.
+pub fn resolve_transfers(
+    process: &Process,
+    assets: &BTreeMap<String, LiveAsset>,
+) -> Result<HashMap<String, Vec<ResolvedTransfer>>, BioforgeError> {
+    let mut resolved = HashMap::new();
+    for method in &process.methods {
+        let Some(transfers) = &method.transfers else {
+            continue;
+        };
+        let mut method_transfers = Vec::new();
+        for transfer in transfers {
+            let outlet = find_capacity(
+                assets,
+                &transfer.from_asset_id,
+                &transfer.from_port_id,
+                transfer,
+                FlowCapacity::allows_outbound,
+                "outbound",
+            )?;
+            let inlet = find_capacity(
+                assets,
+                &transfer.to_asset_id,
+                &transfer.to_port_id,
+                transfer,
+                FlowCapacity::allows_inbound,
+                "inbound",
+            )?;
+            let max_rate_l_per_hr = match (outlet.rate_l_per_hr(), inlet.rate_l_per_hr()) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            method_transfers.push(ResolvedTransfer {
+                from_asset_id: transfer.from_asset_id.clone(),
+                from_port_id: transfer.from_port_id.clone(),
+                to_asset_id: transfer.to_asset_id.clone(),
+                to_port_id: transfer.to_port_id.clone(),
+                volume_l: transfer.volume_l,
+                max_rate_l_per_hr,
+            });
+        }
+        resolved.insert(method.method_id.clone(), method_transfers);
+    }
+    Ok(resolved)
+}
+
+/// Finds a flow capacity on `asset_id`/`port_id` that allows flow in the required direction and
+/// carries the transfer's material.
+fn find_capacity<'a>(
+    assets: &'a BTreeMap<String, LiveAsset>,
+    asset_id: &str,
+    port_id: &str,
+    transfer: &MaterialTransfer,
+    allows: fn(&FlowCapacity) -> bool,
+    direction: &str,
+) -> Result<&'a FlowCapacity, BioforgeError> {
+    let connection_error = |reason: String| BioforgeError::ConnectionError {
+        asset_id: asset_id.to_string(),
+        port_id: port_id.to_string(),
+        reason,
+    };
+
+    let asset = assets
+        .get(asset_id)
+        .ok_or_else(|| BioforgeError::AssetNotFound(asset_id.to_string()))?;
+    let port = asset
+        .definition
+        .connection_points
+        .iter()
+        .flatten()
+        .find(|p| p.port_id == port_id)
+        .ok_or_else(|| connection_error("port is not defined on the asset".to_string()))?;
+
+    let directional: Vec<&FlowCapacity> =
+        port.flow_capacities.iter().filter(|c| allows(c)).collect();
+    if directional.is_empty() {
+        return Err(connection_error(format!("port does not allow {} flow", direction)));
+    }
+
+    directional
+        .into_iter()
+        .find(|c| match (&c.material_id, &transfer.material_id) {
+            (None, _) => true,
+            (Some(port_material), Some(material)) => port_material == material,
+            (Some(_), None) => false,
+        })
+        .ok_or_else(|| {
+            connection_error(format!(
+                "no {} flow capacity carries material '{}'",
+                direction,
+                transfer.material_id.as_deref().unwrap_or("<unspecified>")
+            ))
+        })
+}
+
+/// Moves `volume_l` litres of well-mixed broth from `source` into `target`.
+///
+/// Dissolved components, dissolved gases, and biomass move in proportion to the volume; the
+/// target's concentrations are volume-weighted averages of the two streams.
+pub fn move_volume(source: &mut Vessel, target: &mut Vessel, volume_l: f64) {
+    let source_volume = source.media.volume.value;
+    let volume_l = volume_l.min(source_volume);
+    if volume_l <= 0.0 {
+        return;
+    }
+    let fraction = volume_l / source_volume;
+    let target_volume = target.media.volume.value;
+    let mixed_volume = target_volume + volume_l;
+    let dilution = target_volume / mixed_volume;
+
+    for component in &mut target.media.composition.dissolved_components {
+        component.concentration.value *= dilution;
+    }
+    for incoming in &source.media.composition.dissolved_components {
+        let added = incoming.concentration.value * volume_l / mixed_volume;
+        match target.component_index.get_mut(&mut target.media, &incoming.molecule_id) {
+            Some(existing) => existing.concentration.value += added,
+            None => {
+                let mut component = incoming.clone();
+                component.concentration.value = added;
+                target.component_index.push(&mut target.media, component);
+            }
+        }
+    }
+
+    for gas in &mut target.media.composition.dissolved_gases {
+        gas.concentration.value *= dilution;
+    }
+    for incoming in &source.media.composition.dissolved_gases {
+        let added = incoming.concentration.value * volume_l / mixed_volume;
+        let existing = target
+            .media
+            .composition
+            .dissolved_gases
+            .iter_mut()
+            .find(|g| g.gas_id == incoming.gas_id);
+        match existing {
+            Some(existing) => existing.concentration.value += added,
+            None => {
+                let mut gas = incoming.clone();
+                gas.concentration.value = added;
+                target.media.composition.dissolved_gases.push(gas);
+            }
+        }
+    }
+
+    if target_volume <= 0.0 {
+        target.media.ph = source.media.ph;
+    }
+    target.media.volume.value = mixed_volume;
+    source.media.volume.value -= volume_l;
+
+    for (org_id, org_state) in source.organisms.states.iter_mut() {
+        let moved = org_state.biomass.value * fraction;
+        org_state.biomass.value -= moved;
+        target
+            .organisms
+            .states
+            .entry(org_id.clone())
+            .or_insert_with(|| IndividualOrganismState {
+                biomass: Measurement { value: 0.0, unit: org_state.biomass.unit.clone() },
+            })
+            .biomass
+            .value += moved;
+    }
+}
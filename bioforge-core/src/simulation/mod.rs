@@ -1,3 +1,6 @@
 pub mod builder;
 pub mod engine;
+pub mod flow;
+pub mod observer;
+pub mod pacing;
 pub mod state;
\ No newline at end of file
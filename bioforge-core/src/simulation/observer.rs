@@ -0,0 +1,20 @@
+//! Hooks for pushing the simulation state to external consumers as it advances.
+
+use super::state::SimulationState;
+
+/// Receives the simulation state after every tick, once it has been logged.
+///
+/// Observers see the same state the time-series log records, which makes them suitable for
+/// streaming simulated sensor values to dashboards or hardware controllers.
+pub trait TickObserver {
+    fn on_tick(&mut self, state: &SimulationState, method_id: &str);
+}
+
+impl<F> TickObserver for F
+where
+    F: FnMut(&SimulationState, &str),
+{
+    fn on_tick(&mut self, state: &SimulationState, method_id: &str) {
+        self(state, method_id)
+    }
+}
@@ -0,0 +1,84 @@
+//! Real-time pacing for runs that drive physical equipment.
+
+use super::{
+    engine::SimulationEngine,
+    state::{RunStatus, RunSummary},
+};
+use crate::error::BioforgeError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The longest a paced run sleeps before checking for cancellation again.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// A cloneable flag used to stop a paced run from another thread.
+#[derive(Debug, Clone, Default)]
+pub struct CancelFlag(Arc<AtomicBool>);
+
+impl CancelFlag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Asks the run to stop before its next tick.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+impl SimulationEngine {
+    /// Runs the workflow at a fixed real-time rate of `ticks_per_second`, logging and notifying
+    /// observers as [`run`](Self::run) does.
+    ///
+    /// Cancellation is checked between ticks and while waiting for the next one, so it takes
+    /// effect before another tick starts. A cancelled engine is left between ticks and can be
+    /// resumed by calling `run_paced` or `run` again.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BioforgeError::ConfigError` if `ticks_per_second` is not a positive, finite
+    /// number, and propagates any error raised by a tick.
+    pub fn run_paced(
+        &mut self,
+        ticks_per_second: f64,
+        cancel: &CancelFlag,
+    ) -> Result<RunSummary, BioforgeError> {
+        if !(ticks_per_second.is_finite() && ticks_per_second > 0.0) {
+            return Err(BioforgeError::ConfigError(format!(
+                "ticks_per_second must be a positive number, got {}",
+                ticks_per_second
+            )));
+        }
+        let interval = Duration::from_secs_f64(1.0 / ticks_per_second);
+        let started = Instant::now();
+        self.log_initial_state()?;
+
+        let mut next_tick_at = Instant::now();
+        loop {
+            // Wait for the next tick's slot, waking regularly to honour cancellation.
+            loop {
+                if cancel.is_cancelled() {
+                    return Ok(self.summarize(RunStatus::Cancelled, started));
+                }
+                let now = Instant::now();
+                if now >= next_tick_at {
+                    break;
+                }
+                thread::sleep((next_tick_at - now).min(CANCEL_POLL_INTERVAL));
+            }
+
+            if !self.tick()? {
+                break;
+            }
+            // A slow tick delays the schedule rather than triggering a burst of catch-up ticks.
+            next_tick_at = (next_tick_at + interval).max(Instant::now());
+        }
+        self.finish(started)
+    }
+}
@@ -1,16 +1,82 @@
 use bioforge_schemas::{
     asset::Asset,
-    environment::MediaState,
-    organism_state::OrganismState,
+    environment::{DissolvedComponent, MediaComposition, MediaState, Measurement},
+    organism_state::{IndividualOrganismState, OrganismState},
 };
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum SimulationEvent {
-    MaterialConsumed { id: String, amount: f64 },
+    /// `consumer_id` is the organism that took the material up, or `None` when a unit operation
+    /// consumed it. Logs written before the field existed read as `None`.
+    MaterialConsumed {
+        id: String,
+        amount: f64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        consumer_id: Option<String>,
+    },
     MaterialAdded { id: String, amount: f64 },
+    /// Grams of a dissolved component, or of an organism's biomass keyed by organism id, that
+    /// left the process as waste, e.g. spent media drained from a vessel.
+    MaterialRemoved { id: String, amount: f64 },
+    MaterialTransferred { from_asset_id: String, to_asset_id: String, volume_l: f64 },
+    /// The workflow moved from one method to the next. `from_method_id` is `None` for the first
+    /// stage and `to_method_id` is `None` once the workflow is complete.
+    StageChanged { from_method_id: Option<String>, to_method_id: Option<String>, tick: u64 },
+}
+
+impl SimulationEvent {
+    /// The event's type name, as written in the `type` tag of the event log.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SimulationEvent::MaterialConsumed { .. } => "material_consumed",
+            SimulationEvent::MaterialAdded { .. } => "material_added",
+            SimulationEvent::MaterialRemoved { .. } => "material_removed",
+            SimulationEvent::MaterialTransferred { .. } => "material_transferred",
+            SimulationEvent::StageChanged { .. } => "stage_changed",
+        }
+    }
+
+    /// The tick at which the event happened, given the tick whose log row carries it. Commands
+    /// run after their tick is logged, so the additions, removals, and stage changes they raise
+    /// are logged with the next tick.
+    pub fn tick(&self, logged_tick: u64) -> u64 {
+        match self {
+            SimulationEvent::StageChanged { tick, .. } => *tick,
+            SimulationEvent::MaterialAdded { .. } | SimulationEvent::MaterialRemoved { .. } => {
+                logged_tick.saturating_sub(1)
+            }
+            SimulationEvent::MaterialConsumed { .. } | SimulationEvent::MaterialTransferred { .. } => logged_tick,
+        }
+    }
+}
+
+/// How a simulation run ended. Failures are reported as errors rather than as a status.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum RunStatus {
+    /// Every method of the workflow ran to completion.
+    Completed,
+    /// A paced run was cancelled between ticks; the engine can resume from where it stopped.
+    Cancelled,
+}
+
+/// The outcome of [`SimulationEngine::run`](super::engine::SimulationEngine::run).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunSummary {
+    pub status: RunStatus,
+    pub total_ticks: u64,
+    /// The last workflow method that ran, or `None` for an empty workflow.
+    pub final_stage: Option<String>,
+    /// Final biomass in grams, summed over all vessels, keyed by organism id.
+    pub final_biomass: BTreeMap<String, f64>,
+    /// Number of events raised over the run, keyed by [`SimulationEvent::kind`].
+    pub event_counts: BTreeMap<String, u64>,
+    pub wall_clock_secs: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -20,12 +86,219 @@ pub struct LiveAsset {
     pub ph: f64,
 }
 
+/// Maps a `molecule_id`, and a `molecule_name`, to its position in
+/// `MediaState::composition.dissolved_components`.
+///
+/// The media keeps its schema `Vec` layout so it can be logged as-is; this index turns the
+/// per-tick lookups into O(1) operations. It must be rebuilt whenever components are added
+/// to or removed from the media outside of [`ComponentIndex::push`].
+#[derive(Debug, Clone, Default)]
+pub struct ComponentIndex {
+    by_id: HashMap<String, usize>,
+    /// The first component of each name, as a scan of the media would find it.
+    by_name: HashMap<String, usize>,
+}
+
+impl ComponentIndex {
+    /// Indexes every dissolved component currently in `media`.
+    pub fn build(media: &MediaState) -> Self {
+        let mut index = Self::default();
+        for (i, component) in media.composition.dissolved_components.iter().enumerate() {
+            index.insert(component, i);
+        }
+        index
+    }
+
+    fn insert(&mut self, component: &DissolvedComponent, i: usize) {
+        self.by_id.insert(component.molecule_id.clone(), i);
+        self.by_name.entry(component.molecule_name.clone()).or_insert(i);
+    }
+
+    pub fn get<'a>(&self, media: &'a MediaState, molecule_id: &str) -> Option<&'a DissolvedComponent> {
+        self.by_id
+            .get(molecule_id)
+            .map(|&i| &media.composition.dissolved_components[i])
+    }
+
+    /// The first component named `molecule_name`. Growth is limited by the primary carbon
+    /// source found by name, so media that list it under another id still feed growth.
+    pub fn get_by_name<'a>(&self, media: &'a MediaState, molecule_name: &str) -> Option<&'a DissolvedComponent> {
+        self.by_name
+            .get(molecule_name)
+            .map(|&i| &media.composition.dissolved_components[i])
+    }
+
+    pub fn get_mut<'a>(
+        &self,
+        media: &'a mut MediaState,
+        molecule_id: &str,
+    ) -> Option<&'a mut DissolvedComponent> {
+        self.by_id
+            .get(molecule_id)
+            .map(move |&i| &mut media.composition.dissolved_components[i])
+    }
+
+    /// Appends a component to the media and records its position.
+    pub fn push(&mut self, media: &mut MediaState, component: DissolvedComponent) {
+        self.insert(&component, media.composition.dissolved_components.len());
+        media.composition.dissolved_components.push(component);
+    }
+
+    pub fn contains(&self, molecule_id: &str) -> bool {
+        self.by_id.contains_key(molecule_id)
+    }
+}
+
+/// The contents of a single vessel: its media and the organisms growing in it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Vessel {
+    pub media: MediaState,
+    pub organisms: OrganismState,
+    #[serde(skip)]
+    pub component_index: ComponentIndex,
+}
+
+impl Vessel {
+    pub fn new(media: MediaState, organisms: OrganismState) -> Self {
+        let component_index = ComponentIndex::build(&media);
+        Self { media, organisms, component_index }
+    }
+
+    /// An unoccupied vessel with no media and no organisms.
+    pub fn empty() -> Self {
+        Self::new(
+            MediaState {
+                volume: Measurement { value: 0.0, unit: "L".to_string() },
+                ph: 7.0,
+                composition: MediaComposition {
+                    dissolved_components: Vec::new(),
+                    dissolved_gases: Vec::new(),
+                },
+            },
+            OrganismState { states: BTreeMap::new() },
+        )
+    }
+
+    /// Looks up a dissolved component in the media by its `molecule_id`.
+    pub fn dissolved_component(&self, molecule_id: &str) -> Option<&DissolvedComponent> {
+        self.component_index.get(&self.media, molecule_id)
+    }
+
+    /// Mutable counterpart of [`Vessel::dissolved_component`].
+    pub fn dissolved_component_mut(&mut self, molecule_id: &str) -> Option<&mut DissolvedComponent> {
+        self.component_index.get_mut(&mut self.media, molecule_id)
+    }
+}
+
+/// Rates the engine derives from its own updates during a tick, logged alongside the state.
+///
+/// They come from the exact per-tick deltas rather than from differences between logged rows,
+/// so they stay correct across feeds, transfers, and clamped concentrations.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TickMetrics {
+    /// Biomass of the organisms in the active vessel, in grams.
+    pub total_biomass_g: f64,
+    /// Specific growth rate μ of each organism over the tick, ln(X_t / X_t-1) / Δt in 1/h,
+    /// keyed by organism id. Empty for the initial state and while the active vessel is empty.
+    pub specific_growth_rates: BTreeMap<String, f64>,
+    /// Volumetric uptake rate of each substrate the organisms consume over the tick, in g/L/h,
+    /// keyed by molecule id.
+    pub uptake_rates: BTreeMap<String, f64>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SimulationState {
     pub tick: u64,
     pub ticks_in_current_stage: u64,
-    pub assets: HashMap<String, LiveAsset>,
-    pub media: MediaState,
-    pub organisms: OrganismState,
+    pub assets: BTreeMap<String, LiveAsset>,
+    /// The contents of every occupied vessel, keyed by the asset id that holds them.
+    pub vessels: BTreeMap<String, Vessel>,
+    /// The asset id of the vessel the current method acts on. Always a key of `vessels`.
+    pub active_vessel_id: String,
     pub events: Vec<SimulationEvent>,
-}
\ No newline at end of file
+    /// Rates derived during the last tick.
+    pub metrics: TickMetrics,
+}
+
+impl SimulationState {
+    /// Returns the vessel the current method acts on.
+    pub fn active_vessel(&self) -> &Vessel {
+        &self.vessels[&self.active_vessel_id]
+    }
+
+    /// Mutable counterpart of [`SimulationState::active_vessel`].
+    pub fn active_vessel_mut(&mut self) -> &mut Vessel {
+        self.vessels
+            .get_mut(&self.active_vessel_id)
+            .expect("the active vessel is always present")
+    }
+
+    /// Makes `asset_id` the active vessel.
+    ///
+    /// If that asset does not hold anything yet and `carry_contents` is set, the contents of the
+    /// current vessel move into it, so a single batch follows the workflow from one asset to the
+    /// next. Otherwise an unoccupied asset starts out as an empty vessel (e.g. one that is filled
+    /// by an explicit transfer).
+    pub fn activate_vessel(&mut self, asset_id: &str, carry_contents: bool) {
+        if asset_id == self.active_vessel_id {
+            return;
+        }
+        if !self.vessels.contains_key(asset_id) {
+            let contents = if carry_contents {
+                self.vessels.remove(&self.active_vessel_id)
+            } else {
+                None
+            };
+            self.vessels.insert(asset_id.to_string(), contents.unwrap_or_else(Vessel::empty));
+        }
+        self.active_vessel_id = asset_id.to_string();
+    }
+}
+
+/// A snapshot of an engine's culture at the end of a run, used to start another simulation
+/// (e.g. a downstream process) from the broth that was actually produced.
+#[derive(Debug, Clone)]
+pub struct HandoffState {
+    /// The final media, including any residual substrates and secreted byproducts.
+    pub media: MediaState,
+    /// Final biomass of each organism, keyed by organism id.
+    pub organism_states: BTreeMap<String, IndividualOrganismState>,
+    /// Growth multipliers in effect at the end of the run, keyed by organism id.
+    pub growth_multipliers: HashMap<String, f64>,
+    /// Grams of each target molecule held in the biomass, keyed by molecule name.
+    pub product_pools: BTreeMap<String, f64>,
+}
+
+impl HandoffState {
+    /// Returns the state with every growth multiplier set to zero, modelling a harvested culture
+    /// that no longer grows or takes up substrate.
+    pub fn harvested(mut self) -> Self {
+        for organism_id in self.organism_states.keys() {
+            self.growth_multipliers.insert(organism_id.clone(), 0.0);
+        }
+        self
+    }
+}
+
+/// A run's position rebuilt from its time-series log, from which a new engine can continue the
+/// run (see `analysis::state_from_log` and `SimulationBuilder::with_resume_point`).
+///
+/// Besides the state itself it carries what the engine keeps outside of `SimulationState`.
+/// Growth multipliers are not logged: they start at 1.0 and are set again by the rules that
+/// changed them, which re-fire while their conditions hold.
+#[derive(Debug, Clone)]
+pub struct ResumePoint {
+    /// The state as logged at the end of tick `state.tick`, without that tick's events.
+    pub state: SimulationState,
+    /// Position in the process's default workflow of the method that ran the logged tick.
+    pub step_index: usize,
+    /// Total biomass of the active vessel after each of the last ticks, oldest first, as used by
+    /// `BiomassStationary` conditions.
+    pub recent_biomass: Vec<f64>,
+    /// Litres moved so far in the current method, keyed by source and target asset id.
+    pub transferred_l: BTreeMap<(String, String), f64>,
+    /// Events logged so far, keyed by [`SimulationEvent::kind`].
+    pub event_counts: BTreeMap<String, u64>,
+    /// Wall-clock time of tick zero, if the log carries timestamps.
+    pub start_time: Option<DateTime<Utc>>,
+}
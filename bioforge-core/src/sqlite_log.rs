@@ -0,0 +1,411 @@
+//! SQLite backend for the time-series log, collecting many runs into one queryable database.
+//!
+//! Each engine logs under its own run id. Scalars go to `ticks`, and organisms (with their
+//! specific growth rates), media components and gases, substrate uptake rates, asset states, and
+//! events each get a normalized table keyed by
+//! `(run_id, tick)`, so questions such as "every tick where acetate exceeded 5 g/L, across all
+//! runs" are a single SQL query. Vessel contents are heterogeneous and stay a JSON string.
+
+use crate::{
+    error::BioforgeError,
+    logger::{LogRecord, TimeSeriesSink},
+    simulation::state::SimulationState,
+};
+use bioforge_schemas::{
+    environment::{DissolvedComponent, DissolvedGas, Measurement},
+    organism_state::IndividualOrganismState,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::{params, Connection};
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Ticks written per transaction; SQLite is far faster committing in bulk than per row.
+const COMMIT_TICKS: usize = 1000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        run_id TEXT PRIMARY KEY,
+        label TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS ticks (
+        run_id TEXT NOT NULL REFERENCES runs(run_id),
+        tick INTEGER NOT NULL,
+        stage_id TEXT NOT NULL,
+        timestamp TEXT,
+        media_volume_l REAL NOT NULL,
+        media_ph REAL NOT NULL,
+        total_biomass_g REAL NOT NULL,
+        vessels_json TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS ticks_by_run ON ticks(run_id, tick);
+    CREATE TABLE IF NOT EXISTS organism_states (
+        run_id TEXT NOT NULL,
+        tick INTEGER NOT NULL,
+        organism_id TEXT NOT NULL,
+        biomass REAL NOT NULL,
+        unit TEXT NOT NULL,
+        -- In 1/h; NULL for the initial state.
+        specific_growth_rate REAL
+    );
+    CREATE INDEX IF NOT EXISTS organism_states_by_run ON organism_states(run_id, tick);
+    -- `kind` is 'dissolved' for media components and 'gas' for dissolved gases; `position`
+    -- keeps the media's own ordering.
+    CREATE TABLE IF NOT EXISTS components (
+        run_id TEXT NOT NULL,
+        tick INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        position INTEGER NOT NULL,
+        component_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        concentration REAL NOT NULL,
+        unit TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS components_by_run ON components(run_id, tick);
+    CREATE INDEX IF NOT EXISTS components_by_name ON components(name, concentration);
+    -- Volumetric uptake rate of each substrate in g/L/h.
+    CREATE TABLE IF NOT EXISTS uptake_rates (
+        run_id TEXT NOT NULL,
+        tick INTEGER NOT NULL,
+        molecule_id TEXT NOT NULL,
+        rate REAL NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS uptake_rates_by_run ON uptake_rates(run_id, tick);
+    CREATE TABLE IF NOT EXISTS asset_states (
+        run_id TEXT NOT NULL,
+        tick INTEGER NOT NULL,
+        asset_id TEXT NOT NULL,
+        temperature REAL NOT NULL,
+        ph REAL NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS asset_states_by_run ON asset_states(run_id, tick);
+    CREATE TABLE IF NOT EXISTS events (
+        run_id TEXT NOT NULL,
+        tick INTEGER NOT NULL,
+        seq INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        event_json TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS events_by_run ON events(run_id, tick);
+";
+
+/// Distinguishes runs started within the same microsecond by one process.
+static RUN_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn new_run_id() -> String {
+    format!(
+        "run-{}-{}-{}",
+        Utc::now().format("%Y%m%dT%H%M%S%6f"),
+        std::process::id(),
+        RUN_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// A time-series sink that writes one run into a SQLite database, creating it if needed.
+pub struct SqliteSink {
+    conn: Connection,
+    run_id: String,
+    label: String,
+    uncommitted_ticks: usize,
+}
+
+impl SqliteSink {
+    /// Opens (or creates) the database at `db_path` and registers a new run under `label`, a
+    /// human-readable name such as `upstream_consortium`.
+    pub fn open(db_path: &str, label: &str) -> Result<Self, rusqlite::Error> {
+        let conn = Connection::open(db_path)?;
+        conn.execute_batch(SCHEMA)?;
+        let run_id = new_run_id();
+        conn.execute(
+            "INSERT INTO runs (run_id, label, created_at) VALUES (?1, ?2, ?3)",
+            params![run_id, label, Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)],
+        )?;
+        conn.execute_batch("BEGIN")?;
+        Ok(Self { conn, run_id, label: label.to_string(), uncommitted_ticks: 0 })
+    }
+
+    /// The id under which this sink's ticks are stored.
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    fn insert(
+        &self,
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error> {
+        let vessel = state.active_vessel();
+        let tick = state.tick as i64;
+        let run_id = &self.run_id;
+
+        self.conn
+            .prepare_cached(
+                "INSERT INTO ticks (run_id, tick, stage_id, timestamp, media_volume_l, media_ph, total_biomass_g,
+                 vessels_json) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?
+            .execute(params![
+                run_id,
+                tick,
+                stage_id,
+                timestamp.map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true)),
+                vessel.media.volume.value,
+                vessel.media.ph,
+                state.metrics.total_biomass_g,
+                serde_json::to_string(&state.vessels)?,
+            ])?;
+
+        let mut organisms = self.conn.prepare_cached(
+            "INSERT INTO organism_states (run_id, tick, organism_id, biomass, unit, specific_growth_rate)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (organism_id, organism) in &vessel.organisms.states {
+            organisms.execute(params![
+                run_id,
+                tick,
+                organism_id,
+                organism.biomass.value,
+                organism.biomass.unit,
+                state.metrics.specific_growth_rates.get(organism_id)
+            ])?;
+        }
+
+        let mut uptake_rates = self
+            .conn
+            .prepare_cached("INSERT INTO uptake_rates (run_id, tick, molecule_id, rate) VALUES (?1, ?2, ?3, ?4)")?;
+        for (molecule_id, rate) in &state.metrics.uptake_rates {
+            uptake_rates.execute(params![run_id, tick, molecule_id, rate])?;
+        }
+
+        let mut components = self.conn.prepare_cached(
+            "INSERT INTO components (run_id, tick, kind, position, component_id, name, concentration, unit)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?;
+        let composition = &vessel.media.composition;
+        for (i, c) in composition.dissolved_components.iter().enumerate() {
+            components.execute(params![
+                run_id,
+                tick,
+                "dissolved",
+                i as i64,
+                c.molecule_id,
+                c.molecule_name,
+                c.concentration.value,
+                c.concentration.unit
+            ])?;
+        }
+        for (i, g) in composition.dissolved_gases.iter().enumerate() {
+            components.execute(params![
+                run_id,
+                tick,
+                "gas",
+                i as i64,
+                g.gas_id,
+                g.gas_name,
+                g.concentration.value,
+                g.concentration.unit
+            ])?;
+        }
+
+        let mut assets = self.conn.prepare_cached(
+            "INSERT INTO asset_states (run_id, tick, asset_id, temperature, ph) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (asset_id, asset) in &state.assets {
+            assets.execute(params![run_id, tick, asset_id, asset.temperature, asset.ph])?;
+        }
+
+        let mut events = self.conn.prepare_cached(
+            "INSERT INTO events (run_id, tick, seq, kind, event_json) VALUES (?1, ?2, ?3, ?4, ?5)",
+        )?;
+        for (seq, event) in state.events.iter().enumerate() {
+            events.execute(params![run_id, tick, seq as i64, event.kind(), serde_json::to_string(event)?])?;
+        }
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<(), rusqlite::Error> {
+        if !self.conn.is_autocommit() {
+            self.conn.execute_batch("COMMIT")?;
+        }
+        self.uncommitted_ticks = 0;
+        Ok(())
+    }
+}
+
+impl TimeSeriesSink for SqliteSink {
+    fn log_state(
+        &mut self,
+        state: &SimulationState,
+        stage_id: &str,
+        timestamp: Option<DateTime<Utc>>,
+    ) -> Result<(), anyhow::Error> {
+        if self.conn.is_autocommit() {
+            self.conn.execute_batch("BEGIN")?;
+        }
+        self.insert(state, stage_id, timestamp)?;
+        self.uncommitted_ticks += 1;
+        if self.uncommitted_ticks >= COMMIT_TICKS {
+            self.commit()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), anyhow::Error> {
+        Ok(self.commit()?)
+    }
+
+    fn finish(&mut self) -> Result<(), anyhow::Error> {
+        Ok(self.commit()?)
+    }
+
+    /// Starts a new run, under the same label, in the database at `path`.
+    fn redirect(&mut self, path: &str) -> Result<(), io::Error> {
+        *self = Self::open(path, &self.label).map_err(io::Error::other)?;
+        Ok(())
+    }
+}
+
+impl Drop for SqliteSink {
+    fn drop(&mut self) {
+        if let Err(e) = self.commit() {
+            log::warn!("Failed to commit the SQLite time-series log: {}", e);
+        }
+    }
+}
+
+/// The runs stored in a database, as `(run_id, label)` pairs in the order they were created.
+pub fn list_runs(db_path: &str) -> Result<Vec<(String, String)>, BioforgeError> {
+    let sqlite_error = |e| BioforgeError::SqliteError(db_path.to_string(), e);
+    let conn = Connection::open(db_path).map_err(sqlite_error)?;
+    let mut statement = conn.prepare("SELECT run_id, label FROM runs ORDER BY rowid").map_err(sqlite_error)?;
+    let runs = statement
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .and_then(|rows| rows.collect())
+        .map_err(sqlite_error)?;
+    Ok(runs)
+}
+
+/// Reads one run back into the row layout shared with the file logs.
+pub fn read_sqlite_run(db_path: &str, run_id: &str) -> Result<Vec<LogRecord>, BioforgeError> {
+    let sqlite_error = |e| BioforgeError::SqliteError(db_path.to_string(), e);
+    let conn = Connection::open(db_path).map_err(sqlite_error)?;
+    read_run(&conn, run_id).map_err(|e| match e.downcast::<rusqlite::Error>() {
+        Ok(e) => sqlite_error(e),
+        Err(e) => BioforgeError::LoggingError(e),
+    })
+}
+
+/// The rows of one child table for a run, grouped by tick in insertion order.
+fn by_tick<T>(
+    conn: &Connection,
+    sql: &str,
+    run_id: &str,
+    mut row: impl FnMut(&rusqlite::Row) -> rusqlite::Result<T>,
+) -> rusqlite::Result<BTreeMap<u64, Vec<T>>> {
+    let mut grouped: BTreeMap<u64, Vec<T>> = BTreeMap::new();
+    let mut statement = conn.prepare(sql)?;
+    let mut rows = statement.query(params![run_id])?;
+    while let Some(r) = rows.next()? {
+        let tick: i64 = r.get(0)?;
+        grouped.entry(tick as u64).or_default().push(row(r)?);
+    }
+    Ok(grouped)
+}
+
+fn read_run(conn: &Connection, run_id: &str) -> Result<Vec<LogRecord>, anyhow::Error> {
+    let mut organisms = by_tick(
+        conn,
+        "SELECT tick, organism_id, biomass, unit, specific_growth_rate FROM organism_states
+         WHERE run_id = ?1 ORDER BY rowid",
+        run_id,
+        |r| {
+            let state = IndividualOrganismState { biomass: Measurement { value: r.get(2)?, unit: r.get(3)? } };
+            Ok((r.get::<_, String>(1)?, state, r.get::<_, Option<f64>>(4)?))
+        },
+    )?;
+    let mut uptake_rates = by_tick(
+        conn,
+        "SELECT tick, molecule_id, rate FROM uptake_rates WHERE run_id = ?1 ORDER BY rowid",
+        run_id,
+        |r| Ok((r.get::<_, String>(1)?, r.get::<_, f64>(2)?)),
+    )?;
+    let mut components = by_tick(
+        conn,
+        "SELECT tick, kind, component_id, name, concentration, unit FROM components
+         WHERE run_id = ?1 ORDER BY rowid",
+        run_id,
+        |r| {
+            let kind: String = r.get(1)?;
+            let concentration = Measurement { value: r.get(4)?, unit: r.get(5)? };
+            Ok((kind, r.get::<_, String>(2)?, r.get::<_, String>(3)?, concentration))
+        },
+    )?;
+    let mut assets = by_tick(
+        conn,
+        "SELECT tick, asset_id, temperature, ph FROM asset_states WHERE run_id = ?1 ORDER BY rowid",
+        run_id,
+        |r| {
+            let values = serde_json::json!({ "temperature": r.get::<_, f64>(2)?, "ph": r.get::<_, f64>(3)? });
+            Ok((r.get::<_, String>(1)?, values))
+        },
+    )?;
+    let mut events = by_tick(
+        conn,
+        "SELECT tick, event_json FROM events WHERE run_id = ?1 ORDER BY rowid",
+        run_id,
+        |r| r.get::<_, String>(1),
+    )?;
+
+    let mut statement = conn.prepare(
+        "SELECT tick, stage_id, timestamp, media_volume_l, media_ph, vessels_json, total_biomass_g FROM ticks
+         WHERE run_id = ?1 ORDER BY rowid",
+    )?;
+    let mut rows = statement.query(params![run_id])?;
+    let mut records = Vec::new();
+    while let Some(r) = rows.next()? {
+        let tick = r.get::<_, i64>(0)? as u64;
+        let mut organism_states: BTreeMap<String, IndividualOrganismState> = BTreeMap::new();
+        let mut growth_rates: BTreeMap<String, f64> = BTreeMap::new();
+        for (organism_id, state, rate) in organisms.remove(&tick).unwrap_or_default() {
+            if let Some(rate) = rate {
+                growth_rates.insert(organism_id.clone(), rate);
+            }
+            organism_states.insert(organism_id, state);
+        }
+        let uptake: BTreeMap<String, f64> = uptake_rates.remove(&tick).unwrap_or_default().into_iter().collect();
+        let (mut dissolved, mut gases) = (Vec::new(), Vec::new());
+        for (kind, id, name, concentration) in components.remove(&tick).unwrap_or_default() {
+            if kind == "gas" {
+                gases.push(DissolvedGas { gas_id: id, gas_name: name, concentration });
+            } else {
+                dissolved.push(DissolvedComponent { molecule_id: id, molecule_name: name, concentration });
+            }
+        }
+        let asset_map: serde_json::Map<String, serde_json::Value> =
+            assets.remove(&tick).unwrap_or_default().into_iter().collect();
+        // Events are stored as the logger serialized them, so joining them reproduces the CSV
+        // column exactly.
+        let events_json = format!("[{}]", events.remove(&tick).unwrap_or_default().join(","));
+
+        records.push(LogRecord {
+            tick,
+            stage_id: r.get(1)?,
+            organisms_json: serde_json::to_string(&organism_states)?,
+            media_volume_l: r.get(3)?,
+            media_ph: r.get(4)?,
+            dissolved_components_json: serde_json::to_string(&dissolved)?,
+            dissolved_gases_json: serde_json::to_string(&gases)?,
+            asset_states_json: serde_json::to_string(&asset_map)?,
+            events_json,
+            vessels_json: r.get(5)?,
+            timestamp: r.get(2)?,
+            total_biomass_g: r.get(6)?,
+            growth_rates_json: serde_json::to_string(&growth_rates)?,
+            uptake_rates_json: serde_json::to_string(&uptake)?,
+            series: BTreeMap::new(),
+        });
+    }
+    Ok(records)
+}
@@ -0,0 +1,31 @@
+//! Configurations the builder rejects before the first tick.
+
+mod common;
+
+use bioforge_core::{error::BioforgeError, simulation::builder::SimulationBuilder};
+use bioforge_schemas::process::Process;
+
+fn build(process: Process) -> Result<(), BioforgeError> {
+    SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", 1.0)])
+        .with_process(process)
+        .with_rules(vec![common::time_rule("cult.duration", 40)])
+        .with_initial_media(common::media(1.0, 10.0))
+        .build()
+        .map(|_| ())
+}
+
+#[test]
+fn a_workflow_step_without_a_method_fails_at_build() {
+    let mut process = common::process(
+        "PROC-TEST",
+        &[("MTHD-SEED", "batch", "REACTOR", &["cult.duration"]), ("MTHD-MAIN", "batch", "REACTOR", &["cult.duration"])],
+    );
+    assert!(build(process.clone()).is_ok());
+
+    process.default_workflow.push("MTHD-MISSING".to_string());
+    match build(process) {
+        Err(BioforgeError::MethodNotFound(method_id)) => assert_eq!(method_id, "MTHD-MISSING"),
+        other => panic!("expected the missing method to fail the build, got {:?}", other.err()),
+    }
+}
@@ -0,0 +1,109 @@
+//! The carbon balance of a photoautotroph that takes up nothing but CO₂.
+
+mod common;
+
+use bioforge_core::{
+    analysis::carbon_balance::{carbon_balance_from_records, CarbonBalance, CARBON_DIOXIDE_ID, KG_CO2_PER_G_CARBON},
+    logger::InMemorySink,
+    simulation::builder::SimulationBuilder,
+};
+use bioforge_schemas::{
+    environment::{DissolvedComponent, MediaComposition, MediaState, Measurement},
+    material::Material,
+    organism::Organism,
+    tea_lca::EconomicAssumptions,
+};
+use std::collections::HashMap;
+
+/// CO₂, 27.29% carbon by mass.
+fn carbon_dioxide() -> Material {
+    serde_yaml::from_str(&format!(
+        r#"
+material_id: {CARBON_DIOXIDE_ID}
+material_name: carbon dioxide
+material_class: Chemical
+material_subtype: Gas
+material_category: PurchasedRawMaterial
+unit: kg
+metadata:
+  process_role: Test
+  vendor: null
+  part_number: null
+  notes: null
+  identifiers: null
+specifications:
+- key: carbon_content
+  value: 27.29
+  unit: "%"
+formulation: null
+techno_economic_and_lca_profile:
+  lifecycle_stages:
+    manufacturing_and_acquisition: {{ costs: [], impacts: [] }}
+    use_and_operation: {{ costs: [], impacts: [] }}
+    maintenance: {{ costs: [], impacts: [] }}
+    end_of_life: {{ costs: [], impacts: [] }}
+"#
+    ))
+    .unwrap()
+}
+
+/// The test organism, 50% carbon, taking up dissolved CO₂ in place of glucose and secreting
+/// nothing.
+fn photoautotroph() -> Organism {
+    let mut organism = common::organism("ORG-ALGA", 1.0);
+    let exchange = &mut organism.dynamic_parameters.metabolic_exchange;
+    exchange.media_consumption[0].molecule_id = CARBON_DIOXIDE_ID.to_string();
+    exchange.media_consumption[0].molecule_name = "carbon dioxide".to_string();
+    exchange.media_secretion.clear();
+    organism
+}
+
+/// Six hours of the photoautotroph in 10 L of media holding 5 g/L of dissolved CO₂.
+fn balance() -> CarbonBalance {
+    let media = MediaState {
+        volume: Measurement { value: 10.0, unit: "L".to_string() },
+        ph: 7.0,
+        composition: MediaComposition {
+            dissolved_components: vec![DissolvedComponent {
+                molecule_id: CARBON_DIOXIDE_ID.to_string(),
+                molecule_name: "carbon dioxide".to_string(),
+                concentration: Measurement { value: 5.0, unit: "g/L".to_string() },
+            }],
+            dissolved_gases: vec![],
+        },
+    };
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![photoautotroph()])
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "PBR", &["cult.duration"])]))
+        .with_rules(vec![common::time_rule("cult.duration", 6)])
+        .with_initial_media(media)
+        .with_timeseries_sink(Box::new(InMemorySink::new()))
+        .build()
+        .unwrap();
+    engine.run().unwrap();
+    let materials = HashMap::from([(CARBON_DIOXIDE_ID.to_string(), carbon_dioxide())]);
+    carbon_balance_from_records(engine.logged_records().unwrap(), &[photoautotroph()], &materials, &[]).unwrap()
+}
+
+#[test]
+fn a_photoautotroph_removes_more_co2_than_it_returns() {
+    let balance = balance();
+    assert!(balance.unmatched_molecules.is_empty(), "{:?}", balance.unmatched_molecules);
+    assert_eq!((balance.feedstock_carbon_g, balance.secreted_carbon_g), (0.0, 0.0));
+    assert!(balance.fixed_carbon_g > 0.0 && balance.biomass_carbon_g > 0.0, "{:?}", balance);
+    assert!(balance.reconciles(1e-9), "{:?}", balance);
+
+    // All the carbon fixed is either grown into biomass or respired.
+    assert!((balance.fixed_carbon_g - balance.biomass_carbon_g - balance.respired_carbon_g).abs() < 1e-9, "{:?}", balance);
+    assert!(balance.net_biogenic_kg_co2e < 0.0, "{:?}", balance);
+    assert!((balance.net_biogenic_kg_co2e + balance.biomass_carbon_g * KG_CO2_PER_G_CARBON).abs() < 1e-12, "{:?}", balance);
+}
+
+#[test]
+fn fossil_energy_is_kept_apart_from_the_biogenic_balance() {
+    let assumptions = EconomicAssumptions { grid_gwp_kg_co2e_per_kwh: 0.4, ..Default::default() };
+    let biogenic = balance();
+    let with_energy = biogenic.clone().with_fossil_energy(50.0, &assumptions);
+    assert!((with_energy.fossil_energy_kg_co2e - 20.0).abs() < 1e-12);
+    assert_eq!(with_energy.net_biogenic_kg_co2e, biogenic.net_biogenic_kg_co2e);
+}
@@ -0,0 +1,139 @@
+//! Synthetic organisms, media, processes, and rules shared by the integration tests.
+
+use bioforge_core::{logger::LogRecord, simulation::state::SimulationEvent};
+use bioforge_schemas::{
+    environment::{DissolvedComponent, MediaComposition, MediaState, Measurement},
+    organism::Organism,
+    process::Process,
+    rule::Rule,
+};
+use serde_json::{json, Value};
+
+pub const GLUCOSE_ID: &str = "CHEBI:17234";
+pub const ACETATE_ID: &str = "CHEBI:30089";
+
+/// An aerobic bacterium inoculated with `initial_biomass_g` that grows on glucose and secretes
+/// acetate.
+pub fn organism(organism_id: &str, initial_biomass_g: f64) -> Organism {
+    serde_json::from_value(json!({
+        "organism_id": organism_id,
+        "organism_name": format!("Test organism {}", organism_id),
+        "organism_type": "Bacteria",
+        "strain_details": null,
+        "initial_biomass": { "value": initial_biomass_g, "unit": "g" },
+        "static_properties": {
+            "elemental_composition": {
+                "carbon": 0.5, "hydrogen": 0.07, "oxygen": 0.24,
+                "nitrogen": 0.13, "phosphorus": 0.03, "sulfur": 0.01
+            },
+            "macromolecular_summary": {
+                "protein": 55.0, "carbohydrate": 20.0, "lipid": 10.0, "nucleic_acid": 10.0, "ash": 5.0
+            },
+            "morphology": { "nominal_diameter": { "value": 1.0, "unit": "um" } },
+            "targeted_molecular_classes": {
+                "terpenoids_and_carotenoids": [{ "molecule": "Lutein", "concentration_mg_g_dw": 2.5 }],
+                "cell_wall_components": []
+            }
+        },
+        "dynamic_parameters": {
+            "growth_rate_per_hr": 0.3,
+            "environmental_tolerances": {
+                "photosynthetic_light_response": null,
+                "temperature": { "optimal": { "value": 25.0, "unit": "celsius" }, "range": { "min": 10.0, "max": 40.0 } },
+                "ph": { "optimal": 7.0, "range": { "min": 5.0, "max": 9.0 } },
+                "chemical": []
+            },
+            "metabolic_exchange": {
+                "media_consumption": [{
+                    "molecule_id": GLUCOSE_ID,
+                    "molecule_name": "D-glucose",
+                    "max_exchange_rate": { "value": 2.0, "unit": "mmol/g_dw/hr" },
+                    "conditions": { "aeration": "Aerobic", "light": null, "notes": null }
+                }],
+                "media_secretion": [{
+                    "molecule_id": ACETATE_ID,
+                    "molecule_name": "acetate",
+                    "max_exchange_rate": { "value": 2.0, "unit": "mmol/g_dw/hr" },
+                    "conditions": { "aeration": "Aerobic", "light": null, "notes": null }
+                }],
+                "gas_consumption": [],
+                "gas_secretion": []
+            }
+        }
+    }))
+    .expect("test organism should deserialize")
+}
+
+/// `volume_l` litres of media holding `glucose_g_l` g/L of glucose.
+#[allow(dead_code)] // Not every test binary runs a simulation.
+pub fn media(volume_l: f64, glucose_g_l: f64) -> MediaState {
+    MediaState {
+        volume: Measurement { value: volume_l, unit: "L".to_string() },
+        ph: 7.0,
+        composition: MediaComposition {
+            dissolved_components: vec![DissolvedComponent {
+                molecule_id: GLUCOSE_ID.to_string(),
+                molecule_name: "D-glucose".to_string(),
+                concentration: Measurement { value: glucose_g_l, unit: "g/L".to_string() },
+            }],
+            dissolved_gases: vec![],
+        },
+    }
+}
+
+/// A process running `methods` in order, each given as its method id, technique, asset id, and
+/// the ids of its rules.
+#[allow(dead_code)]
+pub fn process(process_id: &str, methods: &[(&str, &str, &str, &[&str])]) -> Process {
+    let workflow: Vec<&str> = methods.iter().map(|(method_id, ..)| *method_id).collect();
+    let methods: Vec<Value> = methods
+        .iter()
+        .map(|(method_id, technique, asset_id, rule_ids)| {
+            json!({
+                "method_id": method_id,
+                "stage": method_id,
+                "technique": technique,
+                "required_asset_id": asset_id,
+                "operating_parameters": {},
+                "required_materials": [],
+                "qc_checks": [],
+                "required_rule_ids": rule_ids
+            })
+        })
+        .collect();
+    serde_json::from_value(json!({
+        "process_id": process_id,
+        "process_name": process_id,
+        "component_class": "Test",
+        "status": "Active",
+        "notes": "",
+        "default_workflow": workflow,
+        "methods": methods
+    }))
+    .expect("test process should deserialize")
+}
+
+/// A rule named `name` that runs `action` when `condition` holds, both given as in a rule file.
+pub fn rule(name: &str, condition: Value, action: Value) -> Rule {
+    serde_json::from_value(json!({ "name": name, "condition": condition, "action": action }))
+        .expect("test rule should deserialize")
+}
+
+/// A rule named `name` that ends the stage after `ticks` ticks.
+#[allow(dead_code)] // Not every test binary runs stages to a fixed length.
+pub fn time_rule(name: &str, ticks: u64) -> Rule {
+    rule(name, json!({ "type": "time_in_stage", "ticks": ticks }), json!({ "type": "advance_to_next_step" }))
+}
+
+/// Grams of `material_id` consumed over `records`, by anyone.
+#[allow(dead_code)]
+pub fn consumed_g(records: &[LogRecord], material_id: &str) -> f64 {
+    records
+        .iter()
+        .flat_map(|record| serde_json::from_str::<Vec<SimulationEvent>>(&record.events_json).expect("events should parse"))
+        .map(|event| match event {
+            SimulationEvent::MaterialConsumed { id, amount, .. } if id == material_id => amount,
+            _ => 0.0,
+        })
+        .sum()
+}
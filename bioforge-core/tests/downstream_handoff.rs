@@ -0,0 +1,119 @@
+//! Downstream runs started from the harvested broth of an upstream run.
+
+mod common;
+
+use bioforge_core::{
+    logger::{InMemorySink, LogRecord},
+    simulation::{builder::SimulationBuilder, state::{HandoffState, RunSummary}},
+};
+use bioforge_schemas::environment::DissolvedComponent;
+use serde_json::json;
+
+const NAOH_MATERIAL_ID: &str = "CONS-NAOH-1M-01";
+/// Grams of NaOH the engine's saponification takes per gram of dry biomass, and doses per litre
+/// and tick.
+const NAOH_G_PER_G_DW: f64 = 1.0;
+const NAOH_DOSE_G_PER_L: f64 = 0.5;
+const VOLUME_L: f64 = 100.0;
+
+/// The harvested broth of a six-hour batch cultivation of 50 g of inoculum.
+fn harvest() -> HandoffState {
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", 50.0)])
+        .with_process(common::process("PROC-UP", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration"])]))
+        .with_rules(vec![common::time_rule("cult.duration", 6)])
+        .with_initial_media(common::media(VOLUME_L, 20.0))
+        .build()
+        .unwrap();
+    engine.run().unwrap();
+    engine.handoff_state().harvested()
+}
+
+/// Saponifies `handoff`, ending once the biomass is saponified or after 30 ticks.
+fn saponify(handoff: HandoffState) -> (RunSummary, Vec<LogRecord>) {
+    let process = common::process(
+        "PROC-DOWN",
+        &[("MTHD-SAP", "saponification", "REACT-VESSEL", &["sap.complete", "sap.duration"])],
+    );
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", 50.0)])
+        .with_process(process)
+        .with_rules(vec![
+            common::rule("sap.complete", json!({ "type": "unit_operation_complete" }), json!({ "type": "advance_to_next_step" })),
+            common::time_rule("sap.duration", 30),
+        ])
+        .with_handoff(handoff)
+        .with_timeseries_sink(Box::new(InMemorySink::new()))
+        .build()
+        .unwrap();
+    let summary = engine.run().unwrap();
+    (summary, engine.logged_records().unwrap().to_vec())
+}
+
+fn biomass_g(handoff: &HandoffState) -> f64 {
+    handoff.organism_states.values().map(|s| s.biomass.value).sum()
+}
+
+#[test]
+fn halving_the_upstream_biomass_halves_the_saponification() {
+    let full = harvest();
+    let mut half = full.clone();
+    for state in half.organism_states.values_mut() {
+        state.biomass.value *= 0.5;
+    }
+    let (full_biomass, half_biomass) = (biomass_g(&full), biomass_g(&half));
+    let dose_per_tick = NAOH_DOSE_G_PER_L * VOLUME_L;
+    assert!(full_biomass * NAOH_G_PER_G_DW < 30.0 * dose_per_tick, "the full broth should saponify within the time limit");
+
+    let (full_summary, full_records) = saponify(full);
+    let (half_summary, half_records) = saponify(half);
+
+    let full_naoh = common::consumed_g(&full_records, NAOH_MATERIAL_ID);
+    let half_naoh = common::consumed_g(&half_records, NAOH_MATERIAL_ID);
+    assert!((full_naoh - full_biomass * NAOH_G_PER_G_DW).abs() < 1e-6);
+    assert!((half_naoh - half_biomass * NAOH_G_PER_G_DW).abs() < 1e-6);
+    assert!((half_naoh - full_naoh / 2.0).abs() < 1e-6);
+
+    let ticks = |biomass: f64| (biomass * NAOH_G_PER_G_DW / dose_per_tick).ceil() as u64;
+    assert_eq!(full_summary.total_ticks, ticks(full_biomass));
+    assert_eq!(half_summary.total_ticks, ticks(half_biomass));
+    assert!(half_summary.total_ticks < full_summary.total_ticks);
+}
+
+fn concentration_g_l(record: &LogRecord, molecule_id: &str) -> f64 {
+    let components: Vec<DissolvedComponent> = serde_json::from_str(&record.dissolved_components_json).unwrap();
+    components.iter().find(|c| c.molecule_id == molecule_id).map_or(0.0, |c| c.concentration.value)
+}
+
+#[test]
+fn harvested_broth_neither_grows_nor_secretes() {
+    let handoff = harvest();
+    let acetate = handoff.media.composition.dissolved_components.iter().find(|c| c.molecule_id == common::ACETATE_ID).unwrap().concentration.value;
+    assert!(acetate > 0.0, "the culture should have secreted acetate upstream");
+
+    let (summary, records) = saponify(handoff.clone());
+
+    for record in &records {
+        assert_eq!(concentration_g_l(record, common::ACETATE_ID), acetate);
+        assert_eq!(concentration_g_l(record, common::GLUCOSE_ID), concentration_g_l(&records[0], common::GLUCOSE_ID));
+    }
+    assert_eq!(summary.final_biomass["ORG-TEST"], biomass_g(&handoff));
+    assert_eq!(common::consumed_g(&records, common::GLUCOSE_ID), 0.0);
+}
+
+#[test]
+fn doubling_the_upstream_biomass_changes_the_downstream_consumption() {
+    let single = harvest();
+    let mut double = single.clone();
+    for state in double.organism_states.values_mut() {
+        state.biomass.value *= 2.0;
+    }
+
+    let (_, single_records) = saponify(single);
+    let (_, double_records) = saponify(double);
+
+    let single_naoh = common::consumed_g(&single_records, NAOH_MATERIAL_ID);
+    let double_naoh = common::consumed_g(&double_records, NAOH_MATERIAL_ID);
+    assert!(single_naoh > 0.0);
+    assert!((double_naoh - 2.0 * single_naoh).abs() < 1e-6);
+}
@@ -0,0 +1,99 @@
+//! Growth limited by the primary carbon source, which is looked up in the media by name, and
+//! stopped by rules.
+
+mod common;
+
+use bioforge_core::{
+    logger::{InMemorySink, LogRecord},
+    simulation::builder::SimulationBuilder,
+};
+use bioforge_schemas::environment::{DissolvedComponent, MediaState};
+use serde_json::json;
+
+const INOCULUM_G: f64 = 10.0;
+
+/// Runs one tick of `INOCULUM_G` of the test organism on `media`.
+fn grow(media: MediaState) -> (f64, Vec<LogRecord>) {
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", INOCULUM_G)])
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration"])]))
+        .with_rules(vec![common::time_rule("cult.duration", 1)])
+        .with_initial_media(media)
+        .with_timeseries_sink(Box::new(InMemorySink::new()))
+        .build()
+        .unwrap();
+    let summary = engine.run().unwrap();
+    (summary.final_biomass["ORG-TEST"], engine.logged_records().unwrap().to_vec())
+}
+
+/// Ten litres of 20 g/L glucose, listed under `molecule_id` and `molecule_name`.
+fn glucose_media(molecule_id: &str, molecule_name: &str) -> MediaState {
+    let mut media = common::media(10.0, 20.0);
+    let glucose = &mut media.composition.dissolved_components[0];
+    glucose.molecule_id = molecule_id.to_string();
+    glucose.molecule_name = molecule_name.to_string();
+    media
+}
+
+#[test]
+fn the_carbon_source_limits_growth_by_name() {
+    let (reference, reference_records) = grow(glucose_media(common::GLUCOSE_ID, "D-glucose"));
+    assert!(reference > INOCULUM_G);
+    assert!(common::consumed_g(&reference_records, common::GLUCOSE_ID) > 0.0);
+
+    // Listed under another id, the glucose still feeds growth, but it is not consumed, as
+    // consumption goes by id.
+    let (other_id, other_id_records) = grow(glucose_media("CHEBI:4167", "D-glucose"));
+    assert_eq!(other_id, reference);
+    assert_eq!(common::consumed_g(&other_id_records, "CHEBI:4167"), 0.0);
+
+    // Listed under another name, it does not.
+    let (other_name, _) = grow(glucose_media(common::GLUCOSE_ID, "dextrose"));
+    assert_eq!(other_name, INOCULUM_G);
+}
+
+/// Twelve ticks of `INOCULUM_G` of the test organism on 10 L of 20 g/L glucose, with the rule
+/// that stops its growth once it holds `stop_at_lutein_g` of Lutein, as generated for a target.
+fn grow_until(stop_at_lutein_g: Option<f64>) -> Vec<LogRecord> {
+    let stop = stop_at_lutein_g.map(|grams| {
+        common::rule(
+            "stop",
+            json!({ "type": "product_amount", "molecule_name": "Lutein", "target_grams": grams, "organism_id": "ORG-TEST" }),
+            json!({ "type": "set_organism_growth_multiplier", "organism_id": "ORG-TEST", "multiplier": 0.0 }),
+        )
+    });
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", INOCULUM_G)])
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration", "stop"])]))
+        .with_rules([common::time_rule("cult.duration", 12)].into_iter().chain(stop).collect())
+        .with_initial_media(common::media(10.0, 20.0))
+        .with_timeseries_sink(Box::new(InMemorySink::new()))
+        .build()
+        .unwrap();
+    engine.run().unwrap();
+    engine.logged_records().unwrap().to_vec()
+}
+
+fn acetate_g_l(record: &LogRecord) -> f64 {
+    let components: Vec<DissolvedComponent> = serde_json::from_str(&record.dissolved_components_json).unwrap();
+    components.iter().find(|c| c.molecule_id == common::ACETATE_ID).map_or(0.0, |c| c.concentration.value)
+}
+
+#[test]
+fn a_culture_whose_growth_a_rule_stops_stops_secreting_too() {
+    // The test organism holds 2.5 mg/g of Lutein, so 0.05 g once it has doubled.
+    let stopped = grow_until(Some(0.05));
+    let unstopped = grow_until(None);
+    let stopped_at = stopped.iter().position(|r| r.total_biomass_g >= 20.0).expect("the culture should double");
+    assert!(stopped_at + 2 < stopped.len(), "the culture doubled too late to test");
+    assert!(acetate_g_l(&stopped[stopped_at]) > 0.0);
+
+    // From the tick after the rule fires, the culture neither grows nor secretes.
+    let held = &stopped[stopped_at + 1];
+    for record in &stopped[stopped_at + 1..] {
+        assert_eq!(record.total_biomass_g, held.total_biomass_g, "tick {}", record.tick);
+        assert_eq!(acetate_g_l(record), acetate_g_l(held), "tick {}", record.tick);
+    }
+    let last = |records: &[LogRecord]| acetate_g_l(records.last().unwrap());
+    assert!(last(&stopped) < last(&unstopped));
+}
@@ -0,0 +1,106 @@
+//! The elemental mass balance of fixture logs whose closure is known.
+//!
+//! Both fixtures log 10 L of media from which the test organism, 50% carbon and 13% nitrogen by
+//! mass, takes up 100 g of glucose (40% carbon) and 38 g of ammonium chloride (26% nitrogen),
+//! secreting 5 g of acetate (40% carbon). In `mass_balance_closed.jsonl` it grows the 76 g of
+//! biomass that accounts for every gram; in `mass_balance_open.jsonl` it grows 66 g.
+
+mod common;
+
+use bioforge_core::analysis::mass_balance::{mass_balance, ElementBalance, MassBalanceReport};
+use bioforge_schemas::material::Material;
+use std::collections::HashMap;
+
+const AMMONIUM_CHLORIDE_ID: &str = "CHEBI:31206";
+
+/// A material of id `id` holding `specifications`, each an element's `<name>_content` in % w/w.
+fn material(id: &str, specifications: &[(&str, f64)]) -> Material {
+    let specifications: String = specifications
+        .iter()
+        .map(|(key, value)| format!("\n- key: {key}\n  value: {value}\n  unit: \"%\""))
+        .collect();
+    serde_yaml::from_str(&format!(
+        r#"
+material_id: {id}
+material_name: {id}
+material_class: Chemical
+material_subtype: Reagent
+material_category: PurchasedRawMaterial
+unit: kg
+metadata:
+  process_role: Test
+  vendor: null
+  part_number: null
+  notes: null
+  identifiers: null
+specifications:{specifications}
+formulation: null
+techno_economic_and_lca_profile:
+  lifecycle_stages:
+    manufacturing_and_acquisition: {{ costs: [], impacts: [] }}
+    use_and_operation: {{ costs: [], impacts: [] }}
+    maintenance: {{ costs: [], impacts: [] }}
+    end_of_life: {{ costs: [], impacts: [] }}
+"#
+    ))
+    .unwrap()
+}
+
+fn balance(fixture: &str) -> MassBalanceReport {
+    let materials: HashMap<String, Material> = [
+        material(common::GLUCOSE_ID, &[("carbon_content", 40.0)]),
+        material(AMMONIUM_CHLORIDE_ID, &[("nitrogen_content", 26.0)]),
+        material(common::ACETATE_ID, &[("carbon_content", 40.0)]),
+    ]
+    .into_iter()
+    .map(|m| (m.material_id.clone(), m))
+    .collect();
+    let path = format!("{}/tests/fixtures/{}", env!("CARGO_MANIFEST_DIR"), fixture);
+    mass_balance(&path, &[common::organism("ORG-TEST", 10.0)], &materials).unwrap()
+}
+
+fn element<'a>(report: &'a MassBalanceReport, symbol: &str) -> &'a ElementBalance {
+    report.elements.iter().find(|b| b.element == symbol).unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "expected {expected}, got {actual}");
+}
+
+#[test]
+fn a_balanced_run_closes_with_biomass_as_mass_fractions() {
+    let report = balance("mass_balance_closed.jsonl");
+    assert!(report.unmatched_molecules.is_empty(), "{:?}", report.unmatched_molecules);
+
+    // Carbon: 100 g · 40% = 76 g · 0.5 + 5 g · 40%.
+    let carbon = element(&report, "C");
+    assert_close(carbon.uptake_g, 40.0);
+    assert_close(carbon.biomass_g, 38.0);
+    assert_close(carbon.secreted_g, 2.0);
+    // Nitrogen: 38 g · 26% = 76 g · 0.13.
+    let nitrogen = element(&report, "N");
+    assert_close(nitrogen.uptake_g, 9.88);
+    assert_close(nitrogen.biomass_g, 9.88);
+    assert_close(nitrogen.secreted_g, 0.0);
+
+    for balance in &report.elements {
+        assert!(balance.closure_error_g.abs() < 1e-9, "{:?}", balance);
+    }
+    assert_eq!(report.exceeding(1e-6).count(), 0);
+}
+
+#[test]
+fn a_shortfall_of_biomass_shows_as_the_closure_error() {
+    let report = balance("mass_balance_open.jsonl");
+
+    // Ten grams less biomass leave 5 g of carbon and 1.3 g of nitrogen unaccounted for.
+    let carbon = element(&report, "C");
+    assert_close(carbon.closure_error_g, 5.0);
+    assert_close(carbon.relative_error.unwrap(), 5.0 / 40.0);
+    let nitrogen = element(&report, "N");
+    assert_close(nitrogen.closure_error_g, 1.3);
+    assert_close(nitrogen.relative_error.unwrap(), 1.3 / 9.88);
+
+    let exceeding: Vec<&str> = report.exceeding(0.13).map(|b| b.element.as_str()).collect();
+    assert_eq!(exceeding, ["N"]);
+}
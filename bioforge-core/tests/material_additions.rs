@@ -0,0 +1,72 @@
+//! Materials added to, or topped up in, the vessel of the asset a rule names.
+
+mod common;
+
+use bioforge_core::{
+    error::BioforgeError,
+    simulation::{builder::SimulationBuilder, engine::SimulationEngine},
+};
+use serde_json::{json, Value};
+
+/// Runs four ticks of the test organism on glucose in `REACTOR`, beside a 2 L `HOLDING` tank of
+/// media without glucose and an `EMPTY` one without media, with `feed` fired on every tick if
+/// given.
+fn run(feed: Option<Value>) -> Result<SimulationEngine, BioforgeError> {
+    let mut rules = vec![common::time_rule("cult.duration", 4)];
+    let mut rule_ids = vec!["cult.duration"];
+    if let Some(action) = feed {
+        rules.insert(0, common::rule("cult.feed", json!({ "type": "time_in_stage", "ticks": 0 }), action));
+        rule_ids.insert(0, "cult.feed");
+    }
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", 1.0)])
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &rule_ids)]))
+        .with_rules(rules)
+        .with_initial_media(common::media(5.0, 10.0))
+        .with_vessel("HOLDING", common::media(2.0, 0.0), vec![])
+        .with_vessel("EMPTY", common::media(0.0, 0.0), vec![])
+        .build()?;
+    engine.run()?;
+    Ok(engine)
+}
+
+fn glucose_g_l(engine: &SimulationEngine, asset_id: &str) -> f64 {
+    engine.get_vessels()[asset_id].dissolved_component(common::GLUCOSE_ID).unwrap().concentration.value
+}
+
+#[test]
+fn additions_go_to_the_vessel_of_their_asset() {
+    let unfed = run(None).unwrap();
+    let reactor_g_l = glucose_g_l(&unfed, "REACTOR");
+
+    let added = run(Some(json!({
+        "type": "add_material", "asset_id": "HOLDING", "material_id": common::GLUCOSE_ID, "amount_grams": 10.0
+    })))
+    .unwrap();
+    // Ten grams on each of the four ticks, into 2 L.
+    assert_eq!(glucose_g_l(&added, "HOLDING"), 20.0);
+    assert_eq!(glucose_g_l(&added, "REACTOR"), reactor_g_l);
+
+    let topped_up = run(Some(json!({
+        "type": "top_up_material", "asset_id": "HOLDING", "material_id": common::GLUCOSE_ID, "concentration_g_l": 3.0
+    })))
+    .unwrap();
+    assert_eq!(glucose_g_l(&topped_up, "HOLDING"), 3.0);
+    assert_eq!(glucose_g_l(&topped_up, "REACTOR"), reactor_g_l);
+}
+
+#[test]
+fn additions_to_an_asset_without_media_are_skipped() {
+    let reactor_g_l = glucose_g_l(&run(None).unwrap(), "REACTOR");
+    // `MISSING` holds no vessel, and the vessel of `EMPTY` holds no media.
+    for asset_id in ["MISSING", "EMPTY"] {
+        for action in [
+            json!({ "type": "add_material", "asset_id": asset_id, "material_id": common::GLUCOSE_ID, "amount_grams": 10.0 }),
+            json!({ "type": "top_up_material", "asset_id": asset_id, "material_id": common::GLUCOSE_ID, "concentration_g_l": 3.0 }),
+        ] {
+            let engine = run(Some(action)).unwrap();
+            assert_eq!(glucose_g_l(&engine, "REACTOR"), reactor_g_l);
+            assert_eq!(glucose_g_l(&engine, "EMPTY"), 0.0);
+        }
+    }
+}
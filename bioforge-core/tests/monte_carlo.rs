@@ -0,0 +1,84 @@
+//! Monte Carlo studies drawn from a seed.
+
+mod common;
+
+use bioforge_core::analysis::{
+    monte_carlo::{Distribution, MonteCarloStudy},
+    sensitivity::BaseConfiguration,
+};
+use bioforge_schemas::{material::Material, tea_lca::EconomicAssumptions};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Glucose at $0.80/kg, supplying the media's D-glucose.
+fn glucose() -> Material {
+    let no_costs = json!({ "costs": [], "impacts": [] });
+    serde_json::from_value(json!({
+        "material_id": "MEDIA-GLUCOSE", "material_name": "D-glucose", "material_class": "Chemical",
+        "material_subtype": "Carbon Source", "material_category": "PurchasedRawMaterial", "unit": "kg",
+        "metadata": {
+            "process_role": "Test", "vendor": null, "part_number": null, "notes": null,
+            "identifiers": { "cas_number": null, "chebi_id": common::GLUCOSE_ID, "pubchem_cid": null }
+        },
+        "specifications": [], "formulation": null,
+        "techno_economic_and_lca_profile": { "lifecycle_stages": {
+            "manufacturing_and_acquisition": { "costs": [{ "cost_type": "purchase_cost", "value_usd": 0.8 }], "impacts": [] },
+            "use_and_operation": no_costs, "maintenance": no_costs, "end_of_life": no_costs
+        } }
+    }))
+    .expect("test material should deserialize")
+}
+
+/// Six hours of the test organism on ample glucose, making lutein.
+fn base() -> BaseConfiguration {
+    BaseConfiguration {
+        organisms: vec![common::organism("ORG-TEST", 1.0)],
+        assets: HashMap::new(),
+        rules: vec![common::time_rule("cult.duration", 6)],
+        process: common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration"])]),
+        initial_media: common::media(1.0, 50.0),
+        materials: HashMap::from([("MEDIA-GLUCOSE".to_string(), glucose())]),
+        labor_roles: HashMap::new(),
+        assumptions: EconomicAssumptions::default(),
+        target_molecules: vec!["Lutein".to_string()],
+    }
+}
+
+fn study(seed: u64) -> MonteCarloStudy {
+    MonteCarloStudy::new(base(), 12, seed)
+        .with_parameter("organism.ORG-TEST.growth_rate_per_hr", Distribution::Uniform { min: 0.2, max: 0.4 })
+        .with_parameter("assumptions.electricity_price_per_kwh", Distribution::Triangular { min: 0.05, mode: 0.1, max: 0.3 })
+}
+
+#[test]
+fn a_seed_draws_the_same_study_every_time() {
+    let report = study(7).run().unwrap();
+    assert_eq!(report, study(7).run().unwrap());
+    assert_eq!(report.parameters, ["organism.ORG-TEST.growth_rate_per_hr", "assumptions.electricity_price_per_kwh"]);
+    assert_eq!(report.samples.len(), 12);
+
+    let other = study(8).run().unwrap();
+    assert_ne!(report.samples.iter().map(|s| s.values.clone()).collect::<Vec<_>>(), other.samples.iter().map(|s| s.values.clone()).collect::<Vec<_>>());
+}
+
+#[test]
+fn each_sample_runs_with_the_values_drawn_for_it() {
+    let report = study(7).run().unwrap();
+    let mut by_growth: Vec<(f64, f64)> = report.samples.iter().map(|s| (s.values[0], s.outcome.product_grams)).collect();
+    assert!(by_growth.iter().all(|(rate, _)| (0.2..=0.4).contains(rate)), "{:?}", by_growth);
+    assert!(report.samples.iter().all(|s| (0.05..=0.3).contains(&s.values[1])));
+    // A faster-growing organism makes more lutein in the same six hours.
+    by_growth.sort_by(|a, b| a.0.total_cmp(&b.0));
+    assert!(by_growth.windows(2).all(|pair| pair[1].1 > pair[0].1), "{:?}", by_growth);
+
+    let percentiles = report.percentiles(|o| Some(o.product_grams)).unwrap();
+    assert!(percentiles.p10 < percentiles.p50 && percentiles.p50 < percentiles.p90, "{:?}", percentiles);
+}
+
+#[test]
+fn an_invalid_distribution_fails_before_any_run() {
+    let study = MonteCarloStudy::new(base(), 12, 7)
+        .with_parameter("organism.ORG-TEST.growth_rate_per_hr", Distribution::Uniform { min: 0.4, max: 0.2 });
+    let error = study.run().unwrap_err().to_string();
+    assert!(error.contains("Invalid distribution for 'organism.ORG-TEST.growth_rate_per_hr'"), "{}", error);
+}
@@ -0,0 +1,83 @@
+//! Runs paced to wall-clock time, and their cancellation.
+
+mod common;
+
+use bioforge_core::simulation::{
+    builder::SimulationBuilder,
+    engine::SimulationEngine,
+    pacing::CancelFlag,
+    state::{RunStatus, SimulationState},
+};
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A run of `ticks` ticks that records the last tick it completed in `last_tick`.
+fn engine(ticks: u64, last_tick: &Arc<AtomicU64>) -> SimulationEngine {
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", 1.0)])
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration"])]))
+        .with_rules(vec![common::time_rule("cult.duration", ticks)])
+        .with_initial_media(common::media(1.0, 10.0))
+        .build()
+        .unwrap();
+    let last_tick = Arc::clone(last_tick);
+    engine.add_observer(Box::new(move |state: &SimulationState, _: &str| last_tick.store(state.tick, Ordering::SeqCst)));
+    engine
+}
+
+#[test]
+fn a_fast_paced_run_stops_within_a_tick_of_its_cancellation() {
+    let last_tick = Arc::new(AtomicU64::new(0));
+    let mut engine = engine(1_000_000, &last_tick);
+    let cancel = CancelFlag::new();
+    let canceller = {
+        let (cancel, last_tick) = (cancel.clone(), Arc::clone(&last_tick));
+        thread::spawn(move || {
+            while last_tick.load(Ordering::SeqCst) < 20 {
+                thread::sleep(Duration::from_millis(1));
+            }
+            cancel.cancel();
+            (last_tick.load(Ordering::SeqCst), Instant::now())
+        })
+    };
+    // A thousand ticks a second, far faster than the canceller polls.
+    let summary = engine.run_paced(1000.0, &cancel).unwrap();
+    let stopped = Instant::now();
+    let (cancelled_at, cancelled) = canceller.join().unwrap();
+
+    assert_eq!(summary.status, RunStatus::Cancelled);
+    assert!(summary.total_ticks <= cancelled_at + 1, "cancelled at tick {}, stopped at {}", cancelled_at, summary.total_ticks);
+    assert!(stopped - cancelled < Duration::from_millis(500));
+}
+
+#[test]
+fn a_cancelled_run_stops_before_its_next_tick_and_resumes() {
+    let last_tick = Arc::new(AtomicU64::new(0));
+    let mut engine = engine(10, &last_tick);
+    let cancel = CancelFlag::new();
+    let on_tick_five = cancel.clone();
+    engine.add_observer(Box::new(move |state: &SimulationState, _: &str| {
+        if state.tick == 5 {
+            on_tick_five.cancel();
+        }
+    }));
+
+    let summary = engine.run_paced(1000.0, &cancel).unwrap();
+    assert_eq!((summary.status, summary.total_ticks), (RunStatus::Cancelled, 5));
+
+    let summary = engine.run().unwrap();
+    assert_eq!((summary.status, summary.total_ticks), (RunStatus::Completed, 10));
+    assert_eq!(last_tick.load(Ordering::SeqCst), 10);
+}
+
+#[test]
+fn a_pace_that_is_not_a_positive_number_fails() {
+    let mut engine = engine(10, &Arc::new(AtomicU64::new(0)));
+    for ticks_per_second in [0.0, -1.0, f64::INFINITY, f64::NAN] {
+        assert!(engine.run_paced(ticks_per_second, &CancelFlag::new()).is_err());
+    }
+}
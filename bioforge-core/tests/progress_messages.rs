@@ -0,0 +1,56 @@
+//! The progress messages the engine emits through the `log` crate.
+
+mod common;
+
+use bioforge_core::simulation::builder::SimulationBuilder;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::Mutex;
+
+/// A logger that keeps every message at `info` or above, with its level.
+struct CapturingLogger(Mutex<Vec<(Level, String)>>);
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Info
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.0.lock().unwrap().push((record.level(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger(Mutex::new(Vec::new()));
+
+#[test]
+fn each_stage_transition_is_logged() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Info);
+
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", 1.0)])
+        .with_process(common::process(
+            "PROC-TEST",
+            &[("MTHD-SEED", "batch", "REACTOR", &["seed.duration"]), ("MTHD-MAIN", "batch", "REACTOR", &["main.duration"])],
+        ))
+        .with_rules(vec![common::time_rule("seed.duration", 2), common::time_rule("main.duration", 3)])
+        .with_initial_media(common::media(1.0, 10.0))
+        .build()
+        .unwrap();
+    engine.run().unwrap();
+
+    let messages: Vec<(Level, String)> = LOGGER.0.lock().unwrap().clone();
+    let transitions: Vec<&str> = messages
+        .iter()
+        .filter(|(level, message)| *level == Level::Info && message.starts_with("---"))
+        .map(|(_, message)| message.as_str())
+        .collect();
+    assert_eq!(
+        transitions,
+        ["--- Entering stage: MTHD-SEED ---", "--- Entering stage: MTHD-MAIN ---", "--- Reached end of process workflow ---"]
+    );
+    assert_eq!(messages.last().unwrap(), &(Level::Info, "Simulation Complete.".to_string()));
+}
@@ -0,0 +1,41 @@
+//! Runs of the same configuration write byte-identical logs.
+
+mod common;
+
+use bioforge_core::simulation::builder::SimulationBuilder;
+use bioforge_schemas::organism::Organism;
+use std::path::PathBuf;
+
+/// Five organisms sharing the glucose of one vessel, given in the order of `ids`.
+fn organisms(ids: &[&str]) -> Vec<Organism> {
+    ids.iter().enumerate().map(|(i, id)| common::organism(id, 1.0 + i as f64)).collect()
+}
+
+/// Runs `organisms` on scarce glucose for 24 ticks, logging to a CSV file named `name`, and
+/// returns the bytes logged.
+fn logged_bytes(name: &str, organisms: Vec<Organism>) -> Vec<u8> {
+    let path: PathBuf = std::env::temp_dir().join(format!("bioforge_repro_{}_{}.csv", name, std::process::id()));
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(organisms)
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration"])]))
+        .with_rules(vec![common::time_rule("cult.duration", 24)])
+        .with_initial_media(common::media(5.0, 2.0))
+        .with_timeseries_logging_to_file(path.to_str().unwrap())
+        .build()
+        .unwrap();
+    engine.run().unwrap();
+    engine.flush_logs().unwrap();
+    drop(engine);
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    bytes
+}
+
+#[test]
+fn the_same_configuration_logs_identical_bytes() {
+    let ids = ["ORG-C", "ORG-A", "ORG-E", "ORG-B", "ORG-D"];
+    let first = logged_bytes("first", organisms(&ids));
+    let second = logged_bytes("second", organisms(&ids));
+    assert!(!first.is_empty());
+    assert!(first == second, "two runs of the same configuration logged different bytes");
+}
@@ -0,0 +1,96 @@
+//! Rerunning an engine after a reset, and resuming a run from its log.
+
+mod common;
+
+use bioforge_core::{
+    analysis::state_from_log,
+    simulation::{builder::SimulationBuilder, engine::SimulationEngine, state::RunSummary},
+};
+use bioforge_schemas::process::Process;
+use std::{collections::HashMap, fs, path::PathBuf};
+
+/// Twelve ticks in a seed stage, then twelve in the main stage.
+fn two_stage_process() -> Process {
+    common::process(
+        "PROC-TEST",
+        &[("MTHD-SEED", "batch", "REACTOR", &["seed.duration"]), ("MTHD-MAIN", "batch", "REACTOR", &["main.duration"])],
+    )
+}
+
+fn builder() -> SimulationBuilder {
+    SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-A", 1.0), common::organism("ORG-B", 2.0)])
+        .with_process(two_stage_process())
+        .with_rules(vec![common::time_rule("seed.duration", 12), common::time_rule("main.duration", 12)])
+        .with_initial_media(common::media(5.0, 10.0))
+}
+
+/// A CSV log path under the system temp dir, unique to this test binary.
+fn log_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("bioforge_reset_{}_{}.csv", name, std::process::id()))
+}
+
+/// Runs `engine` to completion and returns its summary with the bytes of the log at `path`.
+fn run_to(engine: &mut SimulationEngine, path: &PathBuf) -> (RunSummary, String) {
+    let summary = engine.run().unwrap();
+    engine.flush_logs().unwrap();
+    (summary, fs::read_to_string(path).unwrap())
+}
+
+#[test]
+fn a_reset_rerun_logs_what_a_fresh_engine_logs() {
+    let (fresh_path, first_path, rerun_path) = (log_path("fresh"), log_path("first"), log_path("rerun"));
+
+    let mut fresh = builder().with_timeseries_logging_to_file(fresh_path.to_str().unwrap()).build().unwrap();
+    let (fresh_summary, fresh_log) = run_to(&mut fresh, &fresh_path);
+
+    let mut reused = builder().with_timeseries_logging_to_file(first_path.to_str().unwrap()).build().unwrap();
+    reused.set_growth_multiplier("ORG-A", 0.5);
+    let (_, first_log) = run_to(&mut reused, &first_path);
+    assert_ne!(first_log, fresh_log, "the first run should differ for the rerun to be meaningful");
+
+    reused.reset(Some(rerun_path.to_str().unwrap())).unwrap();
+    let (rerun_summary, rerun_log) = run_to(&mut reused, &rerun_path);
+
+    assert_eq!(rerun_log, fresh_log);
+    assert_eq!(rerun_summary.total_ticks, fresh_summary.total_ticks);
+    assert_eq!(rerun_summary.final_biomass, fresh_summary.final_biomass);
+    assert_eq!(rerun_summary.event_counts, fresh_summary.event_counts);
+    for path in [fresh_path, first_path, rerun_path] {
+        fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn a_run_resumed_from_its_log_matches_the_continuous_run() {
+    let (continuous_path, crashed_path, resumed_path) = (log_path("continuous"), log_path("crashed"), log_path("resumed"));
+
+    let mut continuous = builder().with_timeseries_logging_to_file(continuous_path.to_str().unwrap()).build().unwrap();
+    let (continuous_summary, continuous_log) = run_to(&mut continuous, &continuous_path);
+
+    // The run died after logging tick 18, six ticks into the main stage, partway through its
+    // last line.
+    let lines: Vec<&str> = continuous_log.lines().collect();
+    let last_line = lines[20];
+    let crashed_log = format!("{}\n{}", lines[..20].join("\n"), &last_line[..last_line.len() / 2]);
+    fs::write(&crashed_path, crashed_log).unwrap();
+
+    let point = state_from_log(crashed_path.to_str().unwrap(), &two_stage_process(), &HashMap::new()).unwrap();
+    assert_eq!(point.state.tick, 18);
+    let mut resumed = builder()
+        .with_resume_point(point)
+        .with_timeseries_logging_to_file(resumed_path.to_str().unwrap())
+        .build()
+        .unwrap();
+    let (resumed_summary, _) = run_to(&mut resumed, &resumed_path);
+
+    assert_eq!(resumed_summary.total_ticks, continuous_summary.total_ticks);
+    assert_eq!(resumed_summary.final_stage, continuous_summary.final_stage);
+    for (organism_id, biomass) in &continuous_summary.final_biomass {
+        let resumed_biomass = resumed_summary.final_biomass[organism_id];
+        assert!((resumed_biomass - biomass).abs() <= 1e-9 * biomass, "{}: {} vs {}", organism_id, resumed_biomass, biomass);
+    }
+    for path in [continuous_path, crashed_path, resumed_path] {
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,87 @@
+//! The sinks a run logs to alongside, or in place of, its log file.
+
+mod common;
+
+use bioforge_core::{
+    logger::{ChannelSink, InMemorySink, LogRecord, TimeSeriesSink},
+    simulation::{builder::SimulationBuilder, state::SimulationState},
+};
+use chrono::{DateTime, Utc};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// A sink that fails on the `fails_at`th record and every one after, counting the calls it gets.
+struct FailingSink {
+    fails_at: usize,
+    calls: Arc<AtomicUsize>,
+}
+
+impl TimeSeriesSink for FailingSink {
+    fn log_state(&mut self, _state: &SimulationState, _stage_id: &str, _timestamp: Option<DateTime<Utc>>) -> Result<(), anyhow::Error> {
+        let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+        if call >= self.fails_at {
+            anyhow::bail!("the disk is full");
+        }
+        Ok(())
+    }
+}
+
+/// A five-tick run logged to an in-memory sink, and to each of `additional`.
+fn builder(additional: Vec<Box<dyn TimeSeriesSink>>) -> SimulationBuilder {
+    let mut builder = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", 1.0)])
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration"])]))
+        .with_rules(vec![common::time_rule("cult.duration", 5)])
+        .with_initial_media(common::media(1.0, 10.0))
+        .with_timeseries_sink(Box::new(InMemorySink::new()));
+    for sink in additional {
+        builder = builder.with_additional_sink(sink);
+    }
+    builder
+}
+
+#[test]
+fn every_sink_receives_the_same_records() {
+    let (first, first_records) = ChannelSink::new();
+    let (second, second_records) = ChannelSink::new();
+    let mut engine = builder(vec![Box::new(first), Box::new(second)]).build().unwrap();
+    engine.run().unwrap();
+
+    let logged = engine.logged_records().unwrap().to_vec();
+    // The start of the run and each of its five ticks.
+    assert_eq!(logged.iter().map(|r| r.tick).collect::<Vec<u64>>(), [0, 1, 2, 3, 4, 5]);
+    assert_eq!(first_records.try_iter().collect::<Vec<LogRecord>>(), logged);
+    assert_eq!(second_records.try_iter().collect::<Vec<LogRecord>>(), logged);
+}
+
+#[test]
+fn a_failing_additional_sink_is_skipped_and_the_run_completes() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (channel, received) = ChannelSink::new();
+    let failing = FailingSink { fails_at: 2, calls: Arc::clone(&calls) };
+    let mut engine = builder(vec![Box::new(failing), Box::new(channel)]).build().unwrap();
+    let summary = engine.run().unwrap();
+
+    assert_eq!(summary.total_ticks, 5);
+    // The failing sink is not called again once it has failed; the others log every tick.
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+    assert_eq!(engine.logged_records().unwrap().len(), 6);
+    assert_eq!(received.try_iter().count(), 6);
+}
+
+#[test]
+fn a_failing_main_sink_aborts_the_run_unless_logging_is_lenient() {
+    let main = |calls: &Arc<AtomicUsize>| Box::new(FailingSink { fails_at: 2, calls: Arc::clone(calls) });
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut engine = builder(Vec::new()).with_timeseries_sink(main(&calls)).build().unwrap();
+    let error = engine.run().unwrap_err().to_string();
+    assert!(error.contains("the disk is full"), "{}", error);
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut engine = builder(Vec::new()).with_timeseries_sink(main(&calls)).with_strict_logging(false).build().unwrap();
+    assert_eq!(engine.run().unwrap().total_ticks, 5);
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+}
@@ -0,0 +1,51 @@
+//! A run logged to a SQLite database and queried back with SQL.
+#![cfg(feature = "sqlite")]
+
+mod common;
+
+use bioforge_core::{
+    logger::InMemorySink,
+    simulation::builder::SimulationBuilder,
+    sqlite_log::{list_runs, read_sqlite_run, SqliteSink},
+};
+use rusqlite::{params, Connection};
+
+#[test]
+fn the_biomass_trajectory_queries_back_as_logged() {
+    let db_path = std::env::temp_dir().join(format!("bioforge_sqlite_sink_{}.db", std::process::id()));
+    let db_path = db_path.to_str().unwrap();
+    let sink = SqliteSink::open(db_path, "short_run").unwrap();
+    let run_id = sink.run_id().to_string();
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(vec![common::organism("ORG-TEST", 1.0)])
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration"])]))
+        .with_rules(vec![common::time_rule("cult.duration", 8)])
+        .with_initial_media(common::media(1.0, 10.0))
+        .with_timeseries_sink(Box::new(sink))
+        .with_additional_sink(Box::new(InMemorySink::new()))
+        .build()
+        .unwrap();
+    engine.run().unwrap();
+    let logged: Vec<(u64, f64)> = engine.logged_records().unwrap().iter().map(|r| (r.tick, r.total_biomass_g)).collect();
+    drop(engine);
+
+    assert_eq!(list_runs(db_path).unwrap(), [(run_id.clone(), "short_run".to_string())]);
+    let conn = Connection::open(db_path).unwrap();
+    let mut statement = conn
+        .prepare("SELECT tick, biomass FROM organism_states WHERE run_id = ?1 AND organism_id = 'ORG-TEST' ORDER BY tick")
+        .unwrap();
+    let queried: Vec<(u64, f64)> = statement
+        .query_map(params![run_id], |row| Ok((row.get::<_, i64>(0)? as u64, row.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(queried.len(), 9);
+    assert_eq!(queried, logged);
+    assert!(queried.windows(2).all(|pair| pair[1].1 > pair[0].1), "{:?}", queried);
+
+    let read: Vec<(u64, f64)> = read_sqlite_run(db_path, &run_id).unwrap().iter().map(|r| (r.tick, r.total_biomass_g)).collect();
+    assert_eq!(read, logged);
+    drop(statement);
+    drop(conn);
+    std::fs::remove_file(db_path).unwrap();
+}
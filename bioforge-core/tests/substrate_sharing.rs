@@ -0,0 +1,78 @@
+//! Organisms that together demand more of a substrate than the media holds.
+
+mod common;
+
+use bioforge_core::{
+    logger::{InMemorySink, LogRecord},
+    simulation::{builder::SimulationBuilder, state::SimulationEvent},
+};
+use bioforge_schemas::environment::DissolvedComponent;
+use std::collections::BTreeMap;
+
+const VOLUME_L: f64 = 2.0;
+const GLUCOSE_G_L: f64 = 5.0;
+
+/// Runs ten organisms of 1 to 10 g on `VOLUME_L` litres of glucose for three ticks, enough of
+/// them to be computed in parallel where the feature and threads allow.
+fn run() -> Vec<LogRecord> {
+    let organisms = (1..=10).map(|i| common::organism(&format!("ORG-{:02}", i), i as f64)).collect();
+    let mut engine = SimulationBuilder::new()
+        .with_organisms(organisms)
+        .with_process(common::process("PROC-TEST", &[("MTHD-CULT", "batch", "REACTOR", &["cult.duration"])]))
+        .with_rules(vec![common::time_rule("cult.duration", 3)])
+        .with_initial_media(common::media(VOLUME_L, GLUCOSE_G_L))
+        .with_timeseries_sink(Box::new(InMemorySink::new()))
+        .build()
+        .unwrap();
+    engine.run().unwrap();
+    engine.logged_records().unwrap().to_vec()
+}
+
+/// Grams of glucose each organism consumed in `record`'s tick.
+fn glucose_by_consumer(record: &LogRecord) -> BTreeMap<String, f64> {
+    let events: Vec<SimulationEvent> = serde_json::from_str(&record.events_json).unwrap();
+    events
+        .into_iter()
+        .filter_map(|event| match event {
+            SimulationEvent::MaterialConsumed { id, amount, consumer_id: Some(consumer) } if id == common::GLUCOSE_ID => {
+                Some((consumer, amount))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn an_over_demanded_substrate_is_shared_in_proportion_to_demand() {
+    let records = run();
+    let first = records.iter().find(|record| record.tick == 1).unwrap();
+    let consumed = glucose_by_consumer(first);
+    assert_eq!(consumed.len(), 10);
+
+    // Together the organisms take up exactly what the media held, and no more.
+    let total: f64 = consumed.values().sum();
+    assert!((total - VOLUME_L * GLUCOSE_G_L).abs() < 1e-9, "consumed {} g", total);
+    let glucose: Vec<DissolvedComponent> = serde_json::from_str(&first.dissolved_components_json).unwrap();
+    let left = glucose.iter().find(|c| c.molecule_id == common::GLUCOSE_ID).unwrap().concentration.value;
+    assert!(left.abs() < 1e-12);
+
+    // Identical organisms demand in proportion to their biomass, so each gets the same share of
+    // its biomass.
+    let biomass: BTreeMap<String, f64> = serde_json::from_str::<BTreeMap<String, serde_json::Value>>(&first.organisms_json)
+        .unwrap()
+        .into_iter()
+        .map(|(id, state)| (id, state["biomass"]["value"].as_f64().unwrap()))
+        .collect();
+    let per_gram: Vec<f64> = consumed.iter().map(|(id, grams)| grams / biomass[id]).collect();
+    for ratio in &per_gram {
+        assert!((ratio - per_gram[0]).abs() < 1e-12, "{:?}", per_gram);
+    }
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn results_are_identical_with_and_without_parallelism() {
+    let serial = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap().install(run);
+    let parallel = rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap().install(run);
+    assert_eq!(serial, parallel);
+}
@@ -8,6 +8,33 @@ pub struct FlowCapacity {
     pub material_id: Option<String>,
 }
 
+impl FlowCapacity {
+    /// Bit in `direction` marking a capacity that accepts flow into the asset.
+    pub const INBOUND: i32 = 1;
+    /// Bit in `direction` marking a capacity that allows flow out of the asset.
+    pub const OUTBOUND: i32 = 2;
+
+    pub fn allows_inbound(&self) -> bool {
+        self.direction & Self::INBOUND != 0
+    }
+
+    pub fn allows_outbound(&self) -> bool {
+        self.direction & Self::OUTBOUND != 0
+    }
+
+    /// Returns the rated volumetric flow in litres per hour, or `None` if the capacity is unrated
+    /// (a zero rate or a unit that is not a volumetric flow).
+    pub fn rate_l_per_hr(&self) -> Option<f64> {
+        let per_hr = match self.rate.unit.as_str() {
+            "L/hr" | "L/h" => self.rate.value,
+            "L/min" => self.rate.value * 60.0,
+            "mL/min" => self.rate.value * 60.0 / 1000.0,
+            _ => return None,
+        };
+        (per_hr > 0.0).then_some(per_hr)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ConnectionPoint {
     pub port_id: String,
@@ -1,6 +1,7 @@
-use serde::Deserialize;
+use crate::environment::DissolvedComponent;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Command {
     SetTemperature {
@@ -17,8 +18,28 @@ pub enum Command {
         material_id: String,
         amount_grams: f64,
     },
+    /// Adds as much of `material_id` to the asset's media as brings it up to `concentration_g_l`.
+    TopUpMaterial {
+        asset_id: String,
+        material_id: String,
+        concentration_g_l: f64,
+    },
     SetOrganismGrowthMultiplier {
         organism_id: String,
         multiplier: f64,
     },
+    /// Drains `volume_l` litres of the asset's broth to waste, or all of it if absent.
+    DrainMedia {
+        asset_id: String,
+        volume_l: Option<f64>,
+    },
+    /// Exchanges `volume_l` litres of the asset's broth for as much fresh media holding `feed`,
+    /// as a continuous culture does. The broth taken out, with the organisms it holds, is
+    /// collected in the vessel of `harvest_asset_id`, or drained to waste if absent.
+    DiluteMedia {
+        asset_id: String,
+        volume_l: f64,
+        feed: Vec<DissolvedComponent>,
+        harvest_asset_id: Option<String>,
+    },
 }
\ No newline at end of file
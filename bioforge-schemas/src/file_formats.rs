@@ -1,41 +1,50 @@
 use crate::{
     asset::Asset, labor::LaborRole, material::Material, organism::Organism, process::Process,
-    rule::Rule,
+    rule::Rule, tea_lca::EconomicAssumptions,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize)]
+/// The `schema_version` of the knowledge-base files this build reads, as major and minor. Files
+/// of another major version are rejected; another minor version is read with a warning.
+pub const SCHEMA_VERSION: (u32, u32) = (1, 0);
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct AssetFile {
     pub schema_version: String,
     pub assets: Vec<Asset>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct MaterialFile {
     pub schema_version: String,
     pub materials: Vec<Material>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct OrganismFile {
     pub schema_version: String,
     pub organisms: Vec<Organism>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LaborRoleFile {
     pub schema_version: String,
     pub labor_roles: Vec<LaborRole>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ProcessFile {
     pub schema_version: String,
     pub processes: Vec<Process>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct RuleFile {
     pub schema_version: String,
     pub rules: Vec<Rule>,
-}
\ No newline at end of file
+}
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssumptionsFile {
+    pub schema_version: String,
+    pub assumptions: EconomicAssumptions,
+}
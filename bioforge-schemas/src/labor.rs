@@ -12,4 +12,10 @@ pub struct LaborRole {
     pub skill_level: Option<i32>,
     pub description: Option<String>,
     pub techno_economic_profile: TechnoEconomicProfile,
+    /// People of this role on duty at once, or `None` if staffing is not limited.
+    #[serde(default)]
+    pub available_headcount: Option<u32>,
+    /// Hours of the role's shift, worked once a day, or `None` for round-the-clock cover.
+    #[serde(default)]
+    pub shift_length_hours: Option<f64>,
 }
\ No newline at end of file
@@ -8,4 +8,7 @@ pub mod organism_state;
 pub mod process;
 pub mod rule;
 pub mod tea_lca;
-pub mod file_formats;
\ No newline at end of file
+pub mod file_formats;
+
+/// The version of this crate, for the run manifest.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
\ No newline at end of file
@@ -24,7 +24,7 @@ pub struct StrainDetails {
     pub is_engineered: bool,
 }
 
-/// Represents the elemental composition of the organism's biomass as a percentage of dry weight.
+/// Represents the elemental composition of the organism's biomass as mass fractions of dry weight.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ElementalComposition {
     pub carbon: f64,
@@ -69,6 +69,15 @@ pub struct TargetedMolecularClasses {
     pub cell_wall_components: Vec<TargetMoleculeYield>,
 }
 
+impl TargetedMolecularClasses {
+    /// The molecules of every class, class by class.
+    pub fn iter(&self) -> impl Iterator<Item = &TargetMoleculeYield> {
+        // Destructured so that a new class does not compile until it is listed here.
+        let Self { terpenoids_and_carotenoids, cell_wall_components } = self;
+        [terpenoids_and_carotenoids, cell_wall_components].into_iter().flatten()
+    }
+}
+
 /// Encapsulates the static, inherent properties of an organism that do not change during simulation.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StaticProperties {
@@ -76,6 +85,10 @@ pub struct StaticProperties {
     pub macromolecular_summary: MacromolecularSummary,
     pub morphology: Morphology,
     pub targeted_molecular_classes: TargetedMolecularClasses,
+    /// The molecule ids of the nitrogen sources the organism can grow on, e.g. ammonia, nitrate,
+    /// or urea. Defaults to the nitrogen sources among its media consumption.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub nitrogen_sources: Vec<String>,
 }
 
 /// A generic struct to define a minimum and maximum tolerance range.
@@ -163,6 +176,10 @@ pub struct MediaExchangeRate {
     pub molecule_name: String,
     pub max_exchange_rate: Measurement<f64>,
     pub conditions: ExchangeConditions,
+    /// Grams of dry biomass formed per gram of the molecule consumed, for sizing the media. Only
+    /// meaningful for consumption; defaults to the yield the growth and uptake rates imply.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub biomass_yield_g_g: Option<f64>,
 }
 
 /// Defines the rate of consumption or secretion of a gas.
@@ -187,6 +204,10 @@ pub struct MetabolicExchange {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DynamicParameters {
     pub growth_rate_per_hr: f64,
+    /// The highest dry biomass concentration, in g/L, the organism reaches in culture. Sizes the
+    /// bioreactor; defaults to a typical density when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_cell_density_g_l: Option<f64>,
     pub environmental_tolerances: EnvironmentalTolerances,
     pub metabolic_exchange: MetabolicExchange,
 }
@@ -201,4 +222,92 @@ pub struct Organism {
     pub initial_biomass: Measurement<f64>,
     pub static_properties: StaticProperties,
     pub dynamic_parameters: DynamicParameters,
-}
\ No newline at end of file
+}
+
+impl Organism {
+    /// The organism's yield of `molecule_name`, in mg per g of dry weight, from whichever class
+    /// holds it. Names match ignoring case, though an exact match is preferred.
+    pub fn yield_for(&self, molecule_name: &str) -> Option<f64> {
+        let classes = &self.static_properties.targeted_molecular_classes;
+        let lowercase = molecule_name.to_lowercase();
+        classes
+            .iter()
+            .find(|m| m.molecule == molecule_name)
+            .or_else(|| classes.iter().find(|m| m.molecule.to_lowercase() == lowercase))
+            .map(|m| m.concentration_mg_g_dw)
+    }
+
+    /// Every molecule the organism holds with its yield, in mg per g of dry weight.
+    pub fn all_yields(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.static_properties.targeted_molecular_classes.iter().map(|m| (m.molecule.as_str(), m.concentration_mg_g_dw))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A bacterium holding `terpenoids` and `cell_wall_components`, each a molecule name and
+    /// its yield in mg per g of dry weight.
+    fn organism(terpenoids: &[(&str, f64)], cell_wall_components: &[(&str, f64)]) -> Organism {
+        let class = |molecules: &[(&str, f64)]| -> Vec<serde_json::Value> {
+            molecules.iter().map(|(name, mg_g)| json!({ "molecule": name, "concentration_mg_g_dw": mg_g })).collect()
+        };
+        serde_json::from_value(json!({
+            "organism_id": "ORG-TEST",
+            "organism_name": "Test organism",
+            "organism_type": "Bacteria",
+            "strain_details": null,
+            "initial_biomass": { "value": 1.0, "unit": "g" },
+            "static_properties": {
+                "elemental_composition": {
+                    "carbon": 0.5, "hydrogen": 0.07, "oxygen": 0.24,
+                    "nitrogen": 0.13, "phosphorus": 0.03, "sulfur": 0.01
+                },
+                "macromolecular_summary": {
+                    "protein": 55.0, "carbohydrate": 20.0, "lipid": 10.0, "nucleic_acid": 10.0, "ash": 5.0
+                },
+                "morphology": { "nominal_diameter": { "value": 1.0, "unit": "um" } },
+                "targeted_molecular_classes": {
+                    "terpenoids_and_carotenoids": class(terpenoids),
+                    "cell_wall_components": class(cell_wall_components)
+                }
+            },
+            "dynamic_parameters": {
+                "growth_rate_per_hr": 0.3,
+                "environmental_tolerances": {
+                    "photosynthetic_light_response": null,
+                    "temperature": { "optimal": { "value": 25.0, "unit": "celsius" }, "range": { "min": 10.0, "max": 40.0 } },
+                    "ph": { "optimal": 7.0, "range": { "min": 5.0, "max": 9.0 } },
+                    "chemical": []
+                },
+                "metabolic_exchange": {
+                    "media_consumption": [],
+                    "media_secretion": [],
+                    "gas_consumption": [],
+                    "gas_secretion": []
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn yields_are_found_in_every_class() {
+        let organism = organism(&[("Lutein", 2.5), ("Astaxanthin", 0.4)], &[("Beta-glucan", 120.0)]);
+        assert_eq!(organism.yield_for("Lutein"), Some(2.5));
+        assert_eq!(organism.yield_for("Beta-glucan"), Some(120.0));
+        assert_eq!(organism.yield_for("Chitin"), None);
+        let yields: Vec<(&str, f64)> = organism.all_yields().collect();
+        assert_eq!(yields, [("Lutein", 2.5), ("Astaxanthin", 0.4), ("Beta-glucan", 120.0)]);
+    }
+
+    #[test]
+    fn names_match_ignoring_case_but_an_exact_match_comes_first() {
+        let organism = organism(&[("lutein", 1.0)], &[("Lutein", 2.5)]);
+        assert_eq!(organism.yield_for("Lutein"), Some(2.5));
+        assert_eq!(organism.yield_for("lutein"), Some(1.0));
+        assert_eq!(organism.yield_for("LUTEIN"), Some(1.0));
+    }
+}
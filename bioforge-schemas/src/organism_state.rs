@@ -1,6 +1,6 @@
 use crate::environment::Measurement;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndividualOrganismState {
@@ -9,5 +9,6 @@ pub struct IndividualOrganismState {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrganismState {
-    pub states: HashMap<String, IndividualOrganismState>,
+    /// Per-organism state keyed by organism id. A `BTreeMap` keeps iteration order stable across runs.
+    pub states: BTreeMap<String, IndividualOrganismState>,
 }
\ No newline at end of file
@@ -1,34 +1,78 @@
+use crate::environment::Measurement;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct RequiredMaterial {
     pub r#type: String,
     pub id: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct QcCheck {
     pub method_id: String,
     pub timing: String,
+    /// The cost of one assay beyond its labor and consumables, e.g. instrument time or an outside
+    /// lab's fee, in USD.
+    #[serde(default)]
+    pub cost_usd: Option<f64>,
+    /// The labor role that runs the assay, for `duration` hours of its time.
+    #[serde(default)]
+    pub required_role_id: Option<String>,
+    /// Hands-on time of one assay, e.g. `2 h`; also the assay's turnaround in reports.
+    #[serde(default)]
+    pub duration: Option<Measurement<f64>>,
+    #[serde(default)]
+    pub consumables: Vec<QcConsumable>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A material one QC assay uses up, such as a reference standard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct QcConsumable {
+    pub material_id: String,
+    pub grams: f64,
+}
+
+/// Moves material from one asset to another through their connection points while a method runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub struct MaterialTransfer {
+    pub from_asset_id: String,
+    pub from_port_id: String,
+    pub to_asset_id: String,
+    pub to_port_id: String,
+    /// Litres to move; when omitted, the entire contents of the source vessel are moved.
+    pub volume_l: Option<f64>,
+    /// The material carried by the transfer, matched against the ports' flow capacities.
+    pub material_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Method {
     pub method_id: String,
     pub stage: String,
     pub technique: String,
     pub required_asset_id: String,
-    pub operating_parameters: HashMap<String, serde_json::Value>,
+    pub operating_parameters: BTreeMap<String, serde_json::Value>,
     pub required_materials: Vec<RequiredMaterial>,
     pub qc_checks: Vec<QcCheck>,
     pub required_rule_ids: Option<Vec<String>>,
+    pub transfers: Option<Vec<MaterialTransfer>>,
+    /// How many ticks the method is expected to last, for blueprints of methods that end on a
+    /// condition other than `TimeInStage`.
+    #[serde(default)]
+    pub expected_duration_ticks: Option<u64>,
+    /// The percentage of the impurities left with the product that the method removes, for the
+    /// purity a process reaches. Methods without one leave the purity as it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impurity_removal_percent: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub struct Process {
     pub process_id: String,
@@ -1,7 +1,8 @@
 use crate::command::Command;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::fmt;
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ComparisonOperator {
     LessThan,
@@ -10,7 +11,7 @@ pub enum ComparisonOperator {
     NotEqualTo,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum Condition {
     AssetValue {
@@ -29,17 +30,80 @@ pub enum Condition {
     ProductAmount {
         molecule_name: String,
         target_grams: f64,
+        /// Counts only what this organism holds in its biomass; every producer when `None`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        organism_id: Option<String>,
     },
     MediaValue {
         molecule_id: String,
         operator: ComparisonOperator,
         value: f64,
     },
+    /// The current method's unit operation has treated the whole batch, e.g. a saponification
+    /// has dosed all the NaOH the biomass takes. Operations the engine does not model complete at
+    /// once.
+    UnitOperationComplete,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Rule {
     pub name: String,
     pub condition: Condition,
     pub action: Command,
-}
\ No newline at end of file
+}
+impl Rule {
+    /// Whether the rule moves the workflow on to its next step.
+    pub fn advances(&self) -> bool {
+        matches!(self.action, Command::AdvanceToNextStep)
+    }
+}
+
+/// The conditions under which any of `rules` moves the workflow on, e.g.
+/// "biomass stationary (2%/10 h) OR 120 h", or an empty string if none of them does.
+pub fn transition_conditions<'a>(rules: impl IntoIterator<Item = &'a Rule>) -> String {
+    rules
+        .into_iter()
+        .filter(|rule| rule.advances())
+        .map(|rule| rule.condition.to_string())
+        .collect::<Vec<_>>()
+        .join(" OR ")
+}
+
+impl fmt::Display for ComparisonOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ComparisonOperator::LessThan => "<",
+            ComparisonOperator::GreaterThan => ">",
+            ComparisonOperator::EqualTo => "=",
+            ComparisonOperator::NotEqualTo => "≠",
+        })
+    }
+}
+
+/// Renders a condition for people, e.g. in diagrams: ticks as hours, media concentrations in g/L.
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::AssetValue { asset_id, parameter, operator, value } => {
+                write!(f, "{} {} {} {}", asset_id, parameter, operator, number(*value))
+            }
+            Condition::TimeInStage { ticks } => write!(f, "{} h", ticks),
+            Condition::BiomassStationary { threshold, window } => {
+                write!(f, "biomass stationary ({}%/{} h)", number(threshold * 100.0), window)
+            }
+            Condition::ProductAmount { molecule_name, target_grams, .. } => {
+                write!(f, "{} ≥ {} g", molecule_name, number(*target_grams))
+            }
+            Condition::MediaValue { molecule_id, operator, value } => {
+                write!(f, "{} {} {} g/L", molecule_id, operator, number(*value))
+            }
+            Condition::UnitOperationComplete => f.write_str("unit operation complete"),
+        }
+    }
+}
+
+/// `value` with at most four decimals and no trailing zeros, so 0.02 × 100 reads as 2.
+fn number(value: f64) -> String {
+    let text = format!("{:.4}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
@@ -51,4 +51,66 @@ pub struct TechnoEconomicAndLcaProfile {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub expected_lifespan: Option<Measurement<i32>>,
     pub lifecycle_stages: LifecycleStages,
-}
\ No newline at end of file
+}
+/// Facility-level assumptions behind the COGS and LCA figures, such as the price and impact of
+/// grid electricity. The defaults are the figures used before they became configurable.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EconomicAssumptions {
+    /// Names the assumption set in reports, e.g. `Norway hydro 2026`.
+    pub name: String,
+    /// Price of grid electricity per kWh, in `currency`.
+    pub electricity_price_per_kwh: f64,
+    /// Global warming potential of grid electricity, in kg CO₂e per kWh.
+    pub grid_gwp_kg_co2e_per_kwh: f64,
+    /// Abiotic depletion (fossil) of grid electricity, in MJ per kWh.
+    pub grid_adp_fossil_mj_per_kwh: f64,
+    /// Further impacts of grid electricity per kWh, e.g. `water_use` in `m3`. Entries for `gwp`
+    /// or `adp_fossil` replace the factors above.
+    pub grid_impacts_per_kwh: Vec<ImpactEntry>,
+    /// Hours per year the facility operates, over which annual asset costs and impacts are spread.
+    pub operating_hours_per_year: f64,
+    /// Label printed with costs. Prices in the knowledge base are not converted.
+    pub currency: String,
+    /// Annual discount rate of the financial model's cash flows, as a fraction.
+    pub discount_rate: f64,
+    /// Hours between the end of a batch and the start of the next, e.g. for cleaning and setup.
+    pub batch_turnaround_hours: f64,
+}
+
+impl Default for EconomicAssumptions {
+    fn default() -> Self {
+        Self {
+            name: "built-in defaults".to_string(),
+            electricity_price_per_kwh: 0.12,
+            grid_gwp_kg_co2e_per_kwh: 0.4,
+            grid_adp_fossil_mj_per_kwh: 8.0,
+            grid_impacts_per_kwh: Vec::new(),
+            operating_hours_per_year: 8760.0,
+            currency: "USD".to_string(),
+            discount_rate: 0.0,
+            batch_turnaround_hours: 24.0,
+        }
+    }
+}
+
+impl EconomicAssumptions {
+    /// Every per-kWh impact factor of grid electricity, GWP and ADP (fossil) included.
+    pub fn grid_impact_factors(&self) -> Vec<ImpactEntry> {
+        let mut factors = vec![
+            ImpactEntry {
+                metric: "gwp".to_string(),
+                value: self.grid_gwp_kg_co2e_per_kwh,
+                unit: "kg_CO2e".to_string(),
+            },
+            ImpactEntry {
+                metric: "adp_fossil".to_string(),
+                value: self.grid_adp_fossil_mj_per_kwh,
+                unit: "MJ".to_string(),
+            },
+        ];
+        factors.retain(|factor| !self.grid_impacts_per_kwh.iter().any(|f| f.metric == factor.metric));
+        factors.extend(self.grid_impacts_per_kwh.iter().cloned());
+        factors
+    }
+}